@@ -2,7 +2,7 @@
 
 set -euvx
 
-./megazords/ios-rust/build-xcframework.sh --build-profile release
+./megazords/ios-rust/build-xcframework.sh --build-profile release --with-crashtest
 set -o pipefail && \
 xcodebuild \
   -workspace ./megazords/ios-rust/MozillaTestServices/MozillaTestServices.xcodeproj/project.xcworkspace \
@@ -0,0 +1,204 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+* License, v. 2.0. If a copy of the MPL was not distributed with this
+* file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+// Working out what a stored address can add to a partly filled address form, so the UI can
+// highlight the fields which will be autofilled.
+
+use crate::db::models::address::Address;
+use crate::normalize::normalize_country;
+use AddressFieldName::{AddressLevel1, AddressLevel2, PostalCode, StreetAddress};
+
+/// A field of an address form. These match the fields of `Address`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AddressFieldName {
+    GivenName,
+    AdditionalName,
+    FamilyName,
+    Organization,
+    StreetAddress,
+    AddressLevel3,
+    AddressLevel2,
+    AddressLevel1,
+    PostalCode,
+    Country,
+    Tel,
+    Email,
+}
+
+impl AddressFieldName {
+    fn value(self, address: &Address) -> &str {
+        match self {
+            Self::GivenName => &address.given_name,
+            Self::AdditionalName => &address.additional_name,
+            Self::FamilyName => &address.family_name,
+            Self::Organization => &address.organization,
+            Self::StreetAddress => &address.street_address,
+            Self::AddressLevel3 => &address.address_level3,
+            Self::AddressLevel2 => &address.address_level2,
+            Self::AddressLevel1 => &address.address_level1,
+            Self::PostalCode => &address.postal_code,
+            Self::Country => &address.country,
+            Self::Tel => &address.tel,
+            Self::Email => &address.email,
+        }
+    }
+}
+
+/// A field detected in a form, with what's currently in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressFormField {
+    pub name: AddressFieldName,
+    pub value: String,
+}
+
+/// Returns the fields of a form which are empty, and which `address` has a value for, in the
+/// order they're in the form. These are the fields that autofilling `address` would complete.
+/// If the form has an empty field which the address's country requires, as returned by
+/// `required_address_fields`, and the address doesn't have it, the form would still be
+/// incomplete after autofilling, so nothing is returned.
+pub fn missing_fields(
+    form_fields: Vec<AddressFormField>,
+    address: Address,
+) -> Vec<AddressFieldName> {
+    let required = required_address_fields(address.country.clone());
+    let mut missing = Vec::new();
+    for field in form_fields {
+        if !field.value.trim().is_empty() || missing.contains(&field.name) {
+            continue;
+        }
+        if !field.name.value(&address).trim().is_empty() {
+            missing.push(field.name);
+        } else if required.contains(&field.name) {
+            return Vec::new();
+        }
+    }
+    missing
+}
+
+/// Returns the fields a postal address in `country` needs to be complete. `country` can be a
+/// code or an English name, like for `normalize_country`. Countries we don't know the address
+/// format of get the fields most countries need.
+pub fn required_address_fields(country: String) -> Vec<AddressFieldName> {
+    let country = normalize_country(&country);
+    REQUIRED_FIELDS
+        .iter()
+        .find(|(code, _)| Some(*code) == country.as_deref())
+        .map_or(DEFAULT_REQUIRED_FIELDS, |(_, fields)| *fields)
+        .to_vec()
+}
+
+// Most countries need a street address, city and postal code.
+const DEFAULT_REQUIRED_FIELDS: &[AddressFieldName] = &[StreetAddress, AddressLevel2, PostalCode];
+
+// Countries which also need a region, eg a state or province.
+const WITH_REGION: &[AddressFieldName] = &[StreetAddress, AddressLevel2, AddressLevel1, PostalCode];
+
+// The countries which need something other than `DEFAULT_REQUIRED_FIELDS`.
+const REQUIRED_FIELDS: &[(&str, &[AddressFieldName])] = &[
+    ("AE", &[StreetAddress, AddressLevel1]),
+    ("AU", WITH_REGION),
+    ("BR", WITH_REGION),
+    ("CA", WITH_REGION),
+    ("HK", &[StreetAddress, AddressLevel1]),
+    // Eircodes are optional.
+    ("IE", &[StreetAddress, AddressLevel2]),
+    ("IN", WITH_REGION),
+    ("JP", &[StreetAddress, AddressLevel1, PostalCode]),
+    ("MX", WITH_REGION),
+    ("US", WITH_REGION),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AddressFieldName::*;
+
+    fn field(name: AddressFieldName, value: &str) -> AddressFormField {
+        AddressFormField {
+            name,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let address = Address {
+            given_name: "Jane".into(),
+            family_name: "Doe".into(),
+            street_address: "123 Main St".into(),
+            address_level2: "Springfield".into(),
+            postal_code: "12345".into(),
+            country: "US".into(),
+            ..Default::default()
+        };
+        let form = vec![
+            field(GivenName, "Janet"),
+            field(FamilyName, ""),
+            field(StreetAddress, "  "),
+            // The address has no organization, so can't fill it.
+            field(Organization, ""),
+            field(PostalCode, ""),
+            field(AddressLevel2, "Shelbyville"),
+            // A field which appears twice is only listed once.
+            field(FamilyName, ""),
+        ];
+        assert_eq!(
+            missing_fields(form, address.clone()),
+            vec![FamilyName, StreetAddress, PostalCode]
+        );
+        assert!(missing_fields(vec![], address.clone()).is_empty());
+
+        // US addresses need a state, which this address doesn't have, so it can't complete
+        // a form which asks for one.
+        let form = vec![field(FamilyName, ""), field(AddressLevel1, "")];
+        assert!(missing_fields(form, address).is_empty());
+
+        // Hong Kong addresses don't need a postal code or a city.
+        let address = Address {
+            family_name: "Chan".into(),
+            street_address: "1 Queen's Road Central".into(),
+            address_level1: "Hong Kong Island".into(),
+            country: "HK".into(),
+            ..Default::default()
+        };
+        let form = vec![
+            field(FamilyName, ""),
+            field(StreetAddress, ""),
+            field(AddressLevel2, ""),
+            field(AddressLevel1, ""),
+            field(PostalCode, ""),
+        ];
+        assert_eq!(
+            missing_fields(form.clone(), address.clone()),
+            vec![FamilyName, StreetAddress, AddressLevel1]
+        );
+        // But they do need a region.
+        let address = Address {
+            address_level1: "".into(),
+            ..address
+        };
+        assert!(missing_fields(form, address).is_empty());
+    }
+
+    #[test]
+    fn test_required_address_fields() {
+        assert_eq!(
+            required_address_fields("United States".into()),
+            vec![StreetAddress, AddressLevel2, AddressLevel1, PostalCode]
+        );
+        assert_eq!(
+            required_address_fields("HK".into()),
+            vec![StreetAddress, AddressLevel1]
+        );
+        assert_eq!(
+            required_address_fields("Germany".into()),
+            vec![StreetAddress, AddressLevel2, PostalCode]
+        );
+        assert_eq!(
+            required_address_fields("Atlantis".into()),
+            vec![StreetAddress, AddressLevel2, PostalCode]
+        );
+    }
+}
@@ -11,6 +11,7 @@ use crate::db::{
     schema::{ADDRESS_COMMON_COLS, ADDRESS_COMMON_VALS},
 };
 use crate::error::*;
+use crate::normalize::{has_known_regions, normalize_country, normalize_region};
 
 use rusqlite::{Connection, Transaction};
 use sync_guid::Guid;
@@ -20,6 +21,7 @@ pub(crate) fn add_address(
     conn: &Connection,
     new: UpdatableAddressFields,
 ) -> Result<InternalAddress> {
+    let new = normalize_address_fields(new);
     let tx = conn.unchecked_transaction()?;
     let now = Timestamp::now();
 
@@ -119,6 +121,38 @@ pub(crate) fn get_all_addresses(conn: &Connection) -> Result<Vec<InternalAddress
     Ok(addresses)
 }
 
+/// Get the address which was used most recently, or None if none of them have been used.
+pub(crate) fn get_last_used_address(conn: &Connection) -> Result<Option<InternalAddress>> {
+    Ok(get_all_addresses(conn)?
+        .into_iter()
+        .filter(|address| address.metadata.time_last_used.as_millis() > 0)
+        .max_by_key(|address| address.metadata.time_last_used))
+}
+
+/// Stores the country as an ISO 3166-1 alpha-2 code where we recognize it. Where we know
+/// the country's regions, `address_level1` is stored as the region's code. Countries and
+/// regions we don't recognize are kept as-is, since they may still be good enough to
+/// deliver to, and we'd rather not lose an address the user has already typed in.
+fn normalize_address_fields(mut fields: UpdatableAddressFields) -> UpdatableAddressFields {
+    if fields.country.trim().is_empty() {
+        return fields;
+    }
+    match normalize_country(&fields.country) {
+        Some(code) => fields.country = code,
+        None => {
+            log::warn!("Unrecognized address country");
+            return fields;
+        }
+    }
+    if !fields.address_level1.trim().is_empty() && has_known_regions(&fields.country) {
+        match normalize_region(&fields.country, &fields.address_level1) {
+            Some(code) => fields.address_level1 = code,
+            None => log::warn!("Unrecognized address_level1 for country {}", fields.country),
+        }
+    }
+    fields
+}
+
 /// Updates just the "updatable" columns - suitable for exposure as a public
 /// API.
 pub(crate) fn update_address(
@@ -126,6 +160,11 @@ pub(crate) fn update_address(
     guid: &Guid,
     address: &UpdatableAddressFields,
 ) -> Result<()> {
+    // A malformed guid can't name an existing address.
+    if !guid.is_valid() {
+        return Err(Error::NoSuchRecord(guid.to_string()));
+    }
+    let address = &normalize_address_fields(address.clone());
     let tx = conn.unchecked_transaction()?;
     tx.execute(
         "UPDATE addresses_data
@@ -351,6 +390,74 @@ mod tests {
         assert!(get_address(&db, &saved_address.guid).is_err());
     }
 
+    #[test]
+    fn test_address_country_and_region_normalization() {
+        let db = new_mem_db();
+
+        let saved_address = add_address(
+            &db,
+            UpdatableAddressFields {
+                given_name: "jane".to_string(),
+                address_level1: "washington".to_string(),
+                country: "United States".to_string(),
+                ..UpdatableAddressFields::default()
+            },
+        )
+        .expect("should contain saved address");
+        assert_eq!(saved_address.country, "US");
+        assert_eq!(saved_address.address_level1, "WA");
+
+        // Regions we don't recognize are kept, and ones we can't check are left alone.
+        for (country, region, expected_country, expected_region) in [
+            ("CAN", "Nowhere", "CA", "Nowhere"),
+            ("Germany", "Bayern", "DE", "Bayern"),
+        ] {
+            update_address(
+                &db,
+                &saved_address.guid,
+                &UpdatableAddressFields {
+                    address_level1: region.to_string(),
+                    country: country.to_string(),
+                    ..UpdatableAddressFields::default()
+                },
+            )
+            .expect("should update address");
+            let retrieved_address = get_address(&db, &saved_address.guid).unwrap();
+            assert_eq!(retrieved_address.country, expected_country);
+            assert_eq!(retrieved_address.address_level1, expected_region);
+        }
+
+        // Localized names are normalized too, and countries we don't recognize are kept.
+        for (country, region, expected_country, expected_region) in [
+            ("Deutschland", "Bayern", "DE", "Bayern"),
+            ("México", "Jalisco", "MX", "Jalisco"),
+            ("Atlantis", "california", "Atlantis", "california"),
+        ] {
+            let address = add_address(
+                &db,
+                UpdatableAddressFields {
+                    address_level1: region.to_string(),
+                    country: country.to_string(),
+                    ..UpdatableAddressFields::default()
+                },
+            )
+            .expect("should add address");
+            assert_eq!(address.country, expected_country);
+            assert_eq!(address.address_level1, expected_region);
+        }
+        update_address(
+            &db,
+            &saved_address.guid,
+            &UpdatableAddressFields {
+                country: "Atlantis".to_string(),
+                ..UpdatableAddressFields::default()
+            },
+        )
+        .expect("should update address");
+        let retrieved_address = get_address(&db, &saved_address.guid).unwrap();
+        assert_eq!(retrieved_address.country, "Atlantis");
+    }
+
     #[test]
     fn test_address_missing_guid() {
         let db = new_mem_db();
@@ -480,6 +587,19 @@ mod tests {
         assert_eq!(1, updated_address.metadata.sync_change_counter);
     }
 
+    #[test]
+    fn test_address_update_invalid_guid() {
+        let db = new_mem_db();
+        for guid in ["", "invalid,guid", "invalid\u{e9}guid", &"g".repeat(65)] {
+            let err = update_address(&db, &guid.into(), &UpdatableAddressFields::default())
+                .expect_err("should fail to update");
+            assert!(
+                matches!(err, Error::NoSuchRecord(ref g) if g == guid),
+                "unexpected error updating {guid:?}: {err:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_address_update_internal_address() -> Result<()> {
         let mut db = new_mem_db();
@@ -687,4 +807,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_last_used_address() -> Result<()> {
+        let db = new_mem_db();
+        let add = |given_name: &str, time_last_used: u64| {
+            let address = add_address(
+                &db,
+                UpdatableAddressFields {
+                    given_name: given_name.to_string(),
+                    family_name: "doe".to_string(),
+                    street_address: "123 Second Avenue".to_string(),
+                    address_level2: "Chicago, IL".to_string(),
+                    country: "United States".to_string(),
+
+                    ..UpdatableAddressFields::default()
+                },
+            )
+            .unwrap();
+            db.execute(
+                "UPDATE addresses_data SET time_last_used = :time_last_used WHERE guid = :guid",
+                rusqlite::named_params! {
+                    ":time_last_used": Timestamp(time_last_used),
+                    ":guid": address.guid,
+                },
+            )
+            .unwrap();
+            address.guid
+        };
+
+        assert!(get_last_used_address(&db)?.is_none());
+        add("never used", 0);
+        assert!(get_last_used_address(&db)?.is_none());
+        add("jane", 1000);
+        let john = add("john", 2000);
+        add("jim", 1500);
+        assert_eq!(get_last_used_address(&db)?.map(|a| a.guid), Some(john));
+        Ok(())
+    }
 }
@@ -12,7 +12,8 @@ use crate::db::{
 };
 use crate::error::*;
 
-use rusqlite::{Connection, Transaction};
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use sql_support::ConnExt;
 use sync_guid::Guid;
 use types::Timestamp;
 
@@ -98,6 +99,73 @@ pub(crate) fn get_credit_card(conn: &Connection, guid: &Guid) -> Result<Internal
         })
 }
 
+/// Fetch just the encrypted number of a credit card, without reading the rest of the record.
+pub(crate) fn get_credit_card_number_enc(conn: &Connection, guid: &Guid) -> Result<String> {
+    conn.query_row(
+        "SELECT cc_number_enc FROM credit_cards_data WHERE guid = :guid",
+        [guid],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Error::NoSuchRecord(guid.to_string()),
+        e => e.into(),
+    })
+}
+
+// The guid and encrypted number of every card which has one; scrubbed cards are skipped.
+pub(crate) fn get_all_credit_card_numbers_enc(conn: &Connection) -> Result<Vec<(Guid, String)>> {
+    conn.query_rows_and_then(
+        "SELECT guid, cc_number_enc FROM credit_cards_data WHERE cc_number_enc <> ''",
+        [],
+        |row| -> Result<_> { Ok((row.get("guid")?, row.get("cc_number_enc")?)) },
+    )
+}
+
+// Replaces the encrypted number of a card with the same number encrypted with a different key.
+// The number itself doesn't change, so this isn't a change which needs to be synced.
+pub(crate) fn set_credit_card_number_enc(
+    conn: &Connection,
+    guid: &Guid,
+    cc_number_enc: &str,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE credit_cards_data SET cc_number_enc = :cc_number_enc WHERE guid = :guid",
+        rusqlite::named_params! {
+            ":cc_number_enc": cc_number_enc,
+            ":guid": guid,
+        },
+    )?;
+    Ok(())
+}
+
+// The encrypted sync payload of a card in the mirror, if it's been synced.
+pub(crate) fn get_credit_card_mirror_payload(
+    conn: &Connection,
+    guid: &Guid,
+) -> Result<Option<String>> {
+    Ok(conn.try_query_one(
+        "SELECT payload FROM credit_cards_mirror WHERE guid = :guid",
+        rusqlite::named_params! { ":guid": guid },
+        false,
+    )?)
+}
+
+// Like `set_credit_card_number_enc()`, but for the card's payload in the sync mirror.
+pub(crate) fn set_credit_card_mirror_payload(
+    conn: &Connection,
+    guid: &Guid,
+    payload: &str,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE credit_cards_mirror SET payload = :payload WHERE guid = :guid",
+        rusqlite::named_params! {
+            ":payload": payload,
+            ":guid": guid,
+        },
+    )?;
+    Ok(())
+}
+
 pub(crate) fn get_all_credit_cards(conn: &Connection) -> Result<Vec<InternalCreditCard>> {
     let sql = format!(
         "SELECT
@@ -114,11 +182,114 @@ pub(crate) fn get_all_credit_cards(conn: &Connection) -> Result<Vec<InternalCred
     Ok(credit_cards)
 }
 
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Get the credit cards which haven't expired at `now`, but will within `days`, with the soonest
+/// to expire first. `now` is a parameter so tests don't depend on the real clock.
+pub(crate) fn get_credit_cards_expiring_within(
+    conn: &Connection,
+    days: u32,
+    now: Timestamp,
+) -> Result<Vec<InternalCreditCard>> {
+    let cutoff = now.as_millis() + u64::from(days) * MILLIS_PER_DAY;
+    let mut expiring = get_all_credit_cards(conn)?
+        .into_iter()
+        .filter_map(|card| {
+            let expiry = expiry_time(card.cc_exp_month, card.cc_exp_year)?;
+            (expiry.as_millis() > now.as_millis() && expiry.as_millis() <= cutoff)
+                .then_some((expiry, card))
+        })
+        .collect::<Vec<_>>();
+    expiring.sort_by_key(|(expiry, _)| *expiry);
+    Ok(expiring.into_iter().map(|(_, card)| card).collect())
+}
+
+/// Get the card which was used most recently, for offering a one-tap fill. Cards which have
+/// expired at `now` are only considered if every card has. Cards which have never been used
+/// aren't returned.
+pub(crate) fn get_last_used_credit_card(
+    conn: &Connection,
+    now: Timestamp,
+) -> Result<Option<InternalCreditCard>> {
+    let any_valid: bool = conn.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM credit_cards_data WHERE {not_expired})",
+            not_expired = CREDIT_CARD_NOT_EXPIRED
+        ),
+        rusqlite::named_params! { ":now": now },
+        |row| row.get(0),
+    )?;
+    let sql = format!(
+        "SELECT
+            {common_cols},
+            sync_change_counter
+        FROM credit_cards_data
+        WHERE time_last_used > 0
+          AND (NOT :valid_only OR ({not_expired}))
+        ORDER BY time_last_used DESC
+        LIMIT 1",
+        common_cols = CREDIT_CARD_COMMON_COLS,
+        not_expired = CREDIT_CARD_NOT_EXPIRED
+    );
+    Ok(conn
+        .query_row(
+            &sql,
+            rusqlite::named_params! {
+                ":now": now,
+                ":valid_only": any_valid,
+            },
+            InternalCreditCard::from_row,
+        )
+        .optional()?)
+}
+
+// `expiry_time(cc_exp_month, cc_exp_year) > :now` in SQL, using the `credit_card_expiry`
+// function so the two can't disagree. A card without a valid expiry date isn't known to have
+// expired.
+const CREDIT_CARD_NOT_EXPIRED: &str =
+    "IFNULL(credit_card_expiry(cc_exp_month, cc_exp_year) > :now, 1)";
+
+// Cards are valid until the end of their expiry month, so they expire at the start (UTC) of the
+// following one. Years may be stored with either two or four digits; like desktop, we treat
+// two-digit years as being in this century. Returns None if the card has no valid expiry date.
+pub(crate) fn expiry_time(month: i64, year: i64) -> Option<Timestamp> {
+    let year = match year {
+        0..=99 => year + 2000,
+        1970.. => year,
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let (year, month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let days = days_since_epoch(year, month);
+    Some(Timestamp(u64::try_from(days).ok()? * MILLIS_PER_DAY))
+}
+
+// The number of days between 1970-01-01 and the first day of `month` in `year`, using
+// Howard Hinnant's `days_from_civil` algorithm.
+fn days_since_epoch(year: i64, month: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
 pub fn update_credit_card(
     conn: &Connection,
     guid: &Guid,
     credit_card: &UpdatableCreditCardFields,
 ) -> Result<()> {
+    // A malformed guid can't name an existing credit card.
+    if !guid.is_valid() {
+        return Err(Error::NoSuchRecord(guid.to_string()));
+    }
     let tx = conn.unchecked_transaction()?;
     tx.execute(
         "UPDATE credit_cards_data
@@ -291,6 +462,127 @@ pub(crate) mod tests {
         .expect("should insert");
     }
 
+    #[test]
+    fn test_expiry_time() {
+        // 2023-04-01T00:00:00Z
+        assert_eq!(expiry_time(3, 2023), Some(Timestamp(1_680_307_200_000)));
+        assert_eq!(expiry_time(3, 23), Some(Timestamp(1_680_307_200_000)));
+        // 2024-01-01T00:00:00Z
+        assert_eq!(expiry_time(12, 2023), Some(Timestamp(1_704_067_200_000)));
+        assert_eq!(expiry_time(12, 23), Some(Timestamp(1_704_067_200_000)));
+        // 1970-02-01T00:00:00Z
+        assert_eq!(expiry_time(1, 1970), Some(Timestamp(31 * MILLIS_PER_DAY)));
+        assert_eq!(expiry_time(0, 2023), None);
+        assert_eq!(expiry_time(13, 2023), None);
+        assert_eq!(expiry_time(3, 999), None);
+        assert_eq!(expiry_time(3, -1), None);
+
+        // The SQL function is the same.
+        let db = new_mem_db();
+        let sql_expiry_time = |month: i64, year: i64| -> Option<Timestamp> {
+            db.query_row(
+                "SELECT credit_card_expiry(?, ?)",
+                rusqlite::params![month, year],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(sql_expiry_time(12, 23), expiry_time(12, 23));
+        assert_eq!(sql_expiry_time(13, 2023), None);
+    }
+
+    #[test]
+    fn test_get_credit_cards_expiring_within() -> Result<()> {
+        let db = new_mem_db();
+        let add = |cc_name: &str, cc_exp_month: i64, cc_exp_year: i64| {
+            add_credit_card(
+                &db,
+                UpdatableCreditCardFields {
+                    cc_name: cc_name.to_string(),
+                    cc_number_enc: "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX".to_string(),
+                    cc_number_last_4: "1234".to_string(),
+                    cc_exp_month,
+                    cc_exp_year,
+                    cc_type: "visa".to_string(),
+                },
+            )
+            .unwrap()
+        };
+        add("expired", 2, 2023);
+        let this_month = add("this month", 3, 2023);
+        let next_month_short_year = add("next month", 4, 23);
+        add("in three months", 6, 2023);
+        add("no expiry", 0, 0);
+
+        // 2023-03-15T00:00:00Z
+        let now = Timestamp(1_678_838_400_000);
+        let names = |days| {
+            get_credit_cards_expiring_within(&db, days, now)
+                .unwrap()
+                .into_iter()
+                .map(|card| card.cc_name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(0), Vec::<String>::new());
+        // The card expiring this month is valid until 2023-04-01, 17 days away.
+        assert_eq!(names(16), Vec::<String>::new());
+        assert_eq!(names(17), vec![this_month.cc_name.clone()]);
+        assert_eq!(
+            names(60),
+            vec![this_month.cc_name, next_month_short_year.cc_name]
+        );
+        assert_eq!(names(120).len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_last_used_credit_card() -> Result<()> {
+        let db = new_mem_db();
+        let add = |cc_name: &str, cc_exp_year: i64, time_last_used: u64| {
+            let card = add_credit_card(
+                &db,
+                UpdatableCreditCardFields {
+                    cc_name: cc_name.to_string(),
+                    cc_number_enc: "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX".to_string(),
+                    cc_number_last_4: "1234".to_string(),
+                    cc_exp_month: 6,
+                    cc_exp_year,
+                    cc_type: "visa".to_string(),
+                },
+            )
+            .unwrap();
+            db.execute(
+                "UPDATE credit_cards_data SET time_last_used = :time_last_used WHERE guid = :guid",
+                rusqlite::named_params! {
+                    ":time_last_used": Timestamp(time_last_used),
+                    ":guid": card.guid,
+                },
+            )
+            .unwrap();
+            card.guid
+        };
+        // 2023-03-15T00:00:00Z
+        let now = Timestamp(1_678_838_400_000);
+        let last_used = || get_last_used_credit_card(&db, now).unwrap().map(|c| c.guid);
+
+        assert_eq!(last_used(), None);
+
+        // An expired card is offered when every card has expired...
+        let expired = add("expired", 2022, 3000);
+        assert_eq!(last_used(), Some(expired));
+
+        // ...but not once there's a valid card, even one which hasn't been used.
+        add("never used", 2025, 0);
+        assert_eq!(last_used(), None);
+
+        // A valid card is preferred, even if it was used less recently.
+        let valid = add("valid", 2025, 1000);
+        assert_eq!(last_used(), Some(valid));
+        let more_recent = add("more recent", 23, 2000);
+        assert_eq!(last_used(), Some(more_recent));
+        Ok(())
+    }
+
     #[test]
     fn test_credit_card_create_and_read() -> Result<()> {
         let db = new_mem_db();
@@ -468,6 +760,19 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_credit_card_update_invalid_guid() {
+        let db = new_mem_db();
+        for guid in ["", "invalid,guid", "invalid\u{e9}guid", &"g".repeat(65)] {
+            let err = update_credit_card(&db, &guid.into(), &UpdatableCreditCardFields::default())
+                .expect_err("should fail to update");
+            assert!(
+                matches!(err, Error::NoSuchRecord(ref g) if g == guid),
+                "unexpected error updating {guid:?}: {err:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_credit_card_update_internal_credit_card() -> Result<()> {
         let mut db = new_mem_db();
@@ -707,4 +1012,31 @@ pub(crate) mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_credit_card_number_enc() -> Result<()> {
+        let db = new_mem_db();
+        let saved_credit_card = add_credit_card(
+            &db,
+            UpdatableCreditCardFields {
+                cc_name: "jane doe".to_string(),
+                cc_number_enc: "WWWWWWWWWWWWWWWWWWWWWWWWWWWWWWW".to_string(),
+                cc_number_last_4: "6543".to_string(),
+                cc_exp_month: 5,
+                cc_exp_year: 2024,
+                cc_type: "visa".to_string(),
+            },
+        )?;
+
+        assert_eq!(
+            get_credit_card_number_enc(&db, &saved_credit_card.guid)?,
+            "WWWWWWWWWWWWWWWWWWWWWWWWWWWWWWW"
+        );
+        assert!(matches!(
+            get_credit_card_number_enc(&db, &Guid::random()),
+            Err(Error::NoSuchRecord(_))
+        ));
+
+        Ok(())
+    }
 }
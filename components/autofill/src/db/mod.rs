@@ -10,7 +10,7 @@ pub mod store;
 
 use crate::error::*;
 
-use interrupt_support::{SqlInterruptHandle, SqlInterruptScope};
+use interrupt_support::{register_interrupt, SqlInterruptHandle, SqlInterruptScope};
 use rusqlite::{Connection, OpenFlags};
 use sql_support::open_database;
 use std::sync::Arc;
@@ -50,8 +50,11 @@ impl AutofillDb {
             &schema::AutofillConnectionInitializer,
         )?;
 
+        let interrupt_handle = Arc::new(SqlInterruptHandle::new(&conn));
+        // Allow `interrupt_support::interrupt_all()` and `shutdown()` to interrupt us.
+        register_interrupt(Arc::<SqlInterruptHandle>::downgrade(&interrupt_handle));
         Ok(Self {
-            interrupt_handle: Arc::new(SqlInterruptHandle::new(&conn)),
+            interrupt_handle,
             writer: conn,
         })
     }
@@ -132,6 +135,14 @@ pub(crate) mod sql_fns {
     pub fn now(_ctx: &Context<'_>) -> Result<Timestamp> {
         Ok(Timestamp::now())
     }
+
+    #[inline(never)]
+    pub fn credit_card_expiry(ctx: &Context<'_>) -> Result<Option<Timestamp>> {
+        Ok(crate::db::credit_cards::expiry_time(
+            ctx.get(0)?,
+            ctx.get(1)?,
+        ))
+    }
 }
 
 // Helpers for tests
@@ -6,6 +6,7 @@
 use super::Metadata;
 use rusqlite::Row;
 use sync_guid::Guid;
+use types::Timestamp;
 
 // UpdatableAddressFields contains the fields we support for creating a new
 // address or updating an existing one. It's missing the guid, our "internal"
@@ -46,9 +47,9 @@ pub struct Address {
     pub tel: String,
     pub email: String,
     // We expose some of the metadata
-    pub time_created: i64,
-    pub time_last_used: Option<i64>,
-    pub time_last_modified: i64,
+    pub time_created: Timestamp,
+    pub time_last_used: Option<Timestamp>,
+    pub time_last_modified: Timestamp,
     pub times_used: i64,
 }
 
@@ -70,14 +71,13 @@ impl From<InternalAddress> for Address {
             country: ia.country,
             tel: ia.tel,
             email: ia.email,
-            // note we can't use u64 in uniffi
-            time_created: u64::from(ia.metadata.time_created) as i64,
+            time_created: ia.metadata.time_created,
             time_last_used: if ia.metadata.time_last_used.0 == 0 {
                 None
             } else {
-                Some(ia.metadata.time_last_used.0 as i64)
+                Some(ia.metadata.time_last_used)
             },
-            time_last_modified: u64::from(ia.metadata.time_last_modified) as i64,
+            time_last_modified: ia.metadata.time_last_modified,
             times_used: ia.metadata.times_used,
         }
     }
@@ -6,6 +6,7 @@
 use super::Metadata;
 use rusqlite::Row;
 use sync_guid::Guid;
+use types::Timestamp;
 
 #[derive(Debug, Clone, Default)]
 pub struct UpdatableCreditCardFields {
@@ -33,9 +34,9 @@ pub struct CreditCard {
     pub cc_type: String,
 
     // The metadata
-    pub time_created: i64,
-    pub time_last_used: Option<i64>,
-    pub time_last_modified: i64,
+    pub time_created: Timestamp,
+    pub time_last_used: Option<Timestamp>,
+    pub time_last_modified: Timestamp,
     pub times_used: i64,
 }
 
@@ -51,14 +52,13 @@ impl From<InternalCreditCard> for CreditCard {
             cc_exp_month: icc.cc_exp_month,
             cc_exp_year: icc.cc_exp_year,
             cc_type: icc.cc_type,
-            // note we can't use u64 in uniffi
-            time_created: u64::from(icc.metadata.time_created) as i64,
+            time_created: icc.metadata.time_created,
             time_last_used: if icc.metadata.time_last_used.0 == 0 {
                 None
             } else {
-                Some(icc.metadata.time_last_used.0 as i64)
+                Some(icc.metadata.time_last_used)
             },
-            time_last_modified: u64::from(icc.metadata.time_last_modified) as i64,
+            time_last_modified: icc.metadata.time_last_modified,
             times_used: icc.metadata.times_used,
         }
     }
@@ -4,7 +4,10 @@
 
 use crate::db::sql_fns;
 use rusqlite::{functions::FunctionFlags, Connection, Transaction};
-use sql_support::open_database::{ConnectionInitializer, Error, Result};
+use sql_support::open_database::{
+    ConnectionInitializer, Error, MigrationObserver, MigrationObserverRegistry, Result,
+};
+use std::sync::Arc;
 
 pub const ADDRESS_COMMON_COLS: &str = "
     guid,
@@ -74,6 +77,9 @@ const CREATE_SHARED_SCHEMA_SQL: &str = include_str!("../../sql/create_shared_sch
 const CREATE_SHARED_TRIGGERS_SQL: &str = include_str!("../../sql/create_shared_triggers.sql");
 const CREATE_SYNC_TEMP_TABLES_SQL: &str = include_str!("../../sql/create_sync_temp_tables.sql");
 
+// The observer the application registered with `set_autofill_migration_observer()`, if any.
+pub static MIGRATION_OBSERVER: MigrationObserverRegistry = MigrationObserverRegistry::new();
+
 pub struct AutofillConnectionInitializer;
 
 impl ConnectionInitializer for AutofillConnectionInitializer {
@@ -114,6 +120,10 @@ impl ConnectionInitializer for AutofillConnectionInitializer {
     fn finish(&self, db: &Connection) -> Result<()> {
         Ok(db.execute_batch(CREATE_SHARED_TRIGGERS_SQL)?)
     }
+
+    fn migration_observer(&self) -> Option<Arc<dyn MigrationObserver>> {
+        MIGRATION_OBSERVER.get()
+    }
 }
 
 fn define_functions(c: &Connection) -> Result<()> {
@@ -124,6 +134,12 @@ fn define_functions(c: &Connection) -> Result<()> {
         sql_fns::generate_guid,
     )?;
     c.create_scalar_function("now", 0, FunctionFlags::SQLITE_UTF8, sql_fns::now)?;
+    c.create_scalar_function(
+        "credit_card_expiry",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        sql_fns::credit_card_expiry,
+    )?;
 
     Ok(())
 }
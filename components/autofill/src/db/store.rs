@@ -4,18 +4,26 @@
 
 use crate::db::models::address::{Address, UpdatableAddressFields};
 use crate::db::models::credit_card::{CreditCard, UpdatableCreditCardFields};
+use crate::db::schema::MIGRATION_OBSERVER;
 use crate::db::{addresses, credit_cards, AutofillDb};
+use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
+use crate::observer::{
+    AutofillChange, AutofillChangeObserver, AutofillChangeType, AutofillRecordType,
+    ChangeObserverRegistry,
+};
 use error_support::handle_error;
+use jwcrypto::KeyManager;
 use rusqlite::{
     types::{FromSql, ToSql},
     Connection,
 };
-use sql_support::{self, ConnExt};
+use sql_support::{self, open_database::MigrationObserver, ConnExt};
 use std::path::Path;
 use std::sync::{Arc, Mutex, Weak};
 use sync15::engine::{SyncEngine, SyncEngineId};
 use sync_guid::Guid;
+use types::Timestamp;
 
 // Our "sync manager" will use whatever is stashed here.
 lazy_static::lazy_static! {
@@ -43,9 +51,23 @@ pub fn get_registered_sync_engine(engine_id: &SyncEngineId) -> Option<Box<dyn Sy
     }
 }
 
+/// Register an observer to be told about schema upgrades while the database is opened. This
+/// must be called before creating the `Store` to see the upgrade of an existing database.
+pub fn set_autofill_migration_observer(observer: Box<dyn MigrationObserver>) {
+    MIGRATION_OBSERVER.set(observer);
+}
+
+pub fn unset_autofill_migration_observer() {
+    MIGRATION_OBSERVER.unset();
+}
+
 // This is the type that uniffi exposes.
 pub struct Store {
     pub(crate) db: Mutex<AutofillDb>,
+    // Used by `encrypt_string()`/`decrypt_string()`, and by the credit-card sync engine when the
+    // sync manager wasn't given a key.
+    pub(crate) key_manager: Option<Arc<dyn KeyManager>>,
+    change_observer: ChangeObserverRegistry,
 }
 
 impl Store {
@@ -53,6 +75,20 @@ impl Store {
     pub fn new(db_path: impl AsRef<Path>) -> ApiResult<Self> {
         Ok(Self {
             db: Mutex::new(AutofillDb::new(db_path)?),
+            key_manager: None,
+            change_observer: Default::default(),
+        })
+    }
+
+    #[handle_error(Error)]
+    pub fn new_with_key_manager(
+        db_path: impl AsRef<Path>,
+        key_manager: Box<dyn KeyManager>,
+    ) -> ApiResult<Self> {
+        Ok(Self {
+            db: Mutex::new(AutofillDb::new(db_path)?),
+            key_manager: Some(key_manager.into()),
+            change_observer: Default::default(),
         })
     }
 
@@ -61,6 +97,8 @@ impl Store {
     pub fn new_memory() -> Self {
         Self {
             db: Mutex::new(crate::db::test::new_mem_db()),
+            key_manager: None,
+            change_observer: Default::default(),
         }
     }
 
@@ -69,12 +107,66 @@ impl Store {
     pub fn new_shared_memory(db_name: &str) -> ApiResult<Self> {
         Ok(Self {
             db: Mutex::new(AutofillDb::new_memory(db_name)?),
+            key_manager: None,
+            change_observer: Default::default(),
         })
     }
 
+    /// Register an observer to be told when addresses and credit-cards are added, updated or
+    /// deleted, including by a sync. This replaces any observer which was already set.
+    pub fn set_change_observer(&self, observer: Box<dyn AutofillChangeObserver>) {
+        self.change_observer.set(observer);
+    }
+
+    pub fn unset_change_observer(&self) {
+        self.change_observer.unset();
+    }
+
+    // All the changes from one write or one sync. The database lock must have been released.
+    pub(crate) fn notify_changes(&self, changes: Vec<AutofillChange>) {
+        self.change_observer.notify(changes);
+    }
+
+    fn notify_change(
+        &self,
+        guid: &Guid,
+        record_type: AutofillRecordType,
+        change_type: AutofillChangeType,
+    ) {
+        self.notify_changes(vec![AutofillChange::new(guid, record_type, change_type)]);
+    }
+
+    fn encdec_from_key_manager(&self) -> Result<EncryptorDecryptor> {
+        let key = self
+            .key_manager
+            .as_ref()
+            .and_then(|key_manager| key_manager.get_key())
+            .ok_or(Error::MissingEncryptionKey)?;
+        EncryptorDecryptor::new(&key)
+    }
+
+    /// Like the top-level `encrypt_string()`, but using the key from our key manager.
+    #[handle_error(Error)]
+    pub fn encrypt_string(&self, cleartext: String) -> ApiResult<String> {
+        self.encdec_from_key_manager()?
+            .encrypt(&cleartext, "single string field")
+    }
+
+    /// Like the top-level `decrypt_string()`, but using the key from our key manager.
+    #[handle_error(Error)]
+    pub fn decrypt_string(&self, ciphertext: String) -> ApiResult<String> {
+        self.encdec_from_key_manager()?
+            .decrypt(&ciphertext, "single string field")
+    }
+
     #[handle_error(Error)]
     pub fn add_credit_card(&self, fields: UpdatableCreditCardFields) -> ApiResult<CreditCard> {
         let credit_card = credit_cards::add_credit_card(&self.db.lock().unwrap().writer, fields)?;
+        self.notify_change(
+            &credit_card.guid,
+            AutofillRecordType::CreditCard,
+            AutofillChangeType::Added,
+        );
         Ok(credit_card.into())
     }
 
@@ -94,32 +186,180 @@ impl Store {
         Ok(credit_cards)
     }
 
+    /// Get the credit cards which will expire within `days`, so the user can be reminded to
+    /// update them. Cards which have already expired aren't included.
+    #[handle_error(Error)]
+    pub fn get_credit_cards_expiring_within(&self, days: u32) -> ApiResult<Vec<CreditCard>> {
+        let credit_cards = credit_cards::get_credit_cards_expiring_within(
+            &self.db.lock().unwrap().writer,
+            days,
+            Timestamp::now(),
+        )?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+        Ok(credit_cards)
+    }
+
+    /// Get the most recently used credit card, so the UI can offer to fill it with one tap.
+    /// Expired cards are only returned if every card has expired.
+    #[handle_error(Error)]
+    pub fn get_last_used_credit_card(&self) -> ApiResult<Option<CreditCard>> {
+        let credit_card = credit_cards::get_last_used_credit_card(
+            &self.db.lock().unwrap().writer,
+            Timestamp::now(),
+        )?;
+        Ok(credit_card.map(|x| x.into()))
+    }
+
     #[handle_error(Error)]
     pub fn update_credit_card(
         &self,
         guid: String,
         credit_card: UpdatableCreditCardFields,
     ) -> ApiResult<()> {
-        credit_cards::update_credit_card(
-            &self.db.lock().unwrap().writer,
-            &Guid::new(&guid),
-            &credit_card,
-        )
+        let guid = Guid::new(&guid);
+        credit_cards::update_credit_card(&self.db.lock().unwrap().writer, &guid, &credit_card)?;
+        self.notify_change(
+            &guid,
+            AutofillRecordType::CreditCard,
+            AutofillChangeType::Updated,
+        );
+        Ok(())
     }
 
     #[handle_error(Error)]
     pub fn delete_credit_card(&self, guid: String) -> ApiResult<bool> {
-        credit_cards::delete_credit_card(&self.db.lock().unwrap().writer, &Guid::new(&guid))
+        let guid = Guid::new(&guid);
+        let deleted = credit_cards::delete_credit_card(&self.db.lock().unwrap().writer, &guid)?;
+        if deleted {
+            self.notify_change(
+                &guid,
+                AutofillRecordType::CreditCard,
+                AutofillChangeType::Deleted,
+            );
+        }
+        Ok(deleted)
     }
 
     #[handle_error(Error)]
     pub fn touch_credit_card(&self, guid: String) -> ApiResult<()> {
-        credit_cards::touch(&self.db.lock().unwrap().writer, &Guid::new(&guid))
+        let guid = Guid::new(&guid);
+        credit_cards::touch(&self.db.lock().unwrap().writer, &guid)?;
+        self.notify_change(
+            &guid,
+            AutofillRecordType::CreditCard,
+            AutofillChangeType::Updated,
+        );
+        Ok(())
+    }
+
+    /// Decrypt only the number of a credit card, so it can be filled without decrypting (or
+    /// even reading) the rest of the record. The card is marked as used, like
+    /// `touch_credit_card()`.
+    #[handle_error(Error)]
+    pub fn decrypt_card_number(&self, guid: String, key: String) -> ApiResult<String> {
+        let encdec = EncryptorDecryptor::new(&key)?;
+        let guid = Guid::new(&guid);
+        let cc_number = {
+            let db = self.db.lock().unwrap();
+            let cc_number_enc = credit_cards::get_credit_card_number_enc(&db.writer, &guid)?;
+            let cc_number = encdec.decrypt(&cc_number_enc, "cc_number")?;
+            credit_cards::touch(&db.writer, &guid)?;
+            cc_number
+        };
+        self.notify_change(
+            &guid,
+            AutofillRecordType::CreditCard,
+            AutofillChangeType::Updated,
+        );
+        Ok(cc_number)
+    }
+
+    /// Find the credit cards whose number can't be decrypted with `current_key`, for example
+    /// because an interrupted rekey left them encrypted with the old key. Cards whose number
+    /// was scrubbed are skipped.
+    #[handle_error(Error)]
+    pub fn find_records_with_unreadable_encryption(
+        &self,
+        current_key: String,
+    ) -> ApiResult<Vec<String>> {
+        let encdec = EncryptorDecryptor::new(&current_key)?;
+        let numbers =
+            credit_cards::get_all_credit_card_numbers_enc(&self.db.lock().unwrap().writer)?;
+        Ok(numbers
+            .into_iter()
+            .filter(|(_, cc_number_enc)| encdec.decrypt(cc_number_enc, "cc_number").is_err())
+            .map(|(guid, _)| guid.to_string())
+            .collect())
+    }
+
+    /// Re-encrypt the numbers of the credit cards in `guids` from `old_key` to `new_key`, to
+    /// repair the cards found by `find_records_with_unreadable_encryption()`. Cards which
+    /// don't exist, or which can't be decrypted with `old_key` either, are skipped rather than
+    /// failing the whole repair. The copies of the cards in the sync mirror are re-encrypted
+    /// too, in the same transaction. Returns the guids of the cards which were re-encrypted.
+    #[handle_error(Error)]
+    pub fn reencrypt_records(
+        &self,
+        guids: Vec<String>,
+        old_key: String,
+        new_key: String,
+    ) -> ApiResult<Vec<String>> {
+        let old_encdec = EncryptorDecryptor::new(&old_key)?;
+        let new_encdec = EncryptorDecryptor::new(&new_key)?;
+        let db = self.db.lock().unwrap();
+        let tx = db.writer.unchecked_transaction()?;
+        let mut reencrypted = Vec::with_capacity(guids.len());
+        for guid in guids {
+            let guid = Guid::new(&guid);
+            let cc_number_enc = match credit_cards::get_credit_card_number_enc(&tx, &guid) {
+                Ok(cc_number_enc) => cc_number_enc,
+                Err(Error::NoSuchRecord(_)) => {
+                    log::warn!("Not re-encrypting a card which doesn't exist");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let cc_number = match old_encdec.decrypt(&cc_number_enc, "cc_number") {
+                Ok(cc_number) => cc_number,
+                Err(e) => {
+                    log::warn!("Not re-encrypting a card which the old key can't decrypt: {e}");
+                    continue;
+                }
+            };
+            let cc_number_enc = new_encdec.encrypt(&cc_number, "cc_number")?;
+            credit_cards::set_credit_card_number_enc(&tx, &guid, &cc_number_enc)?;
+            // The mirror's copy of a synced card is encrypted with the same key, and the next
+            // sync would fail to read it if we left it under the old one.
+            if let Some(payload) = credit_cards::get_credit_card_mirror_payload(&tx, &guid)? {
+                match old_encdec.decrypt(&payload, "mirror payload") {
+                    Ok(payload) => {
+                        let payload = new_encdec.encrypt(&payload, "mirror payload")?;
+                        credit_cards::set_credit_card_mirror_payload(&tx, &guid, &payload)?;
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Not re-encrypting a mirrored card the old key can't decrypt: {e}"
+                        )
+                    }
+                }
+            }
+            reencrypted.push(guid.to_string());
+        }
+        tx.commit()?;
+        Ok(reencrypted)
     }
 
     #[handle_error(Error)]
     pub fn add_address(&self, new_address: UpdatableAddressFields) -> ApiResult<Address> {
-        Ok(addresses::add_address(&self.db.lock().unwrap().writer, new_address)?.into())
+        let address = addresses::add_address(&self.db.lock().unwrap().writer, new_address)?;
+        self.notify_change(
+            &address.guid,
+            AutofillRecordType::Address,
+            AutofillChangeType::Added,
+        );
+        Ok(address.into())
     }
 
     #[handle_error(Error)]
@@ -136,26 +376,70 @@ impl Store {
         Ok(addresses)
     }
 
+    /// Get the most recently used address, so the UI can offer to fill it with one tap.
+    #[handle_error(Error)]
+    pub fn get_last_used_address(&self) -> ApiResult<Option<Address>> {
+        let address = addresses::get_last_used_address(&self.db.lock().unwrap().writer)?;
+        Ok(address.map(|x| x.into()))
+    }
+
     #[handle_error(Error)]
     pub fn update_address(&self, guid: String, address: UpdatableAddressFields) -> ApiResult<()> {
-        addresses::update_address(&self.db.lock().unwrap().writer, &Guid::new(&guid), &address)
+        let guid = Guid::new(&guid);
+        addresses::update_address(&self.db.lock().unwrap().writer, &guid, &address)?;
+        self.notify_change(
+            &guid,
+            AutofillRecordType::Address,
+            AutofillChangeType::Updated,
+        );
+        Ok(())
     }
 
     #[handle_error(Error)]
     pub fn delete_address(&self, guid: String) -> ApiResult<bool> {
-        addresses::delete_address(&self.db.lock().unwrap().writer, &Guid::new(&guid))
+        let guid = Guid::new(&guid);
+        let deleted = addresses::delete_address(&self.db.lock().unwrap().writer, &guid)?;
+        if deleted {
+            self.notify_change(
+                &guid,
+                AutofillRecordType::Address,
+                AutofillChangeType::Deleted,
+            );
+        }
+        Ok(deleted)
     }
 
     #[handle_error(Error)]
     pub fn touch_address(&self, guid: String) -> ApiResult<()> {
-        addresses::touch(&self.db.lock().unwrap().writer, &Guid::new(&guid))
+        let guid = Guid::new(&guid);
+        addresses::touch(&self.db.lock().unwrap().writer, &guid)?;
+        self.notify_change(
+            &guid,
+            AutofillRecordType::Address,
+            AutofillChangeType::Updated,
+        );
+        Ok(())
     }
 
     #[handle_error(Error)]
     pub fn scrub_encrypted_data(self: Arc<Self>) -> ApiResult<()> {
         // scrub the data on disk
         // Currently only credit cards have encrypted data
-        credit_cards::scrub_encrypted_credit_card_data(&self.db.lock().unwrap().writer)?;
+        let changes = {
+            let db = self.db.lock().unwrap();
+            credit_cards::scrub_encrypted_credit_card_data(&db.writer)?;
+            credit_cards::get_all_credit_cards(&db.writer)?
+                .into_iter()
+                .map(|card| {
+                    AutofillChange::new(
+                        &card.guid,
+                        AutofillRecordType::CreditCard,
+                        AutofillChangeType::Updated,
+                    )
+                })
+                .collect()
+        };
+        self.notify_changes(changes);
         // Force the sync engine to refetch data (only need to do this for the credit cards, since the
         // addresses engine doesn't store encrypted data).
         crate::sync::credit_card::create_engine(self).reset_local_sync_data()?;
@@ -249,6 +533,256 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_key_manager() {
+        let key = crate::encryption::create_autofill_key().unwrap();
+        let mut store = Store::new_memory();
+        assert!(matches!(
+            store.encrypt_string("secret".to_string()),
+            Err(AutofillApiError::CryptoError { .. })
+        ));
+
+        store.key_manager = Some(Arc::new(jwcrypto::StaticKeyManager::new(key.clone())));
+        let ciphertext = store.encrypt_string("secret".to_string()).unwrap();
+        assert_eq!(
+            crate::encryption::decrypt_string(key, ciphertext.clone()).unwrap(),
+            "secret"
+        );
+        assert_eq!(store.decrypt_string(ciphertext).unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_change_observer() {
+        use crate::observer::test_utils::RecordingObserver;
+
+        // Checks the observer can use the store while it's being notified.
+        struct ReentrantObserver(Arc<Store>, RecordingObserver);
+        impl AutofillChangeObserver for ReentrantObserver {
+            fn on_autofill_changed(&self, changes: Vec<AutofillChange>) {
+                self.0.get_all_addresses().unwrap();
+                self.1.on_autofill_changed(changes);
+            }
+        }
+
+        fn change(
+            guid: &str,
+            record_type: AutofillRecordType,
+            change_type: AutofillChangeType,
+        ) -> Vec<AutofillChange> {
+            vec![AutofillChange::new(
+                &Guid::new(guid),
+                record_type,
+                change_type,
+            )]
+        }
+
+        let store = Arc::new(Store::new_memory());
+        let recorder = RecordingObserver::default();
+        store.set_change_observer(Box::new(ReentrantObserver(
+            Arc::clone(&store),
+            recorder.clone(),
+        )));
+
+        let fields = UpdatableAddressFields {
+            given_name: "jane".to_string(),
+            family_name: "doe".to_string(),
+            street_address: "123 Main Street".to_string(),
+            ..Default::default()
+        };
+        let address = store.add_address(fields.clone()).unwrap();
+        store.update_address(address.guid.clone(), fields).unwrap();
+        store.touch_address(address.guid.clone()).unwrap();
+        assert_eq!(
+            recorder.take(),
+            vec![
+                change(
+                    &address.guid,
+                    AutofillRecordType::Address,
+                    AutofillChangeType::Added
+                ),
+                change(
+                    &address.guid,
+                    AutofillRecordType::Address,
+                    AutofillChangeType::Updated
+                ),
+                change(
+                    &address.guid,
+                    AutofillRecordType::Address,
+                    AutofillChangeType::Updated
+                ),
+            ]
+        );
+
+        let card = store
+            .add_credit_card(UpdatableCreditCardFields {
+                cc_name: "jane doe".to_string(),
+                cc_number_enc: "encrypted".to_string(),
+                cc_number_last_4: "5678".to_string(),
+                cc_exp_month: 5,
+                cc_exp_year: 2024,
+                cc_type: "visa".to_string(),
+            })
+            .unwrap();
+        assert!(store.delete_credit_card(card.guid.clone()).unwrap());
+        // Deleting it again doesn't change anything.
+        assert!(!store.delete_credit_card(card.guid.clone()).unwrap());
+        assert_eq!(
+            recorder.take(),
+            vec![
+                change(
+                    &card.guid,
+                    AutofillRecordType::CreditCard,
+                    AutofillChangeType::Added
+                ),
+                change(
+                    &card.guid,
+                    AutofillRecordType::CreditCard,
+                    AutofillChangeType::Deleted
+                ),
+            ]
+        );
+
+        store.unset_change_observer();
+        store.delete_address(address.guid).unwrap();
+        assert!(recorder.take().is_empty());
+    }
+
+    #[test]
+    fn test_decrypt_card_number() {
+        let key = crate::encryption::create_autofill_key().unwrap();
+        let store = Store::new_memory();
+        let card = store
+            .add_credit_card(UpdatableCreditCardFields {
+                cc_name: "jane doe".to_string(),
+                cc_number_enc: crate::encryption::encrypt_string(
+                    key.clone(),
+                    "1234567812345678".to_string(),
+                )
+                .unwrap(),
+                cc_number_last_4: "5678".to_string(),
+                cc_exp_month: 5,
+                cc_exp_year: 2024,
+                cc_type: "visa".to_string(),
+            })
+            .unwrap();
+        assert_eq!(card.times_used, 0);
+
+        assert_eq!(
+            store
+                .decrypt_card_number(card.guid.clone(), key.clone())
+                .unwrap(),
+            "1234567812345678"
+        );
+        let card = store.get_credit_card(card.guid).unwrap();
+        assert_eq!(card.times_used, 1);
+        assert!(card.time_last_used.is_some());
+
+        // The wrong key fails, and doesn't count as a use.
+        let other_key = crate::encryption::create_autofill_key().unwrap();
+        assert!(matches!(
+            store.decrypt_card_number(card.guid.clone(), other_key),
+            Err(AutofillApiError::CryptoError { .. })
+        ));
+        assert_eq!(store.get_credit_card(card.guid).unwrap().times_used, 1);
+
+        assert!(matches!(
+            store.decrypt_card_number("not-a-guid".to_string(), key),
+            Err(AutofillApiError::NoSuchRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reencrypt_records() {
+        let old_key = crate::encryption::create_autofill_key().unwrap();
+        let new_key = crate::encryption::create_autofill_key().unwrap();
+        let store = Store::new_memory();
+        let add_card = |key: &str, number: &str| {
+            store
+                .add_credit_card(UpdatableCreditCardFields {
+                    cc_name: "jane doe".to_string(),
+                    cc_number_enc: crate::encryption::encrypt_string(
+                        key.to_string(),
+                        number.to_string(),
+                    )
+                    .unwrap(),
+                    cc_number_last_4: number[number.len() - 4..].to_string(),
+                    cc_exp_month: 5,
+                    cc_exp_year: 2024,
+                    cc_type: "visa".to_string(),
+                })
+                .unwrap()
+                .guid
+        };
+        let current = add_card(&new_key, "1111222233334444");
+        let stale = add_card(&old_key, "5555666677778888");
+        // The stale card has been synced, so it's in the mirror under the old key too.
+        store
+            .db
+            .lock()
+            .unwrap()
+            .writer
+            .execute(
+                "INSERT INTO credit_cards_mirror (guid, payload) VALUES (:guid, :payload)",
+                rusqlite::named_params! {
+                    ":guid": stale,
+                    ":payload": crate::encryption::encrypt_string(
+                        old_key.clone(),
+                        "mirrored".to_string(),
+                    )
+                    .unwrap(),
+                },
+            )
+            .unwrap();
+        let lost = add_card(
+            &crate::encryption::create_autofill_key().unwrap(),
+            "9999000011112222",
+        );
+
+        let mut unreadable = store
+            .find_records_with_unreadable_encryption(new_key.clone())
+            .unwrap();
+        unreadable.sort();
+        let mut expected = vec![stale.clone(), lost.clone()];
+        expected.sort();
+        assert_eq!(unreadable, expected);
+
+        // The card we can't decrypt with the old key either, and the unknown guid, are skipped
+        // without failing the others.
+        unreadable.push("not-a-guid".to_string());
+        assert_eq!(
+            store
+                .reencrypt_records(unreadable, old_key, new_key.clone())
+                .unwrap(),
+            vec![stale.clone()]
+        );
+        assert_eq!(
+            store
+                .decrypt_card_number(stale.clone(), new_key.clone())
+                .unwrap(),
+            "5555666677778888"
+        );
+        let mirrored = credit_cards::get_credit_card_mirror_payload(
+            &store.db.lock().unwrap().writer,
+            &Guid::new(&stale),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            crate::encryption::decrypt_string(new_key.clone(), mirrored).unwrap(),
+            "mirrored"
+        );
+        assert_eq!(
+            store.decrypt_card_number(current, new_key.clone()).unwrap(),
+            "1111222233334444"
+        );
+        assert_eq!(
+            store
+                .find_records_with_unreadable_encryption(new_key)
+                .unwrap(),
+            vec![lost]
+        );
+    }
+
     #[test]
     fn test_sync_manager_registration() {
         let store = Arc::new(Store::new_shared_memory("sync-mgr-test").unwrap());
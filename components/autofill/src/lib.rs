@@ -6,19 +6,44 @@
 #![allow(unknown_lints)]
 #![warn(rust_2018_idioms)]
 
+pub mod address_form;
 pub mod db;
 pub mod encryption;
 pub mod error;
+pub mod normalize;
+pub mod observer;
 pub mod sync;
 
 // Re-export stuff the sync manager needs.
 pub use crate::db::store::get_registered_sync_engine;
 
 // Expose stuff needed by the uniffi generated code.
+pub use crate::address_form::{
+    missing_fields, required_address_fields, AddressFieldName, AddressFormField,
+};
 use crate::db::models::address::*;
 use crate::db::models::credit_card::*;
-use crate::db::store::Store;
+use crate::db::store::{set_autofill_migration_observer, unset_autofill_migration_observer, Store};
 use crate::encryption::{create_autofill_key, decrypt_string, encrypt_string};
+pub use crate::normalize::{normalize_country, normalize_region};
+pub use crate::observer::{
+    AutofillChange, AutofillChangeObserver, AutofillChangeType, AutofillRecordType,
+};
 pub use error::{ApiResult, AutofillApiError, Error, Result};
+pub use jwcrypto::KeyManager as AutofillKeyManager;
+pub use sql_support::open_database::MigrationObserver as AutofillMigrationObserver;
+pub use types::Timestamp as AutofillTimestamp;
+
+impl UniffiCustomTypeConverter for AutofillTimestamp {
+    type Builtin = i64;
+
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(val.try_into()?)
+    }
+
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.into()
+    }
+}
 
 uniffi::include_scaffolding!("autofill");
@@ -0,0 +1,565 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+* License, v. 2.0. If a copy of the MPL was not distributed with this
+* file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+// Normalization of the free-form country and region fields of addresses.
+//
+// Countries are stored as ISO 3166-1 alpha-2 codes, which is what desktop and the form
+// autofill heuristics expect. We recognize alpha-2 and alpha-3 codes, English names
+// (including some common alternatives), and the local and former names of some countries.
+// Regions are only checked for the countries in `REGIONS`; for everywhere else we don't
+// know enough to say whether a region is valid.
+
+/// Returns the ISO 3166-1 alpha-2 code for `country`, which can be an alpha-2 or alpha-3
+/// code or a name, ignoring case and accents, or `None` if it isn't a country we recognize.
+pub fn normalize_country(country: &str) -> Option<String> {
+    let key = normalize_key(country);
+    if key.is_empty() {
+        return None;
+    }
+    COUNTRIES
+        .iter()
+        .find(|(alpha2, alpha3, name)| {
+            key.eq_ignore_ascii_case(alpha2)
+                || key.eq_ignore_ascii_case(alpha3)
+                || key == normalize_key(name)
+        })
+        .map(|(alpha2, _, _)| *alpha2)
+        .or_else(|| {
+            COUNTRY_ALIASES
+                .iter()
+                .find(|(alias, _)| key == *alias)
+                .map(|(_, alpha2)| *alpha2)
+        })
+        .map(str::to_string)
+}
+
+/// Returns the ISO 3166-2 subdivision code (without the country prefix, eg "CA" for
+/// California) for `region` in `country`, where `region` can be a code or an English name.
+/// Returns `None` if the region isn't recognized, or if we don't know the regions of the
+/// country.
+pub fn normalize_region(country: &str, region: &str) -> Option<String> {
+    let regions = regions_for_country(&normalize_country(country)?)?;
+    let key = normalize_key(region);
+    regions
+        .iter()
+        .find(|(code, name)| key.eq_ignore_ascii_case(code) || key == normalize_key(name))
+        .map(|(code, _)| code.to_string())
+}
+
+/// Whether we know the regions of `country_code`, and so can tell when one isn't valid.
+pub(crate) fn has_known_regions(country_code: &str) -> bool {
+    regions_for_country(country_code).is_some()
+}
+
+fn regions_for_country(country_code: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    REGIONS
+        .iter()
+        .find(|(code, _)| *code == country_code)
+        .map(|(_, regions)| *regions)
+}
+
+// Lowercase, without periods (so "U.S.A." matches "USA"), with whitespace collapsed and
+// with the accents of common Latin letters removed (so "México" matches "Mexico").
+fn normalize_key(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('.', "")
+        .to_lowercase()
+        .chars()
+        .map(fold_accent)
+        .collect()
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' | 'č' => 'c',
+        'è' | 'é' | 'ê' | 'ë' | 'ě' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ř' => 'r',
+        'š' => 's',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ž' => 'z',
+        _ => c,
+    }
+}
+
+// (alpha-2, alpha-3, English name)
+const COUNTRIES: &[(&str, &str, &str)] = &[
+    ("AD", "AND", "Andorra"),
+    ("AE", "ARE", "United Arab Emirates"),
+    ("AF", "AFG", "Afghanistan"),
+    ("AG", "ATG", "Antigua and Barbuda"),
+    ("AI", "AIA", "Anguilla"),
+    ("AL", "ALB", "Albania"),
+    ("AM", "ARM", "Armenia"),
+    ("AO", "AGO", "Angola"),
+    ("AQ", "ATA", "Antarctica"),
+    ("AR", "ARG", "Argentina"),
+    ("AS", "ASM", "American Samoa"),
+    ("AT", "AUT", "Austria"),
+    ("AU", "AUS", "Australia"),
+    ("AW", "ABW", "Aruba"),
+    ("AX", "ALA", "Åland Islands"),
+    ("AZ", "AZE", "Azerbaijan"),
+    ("BA", "BIH", "Bosnia and Herzegovina"),
+    ("BB", "BRB", "Barbados"),
+    ("BD", "BGD", "Bangladesh"),
+    ("BE", "BEL", "Belgium"),
+    ("BF", "BFA", "Burkina Faso"),
+    ("BG", "BGR", "Bulgaria"),
+    ("BH", "BHR", "Bahrain"),
+    ("BI", "BDI", "Burundi"),
+    ("BJ", "BEN", "Benin"),
+    ("BL", "BLM", "Saint Barthélemy"),
+    ("BM", "BMU", "Bermuda"),
+    ("BN", "BRN", "Brunei"),
+    ("BO", "BOL", "Bolivia"),
+    ("BQ", "BES", "Caribbean Netherlands"),
+    ("BR", "BRA", "Brazil"),
+    ("BS", "BHS", "Bahamas"),
+    ("BT", "BTN", "Bhutan"),
+    ("BV", "BVT", "Bouvet Island"),
+    ("BW", "BWA", "Botswana"),
+    ("BY", "BLR", "Belarus"),
+    ("BZ", "BLZ", "Belize"),
+    ("CA", "CAN", "Canada"),
+    ("CC", "CCK", "Cocos (Keeling) Islands"),
+    ("CD", "COD", "Democratic Republic of the Congo"),
+    ("CF", "CAF", "Central African Republic"),
+    ("CG", "COG", "Republic of the Congo"),
+    ("CH", "CHE", "Switzerland"),
+    ("CI", "CIV", "Côte d'Ivoire"),
+    ("CK", "COK", "Cook Islands"),
+    ("CL", "CHL", "Chile"),
+    ("CM", "CMR", "Cameroon"),
+    ("CN", "CHN", "China"),
+    ("CO", "COL", "Colombia"),
+    ("CR", "CRI", "Costa Rica"),
+    ("CU", "CUB", "Cuba"),
+    ("CV", "CPV", "Cabo Verde"),
+    ("CW", "CUW", "Curaçao"),
+    ("CX", "CXR", "Christmas Island"),
+    ("CY", "CYP", "Cyprus"),
+    ("CZ", "CZE", "Czechia"),
+    ("DE", "DEU", "Germany"),
+    ("DJ", "DJI", "Djibouti"),
+    ("DK", "DNK", "Denmark"),
+    ("DM", "DMA", "Dominica"),
+    ("DO", "DOM", "Dominican Republic"),
+    ("DZ", "DZA", "Algeria"),
+    ("EC", "ECU", "Ecuador"),
+    ("EE", "EST", "Estonia"),
+    ("EG", "EGY", "Egypt"),
+    ("EH", "ESH", "Western Sahara"),
+    ("ER", "ERI", "Eritrea"),
+    ("ES", "ESP", "Spain"),
+    ("ET", "ETH", "Ethiopia"),
+    ("FI", "FIN", "Finland"),
+    ("FJ", "FJI", "Fiji"),
+    ("FK", "FLK", "Falkland Islands"),
+    ("FM", "FSM", "Micronesia"),
+    ("FO", "FRO", "Faroe Islands"),
+    ("FR", "FRA", "France"),
+    ("GA", "GAB", "Gabon"),
+    ("GB", "GBR", "United Kingdom"),
+    ("GD", "GRD", "Grenada"),
+    ("GE", "GEO", "Georgia"),
+    ("GF", "GUF", "French Guiana"),
+    ("GG", "GGY", "Guernsey"),
+    ("GH", "GHA", "Ghana"),
+    ("GI", "GIB", "Gibraltar"),
+    ("GL", "GRL", "Greenland"),
+    ("GM", "GMB", "Gambia"),
+    ("GN", "GIN", "Guinea"),
+    ("GP", "GLP", "Guadeloupe"),
+    ("GQ", "GNQ", "Equatorial Guinea"),
+    ("GR", "GRC", "Greece"),
+    ("GS", "SGS", "South Georgia and the South Sandwich Islands"),
+    ("GT", "GTM", "Guatemala"),
+    ("GU", "GUM", "Guam"),
+    ("GW", "GNB", "Guinea-Bissau"),
+    ("GY", "GUY", "Guyana"),
+    ("HK", "HKG", "Hong Kong"),
+    ("HM", "HMD", "Heard Island and McDonald Islands"),
+    ("HN", "HND", "Honduras"),
+    ("HR", "HRV", "Croatia"),
+    ("HT", "HTI", "Haiti"),
+    ("HU", "HUN", "Hungary"),
+    ("ID", "IDN", "Indonesia"),
+    ("IE", "IRL", "Ireland"),
+    ("IL", "ISR", "Israel"),
+    ("IM", "IMN", "Isle of Man"),
+    ("IN", "IND", "India"),
+    ("IO", "IOT", "British Indian Ocean Territory"),
+    ("IQ", "IRQ", "Iraq"),
+    ("IR", "IRN", "Iran"),
+    ("IS", "ISL", "Iceland"),
+    ("IT", "ITA", "Italy"),
+    ("JE", "JEY", "Jersey"),
+    ("JM", "JAM", "Jamaica"),
+    ("JO", "JOR", "Jordan"),
+    ("JP", "JPN", "Japan"),
+    ("KE", "KEN", "Kenya"),
+    ("KG", "KGZ", "Kyrgyzstan"),
+    ("KH", "KHM", "Cambodia"),
+    ("KI", "KIR", "Kiribati"),
+    ("KM", "COM", "Comoros"),
+    ("KN", "KNA", "Saint Kitts and Nevis"),
+    ("KP", "PRK", "North Korea"),
+    ("KR", "KOR", "South Korea"),
+    ("KW", "KWT", "Kuwait"),
+    ("KY", "CYM", "Cayman Islands"),
+    ("KZ", "KAZ", "Kazakhstan"),
+    ("LA", "LAO", "Laos"),
+    ("LB", "LBN", "Lebanon"),
+    ("LC", "LCA", "Saint Lucia"),
+    ("LI", "LIE", "Liechtenstein"),
+    ("LK", "LKA", "Sri Lanka"),
+    ("LR", "LBR", "Liberia"),
+    ("LS", "LSO", "Lesotho"),
+    ("LT", "LTU", "Lithuania"),
+    ("LU", "LUX", "Luxembourg"),
+    ("LV", "LVA", "Latvia"),
+    ("LY", "LBY", "Libya"),
+    ("MA", "MAR", "Morocco"),
+    ("MC", "MCO", "Monaco"),
+    ("MD", "MDA", "Moldova"),
+    ("ME", "MNE", "Montenegro"),
+    ("MF", "MAF", "Saint Martin"),
+    ("MG", "MDG", "Madagascar"),
+    ("MH", "MHL", "Marshall Islands"),
+    ("MK", "MKD", "North Macedonia"),
+    ("ML", "MLI", "Mali"),
+    ("MM", "MMR", "Myanmar"),
+    ("MN", "MNG", "Mongolia"),
+    ("MO", "MAC", "Macao"),
+    ("MP", "MNP", "Northern Mariana Islands"),
+    ("MQ", "MTQ", "Martinique"),
+    ("MR", "MRT", "Mauritania"),
+    ("MS", "MSR", "Montserrat"),
+    ("MT", "MLT", "Malta"),
+    ("MU", "MUS", "Mauritius"),
+    ("MV", "MDV", "Maldives"),
+    ("MW", "MWI", "Malawi"),
+    ("MX", "MEX", "Mexico"),
+    ("MY", "MYS", "Malaysia"),
+    ("MZ", "MOZ", "Mozambique"),
+    ("NA", "NAM", "Namibia"),
+    ("NC", "NCL", "New Caledonia"),
+    ("NE", "NER", "Niger"),
+    ("NF", "NFK", "Norfolk Island"),
+    ("NG", "NGA", "Nigeria"),
+    ("NI", "NIC", "Nicaragua"),
+    ("NL", "NLD", "Netherlands"),
+    ("NO", "NOR", "Norway"),
+    ("NP", "NPL", "Nepal"),
+    ("NR", "NRU", "Nauru"),
+    ("NU", "NIU", "Niue"),
+    ("NZ", "NZL", "New Zealand"),
+    ("OM", "OMN", "Oman"),
+    ("PA", "PAN", "Panama"),
+    ("PE", "PER", "Peru"),
+    ("PF", "PYF", "French Polynesia"),
+    ("PG", "PNG", "Papua New Guinea"),
+    ("PH", "PHL", "Philippines"),
+    ("PK", "PAK", "Pakistan"),
+    ("PL", "POL", "Poland"),
+    ("PM", "SPM", "Saint Pierre and Miquelon"),
+    ("PN", "PCN", "Pitcairn Islands"),
+    ("PR", "PRI", "Puerto Rico"),
+    ("PS", "PSE", "Palestine"),
+    ("PT", "PRT", "Portugal"),
+    ("PW", "PLW", "Palau"),
+    ("PY", "PRY", "Paraguay"),
+    ("QA", "QAT", "Qatar"),
+    ("RE", "REU", "Réunion"),
+    ("RO", "ROU", "Romania"),
+    ("RS", "SRB", "Serbia"),
+    ("RU", "RUS", "Russia"),
+    ("RW", "RWA", "Rwanda"),
+    ("SA", "SAU", "Saudi Arabia"),
+    ("SB", "SLB", "Solomon Islands"),
+    ("SC", "SYC", "Seychelles"),
+    ("SD", "SDN", "Sudan"),
+    ("SE", "SWE", "Sweden"),
+    ("SG", "SGP", "Singapore"),
+    ("SH", "SHN", "Saint Helena, Ascension and Tristan da Cunha"),
+    ("SI", "SVN", "Slovenia"),
+    ("SJ", "SJM", "Svalbard and Jan Mayen"),
+    ("SK", "SVK", "Slovakia"),
+    ("SL", "SLE", "Sierra Leone"),
+    ("SM", "SMR", "San Marino"),
+    ("SN", "SEN", "Senegal"),
+    ("SO", "SOM", "Somalia"),
+    ("SR", "SUR", "Suriname"),
+    ("SS", "SSD", "South Sudan"),
+    ("ST", "STP", "São Tomé and Príncipe"),
+    ("SV", "SLV", "El Salvador"),
+    ("SX", "SXM", "Sint Maarten"),
+    ("SY", "SYR", "Syria"),
+    ("SZ", "SWZ", "Eswatini"),
+    ("TC", "TCA", "Turks and Caicos Islands"),
+    ("TD", "TCD", "Chad"),
+    ("TF", "ATF", "French Southern Territories"),
+    ("TG", "TGO", "Togo"),
+    ("TH", "THA", "Thailand"),
+    ("TJ", "TJK", "Tajikistan"),
+    ("TK", "TKL", "Tokelau"),
+    ("TL", "TLS", "Timor-Leste"),
+    ("TM", "TKM", "Turkmenistan"),
+    ("TN", "TUN", "Tunisia"),
+    ("TO", "TON", "Tonga"),
+    ("TR", "TUR", "Türkiye"),
+    ("TT", "TTO", "Trinidad and Tobago"),
+    ("TV", "TUV", "Tuvalu"),
+    ("TW", "TWN", "Taiwan"),
+    ("TZ", "TZA", "Tanzania"),
+    ("UA", "UKR", "Ukraine"),
+    ("UG", "UGA", "Uganda"),
+    ("UM", "UMI", "United States Minor Outlying Islands"),
+    ("US", "USA", "United States"),
+    ("UY", "URY", "Uruguay"),
+    ("UZ", "UZB", "Uzbekistan"),
+    ("VA", "VAT", "Vatican City"),
+    ("VC", "VCT", "Saint Vincent and the Grenadines"),
+    ("VE", "VEN", "Venezuela"),
+    ("VG", "VGB", "British Virgin Islands"),
+    ("VI", "VIR", "United States Virgin Islands"),
+    ("VN", "VNM", "Vietnam"),
+    ("VU", "VUT", "Vanuatu"),
+    ("WF", "WLF", "Wallis and Futuna"),
+    ("WS", "WSM", "Samoa"),
+    ("YE", "YEM", "Yemen"),
+    ("YT", "MYT", "Mayotte"),
+    ("ZA", "ZAF", "South Africa"),
+    ("ZM", "ZMB", "Zambia"),
+    ("ZW", "ZWE", "Zimbabwe"),
+];
+
+// Other names people commonly use, already passed through `normalize_key()`.
+const COUNTRY_ALIASES: &[(&str, &str)] = &[
+    ("aland islands", "AX"),
+    ("america", "US"),
+    ("belgie", "BE"),
+    ("belgien", "BE"),
+    ("belgique", "BE"),
+    ("brasil", "BR"),
+    ("burma", "MM"),
+    ("cape verde", "CV"),
+    ("ceska republika", "CZ"),
+    ("cesko", "CZ"),
+    ("ceylon", "LK"),
+    ("congo", "CG"),
+    ("cote d'ivoire", "CI"),
+    ("curacao", "CW"),
+    ("czech republic", "CZ"),
+    ("dahomey", "BJ"),
+    ("danmark", "DK"),
+    ("deutschland", "DE"),
+    ("east timor", "TL"),
+    ("eire", "IE"),
+    ("england", "GB"),
+    ("espana", "ES"),
+    ("great britain", "GB"),
+    ("hellas", "GR"),
+    ("holland", "NL"),
+    ("holy see", "VA"),
+    ("italia", "IT"),
+    ("ivory coast", "CI"),
+    ("kampuchea", "KH"),
+    ("korea", "KR"),
+    ("macau", "MO"),
+    ("macedonia", "MK"),
+    ("magyarorszag", "HU"),
+    ("nederland", "NL"),
+    ("norge", "NO"),
+    ("osterreich", "AT"),
+    ("persia", "IR"),
+    ("polska", "PL"),
+    ("republic of korea", "KR"),
+    ("reunion", "RE"),
+    ("russian federation", "RU"),
+    ("saint barthelemy", "BL"),
+    ("sao tome and principe", "ST"),
+    ("schweiz", "CH"),
+    ("scotland", "GB"),
+    ("suisse", "CH"),
+    ("suomi", "FI"),
+    ("sverige", "SE"),
+    ("svizzera", "CH"),
+    ("swaziland", "SZ"),
+    ("the netherlands", "NL"),
+    ("turkey", "TR"),
+    ("turkiye", "TR"),
+    ("uk", "GB"),
+    ("united kingdom of great britain and northern ireland", "GB"),
+    ("united states of america", "US"),
+    ("upper volta", "BF"),
+    ("us virgin islands", "VI"),
+    ("viet nam", "VN"),
+    ("wales", "GB"),
+    ("western samoa", "WS"),
+    ("zaire", "CD"),
+    ("россия", "RU"),
+    ("中国", "CN"),
+    ("日本", "JP"),
+];
+
+const REGIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "CA",
+        &[
+            ("AB", "Alberta"),
+            ("BC", "British Columbia"),
+            ("MB", "Manitoba"),
+            ("NB", "New Brunswick"),
+            ("NL", "Newfoundland and Labrador"),
+            ("NS", "Nova Scotia"),
+            ("NT", "Northwest Territories"),
+            ("NU", "Nunavut"),
+            ("ON", "Ontario"),
+            ("PE", "Prince Edward Island"),
+            ("QC", "Quebec"),
+            ("SK", "Saskatchewan"),
+            ("YT", "Yukon"),
+        ],
+    ),
+    (
+        "US",
+        &[
+            ("AL", "Alabama"),
+            ("AK", "Alaska"),
+            ("AS", "American Samoa"),
+            ("AZ", "Arizona"),
+            ("AR", "Arkansas"),
+            ("CA", "California"),
+            ("CO", "Colorado"),
+            ("CT", "Connecticut"),
+            ("DE", "Delaware"),
+            ("DC", "District of Columbia"),
+            ("FL", "Florida"),
+            ("GA", "Georgia"),
+            ("GU", "Guam"),
+            ("HI", "Hawaii"),
+            ("ID", "Idaho"),
+            ("IL", "Illinois"),
+            ("IN", "Indiana"),
+            ("IA", "Iowa"),
+            ("KS", "Kansas"),
+            ("KY", "Kentucky"),
+            ("LA", "Louisiana"),
+            ("ME", "Maine"),
+            ("MD", "Maryland"),
+            ("MA", "Massachusetts"),
+            ("MI", "Michigan"),
+            ("MN", "Minnesota"),
+            ("MS", "Mississippi"),
+            ("MO", "Missouri"),
+            ("MT", "Montana"),
+            ("NE", "Nebraska"),
+            ("NV", "Nevada"),
+            ("NH", "New Hampshire"),
+            ("NJ", "New Jersey"),
+            ("NM", "New Mexico"),
+            ("NY", "New York"),
+            ("NC", "North Carolina"),
+            ("ND", "North Dakota"),
+            ("MP", "Northern Mariana Islands"),
+            ("OH", "Ohio"),
+            ("OK", "Oklahoma"),
+            ("OR", "Oregon"),
+            ("PA", "Pennsylvania"),
+            ("PR", "Puerto Rico"),
+            ("RI", "Rhode Island"),
+            ("SC", "South Carolina"),
+            ("SD", "South Dakota"),
+            ("TN", "Tennessee"),
+            ("TX", "Texas"),
+            ("UM", "United States Minor Outlying Islands"),
+            ("UT", "Utah"),
+            ("VT", "Vermont"),
+            ("VI", "United States Virgin Islands"),
+            ("VA", "Virginia"),
+            ("WA", "Washington"),
+            ("WV", "West Virginia"),
+            ("WI", "Wisconsin"),
+            ("WY", "Wyoming"),
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_country() {
+        for input in [
+            "US",
+            "us",
+            "USA",
+            "U.S.A.",
+            "United States",
+            "  united   states ",
+            "United States of America",
+        ] {
+            assert_eq!(normalize_country(input).as_deref(), Some("US"), "{}", input);
+        }
+        assert_eq!(normalize_country("gbr").as_deref(), Some("GB"));
+        assert_eq!(normalize_country("UK").as_deref(), Some("GB"));
+        assert_eq!(normalize_country("UKR").as_deref(), Some("UA"));
+        assert_eq!(normalize_country("Côte d'Ivoire").as_deref(), Some("CI"));
+        assert_eq!(normalize_country("cote d'ivoire").as_deref(), Some("CI"));
+        assert_eq!(normalize_country("Germany").as_deref(), Some("DE"));
+        assert_eq!(normalize_country("Deutschland").as_deref(), Some("DE"));
+        assert_eq!(normalize_country("ESPAÑA").as_deref(), Some("ES"));
+        assert_eq!(normalize_country("Österreich").as_deref(), Some("AT"));
+        assert_eq!(normalize_country("México").as_deref(), Some("MX"));
+        assert_eq!(normalize_country("日本").as_deref(), Some("JP"));
+        assert_eq!(normalize_country("Zaire").as_deref(), Some("CD"));
+        assert_eq!(normalize_country(""), None);
+        assert_eq!(normalize_country("Atlantis"), None);
+        assert_eq!(normalize_country("XX"), None);
+    }
+
+    #[test]
+    fn test_country_table() {
+        for (i, (alpha2, alpha3, _)) in COUNTRIES.iter().enumerate() {
+            assert_eq!(alpha2.len(), 2);
+            assert_eq!(alpha3.len(), 3);
+            assert!(
+                COUNTRIES[i + 1..]
+                    .iter()
+                    .all(|(a2, a3, _)| a2 != alpha2 && a3 != alpha3),
+                "duplicate entry for {}",
+                alpha2
+            );
+        }
+        for (alias, alpha2) in COUNTRY_ALIASES {
+            assert_eq!(normalize_key(alias), *alias);
+            assert!(COUNTRIES.iter().any(|(a2, _, _)| a2 == alpha2));
+        }
+    }
+
+    #[test]
+    fn test_normalize_region() {
+        assert_eq!(normalize_region("US", "CA").as_deref(), Some("CA"));
+        assert_eq!(normalize_region("US", "california").as_deref(), Some("CA"));
+        assert_eq!(normalize_region("USA", "New York").as_deref(), Some("NY"));
+        assert_eq!(normalize_region("Canada", "quebec").as_deref(), Some("QC"));
+        assert_eq!(normalize_region("CA", "ON").as_deref(), Some("ON"));
+        assert_eq!(normalize_region("US", "ON"), None);
+        assert_eq!(normalize_region("US", "Narnia"), None);
+        // We don't know the regions of every country.
+        assert_eq!(normalize_region("DE", "Bayern"), None);
+        assert!(has_known_regions("US"));
+        assert!(!has_known_regions("DE"));
+    }
+}
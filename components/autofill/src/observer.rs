@@ -0,0 +1,95 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Notifying the app about changes to the addresses and credit-cards in a `Store`, whether they
+// were made by the app itself or applied by a sync.
+
+use std::sync::{Arc, Mutex};
+use sync_guid::Guid;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AutofillRecordType {
+    Address,
+    CreditCard,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AutofillChangeType {
+    Added,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct AutofillChange {
+    pub guid: String,
+    pub record_type: AutofillRecordType,
+    pub change_type: AutofillChangeType,
+}
+
+impl AutofillChange {
+    pub(crate) fn new(
+        guid: &Guid,
+        record_type: AutofillRecordType,
+        change_type: AutofillChangeType,
+    ) -> Self {
+        Self {
+            guid: guid.to_string(),
+            record_type,
+            change_type,
+        }
+    }
+}
+
+/// Told about the records which changed, once for each write to the store and once for each
+/// sync which changed anything.
+pub trait AutofillChangeObserver: Send + Sync {
+    fn on_autofill_changed(&self, changes: Vec<AutofillChange>);
+}
+
+#[derive(Default)]
+pub(crate) struct ChangeObserverRegistry(Mutex<Option<Arc<dyn AutofillChangeObserver>>>);
+
+impl ChangeObserverRegistry {
+    pub(crate) fn set(&self, observer: Box<dyn AutofillChangeObserver>) {
+        *self.0.lock().unwrap() = Some(observer.into());
+    }
+
+    pub(crate) fn unset(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+
+    // Callers must not be holding the database lock, so that the observer can use the store.
+    // We don't hold our own lock while notifying either, so the observer can unset itself.
+    pub(crate) fn notify(&self, changes: Vec<AutofillChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        let observer = self.0.lock().unwrap().clone();
+        if let Some(observer) = observer {
+            observer.on_autofill_changed(changes);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+
+    // Remembers each batch of changes it was told about.
+    #[derive(Clone, Default)]
+    pub struct RecordingObserver(Arc<Mutex<Vec<Vec<AutofillChange>>>>);
+
+    impl RecordingObserver {
+        pub fn take(&self) -> Vec<Vec<AutofillChange>> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    impl AutofillChangeObserver for RecordingObserver {
+        fn on_autofill_changed(&self, changes: Vec<AutofillChange>) {
+            self.0.lock().unwrap().push(changes);
+        }
+    }
+}
@@ -13,11 +13,13 @@ use super::{
 };
 use crate::db::models::address::InternalAddress;
 use crate::error::*;
+use crate::observer::AutofillRecordType;
 use crate::sync_merge_field_check;
 use incoming::IncomingAddressesImpl;
 use outgoing::OutgoingAddressesImpl;
-use rusqlite::Transaction;
+use rusqlite::{Connection, Transaction};
 use serde::{Deserialize, Serialize};
+use sql_support::ConnExt;
 use std::sync::Arc;
 use sync_guid::Guid;
 use types::Timestamp;
@@ -28,6 +30,7 @@ pub(crate) fn create_engine(store: Arc<crate::Store>) -> ConfigSyncEngine<Intern
         EngineConfig {
             namespace: "addresses".to_string(),
             collection: "addresses".into(),
+            record_type: AutofillRecordType::Address,
         },
         store,
         Box::new(AddressesEngineStorageImpl {}),
@@ -60,6 +63,18 @@ impl SyncEngineStorageImpl<InternalAddress> for AddressesEngineStorageImpl {
         assert!(enc_key.is_none());
         Ok(Box::new(OutgoingAddressesImpl {}))
     }
+
+    fn has_outgoing(&self, conn: &Connection) -> Result<bool> {
+        Ok(conn.exists(
+            "SELECT 1 FROM addresses_data
+             WHERE sync_change_counter > 0
+                OR guid NOT IN (SELECT guid FROM addresses_mirror)
+             UNION ALL
+             SELECT 1 FROM addresses_tombstones
+             LIMIT 1",
+            [],
+        )?)
+    }
 }
 
 // These structs are a representation of what's stored on the sync server for non-tombstone records.
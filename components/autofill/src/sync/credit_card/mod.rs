@@ -14,39 +14,59 @@ use super::{
 use crate::db::models::credit_card::InternalCreditCard;
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
+use crate::observer::AutofillRecordType;
 use crate::sync_merge_field_check;
 use incoming::IncomingCreditCardsImpl;
+use jwcrypto::KeyManager;
 use outgoing::OutgoingCreditCardsImpl;
-use rusqlite::Transaction;
+use rusqlite::{Connection, Transaction};
 use serde::{Deserialize, Serialize};
+use sql_support::ConnExt;
 use std::sync::Arc;
 use sync_guid::Guid;
 use types::Timestamp;
 
 // The engine.
 pub(crate) fn create_engine(store: Arc<crate::Store>) -> ConfigSyncEngine<InternalCreditCard> {
+    let key_manager = store.key_manager.clone();
     ConfigSyncEngine::new(
         EngineConfig {
             namespace: "credit_cards".to_string(),
             collection: "creditcards".into(),
+            record_type: AutofillRecordType::CreditCard,
         },
         store,
-        Box::new(CreditCardsEngineStorageImpl {}),
+        Box::new(CreditCardsEngineStorageImpl { key_manager }),
     )
 }
 
-pub(super) struct CreditCardsEngineStorageImpl {}
+pub(super) struct CreditCardsEngineStorageImpl {
+    // Used when the sync manager wasn't given a key.
+    key_manager: Option<Arc<dyn KeyManager>>,
+}
+
+impl CreditCardsEngineStorageImpl {
+    // Only called when we're about to encrypt or decrypt, so the key manager isn't asked for
+    // the key on the paths which don't need it.
+    fn encdec(&self, enc_key: &Option<String>) -> Result<EncryptorDecryptor> {
+        let enc_key = match enc_key {
+            Some(enc_key) => enc_key.clone(),
+            None => self
+                .key_manager
+                .as_ref()
+                .and_then(|key_manager| key_manager.get_key())
+                .ok_or(Error::MissingEncryptionKey)?,
+        };
+        EncryptorDecryptor::new(&enc_key)
+    }
+}
 
 impl SyncEngineStorageImpl<InternalCreditCard> for CreditCardsEngineStorageImpl {
     fn get_incoming_impl(
         &self,
         enc_key: &Option<String>,
     ) -> Result<Box<dyn ProcessIncomingRecordImpl<Record = InternalCreditCard>>> {
-        let enc_key = match enc_key {
-            None => return Err(Error::MissingEncryptionKey),
-            Some(enc_key) => enc_key,
-        };
-        let encdec = EncryptorDecryptor::new(enc_key)?;
+        let encdec = self.encdec(enc_key)?;
         Ok(Box::new(IncomingCreditCardsImpl { encdec }))
     }
 
@@ -62,13 +82,21 @@ impl SyncEngineStorageImpl<InternalCreditCard> for CreditCardsEngineStorageImpl
         &self,
         enc_key: &Option<String>,
     ) -> Result<Box<dyn ProcessOutgoingRecordImpl<Record = InternalCreditCard>>> {
-        let enc_key = match enc_key {
-            None => return Err(Error::MissingEncryptionKey),
-            Some(enc_key) => enc_key,
-        };
-        let encdec = EncryptorDecryptor::new(enc_key)?;
+        let encdec = self.encdec(enc_key)?;
         Ok(Box::new(OutgoingCreditCardsImpl { encdec }))
     }
+
+    fn has_outgoing(&self, conn: &Connection) -> Result<bool> {
+        Ok(conn.exists(
+            "SELECT 1 FROM credit_cards_data
+             WHERE sync_change_counter > 0
+                OR guid NOT IN (SELECT guid FROM credit_cards_mirror)
+             UNION ALL
+             SELECT 1 FROM credit_cards_tombstones
+             LIMIT 1",
+            [],
+        )?)
+    }
 }
 
 // These structs are a representation of what's stored on the sync server for non-tombstone records.
@@ -4,6 +4,7 @@
 
 use super::{plan_incoming, ProcessIncomingRecordImpl, ProcessOutgoingRecordImpl, SyncRecord};
 use crate::error::*;
+use crate::observer::AutofillRecordType;
 use crate::Store;
 use rusqlite::{
     types::{FromSql, ToSql},
@@ -18,8 +19,9 @@ use sync_guid::Guid;
 // We have 2 engines in this crate and they are identical except for stuff
 // abstracted here!
 pub struct EngineConfig {
-    pub(crate) namespace: String,          // prefix for meta keys, etc.
-    pub(crate) collection: CollectionName, // collection name on the server.
+    pub(crate) namespace: String,               // prefix for meta keys, etc.
+    pub(crate) collection: CollectionName,      // collection name on the server.
+    pub(crate) record_type: AutofillRecordType, // for notifying the store's change observer.
 }
 
 // meta keys, will be prefixed by the "namespace"
@@ -38,6 +40,9 @@ pub trait SyncEngineStorageImpl<T> {
         &self,
         enc_key: &Option<String>,
     ) -> Result<Box<dyn ProcessOutgoingRecordImpl<Record = T>>>;
+    // Whether the outgoing impl would find any records to upload. Unlike
+    // fetching them, this doesn't need the encryption key.
+    fn has_outgoing(&self, conn: &Connection) -> Result<bool>;
 }
 
 // A sync engine that gets functionality from an EngineConfig.
@@ -130,34 +135,41 @@ impl<T: SyncRecord + std::fmt::Debug> SyncEngine for ConfigSyncEngine<T> {
         timestamp: ServerTimestamp,
         _telem: &mut telemetry::Engine,
     ) -> anyhow::Result<Vec<OutgoingBso>> {
-        let db = &self.store.db.lock().unwrap();
-        let signal = db.begin_interrupt_scope()?;
-        let tx = db.writer.unchecked_transaction()?;
-        let incoming_impl = self.storage_impl.get_incoming_impl(&self.local_enc_key)?;
-        let outgoing_impl = self.storage_impl.get_outgoing_impl(&self.local_enc_key)?;
-
-        // Get "states" for each record...
-        for state in incoming_impl.fetch_incoming_states(&tx)? {
-            signal.err_if_interrupted()?;
-            // Finally get a "plan" and apply it.
-            let action = plan_incoming(&*incoming_impl, &tx, state)?;
-            super::apply_incoming_action(&*incoming_impl, &tx, action)?;
-        }
+        let mut changes = Vec::new();
+        let outgoing = {
+            let db = &self.store.db.lock().unwrap();
+            let signal = db.begin_interrupt_scope()?;
+            let tx = db.writer.unchecked_transaction()?;
+            let incoming_impl = self.storage_impl.get_incoming_impl(&self.local_enc_key)?;
+            let outgoing_impl = self.storage_impl.get_outgoing_impl(&self.local_enc_key)?;
+
+            // Get "states" for each record...
+            for state in incoming_impl.fetch_incoming_states(&tx)? {
+                signal.err_if_interrupted()?;
+                // Finally get a "plan" and apply it.
+                let action = plan_incoming(&*incoming_impl, &tx, state)?;
+                changes.extend(action.changes(self.config.record_type));
+                super::apply_incoming_action(&*incoming_impl, &tx, action)?;
+            }
 
-        // write the timestamp now, so if we are interrupted merging or
-        // creating outgoing changesets we don't need to re-download the same
-        // records.
-        self.put_meta(&tx, LAST_SYNC_META_KEY, &timestamp.as_millis())?;
+            // write the timestamp now, so if we are interrupted merging or
+            // creating outgoing changesets we don't need to re-download the same
+            // records.
+            self.put_meta(&tx, LAST_SYNC_META_KEY, &timestamp.as_millis())?;
 
-        incoming_impl.finish_incoming(&tx)?;
+            incoming_impl.finish_incoming(&tx)?;
 
-        // Finally, stage outgoing items.
-        let outgoing = outgoing_impl.fetch_outgoing_records(&tx)?;
-        // we're committing now because it may take a long time to actually perform the upload
-        // and we've already staged everything we need to complete the sync in a way that
-        // doesn't require the transaction to stay alive, so we commit now and start a new
-        // transaction once complete
-        tx.commit()?;
+            // Finally, stage outgoing items.
+            let outgoing = outgoing_impl.fetch_outgoing_records(&tx)?;
+            // we're committing now because it may take a long time to actually perform the upload
+            // and we've already staged everything we need to complete the sync in a way that
+            // doesn't require the transaction to stay alive, so we commit now and start a new
+            // transaction once complete
+            tx.commit()?;
+            outgoing
+        };
+        // Only once the database lock is released, so the observer can use the store.
+        self.store.notify_changes(changes);
         Ok(outgoing)
     }
 
@@ -171,6 +183,11 @@ impl<T: SyncRecord + std::fmt::Debug> SyncEngine for ConfigSyncEngine<T> {
         Ok(())
     }
 
+    // Addresses and credit cards don't depend on each other, so any we fail to upload can wait.
+    fn upload_must_be_atomic(&self) -> bool {
+        false
+    }
+
     fn get_collection_request(
         &self,
         server_timestamp: ServerTimestamp,
@@ -231,6 +248,11 @@ impl<T: SyncRecord + std::fmt::Debug> SyncEngine for ConfigSyncEngine<T> {
         log::warn!("not implemented as there isn't a valid use case for it");
         Ok(())
     }
+
+    fn has_pending_changes(&self) -> anyhow::Result<bool> {
+        let db = &self.store.db.lock().unwrap();
+        Ok(self.storage_impl.has_outgoing(&db.writer)?)
+    }
 }
 
 #[cfg(test)]
@@ -331,6 +353,127 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_has_pending_changes() -> Result<()> {
+        let engine = create_engine();
+        let encdec = EncryptorDecryptor::new_with_random_key().unwrap();
+        assert!(!engine.has_pending_changes().unwrap());
+
+        let cc = InternalCreditCard {
+            guid: Guid::random(),
+            cc_name: "Ms Jane Doe".to_string(),
+            cc_number_enc: encdec.encrypt("12341232412341234", "cc_number")?,
+            cc_number_last_4: "1234".to_string(),
+            cc_exp_month: 12,
+            cc_exp_year: 2021,
+            cc_type: "visa".to_string(),
+            ..Default::default()
+        };
+        {
+            let db = &engine.store.db.lock().unwrap();
+            let tx = db.writer.unchecked_transaction()?;
+            // Records which have never been synced need uploading.
+            add_internal_credit_card(&tx, &cc)?;
+            tx.commit()?;
+        }
+        assert!(engine.has_pending_changes().unwrap());
+        {
+            let conn = &engine.store.db.lock().unwrap().writer;
+            test_insert_mirror_record(
+                conn,
+                cc.clone()
+                    .into_test_incoming_bso(&encdec, Default::default()),
+            );
+        }
+        assert!(!engine.has_pending_changes().unwrap());
+        {
+            let conn = &engine.store.db.lock().unwrap().writer;
+            insert_tombstone_record(conn, Guid::random().to_string())?;
+        }
+        assert!(engine.has_pending_changes().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_notifies_changes() -> Result<()> {
+        use crate::observer::test_utils::RecordingObserver;
+        use crate::observer::{AutofillChange, AutofillChangeType};
+
+        let mut engine = create_engine();
+        let recorder = RecordingObserver::default();
+        engine.store.set_change_observer(Box::new(recorder.clone()));
+        let test_key = crate::encryption::create_autofill_key().unwrap();
+        engine.set_local_encryption_key(&test_key).unwrap();
+        let encdec = EncryptorDecryptor::new(&test_key)?;
+
+        let card = |guid: &str| InternalCreditCard {
+            guid: Guid::new(guid),
+            cc_name: "Ms Jane Doe".to_string(),
+            cc_number_enc: encdec.encrypt("12341232412341234", "cc_number").unwrap(),
+            cc_number_last_4: "1234".to_string(),
+            cc_exp_month: 12,
+            cc_exp_year: 2021,
+            cc_type: "visa".to_string(),
+            ..Default::default()
+        };
+        {
+            let db = &engine.store.db.lock().unwrap();
+            create_empty_sync_temp_tables(db)?;
+            let tx = db.writer.unchecked_transaction()?;
+            add_internal_credit_card(&tx, &card("deleted-remote"))?;
+            tx.commit()?;
+            test_insert_mirror_record(
+                &db.writer,
+                card("deleted-remote").into_test_incoming_bso(&encdec, Default::default()),
+            );
+        }
+
+        let mut telem = telemetry::Engine::new("whatever");
+        engine
+            .stage_incoming(
+                vec![
+                    card("added-remote").into_test_incoming_bso(&encdec, Default::default()),
+                    IncomingBso::new_test_tombstone(Guid::new("deleted-remote")),
+                    // We never had this one, so it isn't a change.
+                    IncomingBso::new_test_tombstone(Guid::new("never-existed")),
+                ],
+                &mut telem,
+            )
+            .unwrap();
+        engine
+            .apply(ServerTimestamp::from_millis(0), &mut telem)
+            .unwrap();
+
+        // All the changes from a sync are delivered together.
+        let mut batches = recorder.take();
+        assert_eq!(batches.len(), 1);
+        let mut changes = batches.remove(0);
+        changes.sort_by(|a, b| a.guid.cmp(&b.guid));
+        assert_eq!(
+            changes,
+            vec![
+                AutofillChange::new(
+                    &Guid::new("added-remote"),
+                    AutofillRecordType::CreditCard,
+                    AutofillChangeType::Added
+                ),
+                AutofillChange::new(
+                    &Guid::new("deleted-remote"),
+                    AutofillRecordType::CreditCard,
+                    AutofillChangeType::Deleted
+                ),
+            ]
+        );
+
+        // A sync which doesn't change anything doesn't notify.
+        create_empty_sync_temp_tables(&engine.store.db.lock().unwrap())?;
+        engine
+            .apply(ServerTimestamp::from_millis(0), &mut telem)
+            .unwrap();
+        assert!(recorder.take().is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_engine_sync_reset() -> Result<()> {
         let engine = create_engine();
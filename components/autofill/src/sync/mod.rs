@@ -10,6 +10,7 @@ pub mod engine;
 
 pub(crate) use crate::db::models::Metadata;
 use crate::error::Result;
+use crate::observer::{AutofillChange, AutofillChangeType, AutofillRecordType};
 use interrupt_support::Interruptee;
 use rusqlite::Transaction;
 use sync15::bso::{IncomingBso, IncomingContent, IncomingEnvelope, IncomingKind, OutgoingBso};
@@ -231,6 +232,38 @@ enum IncomingAction<T> {
     DoNothing,
 }
 
+impl<T: SyncRecord> IncomingAction<T> {
+    // The changes the user will see once this action is applied.
+    fn changes(&self, record_type: AutofillRecordType) -> Vec<AutofillChange> {
+        let change = |guid: &Guid, change_type| AutofillChange::new(guid, record_type, change_type);
+        match self {
+            IncomingAction::DeleteLocalRecord { guid } => {
+                vec![change(guid, AutofillChangeType::Deleted)]
+            }
+            IncomingAction::Insert { record }
+            | IncomingAction::ResurrectLocalTombstone { record } => {
+                vec![change(record.id(), AutofillChangeType::Added)]
+            }
+            IncomingAction::Update { record, .. } => {
+                vec![change(record.id(), AutofillChangeType::Updated)]
+            }
+            // Our record moves to the forked guid, and the incoming one replaces it.
+            IncomingAction::Fork { forked, incoming } => vec![
+                change(forked.id(), AutofillChangeType::Added),
+                change(incoming.id(), AutofillChangeType::Updated),
+            ],
+            IncomingAction::UpdateLocalGuid { old_guid, record } => vec![
+                change(old_guid, AutofillChangeType::Deleted),
+                change(record.id(), AutofillChangeType::Added),
+            ],
+            // We keep our record as it is, and upload it.
+            IncomingAction::ResurrectRemoteTombstone { .. } | IncomingAction::DoNothing => {
+                vec![]
+            }
+        }
+    }
+}
+
 /// Convert a IncomingState to an IncomingAction - this is where the "policy"
 /// lives for when we resurrect, or merge etc.
 fn plan_incoming<T: std::fmt::Debug + SyncRecord>(
@@ -12,6 +12,7 @@
 // https://github.com/mozilla/uniffi-rs/issues/1018
 #![allow(clippy::redundant_closure)]
 
+use std::sync::Mutex;
 use thiserror::Error;
 
 #[cfg(test)]
@@ -46,6 +47,31 @@ pub fn trigger_rust_panic() {
     panic!("Panic! In The Rust Code.");
 }
 
+/// Trigger a stack overflow inside the Rust code.
+///
+/// This function recurses without bound until it runs out of stack, so after
+/// calling it you should expect your application to be halted with e.g. a
+/// `SIGSEGV` or `SIGABRT`, reported with a deep stack of identical Rust
+/// frames. It's distinct from [`trigger_rust_abort`], to help check that
+/// crash reports for each kind of crash are captured and symbolicated. This
+/// is for diagnostics only.
+///
+pub fn trigger_rust_stack_overflow() {
+    log::error!("Now triggering a stack overflow inside the Rust code");
+    overflow_the_stack(0);
+}
+
+#[allow(unconditional_recursion)]
+#[inline(never)]
+fn overflow_the_stack(depth: u64) -> u64 {
+    // The volatile read stops the compiler from optimizing the frame (or the
+    // recursion) away.
+    let frame = [depth; 64];
+    // Safety: `frame` is a live, aligned local, so reading it is always valid.
+    let frame = unsafe { std::ptr::read_volatile(&frame) };
+    overflow_the_stack(depth + 1) + frame[0]
+}
+
 /// Trigger an error inside the Rust code.
 ///
 /// This function simulates the occurence of an expected error inside
@@ -57,10 +83,145 @@ pub fn trigger_rust_error() -> Result<(), CrashTestError> {
     Err(CrashTestError::ErrorFromTheRustCode)
 }
 
+/// Trigger a hard abort on a background thread, after a delay.
+///
+/// This function returns immediately. After `delay_ms` milliseconds have
+/// passed, a background thread inside the Rust code aborts, so you should
+/// expect your application to be halted while it's in the middle of doing
+/// something else. This is for testing crash handling only.
+///
+pub fn trigger_delayed_crash(delay_ms: u64) {
+    log::error!(
+        "Now triggering an abort on a background thread in {}ms",
+        delay_ms
+    );
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        log::error!("Now triggering an abort on a background thread");
+        std::process::abort();
+    });
+}
+
+/// Trigger a hard abort on a background thread, as soon as possible.
+///
+/// Like [`trigger_delayed_crash`], but without a delay. This is for testing
+/// crash handling only.
+///
+pub fn trigger_crash_on_thread() {
+    trigger_delayed_crash(0)
+}
+
+/// The kinds of error that [`trigger_rust_error_of_kind`] can return.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashTestErrorKind {
+    Network,
+    Authentication,
+    Storage,
+    InvalidInput,
+    Unexpected,
+}
+
+/// Trigger an error of a particular kind inside the Rust code.
+///
+/// Like [`trigger_rust_error`], but lets you pick which of the common
+/// categories of error the Rust code returns, so that you can check each of
+/// them is translated into the right foreign-language error without
+/// crashing anything.
+///
+pub fn trigger_rust_error_of_kind(kind: CrashTestErrorKind) -> Result<(), CrashTestError> {
+    log::error!("Now triggering a {:?} error inside the Rust code", kind);
+    Err(match kind {
+        CrashTestErrorKind::Network => CrashTestError::NetworkError,
+        CrashTestErrorKind::Authentication => CrashTestError::AuthenticationError,
+        CrashTestErrorKind::Storage => CrashTestError::StorageError,
+        CrashTestErrorKind::InvalidInput => CrashTestError::InvalidInput,
+        CrashTestErrorKind::Unexpected => CrashTestError::UnexpectedError,
+    })
+}
+
+const MEGABYTE: usize = 1024 * 1024;
+
+// Memory held by `allocate_megabytes`, until `release_allocated_memory`.
+static ALLOCATED_MEMORY: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+
+fn allocate_megabyte() -> Result<Vec<u8>, CrashTestError> {
+    let mut chunk = Vec::new();
+    chunk
+        .try_reserve_exact(MEGABYTE)
+        .map_err(|_| CrashTestError::AllocationFailed)?;
+    // Actually touch the memory, otherwise the OS may never back it.
+    chunk.resize(MEGABYTE, 1);
+    Ok(chunk)
+}
+
+/// Allocate (and hold on to) `megabytes` megabytes of memory.
+///
+/// This function simulates memory pressure without crashing: the memory stays
+/// allocated until [`release_allocated_memory`] is called, and if it can't be
+/// allocated you get a [`CrashTestError::AllocationFailed`] instead of a crash.
+/// It's a test-only helper, and returns [`CrashTestError::MemoryPressureDisabled`]
+/// unless the component was built with the `memory-pressure` feature.
+///
+pub fn allocate_megabytes(megabytes: u32) -> Result<(), CrashTestError> {
+    if !cfg!(feature = "memory-pressure") {
+        return Err(CrashTestError::MemoryPressureDisabled);
+    }
+    log::error!("Now allocating {}MB of memory", megabytes);
+    let mut allocated = ALLOCATED_MEMORY.lock().unwrap();
+    for _ in 0..megabytes {
+        allocated.push(allocate_megabyte()?);
+    }
+    Ok(())
+}
+
+/// Keep allocating memory until the allocator fails.
+///
+/// This function simulates running out of memory, so after calling it you
+/// should expect your application to be halted (or killed by the OS). Like
+/// [`allocate_megabytes`], it requires the `memory-pressure` feature.
+///
+pub fn allocate_until_oom() -> Result<(), CrashTestError> {
+    if !cfg!(feature = "memory-pressure") {
+        return Err(CrashTestError::MemoryPressureDisabled);
+    }
+    log::error!("Now allocating memory until we run out");
+    let layout = std::alloc::Layout::array::<u8>(MEGABYTE).unwrap();
+    let mut allocated = Vec::new();
+    loop {
+        match allocate_megabyte() {
+            Ok(chunk) => allocated.push(chunk),
+            // This is what the global allocator does on failure, so the
+            // resulting crash looks like a genuine OOM.
+            Err(_) => std::alloc::handle_alloc_error(layout),
+        }
+    }
+}
+
+/// Release all memory held by [`allocate_megabytes`].
+///
+pub fn release_allocated_memory() {
+    ALLOCATED_MEMORY.lock().unwrap().clear();
+}
+
 /// An error that can be returned from Rust code.
 ///
 #[derive(Debug, Error)]
 pub enum CrashTestError {
     #[error("Error! From The Rust Code.")]
     ErrorFromTheRustCode,
+    #[error("The memory-pressure helpers are not enabled in this build")]
+    MemoryPressureDisabled,
+    #[error("Failed to allocate memory")]
+    AllocationFailed,
+    #[error("Network error! From The Rust Code.")]
+    NetworkError,
+    #[error("Authentication error! From The Rust Code.")]
+    AuthenticationError,
+    #[error("Storage error! From The Rust Code.")]
+    StorageError,
+    #[error("Invalid input! From The Rust Code.")]
+    InvalidInput,
+    #[error("Unexpected error! From The Rust Code.")]
+    UnexpectedError,
 }
@@ -19,4 +19,35 @@ fn test_trigger_error() {
     ));
 }
 
+#[test]
+fn test_trigger_error_of_kind() {
+    use crate::{CrashTestError, CrashTestErrorKind};
+    for (kind, expected) in [
+        (CrashTestErrorKind::Network, "NetworkError"),
+        (CrashTestErrorKind::Authentication, "AuthenticationError"),
+        (CrashTestErrorKind::Storage, "StorageError"),
+        (CrashTestErrorKind::InvalidInput, "InvalidInput"),
+        (CrashTestErrorKind::Unexpected, "UnexpectedError"),
+    ] {
+        let err: CrashTestError = crate::trigger_rust_error_of_kind(kind).unwrap_err();
+        assert_eq!(format!("{:?}", err), expected);
+    }
+}
+
+#[test]
+fn test_allocate_megabytes() {
+    let result = crate::allocate_megabytes(2);
+    if cfg!(feature = "memory-pressure") {
+        assert!(result.is_ok());
+        assert_eq!(crate::ALLOCATED_MEMORY.lock().unwrap().len(), 2);
+        crate::release_allocated_memory();
+        assert!(crate::ALLOCATED_MEMORY.lock().unwrap().is_empty());
+    } else {
+        assert!(matches!(
+            result,
+            Err(crate::CrashTestError::MemoryPressureDisabled)
+        ));
+    }
+}
+
 // We can't test `trigger_rust_abort()` here because it's a hard error.
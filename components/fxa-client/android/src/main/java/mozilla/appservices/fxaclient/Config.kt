@@ -13,6 +13,8 @@ class Config constructor(
     val clientId: String,
     val redirectUri: String,
     val tokenServerUrlOverride: String? = null,
+    val refreshMarginSeconds: Long? = null,
+    val networkTimeoutSeconds: Long? = null,
 ) {
     enum class Server(val rustServer: FxaServer) {
         RELEASE(FxaServer.Release),
@@ -30,21 +32,46 @@ class Config constructor(
         clientId: String,
         redirectUri: String,
         tokenServerUrlOverride: String? = null,
-    ) : this(server.rustServer, clientId, redirectUri, tokenServerUrlOverride)
+        refreshMarginSeconds: Long? = null,
+        networkTimeoutSeconds: Long? = null,
+    ) : this(
+        server.rustServer,
+        clientId,
+        redirectUri,
+        tokenServerUrlOverride,
+        refreshMarginSeconds,
+        networkTimeoutSeconds,
+    )
 
     constructor(
         contentUrl: String,
         clientId: String,
         redirectUri: String,
         tokenServerUrlOverride: String? = null,
-    ) : this(FxaServer.Custom(contentUrl), clientId, redirectUri, tokenServerUrlOverride)
+        refreshMarginSeconds: Long? = null,
+        networkTimeoutSeconds: Long? = null,
+    ) : this(
+        FxaServer.Custom(contentUrl),
+        clientId,
+        redirectUri,
+        tokenServerUrlOverride,
+        refreshMarginSeconds,
+        networkTimeoutSeconds,
+    )
 
     val contentUrl get() = this.server.contentUrl
 
     // Rust defines a config and server class that's virtually identically to these.  We should
     // remove the wrapper soon, but let's wait until we have a batch of breaking changes and do them
     // all at once.
-    fun intoRustConfig() = FxaConfig(server, clientId, redirectUri, tokenServerUrlOverride)
+    fun intoRustConfig() = FxaConfig(
+        server,
+        clientId,
+        redirectUri,
+        tokenServerUrlOverride,
+        refreshMarginSeconds?.toULong(),
+        networkTimeoutSeconds?.toULong(),
+    )
 }
 
 val FxaServer.contentUrl: String
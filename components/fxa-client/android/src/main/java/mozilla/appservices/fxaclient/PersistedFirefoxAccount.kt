@@ -138,6 +138,42 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
         this.tryPersistState()
     }
 
+    /**
+     * Checks that a redirect URL reached after completing the sign in flow is for the
+     * configured redirect URI, and that its state parameter is for a flow started by
+     * [beginOAuthFlow] or [beginPairingFlow]. Use this when the URL comes from a source
+     * which can't be trusted, before calling [completeOAuthFlow]. The flow isn't consumed.
+     *
+     * @throws FxaException.NoExistingAuthFlow if no flow is in progress.
+     * @throws FxaException.WrongAuthFlow if the URL doesn't match the flow.
+     */
+    fun validateOAuthCallback(callbackUrl: String) {
+        this.inner.validateOauthCallback(callbackUrl)
+    }
+
+    /**
+     * Tells the account whether the app thinks the device is offline. While it's set, calls
+     * which need the network fail straight away with [FxaException.Network] instead of
+     * waiting for a timeout. The hint is advisory, and the network is still tried as usual
+     * unless it's set, so clear it when connectivity comes back.
+     *
+     * This does not make network requests, and can be used on the main thread.
+     */
+    fun setOfflineHint(offline: Boolean) {
+        this.inner.setOfflineHint(offline)
+    }
+
+    /**
+     * Sets how many seconds requests to the FxA servers wait for a response before giving up.
+     * `null` restores the default timeouts.
+     *
+     * This overrides [Config.networkTimeoutSeconds]. The timeout isn't persisted, so call this
+     * after restoring an account with [fromJSONString] if you don't want the default.
+     */
+    fun setNetworkTimeoutSeconds(seconds: Long?) {
+        this.inner.setNetworkTimeoutSeconds(seconds?.toULong())
+    }
+
     /**
      * Fetches the profile object for the current client either from the existing cached account,
      * or from the server (requires the client to have access to the profile scope).
@@ -243,10 +279,31 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
         }
     }
 
+    /**
+     * Sets how many seconds before a cached access token expires [getAccessToken] fetches a new
+     * one, rather than returning the cached token. `null` restores the default of 60 seconds.
+     *
+     * This overrides [Config.refreshMarginSeconds]. The margin isn't persisted, so if you set
+     * it in the [Config], you must also call this after every restore with [fromJSONString].
+     */
+    fun setRefreshMarginSeconds(seconds: Long?) {
+        this.inner.setRefreshMarginSeconds(seconds?.toULong())
+    }
+
     fun checkAuthorizationStatus(): AuthorizationInfo {
         return this.inner.checkAuthorizationStatus()
     }
 
+    /**
+     * Get the OAuth scopes granted to this application's session, sorted.
+     *
+     * This is empty if the application isn't connected to an account. For states persisted
+     * by older versions, it only has the scopes we hold keys or cached access tokens for.
+     */
+    fun getGrantedScopes(): List<String> {
+        return this.inner.getGrantedScopes()
+    }
+
     /**
      * Tries to return a session token
      *
@@ -319,6 +376,20 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
         }
     }
 
+    /**
+     * Remove the push subscription for the current device, e.g. after unsubscribing from push.
+     * Device commands can still be fetched with [pollDeviceCommands].
+     *
+     * This performs network requests, and should not be used on the main thread.
+     */
+    fun clearDevicePushSubscription() {
+        try {
+            return this.inner.clearPushSubscription()
+        } finally {
+            this.tryPersistState()
+        }
+    }
+
     /**
      * Update the display name (as shown in the FxA device manager, or the Send Tab target list)
      * for the current device.
@@ -355,6 +426,19 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
         this.tryPersistState()
     }
 
+    /**
+     * Disconnect from the account like [disconnect], returning the user's email and display
+     * name so they can be prefilled when the user next signs in. If [persistHints] is false,
+     * they aren't kept in the persisted account state.
+     *
+     * This performs network requests, and should not be used on the main thread.
+     */
+    fun logoutPreservingHints(persistHints: Boolean = true): LoginHints {
+        val hints = this.inner.logoutPreservingHints(persistHints)
+        this.tryPersistState()
+        return hints
+    }
+
     /**
      * Retrieves any pending commands for the current device.
      * This should be called semi-regularly as the main method of commands delivery (push)
@@ -421,6 +505,10 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
     /**
      * Send a single tab to another device identified by its device ID.
      *
+     * If the server can't be reached, the tab is queued and sent by the next
+     * successful call to [sendSingleTab] or [pollDeviceCommands]. At most 50
+     * tabs are queued; after that, the oldest are dropped.
+     *
      * This performs network requests, and should not be used on the main thread.
      *
      * @param targetDeviceId The target Device ID
@@ -429,6 +517,17 @@ class PersistedFirefoxAccount(inner: FirefoxAccount, persistCallback: PersistCal
      */
     fun sendSingleTab(targetDeviceId: String, title: String, url: String) {
         this.inner.sendSingleTab(targetDeviceId, title, url)
+        this.tryPersistState()
+    }
+
+    /**
+     * Get the tabs which are queued to be sent because the server couldn't be
+     * reached, oldest first.
+     *
+     * This does not make network requests, and can be used on the main thread.
+     */
+    fun getPendingSendTabs(): List<PendingSendTab> {
+        return this.inner.getPendingSendTabs()
     }
 
     /**
@@ -26,13 +26,17 @@ open class FxAConfig {
         contentUrl: String,
         clientId: String,
         redirectUri: String,
-        tokenServerUrlOverride: String? = nil
+        tokenServerUrlOverride: String? = nil,
+        refreshMarginSeconds: UInt64? = nil,
+        networkTimeoutSeconds: UInt64? = nil
     ) {
         rustConfig = FxaConfig(
             server: FxaServer.custom(url: contentUrl),
             clientId: clientId,
             redirectUri: redirectUri,
-            tokenServerUrlOverride: tokenServerUrlOverride
+            tokenServerUrlOverride: tokenServerUrlOverride,
+            refreshMarginSeconds: refreshMarginSeconds,
+            networkTimeoutSeconds: networkTimeoutSeconds
         )
     }
 
@@ -40,7 +44,9 @@ open class FxAConfig {
         server: Server,
         clientId: String,
         redirectUri: String,
-        tokenServerUrlOverride: String? = nil
+        tokenServerUrlOverride: String? = nil,
+        refreshMarginSeconds: UInt64? = nil,
+        networkTimeoutSeconds: UInt64? = nil
     ) {
         let rustServer: FxaServer
         switch server {
@@ -60,7 +66,9 @@ open class FxAConfig {
             server: rustServer,
             clientId: clientId,
             redirectUri: redirectUri,
-            tokenServerUrlOverride: tokenServerUrlOverride
+            tokenServerUrlOverride: tokenServerUrlOverride,
+            refreshMarginSeconds: refreshMarginSeconds,
+            networkTimeoutSeconds: networkTimeoutSeconds
         )
     }
 }
@@ -112,6 +112,17 @@ public class DeviceConstellation {
         }
     }
 
+    /// Remove the local AutoPush subscription from the FxA server.
+    public func clearDevicePushSubscription() {
+        DispatchQueue.global().async {
+            do {
+                try self.account.clearDevicePushSubscription()
+            } catch {
+                FxALog.error("Failure clearing push subscription: \(error).")
+            }
+        }
+    }
+
     /// Once Push has decrypted a payload, send the payload to this method
     /// which will tell the app what to do with it in form of  an `AccountEvent`.
     public func handlePushMessage(pushPayload: String,
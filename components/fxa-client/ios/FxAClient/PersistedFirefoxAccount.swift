@@ -91,6 +91,13 @@ class PersistedFirefoxAccount {
         }
     }
 
+    /// Check that a redirect URL reached after completing the sign in flow is for the
+    /// configured redirect URI, and that its state is for a flow in progress, before
+    /// calling `completeOAuthFlow`. The flow isn't consumed.
+    public func validateOAuthCallback(callbackUrl: String) throws {
+        try inner.validateOauthCallback(callbackUrl: callbackUrl)
+    }
+
     public func checkAuthorizationStatus() throws -> AuthorizationInfo {
         defer { tryPersistState() }
         return try notifyAuthErrors {
@@ -98,11 +105,39 @@ class PersistedFirefoxAccount {
         }
     }
 
+    /// The OAuth scopes granted to this application's session, sorted. This is empty if the
+    /// application isn't connected to an account.
+    public func getGrantedScopes() -> [String] {
+        return inner.getGrantedScopes()
+    }
+
     public func disconnect() {
         defer { tryPersistState() }
         inner.disconnect()
     }
 
+    public func logoutPreservingHints(persistHints: Bool = true) -> LoginHints {
+        defer { tryPersistState() }
+        return inner.logoutPreservingHints(persistHints: persistHints)
+    }
+
+    /// Tell the account whether the app thinks the device is offline. While it's set, calls
+    /// which need the network fail straight away with `FxaError.Network` instead of waiting
+    /// for a timeout. The hint is advisory, and the network is still tried as usual unless
+    /// it's set, so clear it when connectivity comes back.
+    public func setOfflineHint(offline: Bool) {
+        inner.setOfflineHint(offline: offline)
+    }
+
+    /// Set how many seconds requests to the FxA servers wait for a response before giving up.
+    /// `nil` restores the default timeouts.
+    ///
+    /// This overrides `FxAConfig.networkTimeoutSeconds`. The timeout isn't persisted, so call
+    /// this after restoring an account with `fromJSON` if you don't want the default.
+    public func setNetworkTimeoutSeconds(seconds: UInt64?) {
+        inner.setNetworkTimeoutSeconds(seconds: seconds)
+    }
+
     public func getProfile(ignoreCache: Bool) throws -> Profile {
         defer { tryPersistState() }
         return try notifyAuthErrors {
@@ -163,11 +198,19 @@ class PersistedFirefoxAccount {
     }
 
     public func setDevicePushSubscription(sub: DevicePushSubscription) throws {
+        defer { tryPersistState() }
         try notifyAuthErrors {
             try self.inner.setPushSubscription(subscription: sub)
         }
     }
 
+    public func clearDevicePushSubscription() throws {
+        defer { tryPersistState() }
+        try notifyAuthErrors {
+            try self.inner.clearPushSubscription()
+        }
+    }
+
     public func handlePushMessage(payload: String) throws -> AccountEvent {
         defer { tryPersistState() }
         return try notifyAuthErrors {
@@ -183,11 +226,16 @@ class PersistedFirefoxAccount {
     }
 
     public func sendSingleTab(targetDeviceId: String, title: String, url: String) throws {
+        defer { tryPersistState() }
         return try notifyAuthErrors {
             try self.inner.sendSingleTab(targetDeviceId: targetDeviceId, title: title, url: url)
         }
     }
 
+    public func getPendingSendTabs() -> [PendingSendTab] {
+        return inner.getPendingSendTabs()
+    }
+
     public func getTokenServerEndpointURL() throws -> URL {
         return try URL(string: inner.getTokenServerEndpointUrl())!
     }
@@ -211,6 +259,15 @@ class PersistedFirefoxAccount {
         }
     }
 
+    /// Set how many seconds before a cached access token expires `getAccessToken` fetches a new
+    /// one, rather than returning the cached token. `nil` restores the default of 60 seconds.
+    ///
+    /// This overrides `FxaConfig.refreshMarginSeconds`. The margin isn't persisted, so call this
+    /// after restoring an account with `fromJSON` if you don't want the default.
+    public func setRefreshMarginSeconds(seconds: UInt64?) {
+        inner.setRefreshMarginSeconds(seconds: seconds)
+    }
+
     public func getSessionToken() throws -> String {
         defer { tryPersistState() }
         return try notifyAuthErrors {
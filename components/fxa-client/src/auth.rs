@@ -50,6 +50,9 @@ impl FirefoxAccount {
     ///         For example, the application toolbar, on the onboarding flow.
     ///   - `metrics` - optionally, additional metrics tracking parameters.
     ///       - These will be included as query parameters in the resulting URL.
+    ///       - Use these for the `utm_*` parameters that attribute where the signin came
+    ///         from. These, and the `entrypoint`, must only use the characters the server
+    ///         accepts, or an error is returned.
     #[handle_error(Error)]
     pub fn begin_oauth_flow<T: AsRef<str>>(
         &self,
@@ -98,6 +101,9 @@ impl FirefoxAccount {
     ///         For example, the application toolbar, on the onboarding flow.
     ///   - `metrics` - optionally, additional metrics tracking parameters.
     ///       - These will be included as query parameters in the resulting URL.
+    ///       - Use these for the `utm_*` parameters that attribute where the signin came
+    ///         from. These, and the `entrypoint`, must only use the characters the server
+    ///         accepts, or an error is returned.
     #[handle_error(Error)]
     pub fn begin_pairing_flow(
         &self,
@@ -131,6 +137,27 @@ impl FirefoxAccount {
         self.internal.lock().complete_oauth_flow(code, state)
     }
 
+    /// Check a callback URL before completing an OAuth flow.
+    ///
+    /// If the URL redirected to at the end of an OAuth flow comes from a source which
+    /// can't be trusted, call this method with it before extracting the `code` and
+    /// `state` for [`complete_oauth_flow`](FirefoxAccount::complete_oauth_flow). It checks
+    /// that the URL is for the application's registered `redirect_uri`, and that its
+    /// `state` is for a flow which is in progress. The flow is kept, so it can still be
+    /// completed.
+    ///
+    /// Throws [`FxaError::NoExistingAuthFlow`](crate::FxaError::NoExistingAuthFlow) if no
+    /// flow is in progress, and [`FxaError::WrongAuthFlow`](crate::FxaError::WrongAuthFlow)
+    /// if the URL doesn't match the flow.
+    ///
+    /// # Arguments
+    ///
+    ///   - `callback_url` - the URL which was redirected to.
+    #[handle_error(Error)]
+    pub fn validate_oauth_callback(&self, callback_url: &str) -> ApiResult<()> {
+        self.internal.lock().validate_oauth_callback(callback_url)
+    }
+
     /// Check authorization status for this application.
     ///
     /// **💾 This method alters the persisted account state.**
@@ -143,6 +170,21 @@ impl FirefoxAccount {
         Ok(self.internal.lock().check_authorization_status()?.into())
     }
 
+    /// Get the OAuth scopes granted to this application's session.
+    ///
+    /// Applications can use this to show the user what they've allowed the application to
+    /// access, and to decide whether they need to start an OAuth flow to ask for more.
+    /// The list is sorted, and is empty if the application isn't connected to an account.
+    ///
+    /// # Notes
+    ///
+    ///    - For account states persisted by older versions of this component, the scopes
+    ///      might not have been recorded. The returned list then contains just the scopes
+    ///      the application has keys or cached access tokens for.
+    pub fn get_granted_scopes(&self) -> Vec<String> {
+        self.internal.lock().get_granted_scopes()
+    }
+
     /// Disconnect from the user's account.
     ///
     /// **💾 This method alters the persisted account state.**
@@ -158,6 +200,31 @@ impl FirefoxAccount {
     pub fn disconnect(&self) {
         self.internal.lock().disconnect()
     }
+
+    /// Disconnect from the user's account, keeping hints to help them sign in again.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    ///
+    /// This method destroys any tokens and keys held by the client, just like
+    /// [`disconnect`](FirefoxAccount::disconnect), and returns the user's email address and
+    /// display name from their last-seen profile, so the application can prefill them when the
+    /// user next signs in.
+    ///
+    /// If `persist_hints` is true, the persisted account state keeps the last-seen profile,
+    /// as it does after `disconnect`. Otherwise it's discarded too, and the application is
+    /// responsible for storing the returned hints if it wants them later.
+    pub fn logout_preserving_hints(&self, persist_hints: bool) -> LoginHints {
+        self.internal.lock().logout_preserving_hints(persist_hints)
+    }
+}
+
+/// Information to help the user sign in to the same account again after signing out.
+///
+/// Both fields are `None` if the application never fetched the user's profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoginHints {
+    pub email: Option<String>,
+    pub display_name: Option<String>,
 }
 
 /// Information about the authorization state of the application.
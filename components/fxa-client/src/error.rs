@@ -26,7 +26,8 @@ pub enum FxaError {
     /// has been initiated. This may indicate a user who navigated directly to the OAuth
     /// `redirect_uri` for the application.
     ///
-    /// **Note:** This error is currently only thrown in the Swift language bindings.
+    /// **Note:** This error is currently only thrown in the Swift language bindings, and by
+    /// [`validate_oauth_callback`](FirefoxAccount::validate_oauth_callback).
     #[error("no authentication flow was active")]
     NoExistingAuthFlow,
     /// Thrown if the application attempts to complete an OAuth flow, but the state
@@ -35,7 +36,9 @@ pub enum FxaError {
     /// This may indicate a stale OAuth flow, or potentially an attempted hijacking
     /// of the flow by an attacker. The signin attempt cannot be completed.
     ///
-    /// **Note:** This error is currently only thrown in the Swift language bindings.
+    /// **Note:** This error is currently only thrown in the Swift language bindings, and by
+    /// [`validate_oauth_callback`](FirefoxAccount::validate_oauth_callback), which also
+    /// throws it if the callback wasn't for the application's `redirect_uri`.
     #[error("the requested authentication flow was not active")]
     WrongAuthFlow,
     /// Thrown if there is a panic in the underlying Rust code.
@@ -58,6 +61,12 @@ pub enum Error {
     #[error("Unknown OAuth State")]
     UnknownOAuthState,
 
+    #[error("No OAuth flow is in progress")]
+    NoExistingAuthFlow,
+
+    #[error("OAuth callback doesn't match the flow: {0}")]
+    OAuthCallbackMismatch(&'static str),
+
     #[error("Multiple OAuth scopes requested")]
     MultipleScopesRequested,
 
@@ -109,6 +118,9 @@ pub enum Error {
     #[error("Unsupported command: {0}")]
     UnsupportedCommand(&'static str),
 
+    #[error("Invalid metrics parameter: {0}")]
+    InvalidMetricsParam(String),
+
     #[error("Missing URL parameter: {0}")]
     MissingUrlParameter(&'static str),
 
@@ -155,6 +167,9 @@ pub enum Error {
     #[error("Network error: {0}")]
     RequestError(#[from] viaduct::Error),
 
+    #[error("Not trying the network because the application said it's offline")]
+    Offline,
+
     #[error("Malformed URL error: {0}")]
     MalformedUrl(#[from] url::ParseError),
 
@@ -193,6 +208,13 @@ impl GetErrorHandling for Error {
             Error::RequestError(_) => {
                 ErrorHandling::convert(crate::FxaError::Network).log_warning()
             }
+            Error::Offline => ErrorHandling::convert(crate::FxaError::Network).log_info(),
+            Error::NoExistingAuthFlow => {
+                ErrorHandling::convert(crate::FxaError::NoExistingAuthFlow).log_warning()
+            }
+            Error::OAuthCallbackMismatch(_) => {
+                ErrorHandling::convert(crate::FxaError::WrongAuthFlow).log_warning()
+            }
             _ => ErrorHandling::convert(crate::FxaError::Other).log_warning(),
         }
     }
@@ -38,6 +38,32 @@ impl EncryptedSendTabPayload {
     }
 }
 
+/// The most tabs we'll queue while offline. Once there are this many, queueing another
+/// drops the oldest, so the persisted state can't grow without limit.
+pub const MAX_PENDING_SEND_TABS: usize = 50;
+
+/// A tab which couldn't be sent because we were offline. It's persisted with the account
+/// state, and sent the next time we're online.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSendTab {
+    pub target_device_id: String,
+    pub title: String,
+    pub url: String,
+    /// When the tab was queued, in milliseconds since the epoch.
+    pub queued_at: u64,
+}
+
+impl From<PendingSendTab> for crate::PendingSendTab {
+    fn from(pending: PendingSendTab) -> Self {
+        crate::PendingSendTab {
+            target_device_id: pending.target_device_id,
+            title: pending.title,
+            url: pending.url,
+            queued_at: pending.queued_at as i64,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SendTabPayload {
     pub entries: Vec<TabHistoryEntry>,
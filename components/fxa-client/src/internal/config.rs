@@ -5,7 +5,13 @@
 use super::http_client;
 use crate::{FxaConfig, Result};
 use serde_derive::{Deserialize, Serialize};
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use url::Url;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -14,6 +20,18 @@ pub struct Config {
     token_server_url_override: Option<String>,
     pub client_id: String,
     pub redirect_uri: String,
+    // Set by the application rather than persisted, so that restored accounts use the
+    // application's current value.
+    #[serde(skip)]
+    pub refresh_margin_seconds: Option<u64>,
+    // How long to wait for a response before giving up on a request, instead of the viaduct
+    // defaults. Like the refresh margin, this is set by the application rather than persisted.
+    #[serde(skip)]
+    pub network_timeout_seconds: Option<u64>,
+    // Set by the application when it knows the network is down. This isn't persisted, and clones
+    // of the config share it.
+    #[serde(skip)]
+    offline_hint: Arc<AtomicBool>,
     // RemoteConfig is lazily fetched from the server.
     #[serde(skip)]
     remote_config: RefCell<Option<Arc<RemoteConfig>>>,
@@ -43,13 +61,23 @@ pub(crate) const CONTENT_URL_RELEASE: &str = "https://accounts.firefox.com";
 pub(crate) const CONTENT_URL_CHINA: &str = "https://accounts.firefox.com.cn";
 
 impl Config {
+    /// The flag which says the application thinks it's offline. While it's set, requests fail
+    /// without trying the network.
+    pub fn offline_hint(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.offline_hint)
+    }
+
+    pub(crate) fn is_offline_hint_set(&self) -> bool {
+        self.offline_hint.load(Ordering::Relaxed)
+    }
+
     fn remote_config(&self) -> Result<Arc<RemoteConfig>> {
         if let Some(remote_config) = self.remote_config.borrow().clone() {
             return Ok(remote_config);
         }
 
-        let client_config = http_client::fxa_client_configuration(self.client_config_url()?)?;
-        let openid_config = http_client::openid_configuration(self.openid_config_url()?)?;
+        let client_config = http_client::fxa_client_configuration(self)?;
+        let openid_config = http_client::openid_configuration(self)?;
 
         let remote_config = self.set_remote_config(RemoteConfig {
             auth_url: format!("{}/", client_config.auth_server_base_url),
@@ -185,6 +213,9 @@ impl From<FxaConfig> for Config {
             client_id: fxa_config.client_id,
             redirect_uri: fxa_config.redirect_uri,
             token_server_url_override,
+            refresh_margin_seconds: fxa_config.refresh_margin_seconds,
+            network_timeout_seconds: fxa_config.network_timeout_seconds,
+            offline_hint: Default::default(),
             remote_config: RefCell::new(None),
         }
     }
@@ -212,6 +243,9 @@ impl Config {
             redirect_uri: redirect_uri.to_string(),
             remote_config: RefCell::new(None),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
+            offline_hint: Default::default(),
         }
     }
 
@@ -256,6 +290,9 @@ mod tests {
             client_id: "263ceaa5546dce83".to_string(),
             redirect_uri: "https://127.0.0.1:8080".to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
+            offline_hint: Default::default(),
         };
         assert_eq!(
             config.auth_url_path("v1/account/keys").unwrap().to_string(),
@@ -308,6 +345,9 @@ mod tests {
             client_id: "263ceaa5546dce83".to_string(),
             redirect_uri: "https://127.0.0.1:8080".to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
+            offline_hint: Default::default(),
         };
 
         config.override_token_server_url("https://foo.bar");
@@ -340,6 +380,9 @@ mod tests {
             client_id: "263ceaa5546dce83".to_string(),
             redirect_uri: "https://127.0.0.1:8080".to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
+            offline_hint: Default::default(),
         };
 
         config.override_token_server_url("https://foo.bar/prefix/1.0/sync/1.5");
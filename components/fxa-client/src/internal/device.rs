@@ -47,6 +47,15 @@ impl FirefoxAccount {
             .client
             .get_devices(self.state.config(), refresh_token)?;
 
+        // If the server tells us our push subscription has expired, forget that we registered
+        // it, so that the app re-registering it isn't skipped as unchanged.
+        if response
+            .iter()
+            .any(|d| d.is_current_device && d.push_endpoint_expired)
+        {
+            self.state.set_last_sent_push_subscription(None);
+        }
+
         self.devices_cache = Some(CachedResponse {
             response: response.clone(),
             cached_at: util::now(),
@@ -184,7 +193,10 @@ impl FirefoxAccount {
     ) -> Result<Vec<IncomingDeviceCommand>> {
         let last_command_index = self.state.last_handled_command_index().unwrap_or(0);
         // We increment last_command_index by 1 because the server response includes the current index.
-        self.fetch_and_parse_commands(last_command_index + 1, None, reason)
+        let commands = self.fetch_and_parse_commands(last_command_index + 1, None, reason)?;
+        // We're online, so send any tabs which were queued while we weren't.
+        self.flush_pending_send_tabs();
+        Ok(commands)
     }
 
     pub fn get_command_for_index(&mut self, index: u64) -> Result<IncomingDeviceCommand> {
@@ -205,12 +217,17 @@ impl FirefoxAccount {
         reason: CommandFetchReason,
     ) -> Result<Vec<IncomingDeviceCommand>> {
         let refresh_token = self.get_refresh_token()?;
-        let pending_commands =
+        let mut pending_commands =
             self.client
                 .get_pending_commands(self.state.config(), refresh_token, index, limit)?;
         if pending_commands.messages.is_empty() {
             return Ok(Vec::new());
         }
+        // Don't deliver the commands which already arrived by push again.
+        let state = &self.state;
+        pending_commands
+            .messages
+            .retain(|msg| !state.is_command_handled(msg.index));
         log::info!("Handling {} messages", pending_commands.messages.len());
         let device_commands = self.parse_commands_messages(pending_commands.messages, reason)?;
         self.state
@@ -278,11 +295,39 @@ impl FirefoxAccount {
         self.update_device(update)
     }
 
+    /// Register our push subscription with the server. This only makes a request when the
+    /// subscription is different from the one we last registered, so it's cheap to call every
+    /// time the app gets its subscription from the push service.
     pub fn set_push_subscription(&mut self, push_subscription: PushSubscription) -> Result<()> {
+        if self.state.current_device_id().is_some()
+            && self.state.last_sent_push_subscription() == Some(&push_subscription)
+        {
+            log::info!("Push subscription is unchanged, not re-registering");
+            return Ok(());
+        }
         let update = DeviceUpdateRequestBuilder::new()
             .push_subscription(&push_subscription)
             .build();
-        self.update_device(update)
+        self.update_device(update)?;
+        self.state
+            .set_last_sent_push_subscription(Some(push_subscription));
+        Ok(())
+    }
+
+    /// Remove our push subscription from the server, e.g. when the app unsubscribes from
+    /// push. The server clears the subscription when it's given empty values.
+    pub fn clear_push_subscription(&mut self) -> Result<()> {
+        let empty = PushSubscription {
+            endpoint: String::new(),
+            public_key: String::new(),
+            auth_key: String::new(),
+        };
+        let update = DeviceUpdateRequestBuilder::new()
+            .push_subscription(&empty)
+            .build();
+        self.update_device(update)?;
+        self.state.set_last_sent_push_subscription(None);
+        Ok(())
     }
 
     pub(crate) fn replace_device(
@@ -300,7 +345,10 @@ impl FirefoxAccount {
         if let Some(push_subscription) = push_subscription {
             builder = builder.push_subscription(push_subscription)
         }
-        self.update_device(builder.build())
+        self.update_device(builder.build())?;
+        self.state
+            .set_last_sent_push_subscription(push_subscription.clone());
+        Ok(())
     }
 
     fn update_device(&mut self, update: DeviceUpdateRequest<'_>) -> Result<()> {
@@ -317,6 +365,7 @@ impl FirefoxAccount {
                 // We failed to write an update to the server.
                 // Clear local state so that we'll be sure to retry later.
                 self.state.clear_last_sent_device_capabilities();
+                self.state.set_last_sent_push_subscription(None);
                 Err(err)
             }
         }
@@ -369,7 +418,12 @@ impl TryFrom<Device> for crate::Device {
             display_name: d.common.display_name,
             device_type: d.common.device_type,
             capabilities,
-            push_subscription: d.common.push_subscription.map(Into::into),
+            // A cleared subscription has empty values.
+            push_subscription: d
+                .common
+                .push_subscription
+                .filter(|s| !s.endpoint.is_empty())
+                .map(Into::into),
             push_endpoint_expired: d.common.push_endpoint_expired,
             is_current_device: d.is_current_device,
             last_access_time: d.last_access_time.map(TryFrom::try_from).transpose()?,
@@ -715,6 +769,113 @@ mod tests {
         fxa.ensure_capabilities(&[Capability::SendTab]).unwrap();
     }
 
+    #[test]
+    fn test_set_push_subscription_only_reregisters_when_changed() {
+        let mut fxa = setup();
+        let subscription = |endpoint: &str| PushSubscription {
+            endpoint: endpoint.to_string(),
+            public_key: "public-key".to_string(),
+            auth_key: "auth-key".to_string(),
+        };
+        let client_expecting_update = || {
+            let mut client = FxAClientMock::new();
+            client
+                .expect_update_device_record(
+                    mockiato::Argument::any,
+                    |arg| arg.partial_eq("refreshtok"),
+                    mockiato::Argument::any,
+                )
+                .times(1)
+                .returns_once(Ok(UpdateDeviceResponse {
+                    id: "device1".to_string(),
+                    display_name: "".to_string(),
+                    device_type: DeviceType::Mobile,
+                    push_subscription: None,
+                    available_commands: HashMap::default(),
+                    push_endpoint_expired: false,
+                }));
+            Arc::new(client)
+        };
+
+        fxa.set_client(client_expecting_update());
+        fxa.set_push_subscription(subscription("https://push/1"))
+            .unwrap();
+        assert_eq!(fxa.get_current_device_id().unwrap(), "device1");
+
+        // Registering the same subscription again, even after restoring from disk, doesn't
+        // hit the server. The FxAClientMock will panic if it does.
+        fxa.set_client(Arc::new(FxAClientMock::new()));
+        fxa.set_push_subscription(subscription("https://push/1"))
+            .unwrap();
+        let mut fxa = FirefoxAccount::from_json(&fxa.to_json().unwrap()).unwrap();
+        fxa.set_client(Arc::new(FxAClientMock::new()));
+        fxa.set_push_subscription(subscription("https://push/1"))
+            .unwrap();
+
+        // A changed subscription is re-registered.
+        fxa.set_client(client_expecting_update());
+        fxa.set_push_subscription(subscription("https://push/2"))
+            .unwrap();
+
+        // If the server says our subscription has expired, the same one is re-registered.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+            .times(1)
+            .returns_once(Ok(vec![Device {
+                common: DeviceResponseCommon {
+                    id: "device1".into(),
+                    display_name: "".to_string(),
+                    device_type: DeviceType::Mobile,
+                    push_subscription: Some(subscription("https://push/2")),
+                    available_commands: HashMap::new(),
+                    push_endpoint_expired: true,
+                },
+                is_current_device: true,
+                location: DeviceLocation {
+                    city: None,
+                    country: None,
+                    state: None,
+                    state_code: None,
+                },
+                last_access_time: None,
+            }]));
+        fxa.set_client(Arc::new(client));
+        fxa.get_devices(true).unwrap();
+        fxa.set_client(client_expecting_update());
+        fxa.set_push_subscription(subscription("https://push/2"))
+            .unwrap();
+
+        // Clearing always hits the server, and the next registration isn't skipped.
+        fxa.set_client(client_expecting_update());
+        fxa.clear_push_subscription().unwrap();
+        assert!(fxa.state.last_sent_push_subscription().is_none());
+        fxa.set_client(client_expecting_update());
+        fxa.set_push_subscription(subscription("https://push/2"))
+            .unwrap();
+
+        // Commands can be polled for the registered device.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_pending_commands(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Ok(PendingCommandsResponse {
+                index: 0,
+                last: Some(true),
+                messages: vec![],
+            }));
+        fxa.set_client(Arc::new(client));
+        assert!(fxa
+            .poll_device_commands(CommandFetchReason::Poll)
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_get_devices() {
         let mut fxa = setup();
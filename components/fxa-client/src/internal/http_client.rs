@@ -104,6 +104,7 @@ pub(crate) trait FxAClient {
         profile_access_token: &str,
         etag: Option<String>,
     ) -> Result<Option<ResponseAndETag<ProfileResponse>>>;
+    fn get_avatar(&self, config: &Config, url: &str) -> Result<AvatarResponse>;
     fn get_pending_commands(
         &self,
         config: &Config,
@@ -159,10 +160,10 @@ impl FxAClient for Client {
         // Why go through two-levels of indirection? It looks kinda dumb.
         // Well, `config:Config` also needs to fetch the config, but does not have access
         // to an instance of `http_client`, so it calls the helper function directly.
-        fxa_client_configuration(config.client_config_url()?)
+        fxa_client_configuration(config)
     }
     fn get_openid_configuration(&self, config: &Config) -> Result<OpenIdConfigurationResponse> {
-        openid_configuration(config.openid_config_url()?)
+        openid_configuration(config)
     }
 
     fn get_profile(
@@ -177,7 +178,7 @@ impl FxAClient for Client {
         if let Some(etag) = etag {
             request = request.header(header_names::IF_NONE_MATCH, format!("\"{}\"", etag))?;
         }
-        let resp = self.make_request(request)?;
+        let resp = self.make_request(config, request)?;
         if resp.status == status_codes::NOT_MODIFIED {
             return Ok(None);
         }
@@ -191,6 +192,20 @@ impl FxAClient for Client {
         }))
     }
 
+    fn get_avatar(&self, config: &Config, url: &str) -> Result<AvatarResponse> {
+        // Avatars are served from a public CDN, so there's no need to authenticate.
+        let resp = self.make_request(config, Request::get(Url::parse(url)?))?;
+        let mime_type = resp
+            .headers
+            .get(header_names::CONTENT_TYPE)
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        Ok(AvatarResponse {
+            bytes: resp.body,
+            mime_type,
+        })
+    }
+
     fn create_refresh_token_using_authorization_code(
         &self,
         config: &Config,
@@ -223,7 +238,7 @@ impl FxAClient for Client {
         let request = HawkRequestBuilder::new(Method::Post, url, &key)
             .body(body)
             .build()?;
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     // For the regular generation of an `access_token` from long-lived credentials.
@@ -260,7 +275,9 @@ impl FxAClient for Client {
         let request = HawkRequestBuilder::new(Method::Post, url, &key)
             .body(parameters)
             .build()?;
-        self.make_request(request)?.json().map_err(Into::into)
+        self.make_request(config, request)?
+            .json()
+            .map_err(Into::into)
     }
 
     fn create_authorization_code_using_session_token(
@@ -276,7 +293,7 @@ impl FxAClient for Client {
             .body(parameters)
             .build()?;
 
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn check_refresh_token_status(
@@ -289,7 +306,9 @@ impl FxAClient for Client {
             "token": refresh_token,
         });
         let url = config.introspection_endpoint()?;
-        Ok(self.make_request(Request::post(url).json(&body))?.json()?)
+        Ok(self
+            .make_request(config, Request::post(url).json(&body))?
+            .json()?)
     }
 
     fn duplicate_session_token(
@@ -306,7 +325,7 @@ impl FxAClient for Client {
             .body(duplicate_body)
             .build()?;
 
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn destroy_access_token(&self, config: &Config, access_token: &str) -> Result<()> {
@@ -337,7 +356,7 @@ impl FxAClient for Client {
         if let Some(limit) = limit {
             request = request.query(&[("limit", &limit.to_string())])
         }
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn invoke_command(
@@ -358,7 +377,7 @@ impl FxAClient for Client {
             .header(header_names::AUTHORIZATION, bearer_token(refresh_token))?
             .header(header_names::CONTENT_TYPE, "application/json")?
             .body(body.to_string());
-        self.make_request(request)?;
+        self.make_request(config, request)?;
         Ok(())
     }
 
@@ -370,7 +389,7 @@ impl FxAClient for Client {
                 "filterIdleDevicesTimestamp",
                 &util::past_timestamp(DEVICES_FILTER_DAYS).to_string(),
             )]);
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn update_device_record(
@@ -384,7 +403,7 @@ impl FxAClient for Client {
             .header(header_names::AUTHORIZATION, bearer_token(refresh_token))?
             .header(header_names::CONTENT_TYPE, "application/json")?
             .body(serde_json::to_string(&update)?);
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn destroy_device_record(&self, config: &Config, refresh_token: &str, id: &str) -> Result<()> {
@@ -397,7 +416,7 @@ impl FxAClient for Client {
             .header(header_names::CONTENT_TYPE, "application/json")?
             .body(body.to_string());
 
-        self.make_request(request)?;
+        self.make_request(config, request)?;
         Ok(())
     }
 
@@ -409,7 +428,7 @@ impl FxAClient for Client {
         let url = config.auth_url_path("v1/account/attached_clients")?;
         let key = derive_auth_key_from_session_token(session_token)?;
         let request = HawkRequestBuilder::new(Method::Get, url, &key).build()?;
-        Ok(self.make_request(request)?.json()?)
+        Ok(self.make_request(config, request)?.json()?)
     }
 
     fn get_scoped_key_data(
@@ -428,26 +447,39 @@ impl FxAClient for Client {
         let request = HawkRequestBuilder::new(Method::Post, url, &key)
             .body(body)
             .build()?;
-        self.make_request(request)?.json().map_err(|e| e.into())
+        self.make_request(config, request)?
+            .json()
+            .map_err(|e| e.into())
     }
 }
 
 macro_rules! fetch {
-    ($url:expr) => {
-        viaduct::Request::get($url)
-            .send()?
+    ($config:expr, $url:expr) => {
+        send($config, viaduct::Request::get($url))?
             .require_success()?
             .json()?
     };
 }
 
 #[inline]
-pub(crate) fn fxa_client_configuration(url: Url) -> Result<ClientConfigurationResponse> {
-    Ok(fetch!(url))
+pub(crate) fn fxa_client_configuration(config: &Config) -> Result<ClientConfigurationResponse> {
+    Ok(fetch!(config, config.client_config_url()?))
 }
 #[inline]
-pub(crate) fn openid_configuration(url: Url) -> Result<OpenIdConfigurationResponse> {
-    Ok(fetch!(url))
+pub(crate) fn openid_configuration(config: &Config) -> Result<OpenIdConfigurationResponse> {
+    Ok(fetch!(config, config.openid_config_url()?))
+}
+
+// Every request to the FxA servers goes through here, so that it uses the timeout from the
+// config, and fails straight away while the application says it's offline.
+fn send(config: &Config, mut request: Request) -> Result<Response> {
+    if config.is_offline_hint_set() {
+        return Err(Error::Offline);
+    }
+    if let Some(timeout) = config.network_timeout_seconds {
+        request = request.timeout(Duration::from_secs(timeout));
+    }
+    Ok(request.send()?)
 }
 
 impl Client {
@@ -459,7 +491,7 @@ impl Client {
 
     fn destroy_token_helper(&self, config: &Config, body: &serde_json::Value) -> Result<()> {
         let url = config.oauth_url_path("v1/destroy")?;
-        self.make_request(Request::post(url).json(body))?;
+        self.make_request(config, Request::post(url).json(body))?;
         Ok(())
     }
 
@@ -469,7 +501,9 @@ impl Client {
         body: serde_json::Value,
     ) -> Result<OAuthTokenResponse> {
         let url = config.token_endpoint()?;
-        Ok(self.make_request(Request::post(url).json(&body))?.json()?)
+        Ok(self
+            .make_request(config, Request::post(url).json(&body))?
+            .json()?)
     }
 
     fn handle_too_many_requests(&self, resp: Response) -> Result<Response> {
@@ -500,7 +534,7 @@ impl Client {
         }
     }
 
-    fn make_request(&self, request: Request) -> Result<Response> {
+    fn make_request(&self, config: &Config, request: Request) -> Result<Response> {
         let url = request.url.path().to_string();
         if let HttpClientState::Backoff {
             backoff_end_duration,
@@ -514,7 +548,7 @@ impl Client {
             }
         }
         self.state.lock().insert(url, HttpClientState::Ok);
-        let resp = request.send()?;
+        let resp = send(config, request)?;
         if resp.is_success() || resp.status == status_codes::NOT_MODIFIED {
             Ok(resp)
         } else {
@@ -659,7 +693,7 @@ pub struct CommandData {
     pub sender: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct PushSubscription {
     #[serde(rename = "pushCallback")]
     pub endpoint: String,
@@ -898,6 +932,21 @@ impl From<ProfileResponse> for crate::Profile {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct AvatarResponse {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+impl From<AvatarResponse> for crate::AvatarImage {
+    fn from(a: AvatarResponse) -> Self {
+        crate::AvatarImage {
+            bytes: a.bytes,
+            mime_type: a.mime_type,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ScopedKeyDataResponse {
     pub identifier: String,
@@ -960,6 +1009,7 @@ mod tests {
             )
             .create();
         let client = Client::new();
+        let config = Config::stable_dev("12345678", "https://foo.bar");
         let path = format!(
             "{}/{}",
             mockito::server_url(),
@@ -968,7 +1018,7 @@ mod tests {
         let url = Url::parse(&path).unwrap();
         let path = url.path().to_string();
         let request = Request::post(url);
-        assert!(client.make_request(request.clone()).is_err());
+        assert!(client.make_request(&config, request.clone()).is_err());
         let state = client.state.lock();
         if let HttpClientState::Backoff {
             backoff_end_duration,
@@ -979,7 +1029,7 @@ mod tests {
             // Hacky way to drop the mutex gaurd, so that the next call to
             // client.make_request doesn't hang or panic
             std::mem::drop(state);
-            assert!(client.make_request(request).is_err());
+            assert!(client.make_request(&config, request).is_err());
             // We should be backed off, the second "make_request" should not
             // send a request to the server
             m.expect(1).assert();
@@ -1007,6 +1057,7 @@ mod tests {
             )
             .create();
         let client = Client::new();
+        let config = Config::stable_dev("12345678", "https://foo.bar");
         let path = format!(
             "{}/{}",
             mockito::server_url(),
@@ -1015,7 +1066,7 @@ mod tests {
         let url = Url::parse(&path).unwrap();
         let path = url.path().to_string();
         let request = Request::post(url);
-        assert!(client.make_request(request.clone()).is_err());
+        assert!(client.make_request(&config, request.clone()).is_err());
         let state = client.state.lock();
         if let HttpClientState::Backoff {
             backoff_end_duration,
@@ -1029,7 +1080,7 @@ mod tests {
             // Hacky way to drop the mutex gaurd, so that the next call to
             // client.make_request doesn't hang or panic
             std::mem::drop(state);
-            assert!(client.make_request(request).is_err());
+            assert!(client.make_request(&config, request).is_err());
             // We backed off, but the time has passed, the second request should have
             // went to the server
             m.expect(2).assert();
@@ -1069,6 +1120,7 @@ mod tests {
             )
             .create();
         let client = Client::new();
+        let config = Config::stable_dev("12345678", "https://foo.bar");
         let path = format!(
             "{}/{}",
             mockito::server_url(),
@@ -1077,7 +1129,7 @@ mod tests {
         let url = Url::parse(&path).unwrap();
         let path = url.path().to_string();
         let request = Request::post(url);
-        assert!(client.make_request(request).is_err());
+        assert!(client.make_request(&config, request).is_err());
         let state = client.state.lock();
         if let HttpClientState::Backoff {
             backoff_end_duration,
@@ -1091,7 +1143,7 @@ mod tests {
             // client.make_request doesn't hang or panic
             std::mem::drop(state);
             let second_request = Request::get(Url::parse(&path2).unwrap());
-            assert!(client.make_request(second_request).is_ok());
+            assert!(client.make_request(&config, second_request).is_ok());
             // The first endpoint is backed off, but the second one is not
             // Both endpoint should be hit
             m1.expect(1).assert();
@@ -11,11 +11,11 @@ use self::{
     state_persistence::PersistedState,
     telemetry::FxaTelemetry,
 };
-use crate::{Error, FxaConfig, Result};
+use crate::{Error, FxaConfig, LoginHints, Result};
 use serde_derive::*;
 use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{atomic::AtomicBool, Arc},
 };
 use url::Url;
 
@@ -52,6 +52,7 @@ pub struct FirefoxAccount {
     state: StateManager,
     attached_clients_cache: Option<CachedResponse<Vec<http_client::GetAttachedClientResponse>>>,
     devices_cache: Option<CachedResponse<Vec<http_client::GetDeviceResponse>>>,
+    avatar_cache: Option<profile::CachedAvatar>,
     auth_circuit_breaker: AuthCircuitBreaker,
     telemetry: FxaTelemetry,
 }
@@ -63,6 +64,7 @@ impl FirefoxAccount {
             state: StateManager::new(state),
             attached_clients_cache: None,
             devices_cache: None,
+            avatar_cache: None,
             auth_circuit_breaker: Default::default(),
             telemetry: FxaTelemetry::new(),
         }
@@ -81,6 +83,9 @@ impl FirefoxAccount {
             current_device_id: None,
             last_seen_profile: None,
             access_token_cache: HashMap::new(),
+            pending_send_tabs: Vec::new(),
+            push_subscription: None,
+            handled_push_commands: BTreeSet::new(),
         })
     }
 
@@ -89,6 +94,18 @@ impl FirefoxAccount {
         Self::with_config(config.into())
     }
 
+    /// The flag which tells requests to fail straight away because the application thinks it's
+    /// offline. See `crate::FirefoxAccount::set_offline_hint`.
+    pub fn offline_hint(&self) -> Arc<AtomicBool> {
+        self.state.config().offline_hint()
+    }
+
+    /// Set how long requests to the FxA servers wait for a response. See
+    /// `crate::FirefoxAccount::set_network_timeout_seconds`.
+    pub fn set_network_timeout_seconds(&mut self, seconds: Option<u64>) {
+        self.state.set_network_timeout_seconds(seconds);
+    }
+
     #[cfg(test)]
     pub(crate) fn set_client(&mut self, client: Arc<FxAClient>) {
         self.client = client;
@@ -219,6 +236,36 @@ impl FirefoxAccount {
                 log::warn!("Error while destroying the device: {}", e);
             }
         }
+        self.forget_account();
+    }
+
+    /// Disconnect from the account like [`disconnect`](FirefoxAccount::disconnect), returning
+    /// the email and display name from the last-seen profile so the app can prefill them when
+    /// the user next signs in. If `persist_hints` is false, the last-seen profile is discarded
+    /// too, so nothing about the user is left in the persisted state.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    pub fn logout_preserving_hints(&mut self, persist_hints: bool) -> LoginHints {
+        let hints = self
+            .state
+            .last_seen_profile()
+            .map(|profile| LoginHints {
+                email: Some(profile.response.email.clone()),
+                display_name: profile.response.display_name.clone(),
+            })
+            .unwrap_or_default();
+        self.disconnect();
+        if !persist_hints {
+            self.state.clear_last_seen_profile();
+        }
+        hints
+    }
+
+    /// Discard the local account state, without telling the server. This is what we do when
+    /// the account no longer exists, so there's nothing left to destroy on the server.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    pub(crate) fn forget_account(&mut self) {
         self.state.disconnect();
         self.clear_devices_and_attached_clients_cache();
         self.telemetry = FxaTelemetry::new();
@@ -256,6 +303,30 @@ mod tests {
         assert_eq!(fxa1_json, fxa2_json);
     }
 
+    #[test]
+    fn test_network_timeout_not_persisted() {
+        let mut config = Config::stable_dev("12345678", "https://foo.bar");
+        config.network_timeout_seconds = Some(10);
+        let fxa = FirefoxAccount::with_config(config);
+        let mut restored = FirefoxAccount::from_json(&fxa.to_json().unwrap()).unwrap();
+        assert_eq!(restored.state.config().network_timeout_seconds, None);
+        restored.set_network_timeout_seconds(Some(10));
+        assert_eq!(restored.state.config().network_timeout_seconds, Some(10));
+    }
+
+    #[test]
+    fn test_offline_hint() {
+        use std::sync::atomic::Ordering;
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let fxa = FirefoxAccount::with_config(config);
+        fxa.offline_hint().store(true, Ordering::Relaxed);
+        // This would fetch the remote config, but doesn't try while we're offline.
+        assert!(matches!(
+            fxa.get_token_server_endpoint_url(),
+            Err(Error::Offline)
+        ));
+    }
+
     #[test]
     fn test_get_connection_success_url() {
         let config = Config::new("https://stable.dev.lcip.org", "12345678", "https://foo.bar");
@@ -346,6 +417,49 @@ mod tests {
         assert!(fxa.state.is_access_token_cache_empty());
     }
 
+    #[test]
+    fn test_logout_preserving_hints() {
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let mut fxa = FirefoxAccount::with_config(config);
+        fxa.add_cached_token(
+            "profile",
+            AccessTokenInfo {
+                scope: "profile".to_string(),
+                token: "profiletok".to_string(),
+                key: None,
+                expires_at: u64::max_value(),
+            },
+        );
+        fxa.state.set_last_seen_profile(CachedResponse {
+            response: profile::Profile {
+                uid: "12345ab".into(),
+                email: "foo@bar.com".into(),
+                display_name: Some("Foo".into()),
+                avatar: "https://foo.avatar".into(),
+                avatar_default: false,
+            },
+            cached_at: util::now(),
+            etag: "fake etag".into(),
+        });
+        fxa.set_client(Arc::new(FxAClientMock::new()));
+
+        let expected = LoginHints {
+            email: Some("foo@bar.com".into()),
+            display_name: Some("Foo".into()),
+        };
+        assert_eq!(fxa.logout_preserving_hints(true), expected);
+        assert!(fxa.state.is_access_token_cache_empty());
+        assert!(fxa.state.last_seen_profile().is_some());
+        assert!(!fxa.to_json().unwrap().contains("profiletok"));
+
+        // The hints are still returned when they aren't persisted.
+        assert_eq!(fxa.logout_preserving_hints(false), expected);
+        assert!(fxa.state.last_seen_profile().is_none());
+        assert!(!fxa.to_json().unwrap().contains("foo@bar.com"));
+
+        assert_eq!(fxa.logout_preserving_hints(false), LoginHints::default());
+    }
+
     #[test]
     fn test_disconnect_device() {
         let config = Config::stable_dev("12345678", "https://foo.bar");
@@ -17,19 +17,26 @@ use rate_limiter::RateLimiter;
 use rc_crypto::digest;
 use serde_derive::*;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     iter::FromIterator,
     time::{SystemTime, UNIX_EPOCH},
 };
 use url::Url;
 // If a cached token has less than `OAUTH_MIN_TIME_LEFT` seconds left to live,
-// it will be considered already expired.
+// it will be considered already expired. Applications can change this with
+// `FxaConfig::refresh_margin_seconds` or `set_refresh_margin_seconds`.
 const OAUTH_MIN_TIME_LEFT: u64 = 60;
 // Special redirect urn based on the OAuth native spec, signals that the
 // WebChannel flow is used
 pub const OAUTH_WEBCHANNEL_REDIRECT: &str = "urn:ietf:wg:oauth:2.0:oob:oauth-redirect-webchannel";
 
 impl FirefoxAccount {
+    /// Set how many seconds before a cached access token expires `get_access_token` fetches a
+    /// new one. `None` restores the default.
+    pub fn set_refresh_margin_seconds(&mut self, seconds: Option<u64>) {
+        self.state.set_refresh_margin_seconds(seconds);
+    }
+
     /// Fetch a short-lived access token using the saved refresh token.
     /// If there is no refresh token held or if it is not authorized for some of the requested
     /// scopes, this method will error-out and a login flow will need to be initiated
@@ -43,8 +50,13 @@ impl FirefoxAccount {
         if scope.contains(' ') {
             return Err(Error::MultipleScopesRequested);
         }
+        let min_time_left = self
+            .state
+            .config()
+            .refresh_margin_seconds
+            .unwrap_or(OAUTH_MIN_TIME_LEFT);
         if let Some(oauth_info) = self.state.get_cached_access_token(scope) {
-            if oauth_info.expires_at > util::now_secs() + OAUTH_MIN_TIME_LEFT {
+            if oauth_info.expires_at > util::now_secs().saturating_add(min_time_left) {
                 return Ok(oauth_info.clone());
             }
         }
@@ -108,6 +120,24 @@ impl FirefoxAccount {
         })
     }
 
+    /// The OAuth scopes granted to the current session, sorted. These are the scopes of the
+    /// refresh token, along with any we hold a scoped key or access token for, since states
+    /// persisted before the refresh token's scopes were recorded may only know of those.
+    /// Returns nothing if there's no refresh token.
+    pub fn get_granted_scopes(&self) -> Vec<String> {
+        let refresh_token = match self.state.refresh_token() {
+            Some(refresh_token) => refresh_token,
+            None => return Vec::new(),
+        };
+        let scopes: BTreeSet<&str> = refresh_token
+            .scopes
+            .iter()
+            .map(String::as_str)
+            .chain(self.state.scopes_with_keys_or_tokens())
+            .collect();
+        scopes.into_iter().map(ToString::to_string).collect()
+    }
+
     /// Initiate a pairing flow and return a URL that should be navigated to.
     ///
     /// * `pairing_url` - A pairing URL obtained by scanning a QR code produced by
@@ -122,6 +152,10 @@ impl FirefoxAccount {
         entrypoint: &str,
         metrics: Option<MetricsParams>,
     ) -> Result<String> {
+        validate_entrypoint(entrypoint)?;
+        if let Some(metrics) = &metrics {
+            metrics.validate()?;
+        }
         let mut url = self.state.config().pair_supp_url()?;
         url.query_pairs_mut().append_pair("entrypoint", entrypoint);
         if let Some(metrics) = metrics {
@@ -139,13 +173,20 @@ impl FirefoxAccount {
     ///
     /// * `scopes` - Space-separated list of requested scopes.
     /// * `entrypoint` - The entrypoint to be used for metrics
-    /// * `metrics` - Optional metrics parameters
+    /// * `metrics` - Optional metrics parameters, such as the `utm_*` parameters
+    ///
+    /// Fails with `Error::InvalidMetricsParam` if the entrypoint or any of the `utm_*`
+    /// parameters would be rejected by the server.
     pub fn begin_oauth_flow(
         &mut self,
         scopes: &[&str],
         entrypoint: &str,
         metrics: Option<MetricsParams>,
     ) -> Result<String> {
+        validate_entrypoint(entrypoint)?;
+        if let Some(metrics) = &metrics {
+            metrics.validate()?;
+        }
         let mut url = if self.state.last_seen_profile().is_some() {
             self.state.config().oauth_force_auth_url()?
         } else {
@@ -318,6 +359,39 @@ impl FirefoxAccount {
         self.handle_oauth_response(resp, oauth_flow.scoped_keys_flow)
     }
 
+    /// Check that a URL which was redirected to at the end of an OAuth flow is for our
+    /// `redirect_uri`, and that its `state` is for a flow initiated in `begin_oauth_flow` or
+    /// `begin_pairing_flow`. The flow is kept, so `complete_oauth_flow` can be called after.
+    pub fn validate_oauth_callback(&self, callback_url: &str) -> Result<()> {
+        if !self.state.has_oauth_flows() {
+            return Err(Error::NoExistingAuthFlow);
+        }
+        let callback_url = Url::parse(callback_url)?;
+        let redirect_url = Url::parse(&self.state.config().redirect_uri)?;
+        // Apps often use their own schemes, which have opaque origins that never compare equal,
+        // so we compare the parts of the origin ourselves.
+        fn redirect_target(url: &Url) -> (&str, Option<&str>, Option<u16>, &str) {
+            (
+                url.scheme(),
+                url.host_str(),
+                url.port_or_known_default(),
+                url.path(),
+            )
+        }
+        if redirect_target(&callback_url) != redirect_target(&redirect_url) {
+            return Err(Error::OAuthCallbackMismatch("redirect_uri"));
+        }
+        let params: HashMap<_, _> = callback_url.query_pairs().collect();
+        match params.get("state") {
+            Some(state) if self.state.has_oauth_flow(state) => (),
+            _ => return Err(Error::OAuthCallbackMismatch("state")),
+        }
+        if !params.contains_key("code") {
+            return Err(Error::MissingUrlParameter("code"));
+        }
+        Ok(())
+    }
+
     pub(crate) fn handle_oauth_response(
         &mut self,
         resp: OAuthTokenResponse,
@@ -511,14 +585,59 @@ impl TryFrom<Url> for AuthorizationParameters {
     }
 }
 
+// The UTM parameters which the FxA server accepts.
+const UTM_PARAMS: &[&str] = &[
+    "utm_campaign",
+    "utm_content",
+    "utm_medium",
+    "utm_source",
+    "utm_term",
+];
+
+// The FxA server rejects metrics parameters longer than this.
+const MAX_METRICS_PARAM_LEN: usize = 128;
+
+fn validate_entrypoint(entrypoint: &str) -> Result<()> {
+    if entrypoint.is_empty()
+        || entrypoint.len() > MAX_METRICS_PARAM_LEN
+        || !entrypoint
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+    {
+        return Err(Error::InvalidMetricsParam("entrypoint".to_string()));
+    }
+    Ok(())
+}
+
 impl MetricsParams {
+    /// Check the parameters are ones the server will accept. Only the `utm_*` parameters
+    /// are checked, since the server ignores other unknown parameters.
+    fn validate(&self) -> Result<()> {
+        for (name, value) in &self.parameters {
+            if !name.starts_with("utm_") {
+                continue;
+            }
+            if !UTM_PARAMS.contains(&name.as_str())
+                || value.is_empty()
+                || value.len() > MAX_METRICS_PARAM_LEN
+                || !value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '%'))
+            {
+                return Err(Error::InvalidMetricsParam(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
     fn append_params_to_url(&self, url: &mut Url) {
-        self.parameters
-            .iter()
-            .for_each(|(parameter_name, parameter_value)| {
-                url.query_pairs_mut()
-                    .append_pair(parameter_name, parameter_value);
-            });
+        // Sort the parameters so the URL doesn't depend on the order of the map.
+        let mut parameters: Vec<_> = self.parameters.iter().collect();
+        parameters.sort();
+        let mut query_pairs = url.query_pairs_mut();
+        for (parameter_name, parameter_value) in parameters {
+            query_pairs.append_pair(parameter_name, parameter_value);
+        }
     }
 }
 
@@ -741,6 +860,63 @@ mod tests {
         assert_eq!(query_params.get("redirect_uri"), None);
     }
 
+    #[test]
+    fn test_flow_url_invalid_metrics_params() {
+        const SCOPES: &[&str] = &["https://identity.mozilla.com/apps/oldsync"];
+        const PAIRING_URL: &str = "https://accounts.firefox.com/pair#channel_id=658db7fe98b249a5897b884f98fb31b7&channel_key=1hIDzTj5oY2HDeSg_jA2DhcOcAn5Uqq0cAYlZRNUIo4";
+
+        let config = Config::new(
+            "https://accounts.firefox.com",
+            "12345678",
+            "https://foo.bar",
+        );
+        let mut fxa = FirefoxAccount::with_config(config);
+
+        for entrypoint in ["", "has spaces", "semi;colon", &"x".repeat(129)] {
+            assert!(matches!(
+                fxa.begin_oauth_flow(SCOPES, entrypoint, None),
+                Err(Error::InvalidMetricsParam(_))
+            ));
+        }
+
+        for (name, value) in [
+            ("utm_unknown", "value"),
+            ("utm_source", ""),
+            ("utm_source", "<script>"),
+        ] {
+            let metrics = MetricsParams {
+                parameters: HashMap::from([(name.to_string(), value.to_string())]),
+            };
+            assert!(matches!(
+                fxa.begin_pairing_flow(PAIRING_URL, SCOPES, "test", Some(metrics)),
+                Err(Error::InvalidMetricsParam(_))
+            ));
+        }
+
+        // Valid UTM parameters, and unknown non-UTM parameters, are added to the URL.
+        let metrics = MetricsParams {
+            parameters: HashMap::from([
+                ("utm_source".to_string(), "app-menu".to_string()),
+                ("utm_campaign".to_string(), "spring/2023".to_string()),
+                ("other".to_string(), "a b&c".to_string()),
+            ]),
+        };
+        let url = fxa
+            .begin_pairing_flow(PAIRING_URL, SCOPES, "app.menu:sync", Some(metrics))
+            .unwrap();
+        let flow_url = Url::parse(&url).unwrap();
+        let pairs: Vec<_> = flow_url.query_pairs().take(4).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (Cow::Borrowed("entrypoint"), Cow::Borrowed("app.menu:sync")),
+                (Cow::Borrowed("other"), Cow::Borrowed("a b&c")),
+                (Cow::Borrowed("utm_campaign"), Cow::Borrowed("spring/2023")),
+                (Cow::Borrowed("utm_source"), Cow::Borrowed("app-menu")),
+            ]
+        );
+    }
+
     #[test]
     fn test_pairing_flow_url() {
         const SCOPES: &[&str] = &["https://identity.mozilla.com/apps/oldsync"];
@@ -852,6 +1028,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_oauth_callback() {
+        let config = Config::stable_dev("12345678", "https://foo.bar/oauth/success");
+        let mut fxa = FirefoxAccount::with_config(config);
+        assert!(matches!(
+            fxa.validate_oauth_callback("https://foo.bar/oauth/success?code=abc&state=xyz"),
+            Err(Error::NoExistingAuthFlow)
+        ));
+
+        fxa.state.begin_oauth_flow(
+            "xyz",
+            OAuthFlow {
+                scoped_keys_flow: None,
+                code_verifier: "verifier".to_string(),
+            },
+        );
+        fxa.validate_oauth_callback("https://foo.bar/oauth/success?code=abc&state=xyz")
+            .unwrap();
+        // The default port is the same origin.
+        fxa.validate_oauth_callback("https://foo.bar:443/oauth/success?state=xyz&code=abc")
+            .unwrap();
+        // Validating doesn't consume the flow.
+        assert!(fxa.state.has_oauth_flow("xyz"));
+
+        for url in [
+            "https://evil.com/oauth/success?code=abc&state=xyz",
+            "http://foo.bar/oauth/success?code=abc&state=xyz",
+            "https://foo.bar:8443/oauth/success?code=abc&state=xyz",
+            "https://foo.bar/other?code=abc&state=xyz",
+        ] {
+            assert!(matches!(
+                fxa.validate_oauth_callback(url),
+                Err(Error::OAuthCallbackMismatch("redirect_uri"))
+            ));
+        }
+        for url in [
+            "https://foo.bar/oauth/success?code=abc&state=other",
+            "https://foo.bar/oauth/success?code=abc",
+        ] {
+            assert!(matches!(
+                fxa.validate_oauth_callback(url),
+                Err(Error::OAuthCallbackMismatch("state"))
+            ));
+        }
+        assert!(matches!(
+            fxa.validate_oauth_callback("https://foo.bar/oauth/success?state=xyz"),
+            Err(Error::MissingUrlParameter("code"))
+        ));
+        assert!(matches!(
+            fxa.validate_oauth_callback("not a url"),
+            Err(Error::MalformedUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_oauth_callback_custom_scheme() {
+        let config = Config::stable_dev("12345678", "myapp://oauth");
+        let mut fxa = FirefoxAccount::with_config(config);
+        fxa.state.begin_oauth_flow(
+            "xyz",
+            OAuthFlow {
+                scoped_keys_flow: None,
+                code_verifier: "verifier".to_string(),
+            },
+        );
+        fxa.validate_oauth_callback("myapp://oauth?code=abc&state=xyz")
+            .unwrap();
+        assert!(matches!(
+            fxa.validate_oauth_callback("otherapp://oauth?code=abc&state=xyz"),
+            Err(Error::OAuthCallbackMismatch("redirect_uri"))
+        ));
+    }
+
+    #[test]
+    fn test_refresh_margin_not_persisted() {
+        let mut config = Config::stable_dev("12345678", "https://foo.bar");
+        config.refresh_margin_seconds = Some(600);
+        let fxa = FirefoxAccount::with_config(config);
+        let mut restored = FirefoxAccount::from_json(&fxa.to_json().unwrap()).unwrap();
+        assert_eq!(restored.state.config().refresh_margin_seconds, None);
+        restored.set_refresh_margin_seconds(Some(600));
+        assert_eq!(restored.state.config().refresh_margin_seconds, Some(600));
+    }
+
+    #[test]
+    fn test_get_access_token_refresh_margin() {
+        let mut fxa =
+            FirefoxAccount::with_config(Config::stable_dev("12345678", "https://foo.bar"));
+        fxa.set_refresh_margin_seconds(Some(600));
+
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refresh_token".to_owned(),
+            scopes: HashSet::from_iter(vec!["profile".to_string()]),
+        });
+        // Still valid for 5 minutes, but that's within the margin.
+        fxa.add_cached_token(
+            "profile",
+            AccessTokenInfo {
+                scope: "profile".to_owned(),
+                token: "old_token".to_owned(),
+                key: None,
+                expires_at: util::now_secs() + 300,
+            },
+        );
+
+        let mut client = FxAClientMock::new();
+        client
+            .expect_create_access_token_using_refresh_token(
+                mockiato::Argument::any,
+                |token| token.partial_eq("refresh_token"),
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Ok(OAuthTokenResponse {
+                keys_jwe: None,
+                refresh_token: None,
+                session_token: None,
+                expires_in: 3600,
+                scope: "profile".to_owned(),
+                access_token: "new_token".to_owned(),
+            }));
+        fxa.set_client(Arc::new(client));
+
+        let token = fxa.get_access_token("profile", None).unwrap();
+        assert_eq!(token.token, "new_token");
+        // The new token is outside the margin, so is returned from the cache.
+        let token = fxa.get_access_token("profile", None).unwrap();
+        assert_eq!(token.token, "new_token");
+    }
+
     #[test]
     fn test_check_authorization_status() {
         let config = Config::stable_dev("12345678", "https://foo.bar");
@@ -876,6 +1183,39 @@ mod tests {
         assert!(auth_status.active);
     }
 
+    #[test]
+    fn test_get_granted_scopes() {
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let mut fxa = FirefoxAccount::with_config(config);
+        assert!(fxa.get_granted_scopes().is_empty());
+
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refresh_token".to_owned(),
+            scopes: HashSet::from_iter(vec!["profile".to_string(), scopes::OLD_SYNC.to_string()]),
+        });
+        assert_eq!(
+            fxa.get_granted_scopes(),
+            vec![scopes::OLD_SYNC.to_string(), "profile".to_string()]
+        );
+
+        // An older state, whose refresh token doesn't know its scopes.
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refresh_token".to_owned(),
+            scopes: HashSet::new(),
+        });
+        assert!(fxa.get_granted_scopes().is_empty());
+        fxa.add_cached_token(
+            "profile",
+            AccessTokenInfo {
+                scope: "profile".to_owned(),
+                token: "profile_token".to_owned(),
+                key: None,
+                expires_at: util::now_secs() + 3600,
+            },
+        );
+        assert_eq!(fxa.get_granted_scopes(), vec!["profile".to_string()]);
+    }
+
     #[test]
     fn test_check_authorization_status_circuit_breaker() {
         let config = Config::stable_dev("12345678", "https://foo.bar");
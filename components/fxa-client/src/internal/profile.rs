@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use super::http_client::AvatarResponse;
 pub use super::http_client::ProfileResponse as Profile;
 use super::{scopes, util, CachedResponse, FirefoxAccount};
 use crate::{Error, Result};
@@ -9,6 +10,14 @@ use crate::{Error, Result};
 // A cached profile response is considered fresh for `PROFILE_FRESHNESS_THRESHOLD` ms.
 const PROFILE_FRESHNESS_THRESHOLD: u64 = 120_000; // 2 minutes
 
+// The avatar image we last downloaded, and the URL it came from. The image at a given URL never
+// changes (the profile gets a new URL when the user changes their avatar), so we keep it until
+// the URL does. It isn't persisted, to keep the account state small.
+pub(crate) struct CachedAvatar {
+    url: String,
+    avatar: AvatarResponse,
+}
+
 impl FirefoxAccount {
     /// Fetch the profile for the user.
     /// This method will error-out if the `profile` scope is not
@@ -36,6 +45,29 @@ impl FirefoxAccount {
         }
     }
 
+    /// Fetch the profile picture for the user, or `None` if their profile doesn't have one.
+    /// The image is downloaded from the `avatar` URL in the profile, and cached in memory
+    /// until that URL changes.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    pub fn fetch_profile_avatar(&mut self) -> Result<Option<AvatarResponse>> {
+        let url = self.get_profile(false)?.avatar;
+        if url.is_empty() {
+            return Ok(None);
+        }
+        if let Some(cached) = &self.avatar_cache {
+            if cached.url == url {
+                return Ok(Some(cached.avatar.clone()));
+            }
+        }
+        let avatar = self.client.get_avatar(self.state.config(), &url)?;
+        self.avatar_cache = Some(CachedAvatar {
+            url,
+            avatar: avatar.clone(),
+        });
+        Ok(Some(avatar))
+    }
+
     fn get_profile_helper(&mut self, ignore_cache: bool) -> Result<Profile> {
         let mut etag = None;
         if let Some(cached_profile) = self.state.last_seen_profile() {
@@ -147,6 +179,70 @@ mod tests {
         assert_eq!(p.email, "foo@bar.com");
     }
 
+    fn set_cached_avatar_url(fxa: &mut FirefoxAccount, avatar: &str) {
+        fxa.state.set_last_seen_profile(CachedResponse {
+            response: Profile {
+                uid: "12345ab".into(),
+                email: "foo@bar.com".into(),
+                display_name: None,
+                avatar: avatar.into(),
+                avatar_default: false,
+            },
+            cached_at: util::now(),
+            etag: "fake etag".into(),
+        });
+    }
+
+    #[test]
+    fn test_fetch_profile_avatar() {
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let mut fxa = FirefoxAccount::with_config(config);
+        set_cached_avatar_url(&mut fxa, "https://foo.avatar/1");
+
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_avatar(mockiato::Argument::any, |url| {
+                url.partial_eq("https://foo.avatar/1")
+            })
+            .times(1)
+            .returns_once(Ok(AvatarResponse {
+                bytes: vec![1, 2, 3],
+                mime_type: "image/png".to_string(),
+            }));
+        client
+            .expect_get_avatar(mockiato::Argument::any, |url| {
+                url.partial_eq("https://foo.avatar/2")
+            })
+            .times(1)
+            .returns_once(Ok(AvatarResponse {
+                bytes: vec![4, 5, 6],
+                mime_type: "image/jpeg".to_string(),
+            }));
+        fxa.set_client(Arc::new(client));
+
+        let avatar = fxa.fetch_profile_avatar().unwrap().unwrap();
+        assert_eq!(avatar.bytes, vec![1, 2, 3]);
+        assert_eq!(avatar.mime_type, "image/png");
+        // The second call is served from the cache.
+        let avatar = fxa.fetch_profile_avatar().unwrap().unwrap();
+        assert_eq!(avatar.bytes, vec![1, 2, 3]);
+
+        // A new avatar URL invalidates the cache.
+        set_cached_avatar_url(&mut fxa, "https://foo.avatar/2");
+        let avatar = fxa.fetch_profile_avatar().unwrap().unwrap();
+        assert_eq!(avatar.bytes, vec![4, 5, 6]);
+        assert_eq!(avatar.mime_type, "image/jpeg");
+    }
+
+    #[test]
+    fn test_fetch_profile_avatar_no_avatar() {
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let mut fxa = FirefoxAccount::with_config(config);
+        fxa.add_cached_profile("123", "test@example.com");
+        fxa.set_client(Arc::new(FxAClientMock::new()));
+        assert!(fxa.fetch_profile_avatar().unwrap().is_none());
+    }
+
     #[test]
     fn test_expired_access_token_refetch() {
         let config = Config::stable_dev("12345678", "https://foo.bar");
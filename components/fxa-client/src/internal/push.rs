@@ -16,7 +16,11 @@ impl FirefoxAccount {
     ///
     /// **💾 This method alters the persisted account state.**
     ///
-    /// **⚠️ This API does not increment the command index if a command was received**
+    /// **⚠️ This API does not increment the command index if a command was received**, because
+    /// there may be earlier commands which haven't been fetched yet. Instead, it remembers that
+    /// the command was handled, so it's neither returned by the next poll nor returned again if
+    /// the push message is redelivered. This is only recorded locally, and the command counts as
+    /// handled as soon as it's fetched.
     pub fn handle_push_message(&mut self, payload: &str) -> Result<AccountEvent> {
         let payload = serde_json::from_str(payload).or_else(|err| {
             let v: serde_json::Value = serde_json::from_str(payload)?;
@@ -27,7 +31,12 @@ impl FirefoxAccount {
         })?;
         match payload {
             PushPayload::CommandReceived(CommandReceivedPushPayload { index, .. }) => {
+                if self.state.is_command_handled(index) {
+                    log::info!("Ignoring push message for a command which was already handled");
+                    return Ok(AccountEvent::CommandAlreadyHandled { index });
+                }
                 let cmd = self.get_command_for_index(index)?;
+                self.state.set_push_command_handled(index);
                 Ok(AccountEvent::CommandReceived {
                     command: cmd.try_into()?,
                 })
@@ -56,15 +65,19 @@ impl FirefoxAccount {
                 })
             }
             PushPayload::AccountDestroyed(AccountDestroyedPushPayload { account_uid }) => {
+                // We're about to discard the account state, so only do it if we can check
+                // the message is for our account.
                 let is_local_account = match self.state.last_seen_profile() {
                     None => false,
                     Some(profile) => profile.response.uid == account_uid,
                 };
-                Ok(if is_local_account {
-                    AccountEvent::AccountDestroyed
-                } else {
+                if !is_local_account {
                     return Err(Error::InvalidPushEvent);
-                })
+                }
+                // The account is gone, so there's no device record or refresh token to
+                // destroy on the server.
+                self.forget_account();
+                Ok(AccountEvent::AccountDestroyed)
             }
             PushPayload::PasswordChanged | PushPayload::PasswordReset => {
                 let status = self.check_authorization_status()?;
@@ -269,6 +282,75 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_push_account_destroyed() {
+        let mut fxa =
+            FirefoxAccount::with_config(Config::stable_dev("12345678", "https://foo.bar"));
+        // We shouldn't make any requests, since the account no longer exists.
+        fxa.set_client(Arc::new(FxAClientMock::new()));
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refresh_token".to_owned(),
+            scopes: std::collections::HashSet::new(),
+        });
+        fxa.state.force_current_device_id("my_id");
+        fxa.add_cached_profile("123", "test@example.com");
+
+        // A push for another account is rejected, and leaves our state alone.
+        let json = "{\"version\":1,\"command\":\"fxaccounts:account_destroyed\",\"data\":{\"uid\":\"456\"}}";
+        assert!(matches!(
+            fxa.handle_push_message(json),
+            Err(Error::InvalidPushEvent)
+        ));
+        assert!(fxa.state.refresh_token().is_some());
+
+        let json = "{\"version\":1,\"command\":\"fxaccounts:account_destroyed\",\"data\":{\"uid\":\"123\"}}";
+        let event = fxa.handle_push_message(json).unwrap();
+        assert!(matches!(event, AccountEvent::AccountDestroyed));
+        assert!(fxa.state.refresh_token().is_none());
+        assert!(fxa.state.last_seen_profile().is_none());
+    }
+
+    #[test]
+    fn test_push_account_destroyed_without_profile() {
+        let mut fxa =
+            FirefoxAccount::with_config(Config::stable_dev("12345678", "https://foo.bar"));
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refresh_token".to_owned(),
+            scopes: std::collections::HashSet::new(),
+        });
+        // Without a profile we can't tell whose account it was, so we leave our state alone.
+        let json = "{\"version\":1,\"command\":\"fxaccounts:account_destroyed\",\"data\":{\"uid\":\"123\"}}";
+        assert!(matches!(
+            fxa.handle_push_message(json),
+            Err(Error::InvalidPushEvent)
+        ));
+        assert!(fxa.state.refresh_token().is_some());
+    }
+
+    #[test]
+    fn test_push_command_already_handled() {
+        let mut fxa =
+            FirefoxAccount::with_config(Config::stable_dev("12345678", "https://foo.bar"));
+        fxa.state.set_last_handled_command_index(5);
+        fxa.state.set_push_command_handled(7);
+        assert!(fxa.state.is_command_handled(4));
+        assert!(!fxa.state.is_command_handled(6));
+        // Commands which were already delivered aren't fetched again.
+        let push = |index: u64| {
+            format!("{{\"version\":1,\"command\":\"fxaccounts:command_received\",\"data\":{{\"command\":\"https://identity.mozilla.com/cmd/open-uri\",\"index\":{index},\"sender\":\"sender\",\"url\":\"https://foo.bar/v1/account/device/commands?index={index}&limit=1\"}}}}")
+        };
+        for index in [4, 5, 7] {
+            let event = fxa.handle_push_message(&push(index)).unwrap();
+            assert!(
+                matches!(event, AccountEvent::CommandAlreadyHandled { index: i } if i == index)
+            );
+        }
+        // Once polling has caught up, the push index isn't needed.
+        fxa.state.set_last_handled_command_index(8);
+        assert!(fxa.state.is_command_handled(7));
+        assert!(!fxa.state.is_command_handled(9));
+    }
+
     #[test]
     fn test_handle_push_message_ignores_unknown_command() {
         let mut fxa =
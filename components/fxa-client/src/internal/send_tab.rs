@@ -5,13 +5,13 @@
 use super::{
     commands::{
         send_tab::{
-            self, EncryptedSendTabPayload, PrivateSendTabKeys, PublicSendTabKeys,
+            self, EncryptedSendTabPayload, PendingSendTab, PrivateSendTabKeys, PublicSendTabKeys,
             SendTabKeysPayload, SendTabPayload,
         },
         IncomingDeviceCommand,
     },
     http_client::GetDeviceResponse,
-    scopes, telemetry, FirefoxAccount,
+    scopes, telemetry, util, FirefoxAccount,
 };
 use crate::{Error, Result};
 
@@ -49,11 +49,68 @@ impl FirefoxAccount {
     /// This probably requires a new "Tab" struct with the title and url.
     /// android-components has SendToAllUseCase(), so this isn't just theoretical.
     /// See <https://github.com/mozilla/application-services/issues/3402>
+    ///
+    /// If we can't reach the server, or the application has said it's offline, the tab is queued
+    /// and sent the next time we're online, and this succeeds.
+    ///
+    /// **💾 This method alters the persisted account state.**
     pub fn send_single_tab(
         &mut self,
         target_device_id: &str,
         title: &str,
         url: &str,
+    ) -> Result<()> {
+        match self.send_single_tab_now(target_device_id, title, url) {
+            Err(e @ (Error::RequestError(_) | Error::Offline)) => {
+                log::info!("Queueing a tab to send later: {}", e);
+                self.state.queue_send_tab(PendingSendTab {
+                    target_device_id: target_device_id.to_owned(),
+                    title: title.to_owned(),
+                    url: url.to_owned(),
+                    queued_at: util::now(),
+                });
+                Ok(())
+            }
+            Err(e) => Err(e),
+            Ok(()) => {
+                self.flush_pending_send_tabs();
+                Ok(())
+            }
+        }
+    }
+
+    /// Send the tabs which were queued because we were offline, oldest first. Tabs which can
+    /// never be sent, because the target device is gone or the server rejected the command, are
+    /// dropped. We stop at the first failure which might go away, such as a network or server
+    /// error, backoff or an expired access token, leaving the rest queued.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    pub(crate) fn flush_pending_send_tabs(&mut self) {
+        while let Some(pending) = self.state.pending_send_tabs().first().cloned() {
+            match self.send_single_tab_now(&pending.target_device_id, &pending.title, &pending.url)
+            {
+                Ok(()) => (),
+                Err(e) if is_definitive_send_error(&e) => {
+                    log::warn!("Dropping a queued tab which can't be sent: {}", e)
+                }
+                Err(e) => {
+                    log::info!("Keeping queued tabs to send later: {}", e);
+                    return;
+                }
+            }
+            self.state.remove_pending_send_tab(&pending);
+        }
+    }
+
+    pub fn get_pending_send_tabs(&self) -> Vec<PendingSendTab> {
+        self.state.pending_send_tabs().to_vec()
+    }
+
+    fn send_single_tab_now(
+        &mut self,
+        target_device_id: &str,
+        title: &str,
+        url: &str,
     ) -> Result<()> {
         let devices = self.get_devices(false)?;
         let target = devices
@@ -159,3 +216,357 @@ impl FirefoxAccount {
         Ok(())
     }
 }
+
+// Whether sending a tab failed in a way which trying again won't fix: the target device isn't on
+// the account any more, or the server rejected the request for a reason other than
+// authentication or rate limiting.
+fn is_definitive_send_error(e: &Error) -> bool {
+    let is_definitive_status = |status: u64| {
+        (400..500).contains(&status)
+            && status != u64::from(viaduct::status_codes::UNAUTHORIZED)
+            && status != u64::from(viaduct::status_codes::TOO_MANY_REQUESTS)
+    };
+    match e {
+        Error::UnknownTargetDevice(_) => true,
+        Error::RemoteError { code, .. } => is_definitive_status(*code),
+        Error::UnexpectedStatus(e) => is_definitive_status(e.status.into()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::http_client::*;
+    use crate::internal::oauth::RefreshToken;
+    use crate::internal::{device::CommandFetchReason, Config};
+    use crate::ScopedKey;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    fn setup() -> FirefoxAccount {
+        let config = Config::stable_dev("12345678", "https://foo.bar");
+        let mut fxa = FirefoxAccount::with_config(config);
+        fxa.state.force_refresh_token(RefreshToken {
+            token: "refreshtok".to_string(),
+            scopes: HashSet::default(),
+        });
+        fxa.state.insert_scoped_key("https://identity.mozilla.com/apps/oldsync", ScopedKey {
+            kty: "oct".to_string(),
+            scope: "https://identity.mozilla.com/apps/oldsync".to_string(),
+            k: "kMtwpVC0ZaYFJymPza8rXK_0CgCp3KMwRStwGfBRBDtL6hXRDVJgQFaoOQ2dimw0Bko5WVv2gNTy7RX5zFYZHg".to_string(),
+            kid: "1542236016429-Ox1FbJfFfwTe5t-xq4v2hQ".to_string(),
+        });
+        fxa
+    }
+
+    fn network_error() -> Error {
+        Error::RequestError(viaduct::Error::NetworkError("offline".to_string()))
+    }
+
+    fn pending_urls(fxa: &FirefoxAccount) -> Vec<(String, String)> {
+        fxa.get_pending_send_tabs()
+            .into_iter()
+            .map(|p| (p.target_device_id, p.url))
+            .collect()
+    }
+
+    // A device called "target" which can receive tabs.
+    fn target_device(fxa: &mut FirefoxAccount) -> GetDeviceResponse {
+        GetDeviceResponse {
+            common: DeviceResponseCommon {
+                id: "target".to_owned(),
+                display_name: "Target".to_owned(),
+                device_type: sync15::DeviceType::Desktop,
+                push_subscription: None,
+                available_commands: HashMap::from([(
+                    send_tab::COMMAND_NAME.to_owned(),
+                    fxa.generate_send_tab_command_data().unwrap(),
+                )]),
+                push_endpoint_expired: false,
+            },
+            is_current_device: false,
+            location: DeviceLocation {
+                city: None,
+                country: None,
+                state: None,
+                state_code: None,
+            },
+            last_access_time: None,
+        }
+    }
+
+    #[test]
+    fn test_send_tab_queued_while_offline() {
+        let mut fxa = setup();
+        let target = target_device(&mut fxa);
+
+        // We can't even fetch the devices, so we don't know this one doesn't exist yet.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+            .times(1)
+            .returns_once(Err(network_error()));
+        fxa.set_client(Arc::new(client));
+        fxa.send_single_tab("unknown", "Title", "https://example.com/1")
+            .unwrap();
+
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+            .times(1)
+            .returns_once(Ok(vec![target]));
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Err(network_error()));
+        fxa.set_client(Arc::new(client));
+        fxa.send_single_tab("target", "Title", "https://example.com/2")
+            .unwrap();
+
+        // Sending the same tab again doesn't queue it twice.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Err(network_error()));
+        fxa.set_client(Arc::new(client));
+        fxa.send_single_tab("target", "Title", "https://example.com/2")
+            .unwrap();
+        assert_eq!(
+            pending_urls(&fxa),
+            vec![
+                ("unknown".to_string(), "https://example.com/1".to_string()),
+                ("target".to_string(), "https://example.com/2".to_string()),
+            ]
+        );
+
+        // Once we're online, the tab for the device which doesn't exist is dropped, and the
+        // other is sent.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_pending_commands(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Ok(PendingCommandsResponse {
+                index: 0,
+                last: Some(true),
+                messages: vec![],
+            }));
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                |command| command.partial_eq(send_tab::COMMAND_NAME),
+                |target| target.partial_eq("target"),
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Ok(()));
+        fxa.set_client(Arc::new(client));
+        fxa.poll_device_commands(CommandFetchReason::Poll).unwrap();
+        assert!(pending_urls(&fxa).is_empty());
+
+        // Other errors aren't queued.
+        assert!(fxa
+            .send_single_tab("unknown", "Title", "https://example.com/3")
+            .is_err());
+        assert!(pending_urls(&fxa).is_empty());
+    }
+
+    #[test]
+    fn test_poll_skips_commands_delivered_by_push() {
+        let mut fxa = setup();
+        // A tab sent to ourselves, so we can decrypt it.
+        let target = target_device(&mut fxa);
+        let message = |index: u64, url: &str| {
+            let (payload, _) = SendTabPayload::single_tab("Title", url);
+            let oldsync_key = fxa.get_scoped_key(scopes::OLD_SYNC).unwrap();
+            PendingCommand {
+                index,
+                data: CommandData {
+                    command: send_tab::COMMAND_NAME.to_owned(),
+                    payload: send_tab::build_send_command(oldsync_key, &target, &payload).unwrap(),
+                    sender: None,
+                },
+            }
+        };
+        let messages = vec![
+            message(2, "https://example.com/2"),
+            message(3, "https://example.com/3"),
+            message(4, "https://example.com/4"),
+        ];
+        fxa.state.set_last_handled_command_index(1);
+        // The app already got the tab at index 3 from a push message.
+        fxa.state.set_push_command_handled(3);
+
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_pending_commands(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                |index| index.partial_eq(2),
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Ok(PendingCommandsResponse {
+                index: 4,
+                last: Some(true),
+                messages,
+            }));
+        client
+            .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+            .times(1)
+            .returns_once(Ok(vec![]));
+        fxa.set_client(Arc::new(client));
+        let urls: Vec<String> = fxa
+            .poll_device_commands(CommandFetchReason::Poll)
+            .unwrap()
+            .into_iter()
+            .map(|command| match command {
+                IncomingDeviceCommand::TabReceived { payload, .. } => {
+                    payload.entries[0].url.clone()
+                }
+            })
+            .collect();
+        assert_eq!(urls, vec!["https://example.com/2", "https://example.com/4"]);
+        assert_eq!(fxa.state.last_handled_command_index(), Some(4));
+        assert!(!fxa.state.is_command_handled(5));
+    }
+
+    #[test]
+    fn test_send_tab_queued_with_offline_hint() {
+        let mut fxa = setup();
+        let mut client = FxAClientMock::new();
+        // `Error` isn't cloneable, so each call needs its own expectation.
+        for _ in 0..2 {
+            client
+                .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+                .times(1)
+                .returns_once(Err(Error::Offline));
+        }
+        fxa.set_client(Arc::new(client));
+        fxa.send_single_tab("target", "Title", "https://example.com/1")
+            .unwrap();
+        // Still offline, so it stays queued.
+        fxa.flush_pending_send_tabs();
+        assert_eq!(
+            pending_urls(&fxa),
+            vec![("target".to_string(), "https://example.com/1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_send_tab_kept_after_server_error() {
+        let mut fxa = setup();
+        let target = target_device(&mut fxa);
+        fxa.state.queue_send_tab(PendingSendTab {
+            target_device_id: "target".to_string(),
+            title: "Title".to_string(),
+            url: "https://example.com/1".to_string(),
+            queued_at: 0,
+        });
+        let server_error = |code| Error::RemoteError {
+            code,
+            errno: 999,
+            error: "Error".to_string(),
+            message: "Error".to_string(),
+            info: "".to_string(),
+        };
+
+        // A server error might go away, so the tab stays queued.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_get_devices(mockiato::Argument::any, mockiato::Argument::any)
+            .times(1)
+            .returns_once(Ok(vec![target]));
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Err(server_error(503)));
+        fxa.set_client(Arc::new(client));
+        fxa.flush_pending_send_tabs();
+        assert_eq!(
+            pending_urls(&fxa),
+            vec![("target".to_string(), "https://example.com/1".to_string())]
+        );
+
+        // As does an authentication error. The devices are cached from now on.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Err(server_error(401)));
+        fxa.set_client(Arc::new(client));
+        fxa.flush_pending_send_tabs();
+        assert_eq!(pending_urls(&fxa).len(), 1);
+
+        // But one the server rejects outright is dropped.
+        let mut client = FxAClientMock::new();
+        client
+            .expect_invoke_command(
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+                mockiato::Argument::any,
+            )
+            .times(1)
+            .returns_once(Err(server_error(400)));
+        fxa.set_client(Arc::new(client));
+        fxa.flush_pending_send_tabs();
+        assert!(pending_urls(&fxa).is_empty());
+    }
+
+    #[test]
+    fn test_send_tab_queue_is_capped() {
+        let mut fxa = setup();
+        for i in 0..send_tab::MAX_PENDING_SEND_TABS + 5 {
+            fxa.state.queue_send_tab(PendingSendTab {
+                target_device_id: "target".to_string(),
+                title: "Title".to_string(),
+                url: format!("https://example.com/{}", i),
+                queued_at: 0,
+            });
+        }
+        let pending = pending_urls(&fxa);
+        assert_eq!(pending.len(), send_tab::MAX_PENDING_SEND_TABS);
+        // The oldest were dropped.
+        assert_eq!(pending[0].1, "https://example.com/5");
+        assert_eq!(
+            pending.last().unwrap().1,
+            format!(
+                "https://example.com/{}",
+                send_tab::MAX_PENDING_SEND_TABS + 4
+            )
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     internal::{
+        commands::send_tab::{PendingSendTab, MAX_PENDING_SEND_TABS},
         device,
         oauth::{AccessTokenInfo, RefreshToken},
         profile::Profile,
@@ -42,6 +43,14 @@ impl StateManager {
         &self.persisted_state.config
     }
 
+    pub fn set_refresh_margin_seconds(&mut self, seconds: Option<u64>) {
+        self.persisted_state.config.refresh_margin_seconds = seconds;
+    }
+
+    pub fn set_network_timeout_seconds(&mut self, seconds: Option<u64>) {
+        self.persisted_state.config.network_timeout_seconds = seconds;
+    }
+
     pub fn refresh_token(&self) -> Option<&RefreshToken> {
         self.persisted_state.refresh_token.as_ref()
     }
@@ -72,6 +81,21 @@ impl StateManager {
         self.persisted_state.device_capabilities = HashSet::new();
     }
 
+    /// Get the push subscription that we last registered with the server, if we believe the
+    /// server still has it.
+    pub fn last_sent_push_subscription(&self) -> Option<&device::PushSubscription> {
+        self.persisted_state.push_subscription.as_ref()
+    }
+
+    /// Update the push subscription that we last registered with the server. Set this to
+    /// `None` to make sure that the next call to `set_push_subscription()` re-registers it.
+    pub fn set_last_sent_push_subscription(
+        &mut self,
+        push_subscription: Option<device::PushSubscription>,
+    ) {
+        self.persisted_state.push_subscription = push_subscription;
+    }
+
     pub fn get_commands_data(&self, key: &str) -> Option<&str> {
         self.persisted_state
             .commands_data
@@ -89,12 +113,60 @@ impl StateManager {
         self.persisted_state.commands_data.remove(key);
     }
 
+    pub fn pending_send_tabs(&self) -> &[PendingSendTab] {
+        &self.persisted_state.pending_send_tabs
+    }
+
+    /// Queue a tab to be sent later. Sending the same URL to the same device again while it's
+    /// still queued doesn't queue it twice. If the queue is full, the oldest tab is dropped.
+    pub fn queue_send_tab(&mut self, pending: PendingSendTab) {
+        let queue = &mut self.persisted_state.pending_send_tabs;
+        if queue
+            .iter()
+            .any(|p| p.target_device_id == pending.target_device_id && p.url == pending.url)
+        {
+            return;
+        }
+        if queue.len() >= MAX_PENDING_SEND_TABS {
+            log::warn!("Too many tabs queued to send; dropping the oldest");
+            let excess = queue.len() + 1 - MAX_PENDING_SEND_TABS;
+            queue.drain(..excess);
+        }
+        queue.push(pending);
+    }
+
+    pub fn remove_pending_send_tab(&mut self, pending: &PendingSendTab) {
+        self.persisted_state
+            .pending_send_tabs
+            .retain(|p| p != pending);
+    }
+
     pub fn last_handled_command_index(&self) -> Option<u64> {
         self.persisted_state.last_handled_command
     }
 
     pub fn set_last_handled_command_index(&mut self, idx: u64) {
-        self.persisted_state.last_handled_command = Some(idx)
+        self.persisted_state.last_handled_command = Some(idx);
+        // Polling starts after `idx` from now on, so we don't need to remember these.
+        self.persisted_state
+            .handled_push_commands
+            .retain(|&handled| handled > idx);
+    }
+
+    /// Whether the command with index `idx` has already been delivered to the application,
+    /// either by polling or by a push message.
+    pub fn is_command_handled(&self, idx: u64) -> bool {
+        self.persisted_state
+            .last_handled_command
+            .map_or(false, |last| idx <= last)
+            || self.persisted_state.handled_push_commands.contains(&idx)
+    }
+
+    /// Record that the command with index `idx` was delivered by a push message.
+    pub fn set_push_command_handled(&mut self, idx: u64) {
+        if !self.is_command_handled(idx) {
+            self.persisted_state.handled_push_commands.insert(idx);
+        }
     }
 
     pub fn current_device_id(&self) -> Option<&str> {
@@ -109,6 +181,15 @@ impl StateManager {
         self.persisted_state.scoped_keys.get(scope)
     }
 
+    /// The scopes we hold a scoped key or a cached access token for.
+    pub fn scopes_with_keys_or_tokens(&self) -> impl Iterator<Item = &str> {
+        self.persisted_state
+            .scoped_keys
+            .keys()
+            .chain(self.persisted_state.access_token_cache.keys())
+            .map(String::as_str)
+    }
+
     pub(crate) fn last_seen_profile(&self) -> Option<&CachedResponse<Profile>> {
         self.persisted_state.last_seen_profile.as_ref()
     }
@@ -149,6 +230,17 @@ impl StateManager {
         self.flow_store.remove(state)
     }
 
+    /// Whether there are any OAuth flows in progress.
+    pub fn has_oauth_flows(&self) -> bool {
+        !self.flow_store.is_empty()
+    }
+
+    /// Whether `state` is for an OAuth flow in progress. Unlike `pop_oauth_flow()`, this
+    /// doesn't remove it.
+    pub fn has_oauth_flow(&self, state: &str) -> bool {
+        self.flow_store.contains_key(state)
+    }
+
     /// Complete an OAuth flow.
     pub fn complete_oauth_flow(
         &mut self,
@@ -159,6 +251,8 @@ impl StateManager {
         // When our keys change, we might need to re-register device capabilities with the server.
         // Ensure that this happens on the next call to ensure_capabilities.
         self.persisted_state.device_capabilities.clear();
+        // Likewise for our push subscription, which may belong to a different device record.
+        self.persisted_state.push_subscription = None;
 
         for (scope, key) in scoped_keys {
             self.persisted_state.scoped_keys.insert(scope, key);
@@ -184,6 +278,7 @@ impl StateManager {
         self.persisted_state.refresh_token = Some(refresh_token);
         self.persisted_state.access_token_cache.clear();
         self.persisted_state.device_capabilities.clear();
+        self.persisted_state.push_subscription = None;
     }
 }
 
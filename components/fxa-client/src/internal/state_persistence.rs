@@ -30,11 +30,12 @@
 //! The code that was deleted demonstrates how we can implement the migration
 
 use serde_derive::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use super::{
+    commands::send_tab::PendingSendTab,
     config::Config,
-    device::Capability as DeviceCapability,
+    device::{Capability as DeviceCapability, PushSubscription},
     oauth::{AccessTokenInfo, RefreshToken},
     profile::Profile,
     CachedResponse, Result,
@@ -103,6 +104,14 @@ pub(crate) struct StateV2 {
     pub(crate) device_capabilities: HashSet<DeviceCapability>,
     #[serde(default)]
     pub(crate) access_token_cache: HashMap<String, AccessTokenInfo>,
+    #[serde(default)]
+    pub(crate) pending_send_tabs: Vec<PendingSendTab>,
+    #[serde(default)]
+    pub(crate) push_subscription: Option<PushSubscription>,
+    // The indexes of commands after `last_handled_command` which were delivered by push, so
+    // polling doesn't deliver them again.
+    #[serde(default)]
+    pub(crate) handled_push_commands: BTreeSet<u64>,
     pub(crate) session_token: Option<String>, // Hex-formatted string.
     pub(crate) last_seen_profile: Option<CachedResponse<Profile>>,
 }
@@ -126,6 +135,9 @@ impl StateV2 {
             commands_data: HashMap::new(),
             access_token_cache: HashMap::new(),
             device_capabilities: HashSet::new(),
+            pending_send_tabs: Vec::new(),
+            push_subscription: None,
+            handled_push_commands: BTreeSet::new(),
             session_token: None,
         }
     }
@@ -166,5 +178,7 @@ mod tests {
             "bed5532f4fea7e39c5c4f609f53603ee7518fd1c103cc4034da3618f786ed188"
         );
         assert_eq!(state.access_token_cache.len(), 0);
+        assert!(state.pending_send_tabs.is_empty());
+        assert!(state.handled_push_commands.is_empty());
     }
 }
@@ -49,16 +49,22 @@ mod token;
 
 pub use sync15::DeviceType;
 
-pub use auth::{AuthorizationInfo, MetricsParams};
+pub use auth::{AuthorizationInfo, LoginHints, MetricsParams};
 pub use device::{AttachedClient, Device, DeviceCapability};
 pub use error::{Error, FxaError};
 use parking_lot::Mutex;
-pub use profile::Profile;
+pub use profile::{AvatarImage, Profile};
 pub use push::{
-    AccountEvent, DevicePushSubscription, IncomingDeviceCommand, SendTabPayload, TabHistoryEntry,
+    AccountEvent, DevicePushSubscription, IncomingDeviceCommand, PendingSendTab, SendTabPayload,
+    TabHistoryEntry,
 };
 pub use token::{AccessTokenInfo, AuthorizationParameters, ScopedKey};
 
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
 /// Result returned by internal functions
 pub type Result<T> = std::result::Result<T, Error>;
 /// Result returned by public-facing API functions
@@ -75,6 +81,9 @@ pub struct FirefoxAccount {
     // For now, we serialize all access on a single `Mutex` for thread safety across
     // the FFI. We should make the locking more granular in future.
     internal: Mutex<internal::FirefoxAccount>,
+    // Shared with the config of `internal`. It's kept out here so that setting it doesn't wait for
+    // a call which holds the lock while it's waiting for the network.
+    offline_hint: Arc<AtomicBool>,
 }
 
 impl FirefoxAccount {
@@ -85,10 +94,37 @@ impl FirefoxAccount {
     /// This method constructs as new [`FirefoxAccount`] instance configured to connect
     /// the application to a user's account.
     pub fn new(config: FxaConfig) -> FirefoxAccount {
+        FirefoxAccount::from_internal(internal::FirefoxAccount::new(config))
+    }
+
+    fn from_internal(internal: internal::FirefoxAccount) -> FirefoxAccount {
         FirefoxAccount {
-            internal: Mutex::new(internal::FirefoxAccount::new(config)),
+            offline_hint: internal.offline_hint(),
+            internal: Mutex::new(internal),
         }
     }
+
+    /// Tell the account whether the application thinks the device is offline.
+    ///
+    /// While the hint is set, methods which need the network fail straight away with
+    /// [`FxaError::Network`], instead of waiting for the request to time out. The hint is
+    /// advisory: the account doesn't check connectivity itself, and tries the network as usual
+    /// unless the hint is set. Applications should clear it when connectivity comes back.
+    ///
+    /// This doesn't wait for calls which are in progress, and isn't persisted.
+    pub fn set_offline_hint(&self, offline: bool) {
+        self.offline_hint.store(offline, Ordering::Relaxed);
+    }
+
+    /// Set how many seconds requests to the FxA servers wait for a response before giving up.
+    /// `None` restores the default viaduct timeouts.
+    ///
+    /// This overrides [`FxaConfig::network_timeout_seconds`]. The timeout isn't persisted, so
+    /// applications which restore an account with [`from_json`](FirefoxAccount::from_json)
+    /// should call this if they don't want the default.
+    pub fn set_network_timeout_seconds(&self, seconds: Option<u64>) {
+        self.internal.lock().set_network_timeout_seconds(seconds)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -107,6 +143,18 @@ pub struct FxaConfig {
     ///  cut out `fxa-client` out of the middle and have applications send the overridden URL
     ///  directly to `SyncManager`.
     pub token_server_url_override: Option<String>,
+    /// How many seconds before a cached access token expires `get_access_token()` should fetch a
+    /// new one, rather than returning the cached token. If `None` then it defaults to 60 seconds.
+    ///
+    /// This isn't persisted, and [`FirefoxAccount::from_json`] doesn't take a config, so accounts
+    /// restored with it use the default. Applications which set this must also call
+    /// [`FirefoxAccount::set_refresh_margin_seconds`] after every restore.
+    pub refresh_margin_seconds: Option<u64>,
+    /// How many seconds to wait for the FxA servers before giving up on a request. If `None` then
+    /// the default viaduct timeouts are used. This isn't persisted: accounts restored with
+    /// [`FirefoxAccount::from_json`] use the default unless the application calls
+    /// [`FirefoxAccount::set_network_timeout_seconds`].
+    pub network_timeout_seconds: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
@@ -139,6 +187,8 @@ impl FxaConfig {
             client_id: client_id.to_string(),
             redirect_uri: redirect_uri.to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
         }
     }
 
@@ -148,6 +198,8 @@ impl FxaConfig {
             client_id: client_id.to_string(),
             redirect_uri: redirect_uri.to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
         }
     }
 
@@ -157,6 +209,8 @@ impl FxaConfig {
             client_id: client_id.to_string(),
             redirect_uri: redirect_uri.to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
         }
     }
 
@@ -166,6 +220,8 @@ impl FxaConfig {
             client_id: client_id.to_string(),
             redirect_uri: redirect_uri.to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
         }
     }
 
@@ -175,6 +231,8 @@ impl FxaConfig {
             client_id: client_id.to_string(),
             redirect_uri: redirect_uri.to_string(),
             token_server_url_override: None,
+            refresh_margin_seconds: None,
+            network_timeout_seconds: None,
         }
     }
 }
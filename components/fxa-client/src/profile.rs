@@ -34,6 +34,24 @@ impl FirefoxAccount {
     pub fn get_profile(&self, ignore_cache: bool) -> ApiResult<Profile> {
         Ok(self.internal.lock().get_profile(ignore_cache)?.into())
     }
+
+    /// Get the profile picture for the signed-in user, if any.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    ///
+    /// This method downloads the image at the `avatar` URL of the user's [`Profile`],
+    /// fetching the profile itself as [`get_profile`](FirefoxAccount::get_profile) would.
+    /// The image is cached in memory until the profile's `avatar` URL changes.
+    ///
+    /// # Notes
+    ///
+    ///    - Returns `None` if the profile doesn't have an avatar URL.
+    ///    - If there is no signed-in user, this method will throw an
+    ///      [`Authentication`](FxaError::Authentication) error.
+    #[handle_error(Error)]
+    pub fn fetch_profile_avatar(&self) -> ApiResult<Option<AvatarImage>> {
+        Ok(self.internal.lock().fetch_profile_avatar()?.map(Into::into))
+    }
 }
 
 /// Information about the user that controls a Firefox Account.
@@ -60,3 +78,11 @@ pub struct Profile {
     /// Whether the `avatar` URL represents the default avatar image.
     pub is_default_avatar: bool,
 }
+
+/// A profile picture, as returned by [`fetch_profile_avatar`](FirefoxAccount::fetch_profile_avatar).
+pub struct AvatarImage {
+    /// The image data.
+    pub bytes: Vec<u8>,
+    /// The MIME type of the image, as reported by the server.
+    pub mime_type: String,
+}
@@ -22,6 +22,9 @@ impl FirefoxAccount {
     ///
     /// # Notes
     ///
+    ///    - The subscription is only sent to the server when it differs from the one last
+    ///      registered, or the server has said that one expired, so it's fine to call this
+    ///      every time the application gets its subscription from the push service.
     ///    - Device registration is only available to applications that have been
     ///      granted the `https://identity.mozilla.com/apps/oldsync` scope.
     #[handle_error(Error)]
@@ -31,6 +34,18 @@ impl FirefoxAccount {
             .set_push_subscription(subscription.into())
     }
 
+    /// Remove the push subscription for this device.
+    ///
+    /// **💾 This method alters the persisted account state.**
+    ///
+    /// Applications should call this when they unsubscribe from push, so that the FxA server
+    /// stops sending notifications to an endpoint which no longer exists. Device commands can
+    /// still be fetched with [`poll_device_commands`](FirefoxAccount::poll_device_commands).
+    #[handle_error(Error)]
+    pub fn clear_push_subscription(&self) -> ApiResult<()> {
+        self.internal.lock().clear_push_subscription()
+    }
+
     /// Process and respond to a server-delivered account update message
     ///
     /// **💾 This method alters the persisted account state.**
@@ -42,7 +57,13 @@ impl FirefoxAccount {
     /// may use for further processing.
     ///
     /// It's important to note if the event is [`AccountEvent::CommandReceived`], the caller should call
-    /// [`FirefoxAccount::poll_device_commands`]
+    /// [`FirefoxAccount::poll_device_commands`]. Commands received this way aren't returned again by
+    /// [`FirefoxAccount::poll_device_commands`], once the account state has been persisted. A
+    /// redelivered push message for one of them returns [`AccountEvent::CommandAlreadyHandled`].
+    ///
+    /// This de-duplication is local to this device: the server has no way to acknowledge a
+    /// command, and a command counts as handled as soon as it's fetched, whether by push or by
+    /// polling. If the application stops before acting on a command, it isn't delivered again.
     #[handle_error(Error)]
     pub fn handle_push_message(&self, payload: &str) -> ApiResult<AccountEvent> {
         self.internal.lock().handle_push_message(payload)
@@ -87,6 +108,9 @@ impl FirefoxAccount {
     ///        - (Yeah...sorry. This should be changed to do something better.)
     ///    - It is not currently possible to send a full [`SendTabPayload`] to another device,
     ///      but that's purely an API limitation that should go away in future.
+    ///    - If the server can't be reached, the tab is queued rather than lost, and this
+    ///      method succeeds. Queued tabs are sent by the next successful call to this method
+    ///      or to [`poll_device_commands`](FirefoxAccount::poll_device_commands).
     ///    - Device commands functionality is only available to applications that have been
     ///      granted the `https://identity.mozilla.com/apps/oldsync` scope.
     #[handle_error(Error)]
@@ -95,6 +119,19 @@ impl FirefoxAccount {
             .lock()
             .send_single_tab(target_device_id, title, url)
     }
+
+    /// Get the tabs which are queued to be sent, because the server couldn't be reached when
+    /// [`send_single_tab`](FirefoxAccount::send_single_tab) was called, oldest first.
+    ///
+    /// This does not make network requests, so it can be used to show pending sends in the UI.
+    pub fn get_pending_send_tabs(&self) -> Vec<PendingSendTab> {
+        self.internal
+            .lock()
+            .get_pending_send_tabs()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
 }
 
 /// Details of a web-push subscription endpoint.
@@ -148,8 +185,9 @@ pub enum AccountEvent {
     AccountAuthStateChanged,
     /// Sent when the user deletes their Firefox Account.
     ///
-    /// When receiving this event, the application should act as though the user had
-    /// signed out, discarding any persisted account state.
+    /// The account state held by this [`FirefoxAccount`] has already been discarded
+    /// when this is returned. The application should act as though the user had
+    /// signed out, deleting any local data tied to the account.
     AccountDestroyed,
     /// Sent when a new device connects to the user's account.
     ///
@@ -166,6 +204,12 @@ pub enum AccountEvent {
         is_local_device: bool,
     },
 
+    /// Sent when a push message is for a command which was already delivered, by an earlier
+    /// push message or by polling.
+    ///
+    /// When receiving this event, the application should ignore it.
+    CommandAlreadyHandled { index: u64 },
+
     /// An unknown event, most likely an event the client doesn't support yet.
     ///
     /// When receiving this event, the application should gracefully ignore it.
@@ -211,3 +255,13 @@ pub struct TabHistoryEntry {
     pub title: String,
     pub url: String,
 }
+
+/// A tab which is queued to be sent to another device once the server can be reached.
+#[derive(Debug)]
+pub struct PendingSendTab {
+    pub target_device_id: String,
+    pub title: String,
+    pub url: String,
+    /// When the tab was queued, in milliseconds since the epoch.
+    pub queued_at: i64,
+}
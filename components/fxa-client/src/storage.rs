@@ -19,7 +19,6 @@
 
 use crate::{internal, ApiResult, Error, FirefoxAccount};
 use error_support::handle_error;
-use parking_lot::Mutex;
 
 impl FirefoxAccount {
     /// Restore a [`FirefoxAccount`] instance from serialized state.
@@ -33,9 +32,9 @@ impl FirefoxAccount {
     /// produce unexpected behaviour.
     #[handle_error(Error)]
     pub fn from_json(data: &str) -> ApiResult<FirefoxAccount> {
-        Ok(FirefoxAccount {
-            internal: Mutex::new(internal::FirefoxAccount::from_json(data)?),
-        })
+        Ok(FirefoxAccount::from_internal(
+            internal::FirefoxAccount::from_json(data)?,
+        ))
     }
 
     /// Save current state to a JSON string.
@@ -54,6 +54,17 @@ impl FirefoxAccount {
             .try_into()
     }
 
+    /// Set how many seconds before a cached access token expires
+    /// [`get_access_token`](FirefoxAccount::get_access_token) should fetch a new one, rather
+    /// than returning the cached token. `None` restores the default of 60 seconds.
+    ///
+    /// This overrides `FxaConfig::refresh_margin_seconds`. The margin isn't persisted, so
+    /// applications which restore an account with [`from_json`](FirefoxAccount::from_json)
+    /// should call this if they don't want the default.
+    pub fn set_refresh_margin_seconds(&self, seconds: Option<u64>) {
+        self.internal.lock().set_refresh_margin_seconds(seconds)
+    }
+
     /// Get the session token for the user's account, if one is available.
     ///
     /// **💾 This method alters the persisted account state.**
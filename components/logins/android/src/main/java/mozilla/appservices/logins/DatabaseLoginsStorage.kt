@@ -106,6 +106,55 @@ class DatabaseLoginsStorage(dbPath: String) : AutoCloseable {
         }
     }
 
+    fun lock() {
+        store.lock()
+    }
+
+    @Throws(LoginsApiException::class)
+    fun unlock(encryptionKey: String) {
+        store.unlock(encryptionKey)
+    }
+
+    fun isLocked(): Boolean {
+        return store.isLocked()
+    }
+
+    /**
+     * Check whether [encryptionKey] is the key the logins are encrypted with, for example
+     * after the user enters their primary password. Nothing is decrypted into memory
+     * beyond what's needed for the check, and the store isn't unlocked.
+     */
+    @Throws(LoginsApiException::class)
+    fun checkEncryptionKey(encryptionKey: String): Boolean {
+        return readQueryCounters.measure {
+            store.checkEncryptionKey(encryptionKey)
+        }
+    }
+
+    /**
+     * Find passwords which are used for more than one site. Returns the ids of the logins
+     * using each reused password, most reused first. Logins which can't be decrypted with
+     * [encryptionKey] are skipped.
+     */
+    @Throws(LoginsApiException::class)
+    fun findReusedPasswords(encryptionKey: String): List<List<String>> {
+        return readQueryCounters.measure {
+            store.findReusedPasswords(encryptionKey)
+        }
+    }
+
+    /**
+     * Register an observer to be told when logins are added, updated or deleted, including
+     * by a sync. This replaces any observer which was already set.
+     */
+    fun setChangeObserver(observer: LoginsChangeObserver) {
+        store.setChangeObserver(observer)
+    }
+
+    fun unsetChangeObserver() {
+        store.unsetChangeObserver()
+    }
+
     fun registerWithSyncManager() {
         return store.registerWithSyncManager()
     }
@@ -82,6 +82,61 @@ open class LoginsStorage {
         }
     }
 
+    /// Lock the store behind a primary password. Until `unlock` is called, anything which
+    /// reads or writes usernames or passwords throws `LoginStoreError.Locked`.
+    open func lock() {
+        queue.sync {
+            self.store.lock()
+        }
+    }
+
+    /// Unlock the store. Throws `LoginStoreError.IncorrectKey` if `encryptionKey` can't
+    /// decrypt the stored logins.
+    open func unlock(encryptionKey: String) throws {
+        try queue.sync {
+            try self.store.unlock(encryptionKey: encryptionKey)
+        }
+    }
+
+    open func isLocked() -> Bool {
+        return queue.sync {
+            self.store.isLocked()
+        }
+    }
+
+    /// Check whether `encryptionKey` is the key the logins are encrypted with, for example after
+    /// the user enters their primary password. This doesn't unlock the store.
+    open func checkEncryptionKey(encryptionKey: String) throws -> Bool {
+        return try queue.sync {
+            try self.store.checkEncryptionKey(encryptionKey: encryptionKey)
+        }
+    }
+
+    /// Find passwords which are used for more than one site. Returns the ids of the logins using
+    /// each reused password, most reused first. Logins which can't be decrypted with
+    /// `encryptionKey` are skipped.
+    open func findReusedPasswords(encryptionKey: String) throws -> [[String]] {
+        return try queue.sync {
+            try self.store.findReusedPasswords(encryptionKey: encryptionKey)
+        }
+    }
+
+    /// Register an observer to be told when logins are added, updated or deleted, including
+    /// by a sync. This replaces any observer which was already set.
+    ///
+    /// The observer is called on a separate queue, so it can use this object without deadlocking.
+    open func setChangeObserver(observer: LoginsChangeObserver) {
+        queue.sync {
+            self.store.setChangeObserver(observer: AsyncChangeObserver(observer))
+        }
+    }
+
+    open func unsetChangeObserver() {
+        queue.sync {
+            self.store.unsetChangeObserver()
+        }
+    }
+
     /// Register with the sync manager
     open func registerWithSyncManager() {
         return queue.sync {
@@ -111,3 +166,20 @@ public func migrateLoginsFromSqlcipher(
 
     return didMigrationSucceed
 }
+
+// Changes are reported while `LoginsStorage.queue` is busy with the write which made them, so we
+// hand them to the real observer on another queue. It's serial so the changes arrive in order.
+private class AsyncChangeObserver: LoginsChangeObserver {
+    private let observer: LoginsChangeObserver
+    private let queue = DispatchQueue(label: "com.mozilla.logins-storage.change-observer")
+
+    init(_ observer: LoginsChangeObserver) {
+        self.observer = observer
+    }
+
+    func onLoginsChanged(changes: [LoginChange]) {
+        queue.async {
+            self.observer.onLoginsChanged(changes: changes)
+        }
+    }
+}
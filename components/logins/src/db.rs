@@ -24,11 +24,14 @@
 ///     loginsL will be an empty table after this.  See mark_as_synchronized() for the details.
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
+use crate::import::{LoginImportOutcome, LoginImportStrategy};
 use crate::login::*;
+use crate::observer::LoginChangeType;
+use crate::repair::{RepairReport, UnrepairableLogin};
 use crate::schema;
 use crate::sync::SyncStatus;
 use crate::util;
-use interrupt_support::{SqlInterruptHandle, SqlInterruptScope};
+use interrupt_support::{register_interrupt, SqlInterruptHandle, SqlInterruptScope};
 use lazy_static::lazy_static;
 use rusqlite::{
     named_params,
@@ -36,11 +39,13 @@ use rusqlite::{
     Connection,
 };
 use sql_support::ConnExt;
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::SystemTime;
 use sync_guid::Guid;
+use types::Timestamp;
 use url::{Host, Url};
 
 pub struct LoginDb {
@@ -61,8 +66,11 @@ impl LoginDb {
         // do this on Android, or allow caller to configure it.
         db.set_pragma("temp_store", 2)?;
 
+        let interrupt_handle = Arc::new(SqlInterruptHandle::new(&db));
+        // Allow `interrupt_support::interrupt_all()` and `shutdown()` to interrupt us.
+        register_interrupt(Arc::<SqlInterruptHandle>::downgrade(&interrupt_handle));
         let mut logins = Self {
-            interrupt_handle: Arc::new(SqlInterruptHandle::new(&db)),
+            interrupt_handle,
             db,
         };
         let tx = logins.db.transaction()?;
@@ -138,6 +146,13 @@ impl LoginDb {
         rows.collect::<Result<_>>()
     }
 
+    /// Any one of the logins, or None if there aren't any.
+    pub(crate) fn get_any(&self) -> Result<Option<EncryptedLogin>> {
+        let mut stmt = self.db.prepare_cached(&GET_ANY_SQL)?;
+        let mut rows = stmt.query_and_then([], EncryptedLogin::from_row)?;
+        rows.next().transpose()
+    }
+
     pub fn get_by_base_domain(&self, base_domain: &str) -> Result<Vec<EncryptedLogin>> {
         // We first parse the input string as a host so it is normalized.
         let base_host = match Host::parse(base_domain) {
@@ -189,6 +204,63 @@ impl LoginDb {
         rows.collect::<Result<_>>()
     }
 
+    // Find the logins which can be filled into a form on `origin` which submits to `action`,
+    // using the same rules as desktop:
+    //
+    //  - Only form logins (those with a `form_action_origin`) match.
+    //  - An empty `action` means the form submits to `origin`.
+    //  - Any `javascript:` action matches logins for the "javascript:" form action origin.
+    //  - Logins with an empty (or ".") form action origin match any action.
+    //
+    // Exact matches on the action are returned before wildcard matches, and each group is
+    // ordered with the most recently used login first.
+    pub fn get_by_form_origin_and_action(
+        &self,
+        origin: &str,
+        action: &str,
+    ) -> Result<Vec<EncryptedLogin>> {
+        let origin = match normalize_origin(origin) {
+            Some(origin) => origin,
+            None => {
+                // don't log the input string as it's PII.
+                log::warn!("get_by_form_origin_and_action was passed an invalid origin");
+                return Ok(vec![]);
+            }
+        };
+        let action = if action.is_empty() {
+            Some(origin.clone())
+        } else if action.starts_with("javascript:") {
+            Some("javascript:".to_string())
+        } else {
+            // An action we can't parse can still be filled by wildcard logins.
+            normalize_origin(action)
+        };
+        let mut stmt = self.db.prepare_cached(&GET_BY_FORM_ORIGIN_SQL)?;
+        let mut logins = stmt
+            .query_and_then(
+                named_params! { ":origin": origin },
+                EncryptedLogin::from_row,
+            )?
+            .filter_map(|r| match r {
+                Ok(login) => {
+                    let is_exact = login.fields.form_action_origin == action;
+                    let is_wildcard = matches!(
+                        login.fields.form_action_origin.as_deref(),
+                        Some("") | Some(".")
+                    );
+                    (is_exact || is_wildcard).then_some(Ok((is_exact, login)))
+                }
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        logins.sort_by(|(a_exact, a), (b_exact, b)| {
+            b_exact
+                .cmp(a_exact)
+                .then(b.record.time_last_used.cmp(&a.record.time_last_used))
+        });
+        Ok(logins.into_iter().map(|(_, login)| login).collect())
+    }
+
     pub fn get_by_id(&self, id: &str) -> Result<Option<EncryptedLogin>> {
         self.try_query_row(
             &GET_BY_GUID_SQL,
@@ -356,8 +428,16 @@ impl LoginDb {
     }
 
     pub fn add(&self, entry: LoginEntry, encdec: &EncryptorDecryptor) -> Result<EncryptedLogin> {
+        let tx = self.unchecked_transaction()?;
+        let result = self.add_in_tx(entry, encdec)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    // Like `add()`, for when the caller already has a transaction open.
+    fn add_in_tx(&self, entry: LoginEntry, encdec: &EncryptorDecryptor) -> Result<EncryptedLogin> {
         let guid = Guid::random();
-        let now_ms = util::system_time_ms_i64(SystemTime::now());
+        let now_ms = Timestamp::now();
 
         let new_entry = self.fixup_and_check_for_dupes(&guid, entry, encdec)?;
         let result = EncryptedLogin {
@@ -371,9 +451,7 @@ impl LoginDb {
             fields: new_entry.fields,
             sec_fields: new_entry.sec_fields.encrypt(encdec)?,
         };
-        let tx = self.unchecked_transaction()?;
         self.insert_new_login(&result)?;
-        tx.commit()?;
         Ok(result)
     }
 
@@ -384,9 +462,25 @@ impl LoginDb {
         encdec: &EncryptorDecryptor,
     ) -> Result<EncryptedLogin> {
         let guid = Guid::new(sguid);
-        let now_ms = util::system_time_ms_i64(SystemTime::now());
+        // A malformed guid can't name an existing login.
+        if !guid.is_valid() {
+            return Err(Error::NoSuchRecord(sguid.to_owned()));
+        }
         let tx = self.unchecked_transaction()?;
+        let result = self.update_in_tx(&guid, entry, encdec)?;
+        tx.commit()?;
+        Ok(result)
+    }
 
+    // Like `update()`, for when the caller already has a transaction open.
+    fn update_in_tx(
+        &self,
+        guid: &Guid,
+        entry: LoginEntry,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<EncryptedLogin> {
+        let sguid = guid.as_str();
+        let now_ms = Timestamp::now();
         let entry = entry.fixup()?;
 
         // Check if there's an existing login that's the dupe of this login.  That indicates that
@@ -394,7 +488,7 @@ impl LoginDb {
         // just log an error and continue.  This avoids a crash on android-components
         // (mozilla-mobile/android-components#11251).
 
-        if self.check_for_dupes(&guid, &entry, encdec).is_err() {
+        if self.check_for_dupes(guid, &entry, encdec).is_err() {
             // Try to detect if sync is enabled by checking if there are any mirror logins
             let has_mirror_row: bool =
                 self.db.query_one("SELECT EXISTS (SELECT 1 FROM loginsM)")?;
@@ -406,8 +500,8 @@ impl LoginDb {
         }
 
         // Note: This fail with NoSuchRecord if the record doesn't exist.
-        self.ensure_local_overlay_exists(&guid)?;
-        self.mark_mirror_overridden(&guid)?;
+        self.ensure_local_overlay_exists(guid)?;
+        self.mark_mirror_overridden(guid)?;
 
         // We must read the existing record so we can correctly manage timePasswordChanged.
         let existing = match self.get_by_id(sguid)? {
@@ -435,7 +529,6 @@ impl LoginDb {
         };
 
         self.update_existing_login(&result)?;
-        tx.commit()?;
         Ok(result)
     }
 
@@ -444,12 +537,173 @@ impl LoginDb {
         entry: LoginEntry,
         encdec: &EncryptorDecryptor,
     ) -> Result<EncryptedLogin> {
+        Ok(self.add_or_update_with_change_type(entry, encdec)?.0)
+    }
+
+    // Like `add_or_update()`, but also says which of those it did.
+    pub(crate) fn add_or_update_with_change_type(
+        &self,
+        entry: LoginEntry,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<(EncryptedLogin, LoginChangeType)> {
         // Make sure to fixup the entry first, in case that changes the username
         let entry = entry.fixup()?;
-        match self.find_login_to_update(entry.clone(), encdec)? {
-            Some(login) => self.update(&login.record.id, entry, encdec),
-            None => self.add(entry, encdec),
+        Ok(match self.find_login_to_update(entry.clone(), encdec)? {
+            Some(login) => (
+                self.update(&login.record.id, entry, encdec)?,
+                LoginChangeType::Updated,
+            ),
+            None => (self.add(entry, encdec)?, LoginChangeType::Added),
+        })
+    }
+
+    // Imports logins in a single transaction, using `strategy` for those with the same origin
+    // and username as an existing login. Invalid logins are reported in their outcome rather
+    // than as an error.
+    pub(crate) fn import(
+        &self,
+        entries: Vec<LoginEntry>,
+        strategy: LoginImportStrategy,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<Vec<LoginImportOutcome>> {
+        let tx = self.unchecked_transaction()?;
+        let mut existing = self.ids_by_origin_and_username(encdec)?;
+        let outcomes = entries
+            .into_iter()
+            .map(
+                |entry| match self.import_one(entry, strategy, &mut existing, encdec) {
+                    Err(Error::InvalidLogin(e)) => Ok(LoginImportOutcome::Failed {
+                        reason: e.to_string(),
+                    }),
+                    result => result,
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(outcomes)
+    }
+
+    fn import_one(
+        &self,
+        entry: LoginEntry,
+        strategy: LoginImportStrategy,
+        existing: &mut HashMap<(String, String), Guid>,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<LoginImportOutcome> {
+        let entry = entry.fixup()?;
+        let key = (
+            entry.fields.origin.clone(),
+            entry.sec_fields.username.clone(),
+        );
+        Ok(match (existing.get(&key).cloned(), strategy) {
+            (Some(existing_id), LoginImportStrategy::Skip) => LoginImportOutcome::Skipped {
+                existing_id: existing_id.into_string(),
+            },
+            (Some(existing_id), LoginImportStrategy::Overwrite) => {
+                LoginImportOutcome::Overwritten {
+                    id: self.update_in_tx(&existing_id, entry, encdec)?.record.id,
+                }
+            }
+            (Some(_), LoginImportStrategy::KeepBoth) | (None, _) => {
+                let id = self.add_in_tx(entry, encdec)?.record.id;
+                existing.entry(key).or_insert_with(|| Guid::new(&id));
+                LoginImportOutcome::Added { id }
+            }
+        })
+    }
+
+    // Maps the origin and username of each login, whatever its form action origin or HTTP
+    // realm, to its id. Each login is only decrypted once.
+    fn ids_by_origin_and_username(
+        &self,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<HashMap<(String, String), Guid>> {
+        let mut ids = HashMap::new();
+        for login in self.get_all()? {
+            let username = login.decrypt_fields(encdec)?.username;
+            ids.entry((login.fields.origin, username))
+                .or_insert_with(|| Guid::new(&login.record.id));
         }
+        Ok(ids)
+    }
+
+    // Checks every login, fixing the ones which can be fixed and reporting the ones which
+    // can't, including those we can't decrypt. Repaired logins are marked as changed, so the
+    // next sync uploads them.
+    pub(crate) fn validate_and_repair(&self, encdec: &EncryptorDecryptor) -> Result<RepairReport> {
+        let tx = self.unchecked_transaction()?;
+        let mut report = RepairReport::default();
+        for login in self.get_all()? {
+            report.checked += 1;
+            let id = login.record.id.clone();
+            match self.repair_login(login, encdec) {
+                Ok(false) => (),
+                Ok(true) => report.repaired.push(id),
+                Err(Error::InvalidLogin(e)) => report.unrepairable.push(UnrepairableLogin {
+                    id,
+                    reason: e.to_string(),
+                }),
+                Err(Error::CryptoError(_)) => report.unrepairable.push(UnrepairableLogin {
+                    id,
+                    reason: "Can't be decrypted".into(),
+                }),
+                Err(e) => return Err(e),
+            }
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    // Fixes `login` if it's invalid and can be fixed, returning whether it was changed. A login
+    // whose fixed version would be a duplicate of another login isn't changed.
+    fn repair_login(&self, login: EncryptedLogin, encdec: &EncryptorDecryptor) -> Result<bool> {
+        let login = login.decrypt(encdec)?;
+        let fixed = match login.entry().maybe_fixup()? {
+            Some(fixed) => fixed,
+            None => return Ok(false),
+        };
+        self.check_for_dupes(&login.guid(), &fixed, encdec)?;
+        self.ensure_local_overlay_exists(&login.record.id)?;
+        self.mark_mirror_overridden(&login.record.id)?;
+        self.update_repaired_login(&login.record.id, fixed, encdec)?;
+        Ok(true)
+    }
+
+    // Like `update_existing_login()`, but leaves the usage stats alone, because the user
+    // didn't touch the login.
+    fn update_repaired_login(
+        &self,
+        guid: &str,
+        entry: LoginEntry,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<()> {
+        let sql = format!(
+            "UPDATE loginsL
+             SET local_modified      = :now_millis,
+                 httpRealm           = :http_realm,
+                 formActionOrigin    = :form_action_origin,
+                 usernameField       = :username_field,
+                 passwordField       = :password_field,
+                 secFields           = :sec_fields,
+                 origin              = :origin,
+                 sync_status         = max(sync_status, {changed})
+             WHERE guid = :guid",
+            changed = SyncStatus::Changed as u8
+        );
+        self.db.execute(
+            &sql,
+            named_params! {
+                ":origin": entry.fields.origin,
+                ":http_realm": entry.fields.http_realm,
+                ":form_action_origin": entry.fields.form_action_origin,
+                ":username_field": entry.fields.username_field,
+                ":password_field": entry.fields.password_field,
+                ":sec_fields": entry.sec_fields.encrypt(encdec)?,
+                ":guid": guid,
+                ":now_millis": util::system_time_ms_i64(SystemTime::now()),
+            },
+        )?;
+        Ok(())
     }
 
     pub fn fixup_and_check_for_dupes(
@@ -716,6 +970,15 @@ impl LoginDb {
     }
 }
 
+// Returns the normalized form of an origin, or None if it isn't a valid origin.
+fn normalize_origin(origin: &str) -> Option<String> {
+    match LoginFields::validate_and_fixup_origin(origin) {
+        Ok(Some(fixed)) => Some(fixed),
+        Ok(None) => Some(origin.to_string()),
+        Err(_) => None,
+    }
+}
+
 lazy_static! {
     static ref GET_ALL_SQL: String = format!(
         "SELECT {common_cols} FROM loginsL WHERE is_deleted = 0
@@ -723,6 +986,21 @@ lazy_static! {
          SELECT {common_cols} FROM loginsM WHERE is_overridden = 0",
         common_cols = schema::COMMON_COLS,
     );
+    static ref GET_ANY_SQL: String = format!("{} LIMIT 1", &*GET_ALL_SQL);
+    static ref GET_BY_FORM_ORIGIN_SQL: String = format!(
+        "SELECT {common_cols} FROM loginsL
+         WHERE is_deleted = 0
+           AND origin = :origin
+           AND formActionOrigin IS NOT NULL
+
+         UNION ALL
+
+         SELECT {common_cols} FROM loginsM
+         WHERE is_overridden = 0
+           AND origin = :origin
+           AND formActionOrigin IS NOT NULL",
+        common_cols = schema::COMMON_COLS,
+    );
     static ref GET_BY_GUID_SQL: String = format!(
         "SELECT {common_cols}
          FROM loginsL
@@ -923,6 +1201,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "sekret".into(),
+                totp: None,
             },
         };
 
@@ -969,6 +1248,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "😍".into(),
                         password: "😍".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1006,6 +1286,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "😍".into(),
                         password: "😍".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1128,6 +1409,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_by_form_origin_and_action() {
+        let db = LoginDb::open_in_memory().unwrap();
+        let add = |username: &str, origin: &str, form_action_origin: Option<&str>| {
+            db.add(
+                LoginEntry {
+                    fields: LoginFields {
+                        origin: origin.into(),
+                        form_action_origin: form_action_origin.map(Into::into),
+                        http_realm: match form_action_origin {
+                            Some(_) => None,
+                            None => Some("realm".into()),
+                        },
+                        ..Default::default()
+                    },
+                    sec_fields: SecureLoginFields {
+                        username: username.into(),
+                        password: "password".into(),
+                        totp: None,
+                    },
+                },
+                &TEST_ENCRYPTOR,
+            )
+            .unwrap()
+        };
+        let wildcard = add("wildcard", "https://www.example.com", Some(""));
+        let exact = add(
+            "exact",
+            "https://www.example.com",
+            Some("https://login.example.com"),
+        );
+        let same_origin = add(
+            "same",
+            "https://www.example.com",
+            Some("https://www.example.com"),
+        );
+        add(
+            "other",
+            "https://www.example.com",
+            Some("https://other.example.com"),
+        );
+        add("auth", "https://www.example.com", None);
+        add("elsewhere", "https://example.com", Some(""));
+
+        let ids = |origin: &str, action: &str| {
+            db.get_by_form_origin_and_action(origin, action)
+                .unwrap()
+                .into_iter()
+                .map(|l| l.record.id)
+                .collect::<Vec<_>>()
+        };
+
+        // Exact matches come before wildcards.
+        assert_eq!(
+            ids(
+                "https://www.example.com",
+                "https://login.example.com/submit"
+            ),
+            vec![exact.record.id.clone(), wildcard.record.id.clone()]
+        );
+        // An empty action means the form submits to its own origin.
+        assert_eq!(
+            ids("https://www.example.com", ""),
+            vec![same_origin.record.id.clone(), wildcard.record.id.clone()]
+        );
+        // Only the wildcard login can be filled into a form with an unknown action.
+        assert_eq!(
+            ids("https://www.example.com", "https://unknown.example.com"),
+            vec![wildcard.record.id.clone()]
+        );
+        assert_eq!(
+            ids("https://www.example.com", "javascript:void(0)"),
+            vec![wildcard.record.id.clone()]
+        );
+        assert!(ids("https://unknown.example.com", "").is_empty());
+        assert!(ids("invalid origin", "").is_empty());
+
+        // Within each group, the most recently used login comes first.
+        let wildcard2 = add("wildcard2", "https://www.example.com", Some("."));
+        let set_last_used = |id: &str, time: i64| {
+            db.execute(
+                "UPDATE loginsL SET timeLastUsed = :time WHERE guid = :guid",
+                named_params! { ":time": time, ":guid": id },
+            )
+            .unwrap();
+        };
+        set_last_used(&wildcard.record.id, 2000);
+        set_last_used(&wildcard2.record.id, 1000);
+        assert_eq!(
+            ids("https://www.example.com", "https://unknown.example.com"),
+            vec![wildcard.record.id.clone(), wildcard2.record.id.clone()]
+        );
+        set_last_used(&wildcard2.record.id, 3000);
+        assert_eq!(
+            ids("https://www.example.com", "https://unknown.example.com"),
+            vec![wildcard2.record.id, wildcard.record.id]
+        );
+    }
+
     #[test]
     fn test_add() {
         let db = LoginDb::open_in_memory().unwrap();
@@ -1140,6 +1520,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test_user".into(),
                 password: "test_password".into(),
+                totp: None,
             },
         };
         let login = db.add(to_add, &TEST_ENCRYPTOR).unwrap();
@@ -1164,6 +1545,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "user1".into(),
                         password: "password1".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1180,6 +1562,7 @@ mod tests {
                 sec_fields: SecureLoginFields {
                     username: "user2".into(),
                     password: "password2".into(),
+                    totp: None,
                 },
             },
             &TEST_ENCRYPTOR,
@@ -1198,6 +1581,32 @@ mod tests {
         assert_eq!(sec_fields.password, "password2");
     }
 
+    #[test]
+    fn test_update_invalid_guid() {
+        let db = LoginDb::open_in_memory().unwrap();
+        let entry = LoginEntry {
+            fields: LoginFields {
+                origin: "https://www.example.com".into(),
+                http_realm: Some("https://www.example.com".into()),
+                ..Default::default()
+            },
+            sec_fields: SecureLoginFields {
+                username: "user1".into(),
+                password: "password1".into(),
+                totp: None,
+            },
+        };
+        for guid in ["", "login,guid", "login\u{e9}guid", &"l".repeat(65)] {
+            let err = db
+                .update(guid, entry.clone(), &TEST_ENCRYPTOR)
+                .expect_err("should fail to update");
+            assert!(
+                matches!(err, Error::NoSuchRecord(ref g) if g == guid),
+                "unexpected error updating {guid:?}: {err:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_touch() {
         let db = LoginDb::open_in_memory().unwrap();
@@ -1212,6 +1621,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "user1".into(),
                         password: "password1".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1237,6 +1647,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "test_user".into(),
                         password: "test_password".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1273,6 +1684,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "test_user_1".into(),
                         password: "test_password_1".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1290,6 +1702,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "test_user_1".into(),
                         password: "test_password_2".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1319,6 +1732,88 @@ mod tests {
         assert!(!db.exists(login2.guid_str()).unwrap());
     }
 
+    #[test]
+    fn test_validate_and_repair() {
+        use crate::login::test_utils::enc_login;
+        use sync15::ServerTimestamp;
+
+        let db = LoginDb::open_in_memory().unwrap();
+        let mut unnormalized = enc_login("unnormalized", "password");
+        unnormalized.fields.origin = "https://UNNORMALIZED.example.com/login".into();
+        let server_modified = ServerTimestamp(util::system_time_ms_i64(SystemTime::now()));
+        test_utils::add_mirror(&db, &unnormalized, &server_modified, false).unwrap();
+        db.insert_new_login(&enc_login("valid", "password"))
+            .unwrap();
+        db.insert_new_login(&enc_login("empty", "")).unwrap();
+        // Fixing this one would make it a duplicate of "valid".
+        let mut dupe = enc_login("dupe", "password");
+        dupe.fields.origin = "https://VALID.example.com/login".into();
+        dupe.fields.form_action_origin = Some("https://valid.example.com".into());
+        db.insert_new_login(&dupe).unwrap();
+        let mut undecryptable = enc_login("undecryptable", "password");
+        undecryptable.sec_fields =
+            EncryptorDecryptor::new(&crate::encryption::create_key().unwrap())
+                .unwrap()
+                .encrypt_struct(
+                    &SecureLoginFields {
+                        username: "user".into(),
+                        password: "password".into(),
+                        totp: None,
+                    },
+                    "test",
+                )
+                .unwrap();
+        db.insert_new_login(&undecryptable).unwrap();
+
+        let report = db.validate_and_repair(&TEST_ENCRYPTOR).unwrap();
+        assert_eq!(report.checked, 5);
+        assert_eq!(report.repaired, vec!["unnormalized".to_string()]);
+        let mut unrepairable = report.unrepairable;
+        unrepairable.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(
+            unrepairable,
+            vec![
+                UnrepairableLogin {
+                    id: "dupe".into(),
+                    reason: "Login already exists".into(),
+                },
+                UnrepairableLogin {
+                    id: "empty".into(),
+                    reason: "Password is empty".into(),
+                },
+                UnrepairableLogin {
+                    id: "undecryptable".into(),
+                    reason: "Can't be decrypted".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            db.get_by_id("dupe").unwrap().unwrap().fields.origin,
+            "https://VALID.example.com/login"
+        );
+
+        // The repaired login is now a local change, which overrides the mirror.
+        let repaired = db.get_by_id("unnormalized").unwrap().unwrap();
+        assert_eq!(repaired.fields.origin, "https://unnormalized.example.com");
+        let (sync_status, is_overridden): (u8, bool) = db
+            .query_row(
+                "SELECT l.sync_status, m.is_overridden
+                 FROM loginsL l JOIN loginsM m ON l.guid = m.guid
+                 WHERE l.guid = 'unnormalized'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(sync_status, SyncStatus::Changed as u8);
+        assert!(is_overridden);
+        // The unrepairable login is left alone.
+        assert!(db.exists("empty").unwrap());
+
+        let report = db.validate_and_repair(&TEST_ENCRYPTOR).unwrap();
+        assert!(report.repaired.is_empty());
+        assert_eq!(report.unrepairable.len(), 3);
+    }
+
     mod test_find_login_to_update {
         use super::*;
 
@@ -1332,6 +1827,7 @@ mod tests {
                 sec_fields: SecureLoginFields {
                     username: username.into(),
                     password: password.into(),
+                    totp: None,
                 },
             }
         }
@@ -1370,6 +1866,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "user".into(),
                         password: "pass".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -1386,6 +1883,7 @@ mod tests {
                     sec_fields: SecureLoginFields {
                         username: "user".into(),
                         password: "pass".into(),
+                        totp: None,
                     },
                 },
                 &TEST_ENCRYPTOR,
@@ -24,6 +24,15 @@ pub enum LoginsApiError {
     #[error("Encryption key is in the correct format, but is not the correct key.")]
     IncorrectKey,
 
+    #[error("No encryption key is available.")]
+    MissingKey,
+
+    #[error("The store is locked.")]
+    Locked,
+
+    #[error("Invalid password policy: {reason}")]
+    InvalidPasswordPolicy { reason: String },
+
     #[error("{reason}")]
     Interrupted { reason: String },
 
@@ -58,6 +67,9 @@ pub enum Error {
     #[error("local encryption key not set")]
     EncryptionKeyMissing,
 
+    #[error("The store is locked")]
+    Locked,
+
     #[error("Error synchronizing: {0}")]
     SyncAdapterError(#[from] sync15::Error),
 
@@ -87,6 +99,12 @@ pub enum Error {
 
     #[error("Migration Error: {0}")]
     MigrationError(String),
+
+    #[error("Invalid password policy: {0}")]
+    InvalidPasswordPolicy(String),
+
+    #[error("Crypto error: {0}")]
+    RcCryptoError(#[from] rc_crypto::Error),
 }
 
 /// Error::InvalidLogin subtypes
@@ -141,6 +159,20 @@ impl GetErrorHandling for Error {
             }
             Self::CryptoError { .. } => ErrorHandling::convert(LoginsApiError::IncorrectKey)
                 .report_error("logins-crypto-error"),
+            // The key manager couldn't provide the key (eg, the user cancelled a biometric
+            // prompt), or the sync manager wasn't given one.  This is expected, so isn't reported.
+            Self::EncryptionKeyMissing => {
+                ErrorHandling::convert(LoginsApiError::MissingKey).log_warning()
+            }
+            // The app locked the store, and hasn't unlocked it yet.
+            Self::Locked => ErrorHandling::convert(LoginsApiError::Locked),
+            // A bug in the caller, so there's no need to report it.
+            Self::InvalidPasswordPolicy(reason) => {
+                ErrorHandling::convert(LoginsApiError::InvalidPasswordPolicy {
+                    reason: reason.clone(),
+                })
+                .log_warning()
+            }
             Self::Interrupted(_) => ErrorHandling::convert(LoginsApiError::Interrupted {
                 reason: self.to_string(),
             }),
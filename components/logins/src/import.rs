@@ -0,0 +1,49 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Importing logins in bulk, for example when migrating from another browser.
+
+use crate::observer::{LoginChange, LoginChangeType};
+
+/// What to do when an imported login has the same origin and username as an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginImportStrategy {
+    /// Leave the existing login alone, and don't import this one.
+    Skip,
+    /// Replace the fields of the existing login with the imported ones.
+    Overwrite,
+    /// Add the imported login as well. This still fails if the existing login also has the
+    /// same form action origin or HTTP realm, because that would be a duplicate.
+    KeepBoth,
+}
+
+/// What happened to a single imported login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginImportOutcome {
+    Added {
+        id: String,
+    },
+    Overwritten {
+        id: String,
+    },
+    Skipped {
+        existing_id: String,
+    },
+    /// The login is invalid, or is a duplicate of an existing login.
+    Failed {
+        reason: String,
+    },
+}
+
+impl LoginImportOutcome {
+    pub(crate) fn change(&self) -> Option<LoginChange> {
+        match self {
+            Self::Added { id } => Some(LoginChange::new(id.as_str(), LoginChangeType::Added)),
+            Self::Overwritten { id } => {
+                Some(LoginChange::new(id.as_str(), LoginChangeType::Updated))
+            }
+            Self::Skipped { .. } | Self::Failed { .. } => None,
+        }
+    }
+}
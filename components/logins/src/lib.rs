@@ -7,14 +7,19 @@
 
 #[macro_use]
 mod error;
+mod import;
 mod login;
+mod password_generator;
+mod repair;
 
 mod db;
 pub mod encryption;
 pub mod migrate_sqlcipher_db;
+mod observer;
 mod schema;
 mod store;
 mod sync;
+mod totp;
 mod util;
 
 uniffi::include_scaffolding!("logins");
@@ -22,10 +27,40 @@ uniffi::include_scaffolding!("logins");
 pub use crate::db::LoginDb;
 use crate::encryption::{check_canary, create_canary, create_key};
 pub use crate::error::*;
+pub use crate::import::{LoginImportOutcome, LoginImportStrategy};
 pub use crate::login::*;
 pub use crate::migrate_sqlcipher_db::migrate_logins;
+pub use crate::observer::{LoginChange, LoginChangeType, LoginsChangeObserver};
+pub use crate::password_generator::{generate_password, password_satisfies_policy, PasswordPolicy};
+pub use crate::repair::{RepairReport, UnrepairableLogin};
 pub use crate::store::*;
 pub use crate::sync::LoginsSyncEngine;
+pub use crate::totp::{TotpAlgorithm, TotpConfig};
+pub use jwcrypto::{KeyManager as LoginsKeyManager, StaticKeyManager};
+pub use sql_support::open_database::MigrationObserver as LoginsMigrationObserver;
+pub use types::Timestamp as LoginsTimestamp;
+
+impl UniffiCustomTypeConverter for LoginsTimestamp {
+    type Builtin = i64;
+
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(val.try_into()?)
+    }
+
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.into()
+    }
+}
+
+/// Register an observer to be told about schema upgrades while the database is opened. This
+/// must be called before creating the `LoginStore` to see the upgrade of an existing database.
+pub fn set_logins_migration_observer(observer: Box<dyn LoginsMigrationObserver>) {
+    schema::MIGRATION_OBSERVER.set(observer);
+}
+
+pub fn unset_logins_migration_observer() {
+    schema::MIGRATION_OBSERVER.unset();
+}
 
 // Public encryption functions.  We publish these as top-level functions to expose them across
 // UniFFI
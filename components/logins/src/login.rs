@@ -74,6 +74,7 @@
 //! The struct used to hold the fields which are stored encrypted. It contains:
 //! - username: A string.
 //! - password: A string.
+//! - totp: An optional [`TotpConfig`], for sites which use 2FA.
 //!
 //! # LoginFields
 //!
@@ -278,10 +279,11 @@
 //! - `Login::fixup()`:   Returns either the existing login if it is valid, a clone with invalid fields
 //!                       fixed up if it was safe to do so, or an error if the login is irreparably invalid.
 
-use crate::{encryption::EncryptorDecryptor, error::*};
+use crate::{encryption::EncryptorDecryptor, error::*, totp::TotpConfig};
 use rusqlite::Row;
 use serde_derive::*;
 use sync_guid::Guid;
+use types::Timestamp;
 use url::Url;
 
 // LoginEntry fields that are stored in cleartext
@@ -297,7 +299,7 @@ pub struct LoginFields {
 impl LoginFields {
     /// Internal helper for validation and fixups of an "origin" stored as
     /// a string.
-    fn validate_and_fixup_origin(origin: &str) -> Result<Option<String>> {
+    pub(crate) fn validate_and_fixup_origin(origin: &str) -> Result<Option<String>> {
         // Check we can parse the origin, then use the normalized version of it.
         match Url::parse(origin) {
             Ok(mut u) => {
@@ -363,6 +365,8 @@ pub struct SecureLoginFields {
     pub username: String,
     #[serde(rename = "p")]
     pub password: String,
+    #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<TotpConfig>,
 }
 
 impl SecureLoginFields {
@@ -379,9 +383,9 @@ impl SecureLoginFields {
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
 pub struct RecordFields {
     pub id: String,
-    pub time_created: i64,
-    pub time_password_changed: i64,
-    pub time_last_used: i64,
+    pub time_created: Timestamp,
+    pub time_password_changed: Timestamp,
+    pub time_last_used: Timestamp,
     pub times_used: i64,
 }
 
@@ -461,7 +465,7 @@ impl EncryptedLogin {
                 time_created: row.get("timeCreated")?,
                 // Might be null
                 time_last_used: row
-                    .get::<_, Option<i64>>("timeLastUsed")?
+                    .get::<_, Option<Timestamp>>("timeLastUsed")?
                     .unwrap_or_default(),
 
                 time_password_changed: row.get("timePasswordChanged")?,
@@ -668,8 +672,8 @@ impl ValidateAndFixup for LoginFields {
 }
 
 impl ValidateAndFixup for SecureLoginFields {
-    /// We don't actually have fixups.
-    fn validate_and_fixup(&self, _fixup: bool) -> Result<Option<Self>> {
+    /// The only fixup is normalizing the TOTP secret.
+    fn validate_and_fixup(&self, fixup: bool) -> Result<Option<Self>> {
         // \r\n chars are valid in desktop for some reason, so we allow them here too.
         if self.username.contains('\0') {
             return Err(InvalidLogin::IllegalFieldValue {
@@ -686,7 +690,13 @@ impl ValidateAndFixup for SecureLoginFields {
             }
             .into());
         }
-        Ok(None)
+        Ok(match &self.totp {
+            Some(totp) => totp.validate_and_fixup(fixup)?.map(|totp| Self {
+                totp: Some(totp),
+                ..self.clone()
+            }),
+            None => None,
+        })
     }
 }
 
@@ -721,6 +731,7 @@ pub mod test_utils {
         let sec_fields = SecureLoginFields {
             username: "user".to_string(),
             password: password.to_string(),
+            totp: None,
         };
         EncryptedLogin {
             record: RecordFields {
@@ -811,6 +822,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -823,6 +835,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -835,6 +848,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "".into(),
+                totp: None,
             },
         };
 
@@ -848,6 +862,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -859,6 +874,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -871,6 +887,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -883,6 +900,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -895,6 +913,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "\0".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -907,6 +926,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "username".into(),
                 password: "test\0".into(),
+                totp: None,
             },
         };
 
@@ -919,6 +939,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -932,6 +953,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -944,6 +966,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -956,6 +979,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test\n".into(),
+                totp: None,
             },
         };
 
@@ -969,6 +993,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -981,6 +1006,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -993,6 +1019,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1005,6 +1032,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1017,6 +1045,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1029,6 +1058,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1041,6 +1071,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1053,6 +1084,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1209,6 +1241,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1221,6 +1254,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1233,6 +1267,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
         let login_with_empty_fsu = LoginEntry {
@@ -1244,6 +1279,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1261,6 +1297,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
 
@@ -1324,6 +1361,7 @@ mod tests {
         let sf = SecureLoginFields {
             username: "foo".into(),
             password: "pwd".into(),
+            totp: None,
         };
         assert_eq!(
             serde_json::to_string(&sf).unwrap(),
@@ -1333,7 +1371,57 @@ mod tests {
         let expected = SecureLoginFields {
             username: "user".into(),
             password: "p".into(),
+            totp: None,
         };
         assert_eq!(got, expected);
+        error_support::testing::assert_json_round_trip::<SecureLoginFields>(
+            serde_json::json!({"u": "user", "p": "p"}),
+        );
+    }
+
+    #[test]
+    fn test_ffi_round_trip() {
+        use error_support::testing::assert_ffi_round_trip;
+
+        let fields = |http_realm: Option<&str>, form_action_origin: Option<&str>| LoginFields {
+            origin: "https://www.example.com".into(),
+            http_realm: http_realm.map(Into::into),
+            form_action_origin: form_action_origin.map(Into::into),
+            username_field: "username".into(),
+            password_field: "password".into(),
+        };
+        let sec_fields = SecureLoginFields {
+            username: "user".into(),
+            password: "p".into(),
+            totp: None,
+        };
+        let record = RecordFields {
+            id: "aaaaaaaaaaaa".into(),
+            times_used: 1,
+            time_created: Timestamp(1_690_000_000_000),
+            time_last_used: Timestamp(1_690_000_000_001),
+            time_password_changed: Timestamp(0),
+        };
+
+        for fields in [
+            fields(Some("realm"), None),
+            fields(None, Some("https://www.example.com")),
+            fields(None, None),
+        ] {
+            assert_ffi_round_trip::<crate::UniFfiTag, _>(LoginEntry {
+                fields: fields.clone(),
+                sec_fields: sec_fields.clone(),
+            });
+            assert_ffi_round_trip::<crate::UniFfiTag, _>(Login {
+                record: record.clone(),
+                fields: fields.clone(),
+                sec_fields: sec_fields.clone(),
+            });
+            assert_ffi_round_trip::<crate::UniFfiTag, _>(EncryptedLogin {
+                record: record.clone(),
+                fields,
+                sec_fields: "ciphertext".into(),
+            });
+        }
     }
 }
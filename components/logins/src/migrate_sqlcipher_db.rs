@@ -19,6 +19,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::time::SystemTime;
 use sync15::ServerTimestamp;
+use types::Timestamp;
 
 #[derive(Debug)]
 struct MigrationPlan {
@@ -615,9 +616,14 @@ fn get_login_from_row(row: &Row<'_>) -> Result<Login> {
     let form_action_origin: Option<String> = row.get("formSubmitURL").unwrap_or_default();
     let username_field: String = row.get("usernameField").unwrap_or_default();
     let password_field: String = row.get("passwordField").unwrap_or_default();
-    let time_created: i64 = row.get("timeCreated").unwrap_or_default();
-    let time_last_used: i64 = row.get("timeLastUsed").unwrap_or_default();
-    let time_password_changed: i64 = row.get("timePasswordChanged").unwrap_or_default();
+    // Negative timestamps can't be valid, so are treated the same as missing ones.
+    let timestamp = |col: &str| -> Timestamp {
+        let ms: i64 = row.get(col).unwrap_or_default();
+        Timestamp::try_from(ms).unwrap_or_default()
+    };
+    let time_created = timestamp("timeCreated");
+    let time_last_used = timestamp("timeLastUsed");
+    let time_password_changed = timestamp("timePasswordChanged");
     let times_used: i64 = row.get("timesUsed").unwrap_or_default();
 
     let login = Login {
@@ -635,7 +641,11 @@ fn get_login_from_row(row: &Row<'_>) -> Result<Login> {
             username_field,
             password_field,
         },
-        sec_fields: SecureLoginFields { username, password },
+        sec_fields: SecureLoginFields {
+            username,
+            password,
+            totp: None,
+        },
     };
     Ok(login)
 }
@@ -1128,6 +1138,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
         let valid_login_guid2: Guid = Guid::random();
@@ -1145,6 +1156,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test2".into(),
                 password: "test2".into(),
+                totp: None,
             },
         };
         let valid_login_guid3: Guid = Guid::random();
@@ -1162,6 +1174,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test3".into(),
                 password: "test3".into(),
+                totp: None,
             },
         };
         // local login + mirror login with override
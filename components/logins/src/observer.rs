@@ -0,0 +1,83 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Notifying the app about changes to the logins in a `LoginStore`, whether they were made by
+// the app itself or applied by a sync.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum LoginChangeType {
+    Added,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct LoginChange {
+    pub id: String,
+    pub change_type: LoginChangeType,
+}
+
+impl LoginChange {
+    pub(crate) fn new(id: impl Into<String>, change_type: LoginChangeType) -> Self {
+        Self {
+            id: id.into(),
+            change_type,
+        }
+    }
+}
+
+/// Told about the logins which changed, once for each write to the store and once for each
+/// sync which changed anything.
+pub trait LoginsChangeObserver: Send + Sync {
+    fn on_logins_changed(&self, changes: Vec<LoginChange>);
+}
+
+#[derive(Default)]
+pub(crate) struct ChangeObserverRegistry(Mutex<Option<Arc<dyn LoginsChangeObserver>>>);
+
+impl ChangeObserverRegistry {
+    pub(crate) fn set(&self, observer: Box<dyn LoginsChangeObserver>) {
+        *self.0.lock() = Some(observer.into());
+    }
+
+    pub(crate) fn unset(&self) {
+        *self.0.lock() = None;
+    }
+
+    // Callers must not be holding the database lock, so that the observer can use the store.
+    // We don't hold our own lock while notifying either, so the observer can unset itself.
+    pub(crate) fn notify(&self, changes: Vec<LoginChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        let observer = self.0.lock().clone();
+        if let Some(observer) = observer {
+            observer.on_logins_changed(changes);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+
+    // Remembers each batch of changes it was told about.
+    #[derive(Clone, Default)]
+    pub struct RecordingObserver(Arc<Mutex<Vec<Vec<LoginChange>>>>);
+
+    impl RecordingObserver {
+        pub fn take(&self) -> Vec<Vec<LoginChange>> {
+            std::mem::take(&mut *self.0.lock())
+        }
+    }
+
+    impl LoginsChangeObserver for RecordingObserver {
+        fn on_logins_changed(&self, changes: Vec<LoginChange>) {
+            self.0.lock().push(changes);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Generates passwords for the save-password flow, so that every platform
+// offers passwords with the same entropy.
+//
+// The character classes match desktop's `PasswordGenerator.sys.mjs`, which
+// leaves out characters which are easily confused with each other, such as
+// `l`, `1` and `I`.
+
+use crate::error::*;
+
+const LOWERCASE: &[u8] = b"abcdefghijkmnpqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"23456789";
+const SYMBOLS: &[u8] = b"-~!@#$%^&*_+=)}:;\"'>,.?]";
+
+pub const DEFAULT_PASSWORD_LENGTH: u32 = 15;
+pub const MAX_PASSWORD_LENGTH: u32 = 128;
+
+/// Which characters a generated password is made of. Every enabled class
+/// appears in the password at least once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub length: u32,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        // The same defaults as desktop, which doesn't use symbols unless a site
+        // requires them.
+        Self {
+            length: DEFAULT_PASSWORD_LENGTH,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    fn classes(&self) -> Vec<&'static [u8]> {
+        [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, class)| enabled.then_some(class))
+        .collect()
+    }
+
+    fn validate(&self) -> Result<Vec<&'static [u8]>> {
+        let classes = self.classes();
+        if classes.is_empty() {
+            return Err(Error::InvalidPasswordPolicy(
+                "at least one character class must be enabled".into(),
+            ));
+        }
+        if (self.length as usize) < classes.len() || self.length > MAX_PASSWORD_LENGTH {
+            return Err(Error::InvalidPasswordPolicy(format!(
+                "length must be between {} and {}",
+                classes.len(),
+                MAX_PASSWORD_LENGTH
+            )));
+        }
+        Ok(classes)
+    }
+}
+
+/// Generate a random password which satisfies `policy`, using the CSPRNG
+/// provided by NSS.
+#[handle_error(Error)]
+pub fn generate_password(policy: PasswordPolicy) -> ApiResult<String> {
+    let classes = policy.validate()?;
+    let all_chars = classes.concat();
+    let mut password = Vec::with_capacity(policy.length as usize);
+    // One character from each class, so they're all covered...
+    for class in &classes {
+        password.push(class[random_index(class.len())?]);
+    }
+    // ...then the rest from any of them...
+    while password.len() < policy.length as usize {
+        password.push(all_chars[random_index(all_chars.len())?]);
+    }
+    // ...and shuffle, so the required characters aren't always at the start.
+    for i in (1..password.len()).rev() {
+        password.swap(i, random_index(i + 1)?);
+    }
+    // All our character classes are ASCII.
+    Ok(String::from_utf8(password).expect("generated password is ASCII"))
+}
+
+/// Check that `password` is exactly as long as `policy` requires, contains at
+/// least one character from each enabled class, and nothing else.
+pub fn password_satisfies_policy(password: &str, policy: &PasswordPolicy) -> bool {
+    let classes = match policy.validate() {
+        Ok(classes) => classes,
+        Err(_) => return false,
+    };
+    password.chars().count() == policy.length as usize
+        && password
+            .bytes()
+            .all(|b| classes.iter().any(|c| c.contains(&b)))
+        && classes
+            .iter()
+            .all(|c| password.bytes().any(|b| c.contains(&b)))
+}
+
+// A uniformly distributed index in `0..len`, which must be at most 256. We
+// reject the bytes which would bias the result towards the lower indexes.
+fn random_index(len: usize) -> Result<usize> {
+    debug_assert!(len > 0 && len <= 256);
+    let limit = 256 - (256 % len);
+    loop {
+        let mut byte = [0u8; 1];
+        rc_crypto::rand::fill(&mut byte)?;
+        if (byte[0] as usize) < limit {
+            return Ok(byte[0] as usize % len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(
+        length: u32,
+        lowercase: bool,
+        uppercase: bool,
+        digits: bool,
+        symbols: bool,
+    ) -> PasswordPolicy {
+        PasswordPolicy {
+            length,
+            lowercase,
+            uppercase,
+            digits,
+            symbols,
+        }
+    }
+
+    #[test]
+    fn test_generate_default() {
+        let policy = PasswordPolicy::default();
+        for _ in 0..100 {
+            let password = generate_password(policy.clone()).unwrap();
+            assert_eq!(password.len(), 15);
+            assert!(password.bytes().any(|b| LOWERCASE.contains(&b)));
+            assert!(password.bytes().any(|b| UPPERCASE.contains(&b)));
+            assert!(password.bytes().any(|b| DIGITS.contains(&b)));
+            assert!(!password.bytes().any(|b| SYMBOLS.contains(&b)));
+            assert!(password_satisfies_policy(&password, &policy));
+        }
+    }
+
+    #[test]
+    fn test_generate_covers_classes() {
+        let policies = [
+            policy(4, true, true, true, true),
+            policy(1, false, false, false, true),
+            policy(2, false, true, true, false),
+            policy(MAX_PASSWORD_LENGTH, true, false, false, true),
+        ];
+        for policy in &policies {
+            for _ in 0..100 {
+                let password = generate_password(policy.clone()).unwrap();
+                assert!(
+                    password_satisfies_policy(&password, policy),
+                    "{password} doesn't satisfy {policy:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_is_random() {
+        let policy = PasswordPolicy::default();
+        let a = generate_password(policy.clone()).unwrap();
+        let b = generate_password(policy).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        for policy in [
+            policy(15, false, false, false, false),
+            policy(3, true, true, true, true),
+            policy(0, true, false, false, false),
+            policy(MAX_PASSWORD_LENGTH + 1, true, false, false, false),
+        ] {
+            assert!(matches!(
+                generate_password(policy.clone()),
+                Err(LoginsApiError::InvalidPasswordPolicy { .. })
+            ));
+            assert!(!password_satisfies_policy("aB3$", &policy));
+        }
+    }
+
+    #[test]
+    fn test_satisfies_policy() {
+        let policy = policy(4, true, true, true, false);
+        assert!(password_satisfies_policy("aB3c", &policy));
+        // Too short or too long.
+        assert!(!password_satisfies_policy("aB3", &policy));
+        assert!(!password_satisfies_policy("aB3cd", &policy));
+        // Missing a class.
+        assert!(!password_satisfies_policy("aBcd", &policy));
+        // Characters from a disabled class, or from no class at all.
+        assert!(!password_satisfies_policy("aB3$", &policy));
+        assert!(!password_satisfies_policy("aB3é", &policy));
+        // Confusable characters are never generated.
+        assert!(!password_satisfies_policy("aB3l", &policy));
+    }
+}
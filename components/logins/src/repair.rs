@@ -0,0 +1,38 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Checking every stored login with the same validation we use for new logins, and fixing the
+// ones we can. Older versions, and other clients via sync, may have stored logins which are
+// now considered invalid.
+
+use crate::observer::{LoginChange, LoginChangeType};
+
+/// A login which is invalid, and which couldn't be repaired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnrepairableLogin {
+    pub id: String,
+    pub reason: String,
+}
+
+/// What `LoginStore::validate_and_repair()` found and changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of logins which were checked.
+    pub checked: u32,
+    /// The ids of the logins which were repaired. They're uploaded by the next sync.
+    pub repaired: Vec<String>,
+    /// Logins which are invalid, but couldn't be repaired, including ones which can't be
+    /// decrypted and ones which would be duplicates of another login once repaired. They're
+    /// left alone, so the user can fix or delete them.
+    pub unrepairable: Vec<UnrepairableLogin>,
+}
+
+impl RepairReport {
+    pub(crate) fn changes(&self) -> Vec<LoginChange> {
+        self.repaired
+            .iter()
+            .map(|id| LoginChange::new(id.as_str(), LoginChangeType::Updated))
+            .collect()
+    }
+}
@@ -91,7 +91,7 @@
 use crate::error::*;
 use lazy_static::lazy_static;
 use rusqlite::Connection;
-use sql_support::ConnExt;
+use sql_support::{open_database::MigrationObserverRegistry, ConnExt};
 
 /// Version 1: SQLCipher -> plaintext migration.
 /// Version 2: addition of `loginsM.enc_unknown_fields`.
@@ -195,6 +195,9 @@ pub(crate) static GLOBAL_STATE_META_KEY: &str = "global_state_v2";
 pub(crate) static GLOBAL_SYNCID_META_KEY: &str = "global_sync_id";
 pub(crate) static COLLECTION_SYNCID_META_KEY: &str = "passwords_sync_id";
 
+// The observer the application registered with `set_logins_migration_observer()`, if any.
+pub(crate) static MIGRATION_OBSERVER: MigrationObserverRegistry = MigrationObserverRegistry::new();
+
 pub(crate) fn init(db: &Connection) -> Result<()> {
     let user_version = db.query_one::<i64>("PRAGMA user_version")?;
     log::warn!("user_version: {}", user_version);
@@ -228,6 +231,13 @@ fn upgrade(db: &Connection, from: i64) -> Result<()> {
         "Upgrading from user_version = 0 should already be handled (in `init`)"
     );
 
+    // Unlike the other stores, we don't commit each version, so we can only
+    // report the start and the end of the upgrade.
+    let observer = MIGRATION_OBSERVER.get();
+    if let Some(observer) = &observer {
+        observer.on_migration_progress(from as u32, VERSION as u32, from as u32);
+    }
+
     // Schema upgrades.
     if from == 1 {
         // Just one new nullable column makes this fairly easy
@@ -237,6 +247,9 @@ fn upgrade(db: &Connection, from: i64) -> Result<()> {
     // from = 2;
     // if from == 2 ...
     db.execute_batch(&SET_VERSION_SQL)?;
+    if let Some(observer) = &observer {
+        observer.on_migration_progress(from as u32, VERSION as u32, VERSION as u32);
+    }
     Ok(())
 }
 
@@ -4,9 +4,15 @@
 use crate::db::LoginDb;
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
+use crate::import::{LoginImportOutcome, LoginImportStrategy};
 use crate::login::{EncryptedLogin, Login, LoginEntry};
+use crate::observer::{ChangeObserverRegistry, LoginChange, LoginChangeType, LoginsChangeObserver};
+use crate::repair::RepairReport;
 use crate::LoginsSyncEngine;
+use jwcrypto::KeyManager;
 use parking_lot::Mutex;
+use rc_crypto::{digest, hmac};
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
 use std::sync::{Arc, Weak};
 use sync15::engine::{EngineSyncAssociation, SyncEngine, SyncEngineId};
@@ -46,25 +52,194 @@ fn create_sync_engine(
     }
 }
 
+// Whether the app has locked the store behind a primary password.
+enum LockState {
+    // The store was never locked, or was unlocked with `key`.
+    Unlocked { key: Option<String> },
+    Locked,
+}
+
+impl Default for LockState {
+    fn default() -> Self {
+        Self::Unlocked { key: None }
+    }
+}
+
 pub struct LoginStore {
     pub db: Mutex<LoginDb>,
+    // Used by the methods which don't take an encryption key, and by the sync engine when the
+    // sync manager wasn't given a key.
+    key_manager: Option<Arc<dyn KeyManager>>,
+    lock_state: Mutex<LockState>,
+    change_observer: ChangeObserverRegistry,
 }
 
 impl LoginStore {
     #[handle_error(Error)]
     pub fn new(path: impl AsRef<Path>) -> ApiResult<Self> {
         let db = Mutex::new(LoginDb::open(path)?);
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            key_manager: None,
+            lock_state: Default::default(),
+            change_observer: Default::default(),
+        })
+    }
+
+    #[handle_error(Error)]
+    pub fn new_with_key_manager(
+        path: impl AsRef<Path>,
+        key_manager: Box<dyn KeyManager>,
+    ) -> ApiResult<Self> {
+        let db = Mutex::new(LoginDb::open(path)?);
+        Ok(Self {
+            db,
+            key_manager: Some(key_manager.into()),
+            lock_state: Default::default(),
+            change_observer: Default::default(),
+        })
     }
 
     pub fn new_from_db(db: LoginDb) -> Self {
-        Self { db: Mutex::new(db) }
+        Self {
+            db: Mutex::new(db),
+            key_manager: None,
+            lock_state: Default::default(),
+            change_observer: Default::default(),
+        }
     }
 
     #[handle_error(Error)]
     pub fn new_in_memory() -> ApiResult<Self> {
         let db = Mutex::new(LoginDb::open_in_memory()?);
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            key_manager: None,
+            lock_state: Default::default(),
+            change_observer: Default::default(),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn new_in_memory_with_key_manager(key_manager: Box<dyn KeyManager>) -> Result<Self> {
+        let db = Mutex::new(LoginDb::open_in_memory()?);
+        Ok(Self {
+            db,
+            key_manager: Some(key_manager.into()),
+            lock_state: Default::default(),
+            change_observer: Default::default(),
+        })
+    }
+
+    // Fetch the key from our key manager, if we have one and it can currently provide the key,
+    // or else the key we were unlocked with. There's no key while we're locked.
+    pub(crate) fn key_from_key_manager(&self) -> Option<String> {
+        let unlocked_key = match &*self.lock_state.lock() {
+            LockState::Unlocked { key } => key.clone(),
+            LockState::Locked => return None,
+        };
+        self.key_manager
+            .as_ref()
+            .and_then(|key_manager| key_manager.get_key())
+            .or(unlocked_key)
+    }
+
+    fn encdec_from_key_manager(&self) -> Result<EncryptorDecryptor> {
+        self.ensure_unlocked()?;
+        let key = self
+            .key_from_key_manager()
+            .ok_or(Error::EncryptionKeyMissing)?;
+        EncryptorDecryptor::new(&key)
+    }
+
+    // Operations which read or write the secure fields fail while we're locked. The other
+    // fields are still available.
+    fn ensure_unlocked(&self) -> Result<()> {
+        match *self.lock_state.lock() {
+            LockState::Unlocked { .. } => Ok(()),
+            LockState::Locked => Err(Error::Locked),
+        }
+    }
+
+    /// Lock the store behind a primary password. Until `unlock()` is called, anything which
+    /// needs the secure fields fails with `LoginsApiError::Locked`, and the key we were unlocked
+    /// with is forgotten.
+    pub fn lock(&self) {
+        *self.lock_state.lock() = LockState::Locked;
+    }
+
+    /// Unlock the store with `key`, which is also used by the methods which don't take a key if
+    /// we don't have a key manager. Fails with `IncorrectKey` if `key` can't decrypt our logins.
+    /// If there aren't any logins, any valid key unlocks the store.
+    #[handle_error(Error)]
+    pub fn unlock(&self, key: &str) -> ApiResult<()> {
+        let encdec = EncryptorDecryptor::new(key)?;
+        // All our logins are encrypted with the same key, so checking one is enough.
+        if let Some(login) = self.db.lock().get_any()? {
+            login.decrypt_fields(&encdec)?;
+        }
+        *self.lock_state.lock() = LockState::Unlocked {
+            key: Some(key.to_string()),
+        };
+        Ok(())
+    }
+
+    /// Check whether `key` is the key our logins are encrypted with, by decrypting one of them.
+    /// Returns false rather than failing if it's the wrong key, or isn't a valid key at all, and
+    /// true if there are no logins to check it against.
+    ///
+    /// Decryption is authenticated, so a wrong key is rejected before any plaintext exists to
+    /// compare, and the decrypted fields are dropped without being returned.
+    #[handle_error(Error)]
+    pub fn check_encryption_key(&self, key: &str) -> ApiResult<bool> {
+        let encdec = match EncryptorDecryptor::new(key) {
+            Ok(encdec) => encdec,
+            Err(Error::CryptoError(_)) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        // All our logins are encrypted with the same key, so checking one is enough. We always
+        // check a real login rather than something we stored earlier, because logins can be
+        // added, replaced or re-encrypted with a different key at any time.
+        match self.db.lock().get_any()? {
+            Some(login) => match login.decrypt_fields(&encdec) {
+                Ok(_) => Ok(true),
+                Err(Error::CryptoError(_)) => Ok(false),
+                Err(e) => Err(e),
+            },
+            None => Ok(true),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(*self.lock_state.lock(), LockState::Locked)
+    }
+
+    /// Register an observer to be told when logins are added, updated or deleted, including by
+    /// a sync. This replaces any observer which was already set.
+    pub fn set_change_observer(&self, observer: Box<dyn LoginsChangeObserver>) {
+        self.change_observer.set(observer);
+    }
+
+    pub fn unset_change_observer(&self) {
+        self.change_observer.unset();
+    }
+
+    // Must be called after the database lock is released - see `ChangeObserverRegistry`.
+    pub(crate) fn notify_changes(&self, changes: Vec<LoginChange>) {
+        self.change_observer.notify(changes);
+    }
+
+    fn notify_change(&self, id: &str, change_type: LoginChangeType) {
+        self.notify_changes(vec![LoginChange::new(id, change_type)]);
+    }
+
+    // All the logins which are currently visible are about to be deleted.
+    pub(crate) fn deleted_changes(db: &LoginDb) -> Result<Vec<LoginChange>> {
+        Ok(db
+            .get_all()?
+            .into_iter()
+            .map(|login| LoginChange::new(login.record.id, LoginChangeType::Deleted))
+            .collect())
     }
 
     #[handle_error(Error)]
@@ -88,18 +263,104 @@ impl LoginStore {
         entry: LoginEntry,
         enc_key: &str,
     ) -> ApiResult<Option<Login>> {
+        self.ensure_unlocked()?;
         let encdec = EncryptorDecryptor::new(enc_key)?;
         self.db.lock().find_login_to_update(entry, &encdec)
     }
 
+    #[handle_error(Error)]
+    pub fn get_by_form_origin_and_action(
+        &self,
+        origin: &str,
+        action: &str,
+        enc_key: &str,
+    ) -> ApiResult<Vec<Login>> {
+        self.ensure_unlocked()?;
+        let encdec = EncryptorDecryptor::new(enc_key)?;
+        self.db
+            .lock()
+            .get_by_form_origin_and_action(origin, action)?
+            .into_iter()
+            .map(|login| login.decrypt(&encdec))
+            .collect()
+    }
+
+    /// Check every stored login with the same validation as new logins. Logins which can be
+    /// fixed, for example because their origin isn't normalized, are updated and marked to be
+    /// uploaded by the next sync. Logins which can't be fixed are left alone and reported.
+    #[handle_error(Error)]
+    pub fn validate_and_repair(&self, enc_key: &str) -> ApiResult<RepairReport> {
+        self.ensure_unlocked()?;
+        let encdec = EncryptorDecryptor::new(enc_key)?;
+        let report = self.db.lock().validate_and_repair(&encdec)?;
+        self.notify_changes(report.changes());
+        Ok(report)
+    }
+
+    /// Find passwords which are used by logins for more than one site, for warning the user about
+    /// reused passwords. Returns the IDs of the logins using each reused password, sorted, with
+    /// the most reused passwords first. Logins for the same origin with different usernames don't
+    /// count as reuse.
+    ///
+    /// Passwords are compared by their HMAC with a random key which is thrown away afterwards,
+    /// so the plaintext is never compared or kept, and timing reveals nothing about it. Logins
+    /// which can't be decrypted are skipped.
+    #[handle_error(Error)]
+    pub fn find_reused_passwords(&self, enc_key: &str) -> ApiResult<Vec<Vec<String>>> {
+        self.ensure_unlocked()?;
+        let encdec = EncryptorDecryptor::new(enc_key)?;
+        let logins = self.db.lock().get_all()?;
+        let mut key_value = [0u8; 32];
+        rc_crypto::rand::fill(&mut key_value)?;
+        let key = hmac::SigningKey::new(&digest::SHA256, &key_value);
+
+        let mut by_password: HashMap<Vec<u8>, Vec<EncryptedLogin>> = HashMap::new();
+        for login in logins {
+            let sec_fields = match login.decrypt_fields(&encdec) {
+                Ok(sec_fields) => sec_fields,
+                Err(e) => {
+                    log::warn!("Skipping a login which can't be decrypted: {}", e);
+                    continue;
+                }
+            };
+            let tag = hmac::sign(&key, sec_fields.password.as_bytes())?;
+            by_password
+                .entry(tag.as_ref().to_vec())
+                .or_default()
+                .push(login);
+        }
+
+        let mut reused: Vec<Vec<String>> = by_password
+            .into_values()
+            .filter(|logins| {
+                let origins: BTreeSet<&str> =
+                    logins.iter().map(|l| l.fields.origin.as_str()).collect();
+                origins.len() > 1
+            })
+            .map(|logins| {
+                let mut ids: Vec<String> = logins.into_iter().map(|l| l.record.id).collect();
+                ids.sort();
+                ids
+            })
+            .collect();
+        reused.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Ok(reused)
+    }
+
     #[handle_error(Error)]
     pub fn touch(&self, id: &str) -> ApiResult<()> {
-        self.db.lock().touch(id)
+        self.db.lock().touch(id)?;
+        self.notify_change(id, LoginChangeType::Updated);
+        Ok(())
     }
 
     #[handle_error(Error)]
     pub fn delete(&self, id: &str) -> ApiResult<bool> {
-        self.db.lock().delete(id)
+        let deleted = self.db.lock().delete(id)?;
+        if deleted {
+            self.notify_change(id, LoginChangeType::Deleted);
+        }
+        Ok(deleted)
     }
 
     #[handle_error(Error)]
@@ -109,15 +370,26 @@ impl LoginStore {
         // sense though.
         // TODO: this is exposed to android-components consumers - we should
         // check if anyone actually calls it.
-        let db = self.db.lock();
-        let scope = db.begin_interrupt_scope()?;
-        db.wipe(&scope)?;
+        let changes = {
+            let db = self.db.lock();
+            let scope = db.begin_interrupt_scope()?;
+            let changes = Self::deleted_changes(&db)?;
+            db.wipe(&scope)?;
+            changes
+        };
+        self.notify_changes(changes);
         Ok(())
     }
 
     #[handle_error(Error)]
     pub fn wipe_local(&self) -> ApiResult<()> {
-        self.db.lock().wipe_local()?;
+        let changes = {
+            let db = self.db.lock();
+            let changes = Self::deleted_changes(&db)?;
+            db.wipe_local()?;
+            changes
+        };
+        self.notify_changes(changes);
         Ok(())
     }
 
@@ -133,20 +405,124 @@ impl LoginStore {
 
     #[handle_error(Error)]
     pub fn update(&self, id: &str, entry: LoginEntry, enc_key: &str) -> ApiResult<EncryptedLogin> {
+        self.ensure_unlocked()?;
         let encdec = EncryptorDecryptor::new(enc_key)?;
-        self.db.lock().update(id, entry, &encdec)
+        self.update_with(id, entry, &encdec)
     }
 
     #[handle_error(Error)]
     pub fn add(&self, entry: LoginEntry, enc_key: &str) -> ApiResult<EncryptedLogin> {
+        self.ensure_unlocked()?;
         let encdec = EncryptorDecryptor::new(enc_key)?;
-        self.db.lock().add(entry, &encdec)
+        self.add_with(entry, &encdec)
     }
 
     #[handle_error(Error)]
     pub fn add_or_update(&self, entry: LoginEntry, enc_key: &str) -> ApiResult<EncryptedLogin> {
+        self.ensure_unlocked()?;
         let encdec = EncryptorDecryptor::new(enc_key)?;
-        self.db.lock().add_or_update(entry, &encdec)
+        self.add_or_update_with(entry, &encdec)
+    }
+
+    fn add_with(&self, entry: LoginEntry, encdec: &EncryptorDecryptor) -> Result<EncryptedLogin> {
+        let login = self.db.lock().add(entry, encdec)?;
+        self.notify_change(&login.record.id, LoginChangeType::Added);
+        Ok(login)
+    }
+
+    fn update_with(
+        &self,
+        id: &str,
+        entry: LoginEntry,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<EncryptedLogin> {
+        let login = self.db.lock().update(id, entry, encdec)?;
+        self.notify_change(&login.record.id, LoginChangeType::Updated);
+        Ok(login)
+    }
+
+    fn add_or_update_with(
+        &self,
+        entry: LoginEntry,
+        encdec: &EncryptorDecryptor,
+    ) -> Result<EncryptedLogin> {
+        let (login, change_type) = self
+            .db
+            .lock()
+            .add_or_update_with_change_type(entry, encdec)?;
+        self.notify_change(&login.record.id, change_type);
+        Ok(login)
+    }
+
+    // The methods below are the same as the ones above, but fetch the encryption key from the
+    // `KeyManager` passed to `new_with_key_manager()`.
+
+    #[handle_error(Error)]
+    pub fn add_login(&self, entry: LoginEntry) -> ApiResult<EncryptedLogin> {
+        let encdec = self.encdec_from_key_manager()?;
+        self.add_with(entry, &encdec)
+    }
+
+    #[handle_error(Error)]
+    pub fn update_login(&self, id: &str, entry: LoginEntry) -> ApiResult<EncryptedLogin> {
+        let encdec = self.encdec_from_key_manager()?;
+        self.update_with(id, entry, &encdec)
+    }
+
+    #[handle_error(Error)]
+    pub fn add_or_update_login(&self, entry: LoginEntry) -> ApiResult<EncryptedLogin> {
+        let encdec = self.encdec_from_key_manager()?;
+        self.add_or_update_with(entry, &encdec)
+    }
+
+    #[handle_error(Error)]
+    pub fn find_existing_login(&self, entry: LoginEntry) -> ApiResult<Option<Login>> {
+        let encdec = self.encdec_from_key_manager()?;
+        self.db.lock().find_login_to_update(entry, &encdec)
+    }
+
+    /// Import `entries` in a single transaction, returning what happened to each one, in the
+    /// same order. `strategy` decides what happens to entries with the same origin and username
+    /// as an existing login. Invalid entries are reported as `Failed`, rather than failing the
+    /// whole import.
+    #[handle_error(Error)]
+    pub fn import_logins(
+        &self,
+        entries: Vec<LoginEntry>,
+        strategy: LoginImportStrategy,
+    ) -> ApiResult<Vec<LoginImportOutcome>> {
+        let encdec = self.encdec_from_key_manager()?;
+        let outcomes = self.db.lock().import(entries, strategy, &encdec)?;
+        self.notify_changes(
+            outcomes
+                .iter()
+                .filter_map(LoginImportOutcome::change)
+                .collect(),
+        );
+        Ok(outcomes)
+    }
+
+    #[handle_error(Error)]
+    pub fn decrypt_login(&self, login: EncryptedLogin) -> ApiResult<Login> {
+        let encdec = self.encdec_from_key_manager()?;
+        login.decrypt(&encdec)
+    }
+
+    /// Generate the TOTP code for the login with `id` which is valid at `at_time`, in
+    /// milliseconds since the epoch. Returns `None` if the login doesn't have a TOTP secret.
+    #[handle_error(Error)]
+    pub fn generate_totp(&self, id: &str, at_time: i64) -> ApiResult<Option<String>> {
+        let encdec = self.encdec_from_key_manager()?;
+        let login = self
+            .db
+            .lock()
+            .get_by_id(id)?
+            .ok_or_else(|| Error::NoSuchRecord(id.to_string()))?;
+        login
+            .decrypt_fields(&encdec)?
+            .totp
+            .map(|totp| totp.generate(at_time))
+            .transpose()
     }
 
     // This allows the embedding app to say "make this instance available to
@@ -175,11 +551,10 @@ impl LoginStore {
 mod test {
     use super::*;
     use crate::encryption::test_utils::{TEST_ENCRYPTION_KEY, TEST_ENCRYPTOR};
-    use crate::util;
-    use crate::{LoginFields, SecureLoginFields};
+    use crate::{LoginFields, SecureLoginFields, TotpAlgorithm, TotpConfig};
     use more_asserts::*;
     use std::cmp::Reverse;
-    use std::time::SystemTime;
+    use types::Timestamp;
 
     fn assert_logins_equiv(a: &LoginEntry, b: &EncryptedLogin) {
         let b_e = b.decrypt_fields(&TEST_ENCRYPTOR).unwrap();
@@ -193,7 +568,7 @@ mod test {
         let store = LoginStore::new_in_memory().unwrap();
         let list = store.list().expect("Grabbing Empty list to work");
         assert_eq!(list.len(), 0);
-        let start_us = util::system_time_ms_i64(SystemTime::now());
+        let start_us = Timestamp::now();
 
         let a = LoginEntry {
             fields: LoginFields {
@@ -206,6 +581,7 @@ mod test {
             sec_fields: SecureLoginFields {
                 username: "coolperson21".into(),
                 password: "p4ssw0rd".into(),
+                totp: None,
             },
         };
 
@@ -218,6 +594,7 @@ mod test {
             sec_fields: SecureLoginFields {
                 username: "asdf".into(),
                 password: "fdsa".into(),
+                totp: None,
             },
         };
         let a_id = store
@@ -283,11 +660,12 @@ mod test {
             .expect("Expect an empty list");
         assert_eq!(list.len(), 0);
 
-        let now_us = util::system_time_ms_i64(SystemTime::now());
+        let now_us = Timestamp::now();
         let b2 = LoginEntry {
             sec_fields: SecureLoginFields {
                 username: b.sec_fields.username.to_owned(),
                 password: "newpass".into(),
+                totp: None,
             },
             ..b
         };
@@ -310,6 +688,275 @@ mod test {
         assert_eq!(b_after_update.record.times_used, 2);
     }
 
+    #[test]
+    fn test_key_manager() {
+        struct UnavailableKeyManager;
+        impl KeyManager for UnavailableKeyManager {
+            fn get_key(&self) -> Option<String> {
+                None
+            }
+        }
+
+        let entry = LoginEntry {
+            fields: LoginFields {
+                origin: "https://www.example.com".into(),
+                form_action_origin: Some("https://www.example.com".into()),
+                username_field: "user_input".into(),
+                password_field: "pass_input".into(),
+                ..Default::default()
+            },
+            sec_fields: SecureLoginFields {
+                username: "coolperson21".into(),
+                password: "p4ssw0rd".into(),
+                totp: None,
+            },
+        };
+
+        let store = LoginStore::new_in_memory_with_key_manager(Box::new(
+            jwcrypto::StaticKeyManager::new(TEST_ENCRYPTION_KEY.clone()),
+        ))
+        .unwrap();
+        let added = store.add_login(entry.clone()).unwrap();
+        assert_logins_equiv(&entry, &added);
+        let decrypted = store.decrypt_login(added.clone()).unwrap();
+        assert_eq!(decrypted.sec_fields, entry.sec_fields);
+        let found = store
+            .find_existing_login(entry.clone())
+            .unwrap()
+            .expect("should find the login we added");
+        assert_eq!(found.record.id, added.record.id);
+
+        // A store without a key manager, or whose key manager can't provide the key, fails
+        // with `MissingKey`.
+        let store = LoginStore::new_in_memory().unwrap();
+        assert!(matches!(
+            store.add_login(entry.clone()),
+            Err(LoginsApiError::MissingKey)
+        ));
+        let store =
+            LoginStore::new_in_memory_with_key_manager(Box::new(UnavailableKeyManager)).unwrap();
+        assert!(matches!(
+            store.add_login(entry),
+            Err(LoginsApiError::MissingKey)
+        ));
+    }
+
+    #[test]
+    fn test_get_by_form_origin_and_action() {
+        // No key manager, so this only works because we pass the key.
+        let store = LoginStore::new_in_memory().unwrap();
+        let login = store
+            .add(
+                LoginEntry {
+                    fields: LoginFields {
+                        origin: "https://www.example.com".into(),
+                        form_action_origin: Some("https://login.example.com".into()),
+                        ..Default::default()
+                    },
+                    sec_fields: SecureLoginFields {
+                        username: "user".into(),
+                        password: "password".into(),
+                        totp: None,
+                    },
+                },
+                &TEST_ENCRYPTION_KEY,
+            )
+            .unwrap();
+        let found = store
+            .get_by_form_origin_and_action(
+                "https://www.example.com",
+                "https://login.example.com/submit",
+                &TEST_ENCRYPTION_KEY,
+            )
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].record.id, login.record.id);
+        assert_eq!(found[0].sec_fields.password, "password");
+
+        assert!(store
+            .get_by_form_origin_and_action(
+                "https://www.example.com",
+                "https://other.example.com/submit",
+                &TEST_ENCRYPTION_KEY,
+            )
+            .unwrap()
+            .is_empty());
+
+        store.lock();
+        assert!(matches!(
+            store.get_by_form_origin_and_action(
+                "https://www.example.com",
+                "https://login.example.com/submit",
+                &TEST_ENCRYPTION_KEY,
+            ),
+            Err(LoginsApiError::Locked)
+        ));
+    }
+
+    #[test]
+    fn test_lock() {
+        let entry = LoginEntry {
+            fields: LoginFields {
+                origin: "https://www.example.com".into(),
+                http_realm: Some("the website".into()),
+                ..Default::default()
+            },
+            sec_fields: SecureLoginFields {
+                username: "coolperson21".into(),
+                password: "p4ssw0rd".into(),
+                totp: None,
+            },
+        };
+        let store = LoginStore::new_in_memory().unwrap();
+        assert!(!store.is_locked());
+
+        // With no logins, any valid key unlocks the store.
+        store.lock();
+        store
+            .unlock(&crate::encryption::create_key().unwrap())
+            .unwrap();
+        assert!(!store.is_locked());
+
+        let added = store.add(entry.clone(), &TEST_ENCRYPTION_KEY).unwrap();
+
+        store.lock();
+        assert!(store.is_locked());
+        // Anything which needs the secure fields fails...
+        assert!(matches!(
+            store.add(entry.clone(), &TEST_ENCRYPTION_KEY),
+            Err(LoginsApiError::Locked)
+        ));
+        assert!(matches!(
+            store.update(&added.record.id, entry.clone(), &TEST_ENCRYPTION_KEY),
+            Err(LoginsApiError::Locked)
+        ));
+        assert!(matches!(
+            store.find_login_to_update(entry.clone(), &TEST_ENCRYPTION_KEY),
+            Err(LoginsApiError::Locked)
+        ));
+        assert!(matches!(
+            store.decrypt_login(added.clone()),
+            Err(LoginsApiError::Locked)
+        ));
+        // ...but the metadata is still available.
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.get(&added.record.id).unwrap().is_some());
+        store.touch(&added.record.id).unwrap();
+
+        // The wrong key doesn't unlock the store.
+        let wrong_key = crate::encryption::create_key().unwrap();
+        assert!(matches!(
+            store.unlock(&wrong_key),
+            Err(LoginsApiError::IncorrectKey)
+        ));
+        assert!(store.is_locked());
+
+        // The right key does, and is used by the methods which don't take one.
+        store.unlock(&TEST_ENCRYPTION_KEY).unwrap();
+        assert!(!store.is_locked());
+        let decrypted = store.decrypt_login(added.clone()).unwrap();
+        assert_eq!(decrypted.sec_fields, entry.sec_fields);
+        store
+            .update(&added.record.id, entry, &TEST_ENCRYPTION_KEY)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_check_encryption_key() {
+        let entry = LoginEntry {
+            fields: LoginFields {
+                origin: "https://www.example.com".into(),
+                http_realm: Some("the website".into()),
+                ..Default::default()
+            },
+            sec_fields: SecureLoginFields {
+                username: "coolperson21".into(),
+                password: "p4ssw0rd".into(),
+                totp: None,
+            },
+        };
+        let wrong_key = crate::encryption::create_key().unwrap();
+        let store = LoginStore::new_in_memory().unwrap();
+        // With no logins, any valid key will do.
+        assert!(store.check_encryption_key(&wrong_key).unwrap());
+        assert!(!store.check_encryption_key("not a key").unwrap());
+
+        let added = store.add(entry.clone(), &TEST_ENCRYPTION_KEY).unwrap();
+        assert!(!store.check_encryption_key(&wrong_key).unwrap());
+        assert!(store.check_encryption_key(&TEST_ENCRYPTION_KEY).unwrap());
+
+        // If the logins are replaced by ones with a different key, an earlier successful check
+        // doesn't count for anything.
+        store.delete(&added.record.id).unwrap();
+        store.add(entry, &wrong_key).unwrap();
+        assert!(!store.check_encryption_key(&TEST_ENCRYPTION_KEY).unwrap());
+        assert!(store.check_encryption_key(&wrong_key).unwrap());
+    }
+
+    #[test]
+    fn test_generate_totp() {
+        let mut entry = LoginEntry {
+            fields: LoginFields {
+                origin: "https://www.example.com".into(),
+                http_realm: Some("the website".into()),
+                ..Default::default()
+            },
+            sec_fields: SecureLoginFields {
+                username: "coolperson21".into(),
+                password: "p4ssw0rd".into(),
+                totp: None,
+            },
+        };
+        let store = LoginStore::new_in_memory_with_key_manager(Box::new(
+            jwcrypto::StaticKeyManager::new(TEST_ENCRYPTION_KEY.clone()),
+        ))
+        .unwrap();
+        let without_totp = store.add_login(entry.clone()).unwrap();
+        assert_eq!(
+            store
+                .generate_totp(&without_totp.record.id, 59_000)
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            store.generate_totp("not-a-guid", 59_000),
+            Err(LoginsApiError::NoSuchRecord { .. })
+        ));
+
+        // An invalid secret is rejected when the login is added...
+        entry.fields.origin = "https://totp.example.com".into();
+        entry.sec_fields.totp = Some(TotpConfig {
+            secret: "not base32!".into(),
+            algorithm: TotpAlgorithm::Sha1,
+            period: 30,
+            digits: 8,
+        });
+        assert!(matches!(
+            store.add_login(entry.clone()),
+            Err(LoginsApiError::InvalidRecord { .. })
+        ));
+
+        // ...and a valid one is normalized and used to generate codes.
+        entry.sec_fields.totp.as_mut().unwrap().secret =
+            "gezd gnbv gy3t qojq gezd gnbv gy3t qojq".into();
+        let with_totp = store.add_login(entry).unwrap();
+        let decrypted = store.decrypt_login(with_totp.clone()).unwrap();
+        assert_eq!(
+            decrypted.sec_fields.totp.unwrap().secret,
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert_eq!(
+            store.generate_totp(&with_totp.record.id, 59_000).unwrap(),
+            Some("94287082".to_string())
+        );
+
+        store.lock();
+        assert!(matches!(
+            store.generate_totp(&with_totp.record.id, 59_000),
+            Err(LoginsApiError::Locked)
+        ));
+    }
+
     #[test]
     fn test_sync_manager_registration() {
         let store = Arc::new(LoginStore::new_in_memory().unwrap());
@@ -328,6 +975,326 @@ mod test {
         drop(store);
         assert!(STORE_FOR_MANAGER.lock().upgrade().is_none());
     }
+
+    #[test]
+    fn test_change_observer() {
+        use crate::observer::test_utils::RecordingObserver;
+
+        // Checks the observer can use the store while it's being notified.
+        struct ReentrantObserver(Weak<LoginStore>, RecordingObserver);
+        impl LoginsChangeObserver for ReentrantObserver {
+            fn on_logins_changed(&self, changes: Vec<LoginChange>) {
+                self.0.upgrade().unwrap().list().unwrap();
+                self.1.on_logins_changed(changes);
+            }
+        }
+
+        fn entry(password: &str) -> LoginEntry {
+            LoginEntry {
+                fields: LoginFields {
+                    origin: "https://www.example.com".into(),
+                    http_realm: Some("https://www.example.com".into()),
+                    ..Default::default()
+                },
+                sec_fields: SecureLoginFields {
+                    username: "user".into(),
+                    password: password.into(),
+                    totp: None,
+                },
+            }
+        }
+        fn change(id: &str, change_type: LoginChangeType) -> Vec<LoginChange> {
+            vec![LoginChange::new(id, change_type)]
+        }
+
+        let store = Arc::new(LoginStore::new_in_memory().unwrap());
+        let recorder = RecordingObserver::default();
+        store.set_change_observer(Box::new(ReentrantObserver(
+            Arc::downgrade(&store),
+            recorder.clone(),
+        )));
+
+        let id = store
+            .add(entry("first"), &TEST_ENCRYPTION_KEY)
+            .unwrap()
+            .record
+            .id;
+        assert_eq!(recorder.take(), vec![change(&id, LoginChangeType::Added)]);
+
+        store
+            .update(&id, entry("second"), &TEST_ENCRYPTION_KEY)
+            .unwrap();
+        store
+            .add_or_update(entry("third"), &TEST_ENCRYPTION_KEY)
+            .unwrap();
+        store.touch(&id).unwrap();
+        assert_eq!(
+            recorder.take(),
+            vec![
+                change(&id, LoginChangeType::Updated),
+                change(&id, LoginChangeType::Updated),
+                change(&id, LoginChangeType::Updated),
+            ]
+        );
+
+        // Failed writes and deleting a login which doesn't exist aren't changes.
+        assert!(store.touch("unknown").is_err());
+        assert!(!store.delete("unknown").unwrap());
+        assert!(recorder.take().is_empty());
+
+        assert!(store.delete(&id).unwrap());
+        assert_eq!(recorder.take(), vec![change(&id, LoginChangeType::Deleted)]);
+
+        let id2 = store
+            .add(entry("fourth"), &TEST_ENCRYPTION_KEY)
+            .unwrap()
+            .record
+            .id;
+        recorder.take();
+        store.wipe_local().unwrap();
+        assert_eq!(
+            recorder.take(),
+            vec![change(&id2, LoginChangeType::Deleted)]
+        );
+
+        store.unset_change_observer();
+        store.add(entry("fifth"), &TEST_ENCRYPTION_KEY).unwrap();
+        assert!(recorder.take().is_empty());
+    }
+
+    #[test]
+    fn test_find_reused_passwords() {
+        // No key manager, so this only works because we pass the key.
+        let store = LoginStore::new_in_memory().unwrap();
+        let add_with_key = |origin: &str, username: &str, password: &str, key: &str| {
+            store
+                .add(
+                    LoginEntry {
+                        fields: LoginFields {
+                            origin: origin.into(),
+                            form_action_origin: Some(origin.into()),
+                            ..Default::default()
+                        },
+                        sec_fields: SecureLoginFields {
+                            username: username.into(),
+                            password: password.into(),
+                            totp: None,
+                        },
+                    },
+                    key,
+                )
+                .unwrap()
+                .record
+                .id
+        };
+        let add = |origin: &str, username: &str, password: &str| {
+            add_with_key(origin, username, password, &TEST_ENCRYPTION_KEY)
+        };
+        let mut twice = vec![
+            add("https://a.com", "user", "hunter2"),
+            add("https://b.com", "user", "hunter2"),
+        ];
+        let mut three_times = vec![
+            add("https://a.com", "other", "letmein"),
+            add("https://b.com", "other", "letmein"),
+            add("https://c.com", "user", "letmein"),
+        ];
+        add("https://c.com", "another", "unique");
+        // Logins for the same site sharing a password isn't reuse.
+        add("https://d.com", "user", "samesite");
+        add("https://d.com", "other", "samesite");
+        // Logins we can't decrypt are skipped, rather than failing the whole check.
+        let other_key = crate::encryption::create_key().unwrap();
+        add_with_key("https://e.com", "user", "hunter2", &other_key);
+        twice.sort();
+        three_times.sort();
+
+        assert_eq!(
+            store.find_reused_passwords(&TEST_ENCRYPTION_KEY).unwrap(),
+            vec![three_times, twice]
+        );
+
+        store.lock();
+        assert!(matches!(
+            store.find_reused_passwords(&TEST_ENCRYPTION_KEY),
+            Err(LoginsApiError::Locked)
+        ));
+    }
+
+    #[test]
+    fn test_validate_and_repair() {
+        // No key manager, so this only works because we pass the key.
+        let store = LoginStore::new_in_memory().unwrap();
+        let id = store
+            .add(
+                LoginEntry {
+                    fields: LoginFields {
+                        origin: "https://www.example.com".into(),
+                        http_realm: Some("the website".into()),
+                        ..Default::default()
+                    },
+                    sec_fields: SecureLoginFields {
+                        username: "user".into(),
+                        password: "password".into(),
+                        totp: None,
+                    },
+                },
+                &TEST_ENCRYPTION_KEY,
+            )
+            .unwrap()
+            .record
+            .id;
+        // Logins stored by older versions might not be normalized.
+        store
+            .db
+            .lock()
+            .execute(
+                "UPDATE loginsL SET origin = 'https://WWW.example.com/login' WHERE guid = ?",
+                [&id],
+            )
+            .unwrap();
+
+        let report = store.validate_and_repair(&TEST_ENCRYPTION_KEY).unwrap();
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.repaired, vec![id.clone()]);
+        assert_eq!(
+            store.get(&id).unwrap().unwrap().fields.origin,
+            "https://www.example.com"
+        );
+
+        store.lock();
+        assert!(matches!(
+            store.validate_and_repair(&TEST_ENCRYPTION_KEY),
+            Err(LoginsApiError::Locked)
+        ));
+    }
+
+    #[test]
+    fn test_import_logins() {
+        use crate::observer::test_utils::RecordingObserver;
+
+        fn entry(origin: &str, realm: Option<&str>, password: &str) -> LoginEntry {
+            LoginEntry {
+                fields: LoginFields {
+                    origin: origin.into(),
+                    http_realm: realm.map(Into::into),
+                    form_action_origin: match realm {
+                        Some(_) => None,
+                        None => Some(origin.into()),
+                    },
+                    ..Default::default()
+                },
+                sec_fields: SecureLoginFields {
+                    username: "user".into(),
+                    password: password.into(),
+                    totp: None,
+                },
+            }
+        }
+
+        // Imports the same logins into a store with one existing login, which the first and
+        // last of them collide with.
+        fn import(strategy: LoginImportStrategy) -> (LoginStore, String, Vec<LoginImportOutcome>) {
+            let store = LoginStore::new_in_memory_with_key_manager(Box::new(
+                jwcrypto::StaticKeyManager::new(TEST_ENCRYPTION_KEY.clone()),
+            ))
+            .unwrap();
+            let existing = store
+                .add_login(entry("https://a.com", Some("realm"), "old"))
+                .unwrap()
+                .record
+                .id;
+            let outcomes = store
+                .import_logins(
+                    vec![
+                        entry("https://a.com", Some("realm"), "new"),
+                        entry("https://b.com", Some("realm"), "new"),
+                        entry("https://a.com", Some("realm"), ""),
+                        entry("https://a.com", None, "newer"),
+                    ],
+                    strategy,
+                )
+                .unwrap();
+            (store, existing, outcomes)
+        }
+
+        fn added_id(outcome: &LoginImportOutcome) -> String {
+            match outcome {
+                LoginImportOutcome::Added { id } => id.clone(),
+                _ => panic!("expected the login to be added, got {:?}", outcome),
+            }
+        }
+
+        fn password(store: &LoginStore, id: &str) -> String {
+            store
+                .get(id)
+                .unwrap()
+                .unwrap()
+                .decrypt_fields(&TEST_ENCRYPTOR)
+                .unwrap()
+                .password
+        }
+
+        let invalid = LoginImportOutcome::Failed {
+            reason: "Password is empty".into(),
+        };
+
+        let (store, existing, outcomes) = import(LoginImportStrategy::Skip);
+        added_id(&outcomes[1]);
+        assert_eq!(
+            outcomes[0],
+            LoginImportOutcome::Skipped {
+                existing_id: existing.clone()
+            }
+        );
+        assert_eq!(outcomes[2], invalid);
+        assert_eq!(outcomes[3], outcomes[0]);
+        assert_eq!(password(&store, &existing), "old");
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        let (store, existing, outcomes) = import(LoginImportStrategy::Overwrite);
+        added_id(&outcomes[1]);
+        let overwritten = LoginImportOutcome::Overwritten {
+            id: existing.clone(),
+        };
+        assert_eq!(outcomes[0], overwritten);
+        assert_eq!(outcomes[2], invalid);
+        assert_eq!(outcomes[3], overwritten);
+        assert_eq!(password(&store, &existing), "newer");
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        // Keeping both still refuses to add an exact duplicate.
+        let (store, existing, outcomes) = import(LoginImportStrategy::KeepBoth);
+        assert_eq!(
+            outcomes[0],
+            LoginImportOutcome::Failed {
+                reason: "Login already exists".into()
+            }
+        );
+        added_id(&outcomes[1]);
+        assert_eq!(outcomes[2], invalid);
+        let kept = added_id(&outcomes[3]);
+        assert_eq!(password(&store, &existing), "old");
+        assert_eq!(password(&store, &kept), "newer");
+        assert_eq!(store.list().unwrap().len(), 3);
+
+        // The observer is told about everything which was written, all at once.
+        let recorder = RecordingObserver::default();
+        store.set_change_observer(Box::new(recorder.clone()));
+        let outcomes = store
+            .import_logins(
+                vec![entry("https://c.com", Some("realm"), "pw")],
+                LoginImportStrategy::Skip,
+            )
+            .unwrap();
+        assert_eq!(
+            recorder.take(),
+            vec![vec![LoginChange::new(
+                added_id(&outcomes[0]),
+                LoginChangeType::Added
+            )]]
+        );
+    }
 }
 
 #[test]
@@ -9,11 +9,13 @@ use crate::db::CLONE_ENTIRE_MIRROR_SQL;
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
 use crate::login::EncryptedLogin;
+use crate::observer::{LoginChange, LoginChangeType};
 use crate::schema;
 use crate::util;
 use crate::LoginDb;
 use crate::LoginStore;
 use interrupt_support::SqlInterruptScope;
+use once_cell::unsync::OnceCell;
 use rusqlite::named_params;
 use sql_support::ConnExt;
 use std::cell::RefCell;
@@ -32,13 +34,23 @@ pub struct LoginsSyncEngine {
     // It's unfortunate this is an Option<>, but tricky to change because sometimes we construct
     // an engine for, say, a `reset()` where this isn't needed or known.
     encdec: Option<EncryptorDecryptor>,
+    // If `set_local_encryption_key()` isn't called, we use the store's key manager. It's only
+    // asked for the key the first time we need to encrypt or decrypt, because some operations
+    // (eg, reset) never do.
+    key_manager_encdec: OnceCell<EncryptorDecryptor>,
 }
 
 impl LoginsSyncEngine {
     fn encdec(&self) -> Result<&EncryptorDecryptor> {
         match &self.encdec {
             Some(encdec) => Ok(encdec),
-            None => Err(Error::EncryptionKeyMissing),
+            None => self.key_manager_encdec.get_or_try_init(|| {
+                let key = self
+                    .store
+                    .key_from_key_manager()
+                    .ok_or(Error::EncryptionKeyMissing)?;
+                EncryptorDecryptor::new(&key)
+            }),
         }
     }
 
@@ -49,6 +61,7 @@ impl LoginsSyncEngine {
             scope,
             staged: RefCell::new(vec![]),
             encdec: None,
+            key_manager_encdec: OnceCell::new(),
         })
     }
 
@@ -64,10 +77,30 @@ impl LoginsSyncEngine {
         for mut record in records {
             self.scope.err_if_interrupted()?;
             log::debug!("Processing remote change {}", record.guid());
+            // Whether the user can currently see this login.
+            let was_visible = match (&record.local, &record.mirror) {
+                (Some(local), _) => !local.is_deleted,
+                (None, Some(mirror)) => !mirror.is_overridden,
+                (None, None) => false,
+            };
+            let changed = |guid: &Guid| {
+                let change_type = if was_visible {
+                    LoginChangeType::Updated
+                } else {
+                    LoginChangeType::Added
+                };
+                LoginChange::new(guid.as_str(), change_type)
+            };
             let upstream = if let Some(inbound) = record.inbound.take() {
                 inbound
             } else {
                 log::debug!("Processing inbound deletion (always prefer)");
+                if was_visible {
+                    plan.changes.push(LoginChange::new(
+                        record.guid.as_str(),
+                        LoginChangeType::Deleted,
+                    ));
+                }
                 plan.plan_delete(record.guid.clone());
                 continue;
             };
@@ -75,6 +108,7 @@ impl LoginsSyncEngine {
             match (record.mirror.take(), record.local.take()) {
                 (Some(mirror), Some(local)) => {
                     log::debug!("  Conflict between remote and local, Resolving with 3WM");
+                    plan.changes.push(changed(&record.guid));
                     plan.plan_three_way_merge(
                         local,
                         mirror,
@@ -87,12 +121,15 @@ impl LoginsSyncEngine {
                 }
                 (Some(_mirror), None) => {
                     log::debug!("  Forwarding mirror to remote");
+                    plan.changes.push(changed(&record.guid));
                     plan.plan_mirror_update(upstream, upstream_time);
                     telem.applied(1);
                 }
                 (None, Some(local)) => {
                     log::debug!("  Conflicting record without shared parent, using newer");
-                    plan.plan_two_way_merge(&local.login, (upstream, upstream_time));
+                    if plan.plan_two_way_merge(&local.login, (upstream, upstream_time)) {
+                        plan.changes.push(changed(&record.guid));
+                    }
                     telem.reconciled(1);
                 }
                 (None, None) => {
@@ -102,9 +139,14 @@ impl LoginsSyncEngine {
                             upstream.guid(),
                             dupe.guid()
                         );
-                        plan.plan_two_way_merge(&dupe, (upstream, upstream_time));
+                        if plan.plan_two_way_merge(&dupe, (upstream, upstream_time)) {
+                            plan.changes
+                                .push(LoginChange::new(dupe.guid_str(), LoginChangeType::Deleted));
+                            plan.changes.push(changed(&record.guid));
+                        }
                     } else {
                         log::debug!("  No dupe found, inserting into mirror");
+                        plan.changes.push(changed(&record.guid));
                         plan.plan_mirror_insert(upstream, upstream_time, false);
                     }
                     telem.applied(1);
@@ -275,12 +317,14 @@ impl LoginsSyncEngine {
     ) -> Result<Vec<OutgoingBso>> {
         let mut incoming_telemetry = telemetry::EngineIncoming::new();
         let data = self.fetch_login_data(inbound, &mut incoming_telemetry)?;
-        let plan = {
+        let mut plan = {
             let result = self.reconcile(data, timestamp, &mut incoming_telemetry);
             telem.incoming(incoming_telemetry);
             result
         }?;
+        let changes = std::mem::take(&mut plan.changes);
         self.execute_plan(plan)?;
+        self.store.notify_changes(changes);
         self.fetch_outgoing()
     }
 
@@ -463,6 +507,11 @@ impl SyncEngine for LoginsSyncEngine {
         )?)
     }
 
+    // Logins don't depend on each other, so any we fail to upload can wait.
+    fn upload_must_be_atomic(&self) -> bool {
+        false
+    }
+
     fn get_collection_request(
         &self,
         server_timestamp: ServerTimestamp,
@@ -497,10 +546,26 @@ impl SyncEngine for LoginsSyncEngine {
     }
 
     fn wipe(&self) -> anyhow::Result<()> {
-        let db = self.store.db.lock();
-        db.wipe(&self.scope)?;
+        let changes = {
+            let db = self.store.db.lock();
+            let changes = LoginStore::deleted_changes(&db)?;
+            db.wipe(&self.scope)?;
+            changes
+        };
+        self.store.notify_changes(changes);
         Ok(())
     }
+
+    fn has_pending_changes(&self) -> anyhow::Result<bool> {
+        let db = self.store.db.lock();
+        Ok(db.exists(
+            &format!(
+                "SELECT 1 FROM loginsL WHERE sync_status IS NOT {synced} LIMIT 1",
+                synced = SyncStatus::Synced as u8
+            ),
+            [],
+        )?)
+    }
 }
 
 #[cfg(test)]
@@ -511,6 +576,7 @@ mod tests {
     use crate::login::test_utils::enc_login;
     use crate::{LoginEntry, LoginFields, RecordFields, SecureLoginFields};
     use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
     // Wrap sync functions for easier testing
@@ -673,6 +739,57 @@ mod tests {
         assert!(changes["changed"].get("deleted").is_none());
     }
 
+    #[test]
+    fn test_has_pending_changes() {
+        let store = Arc::new(LoginStore::new_in_memory().unwrap());
+        let engine = LoginsSyncEngine::new(store.clone()).unwrap();
+        assert!(!engine.has_pending_changes().unwrap());
+        // Logins which only exist in the mirror are already synced.
+        insert_login(&store.db.lock(), "unchanged", None, Some("password"));
+        assert!(!engine.has_pending_changes().unwrap());
+        insert_login(
+            &store.db.lock(),
+            "changed",
+            Some("new-password"),
+            Some("password"),
+        );
+        assert!(engine.has_pending_changes().unwrap());
+        engine
+            .mark_as_synchronized(&["changed"], ServerTimestamp::default())
+            .unwrap();
+        assert!(!engine.has_pending_changes().unwrap());
+        store.db.lock().delete("unchanged").unwrap();
+        assert!(engine.has_pending_changes().unwrap());
+    }
+
+    #[test]
+    fn test_key_manager_is_lazy() {
+        struct CountingKeyManager(Arc<AtomicUsize>);
+        impl jwcrypto::KeyManager for CountingKeyManager {
+            fn get_key(&self) -> Option<String> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Some(TEST_ENCRYPTION_KEY.clone())
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let store =
+            LoginStore::new_in_memory_with_key_manager(Box::new(CountingKeyManager(calls.clone())))
+                .unwrap();
+        insert_login(&store.db.lock(), "added", Some("password"), None);
+
+        // Operations which don't encrypt or decrypt don't ask for the key.
+        let engine = LoginsSyncEngine::new(Arc::new(store)).unwrap();
+        assert!(engine.has_pending_changes().unwrap());
+        engine.reset(&EngineSyncAssociation::Disconnected).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // The key is fetched once, when it's first needed.
+        assert_eq!(engine.fetch_outgoing().unwrap().len(), 1);
+        assert_eq!(engine.fetch_outgoing().unwrap().len(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_bad_record() {
         let store = LoginStore::new_in_memory().unwrap();
@@ -725,6 +842,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: username.into(),
                 password: password.into(),
+                totp: None,
             }
             .encrypt(&TEST_ENCRYPTOR)
             .unwrap(),
@@ -744,6 +862,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test".into(),
                 password: "test".into(),
+                totp: None,
             },
         };
         let first_id = store
@@ -761,6 +880,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test1".into(),
                 password: "test1".into(),
+                totp: None,
             },
         };
         let second_id = store
@@ -778,6 +898,7 @@ mod tests {
             sec_fields: SecureLoginFields {
                 username: "test1".into(),
                 password: "test1".into(),
+                totp: None,
             },
         };
         let no_form_origin_id = store
@@ -853,6 +974,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_apply_incoming_notifies_changes() {
+        use crate::observer::test_utils::RecordingObserver;
+
+        fn apply(engine: &LoginsSyncEngine, bsos: Vec<IncomingBso>) {
+            let mut telem = sync15::telemetry::Engine::new(engine.collection_name());
+            engine.stage_incoming(bsos, &mut telem).unwrap();
+            engine
+                .apply(ServerTimestamp::from_millis(0), &mut telem)
+                .unwrap();
+        }
+
+        let store = Arc::new(LoginStore::new_in_memory().unwrap());
+        let recorder = RecordingObserver::default();
+        store.set_change_observer(Box::new(recorder.clone()));
+        insert_login(&store.db.lock(), "updated_remotely", None, Some("password"));
+        insert_login(&store.db.lock(), "deleted_remotely", None, Some("password"));
+        let mut engine = LoginsSyncEngine::new(Arc::clone(&store)).unwrap();
+        engine
+            .set_local_encryption_key(&TEST_ENCRYPTION_KEY)
+            .unwrap();
+
+        apply(
+            &engine,
+            vec![
+                enc_login("updated_remotely", "new-password")
+                    .into_bso(&TEST_ENCRYPTOR, None)
+                    .unwrap()
+                    .to_test_incoming(),
+                enc_login("added_remotely", "password")
+                    .into_bso(&TEST_ENCRYPTOR, None)
+                    .unwrap()
+                    .to_test_incoming(),
+                IncomingBso::new_test_tombstone(Guid::new("deleted_remotely")),
+                // We never had this one, so it isn't a change.
+                IncomingBso::new_test_tombstone(Guid::new("never_existed")),
+            ],
+        );
+
+        // All the changes from a sync are delivered together.
+        let mut batches = recorder.take();
+        assert_eq!(batches.len(), 1);
+        let mut changes = batches.remove(0);
+        changes.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(
+            changes,
+            vec![
+                LoginChange::new("added_remotely", LoginChangeType::Added),
+                LoginChange::new("deleted_remotely", LoginChangeType::Deleted),
+                LoginChange::new("updated_remotely", LoginChangeType::Updated),
+            ]
+        );
+
+        // A sync which doesn't change anything doesn't notify.
+        apply(&engine, vec![]);
+        assert!(recorder.take().is_empty());
+    }
+
     #[test]
     fn test_roundtrip_unknown() {
         // A couple of helpers
@@ -880,6 +1059,7 @@ mod tests {
                         sec_fields: SecureLoginFields {
                             username: "test".into(),
                             password: "test".into(),
+                            totp: None,
                         },
                     },
                     &TEST_ENCRYPTION_KEY,
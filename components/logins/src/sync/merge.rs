@@ -7,12 +7,14 @@ use super::{IncomingLogin, LoginPayload, SyncStatus};
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
 use crate::login::EncryptedLogin;
+use crate::totp::TotpConfig;
 use crate::util;
 use rusqlite::Row;
 use std::time::{self, SystemTime};
 use sync15::bso::{IncomingBso, IncomingKind};
 use sync15::ServerTimestamp;
 use sync_guid::Guid;
+use types::Timestamp;
 
 #[derive(Clone, Debug)]
 pub(crate) struct MirrorLogin {
@@ -186,12 +188,14 @@ pub(crate) struct LoginDelta {
     pub origin: Option<String>,
     pub password: Option<String>,
     pub username: Option<String>,
+    // `Some(None)` means the TOTP config was removed.
+    pub totp: Option<Option<TotpConfig>>,
     pub http_realm: Option<String>,
     pub form_action_origin: Option<String>,
 
-    pub time_created: Option<i64>,
-    pub time_last_used: Option<i64>,
-    pub time_password_changed: Option<i64>,
+    pub time_created: Option<Timestamp>,
+    pub time_last_used: Option<Timestamp>,
+    pub time_password_changed: Option<Timestamp>,
 
     // "non-conflicting" fields (which are the same)
     pub password_field: Option<String>,
@@ -223,6 +227,7 @@ impl LoginDelta {
         merge_field!(merged, b, b_is_newer, origin);
         merge_field!(merged, b, b_is_newer, password);
         merge_field!(merged, b, b_is_newer, username);
+        merge_field!(merged, b, b_is_newer, totp);
         merge_field!(merged, b, b_is_newer, http_realm);
         merge_field!(merged, b, b_is_newer, form_action_origin);
 
@@ -278,6 +283,9 @@ impl EncryptedLogin {
         if let Some(username) = delta.username.take() {
             sec_fields.username = username;
         }
+        if let Some(totp) = delta.totp.take() {
+            sec_fields.totp = totp;
+        }
         self.sec_fields = sec_fields.encrypt(encdec)?;
 
         // Use Some("") to indicate that it should be changed to be None (hacky...)
@@ -317,6 +325,9 @@ impl EncryptedLogin {
         if self_sec_fields.username != older_sec_fields.username {
             delta.username = Some(self_sec_fields.username.clone());
         }
+        if self_sec_fields.totp != older_sec_fields.totp {
+            delta.totp = Some(self_sec_fields.totp.clone());
+        }
         if self_sec_fields.password != older_sec_fields.password {
             delta.password = Some(self_sec_fields.password);
         }
@@ -336,15 +347,17 @@ impl EncryptedLogin {
         // `time_password_changed`. Doing this properly would probably require
         // a scheme analogous to Desktop's weak-reupload system, so I'm punting
         // on it for now.
-        if self.record.time_created > 0 && self.record.time_created != older.record.time_created {
+        if self.record.time_created > Timestamp(0)
+            && self.record.time_created != older.record.time_created
+        {
             delta.time_created = Some(self.record.time_created);
         }
-        if self.record.time_last_used > 0
+        if self.record.time_last_used > Timestamp(0)
             && self.record.time_last_used != older.record.time_last_used
         {
             delta.time_last_used = Some(self.record.time_last_used);
         }
-        if self.record.time_password_changed > 0
+        if self.record.time_password_changed > Timestamp(0)
             && self.record.time_password_changed != older.record.time_password_changed
         {
             delta.time_password_changed = Some(self.record.time_password_changed);
@@ -382,11 +395,11 @@ mod tests {
             .inbound
             .unwrap()
             .login;
-        assert_eq!(login.record.time_created, 0);
-        assert_eq!(login.record.time_last_used, 0);
-        assert_eq!(login.record.time_password_changed, 0);
+        assert_eq!(login.record.time_created, Timestamp(0));
+        assert_eq!(login.record.time_last_used, Timestamp(0));
+        assert_eq!(login.record.time_password_changed, Timestamp(0));
 
-        let now64 = util::system_time_ms_i64(std::time::SystemTime::now());
+        let now64 = Timestamp::now().as_millis();
         let good_payload = IncomingBso::from_test_content(serde_json::json!({
             "id": "123412341234",
             "formSubmitURL": "https://www.example.com/submit",
@@ -404,8 +417,8 @@ mod tests {
             .unwrap()
             .login;
 
-        assert_eq!(login.record.time_created, now64 - 100);
-        assert_eq!(login.record.time_last_used, now64 - 50);
-        assert_eq!(login.record.time_password_changed, now64 - 25);
+        assert_eq!(login.record.time_created, Timestamp(now64 - 100));
+        assert_eq!(login.record.time_last_used, Timestamp(now64 - 50));
+        assert_eq!(login.record.time_password_changed, Timestamp(now64 - 25));
     }
 }
@@ -10,11 +10,11 @@
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
 use crate::login::ValidateAndFixup;
-use crate::SecureLoginFields;
-use crate::{EncryptedLogin, LoginFields, RecordFields};
+use crate::{EncryptedLogin, LoginFields, RecordFields, SecureLoginFields, TotpConfig};
 use serde_derive::*;
 use sync15::bso::OutgoingBso;
 use sync_guid::Guid;
+use types::Timestamp;
 
 type UnknownFields = serde_json::Map<String, serde_json::Value>;
 
@@ -63,6 +63,7 @@ impl IncomingLogin {
         let sec_fields = SecureLoginFields {
             username: p.username,
             password: p.password,
+            totp: p.totp,
         };
         // We handle NULL in the DB for migrated databases and it's wasteful
         // to encrypt the common case of an empty map, so...
@@ -126,19 +127,23 @@ pub struct LoginPayload {
 
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_timestamp")]
-    pub time_created: i64,
+    pub time_created: Timestamp,
 
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_timestamp")]
-    pub time_password_changed: i64,
+    pub time_password_changed: Timestamp,
 
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_timestamp")]
-    pub time_last_used: i64,
+    pub time_last_used: Timestamp,
 
     #[serde(default)]
     pub times_used: i64,
 
+    // Only our clients know about this, but desktop round-trips it as an unknown field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<TotpConfig>,
+
     // Additional "unknown" round-tripped fields.
     #[serde(flatten)]
     unknown_fields: UnknownFields,
@@ -166,6 +171,7 @@ impl EncryptedLogin {
                 password_field: self.fields.password_field,
                 username: sec_fields.username,
                 password: sec_fields.password,
+                totp: sec_fields.totp,
                 time_created: self.record.time_created,
                 time_password_changed: self.record.time_password_changed,
                 time_last_used: self.record.time_last_used,
@@ -178,7 +184,7 @@ impl EncryptedLogin {
 
 // Quiet clippy, since this function is passed to deserialiaze_with...
 #[allow(clippy::unnecessary_wraps)]
-fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<i64, D::Error>
+fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<Timestamp, D::Error>
 where
     D: serde::de::Deserializer<'de>,
 {
@@ -187,7 +193,7 @@ where
     // should investigate replacing values that are unreasonable but still fit
     // in an i64 (a date 1000 years in the future, for example), but
     // appropriately handling that is complex.
-    Ok(i64::deserialize(deserializer).unwrap_or_default().max(0))
+    Ok(Timestamp::try_from(i64::deserialize(deserializer).unwrap_or_default()).unwrap_or_default())
 }
 
 #[cfg(test)]
@@ -412,4 +418,37 @@ mod tests {
         .login;
         assert_eq!(login.fields.password_field, "");
     }
+
+    #[test]
+    fn test_payload_totp_round_trip() {
+        let bso = IncomingBso::from_test_content(serde_json::json!({
+            "id": "123412341234",
+            "httpRealm": "test",
+            "hostname": "https://www.example.com",
+            "username": "user",
+            "password": "password",
+            "totp": {"secret": "gezd gnbv gy3t qojq", "algorithm": "SHA256"},
+        }));
+        let payload = bso.into_content::<LoginPayload>().content().unwrap();
+        assert!(payload.unknown_fields.is_empty());
+        let login = IncomingLogin::from_incoming_payload(payload, &TEST_ENCRYPTOR)
+            .unwrap()
+            .login;
+        // The secret is normalized, and the missing fields get their defaults.
+        let totp = login.decrypt_fields(&TEST_ENCRYPTOR).unwrap().totp.unwrap();
+        assert_eq!(totp.secret, "GEZDGNBVGY3TQOJQ");
+        assert_eq!(totp.period, 30);
+
+        let outgoing = login.into_bso(&TEST_ENCRYPTOR, None).unwrap();
+        let payload_data: serde_json::Value = serde_json::from_str(&outgoing.payload).unwrap();
+        assert_eq!(
+            payload_data["totp"],
+            serde_json::json!({
+                "secret": "GEZDGNBVGY3TQOJQ",
+                "algorithm": "SHA256",
+                "period": 30,
+                "digits": 6,
+            })
+        );
+    }
 }
@@ -7,6 +7,7 @@ use super::{IncomingLogin, SyncStatus};
 use crate::encryption::EncryptorDecryptor;
 use crate::error::*;
 use crate::login::EncryptedLogin;
+use crate::observer::LoginChange;
 use crate::util;
 use interrupt_support::SqlInterruptScope;
 use rusqlite::{named_params, Connection};
@@ -22,6 +23,9 @@ pub(super) struct UpdatePlan {
     // the bool is the `is_overridden` flag, the i64 is ServerTimestamp in millis
     pub mirror_inserts: Vec<(IncomingLogin, i64, bool)>,
     pub mirror_updates: Vec<(IncomingLogin, i64)>,
+    // How the logins the user can see change when the plan is executed, for the store's
+    // change observer.
+    pub changes: Vec<LoginChange>,
 }
 
 impl UpdatePlan {
@@ -29,7 +33,7 @@ impl UpdatePlan {
         &mut self,
         local: &EncryptedLogin,
         upstream: (IncomingLogin, ServerTimestamp),
-    ) {
+    ) -> bool {
         let is_override =
             local.record.time_password_changed > upstream.0.login.record.time_password_changed;
         self.mirror_inserts
@@ -37,6 +41,8 @@ impl UpdatePlan {
         if !is_override {
             self.delete_local.push(local.guid());
         }
+        // Whether the incoming login replaces the local one.
+        !is_override
     }
 
     pub fn plan_three_way_merge(
@@ -0,0 +1,238 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Time-based one-time passwords (RFC 6238) for logins which use 2FA.
+//
+// The TOTP config is stored with the other `SecureLoginFields`, so it's encrypted both locally
+// and on the sync server, and is only available while the store is unlocked.
+
+use crate::error::*;
+use crate::login::ValidateAndFixup;
+use rc_crypto::{digest, hmac};
+use serde_derive::*;
+
+pub const DEFAULT_TOTP_PERIOD: u32 = 30;
+pub const DEFAULT_TOTP_DIGITS: u32 = 6;
+// RFC 4226 requires at least 6 digits, and suggests no more than 8.
+const MIN_TOTP_DIGITS: u32 = 6;
+const MAX_TOTP_DIGITS: u32 = 8;
+
+/// The HMAC hash function used to compute a code. Almost every site uses SHA-1, which is
+/// the default in `otpauth://` URIs.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    fn digest_algorithm(self) -> &'static digest::Algorithm {
+        match self {
+            TotpAlgorithm::Sha1 => &digest::SHA1,
+            TotpAlgorithm::Sha256 => &digest::SHA256,
+            TotpAlgorithm::Sha512 => &digest::SHA512,
+        }
+    }
+}
+
+/// The shared secret and parameters a site gave us for its authenticator codes.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpConfig {
+    /// The base32-encoded secret (RFC 4648, without padding).
+    pub secret: String,
+    #[serde(default)]
+    pub algorithm: TotpAlgorithm,
+    /// How long each code is valid for, in seconds.
+    #[serde(default = "default_period")]
+    pub period: u32,
+    #[serde(default = "default_digits")]
+    pub digits: u32,
+}
+
+fn default_period() -> u32 {
+    DEFAULT_TOTP_PERIOD
+}
+
+fn default_digits() -> u32 {
+    DEFAULT_TOTP_DIGITS
+}
+
+impl TotpConfig {
+    /// Compute the code which is valid at `at_time`, in milliseconds since the epoch like our
+    /// other timestamps.
+    pub(crate) fn generate(&self, at_time: i64) -> Result<String> {
+        self.check_valid()?;
+        let key = decode_base32(&self.secret).ok_or_else(|| invalid_totp("secret"))?;
+        let counter = (at_time.max(0) / 1000) as u64 / u64::from(self.period);
+        let signing_key = hmac::SigningKey::new(self.algorithm.digest_algorithm(), &key);
+        let signature = hmac::sign(&signing_key, &counter.to_be_bytes())?;
+        // "Dynamic truncation", as described in RFC 4226 section 5.3.
+        let hash = signature.as_ref();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        let code = binary % 10u32.pow(self.digits);
+        Ok(format!("{:0width$}", code, width = self.digits as usize))
+    }
+}
+
+impl ValidateAndFixup for TotpConfig {
+    /// Secrets are often shown to users in lowercase, or in groups separated by spaces, so we
+    /// normalize them to what `decode_base32()` expects.
+    fn validate_and_fixup(&self, fixup: bool) -> Result<Option<Self>> {
+        let normalized = normalize_secret(&self.secret);
+        match decode_base32(&normalized) {
+            Some(key) if !key.is_empty() => (),
+            _ => return Err(invalid_totp("secret")),
+        }
+        if self.period == 0 {
+            return Err(invalid_totp("period"));
+        }
+        if !(MIN_TOTP_DIGITS..=MAX_TOTP_DIGITS).contains(&self.digits) {
+            return Err(invalid_totp("digits"));
+        }
+        if normalized == self.secret {
+            Ok(None)
+        } else if fixup {
+            Ok(Some(Self {
+                secret: normalized,
+                ..self.clone()
+            }))
+        } else {
+            Err(invalid_totp("secret"))
+        }
+    }
+}
+
+fn invalid_totp(field: &str) -> Error {
+    InvalidLogin::IllegalFieldValue {
+        field_info: format!("`totp.{field}` is invalid"),
+    }
+    .into()
+}
+
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+// Decode an unpadded, uppercase base32 string, returning `None` if it has any characters
+// outside the RFC 4648 alphabet.
+fn decode_base32(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(secret: &str, algorithm: TotpAlgorithm) -> TotpConfig {
+        TotpConfig {
+            secret: secret.into(),
+            algorithm,
+            period: DEFAULT_TOTP_PERIOD,
+            digits: 8,
+        }
+    }
+
+    #[test]
+    fn test_decode_base32() {
+        assert_eq!(decode_base32("MZXW6YTBOI").unwrap(), b"foobar");
+        assert_eq!(decode_base32("").unwrap(), b"");
+        assert!(decode_base32("MZXW6YTBO1").is_none());
+        assert!(decode_base32("mzxw6ytboi").is_none());
+    }
+
+    // The test vectors from RFC 6238 appendix B.
+    #[test]
+    fn test_generate_rfc_vectors() {
+        let sha1 = config("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", TotpAlgorithm::Sha1);
+        assert_eq!(sha1.generate(59_000).unwrap(), "94287082");
+        assert_eq!(sha1.generate(1_111_111_109_000).unwrap(), "07081804");
+        let sha256 = config(
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA",
+            TotpAlgorithm::Sha256,
+        );
+        assert_eq!(sha256.generate(59_000).unwrap(), "46119246");
+        let sha512 = config(
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNA",
+            TotpAlgorithm::Sha512,
+        );
+        assert_eq!(sha512.generate(59_000).unwrap(), "90693936");
+
+        let six_digits = TotpConfig { digits: 6, ..sha1 };
+        assert_eq!(six_digits.generate(1_111_111_109_000).unwrap(), "081804");
+    }
+
+    #[test]
+    fn test_validate_and_fixup() {
+        let valid = config("GEZDGNBVGY3TQOJQ", TotpAlgorithm::Sha1);
+        assert!(valid.validate_and_fixup(false).unwrap().is_none());
+
+        let spaced = config("gezd gnbv gy3t qojq", TotpAlgorithm::Sha1);
+        assert!(spaced.check_valid().is_err());
+        assert_eq!(spaced.fixup().unwrap(), valid);
+
+        for bad in [
+            config("", TotpAlgorithm::Sha1),
+            config("not base32!", TotpAlgorithm::Sha1),
+            TotpConfig {
+                period: 0,
+                ..valid.clone()
+            },
+            TotpConfig {
+                digits: 4,
+                ..valid.clone()
+            },
+        ] {
+            assert!(bad.maybe_fixup().is_err(), "{:?} should be invalid", bad);
+        }
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let config: TotpConfig = serde_json::from_str(r#"{"secret": "GEZDGNBVGY3TQOJQ"}"#).unwrap();
+        assert_eq!(config.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!(config.period, DEFAULT_TOTP_PERIOD);
+        assert_eq!(config.digits, DEFAULT_TOTP_DIGITS);
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::json!({
+                "secret": "GEZDGNBVGY3TQOJQ",
+                "algorithm": "SHA1",
+                "period": 30,
+                "digits": 6,
+            })
+        );
+    }
+}
@@ -13,6 +13,7 @@ import org.json.JSONException
 import org.json.JSONObject
 
 private const val NIMBUS_FLAG = "nimbus-cli"
+private const val CONTEXT_KEY = "context"
 private const val EXPERIMENTS_KEY = "experiments"
 private const val LOG_STATE_KEY = "log-state"
 private const val RESET_DB_KEY = "reset-db"
@@ -34,6 +35,10 @@ fun NimbusInterface.initializeTooling(context: Context, intent: Intent) {
         }
     }
 
+    args.context?.let { context ->
+        setTargetingAttributesOverride(JSONObject(context))
+    }
+
     args.experiments?.let { experiments ->
         setExperimentsLocally(experiments)
         val job = applyPendingExperiments()
@@ -64,10 +69,13 @@ private fun createCommandLineArgs(intent: Intent): CliArgs? {
         // through the multiple shells properly. This steps around this issue completely.
         ?.replace("&apos;", "'")
 
+    val context = intent.getStringExtra(CONTEXT_KEY)
+        ?.replace("&apos;", "'")
+
     val resetDatabase = intent.getBooleanExtra(RESET_DB_KEY, false)
     val logState = intent.getBooleanExtra(LOG_STATE_KEY, false)
 
-    return check(CliArgs(resetDatabase, experiments, logState))
+    return check(CliArgs(resetDatabase, experiments, logState, context))
 }
 
 @Suppress("ReturnCount")
@@ -84,6 +92,15 @@ private fun check(args: CliArgs): CliArgs? {
             return null
         }
     }
+    // The context, if present, should be a JSON object of targeting attributes.
+    val context = args.context
+    if (context != null) {
+        try {
+            JSONObject(context)
+        } catch (e: JSONException) {
+            return null
+        }
+    }
     return args
 }
 
@@ -103,10 +120,16 @@ fun createCommandLineArgs(uri: Uri): CliArgs? {
 
     // Percent decoding happens transparently here:
     val experiments = uri.getQueryParameter("--$EXPERIMENTS_KEY")
+    val context = uri.getQueryParameter("--$CONTEXT_KEY")
     val resetDatabase = uri.getBooleanQueryParameter("--$RESET_DB_KEY", false)
     val logState = uri.getBooleanQueryParameter("--$LOG_STATE_KEY", false)
 
-    return check(CliArgs(resetDatabase, experiments, logState))
+    return check(CliArgs(resetDatabase, experiments, logState, context))
 }
 
-data class CliArgs(val resetDatabase: Boolean, val experiments: String?, val logState: Boolean)
+data class CliArgs(
+    val resetDatabase: Boolean,
+    val experiments: String?,
+    val logState: Boolean,
+    val context: String? = null,
+)
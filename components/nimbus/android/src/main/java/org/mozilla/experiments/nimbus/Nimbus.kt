@@ -33,6 +33,7 @@ import org.mozilla.experiments.nimbus.internal.AvailableRandomizationUnits
 import org.mozilla.experiments.nimbus.internal.EnrolledExperiment
 import org.mozilla.experiments.nimbus.internal.EnrollmentChangeEvent
 import org.mozilla.experiments.nimbus.internal.EnrollmentChangeEventType
+import org.mozilla.experiments.nimbus.internal.FeatureConfigObserver
 import org.mozilla.experiments.nimbus.internal.NimbusClient
 import org.mozilla.experiments.nimbus.internal.NimbusClientInterface
 import org.mozilla.experiments.nimbus.internal.NimbusException
@@ -64,6 +65,8 @@ open class Nimbus(
     deviceInfo: NimbusDeviceInfo,
     private val observer: NimbusInterface.Observer? = null,
     delegate: NimbusDelegate,
+    featureIds: List<String> = listOf(),
+    featureDefaults: String = "{}",
 ) : NimbusInterface {
     // An I/O scope is used for reading or writing from the Nimbus's RKV database.
     private val dbScope: CoroutineScope = delegate.dbScope
@@ -119,6 +122,8 @@ open class Nimbus(
             // so we just automatically set it to a dummy value.
             AvailableRandomizationUnits(clientId = null, userId = null, dummy = 0),
         )
+        nimbusClient.registerFeatureIds(featureIds)
+        nimbusClient.registerFeatureDefaults(featureDefaults)
     }
 
     // This is currently not available from the main thread.
@@ -128,6 +133,11 @@ open class Nimbus(
         nimbusClient.getActiveExperiments()
     } ?: emptyList()
 
+    @WorkerThread
+    override fun getEnrollmentStateChecksum(): String = withCatchAll("getEnrollmentStateChecksum") {
+        nimbusClient.getEnrollmentStateChecksum()
+    } ?: ""
+
     @WorkerThread
     override fun getAvailableExperiments(): List<AvailableExperiment> = withCatchAll("getAvailableExperiments") {
         nimbusClient.getAvailableExperiments()
@@ -164,6 +174,14 @@ open class Nimbus(
         nimbusClient.getExperimentBranch(experimentId)
     }
 
+    override fun isFeatureInControlBranch(featureId: String): Boolean = withCatchAll("isFeatureInControlBranch") {
+        nimbusClient.isFeatureInControlBranch(featureId)
+    } ?: false
+
+    override fun registerFeatureObserver(featureId: String, observer: FeatureConfigObserver) {
+        nimbusClient.registerFeatureObserver(featureId, observer)
+    }
+
     override fun getVariables(featureId: String, recordExposureEvent: Boolean): Variables =
         getFeatureConfigVariablesJson(featureId)?.let { json ->
             if (recordExposureEvent) {
@@ -327,6 +345,12 @@ open class Nimbus(
         nimbusClient.setExperimentsLocally(payload)
     }
 
+    override fun setTargetingAttributesOverride(attributes: JSONObject?) {
+        withCatchAll("setTargetingAttributesOverride") {
+            nimbusClient.setTargetingAttributesOverride(attributes)
+        }
+    }
+
     override fun resetEnrollmentsDatabase() =
         dbScope.launch {
             withCatchAll("resetEnrollments") {
@@ -334,6 +358,14 @@ open class Nimbus(
             }
         }
 
+    override fun setExperimentsLocallyWithEnrollment(payload: String, branches: Map<String, String>) =
+        dbScope.launch {
+            withCatchAll("setExperimentsLocallyWithEnrollment") {
+                nimbusClient.setExperimentsLocallyWithEnrollment(payload, branches).also(::recordExperimentTelemetryEvents)
+                postEnrolmentCalculation()
+            }
+        }
+
     @WorkerThread
     @VisibleForTesting(otherwise = VisibleForTesting.PRIVATE)
     internal fun setGlobalUserParticipationOnThisThread(active: Boolean) = withCatchAll("setGlobalUserParticipation") {
@@ -496,18 +528,15 @@ open class Nimbus(
     }
 
     private fun recordExposureFromFeature(featureId: String) = withCatchAll("recordExposureFromFeature") {
-        // First, we get the enrolled feature, if there is one, for this id.
-        val enrollment = nimbusClient.getEnrollmentByFeature(featureId) ?: return@withCatchAll
-        // If branch is null, this is a rollout, and we're not interested in recording
-        // exposure for rollouts.
-        val branch = enrollment.branch ?: return@withCatchAll
-        // Finally, if we do have an experiment for the given featureId, we will record the
-        // exposure event in Glean. This is to protect against accidentally recording an event
-        // for an experiment without an active enrollment.
+        // Nimbus tells us which experiment and branch the feature is enrolled in, if any. Rollouts
+        // have no branch, and we're not interested in recording exposure for them. This is to
+        // protect against accidentally recording an event for an experiment without an active
+        // enrollment.
+        val exposure = nimbusClient.recordFeatureExposure(featureId, false) ?: return@withCatchAll
         NimbusEvents.exposure.record(
             NimbusEvents.ExposureExtra(
-                experiment = enrollment.slug,
-                branch = branch,
+                experiment = exposure.experimentSlug,
+                branch = exposure.branch,
                 featureId = featureId,
             ),
         )
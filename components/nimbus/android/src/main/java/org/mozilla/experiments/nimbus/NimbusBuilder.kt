@@ -188,6 +188,20 @@ abstract class AbstractNimbusBuilder<T : NimbusInterface>(val context: Context)
      */
     protected fun getCoenrollingFeatureIds(): List<String> =
         featureManifest?.getCoenrollingFeatureIds() ?: listOf()
+
+    /**
+     * Returns a list of the ids of every feature in the manifest. Implementers of [newNimbus]
+     * should use this to pass into the [NimbusInterface] instance.
+     */
+    protected fun getFeatureIds(): List<String> =
+        featureManifest?.getFeatureIds() ?: listOf()
+
+    /**
+     * Returns the default values of every feature in the manifest as a JSON string.
+     * Implementers of [newNimbus] should use this to pass into the [NimbusInterface] instance.
+     */
+    protected fun getFeatureDefaults(): String =
+        featureManifest?.getFeatureDefaults() ?: "{}"
 }
 
 private class Observer(
@@ -215,6 +229,8 @@ class DefaultNimbusBuilder(context: Context) : AbstractNimbusBuilder<NimbusInter
             deviceInfo = createDeviceInfo(),
             delegate = createDelegate(),
             observer = createObserver(),
+            featureIds = getFeatureIds(),
+            featureDefaults = getFeatureDefaults(),
         )
 
     override fun newNimbusDisabled() = NullNimbus(context)
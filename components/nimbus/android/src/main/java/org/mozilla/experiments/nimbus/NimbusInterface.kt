@@ -10,9 +10,11 @@ import android.os.Build
 import androidx.annotation.AnyThread
 import androidx.annotation.RawRes
 import kotlinx.coroutines.Job
+import org.json.JSONObject
 import org.mozilla.experiments.nimbus.internal.AvailableExperiment
 import org.mozilla.experiments.nimbus.internal.EnrolledExperiment
 import org.mozilla.experiments.nimbus.internal.ExperimentBranch
+import org.mozilla.experiments.nimbus.internal.FeatureConfigObserver
 import java.time.Duration
 import java.util.concurrent.TimeUnit
 
@@ -33,6 +35,16 @@ interface NimbusInterface : FeaturesInterface, GleanPlumbInterface, NimbusEventS
      */
     fun getActiveExperiments(): List<EnrolledExperiment> = listOf()
 
+    /**
+     * Get a checksum of the slugs and branches of the currently enrolled experiments and rollouts.
+     *
+     * This is the same across restarts as long as the enrollments are, so it can be stored and
+     * compared on the next launch to tell whether anything has changed.
+     *
+     * @return A hex string, or an empty string if it couldn't be calculated
+     */
+    fun getEnrollmentStateChecksum(): String = ""
+
     /**
      * Get the list of available experiments
      *
@@ -50,6 +62,32 @@ interface NimbusInterface : FeaturesInterface, GleanPlumbInterface, NimbusEventS
     @AnyThread
     fun getExperimentBranch(experimentId: String): String? = null
 
+    /**
+     * Whether the given feature is under experiment, and the user is in the control (or holdback)
+     * branch of that experiment.
+     *
+     * @param featureId The string feature id that applies to the feature under experiment.
+     *
+     * @return `true` if the user is in the experiment's reference branch.
+     */
+    @AnyThread
+    fun isFeatureInControlBranch(featureId: String): Boolean = false
+
+    /**
+     * Register an observer which is called whenever the configuration of the given feature
+     * changes, because experiments or rollouts for it were applied, enrolled in or unenrolled
+     * from. This is finer grained than [Observer.onUpdatesApplied], for apps which cache feature
+     * configurations.
+     *
+     * The observer is called on the thread which made the change, after the new configuration is
+     * available from [getVariables].
+     *
+     * @param featureId The string feature id to observe.
+     * @param observer The observer to call with the feature id.
+     */
+    @AnyThread
+    fun registerFeatureObserver(featureId: String, observer: FeatureConfigObserver) = Unit
+
     /**
      * Get the list of experiment branches for the given experiment
      *
@@ -160,6 +198,28 @@ interface NimbusInterface : FeaturesInterface, GleanPlumbInterface, NimbusEventS
      */
     fun resetEnrollmentsDatabase(): Job = Job()
 
+    /**
+     * Testing method to apply the experiments in `payload`, and then enroll in the given branch
+     * of some of them, bypassing targeting and bucketing. This lets UI tests land in a known
+     * branch without the network.
+     *
+     * This only works with a megazord built with the `nimbus-testing` feature; otherwise it
+     * fails, and the failure is logged.
+     *
+     * @param payload the experiments, in the same JSON format that is delivered from the server.
+     * @param branches a map of experiment slugs to the slug of the branch to enroll in.
+     */
+    fun setExperimentsLocallyWithEnrollment(payload: String, branches: Map<String, String>): Job = Job()
+
+    /**
+     * Testing method to override some of the targeting attributes, e.g. a region or locale that
+     * cannot be set on the device. This changes how experiments are evaluated, so is only used
+     * during QA of the app.
+     *
+     * @param attributes the attributes to override, or `null` to remove all overrides.
+     */
+    fun setTargetingAttributesOverride(attributes: JSONObject?) = Unit
+
     /**
      * Opt into a specific branch for the given experiment.
      *
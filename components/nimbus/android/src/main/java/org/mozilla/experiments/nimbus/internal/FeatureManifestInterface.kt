@@ -43,4 +43,15 @@ interface FeatureManifestInterface<T> {
     fun getFeature(featureId: String): FeatureHolder<*>?
 
     fun getCoenrollingFeatureIds(): List<String>
+
+    /**
+     * Get the ids of every feature in the manifest.
+     */
+    fun getFeatureIds(): List<String>
+
+    /**
+     * Get the default values of every feature in the manifest, as a JSON object of feature id
+     * to feature JSON.
+     */
+    fun getFeatureDefaults(): String
 }
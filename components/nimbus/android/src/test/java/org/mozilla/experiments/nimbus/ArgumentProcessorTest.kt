@@ -84,6 +84,22 @@ class ArgumentProcessorTest {
         isInvalid("{\"data\": 1}")
     }
 
+    @Test
+    fun `test createCliArgsFromUri context JSON`() {
+        val context = "{\"region\":\"DE\"}"
+        val encoded = URLEncoder.encode(context, "UTF-8")
+        val obs = createCommandLineArgs(
+            Uri.parse("my-app://foo?--nimbus-cli&--context=$encoded"),
+        )
+        assertNotNull(obs)
+        assertEquals(CliArgs(false, null, false, context), obs)
+
+        val obs1 = createCommandLineArgs(
+            Uri.parse("my-app://foo?--nimbus-cli&--context=[]"),
+        )
+        assertNull(obs1)
+    }
+
     @Test
     fun `test createCliArgsFromUri with badly formed URL safely fails`() {
         val experiments = "{\"data\":[]}"
@@ -197,6 +197,7 @@ fn main() -> Result<()> {
         server_url: Some(server_url.to_string()),
         bucket_name: None,
         collection_name: collection_name.to_string(),
+        filter_context: None,
     };
 
     let aru = AvailableRandomizationUnits::with_client_id(&client_id);
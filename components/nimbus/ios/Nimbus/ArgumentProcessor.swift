@@ -9,6 +9,9 @@ enum ArgumentProcessor {
         if args.resetDatabase {
             nimbus.resetEnrollmentsDatabase().waitUntilFinished()
         }
+        if let context = args.context {
+            nimbus.setTargetingAttributesOverride(context)
+        }
         if let experiments = args.experiments {
             nimbus.setExperimentsLocally(experiments)
             nimbus.applyPendingExperiments().waitUntilFinished()
@@ -32,6 +35,7 @@ enum ArgumentProcessor {
         }
 
         var experiments: String?
+        var context: String?
         var resetDatabase = false
         var logState = false
         var meantForUs = false
@@ -49,6 +53,8 @@ enum ArgumentProcessor {
                 meantForUs = flag(item.value)
             case "--experiments":
                 experiments = item.value?.removingPercentEncoding
+            case "--context":
+                context = item.value?.removingPercentEncoding
             case "--reset-db":
                 resetDatabase = flag(item.value)
             case "--log-state":
@@ -62,7 +68,7 @@ enum ArgumentProcessor {
             return nil
         }
 
-        return check(args: CliArgs(resetDatabase: resetDatabase, experiments: experiments, logState: logState))
+        return check(args: CliArgs(resetDatabase: resetDatabase, experiments: experiments, logState: logState, context: context))
     }
 
     static func createCommandLineArgs(args: [String]?) -> CliArgs? {
@@ -85,6 +91,8 @@ enum ArgumentProcessor {
                 key = "version"
             case "--experiments":
                 key = "experiments"
+            case "--context":
+                key = "context"
             case "--reset-db":
                 resetDatabase = true
             case "--log-state":
@@ -105,8 +113,9 @@ enum ArgumentProcessor {
         }
 
         let experiments = argMap["experiments"]
+        let context = argMap["context"]
 
-        return check(args: CliArgs(resetDatabase: resetDatabase, experiments: experiments, logState: logState))
+        return check(args: CliArgs(resetDatabase: resetDatabase, experiments: experiments, logState: logState, context: context))
     }
 
     static func check(args: CliArgs) -> CliArgs? {
@@ -116,6 +125,11 @@ enum ArgumentProcessor {
                 return nil
             }
         }
+        if let string = args.context {
+            guard (try? Dictionary.parse(jsonString: string)) != nil else {
+                return nil
+            }
+        }
         return args
     }
 }
@@ -124,6 +138,7 @@ struct CliArgs: Equatable {
     let resetDatabase: Bool
     let experiments: String?
     let logState: Bool
+    var context: String?
 }
 
 public extension NimbusInterface {
@@ -33,4 +33,10 @@ public protocol FeatureManifestInterface {
     func invalidateCachedValues()
 
     func getCoenrollingFeatureIds() -> [String]
+
+    /// Get the ids of every feature in the manifest.
+    func getFeatureIds() -> [String]
+
+    /// Get the default values of every feature in the manifest, as a JSON object of feature id to feature JSON.
+    func getFeatureDefaults() -> String
 }
@@ -111,18 +111,16 @@ extension Nimbus: FeaturesInterface {
     }
 
     func recordExposureFromFeature(featureId: String) {
-        // First, we get the enrolled feature, if there is one, for this id.
-        if let enrollment = getEnrollmentByFeature(featureId: featureId),
-           // If branch is nil, this is a rollout, and we're not interested in recording
-           // exposure for rollouts.
-           let branch = enrollment.branch
-        {
-            // Finally, if we do have an experiment for the given featureId, we will record the
-            // exposure event in Glean. This is to protect against accidentally recording an event
-            // for an experiment without an active enrollment.
+        // Nimbus tells us which experiment and branch the feature is enrolled in, if any. Rollouts
+        // have no branch, and we're not interested in recording exposure for them. This is to
+        // protect against accidentally recording an event for an experiment without an active
+        // enrollment.
+        if let exposure = catchAll({
+            try nimbusClient.recordFeatureExposure(featureId: featureId, oncePerSession: false)
+        }) {
             GleanMetrics.NimbusEvents.exposure.record(GleanMetrics.NimbusEvents.ExposureExtra(
-                branch: branch,
-                experiment: enrollment.slug,
+                branch: exposure.branch,
+                experiment: exposure.experimentSlug,
                 featureId: featureId
             ))
         }
@@ -326,6 +324,12 @@ extension Nimbus: NimbusUserConfiguration {
         } ?? []
     }
 
+    public func getEnrollmentStateChecksum() -> String {
+        return catchAll {
+            try nimbusClient.getEnrollmentStateChecksum()
+        } ?? ""
+    }
+
     public func getAvailableExperiments() -> [AvailableExperiment] {
         return catchAll {
             try nimbusClient.getAvailableExperiments()
@@ -427,6 +431,31 @@ extension Nimbus: NimbusStartup {
         }
     }
 
+    /// Testing method to apply the experiments in `experimentsJson`, and then enroll in the given
+    /// branch of some of them, bypassing targeting and bucketing. This lets UI tests land in a
+    /// known branch without the network.
+    ///
+    /// This only works with a megazord built with the `nimbus-testing` feature; otherwise it
+    /// fails, and the error is passed to the error reporter.
+    ///
+    /// - Parameter experimentsJson the experiments, in the same format delivered by RemoteSettings.
+    /// - Parameter branches a map of experiment slugs to the slug of the branch to enroll in.
+    public func setExperimentsLocallyWithEnrollment(_ experimentsJson: String,
+                                                    branches: [String: String]) -> Operation
+    {
+        catchAll(dbQueue) { _ in
+            let changes = try self.nimbusClient.setExperimentsLocallyWithEnrollment(
+                experimentsJson: experimentsJson,
+                forcedBranches: branches
+            )
+            self.postEnrollmentCalculation(changes)
+        }
+    }
+
+    public func setTargetingAttributesOverride(_ attributesJson: String?) {
+        nimbusClient.setTargetingAttributesOverride(overrides: attributesJson)
+    }
+
     public func dumpStateToLog() {
         catchAll {
             try self.nimbusClient.dumpStateToLog()
@@ -440,6 +469,16 @@ extension Nimbus: NimbusBranchInterface {
             try nimbusClient.getExperimentBranch(id: experimentId)
         }
     }
+
+    public func isFeatureInControlBranch(featureId: String) -> Bool {
+        return catchAll {
+            try nimbusClient.isFeatureInControlBranch(featureId: featureId)
+        } ?? false
+    }
+
+    public func registerFeatureObserver(featureId: String, observer: FeatureConfigObserver) {
+        nimbusClient.registerFeatureObserver(featureId: featureId, observer: observer)
+    }
 }
 
 extension Nimbus: NimbusMessagingProtocol {
@@ -479,6 +518,10 @@ public extension NimbusDisabled {
         return []
     }
 
+    func getEnrollmentStateChecksum() -> String {
+        return ""
+    }
+
     func getAvailableExperiments() -> [AvailableExperiment] {
         return []
     }
@@ -487,6 +530,8 @@ public extension NimbusDisabled {
         return nil
     }
 
+    func registerFeatureObserver(featureId _: String, observer _: FeatureConfigObserver) {}
+
     func getVariables(featureId _: String, sendExposureEvent _: Bool) -> Variables {
         return NilVariables.instance
     }
@@ -517,6 +562,8 @@ public extension NimbusDisabled {
         BlockOperation()
     }
 
+    func setTargetingAttributesOverride(_: String?) {}
+
     func optOut(_: String) {}
 
     func optIn(_: String, branch _: String) {}
@@ -29,6 +29,34 @@ public protocol NimbusBranchInterface {
     ///
     /// - Note: Consumers of this API should switch to using the Feature Variables API
     func getExperimentBranch(experimentId: String) -> String?
+
+    /// Whether the given feature is under experiment, and the user is in the control (or holdback)
+    /// branch of that experiment.
+    ///
+    /// - Parameter featureId The string feature id that applies to the feature under experiment.
+    /// - Returns `true` if the user is in the experiment's reference branch.
+    func isFeatureInControlBranch(featureId: String) -> Bool
+
+    /// Register an observer which is called whenever the configuration of the given feature changes,
+    /// because experiments or rollouts for it were applied, enrolled in or unenrolled from. This is
+    /// finer grained than the `.nimbusExperimentsApplied` notification, for apps which cache feature
+    /// configurations.
+    ///
+    /// The observer is called on the thread which made the change, after the new configuration is
+    /// available from `getVariables`.
+    ///
+    /// - Parameters:
+    ///     - featureId The string feature id to observe.
+    ///     - observer The observer to call with the feature id.
+    func registerFeatureObserver(featureId: String, observer: FeatureConfigObserver)
+}
+
+public extension NimbusBranchInterface {
+    /// By default, no feature is in a control branch, so that existing implementations of
+    /// this protocol don't need to add this.
+    func isFeatureInControlBranch(featureId _: String) -> Bool {
+        return false
+    }
 }
 
 public extension FeaturesInterface {
@@ -103,6 +131,14 @@ public protocol NimbusStartup {
     /// Testing method to reset the enrollments and experiments database back to its initial state.
     func resetEnrollmentsDatabase() -> Operation
 
+    /// Testing method to override some of the targeting attributes, e.g. a region or locale that
+    /// cannot be set on the device. This changes how experiments are evaluated, so is only used
+    /// during QA of the app.
+    ///
+    /// - Parameter attributesJson a JSON object of the attributes to override, or `nil` to remove
+    ///             all overrides.
+    func setTargetingAttributesOverride(_ attributesJson: String?)
+
     /// Enable or disable fetching of experiments.
     ///
     /// This is performed on a background thread.
@@ -156,6 +192,15 @@ public protocol NimbusUserConfiguration {
     ///
     func getActiveExperiments() -> [EnrolledExperiment]
 
+    /// Get a checksum of the slugs and branches of the currently enrolled experiments and rollouts.
+    ///
+    /// This is the same across restarts as long as the enrollments are, so it can be stored and
+    /// compared on the next launch to tell whether anything has changed.
+    ///
+    /// - Returns A hex string, or an empty string if it couldn't be calculated
+    ///
+    func getEnrollmentStateChecksum() -> String
+
     /// For a given experiment id, returns the branches available.
     ///
     /// - Parameter experimentId the specifies the experiment.
@@ -234,10 +234,20 @@ public class NimbusBuilder {
         featureManifest?.getCoenrollingFeatureIds() ?? []
     }
 
+    func getFeatureIds() -> [String] {
+        featureManifest?.getFeatureIds() ?? []
+    }
+
+    func getFeatureDefaults() -> String {
+        featureManifest?.getFeatureDefaults() ?? "{}"
+    }
+
     func newNimbus(_ appInfo: NimbusAppSettings, serverSettings: NimbusServerSettings?) throws -> NimbusInterface {
         try Nimbus.create(serverSettings,
                           appSettings: appInfo,
                           coenrollingFeatureIds: getCoenrollingFeatureIds(),
+                          featureIds: getFeatureIds(),
+                          featureDefaults: getFeatureDefaults(),
                           dbPath: dbFilePath,
                           resourceBundles: resourceBundles,
                           errorReporter: errorReporter)
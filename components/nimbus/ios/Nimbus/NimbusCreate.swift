@@ -24,6 +24,8 @@ public extension Nimbus {
     /// - Parameters:
     ///     - server: the server that experiments will be downloaded from
     ///     - appSettings: the name and channel for the app
+    ///     - featureIds: the ids of every feature in the app's feature manifest
+    ///     - featureDefaults: the default values of the features in the app's feature manifest, as JSON
     ///     - dbPath: the path on disk for the database
     ///     - resourceBundles: an optional array of `Bundle` objects that are used to lookup text and images
     ///     - enabled: intended for FeatureFlags. If false, then return a dummy `Nimbus` instance. Defaults to `true`.
@@ -35,6 +37,8 @@ public extension Nimbus {
         _ server: NimbusServerSettings?,
         appSettings: NimbusAppSettings,
         coenrollingFeatureIds: [String] = [],
+        featureIds: [String] = [],
+        featureDefaults: String = "{}",
         dbPath: String,
         resourceBundles: [Bundle] = [Bundle.main],
         enabled: Bool = true,
@@ -60,6 +64,8 @@ public extension Nimbus {
             // so we just automatically set it to a dummy value.
             availableRandomizationUnits: AvailableRandomizationUnits(clientId: nil, userId: nil, dummy: 0)
         )
+        nimbusClient.registerFeatureIds(featureIds: featureIds)
+        try nimbusClient.registerFeatureDefaults(defaultsJson: featureDefaults)
 
         return Nimbus(nimbusClient: nimbusClient, resourceBundles: resourceBundles, errorReporter: errorReporter)
     }
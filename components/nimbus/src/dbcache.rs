@@ -4,12 +4,13 @@
 
 use crate::enrollment::{
     get_enrollments, map_features_by_feature_id, EnrolledFeature, EnrolledFeatureConfig,
+    FeatureExposure,
 };
 use crate::error::{NimbusError, Result};
 use crate::persistence::{Database, StoreId, Writer};
 use crate::EnrolledExperiment;
 use crate::{enrollment::ExperimentEnrollment, Experiment};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::RwLock;
 
 // This module manages an in-memory cache of the database, so that some
@@ -22,6 +23,7 @@ use std::sync::RwLock;
 struct CachedData {
     pub experiments_by_slug: HashMap<String, EnrolledExperiment>,
     pub features_by_feature_id: HashMap<String, EnrolledFeatureConfig>,
+    pub reference_branches_by_slug: HashMap<String, String>,
 }
 
 // This is the public cache API. Each NimbusClient can create one of these and
@@ -44,12 +46,16 @@ impl DatabaseCache {
     //    and thus prevent the possibility of caching stale data.
     //  * By taking ownership of the `Writer`, we ensure that the calling code
     //    updates the cache after all of its writes have been performed.
+    //
+    // Returns the ids of the features whose merged config is different from the
+    // one which was cached before, sorted. Before the first update, no features
+    // have a config.
     pub fn commit_and_update(
         &self,
         db: &Database,
         writer: Writer,
         coenrolling_ids: &HashSet<&str>,
-    ) -> Result<()> {
+    ) -> Result<Vec<String>> {
         // By passing in the active `writer` we read the state of enrollments
         // as written by the calling code, before it's committed to the db.
         let enrollments = get_enrollments(db, &writer)?;
@@ -69,6 +75,12 @@ impl DatabaseCache {
         let features_by_feature_id =
             map_features_by_feature_id(&enrollments, &experiments, coenrolling_ids);
 
+        // The reference branch is the control (or holdback) of an experiment.
+        let reference_branches_by_slug = experiments
+            .iter()
+            .filter_map(|e| Some((e.slug.clone(), e.reference_branch.clone()?)))
+            .collect();
+
         // This is where testing tools would override i.e. replace experimental feature configurations.
         // i.e. testing tools would cause custom feature configs to be stored in a Store.
         // Here, we get those overrides out of the store, and merge it with this map.
@@ -78,6 +90,7 @@ impl DatabaseCache {
         let data = CachedData {
             experiments_by_slug,
             features_by_feature_id,
+            reference_branches_by_slug,
         };
 
         // Try to commit the change to disk and update the cache as close
@@ -88,8 +101,9 @@ impl DatabaseCache {
         // and we don't want to risk blocking the main thread.
         writer.commit()?;
         let mut cached = self.data.write().unwrap();
+        let changed_feature_ids = changed_feature_ids(cached.as_ref(), &data);
         cached.replace(data);
-        Ok(())
+        Ok(changed_feature_ids)
     }
 
     // Abstracts safely referencing our cached data.
@@ -139,6 +153,22 @@ impl DatabaseCache {
         })
     }
 
+    // The experiment and branch which should be reported when `feature_id` is exposed to the
+    // user, or `None` if it isn't under experiment. Rollouts don't have a branch, and aren't
+    // reported.
+    pub fn get_feature_exposure(&self, feature_id: &str) -> Result<Option<FeatureExposure>> {
+        self.get_data(|data| {
+            let feature = data.features_by_feature_id.get(feature_id)?;
+            let branch = feature.branch.clone()?;
+            Some(FeatureExposure {
+                is_control: data.reference_branches_by_slug.get(&feature.slug) == Some(&branch),
+                experiment_slug: feature.slug.clone(),
+                branch,
+                feature_id: feature_id.to_string(),
+            })
+        })
+    }
+
     pub fn get_active_experiments(&self) -> Result<Vec<EnrolledExperiment>> {
         self.get_data(|data| {
             data.experiments_by_slug
@@ -148,3 +178,30 @@ impl DatabaseCache {
         })
     }
 }
+
+fn changed_feature_ids(old: Option<&CachedData>, new: &CachedData) -> Vec<String> {
+    let old_features = old.map(|data| &data.features_by_feature_id);
+    let feature_ids: BTreeSet<&String> = new
+        .features_by_feature_id
+        .keys()
+        .chain(
+            old_features
+                .into_iter()
+                .flat_map(|features| features.keys()),
+        )
+        .collect();
+    feature_ids
+        .into_iter()
+        .filter(|feature_id| {
+            let old_value = old_features
+                .and_then(|features| features.get(*feature_id))
+                .map(|feature| &feature.feature.value);
+            let new_value = new
+                .features_by_feature_id
+                .get(*feature_id)
+                .map(|feature| &feature.feature.value);
+            old_value != new_value
+        })
+        .cloned()
+        .collect()
+}
@@ -4,7 +4,7 @@
 use crate::{
     defaults::Defaults,
     error::{NimbusError, Result},
-    evaluator::evaluate_enrollment,
+    evaluator::{evaluate_enrollment_with_decision, EnrollmentDecision},
     json, AvailableRandomizationUnits, Experiment, FeatureConfig, NimbusTargetingHelper,
     SLUG_REPLACEMENT_PATTERN,
 };
@@ -12,6 +12,7 @@ use crate::{
 use ::uuid::Uuid;
 use serde_derive::*;
 use std::{
+    cell::RefCell,
     collections::{HashMap, HashSet},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -74,6 +75,9 @@ pub enum DisqualifiedReason {
     NotTargeted,
     /// The bucketing has changed for an experiment.
     NotSelected,
+    /// The user opted-out from experiments in general while we were enrolled. Unlike `OptOut`,
+    /// the enrollment is evaluated again if they opt back in.
+    GlobalOptOut,
 }
 
 // Every experiment has an ExperimentEnrollment, even when we aren't enrolled.
@@ -96,6 +100,7 @@ impl ExperimentEnrollment {
         experiment: &Experiment,
         targeting_helper: &NimbusTargetingHelper,
         out_enrollment_events: &mut Vec<EnrollmentChangeEvent>,
+        out_decision: &mut Option<EnrollmentDecision>,
     ) -> Result<Self> {
         Ok(if !is_user_participating {
             Self {
@@ -112,12 +117,13 @@ impl ExperimentEnrollment {
                 },
             }
         } else {
-            let enrollment = evaluate_enrollment(
+            let (enrollment, decision) = evaluate_enrollment_with_decision(
                 nimbus_id,
                 available_randomization_units,
                 experiment,
                 targeting_helper,
             )?;
+            *out_decision = Some(decision);
             log::debug!(
                 "Experiment '{}' is new - enrollment status is {:?}",
                 &enrollment.slug,
@@ -169,18 +175,20 @@ impl ExperimentEnrollment {
         updated_experiment: &Experiment,
         targeting_helper: &NimbusTargetingHelper,
         out_enrollment_events: &mut Vec<EnrollmentChangeEvent>,
+        out_decision: &mut Option<EnrollmentDecision>,
     ) -> Result<Self> {
         Ok(match &self.status {
             EnrollmentStatus::NotEnrolled { .. } | EnrollmentStatus::Error { .. } => {
                 if !is_user_participating || updated_experiment.is_enrollment_paused {
                     self.clone()
                 } else {
-                    let updated_enrollment = evaluate_enrollment(
+                    let (updated_enrollment, decision) = evaluate_enrollment_with_decision(
                         nimbus_id,
                         available_randomization_units,
                         updated_experiment,
                         targeting_helper,
                     )?;
+                    *out_decision = Some(decision);
                     log::debug!(
                         "Experiment '{}' with enrollment {:?} is now {:?}",
                         &self.slug,
@@ -205,7 +213,7 @@ impl ExperimentEnrollment {
                         &self.slug
                     );
                     let updated_enrollment =
-                        self.disqualify_from_enrolled(DisqualifiedReason::OptOut);
+                        self.disqualify_from_enrolled(DisqualifiedReason::GlobalOptOut);
                     out_enrollment_events.push(updated_enrollment.get_change_event());
                     updated_enrollment
                 } else if !updated_experiment.has_branch(branch) {
@@ -219,12 +227,13 @@ impl ExperimentEnrollment {
                     // we don't need to update our enrollment
                     self.clone()
                 } else {
-                    let evaluated_enrollment = evaluate_enrollment(
+                    let (evaluated_enrollment, decision) = evaluate_enrollment_with_decision(
                         nimbus_id,
                         available_randomization_units,
                         updated_experiment,
                         targeting_helper,
                     )?;
+                    *out_decision = Some(decision);
                     match evaluated_enrollment.status {
                         EnrollmentStatus::Error { .. } => {
                             let updated_enrollment =
@@ -241,6 +250,15 @@ impl ExperimentEnrollment {
                             out_enrollment_events.push(updated_enrollment.get_change_event());
                             updated_enrollment
                         }
+                        EnrollmentStatus::NotEnrolled {
+                            reason: NotEnrolledReason::NotSelected,
+                        } if updated_experiment.is_enrollment_paused
+                            && !updated_experiment.is_rollout =>
+                        {
+                            // Once an experiment's enrollment is paused, the clients already
+                            // enrolled should stay enrolled, even if the bucketing changes.
+                            self.clone()
+                        }
                         EnrollmentStatus::NotEnrolled {
                             reason: NotEnrolledReason::NotSelected,
                         } => {
@@ -272,23 +290,51 @@ impl ExperimentEnrollment {
                     Self {
                         slug: self.slug.clone(),
                         status: EnrollmentStatus::Disqualified {
-                            reason: DisqualifiedReason::OptOut,
+                            // Keep track of the enrollments which should come back when the
+                            // user opts in again.
+                            reason: match reason {
+                                DisqualifiedReason::GlobalOptOut => {
+                                    DisqualifiedReason::GlobalOptOut
+                                }
+                                _ => DisqualifiedReason::OptOut,
+                            },
                             enrollment_id: *enrollment_id,
                             branch: branch.clone(),
                         },
                     }
+                } else if matches!(reason, DisqualifiedReason::GlobalOptOut) {
+                    // The user opted back in, so this is evaluated like a new enrollment.
+                    let (evaluated_enrollment, decision) = evaluate_enrollment_with_decision(
+                        nimbus_id,
+                        available_randomization_units,
+                        updated_experiment,
+                        targeting_helper,
+                    )?;
+                    *out_decision = Some(decision);
+                    match evaluated_enrollment.status {
+                        EnrollmentStatus::Enrolled { .. } => {
+                            log::debug!(
+                                "Experiment '{}' is enrolled again (global opt-in)",
+                                &self.slug
+                            );
+                            out_enrollment_events.push(evaluated_enrollment.get_change_event());
+                            evaluated_enrollment
+                        }
+                        _ => self.clone(),
+                    }
                 } else if updated_experiment.is_rollout
                     && matches!(
                         reason,
                         DisqualifiedReason::NotSelected | DisqualifiedReason::NotTargeted,
                     )
                 {
-                    let evaluated_enrollment = evaluate_enrollment(
+                    let (evaluated_enrollment, decision) = evaluate_enrollment_with_decision(
                         nimbus_id,
                         available_randomization_units,
                         updated_experiment,
                         targeting_helper,
                     )?;
+                    *out_decision = Some(decision);
                     match evaluated_enrollment.status {
                         EnrollmentStatus::Enrolled { .. } => evaluated_enrollment,
                         _ => self.clone(),
@@ -361,6 +407,20 @@ impl ExperimentEnrollment {
                     reason: NotEnrolledReason::OptOut, // Explicitly set the reason to OptOut.
                 },
             },
+            EnrollmentStatus::Disqualified {
+                ref branch,
+                enrollment_id,
+                reason: DisqualifiedReason::GlobalOptOut,
+            } => Self {
+                // The user doesn't want this one back when they opt in to experiments again.
+                // The disqualification was already reported, so there's no event.
+                slug: self.slug.clone(),
+                status: EnrollmentStatus::Disqualified {
+                    reason: DisqualifiedReason::OptOut,
+                    enrollment_id,
+                    branch: branch.clone(),
+                },
+            },
             EnrollmentStatus::Disqualified { .. }
             | EnrollmentStatus::WasEnrolled { .. }
             | EnrollmentStatus::Error { .. } => {
@@ -457,7 +517,7 @@ impl ExperimentEnrollment {
                 match reason {
                     DisqualifiedReason::NotSelected => Some("bucketing"),
                     DisqualifiedReason::NotTargeted => Some("targeting"),
-                    DisqualifiedReason::OptOut => Some("optout"),
+                    DisqualifiedReason::OptOut | DisqualifiedReason::GlobalOptOut => Some("optout"),
                     DisqualifiedReason::Error => Some("error"),
                 },
                 EnrollmentChangeEventType::Disqualification,
@@ -557,6 +617,38 @@ impl EnrollmentStatus {
         };
         updated
     }
+
+    /// The name of the status, without any of its details.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnrollmentStatus::Enrolled { .. } => "Enrolled",
+            EnrollmentStatus::NotEnrolled { .. } => "NotEnrolled",
+            EnrollmentStatus::Disqualified { .. } => "Disqualified",
+            EnrollmentStatus::WasEnrolled { .. } => "WasEnrolled",
+            EnrollmentStatus::Error { .. } => "Error",
+        }
+    }
+
+    /// The branch of the status, if it has one.
+    pub fn branch(&self) -> Option<&str> {
+        match self {
+            EnrollmentStatus::Enrolled { branch, .. }
+            | EnrollmentStatus::Disqualified { branch, .. }
+            | EnrollmentStatus::WasEnrolled { branch, .. } => Some(branch),
+            EnrollmentStatus::NotEnrolled { .. } | EnrollmentStatus::Error { .. } => None,
+        }
+    }
+
+    /// Why we are (or aren't) enrolled, if the status says.
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            EnrollmentStatus::Enrolled { reason, .. } => Some(format!("{:?}", reason)),
+            EnrollmentStatus::NotEnrolled { reason } => Some(format!("{:?}", reason)),
+            EnrollmentStatus::Disqualified { reason, .. } => Some(format!("{:?}", reason)),
+            EnrollmentStatus::WasEnrolled { .. } => None,
+            EnrollmentStatus::Error { reason } => Some(reason.clone()),
+        }
+    }
 }
 
 pub(crate) trait ExperimentMetadata {
@@ -595,6 +687,7 @@ pub fn get_enrollments<'r>(
                         user_facing_description: experiment.user_facing_description,
                         branch_slug: branch.to_string(),
                         enrollment_id: enrollment_id.to_string(),
+                        is_enrollment_paused: experiment.is_enrollment_paused,
                     });
                 }
                 _ => {
@@ -614,6 +707,8 @@ pub(crate) struct EnrollmentsEvolver<'a> {
     available_randomization_units: &'a AvailableRandomizationUnits,
     targeting_helper: &'a NimbusTargetingHelper,
     coenrolling_feature_ids: &'a HashSet<&'a str>,
+    // The targeting and bucketing decisions made while evolving, keyed by experiment slug.
+    decisions: RefCell<HashMap<String, EnrollmentDecision>>,
 }
 
 impl<'a> EnrollmentsEvolver<'a> {
@@ -628,9 +723,17 @@ impl<'a> EnrollmentsEvolver<'a> {
             available_randomization_units,
             targeting_helper,
             coenrolling_feature_ids,
+            decisions: Default::default(),
         }
     }
 
+    /// Take the targeting and bucketing decisions made by the enrollments evolved so far,
+    /// keyed by experiment slug. Experiments which weren't evaluated have no decision.
+    #[cfg_attr(not(feature = "stateful"), allow(unused))]
+    pub(crate) fn take_decisions(&self) -> HashMap<String, EnrollmentDecision> {
+        self.decisions.take()
+    }
+
     /// Convenient wrapper around `evolve_enrollments` that fetches the current state of experiments,
     /// enrollments and user participation from the database.
     #[cfg(feature = "stateful")]
@@ -971,7 +1074,8 @@ impl<'a> EnrollmentsEvolver<'a> {
             .targeting_helper
             .put("is_already_enrolled", is_already_enrolled);
 
-        Ok(match (prev_experiment, next_experiment, prev_enrollment) {
+        let mut decision = None;
+        let next_enrollment = match (prev_experiment, next_experiment, prev_enrollment) {
             // New experiment.
             (None, Some(experiment), None) => Some(ExperimentEnrollment::from_new_experiment(
                 is_user_participating,
@@ -980,6 +1084,7 @@ impl<'a> EnrollmentsEvolver<'a> {
                 experiment,
                 &th,
                 out_enrollment_events,
+                &mut decision,
             )?),
             // Experiment deleted remotely.
             (Some(_), None, Some(enrollment)) => {
@@ -994,6 +1099,7 @@ impl<'a> EnrollmentsEvolver<'a> {
                     experiment,
                     &th,
                     out_enrollment_events,
+                    &mut decision,
                 )?)
             }
             (None, None, Some(enrollment)) => enrollment.maybe_garbage_collect(),
@@ -1012,7 +1118,13 @@ impl<'a> EnrollmentsEvolver<'a> {
                     "evolve_experiment called with nothing that could evolve or be evolved",
                 ))
             }
-        })
+        };
+        if let (Some(decision), Some(enrollment)) = (decision, &next_enrollment) {
+            self.decisions
+                .borrow_mut()
+                .insert(enrollment.slug.clone(), decision);
+        }
+        Ok(next_enrollment)
     }
 }
 
@@ -1280,7 +1392,17 @@ impl From<&EnrolledFeatureConfig> for EnrolledFeature {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The experiment and branch a feature was exposed to the user from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FeatureExposure {
+    pub feature_id: String,
+    pub experiment_slug: String,
+    pub branch: String,
+    /// Whether `branch` is the experiment's reference branch, i.e. its control or holdback.
+    pub is_control: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct EnrollmentChangeEvent {
     pub experiment_slug: String,
     pub branch_slug: String,
@@ -1307,6 +1429,67 @@ impl EnrollmentChangeEvent {
     }
 }
 
+/// How a single experiment was evaluated by one `apply_pending_experiments_with_report()` call.
+#[cfg(feature = "stateful")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentEvaluation {
+    pub experiment_slug: String,
+    pub is_rollout: bool,
+    /// Whether the experiment's targeting matched, or `None` if it wasn't evaluated, e.g.
+    /// because the experiment was removed, had a feature conflict, or the targeting
+    /// expression failed.
+    pub targeting_matched: Option<bool>,
+    /// Whether the bucketing selected us, or `None` if it wasn't evaluated, because the
+    /// targeting didn't match or there was no randomization unit.
+    pub bucketing_selected: Option<bool>,
+    /// The name of the enrollment status before the call, or `None` if there wasn't one.
+    pub previous_status: Option<String>,
+    /// The name of the enrollment status after the call, or `None` if it was discarded.
+    pub status: Option<String>,
+    pub branch_slug: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// The result of `apply_pending_experiments_with_report()`: how every experiment was
+/// evaluated, sorted by slug, along with the usual enrollment change events.
+#[cfg(feature = "stateful")]
+#[derive(Debug, Clone, Default)]
+pub struct EnrollmentReport {
+    pub evaluations: Vec<ExperimentEvaluation>,
+    pub events: Vec<EnrollmentChangeEvent>,
+}
+
+/// The enrollment status of a single experiment, as returned by `get_enrollment_statuses()`.
+#[cfg(feature = "stateful")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentEnrollmentStatus {
+    pub experiment_slug: String,
+    /// The name of the enrollment status, or `"NotFound"` if there's no enrollment for the slug.
+    pub status: String,
+    pub branch_slug: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[cfg(feature = "stateful")]
+impl ExperimentEnrollmentStatus {
+    pub(crate) fn new(slug: String, enrollment: Option<&ExperimentEnrollment>) -> Self {
+        match enrollment {
+            Some(enrollment) => Self {
+                experiment_slug: slug,
+                status: enrollment.status.name().to_string(),
+                branch_slug: enrollment.status.branch().map(str::to_string),
+                reason: enrollment.status.reason(),
+            },
+            None => Self {
+                experiment_slug: slug,
+                status: "NotFound".to_string(),
+                branch_slug: None,
+                reason: None,
+            },
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum EnrollmentChangeEventType {
     Enrollment,
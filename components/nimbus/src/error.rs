@@ -68,6 +68,19 @@ pub enum NimbusError {
     CirrusError(#[from] CirrusClientError),
 }
 
+// Without the stateful client, the database can still be read, for example by nimbus-cli. The
+// errors stay the same as Cirrus's in that case, so that enabling the database doesn't change
+// the Cirrus bindings, and database errors are reported as IO errors.
+#[cfg(all(feature = "rkv-safe-mode", not(feature = "stateful")))]
+impl From<rkv::StoreError> for NimbusError {
+    fn from(e: rkv::StoreError) -> Self {
+        NimbusError::IOError(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            e.to_string(),
+        ))
+    }
+}
+
 #[cfg(feature = "stateful")]
 #[derive(Debug, thiserror::Error)]
 pub enum BehaviorError {
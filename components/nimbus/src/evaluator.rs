@@ -141,63 +141,102 @@ pub fn evaluate_enrollment(
     exp: &Experiment,
     th: &NimbusTargetingHelper,
 ) -> Result<ExperimentEnrollment> {
+    Ok(evaluate_enrollment_with_decision(nimbus_id, available_randomization_units, exp, th)?.0)
+}
+
+/// The targeting and bucketing decisions made while evaluating an enrollment.
+///
+/// Each is `None` if it wasn't made: the targeting if the expression failed to evaluate, and the
+/// bucketing if the targeting didn't match or there was no suitable randomization unit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EnrollmentDecision {
+    pub(crate) targeting_matched: Option<bool>,
+    pub(crate) bucketing_selected: Option<bool>,
+}
+
+/// Like `evaluate_enrollment`, but also returns the targeting and bucketing decisions that
+/// led to the enrollment.
+pub(crate) fn evaluate_enrollment_with_decision(
+    nimbus_id: &Uuid,
+    available_randomization_units: &AvailableRandomizationUnits,
+    exp: &Experiment,
+    th: &NimbusTargetingHelper,
+) -> Result<(ExperimentEnrollment, EnrollmentDecision)> {
+    let mut decision = EnrollmentDecision::default();
     if !is_experiment_available(th, exp, true) {
-        return Ok(ExperimentEnrollment {
-            slug: exp.slug.clone(),
-            status: EnrollmentStatus::NotEnrolled {
-                reason: NotEnrolledReason::NotTargeted,
+        decision.targeting_matched = Some(false);
+        return Ok((
+            ExperimentEnrollment {
+                slug: exp.slug.clone(),
+                status: EnrollmentStatus::NotEnrolled {
+                    reason: NotEnrolledReason::NotTargeted,
+                },
             },
-        });
+            decision,
+        ));
     }
 
     // Get targeting out of the way - "if let chains" are experimental,
     // otherwise we could improve this.
     if let Some(expr) = &exp.targeting {
         if let Some(status) = targeting(expr, th) {
-            return Ok(ExperimentEnrollment {
-                slug: exp.slug.clone(),
-                status,
-            });
+            if matches!(status, EnrollmentStatus::NotEnrolled { .. }) {
+                decision.targeting_matched = Some(false);
+            }
+            return Ok((
+                ExperimentEnrollment {
+                    slug: exp.slug.clone(),
+                    status,
+                },
+                decision,
+            ));
         }
     }
-    Ok(ExperimentEnrollment {
-        slug: exp.slug.clone(),
-        status: {
-            let bucket_config = exp.bucket_config.clone();
-            match available_randomization_units
-                .get_value(&nimbus_id.to_string(), &bucket_config.randomization_unit)
-            {
-                Some(id) => {
-                    if sampling::bucket_sample(
-                        vec![id.to_owned(), bucket_config.namespace],
-                        bucket_config.start,
-                        bucket_config.count,
-                        bucket_config.total,
-                    )? {
-                        EnrollmentStatus::new_enrolled(
-                            EnrolledReason::Qualified,
-                            &choose_branch(&exp.slug, &exp.branches, id)?.clone().slug,
-                        )
-                    } else {
-                        EnrollmentStatus::NotEnrolled {
-                            reason: NotEnrolledReason::NotSelected,
-                        }
+    decision.targeting_matched = Some(true);
+    let status = {
+        let bucket_config = exp.bucket_config.clone();
+        match available_randomization_units
+            .get_value(&nimbus_id.to_string(), &bucket_config.randomization_unit)
+        {
+            Some(id) => {
+                let is_selected = sampling::bucket_sample(
+                    vec![id.to_owned(), bucket_config.namespace],
+                    bucket_config.start,
+                    bucket_config.count,
+                    bucket_config.total,
+                )?;
+                decision.bucketing_selected = Some(is_selected);
+                if is_selected {
+                    EnrollmentStatus::new_enrolled(
+                        EnrolledReason::Qualified,
+                        &choose_branch(&exp.slug, &exp.branches, id)?.clone().slug,
+                    )
+                } else {
+                    EnrollmentStatus::NotEnrolled {
+                        reason: NotEnrolledReason::NotSelected,
                     }
                 }
-                None => {
-                    // XXX: When we link in glean, it would be nice if we could emit
-                    // a failure telemetry event here.
-                    log::info!(
-                        "Could not find a suitable randomization unit for {}. Skipping experiment.",
-                        &exp.slug
-                    );
-                    EnrollmentStatus::Error {
-                        reason: "No randomization unit".into(),
-                    }
+            }
+            None => {
+                // XXX: When we link in glean, it would be nice if we could emit
+                // a failure telemetry event here.
+                log::info!(
+                    "Could not find a suitable randomization unit for {}. Skipping experiment.",
+                    &exp.slug
+                );
+                EnrollmentStatus::Error {
+                    reason: "No randomization unit".into(),
                 }
             }
+        }
+    };
+    Ok((
+        ExperimentEnrollment {
+            slug: exp.slug.clone(),
+            status,
         },
-    })
+        decision,
+    ))
 }
 
 /// Check if an experiment is available for this app defined by this `AppContext`.
@@ -15,7 +15,7 @@ pub mod error;
 pub mod schema;
 pub mod versioning;
 
-pub use enrollment::{EnrolledFeature, EnrollmentStatus};
+pub use enrollment::{EnrolledFeature, EnrollmentStatus, FeatureExposure};
 pub use error::{NimbusError, Result};
 #[cfg(debug_assertions)]
 pub use evaluator::evaluate_enrollment;
@@ -23,6 +23,11 @@ pub use matcher::AppContext;
 pub use schema::*;
 pub use targeting::NimbusTargetingHelper;
 
+// Tools which read the database, like nimbus-cli, can use this without the rest of the stateful
+// client.
+#[cfg(feature = "rkv-safe-mode")]
+pub mod persistence;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "stateful")] {
         mod behavior;
@@ -31,7 +36,6 @@ cfg_if::cfg_if! {
         mod updating;
 
         pub mod nimbus_client;
-        pub mod persistence;
 
         pub use crate::nimbus_client::*;
         pub use remote_settings::RemoteSettingsConfig;
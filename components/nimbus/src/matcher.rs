@@ -36,7 +36,7 @@ use serde_json::{Map, Value};
 /// - `home_directory`: The application's home directory
 /// - `custom_targeting_attributes`: Contains attributes specific to the application, derived by the application
 #[cfg(feature = "stateful")]
-#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct AppContext {
     pub app_name: String,
     pub app_id: String,
@@ -76,7 +76,7 @@ pub struct AppContext {
 /// - `user_agent`: The user agent as defined by the browser (e.g. "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:109.0) Gecko/20100101 Firefox/114.0" )
 /// - `custom_targeting_attributes`: Contains attributes specific to the application, derived by the application
 #[cfg(not(feature = "stateful"))]
-#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct AppContext {
     pub app_name: String,
     pub app_id: String,
@@ -8,25 +8,30 @@ use crate::{
     dbcache::DatabaseCache,
     defaults::Defaults,
     enrollment::{
-        get_global_user_participation, opt_in_with_branch, opt_out, reset_telemetry_identifiers,
-        set_global_user_participation, EnrolledFeature, EnrollmentChangeEvent,
-        EnrollmentChangeEventType, EnrollmentStatus, EnrollmentsEvolver, ExperimentEnrollment,
+        get_global_user_participation, map_features_by_feature_id, opt_in_with_branch, opt_out,
+        reset_telemetry_identifiers, set_global_user_participation, EnrolledFeature,
+        EnrollmentChangeEvent, EnrollmentChangeEventType, EnrollmentReport, EnrollmentStatus,
+        EnrollmentsEvolver, ExperimentEnrollment, ExperimentEnrollmentStatus, ExperimentEvaluation,
+        ExperimentMetadata, FeatureExposure,
     },
     error::BehaviorError,
-    evaluator::{is_experiment_available, TargetingAttributes},
+    evaluator::{is_experiment_available, EnrollmentDecision, TargetingAttributes},
+    json,
     matcher::AppContext,
     persistence::{Database, StoreId, Writer},
     schema::parse_experiments,
     strings::fmt_with_map,
     updating::{read_and_remove_pending_experiments, write_pending_experiments},
     AvailableExperiment, AvailableRandomizationUnits, EnrolledExperiment, Experiment,
-    ExperimentBranch, NimbusError, NimbusTargetingHelper, Result,
+    ExperimentBranch, ExperimentSchedule, NimbusError, NimbusTargetingHelper, Result,
+    SLUG_REPLACEMENT_PATTERN,
 };
 use chrono::{DateTime, NaiveDateTime, Utc};
 use once_cell::sync::OnceCell;
 use remote_settings::RemoteSettingsConfig;
 use serde_json::{Map, Value};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, MutexGuard};
 use uuid::Uuid;
@@ -46,6 +51,24 @@ pub struct InternalMutableState {
     pub(crate) available_randomization_units: AvailableRandomizationUnits,
     // Application level targeting attributes
     targeting_attributes: TargetingAttributes,
+    // Test only overrides of the targeting attributes
+    targeting_attributes_override: Option<JsonObject>,
+    // The ids of the features in the app's feature manifest
+    manifest_feature_ids: Vec<String>,
+    // The default values of the features in the app's feature manifest, by feature id
+    manifest_feature_defaults: JsonObject,
+}
+
+impl InternalMutableState {
+    // The context that targeting is evaluated against: the targeting attributes,
+    // with any overrides applied on top.
+    fn targeting_context(&self) -> Result<Value> {
+        let targeting = serde_json::to_value(&self.targeting_attributes)?;
+        Ok(match &self.targeting_attributes_override {
+            Some(overrides) => Value::Object(overrides.clone()).defaults(&targeting)?,
+            None => targeting,
+        })
+    }
 }
 
 /// Nimbus is the main struct representing the experiments state
@@ -62,6 +85,18 @@ pub struct NimbusClient {
     db_path: PathBuf,
     coenrolling_feature_ids: Vec<String>,
     event_store: Arc<Mutex<EventStore>>,
+    // The observers registered for each feature id. Not part of `mutable_state`, because
+    // they're called after that's unlocked.
+    feature_observers: Mutex<HashMap<String, Vec<Arc<dyn FeatureConfigObserver>>>>,
+    // The exposures recorded since the client was created. Not part of `mutable_state`,
+    // because that's held while doing IO, and recording an exposure mustn't block.
+    recorded_exposures: Mutex<HashSet<FeatureExposure>>,
+}
+
+/// Notified when the config of a feature changes, so that apps which cache feature configs
+/// know when to resolve them again.
+pub trait FeatureConfigObserver: Send + Sync {
+    fn on_feature_config_changed(&self, feature_id: String);
 }
 
 impl NimbusClient {
@@ -79,6 +114,9 @@ impl NimbusClient {
         let mutable_state = Mutex::new(InternalMutableState {
             available_randomization_units,
             targeting_attributes: app_context.clone().into(),
+            targeting_attributes_override: None,
+            manifest_feature_ids: Vec::new(),
+            manifest_feature_defaults: Default::default(),
         });
 
         Ok(Self {
@@ -90,6 +128,8 @@ impl NimbusClient {
             coenrolling_feature_ids,
             db: OnceCell::default(),
             event_store: Arc::default(),
+            feature_observers: Default::default(),
+            recorded_exposures: Default::default(),
         })
     }
 
@@ -103,6 +143,14 @@ impl NimbusClient {
         state.targeting_attributes.clone()
     }
 
+    // Overrides some of the targeting attributes, e.g. to test a region or
+    // locale that can't be set on the device - TEST ONLY - should not be
+    // exposed to real clients, as it changes how experiments are evaluated.
+    pub fn set_targeting_attributes_override(&self, overrides: Option<JsonObject>) {
+        let mut state = self.mutable_state.lock().unwrap();
+        state.targeting_attributes_override = overrides;
+    }
+
     pub fn initialize(&self) -> Result<()> {
         let db = self.db()?;
         // We're not actually going to write, we just want to exclude concurrent writers.
@@ -110,7 +158,9 @@ impl NimbusClient {
 
         let mut state = self.mutable_state.lock().unwrap();
         self.begin_initialize(db, &mut writer, &mut state)?;
-        self.end_initialize(db, writer, &mut state)?;
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
 
         Ok(())
     }
@@ -130,12 +180,15 @@ impl NimbusClient {
 
     // These are tasks which should be in the initialize and apply_pending_experiments
     // but should happen after the enrollment calculations are done.
+    //
+    // Returns the ids of the features whose config changed. Callers should pass them to
+    // `notify_feature_observers()` once they've unlocked `state`.
     fn end_initialize(
         &self,
         db: &Database,
         writer: Writer,
         state: &mut MutexGuard<InternalMutableState>,
-    ) -> Result<()> {
+    ) -> Result<Vec<String>> {
         self.update_ta_active_experiments(db, &writer, state)?;
         let coenrolling_ids = self
             .coenrolling_feature_ids
@@ -143,14 +196,81 @@ impl NimbusClient {
             .map(|s| s.as_str())
             .collect();
         self.database_cache
-            .commit_and_update(db, writer, &coenrolling_ids)?;
-        Ok(())
+            .commit_and_update(db, writer, &coenrolling_ids)
+    }
+
+    /// Registers an observer which is called whenever the config of `feature_id` changes,
+    /// because experiments or rollouts for it were applied, enrolled in or unenrolled from.
+    /// The observer is called after the change is visible to `get_feature_config_variables()`,
+    /// on the thread which made the change.
+    pub fn register_feature_observer(
+        &self,
+        feature_id: String,
+        observer: Box<dyn FeatureConfigObserver>,
+    ) {
+        self.feature_observers
+            .lock()
+            .unwrap()
+            .entry(feature_id)
+            .or_default()
+            .push(Arc::from(observer));
+    }
+
+    // This must not be called with `mutable_state` locked, because observers may call back
+    // into the client.
+    fn notify_feature_observers(&self, changed_feature_ids: Vec<String>) {
+        for feature_id in changed_feature_ids {
+            // Cloned, so that observers can register more observers.
+            let observers = match self.feature_observers.lock().unwrap().get(&feature_id) {
+                Some(observers) => observers.clone(),
+                None => continue,
+            };
+            for observer in observers {
+                observer.on_feature_config_changed(feature_id.clone());
+            }
+        }
     }
 
     pub fn get_enrollment_by_feature(&self, feature_id: String) -> Result<Option<EnrolledFeature>> {
         self.database_cache.get_enrollment_by_feature(&feature_id)
     }
 
+    /// Records that `feature_id` was exposed to the user, and returns the experiment and branch
+    /// the exposure event should be reported for. Returns `None` if the feature isn't under
+    /// experiment, or if `once_per_session` is true and the same exposure was already recorded
+    /// by this client.
+    ///
+    /// Note: the contract for this function is that it never blocks on IO.
+    pub fn record_feature_exposure(
+        &self,
+        feature_id: String,
+        once_per_session: bool,
+    ) -> Result<Option<FeatureExposure>> {
+        let exposure = match self.database_cache.get_feature_exposure(&feature_id)? {
+            Some(exposure) => exposure,
+            None => return Ok(None),
+        };
+        let is_new = self
+            .recorded_exposures
+            .lock()
+            .unwrap()
+            .insert(exposure.clone());
+        Ok(if is_new || !once_per_session {
+            Some(exposure)
+        } else {
+            None
+        })
+    }
+
+    /// Whether `feature_id` is under experiment, and the user is in the control (or holdback)
+    /// branch of that experiment.
+    pub fn is_feature_in_control_branch(&self, feature_id: String) -> Result<bool> {
+        Ok(self
+            .database_cache
+            .get_feature_exposure(&feature_id)?
+            .map_or(false, |exposure| exposure.is_control))
+    }
+
     // Note: the contract for this function is that it never blocks on IO.
     pub fn get_experiment_branch(&self, slug: String) -> Result<Option<String>> {
         self.database_cache.get_experiment_branch(&slug)
@@ -169,6 +289,112 @@ impl NimbusClient {
             .ok_or(NimbusError::NoSuchExperiment(slug))
     }
 
+    pub fn get_experiment_schedule(&self, slug: String) -> Result<Option<ExperimentSchedule>> {
+        let db = self.db()?;
+        let reader = db.read()?;
+        Ok(db
+            .get_store(StoreId::Experiments)
+            .get::<Experiment, _>(&reader, &slug)?
+            .and_then(|exp| exp.get_schedule()))
+    }
+
+    /// Returns the recipe of an applied experiment as the JSON we stored. Experiments which
+    /// have been fetched but not yet applied are not included.
+    pub fn get_experiment_recipe(&self, slug: String) -> Result<Option<String>> {
+        let db = self.db()?;
+        let reader = db.read()?;
+        Ok(db
+            .get_store(StoreId::Experiments)
+            .get_json(&reader, &slug)?
+            .map(str::to_string))
+    }
+
+    /// Returns the enrollment status of each of `slugs`, in the same order, from a single read
+    /// of the database. Slugs we have no enrollment for are reported as `"NotFound"`.
+    pub fn get_enrollment_statuses(
+        &self,
+        slugs: Vec<String>,
+    ) -> Result<Vec<ExperimentEnrollmentStatus>> {
+        let db = self.db()?;
+        let reader = db.read()?;
+        let store = db.get_store(StoreId::Enrollments);
+        slugs
+            .into_iter()
+            .map(|slug| {
+                let enrollment = store.get::<ExperimentEnrollment, _>(&reader, &slug)?;
+                Ok(ExperimentEnrollmentStatus::new(slug, enrollment.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Returns the feature JSON that a branch of an applied experiment would configure
+    /// `feature_id` with, merged over the values from any rollouts the user is enrolled in
+    /// and then over the defaults registered from the feature manifest, so it's the complete
+    /// configuration the feature would see. This doesn't change any enrollments.
+    pub fn get_branch_feature_values(
+        &self,
+        experiment_slug: String,
+        branch_slug: String,
+        feature_id: String,
+    ) -> Result<Option<String>> {
+        let db = self.db()?;
+        let reader = db.read()?;
+        let experiments: Vec<Experiment> =
+            db.get_store(StoreId::Experiments).collect_all(&reader)?;
+        let feature = experiments
+            .iter()
+            .find(|exp| exp.slug == experiment_slug)
+            .and_then(|exp| exp.get_branch(&branch_slug))
+            .and_then(|branch| {
+                branch
+                    .get_feature_configs()
+                    .into_iter()
+                    .find(|f| f.feature_id == feature_id)
+            });
+        let mut value = match feature {
+            Some(feature) => feature.value,
+            None => return Ok(None),
+        };
+        json::replace_str_in_map(&mut value, SLUG_REPLACEMENT_PATTERN, &experiment_slug);
+
+        // If we're previewing a rollout, it replaces itself rather than merging over itself.
+        let rollouts: Vec<Experiment> = experiments
+            .into_iter()
+            .filter(|exp| exp.is_rollout() && exp.slug != experiment_slug)
+            .collect();
+        let enrollments: Vec<ExperimentEnrollment> =
+            db.get_store(StoreId::Enrollments).collect_all(&reader)?;
+        let coenrolling_ids = self
+            .coenrolling_feature_ids
+            .iter()
+            .map(|s| s.as_str())
+            .collect();
+        let value = match map_features_by_feature_id(&enrollments, &rollouts, &coenrolling_ids)
+            .get(&feature_id)
+        {
+            Some(rollout) => value.defaults(&rollout.feature.value)?,
+            None => value,
+        };
+        let value = {
+            let state = self.mutable_state.lock().unwrap();
+            match state.manifest_feature_defaults.get(&feature_id) {
+                Some(defaults) => value.defaults(defaults)?,
+                None => value,
+            }
+        };
+        Ok(Some(serde_json::to_string(&value)?))
+    }
+
+    /// Tells the client the default values of the features in the app's feature manifest, as
+    /// a JSON object of feature id to feature JSON returned by `getFeatureDefaults()` of the
+    /// FML generated code. These are merged under the values from `get_branch_feature_values`.
+    pub fn register_feature_defaults(&self, defaults_json: String) -> Result<()> {
+        let defaults: JsonObject = serde_json::from_str(&defaults_json)?;
+        let mut state = self.mutable_state.lock().unwrap();
+        state.manifest_feature_defaults = defaults;
+        Ok(())
+    }
+
     pub fn get_global_user_participation(&self) -> Result<bool> {
         let db = self.db()?;
         let reader = db.read()?;
@@ -188,8 +414,11 @@ impl NimbusClient {
             db.get_store(StoreId::Experiments).collect_all(&writer)?;
         // We pass the existing experiments as "updated experiments"
         // to the evolver.
-        let events = self.evolve_experiments(db, &mut writer, &mut state, &existing_experiments)?;
-        self.end_initialize(db, writer, &mut state)?;
+        let (events, _) =
+            self.evolve_experiments(db, &mut writer, &mut state, &existing_experiments)?;
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
         Ok(events)
     }
 
@@ -197,6 +426,47 @@ impl NimbusClient {
         self.database_cache.get_active_experiments()
     }
 
+    /// Returns a hex SHA-256 of the slugs and branches of the active experiments and rollouts,
+    /// so the app can store it and cheaply tell whether they've changed since it last looked.
+    /// It only depends on the enrollments, so it's the same across restarts if they are.
+    pub fn get_enrollment_state_checksum(&self) -> Result<String> {
+        let enrollments: BTreeSet<(String, String)> = self
+            .get_active_experiments()?
+            .into_iter()
+            .map(|exp| (exp.slug, exp.branch_slug))
+            .collect();
+        let mut hasher = Sha256::new();
+        for (slug, branch) in enrollments {
+            // Slugs can't contain tabs or newlines, so this can't be ambiguous.
+            hasher.update(format!("{}\t{}\n", slug, branch).as_bytes());
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Tells the client the ids of every feature in the app's feature manifest, as returned by
+    /// `getFeatureIds()` of the FML generated code. The Android and iOS wrappers do this when
+    /// Nimbus is created.
+    pub fn register_feature_ids(&self, feature_ids: Vec<String>) {
+        let mut state = self.mutable_state.lock().unwrap();
+        state.manifest_feature_ids = feature_ids;
+    }
+
+    /// Returns the ids of every feature we know about, sorted: those registered from the
+    /// feature manifest, the coenrolling features, and any features configured by the
+    /// experiments and rollouts which have been applied.
+    pub fn get_feature_ids(&self) -> Result<Vec<String>> {
+        let mut feature_ids: BTreeSet<String> = {
+            let state = self.mutable_state.lock().unwrap();
+            state.manifest_feature_ids.iter().cloned().collect()
+        };
+        feature_ids.extend(self.coenrolling_feature_ids.iter().cloned());
+        for experiment in self.get_all_experiments()? {
+            feature_ids.extend(experiment.get_feature_ids());
+            feature_ids.extend(experiment.feature_ids);
+        }
+        Ok(feature_ids.into_iter().collect())
+    }
+
     pub fn get_all_experiments(&self) -> Result<Vec<Experiment>> {
         let db = self.db()?;
         let reader = db.read()?;
@@ -223,7 +493,9 @@ impl NimbusClient {
         let mut writer = db.write()?;
         let result = opt_in_with_branch(db, &mut writer, &experiment_slug, &branch)?;
         let mut state = self.mutable_state.lock().unwrap();
-        self.end_initialize(db, writer, &mut state)?;
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
         Ok(result)
     }
 
@@ -232,7 +504,9 @@ impl NimbusClient {
         let mut writer = db.write()?;
         let result = opt_out(db, &mut writer, &experiment_slug)?;
         let mut state = self.mutable_state.lock().unwrap();
-        self.end_initialize(db, writer, &mut state)?;
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
         Ok(result)
     }
 
@@ -344,10 +618,13 @@ impl NimbusClient {
         writer: &mut Writer,
         state: &mut InternalMutableState,
         experiments: &[Experiment],
-    ) -> Result<Vec<EnrollmentChangeEvent>> {
+    ) -> Result<(
+        Vec<EnrollmentChangeEvent>,
+        HashMap<String, EnrollmentDecision>,
+    )> {
         let nimbus_id = self.read_or_create_nimbus_id(db, writer)?;
         let targeting_helper =
-            NimbusTargetingHelper::new(&state.targeting_attributes, self.event_store.clone());
+            NimbusTargetingHelper::new(state.targeting_context()?, self.event_store.clone());
         let coenrolling_feature_ids = self
             .coenrolling_feature_ids
             .iter()
@@ -359,10 +636,24 @@ impl NimbusClient {
             &targeting_helper,
             &coenrolling_feature_ids,
         );
-        evolver.evolve_enrollments_in_db(db, writer, experiments)
+        let events = evolver.evolve_enrollments_in_db(db, writer, experiments)?;
+        Ok((events, evolver.take_decisions()))
     }
 
     pub fn apply_pending_experiments(&self) -> Result<Vec<EnrollmentChangeEvent>> {
+        Ok(self.apply_pending_experiments_impl(false)?.events)
+    }
+
+    /// Like `apply_pending_experiments()`, but also reports how each experiment was evaluated:
+    /// its targeting and bucketing results, and its enrollment status before and after.
+    ///
+    /// This is for developer-facing diagnostics, to explain why an enrollment changed. If
+    /// there were no pending experiments, nothing is evaluated and the report is empty.
+    pub fn apply_pending_experiments_with_report(&self) -> Result<EnrollmentReport> {
+        self.apply_pending_experiments_impl(true)
+    }
+
+    fn apply_pending_experiments_impl(&self, with_report: bool) -> Result<EnrollmentReport> {
         log::info!("updating experiment list");
         let db = self.db()?;
         let mut writer = db.write()?;
@@ -373,18 +664,98 @@ impl NimbusClient {
         let mut state = self.mutable_state.lock().unwrap();
         self.begin_initialize(db, &mut writer, &mut state)?;
 
-        let res = match pending_updates {
-            Some(new_experiments) => {
-                self.update_ta_active_experiments(db, &writer, &mut state)?;
-                // Perform the enrollment calculations if there are pending experiments.
-                self.evolve_experiments(db, &mut writer, &mut state, &new_experiments)?
+        let mut report = EnrollmentReport::default();
+        if let Some(new_experiments) = pending_updates {
+            self.update_ta_active_experiments(db, &writer, &mut state)?;
+            // The evolver replaces these, so keep them for the report.
+            let prev = if with_report {
+                let prev_experiments: Vec<Experiment> =
+                    db.get_store(StoreId::Experiments).collect_all(&writer)?;
+                let prev_enrollments: Vec<ExperimentEnrollment> =
+                    db.get_store(StoreId::Enrollments).collect_all(&writer)?;
+                Some((prev_experiments, prev_enrollments))
+            } else {
+                None
+            };
+            // Perform the enrollment calculations if there are pending experiments.
+            let (events, decisions) =
+                self.evolve_experiments(db, &mut writer, &mut state, &new_experiments)?;
+            report.events = events;
+            if let Some((prev_experiments, prev_enrollments)) = prev {
+                report.evaluations = self.evaluate_for_report(
+                    db,
+                    &writer,
+                    &decisions,
+                    &prev_experiments,
+                    &prev_enrollments,
+                    &new_experiments,
+                )?;
             }
-            None => vec![],
-        };
+        }
 
         // Finish up any cleanup, e.g. copying from database in to memory.
-        self.end_initialize(db, writer, &mut state)?;
-        Ok(res)
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
+        Ok(report)
+    }
+
+    fn evaluate_for_report(
+        &self,
+        db: &Database,
+        writer: &Writer,
+        decisions: &HashMap<String, EnrollmentDecision>,
+        prev_experiments: &[Experiment],
+        prev_enrollments: &[ExperimentEnrollment],
+        next_experiments: &[Experiment],
+    ) -> Result<Vec<ExperimentEvaluation>> {
+        let next_enrollments: Vec<ExperimentEnrollment> =
+            db.get_store(StoreId::Enrollments).collect_all(writer)?;
+
+        let prev_experiments: HashMap<_, _> = prev_experiments
+            .iter()
+            .map(|e| (e.slug.as_str(), e))
+            .collect();
+        let next_experiments: HashMap<_, _> = next_experiments
+            .iter()
+            .map(|e| (e.slug.as_str(), e))
+            .collect();
+        let prev_enrollments: HashMap<_, _> = prev_enrollments
+            .iter()
+            .map(|e| (e.slug.as_str(), &e.status))
+            .collect();
+        let next_enrollments: HashMap<_, _> = next_enrollments
+            .iter()
+            .map(|e| (e.slug.as_str(), &e.status))
+            .collect();
+
+        let slugs: BTreeSet<&str> = next_experiments
+            .keys()
+            .chain(prev_enrollments.keys())
+            .copied()
+            .collect();
+        let mut evaluations = Vec::with_capacity(slugs.len());
+        for slug in slugs {
+            let prev_status = prev_enrollments.get(slug).copied();
+            let status = next_enrollments.get(slug).copied();
+            // Only the decisions the evolver actually made are reported; e.g. an experiment
+            // we were opted in to, or which had a feature conflict, has neither.
+            let decision = decisions.get(slug).copied().unwrap_or_default();
+            evaluations.push(ExperimentEvaluation {
+                experiment_slug: slug.to_string(),
+                is_rollout: next_experiments
+                    .get(slug)
+                    .or_else(|| prev_experiments.get(slug))
+                    .map_or(false, |e| e.is_rollout),
+                targeting_matched: decision.targeting_matched,
+                bucketing_selected: decision.bucketing_selected,
+                previous_status: prev_status.map(|s| s.name().to_string()),
+                status: status.map(|s| s.name().to_string()),
+                branch_slug: status.and_then(|s| s.branch()).map(str::to_string),
+                reason: status.and_then(EnrollmentStatus::reason),
+            });
+        }
+        Ok(evaluations)
     }
 
     fn get_installation_date(&self, db: &Database, writer: &mut Writer) -> Result<DateTime<Utc>> {
@@ -489,6 +860,54 @@ impl NimbusClient {
         Ok(())
     }
 
+    /// Like `set_experiments_locally()` followed by `apply_pending_experiments()`, but then
+    /// enrolls in the branches given in `forced_branches` (experiment slug to branch slug),
+    /// bypassing targeting and bucketing for those experiments. They're enrolled as if the
+    /// user had opted in, so later updates keep them in the same branch.
+    ///
+    /// This is for UI and integration tests which need to land in a known branch, and is only
+    /// available with the `testing` feature. Without it, this fails with an `InternalError`.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn set_experiments_locally_with_enrollment(
+        &self,
+        experiments_json: String,
+        forced_branches: HashMap<String, String>,
+    ) -> Result<Vec<EnrollmentChangeEvent>> {
+        self.set_experiments_locally(experiments_json)?;
+        let mut events = self.apply_pending_experiments()?;
+
+        let db = self.db()?;
+        let mut writer = db.write()?;
+        // Sorted, so the events come out in the same order every time.
+        let forced_branches: BTreeSet<_> = forced_branches.into_iter().collect();
+        for (experiment_slug, branch) in forced_branches {
+            let experiment = db
+                .get_store(StoreId::Experiments)
+                .get::<Experiment, _>(&writer, &experiment_slug)?
+                .ok_or_else(|| NimbusError::NoSuchExperiment(experiment_slug.clone()))?;
+            let enrollment =
+                ExperimentEnrollment::from_explicit_opt_in(&experiment, &branch, &mut events)?;
+            db.get_store(StoreId::Enrollments)
+                .put(&mut writer, &experiment_slug, &enrollment)?;
+        }
+        let mut state = self.mutable_state.lock().unwrap();
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
+        Ok(events)
+    }
+
+    #[cfg(not(any(test, feature = "testing")))]
+    pub fn set_experiments_locally_with_enrollment(
+        &self,
+        _experiments_json: String,
+        _forced_branches: HashMap<String, String>,
+    ) -> Result<Vec<EnrollmentChangeEvent>> {
+        Err(NimbusError::InternalError(
+            "set_experiments_locally_with_enrollment needs the `testing` feature",
+        ))
+    }
+
     /// Reset all enrollments and experiments in the database.
     ///
     /// This should only be used in testing.
@@ -497,7 +916,9 @@ impl NimbusClient {
         let mut writer = db.write()?;
         let mut state = self.mutable_state.lock().unwrap();
         db.clear_experiments_and_enrollments(&mut writer)?;
-        self.end_initialize(db, writer, &mut state)?;
+        let changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
         Ok(())
     }
 
@@ -514,6 +935,7 @@ impl NimbusClient {
         new_randomization_units: AvailableRandomizationUnits,
     ) -> Result<Vec<EnrollmentChangeEvent>> {
         let mut events = vec![];
+        let mut changed_feature_ids = vec![];
         let db = self.db()?;
         let mut writer = db.write()?;
         let mut state = self.mutable_state.lock().unwrap();
@@ -530,11 +952,13 @@ impl NimbusClient {
             // The `nimbus_id` itself is a unique identifier.
             // N.B. we do this last, as a signal that all data has been reset.
             store.delete(&mut writer, DB_KEY_NIMBUS_ID)?;
-            self.end_initialize(db, writer, &mut state)?;
+            changed_feature_ids = self.end_initialize(db, writer, &mut state)?;
         }
 
         // (No need to commit `writer` if the above check was false, since we didn't change anything)
         state.available_randomization_units = new_randomization_units;
+        drop(state);
+        self.notify_feature_observers(changed_feature_ids);
 
         Ok(events)
     }
@@ -580,7 +1004,7 @@ impl NimbusClient {
 
     fn merge_additional_context(&self, context: Option<JsonObject>) -> Result<Value> {
         let context = context.map(Value::Object);
-        let targeting = serde_json::to_value(self.get_targeting_attributes())?;
+        let targeting = self.mutable_state.lock().unwrap().targeting_context()?;
         let context = match context {
             Some(v) => v.defaults(&targeting)?,
             None => targeting,
@@ -182,16 +182,21 @@ impl SingleStore {
     where
         R: Readable<'r>,
         T: serde::Serialize + for<'de> serde::Deserialize<'de>,
+    {
+        self.get_json(reader, key)?
+            .map(|data| Ok(serde_json::from_str::<T>(data)?))
+            .transpose()
+    }
+
+    /// Like `get`, but returns the persisted JSON as it was stored, without parsing it.
+    pub fn get_json<'r, R>(&self, reader: &'r R, key: &str) -> Result<Option<&'r str>>
+    where
+        R: Readable<'r>,
     {
         let persisted_data = self.store.get(reader, key)?;
         match persisted_data {
-            Some(data) => {
-                if let rkv::Value::Json(data) = data {
-                    Ok(Some(serde_json::from_str::<T>(data)?))
-                } else {
-                    Err(NimbusError::InvalidPersistedData)
-                }
-            }
+            Some(rkv::Value::Json(data)) => Ok(Some(data)),
+            Some(_) => Err(NimbusError::InvalidPersistedData),
             None => Ok(None),
         }
     }
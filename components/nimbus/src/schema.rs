@@ -3,13 +3,14 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{defaults::Defaults, enrollment::ExperimentMetadata, NimbusError, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde_derive::*;
 use serde_json::{Map, Value};
 use std::collections::HashSet;
 
 const DEFAULT_TOTAL_BUCKETS: u32 = 10000;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnrolledExperiment {
     pub feature_ids: Vec<String>,
     pub slug: String,
@@ -17,6 +18,7 @@ pub struct EnrolledExperiment {
     pub user_facing_description: String,
     pub branch_slug: String,
     pub enrollment_id: String,
+    pub is_enrollment_paused: bool,
 }
 
 // ⚠️ Attention : Changes to this type should be accompanied by a new test  ⚠️
@@ -76,6 +78,40 @@ impl Experiment {
 
         feature_ids.into_iter().collect()
     }
+
+    /// Compute the schedule of this experiment from its recipe.
+    ///
+    /// Returns `None` if the recipe has no start date or no proposed duration, or if the
+    /// start date can't be parsed. If the recipe has an explicit end date, it is used in
+    /// preference to the one computed from the proposed duration.
+    pub(crate) fn get_schedule(&self) -> Option<ExperimentSchedule> {
+        let proposed_duration = self.proposed_duration?;
+        let start_date = parse_recipe_date(self.start_date.as_deref()?)?;
+        let end_date = self
+            .end_date
+            .as_deref()
+            .and_then(parse_recipe_date)
+            .unwrap_or_else(|| start_date + Duration::days(proposed_duration.into()));
+        Some(ExperimentSchedule {
+            slug: self.slug.clone(),
+            start_date: start_date.timestamp_millis(),
+            proposed_duration_days: proposed_duration,
+            end_date: end_date.timestamp_millis(),
+        })
+    }
+}
+
+// Recipe dates are usually plain `YYYY-MM-DD` dates, but we also accept full RFC 3339
+// timestamps.
+#[cfg_attr(not(feature = "stateful"), allow(unused))]
+fn parse_recipe_date(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(date) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    let naive = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)?;
+    Some(DateTime::<Utc>::from_utc(naive, Utc))
 }
 
 impl ExperimentMetadata for Experiment {
@@ -205,19 +241,32 @@ impl BucketConfig {
 }
 
 // This type is passed across the FFI to client consumers, e.g. UI for testing tooling.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AvailableExperiment {
     pub slug: String,
     pub user_facing_name: String,
     pub user_facing_description: String,
     pub branches: Vec<ExperimentBranch>,
     pub reference_branch: Option<String>,
+    pub is_enrollment_paused: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExperimentBranch {
     pub slug: String,
     pub ratio: i32,
 }
 
+// This type is passed across the FFI to client consumers, e.g. UI showing the time
+// remaining in an experiment. Dates are in milliseconds since the epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentSchedule {
+    pub slug: String,
+    pub start_date: i64,
+    pub proposed_duration_days: u32,
+    pub end_date: i64,
+}
+
 impl From<Experiment> for AvailableExperiment {
     fn from(exp: Experiment) -> Self {
         Self {
@@ -226,6 +275,7 @@ impl From<Experiment> for AvailableExperiment {
             user_facing_description: exp.user_facing_description,
             branches: exp.branches.into_iter().map(|b| b.into()).collect(),
             reference_branch: exp.reference_branch,
+            is_enrollment_paused: exp.is_enrollment_paused,
         }
     }
 }
@@ -255,7 +305,7 @@ impl Default for RandomizationUnit {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct AvailableRandomizationUnits {
     pub client_id: Option<String>,
     pub user_id: Option<String>,
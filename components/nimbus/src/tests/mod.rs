@@ -8,6 +8,7 @@ mod test_enrollment;
 mod test_enrollment_bw_compat;
 mod test_evaluator;
 mod test_lib_bw_compat;
+mod test_round_trip;
 mod test_sampling;
 mod test_schema;
 mod test_versioning;
@@ -13,7 +13,7 @@ use crate::{
         EnrollmentChangeEventType, EnrollmentStatus, EnrollmentsEvolver, ExperimentEnrollment,
         NotEnrolledReason,
     },
-    persistence::{Database, Readable, StoreId},
+    persistence::{Database, Readable, StoreId, Writer},
     tests::helpers::{get_test_experiments, no_coenrolling_features},
     AppContext, NimbusTargetingHelper, Result,
 };
@@ -223,6 +223,7 @@ fn test_global_opt_out() -> Result<()> {
         .filter(|enr| matches!(enr.status, EnrollmentStatus::Enrolled { .. }))
         .count();
     assert_eq!(num_enrolled_enrollments, 2);
+    let enrollments_before_opt_out = enrollments;
 
     // Opting out and updating should give us two disqualified enrollments
     set_global_user_participation(&db, &mut writer, false)?;
@@ -235,48 +236,70 @@ fn test_global_opt_out() -> Result<()> {
     assert_eq!(events.len(), 2);
     // We should see 2 experiment enrolments, this time they're both opt outs
     assert_eq!(get_experiment_enrollments(&db, &writer)?.len(), 2);
-
-    assert_eq!(
-        get_experiment_enrollments(&db, &writer)?
+    let count_global_opt_outs = |writer: &Writer| -> Result<usize> {
+        Ok(get_experiment_enrollments(&db, writer)?
             .into_iter()
             .filter(|enr| {
                 matches!(
                     enr.status,
                     EnrollmentStatus::Disqualified {
-                        reason: DisqualifiedReason::OptOut,
+                        reason: DisqualifiedReason::GlobalOptOut,
                         ..
                     }
                 )
             })
-            .count(),
-        2
-    );
+            .count())
+    };
+    assert_eq!(count_global_opt_outs(&writer)?, 2);
 
-    // Opting in again and updating SHOULD NOT enroll us again (we've been disqualified).
+    // Updating while still opted out keeps them that way.
+    let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
+    let events = evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
+    assert!(events.is_empty());
+    assert_eq!(count_global_opt_outs(&writer)?, 2);
+
+    // Opting in again and updating enrolls us again, in the same branches.
     set_global_user_participation(&db, &mut writer, true)?;
 
     let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
     let events = evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
 
+    let re_enrollments = get_enrollments(&db, &writer)?;
+    assert_eq!(re_enrollments.len(), 2);
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|e| matches!(e.change, EnrollmentChangeEventType::Enrollment)));
+    for (before, after) in enrollments_before_opt_out.iter().zip(re_enrollments.iter()) {
+        assert_eq!(before.slug, after.slug);
+        assert_eq!(before.branch_slug, after.branch_slug);
+    }
+
+    // An experiment the user opted out of explicitly doesn't come back.
+    opt_out(&db, &mut writer, &exps[0].slug)?;
+    set_global_user_participation(&db, &mut writer, false)?;
+    let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
+    evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
+    set_global_user_participation(&db, &mut writer, true)?;
+    let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
+    let events = evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
+    assert_eq!(events.len(), 1);
     let enrollments = get_enrollments(&db, &writer)?;
-    assert_eq!(enrollments.len(), 0);
-    assert!(events.is_empty());
+    assert_eq!(enrollments.len(), 1);
+    assert_eq!(enrollments[0].slug, exps[1].slug);
 
-    assert_eq!(
-        get_experiment_enrollments(&db, &writer)?
-            .into_iter()
-            .filter(|enr| {
-                matches!(
-                    enr.status,
-                    EnrollmentStatus::Disqualified {
-                        reason: DisqualifiedReason::OptOut,
-                        ..
-                    }
-                )
-            })
-            .count(),
-        2
-    );
+    // Nor does one the user opted out of explicitly while opted out of experiments in general.
+    set_global_user_participation(&db, &mut writer, false)?;
+    let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
+    evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
+    assert_eq!(count_global_opt_outs(&writer)?, 1);
+    opt_out(&db, &mut writer, &exps[1].slug)?;
+    assert_eq!(count_global_opt_outs(&writer)?, 0);
+    set_global_user_participation(&db, &mut writer, true)?;
+    let evolver = EnrollmentsEvolver::new(&nimbus_id, &aru, &th, &ids);
+    let events = evolver.evolve_enrollments_in_db(&db, &mut writer, &exps)?;
+    assert!(events.is_empty());
+    assert!(get_enrollments(&db, &writer)?.is_empty());
 
     writer.commit()?;
     Ok(())
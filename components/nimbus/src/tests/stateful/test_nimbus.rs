@@ -4,7 +4,8 @@
 
 use crate::enrollment::DisqualifiedReason;
 use crate::tests::helpers::{
-    get_bucketed_rollout, get_targeted_experiment, to_local_experiments_string,
+    get_bucketed_rollout, get_targeted_experiment, get_test_experiments,
+    to_local_experiments_string,
 };
 use crate::{
     behavior::{
@@ -16,13 +17,15 @@ use crate::{
     persistence::Database,
     persistence::StoreId,
     tests::helpers::get_ios_rollout_experiment,
-    AppContext, AvailableRandomizationUnits, Experiment, NimbusClient, TargetingAttributes,
-    DB_KEY_APP_VERSION, DB_KEY_UPDATE_DATE,
+    AppContext, AvailableRandomizationUnits, Experiment, FeatureConfigObserver, FeatureExposure,
+    NimbusClient, NimbusError, TargetingAttributes, DB_KEY_APP_VERSION, DB_KEY_UPDATE_DATE,
 };
 use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 
 #[test]
@@ -1010,6 +1013,95 @@ fn test_fetch_enabled() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_apply_pending_experiments_with_report() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let mut client = NimbusClient::new(
+        app_context.clone(),
+        Default::default(),
+        temp_dir.path(),
+        None,
+        Default::default(),
+    )?;
+    client.with_targeting_attributes(TargetingAttributes {
+        app_context,
+        ..Default::default()
+    });
+    client.initialize()?;
+
+    // Nothing is pending, so nothing is evaluated.
+    let report = client.apply_pending_experiments_with_report()?;
+    assert!(report.evaluations.is_empty());
+    assert!(report.events.is_empty());
+
+    let targeted = get_targeted_experiment("targeted", "true");
+    let mut not_targeted = get_targeted_experiment("not-targeted", "false");
+    not_targeted["featureIds"] = json!(["some-feature-2"]);
+    let not_selected = serde_json::to_value(get_bucketed_rollout("not-selected", 0))?;
+    client.set_experiments_locally(to_local_experiments_string(&[
+        targeted,
+        not_targeted,
+        not_selected,
+    ])?)?;
+    let report = client.apply_pending_experiments_with_report()?;
+
+    let slugs: Vec<_> = report
+        .evaluations
+        .iter()
+        .map(|e| e.experiment_slug.as_str())
+        .collect();
+    assert_eq!(slugs, vec!["not-selected", "not-targeted", "targeted"]);
+
+    let not_selected = &report.evaluations[0];
+    assert!(not_selected.is_rollout);
+    assert_eq!(not_selected.targeting_matched, Some(true));
+    assert_eq!(not_selected.bucketing_selected, Some(false));
+    assert_eq!(not_selected.previous_status, None);
+    assert_eq!(not_selected.status.as_deref(), Some("NotEnrolled"));
+    assert_eq!(not_selected.reason.as_deref(), Some("NotSelected"));
+
+    let not_targeted = &report.evaluations[1];
+    assert!(!not_targeted.is_rollout);
+    assert_eq!(not_targeted.targeting_matched, Some(false));
+    assert_eq!(not_targeted.bucketing_selected, None);
+    assert_eq!(not_targeted.status.as_deref(), Some("NotEnrolled"));
+    assert_eq!(not_targeted.reason.as_deref(), Some("NotTargeted"));
+
+    let targeted = &report.evaluations[2];
+    assert_eq!(targeted.targeting_matched, Some(true));
+    assert_eq!(targeted.bucketing_selected, Some(true));
+    assert_eq!(targeted.status.as_deref(), Some("Enrolled"));
+    assert_eq!(targeted.reason.as_deref(), Some("Qualified"));
+    let branch = targeted.branch_slug.clone().unwrap();
+
+    // The report has the same events as `apply_pending_experiments()` returns.
+    assert_eq!(report.events.len(), 1);
+    assert_eq!(report.events[0].experiment_slug, "targeted");
+    assert_eq!(report.events[0].branch_slug, branch);
+
+    // Removing the experiments reports what happened to the enrollments.
+    client.set_experiments_locally(to_local_experiments_string::<Experiment>(&[])?)?;
+    let report = client.apply_pending_experiments_with_report()?;
+    assert_eq!(report.evaluations.len(), 3);
+    let targeted = &report.evaluations[2];
+    assert_eq!(targeted.experiment_slug, "targeted");
+    assert_eq!(targeted.targeting_matched, None);
+    assert_eq!(targeted.previous_status.as_deref(), Some("Enrolled"));
+    assert_eq!(targeted.status.as_deref(), Some("WasEnrolled"));
+    assert_eq!(targeted.branch_slug.as_deref(), Some(branch.as_str()));
+    // We were never enrolled in the others, so their enrollments were discarded.
+    assert_eq!(report.evaluations[1].status, None);
+
+    Ok(())
+}
+
 #[test]
 fn test_active_enrollment_in_targeting() -> Result<()> {
     let mock_client_id = "client-1".to_string();
@@ -1067,6 +1159,615 @@ fn test_active_enrollment_in_targeting() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_targeting_attributes_override() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        locale: Some("en-US".to_string()),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    let exp = get_targeted_experiment("test-1", "region == 'DE'");
+    client.set_experiments_locally(to_local_experiments_string(&[exp.clone()])?)?;
+    client.apply_pending_experiments()?;
+    assert_eq!(client.get_active_experiments()?.len(), 0);
+
+    // Overriding the region means the experiment is now targeted.
+    let overrides = json!({ "region": "DE" });
+    client.set_targeting_attributes_override(overrides.as_object().cloned());
+    let targeting_helper = client.create_targeting_helper(None)?;
+    assert!(targeting_helper.eval_jexl("region == 'DE'".to_string())?);
+    assert!(targeting_helper.eval_jexl("language == 'en'".to_string())?);
+
+    client.set_experiments_locally(to_local_experiments_string(&[exp])?)?;
+    client.apply_pending_experiments()?;
+    assert_eq!(client.get_active_experiments()?.len(), 1);
+
+    // Removing the override restores the original attributes.
+    client.set_targeting_attributes_override(None);
+    let targeting_helper = client.create_targeting_helper(None)?;
+    assert!(targeting_helper.eval_jexl("region == 'US'".to_string())?);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_experiment_schedule() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    let mut scheduled = get_targeted_experiment("scheduled", "true");
+    scheduled["startDate"] = json!("2023-05-01");
+    scheduled["proposedDuration"] = json!(28);
+    let unscheduled = get_targeted_experiment("unscheduled", "true");
+    client.set_experiments_locally(to_local_experiments_string(&[scheduled, unscheduled])?)?;
+    client.apply_pending_experiments()?;
+
+    let schedule = client
+        .get_experiment_schedule("scheduled".to_string())?
+        .expect("the experiment has a schedule");
+    assert_eq!(schedule.proposed_duration_days, 28);
+    assert_eq!(
+        schedule.end_date - schedule.start_date,
+        Duration::days(28).num_milliseconds()
+    );
+
+    assert_eq!(
+        client.get_experiment_schedule("unscheduled".to_string())?,
+        None
+    );
+    assert_eq!(client.get_experiment_schedule("unknown".to_string())?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_experiment_recipe() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    let exp = get_targeted_experiment("test-1", "true");
+    client.set_experiments_locally(to_local_experiments_string(&[exp.clone()])?)?;
+
+    // The recipe has been fetched, but not applied.
+    assert_eq!(client.get_experiment_recipe("test-1".to_string())?, None);
+
+    client.apply_pending_experiments()?;
+    let recipe = client
+        .get_experiment_recipe("test-1".to_string())?
+        .expect("the recipe has been applied");
+    assert_eq!(
+        serde_json::from_str::<Experiment>(&recipe)?,
+        serde_json::from_value::<Experiment>(exp.clone())?
+    );
+
+    // The stored JSON is returned as-is, so nothing is lost by parsing it.
+    let mut stored = exp;
+    stored["someNewField"] = json!({ "nested": [1, 2, 3] });
+    let db = client.db()?;
+    let mut writer = db.write()?;
+    db.get_store(StoreId::Experiments)
+        .put(&mut writer, "test-1", &stored)?;
+    writer.commit()?;
+    let recipe = client
+        .get_experiment_recipe("test-1".to_string())?
+        .expect("the recipe is still there");
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&recipe)?, stored);
+
+    assert_eq!(client.get_experiment_recipe("unknown".to_string())?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_enrollment_statuses() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    client.set_experiments_locally(to_local_experiments_string(&[
+        get_targeted_experiment("targeted", "true"),
+        get_targeted_experiment("untargeted", "false"),
+    ])?)?;
+    client.apply_pending_experiments()?;
+
+    let statuses = client.get_enrollment_statuses(vec![
+        "untargeted".to_string(),
+        "unknown".to_string(),
+        "targeted".to_string(),
+    ])?;
+    assert_eq!(
+        statuses
+            .iter()
+            .map(|s| (s.experiment_slug.as_str(), s.status.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("untargeted", "NotEnrolled"),
+            ("unknown", "NotFound"),
+            ("targeted", "Enrolled"),
+        ]
+    );
+    assert_eq!(statuses[0].reason.as_deref(), Some("NotTargeted"));
+    assert_eq!(statuses[0].branch_slug, None);
+    assert_eq!(statuses[1].reason, None);
+    assert!(statuses[2].branch_slug.is_some());
+    assert_eq!(statuses[2].reason.as_deref(), Some("Qualified"));
+
+    assert!(client.get_enrollment_statuses(vec![])?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_set_experiments_locally_with_enrollment() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    let experiments_json = to_local_experiments_string(&[
+        get_targeted_experiment("forced", "false"),
+        get_targeted_experiment("bucketed", "true"),
+    ])?;
+
+    // Unknown experiments and branches are errors, rather than silently not enrolling.
+    assert!(matches!(
+        client.set_experiments_locally_with_enrollment(
+            experiments_json.clone(),
+            HashMap::from([("unknown".to_string(), "control".to_string())]),
+        ),
+        Err(NimbusError::NoSuchExperiment(_))
+    ));
+    assert!(matches!(
+        client.set_experiments_locally_with_enrollment(
+            experiments_json.clone(),
+            HashMap::from([("forced".to_string(), "unknown".to_string())]),
+        ),
+        Err(NimbusError::NoSuchBranch(_, _))
+    ));
+
+    // The forced experiment is enrolled even though its targeting doesn't match, and the
+    // others are enrolled as usual.
+    let events = client.set_experiments_locally_with_enrollment(
+        experiments_json.clone(),
+        HashMap::from([("forced".to_string(), "treatment".to_string())]),
+    )?;
+    assert!(events
+        .iter()
+        .any(|e| e.experiment_slug == "forced" && e.branch_slug == "treatment"));
+    assert_eq!(
+        client.get_experiment_branch("forced".to_string())?,
+        Some("treatment".to_string())
+    );
+    assert!(client
+        .get_experiment_branch("bucketed".to_string())?
+        .is_some());
+
+    // Applying the same experiments again keeps the forced branch.
+    client.set_experiments_locally(experiments_json)?;
+    client.apply_pending_experiments()?;
+    assert_eq!(
+        client.get_experiment_branch("forced".to_string())?,
+        Some("treatment".to_string())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_record_feature_exposure() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+    let exposure = |once_per_session| {
+        client.record_feature_exposure("some_control".to_string(), once_per_session)
+    };
+    assert_eq!(exposure(false)?, None);
+
+    let experiment = get_test_experiments().remove(0);
+    let slug = experiment.slug.clone();
+    let rollout = get_bucketed_rollout("rollout", 10_000);
+    let experiments_json = to_local_experiments_string(&[experiment, rollout])?;
+    client.set_experiments_locally_with_enrollment(
+        experiments_json.clone(),
+        HashMap::from([(slug.clone(), "control".to_string())]),
+    )?;
+    let control = FeatureExposure {
+        feature_id: "some_control".to_string(),
+        experiment_slug: slug.clone(),
+        branch: "control".to_string(),
+        is_control: true,
+    };
+    assert_eq!(exposure(true)?, Some(control.clone()));
+    // Once per session exposures are only returned the first time, but others every time.
+    assert_eq!(exposure(true)?, None);
+    assert_eq!(exposure(false)?, Some(control));
+    assert!(client.is_feature_in_control_branch("some_control".to_string())?);
+
+    // Exposures aren't recorded for rollouts.
+    assert_eq!(
+        client.record_feature_exposure("a-feature".to_string(), false)?,
+        None
+    );
+    assert!(!client.is_feature_in_control_branch("a-feature".to_string())?);
+
+    // Changing branch is a new exposure.
+    client.set_experiments_locally_with_enrollment(
+        experiments_json,
+        HashMap::from([(slug.clone(), "treatment".to_string())]),
+    )?;
+    assert_eq!(
+        exposure(true)?,
+        Some(FeatureExposure {
+            feature_id: "some_control".to_string(),
+            experiment_slug: slug,
+            branch: "treatment".to_string(),
+            is_control: false,
+        })
+    );
+    assert!(!client.is_feature_in_control_branch("some_control".to_string())?);
+
+    Ok(())
+}
+
+#[test]
+fn test_feature_observers() -> Result<()> {
+    struct TestObserver(Arc<Mutex<Vec<String>>>);
+
+    impl FeatureConfigObserver for TestObserver {
+        fn on_feature_config_changed(&self, feature_id: String) {
+            self.0.lock().unwrap().push(feature_id);
+        }
+    }
+
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    let changed = Arc::new(Mutex::new(Vec::new()));
+    let take_changed = || std::mem::take(&mut *changed.lock().unwrap());
+    client.register_feature_observer(
+        "some_control".to_string(),
+        Box::new(TestObserver(changed.clone())),
+    );
+    client.register_feature_observer(
+        "a-feature".to_string(),
+        Box::new(TestObserver(changed.clone())),
+    );
+    client.initialize()?;
+    assert!(take_changed().is_empty());
+
+    let experiment = get_test_experiments().remove(0);
+    let slug = experiment.slug.clone();
+    let rollout = get_bucketed_rollout("rollout", 10_000);
+    let experiments_json = to_local_experiments_string(&[experiment, rollout])?;
+    client.set_experiments_locally_with_enrollment(
+        experiments_json.clone(),
+        HashMap::from([(slug.clone(), "control".to_string())]),
+    )?;
+    assert_eq!(take_changed(), ["a-feature", "some_control"]);
+
+    // Both branches configure the feature the same way, so moving between them doesn't
+    // change its config.
+    client.set_experiments_locally_with_enrollment(
+        experiments_json,
+        HashMap::from([(slug.clone(), "treatment".to_string())]),
+    )?;
+    assert!(take_changed().is_empty());
+
+    client.opt_out(slug)?;
+    assert_eq!(take_changed(), ["some_control"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_feature_ids() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        vec!["coenrolling-feature".to_string()],
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+    assert_eq!(client.get_feature_ids()?, vec!["coenrolling-feature"]);
+
+    client.register_feature_ids(vec![
+        "manifest-feature".to_string(),
+        "coenrolling-feature".to_string(),
+    ]);
+    assert_eq!(
+        client.get_feature_ids()?,
+        vec!["coenrolling-feature", "manifest-feature"]
+    );
+
+    // Features which aren't in the manifest, but are configured by experiments, are included.
+    let exp = get_targeted_experiment("test-1", "true");
+    client.set_experiments_locally(to_local_experiments_string(&[exp])?)?;
+    client.apply_pending_experiments()?;
+    assert_eq!(
+        client.get_feature_ids()?,
+        vec!["coenrolling-feature", "manifest-feature", "some-feature-1"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_get_enrollment_state_checksum() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let new_client = || {
+        NimbusClient::new(
+            app_context.clone(),
+            Default::default(),
+            temp_dir.path(),
+            None,
+            AvailableRandomizationUnits {
+                client_id: Some("client-1".to_string()),
+                ..AvailableRandomizationUnits::default()
+            },
+        )
+    };
+    let client = new_client()?;
+    client.initialize()?;
+    // The SHA-256 of nothing, since there are no enrollments.
+    let empty = client.get_enrollment_state_checksum()?;
+    assert_eq!(
+        empty,
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+
+    let exp_1 = get_targeted_experiment("test-1", "true");
+    let mut exp_2 = get_targeted_experiment("test-2", "true");
+    exp_2["featureIds"] = json!(["some-feature-2"]);
+    client.set_experiments_locally(to_local_experiments_string(&[exp_1, exp_2])?)?;
+    client.apply_pending_experiments()?;
+    assert_eq!(client.get_active_experiments()?.len(), 2);
+    let enrolled = client.get_enrollment_state_checksum()?;
+    assert_ne!(enrolled, empty);
+    // It doesn't change unless the enrollments do.
+    assert_eq!(client.get_enrollment_state_checksum()?, enrolled);
+
+    // It's the same after a restart.
+    drop(client);
+    let client = new_client()?;
+    client.initialize()?;
+    assert_eq!(client.get_enrollment_state_checksum()?, enrolled);
+
+    client.opt_out("test-2".to_string())?;
+    let opted_out = client.get_enrollment_state_checksum()?;
+    assert_ne!(opted_out, enrolled);
+    assert_ne!(opted_out, empty);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_branch_feature_values() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let app_context = AppContext {
+        app_name: "fenix".to_string(),
+        app_id: "org.mozilla.fenix".to_string(),
+        channel: "nightly".to_string(),
+        ..Default::default()
+    };
+    let client = NimbusClient::new(
+        app_context,
+        Default::default(),
+        temp_dir.path(),
+        None,
+        AvailableRandomizationUnits {
+            client_id: Some("client-1".to_string()),
+            ..AvailableRandomizationUnits::default()
+        },
+    )?;
+    client.initialize()?;
+
+    let mut rollout = get_bucketed_rollout("rollout", 10_000);
+    rollout.branches[0].feature.as_mut().unwrap().value =
+        json!({ "a": 1, "b": 1 }).as_object().unwrap().clone();
+    // Nobody is targeted by this experiment, so we can only preview it.
+    let mut exp = get_targeted_experiment("exp", "false");
+    exp["featureIds"] = json!(["a-feature"]);
+    exp["branches"][1]["feature"] = json!({
+        "featureId": "a-feature",
+        "value": { "b": 2, "c": "{experiment}" },
+    });
+    client.set_experiments_locally(to_local_experiments_string(&[
+        serde_json::to_value(rollout)?,
+        exp,
+    ])?)?;
+    client.apply_pending_experiments()?;
+
+    let values =
+        |slug: &str, branch: &str, feature_id: &str| -> Result<Option<serde_json::Value>> {
+            Ok(client
+                .get_branch_feature_values(
+                    slug.to_string(),
+                    branch.to_string(),
+                    feature_id.to_string(),
+                )?
+                .map(|s| serde_json::from_str(&s))
+                .transpose()?)
+        };
+
+    // The branch is merged over the rollout.
+    assert_eq!(
+        values("exp", "treatment", "a-feature")?,
+        Some(json!({ "a": 1, "b": 2, "c": "exp" }))
+    );
+    // A rollout isn't merged over itself.
+    assert_eq!(
+        values("rollout", "control", "a-feature")?,
+        Some(json!({ "a": 1, "b": 1 }))
+    );
+    assert_eq!(values("exp", "control", "a-feature")?, None);
+    assert_eq!(values("exp", "treatment", "another-feature")?, None);
+    assert_eq!(values("exp", "unknown", "a-feature")?, None);
+    assert_eq!(values("unknown", "treatment", "a-feature")?, None);
+
+    // The defaults from the feature manifest are merged under both.
+    client.register_feature_defaults(
+        json!({
+            "a-feature": { "a": 0, "d": 0 },
+            "another-feature": { "e": 0 },
+        })
+        .to_string(),
+    )?;
+    assert_eq!(
+        values("exp", "treatment", "a-feature")?,
+        Some(json!({ "a": 1, "b": 2, "c": "exp", "d": 0 }))
+    );
+    assert_eq!(values("exp", "treatment", "another-feature")?, None);
+    assert!(client
+        .register_feature_defaults("not json".to_string())
+        .is_err());
+
+    // Previewing didn't enroll us.
+    let active_experiments = client.get_active_experiments()?;
+    assert_eq!(active_experiments.len(), 1);
+    assert_eq!(active_experiments[0].slug, "rollout");
+    let variables = client
+        .get_feature_config_variables("a-feature".to_string())?
+        .expect("the rollout is enrolled");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&variables)?,
+        json!({ "a": 1, "b": 1 })
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_previous_enrollments_in_targeting() -> Result<()> {
     let mock_client_id = "client-1".to_string();
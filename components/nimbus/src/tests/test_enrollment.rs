@@ -9,6 +9,7 @@ use crate::{
     defaults::Defaults,
     enrollment::*,
     error::Result,
+    evaluator::EnrollmentDecision,
     tests::helpers::{
         get_ios_rollout_experiment, get_multi_feature_experiment, get_single_feature_experiment,
         get_test_experiments, no_coenrolling_features,
@@ -477,6 +478,14 @@ fn test_evolver_new_experiment_not_enrolled() -> Result<()> {
         }
     ));
     assert!(events.is_empty());
+    let decisions = evolver.take_decisions();
+    assert_eq!(
+        decisions.get(&exp.slug),
+        Some(&EnrollmentDecision {
+            targeting_matched: Some(true),
+            bucketing_selected: Some(false),
+        })
+    );
     Ok(())
 }
 
@@ -498,6 +507,8 @@ fn test_evolver_new_experiment_globally_opted_out() -> Result<()> {
         }
     ));
     assert!(events.is_empty());
+    // Opted out users aren't evaluated, so no decision was made.
+    assert!(evolver.take_decisions().is_empty());
     Ok(())
 }
 
@@ -679,7 +690,7 @@ fn test_evolver_experiment_update_enrolled_then_opted_out() -> Result<()> {
     assert!(matches!(
         enrollment.status,
         EnrollmentStatus::Disqualified {
-            reason: DisqualifiedReason::OptOut,
+            reason: DisqualifiedReason::GlobalOptOut,
             ..
         }
     ));
@@ -739,6 +750,56 @@ fn test_evolver_experiment_update_enrolled_then_experiment_paused() -> Result<()
     Ok(())
 }
 
+#[test]
+fn test_evolver_experiment_paused_mid_lifecycle() -> Result<()> {
+    let exp = get_test_experiments()[0].clone();
+    let (nimbus_id, app_ctx, aru) = local_ctx();
+    let th = app_ctx.into();
+    let ids = no_coenrolling_features();
+    let evolver = enrollment_evolver(&nimbus_id, &th, &aru, &ids);
+    let mut events = vec![];
+
+    // We enroll while enrollment is open.
+    let enrollment = evolver
+        .evolve_enrollment::<Experiment>(true, None, Some(&exp), None, &mut events)?
+        .unwrap();
+    assert!(matches!(
+        enrollment.status,
+        EnrollmentStatus::Enrolled { .. }
+    ));
+    assert_eq!(events.len(), 1);
+
+    // Enrollment is paused, and the bucketing no longer includes us, but we stay enrolled.
+    let mut paused_exp = exp.clone();
+    paused_exp.is_enrollment_paused = true;
+    paused_exp.bucket_config.count = 0;
+    events.clear();
+    let paused_enrollment = evolver
+        .evolve_enrollment(
+            true,
+            Some(&exp),
+            Some(&paused_exp),
+            Some(&enrollment),
+            &mut events,
+        )?
+        .unwrap();
+    assert_eq!(paused_enrollment, enrollment);
+    assert!(events.is_empty());
+
+    // A client which hadn't seen the experiment before it was paused doesn't enroll.
+    let new_enrollment = evolver
+        .evolve_enrollment::<Experiment>(true, None, Some(&paused_exp), None, &mut events)?
+        .unwrap();
+    assert!(matches!(
+        new_enrollment.status,
+        EnrollmentStatus::NotEnrolled {
+            reason: NotEnrolledReason::EnrollmentsPaused
+        }
+    ));
+    assert!(events.is_empty());
+    Ok(())
+}
+
 #[test]
 fn test_evolver_experiment_update_enrolled_then_targeting_changed() -> Result<()> {
     let exp = get_test_experiments()[0].clone();
@@ -1117,6 +1178,57 @@ fn test_evolver_experiment_update_enrolled_then_branch_disappears() -> Result<()
     Ok(())
 }
 
+#[test]
+fn test_evolver_experiment_update_global_opt_out_then_opted_in() -> Result<()> {
+    let exp = get_test_experiments()[0].clone();
+    let (nimbus_id, app_ctx, aru) = local_ctx();
+    let th = app_ctx.into();
+    let ids = no_coenrolling_features();
+    let evolver = enrollment_evolver(&nimbus_id, &th, &aru, &ids);
+    let mut events = vec![];
+    let existing_enrollment = ExperimentEnrollment {
+        slug: exp.slug.clone(),
+        status: EnrollmentStatus::Disqualified {
+            enrollment_id: Uuid::new_v4(),
+            branch: "control".to_owned(),
+            reason: DisqualifiedReason::GlobalOptOut,
+        },
+    };
+    // Still opted out, so nothing changes.
+    let enrollment = evolver
+        .evolve_enrollment(
+            false,
+            Some(&exp),
+            Some(&exp),
+            Some(&existing_enrollment),
+            &mut events,
+        )?
+        .unwrap();
+    assert_eq!(enrollment, existing_enrollment);
+    assert!(events.is_empty());
+
+    // Opted back in, so we're enrolled again.
+    let enrollment = evolver
+        .evolve_enrollment(
+            true,
+            Some(&exp),
+            Some(&exp),
+            Some(&existing_enrollment),
+            &mut events,
+        )?
+        .unwrap();
+    assert!(matches!(
+        enrollment.status,
+        EnrollmentStatus::Enrolled {
+            reason: EnrolledReason::Qualified,
+            ..
+        }
+    ));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].change, EnrollmentChangeEventType::Enrollment);
+    Ok(())
+}
+
 #[test]
 fn test_evolver_experiment_update_disqualified_then_opted_out() -> Result<()> {
     let exp = get_test_experiments()[0].clone();
@@ -69,3 +69,22 @@ fn test_not_enrolled_reason_schema_with_feature_conflict() {
         matches!(non_enrollment.status, EnrollmentStatus::NotEnrolled{ ref reason, ..} if reason == &NotEnrolledReason::FeatureConflict)
     );
 }
+
+// We added a GlobalOptOut variant to the DisqualifiedReason schema, for enrollments which are
+// evaluated again when the user opts back in to experiments.
+#[test]
+fn test_disqualified_reason_schema_with_global_opt_out() {
+    // ⚠️ Warning : Do not change the JSON data used by this test. ⚠️
+    let enrollment: ExperimentEnrollment = serde_json::from_value(json!({
+        "slug": "secure-gold",
+        "status": {"Disqualified": {
+            "enrollment_id": "b6d6f532-e219-4b5a-8ddf-66700dd47d68",
+            "branch": "hello",
+            "reason": "GlobalOptOut",
+        }}
+    }))
+    .unwrap();
+    assert!(
+        matches!(enrollment.status, EnrollmentStatus::Disqualified{ ref reason, ..} if reason == &DisqualifiedReason::GlobalOptOut)
+    );
+}
@@ -0,0 +1,188 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+* License, v. 2.0. If a copy of the MPL was not distributed with this
+* file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Checks that the types we receive as JSON, or send across the FFI, don't lose
+// any fields along the way, e.g. the `userFacingName` of an experiment.
+
+use crate::{BucketConfig, Experiment};
+use error_support::testing::assert_json_round_trip;
+use serde_json::{json, Value};
+
+fn experiment_json(optional: Value) -> Value {
+    let mut exp = json!({
+        "schemaVersion": "1.0.0",
+        "slug": "round-trip",
+        "userFacingName": "Round trip experiment",
+        "userFacingDescription": "An experiment which goes there and back again",
+        "isEnrollmentPaused": false,
+        "bucketConfig": {
+            "randomizationUnit": "nimbus_id",
+            "namespace": "round-trip",
+            "start": 0,
+            "count": 5000,
+            "total": 10000,
+        },
+        "branches": [
+            {
+                "slug": "control",
+                "ratio": 1,
+                "feature": { "featureId": "my-feature", "value": {} },
+            },
+            {
+                "slug": "treatment",
+                "ratio": 2,
+                "features": [{ "featureId": "my-feature", "value": { "enabled": true } }],
+            },
+        ],
+        "featureIds": ["my-feature"],
+        "proposedEnrollment": 7,
+        "isRollout": false,
+    });
+    exp.as_object_mut()
+        .unwrap()
+        .extend(optional.as_object().unwrap().clone());
+    exp
+}
+
+#[test]
+fn test_experiment_json_round_trip() {
+    assert_json_round_trip::<Experiment>(experiment_json(json!({
+        "appName": "fenix",
+        "appId": "org.mozilla.fenix",
+        "channel": "nightly",
+        "targeting": "true",
+        "startDate": "2023-07-01",
+        "endDate": "2023-08-01",
+        "proposedDuration": 28,
+        "referenceBranch": "control",
+    })));
+
+    assert_json_round_trip::<Experiment>(experiment_json(json!({
+        "appName": null,
+        "appId": null,
+        "channel": null,
+        "targeting": null,
+        "startDate": null,
+        "endDate": null,
+        "proposedDuration": null,
+        "referenceBranch": null,
+    })));
+
+    assert_json_round_trip::<Experiment>(experiment_json(json!({})));
+}
+
+#[test]
+fn test_bucket_config_json_round_trip() {
+    assert_json_round_trip::<BucketConfig>(json!({
+        "randomizationUnit": "client_id",
+        "namespace": "round-trip",
+        "start": 0,
+        "count": 2000,
+        "total": 10000,
+    }));
+}
+
+#[cfg(feature = "stateful")]
+mod ffi {
+    use crate::{
+        enrollment::{EnrolledFeature, EnrollmentChangeEvent, EnrollmentChangeEventType},
+        AppContext, AvailableExperiment, AvailableRandomizationUnits, EnrolledExperiment,
+        ExperimentBranch, ExperimentSchedule, UniFfiTag,
+    };
+    use error_support::testing::assert_ffi_round_trip;
+    use serde_json::json;
+
+    #[test]
+    fn test_experiments_ffi_round_trip() {
+        assert_ffi_round_trip::<UniFfiTag, _>(EnrolledExperiment {
+            feature_ids: vec!["my-feature".to_string()],
+            slug: "round-trip".to_string(),
+            user_facing_name: "Round trip experiment".to_string(),
+            user_facing_description: "An experiment which goes there and back again".to_string(),
+            branch_slug: "control".to_string(),
+            enrollment_id: "00000000-0000-0000-0000-000000000000".to_string(),
+            is_enrollment_paused: false,
+        });
+
+        for reference_branch in [Some("control".to_string()), None] {
+            assert_ffi_round_trip::<UniFfiTag, _>(AvailableExperiment {
+                slug: "round-trip".to_string(),
+                user_facing_name: "Round trip experiment".to_string(),
+                user_facing_description: "An experiment which goes there and back again"
+                    .to_string(),
+                branches: vec![ExperimentBranch {
+                    slug: "control".to_string(),
+                    ratio: 1,
+                }],
+                reference_branch,
+                is_enrollment_paused: true,
+            });
+        }
+
+        assert_ffi_round_trip::<UniFfiTag, _>(ExperimentSchedule {
+            slug: "round-trip".to_string(),
+            start_date: 1_682_899_200_000,
+            proposed_duration_days: 28,
+            end_date: 1_685_318_400_000,
+        });
+
+        for branch in [Some("control".to_string()), None] {
+            assert_ffi_round_trip::<UniFfiTag, _>(EnrolledFeature {
+                slug: "round-trip".to_string(),
+                branch,
+                feature_id: "my-feature".to_string(),
+            });
+        }
+
+        for reason in [Some("targeting".to_string()), None] {
+            assert_ffi_round_trip::<UniFfiTag, _>(EnrollmentChangeEvent {
+                experiment_slug: "round-trip".to_string(),
+                branch_slug: "control".to_string(),
+                enrollment_id: "00000000-0000-0000-0000-000000000000".to_string(),
+                reason,
+                change: EnrollmentChangeEventType::Disqualification,
+            });
+        }
+    }
+
+    #[test]
+    fn test_app_context_ffi_round_trip() {
+        assert_ffi_round_trip::<UniFfiTag, _>(AppContext {
+            app_name: "fenix".to_string(),
+            app_id: "org.mozilla.fenix".to_string(),
+            channel: "nightly".to_string(),
+            ..Default::default()
+        });
+
+        assert_ffi_round_trip::<UniFfiTag, _>(AppContext {
+            app_name: "fenix".to_string(),
+            app_id: "org.mozilla.fenix".to_string(),
+            channel: "nightly".to_string(),
+            app_version: Some("117.0".to_string()),
+            app_build: Some("1".to_string()),
+            architecture: Some("arm64".to_string()),
+            device_manufacturer: Some("Google".to_string()),
+            device_model: Some("Pixel".to_string()),
+            locale: Some("en-US".to_string()),
+            os: Some("Android".to_string()),
+            os_version: Some("13".to_string()),
+            android_sdk_version: Some("33".to_string()),
+            debug_tag: Some("round-trip".to_string()),
+            installation_date: Some(1_690_000_000_000),
+            home_directory: Some("/data/user/0".to_string()),
+            custom_targeting_attributes: json!({ "is_first_run": true }).as_object().cloned(),
+        });
+
+        for (client_id, user_id) in [
+            (Some("client".to_string()), None),
+            (None, Some("user".to_string())),
+        ] {
+            assert_ffi_round_trip::<UniFfiTag, _>(AvailableRandomizationUnits {
+                client_id,
+                user_id,
+                dummy: 0,
+            });
+        }
+    }
+}
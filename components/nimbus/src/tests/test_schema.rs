@@ -2,7 +2,7 @@
 * License, v. 2.0. If a copy of the MPL was not distributed with this
 * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use crate::{error::Result, FeatureConfig};
+use crate::{error::Result, Experiment, ExperimentSchedule, FeatureConfig};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
@@ -68,3 +68,48 @@ fn test_deserialize_untyped_json() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_experiment_schedule() {
+    let exp = Experiment {
+        slug: "test-schedule".to_string(),
+        start_date: Some("2023-05-01".to_string()),
+        proposed_duration: Some(28),
+        ..Default::default()
+    };
+    // 2023-05-01T00:00:00Z and 2023-05-29T00:00:00Z.
+    assert_eq!(
+        exp.get_schedule(),
+        Some(ExperimentSchedule {
+            slug: "test-schedule".to_string(),
+            start_date: 1_682_899_200_000,
+            proposed_duration_days: 28,
+            end_date: 1_685_318_400_000,
+        })
+    );
+
+    // An explicit end date wins over the proposed duration.
+    let ended = Experiment {
+        end_date: Some("2023-05-10T12:00:00Z".to_string()),
+        ..exp.clone()
+    };
+    assert_eq!(ended.get_schedule().unwrap().end_date, 1_683_720_000_000);
+
+    let no_duration = Experiment {
+        proposed_duration: None,
+        ..exp.clone()
+    };
+    assert_eq!(no_duration.get_schedule(), None);
+
+    let not_started = Experiment {
+        start_date: None,
+        ..exp.clone()
+    };
+    assert_eq!(not_started.get_schedule(), None);
+
+    let bad_date = Experiment {
+        start_date: Some("not a date".to_string()),
+        ..exp
+    };
+    assert_eq!(bad_date.get_schedule(), None);
+}
@@ -34,6 +34,7 @@ fn new_test_client_internal(
         server_url: Some(url.as_str().to_string()),
         bucket_name: None,
         collection_name: "doesn't matter".to_string(),
+        filter_context: None,
     };
     let aru = Default::default();
     let ctx = AppContext {
@@ -27,6 +27,7 @@ fn test_simple() -> Result<()> {
         server_url: Some(url.as_str().to_string()),
         bucket_name: None,
         collection_name: "doesn't matter".to_string(),
+        filter_context: None,
     };
 
     let tmp_dir = tempfile::tempdir()?;
@@ -5,6 +5,7 @@
 package mozilla.appservices.places
 
 import mozilla.appservices.places.uniffi.BookmarkItem
+import mozilla.appservices.places.uniffi.BookmarksImportResult
 
 /**
  * Enumeration of the ids of the roots of the bookmarks tree.
@@ -119,6 +120,17 @@ interface ReadableBookmarksConnection : InterruptibleConnection {
      * has its `interrupt()` method called on another thread.
      */
     fun getRecentBookmarks(limit: Int): List<BookmarkItem>
+
+    /**
+     * Writes every bookmark to the file at [path] in the Netscape bookmark file
+     * format (`bookmarks.html`), which most browsers can import.
+     *
+     * @param path The path of the file to write. It is replaced if it exists.
+     *
+     * @throws OperationInterrupted if this database implements [InterruptibleConnection] and
+     * has its `interrupt()` method called on another thread.
+     */
+    fun exportBookmarksHtml(path: String)
 }
 
 /**
@@ -229,4 +241,16 @@ interface WritableBookmarksConnection : ReadableBookmarksConnection {
      * folder node.
      */
     fun updateBookmark(guid: Guid, parentGuid: Guid?, position: UInt?, title: String?, url: Url?)
+
+    /**
+     * Imports the bookmarks from a `bookmarks.html` file, as written by most browsers.
+     *
+     * Folders are merged with existing folders of the same name in the same parent, and
+     * bookmarks which are already in the same folder are skipped, so importing the same
+     * file twice is harmless. Items which can't be imported are skipped.
+     *
+     * @param path The path of the file to import.
+     * @return The number of items imported and skipped.
+     */
+    fun importBookmarksHtml(path: String): BookmarksImportResult
 }
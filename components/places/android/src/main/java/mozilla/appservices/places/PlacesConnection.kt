@@ -7,11 +7,14 @@ package mozilla.appservices.places
 import mozilla.appservices.places.uniffi.BookmarkItem
 import mozilla.appservices.places.uniffi.BookmarkPosition
 import mozilla.appservices.places.uniffi.BookmarkUpdateInfo
+import mozilla.appservices.places.uniffi.BookmarksImportResult
 import mozilla.appservices.places.uniffi.ConnectionType
 import mozilla.appservices.places.uniffi.DocumentType
 import mozilla.appservices.places.uniffi.FrecencyThresholdOption
 import mozilla.appservices.places.uniffi.HistoryHighlight
 import mozilla.appservices.places.uniffi.HistoryHighlightWeights
+import mozilla.appservices.places.uniffi.HistoryImportObserver
+import mozilla.appservices.places.uniffi.HistoryInfo
 import mozilla.appservices.places.uniffi.HistoryMetadata
 import mozilla.appservices.places.uniffi.HistoryMetadataObservation
 import mozilla.appservices.places.uniffi.HistoryVisitInfo
@@ -20,6 +23,7 @@ import mozilla.appservices.places.uniffi.InsertableBookmark
 import mozilla.appservices.places.uniffi.InsertableBookmarkFolder
 import mozilla.appservices.places.uniffi.InsertableBookmarkItem
 import mozilla.appservices.places.uniffi.InsertableBookmarkSeparator
+import mozilla.appservices.places.uniffi.PageAnnotation
 import mozilla.appservices.places.uniffi.PlacesApiException
 import mozilla.appservices.places.uniffi.SearchResult
 import mozilla.appservices.places.uniffi.SqlInterruptHandle
@@ -162,10 +166,24 @@ open class PlacesReaderConnection internal constructor(conn: UniffiPlacesConnect
         return this.conn.getTopFrecentSiteInfos(numItems, frecencyThreshold)
     }
 
+    override fun getFrequentUnbookmarked(limit: Int, minVisits: Int, excludeUrls: List<Url>): List<HistoryInfo> {
+        return this.conn.getFrequentUnbookmarked(limit, minVisits, excludeUrls)
+    }
+
+    override fun searchHistory(query: String, limit: Int): List<HistoryMatch> {
+        return this.conn.searchHistory(query, limit)
+    }
+
     override fun getVisited(urls: List<String>): List<Boolean> {
         return this.conn.getVisited(urls)
     }
 
+    override fun getVisitInfosForUrls(urls: List<String>): List<HistoryInfo?> {
+        readQueryCounters.measure {
+            return this.conn.getVisitInfosForUrls(urls)
+        }
+    }
+
     override fun getVisitedUrlsInRange(start: Long, end: Long, includeRemote: Boolean): List<String> {
         return this.conn.getVisitedUrlsInRange(start, end, includeRemote)
     }
@@ -193,6 +211,12 @@ open class PlacesReaderConnection internal constructor(conn: UniffiPlacesConnect
         return this.conn.getVisitCount(visitTransitionSet(excludeTypes))
     }
 
+    override fun getPageAnnotations(url: String): List<PageAnnotation> {
+        return readQueryCounters.measure {
+            this.conn.getPageAnnotations(url)
+        }
+    }
+
     override suspend fun getLatestHistoryMetadataForUrl(url: Url): HistoryMetadata? {
         return readQueryCounters.measure {
             this.conn.getLatestHistoryMetadataForUrl(url)
@@ -262,6 +286,12 @@ open class PlacesReaderConnection internal constructor(conn: UniffiPlacesConnect
         }
     }
 
+    override fun exportBookmarksHtml(path: String) {
+        return readQueryCounters.measure {
+            this.conn.bookmarksExportHtml(path)
+        }
+    }
+
     private val readQueryCounters: PlacesManagerCounterMetrics by lazy {
         PlacesManagerCounterMetrics(
             PlacesManagerMetrics.readQueryCount,
@@ -297,6 +327,12 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         }
     }
 
+    override fun importVisits(visits: List<VisitObservation>, observer: HistoryImportObserver?): Int {
+        return writeQueryCounters.measure {
+            this.conn.importVisits(visits, observer).toInt()
+        }
+    }
+
     override fun deleteVisitsFor(url: String) {
         return writeQueryCounters.measure {
             this.conn.deleteVisitsFor(url)
@@ -319,6 +355,13 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         }
     }
 
+    override fun deleteRecentHistory(durationMs: Long): Int {
+        require(durationMs >= 0) { "durationMs must not be negative" }
+        return writeQueryCounters.measure {
+            this.conn.deleteRecentHistory(durationMs.toULong()).toInt()
+        }
+    }
+
     override fun wipeLocal() {
         this.conn.wipeLocalHistory()
     }
@@ -345,6 +388,20 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         PlacesManagerMetrics.dbSizeAfterMaintenance.accumulateSamples(listOf(pruneMetrics.dbSizeAfter.toLong() / 1024))
     }
 
+    override fun decayFrecency() {
+        this.conn.decayFrecency()
+    }
+
+    override fun getLastFrecencyDecay(): Long? {
+        return this.conn.getLastFrecencyDecay()
+    }
+
+    override fun coalesceDuplicateUrls(): Int {
+        return writeQueryCounters.measure {
+            this.conn.coalesceDuplicateUrls().toInt()
+        }
+    }
+
     override fun pruneDestructively() {
         this.conn.pruneDestructively()
     }
@@ -355,6 +412,24 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         }
     }
 
+    override fun deleteAllHistory() {
+        return writeQueryCounters.measure {
+            this.conn.deleteAllHistory()
+        }
+    }
+
+    override fun setPageAnnotation(url: String, key: String, value: String) {
+        return writeQueryCounters.measure {
+            this.conn.setPageAnnotation(url, key, value)
+        }
+    }
+
+    override fun deletePageAnnotation(url: String, key: String) {
+        return writeQueryCounters.measure {
+            this.conn.deletePageAnnotation(url, key)
+        }
+    }
+
     override fun deleteAllBookmarks() {
         return writeQueryCounters.measure {
             this.conn.bookmarksDeleteEverything()
@@ -398,6 +473,16 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         noteHistoryMetadataObservation(obs)
     }
 
+    override suspend fun noteHistoryMetadataObservationScrollDepth(key: HistoryMetadataKey, scrollDepth: Int) {
+        val obs = HistoryMetadataObservation(
+            url = key.url,
+            searchTerm = key.searchTerm,
+            referrerUrl = key.referrerUrl,
+            scrollDepth = scrollDepth,
+        )
+        noteHistoryMetadataObservation(obs)
+    }
+
     override suspend fun deleteHistoryMetadataOlderThan(olderThan: Long) {
         return writeQueryCounters.measure {
             this.conn.metadataDeleteOlderThan(olderThan)
@@ -463,6 +548,12 @@ class PlacesWriterConnection internal constructor(conn: UniffiPlacesConnection,
         }
     }
 
+    override fun importBookmarksHtml(path: String): BookmarksImportResult {
+        return writeQueryCounters.measure {
+            this.conn.bookmarksImportHtml(path)
+        }
+    }
+
     override fun acceptResult(searchString: String, url: String) {
         return this.conn.acceptResult(searchString, url)
     }
@@ -638,6 +729,7 @@ interface WritableHistoryMetadataConnection : ReadableHistoryMetadataConnection
     // this as the entire "history metadata" requirement evolves.
     suspend fun noteHistoryMetadataObservationViewTime(key: HistoryMetadataKey, viewTime: Int)
     suspend fun noteHistoryMetadataObservationDocumentType(key: HistoryMetadataKey, documentType: DocumentType)
+    suspend fun noteHistoryMetadataObservationScrollDepth(key: HistoryMetadataKey, scrollDepth: Int)
 
     /**
      * Deletes [HistoryMetadata] with [HistoryMetadata.updatedAt] older than [olderThan].
@@ -686,6 +778,28 @@ interface ReadableHistoryConnection : InterruptibleConnection {
      */
     fun getTopFrecentSiteInfos(numItems: Int, frecencyThreshold: FrecencyThresholdOption): List<TopFrecentSiteInfo>
 
+    /**
+     * Returns pages which have been visited often but aren't bookmarked, sorted by most to
+     * least frecent, for suggesting pages the user might want to bookmark.
+     *
+     * @param limit the maximum number of pages to return.
+     * @param minVisits the minimum number of visits for a page to be included.
+     * @param excludeUrls pages to leave out, such as suggestions the user has dismissed.
+     * @return a list of history infos sorted by most to least frecent.
+     */
+    fun getFrequentUnbookmarked(limit: Int, minVisits: Int, excludeUrls: List<Url> = listOf()): List<HistoryInfo>
+
+    /**
+     * Searches history for pages whose title or URL contains every word in [query], ignoring
+     * case, sorted by most to least frecent and then by most recent visit.
+     *
+     * @param query the words to search for.
+     * @param limit the maximum number of matches to return.
+     * @return a list of matches, with the ranges of their title and URL to highlight. The
+     * ranges are offsets into the strings, which can be used for spans directly.
+     */
+    fun searchHistory(query: String, limit: Int): List<HistoryMatch>
+
     /**
      * Maps a list of page URLs to a list of booleans indicating if each URL was visited.
      *
@@ -695,6 +809,15 @@ interface ReadableHistoryConnection : InterruptibleConnection {
      */
     fun getVisited(urls: List<String>): List<Boolean>
 
+    /**
+     * Looks up the title, visit count and last visit date of many pages at once.
+     *
+     * @param urls a list of page URLs about which visit information is being requested.
+     * @return a list with the [HistoryInfo] of each corresponding page URL from [urls], or
+     * null for pages which have never been visited.
+     */
+    fun getVisitInfosForUrls(urls: List<String>): List<HistoryInfo?>
+
     /**
      * Returns a list of visited URLs for a given time range.
      *
@@ -763,6 +886,15 @@ interface ReadableHistoryConnection : InterruptibleConnection {
      * @param excludeTypes List of visit types to exclude.
      */
     fun getVisitCount(excludeTypes: List<VisitType> = listOf()): Long
+
+    /**
+     * Returns every annotation set on a page with [WritableHistoryConnection.setPageAnnotation],
+     * ordered by key.
+     *
+     * @param url The page's URL.
+     * @return A `List` of [PageAnnotation], empty if the page has none or isn't known.
+     */
+    fun getPageAnnotations(url: String): List<PageAnnotation>
 }
 
 interface WritableHistoryConnection : ReadableHistoryConnection {
@@ -771,6 +903,20 @@ interface WritableHistoryConnection : ReadableHistoryConnection {
      */
     fun noteObservation(data: VisitObservation)
 
+    /**
+     * Record many visits at once, for example when migrating a large profile. This is much
+     * faster than calling [noteObservation] for each one: the visits are committed in
+     * batches, and frecencies are only recalculated at the end.
+     *
+     * The import can be interrupted with [interrupt], in which case the batches already
+     * committed are kept.
+     *
+     * @param visits The observations to record.
+     * @param observer Told how many observations have been recorded after each batch.
+     * @return The number of visits added.
+     */
+    fun importVisits(visits: List<VisitObservation>, observer: HistoryImportObserver? = null): Int
+
     /**
      * Deletes all history visits, without recording tombstones.
      *
@@ -802,6 +948,32 @@ interface WritableHistoryConnection : ReadableHistoryConnection {
      */
     fun runMaintenance(dbSizeLimit: UInt = 0U)
 
+    /**
+     * Decay the frecency of every page and origin, so that pages which
+     * haven't been visited in a while gradually drop in the rankings.
+     *
+     * This should be called periodically (ideally about once a day) while the
+     * app is idle. Use [getLastFrecencyDecay] to decide whether it's due.
+     */
+    fun decayFrecency()
+
+    /**
+     * Returns the time [decayFrecency] last ran, in milliseconds since the
+     * epoch, or null if it never has.
+     */
+    fun getLastFrecencyDecay(): Long?
+
+    /**
+     * Merge history pages whose URLs are the same once they're canonicalized,
+     * which can happen when URL canonicalization changes. The duplicates'
+     * visits, bookmarks, tags and keywords are moved to one page, and the
+     * duplicates are removed (and if they've been synced, the removal will
+     * also be synced).
+     *
+     * @return how many pages were removed.
+     */
+    fun coalesceDuplicateUrls(): Int
+
     /**
      * Aggressively prune history visits. These deletions are not intended
      * to be synced, however due to the way history sync works, this can
@@ -828,6 +1000,38 @@ interface WritableHistoryConnection : ReadableHistoryConnection {
      */
     fun deleteEverything()
 
+    /**
+     * Deletes all history visits, and every page which isn't bookmarked, in a
+     * single transaction, so an interrupted clear never leaves history half
+     * deleted.
+     *
+     * Bookmarked pages (and pages with keywords or tags) are kept, but lose
+     * their visits. Unlike [deleteEverything], the deletions are synced, so
+     * the history is removed from other devices too.
+     */
+    fun deleteAllHistory()
+
+    /**
+     * Sets the annotation [key] on a page to [value], replacing any existing
+     * value for the key. Annotations are local-only key/value pairs for
+     * features like a reading list ("read later", "archived"). They aren't
+     * synced, and are removed along with the page.
+     *
+     * @param url The page's URL, which must already be in the database.
+     * @param key The annotation's key.
+     * @param value The annotation's value.
+     */
+    fun setPageAnnotation(url: String, key: String, value: String)
+
+    /**
+     * Removes the annotation [key] from a page. Does nothing if the page
+     * doesn't have it.
+     *
+     * @param url The page's URL.
+     * @param key The annotation's key.
+     */
+    fun deletePageAnnotation(url: String, key: String)
+
     /**
      * Deletes all visits from the given URL. If the page has previously
      * been synced, a tombstone will be written to the Sync server, meaning
@@ -868,6 +1072,17 @@ interface WritableHistoryConnection : ReadableHistoryConnection {
      */
     fun deleteVisitsBetween(startTime: Long, endTime: Long)
 
+    /**
+     * Deletes all visits in the last `durationMs` milliseconds, for a "forget the
+     * last hour" button. Visits dated in the future are deleted too.
+     *
+     * See documentation for deleteVisitsSince for caveats.
+     *
+     * @param durationMs How far back to delete, in milliseconds.
+     * @return how many visits were deleted.
+     */
+    fun deleteRecentHistory(durationMs: Long): Int
+
     /**
      * Delete the single visit that occurred at the provided timestamp.
      *
@@ -903,14 +1118,43 @@ enum class VisitType(val type: Int) {
      */
     TYPED(2),
 
-    // TODO: rest of docs
+    /** This transition type means the user opened the page from a bookmark. */
     BOOKMARK(3),
+
+    /** This transition type means the page was loaded as part of another page,
+     *  like an image or an iframe which the user didn't navigate to.
+     */
     EMBED(4),
+
+    /** This transition type means the page was the target of a permanent redirect. */
     REDIRECT_PERMANENT(5),
+
+    /** This transition type means the page was the target of a temporary redirect. */
     REDIRECT_TEMPORARY(6),
+
+    /** This transition type means the user downloaded the page's URL. */
     DOWNLOAD(7),
+
+    /** This transition type means the user followed a link in a frame, rather
+     *  than in the top-level page.
+     */
     FRAMED_LINK(8),
+
+    /** This transition type means the user reloaded the page. */
     RELOAD(9),
+    ;
+
+    companion object {
+        /**
+         * Every visit type except [types], for the `excludeTypes` argument of the
+         * history queries when only some types should be included. For example,
+         * `VisitType.allExcept(listOf(VisitType.LINK, VisitType.TYPED))` only
+         * includes visits where the user followed a link or typed the URL.
+         */
+        fun allExcept(types: List<VisitType>): List<VisitType> {
+            return values().filter { it != UPDATE_PLACE && it !in types }
+        }
+    }
 }
 
 /**
@@ -305,6 +305,9 @@ class PlacesConnectionTest {
         assertEquals("https://www.example.com/2a", infos[0].url)
         assertEquals("https://www.example.com/2b", infos[1].url)
         assertEquals("https://www.example.com/3", infos[2].url)
+        infos = db.getVisitInfos(125000, 225000, excludeTypes = VisitType.allExcept(listOf(VisitType.REDIRECT_TEMPORARY)))
+        assertEquals(1, infos.size)
+        assertEquals("https://www.example.com/2a", infos[0].url)
     }
 
     @Test
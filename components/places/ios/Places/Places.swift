@@ -299,6 +299,25 @@ public class PlacesReadConnection {
         }
     }
 
+    /**
+     * Writes every bookmark to the file at `path` in the Netscape bookmark file
+     * format (`bookmarks.html`), which most browsers can import. The file is
+     * replaced if it exists.
+     *
+     * - Throws:
+     *     - `PlacesConnectionError.connUseAfterAPIClosed`: If the PlacesAPI that returned
+     *                                                      this connection object has
+     *                                                      been closed. This indicates
+     *                                                      API misuse.
+     *     - `PlacesApiError.unexpected`: When the file can't be written.
+     */
+    open func exportBookmarksHtml(path: String) throws {
+        return try queue.sync {
+            try self.checkApi()
+            try self.conn.bookmarksExportHtml(path: path)
+        }
+    }
+
     open func getLatestHistoryMetadataForUrl(url: Url) throws -> HistoryMetadata? {
         return try queue.sync {
             try self.checkApi()
@@ -375,6 +394,13 @@ public class PlacesReadConnection {
         }
     }
 
+    open func getPageAnnotations(url: Url) throws -> [PageAnnotation] {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.getPageAnnotations(url: url)
+        }
+    }
+
     open func getVisitPageWithBound(
         bound: Int64,
         offset: Int64,
@@ -398,6 +424,17 @@ public class PlacesReadConnection {
         }
     }
 
+    /**
+     * Returns the title, visit count and last visit date of each of `urls`, in the same order,
+     * or nil for pages which have never been visited.
+     */
+    open func getVisitInfosForUrls(urls: [String]) throws -> [HistoryInfo?] {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.getVisitInfosForUrls(urls: urls)
+        }
+    }
+
     open func getTopFrecentSiteInfos(numItems: Int32, thresholdOption: FrecencyThresholdOption)
         throws -> [TopFrecentSiteInfo]
     {
@@ -410,6 +447,35 @@ public class PlacesReadConnection {
         }
     }
 
+    /**
+     * Returns pages which have been visited at least `minVisits` times but aren't bookmarked,
+     * most frecent first. Pages in `excludeUrls`, such as dismissed suggestions, are skipped.
+     */
+    open func getFrequentUnbookmarked(limit: Int32, minVisits: Int32, excludeUrls: [Url] = [])
+        throws -> [HistoryInfo]
+    {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.getFrequentUnbookmarked(
+                limit: limit,
+                minVisits: minVisits,
+                excludeUrls: excludeUrls
+            )
+        }
+    }
+
+    /**
+     * Searches history for pages whose title or URL contains every word in `query`, ignoring
+     * case, most frecent first. Each match has the ranges of its title and URL to highlight,
+     * as UTF-16 offsets, like `NSRange`.
+     */
+    open func searchHistory(query: String, limit: Int32) throws -> [HistoryMatch] {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.searchHistory(query: query, limit: limit)
+        }
+    }
+
     /**
      * Attempt to interrupt a long-running operation which may be
      * happening concurrently. If the operation is interrupted,
@@ -469,6 +535,56 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 
+    /**
+     * Decay the frecency of every page and origin, so that pages which haven't
+     * been visited in a while gradually drop in the rankings.
+     *
+     * This should be called periodically (ideally about once a day) while the
+     * app is idle. Use `getLastFrecencyDecay()` to decide whether it's due.
+     *
+     * - Throws:
+     *     - `PlacesApiError.databaseInterrupted`: If a call is made to
+     *                                             `interrupt()` on this
+     *                                             object from another thread.
+     *     - `PlacesConnectionError.connUseAfterAPIClosed`: If the PlacesAPI that returned
+     *                                                      this connection object has
+     *                                                      been closed. This indicates
+     *                                                      API misuse.
+     */
+    open func decayFrecency() throws {
+        return try queue.sync {
+            try self.checkApi()
+            try self.conn.decayFrecency()
+        }
+    }
+
+    /**
+     * Returns the time `decayFrecency()` last ran, in milliseconds since the
+     * epoch, or nil if it never has.
+     */
+    open func getLastFrecencyDecay() throws -> Int64? {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.getLastFrecencyDecay()
+        }
+    }
+
+    /**
+     * Merge history pages whose URLs are the same once they're canonicalized,
+     * which can happen when URL canonicalization changes. The duplicates'
+     * visits, bookmarks, tags and keywords are moved to one page, and the
+     * duplicates are removed (and if they've been synced, the removal will
+     * also be synced).
+     *
+     * Returns how many pages were removed.
+     */
+    open func coalesceDuplicateUrls() throws -> UInt32 {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.coalesceDuplicateUrls()
+        }
+    }
+
     /**
      * Delete the bookmark with the provided GUID.
      *
@@ -688,6 +804,19 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 
+    /**
+     * Imports the bookmarks from a `bookmarks.html` file at `path`, as written by
+     * most browsers. Folders are merged with existing folders of the same name in
+     * the same parent, and bookmarks which are already in the same folder are
+     * skipped, so importing the same file twice is harmless.
+     */
+    open func importBookmarksHtml(path: String) throws -> BookmarksImportResult {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.bookmarksImportHtml(path: path)
+        }
+    }
+
     // Helper for the various creation functions.
     // Note: Caller synchronizes
     private func doInsert(item: InsertableBookmarkItem) throws -> Guid {
@@ -737,6 +866,16 @@ public class PlacesWriteConnection: PlacesReadConnection {
         try noteHistoryMetadataObservation(observation: obs)
     }
 
+    open func noteHistoryMetadataObservationScrollDepth(key: HistoryMetadataKey, scrollDepth: Int32) throws {
+        let obs = HistoryMetadataObservation(
+            url: key.url,
+            referrerUrl: key.referrerUrl,
+            searchTerm: key.searchTerm,
+            scrollDepth: scrollDepth
+        )
+        try noteHistoryMetadataObservation(observation: obs)
+    }
+
     open func deleteHistoryMetadataOlderThan(olderThan: Int64) throws {
         try queue.sync {
             try self.checkApi()
@@ -771,6 +910,19 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 
+    /**
+     * Delete all visits in the last `durationMs` milliseconds, for a "forget the
+     * last hour" button. Visits dated in the future are deleted too.
+     *
+     * Returns how many visits were deleted.
+     */
+    open func deleteRecentHistory(durationMs: UInt64) throws -> UInt32 {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.deleteRecentHistory(durationMs: durationMs)
+        }
+    }
+
     open func deleteVisit(url: Url, timestamp: PlacesTimestamp) throws {
         try queue.sync {
             try self.checkApi()
@@ -785,6 +937,32 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 
+    /**
+     * Deletes all history visits, and every page which isn't bookmarked, in a single
+     * transaction. Bookmarked pages are kept, but lose their visits. Unlike
+     * `deleteEverythingHistory`, the deletions are synced to other devices.
+     */
+    open func deleteAllHistory() throws {
+        try queue.sync {
+            try self.checkApi()
+            try self.conn.deleteAllHistory()
+        }
+    }
+
+    open func setPageAnnotation(url: Url, key: String, value: String) throws {
+        try queue.sync {
+            try self.checkApi()
+            try self.conn.setPageAnnotation(url: url, key: key, value: value)
+        }
+    }
+
+    open func deletePageAnnotation(url: Url, key: String) throws {
+        try queue.sync {
+            try self.checkApi()
+            try self.conn.deletePageAnnotation(url: url, key: key)
+        }
+    }
+
     open func pruneDestructively() throws {
         try queue.sync {
             try self.checkApi()
@@ -806,6 +984,25 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 
+    /**
+     * Records many visits at once, for example when migrating a large profile. This is much
+     * faster than calling `applyObservation` for each one: the visits are committed in batches,
+     * and frecencies are only recalculated at the end.
+     *
+     * - Parameter visitObservations: The observations to record.
+     * - Parameter observer: Told how many observations have been recorded after each batch.
+     * - Returns: The number of visits added.
+     */
+    open func importVisits(
+        visitObservations: [VisitObservation],
+        observer: HistoryImportObserver? = nil
+    ) throws -> UInt32 {
+        return try queue.sync {
+            try self.checkApi()
+            return try self.conn.importVisits(visits: visitObservations, observer: observer)
+        }
+    }
+
     open func migrateHistoryFromBrowserDb(path: String, lastSyncTimestamp: Int64) throws -> HistoryMigrationResult {
         return try queue.sync {
             try self.checkApi()
@@ -813,3 +1010,39 @@ public class PlacesWriteConnection: PlacesReadConnection {
         }
     }
 }
+
+public extension VisitTransition {
+    /// The value of this transition type, which is stored in the database
+    /// and used as its bit in a `VisitTransitionSet`.
+    var value: Int32 {
+        switch self {
+        case .link: return 1
+        case .typed: return 2
+        case .bookmark: return 3
+        case .embed: return 4
+        case .redirectPermanent: return 5
+        case .redirectTemporary: return 6
+        case .download: return 7
+        case .framedLink: return 8
+        case .reload: return 9
+        }
+    }
+
+    static let allTypes: [VisitTransition] = [
+        .link, .typed, .bookmark, .embed, .redirectPermanent,
+        .redirectTemporary, .download, .framedLink, .reload,
+    ]
+
+    /// A `VisitTransitionSet` of `types`, for the `excludeTypes` arguments of
+    /// the history queries.
+    static func set(of types: [VisitTransition]) -> VisitTransitionSet {
+        return types.reduce(0) { $0 | (1 << $1.value) }
+    }
+
+    /// A `VisitTransitionSet` of every type except `types`, for the
+    /// `excludeTypes` arguments of the history queries when only some types
+    /// should be included.
+    static func setExcluding(allBut types: [VisitTransition]) -> VisitTransitionSet {
+        return set(of: allTypes.filter { !types.contains($0) })
+    }
+}
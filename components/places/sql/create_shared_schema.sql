@@ -261,6 +261,7 @@ CREATE TABLE IF NOT EXISTS moz_places_metadata (
     document_type INTEGER NOT NULL DEFAULT 0, -- 0=generic, 1=media
     typing_time INTEGER NOT NULL DEFAULT 0,
     key_presses INTEGER NOT NULL DEFAULT 0,
+    max_scroll_depth INTEGER NOT NULL DEFAULT 0, -- percentage of the page scrolled, 0-100
 
     FOREIGN KEY(place_id) REFERENCES moz_places(id) ON DELETE CASCADE,
     FOREIGN KEY(search_query_id) REFERENCES moz_places_metadata_search_queries(id) ON DELETE CASCADE,
@@ -273,3 +274,13 @@ CREATE TABLE IF NOT EXISTS moz_places_metadata_search_queries (
     id INTEGER PRIMARY KEY,
     term TEXT NOT NULL UNIQUE
 );
+
+-- Local-only key/value annotations on pages, like "read later" for a reading
+-- list. They aren't synced, and are removed along with their page.
+CREATE TABLE IF NOT EXISTS moz_places_annotations (
+    place_id INTEGER NOT NULL REFERENCES moz_places(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY(place_id, key)
+) WITHOUT ROWID;
@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use crate::bookmark_sync::BookmarksSyncEngine;
-use crate::db::db::{PlacesDb, SharedPlacesDb};
+use crate::db::db::{PlacesDb, SharedPlacesDb, MIGRATION_OBSERVER};
 use crate::error::*;
 use crate::history_sync::HistorySyncEngine;
 use crate::storage::{
@@ -15,6 +15,7 @@ use interrupt_support::register_interrupt;
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use rusqlite::OpenFlags;
+use sql_support::open_database::MigrationObserver;
 use std::cell::Cell;
 use std::collections::HashMap;
 use std::mem;
@@ -135,6 +136,16 @@ pub fn places_api_new(db_name: impl AsRef<Path>) -> ApiResult<Arc<PlacesApi>> {
     PlacesApi::new(db_name)
 }
 
+/// Register an observer to be told about schema upgrades while the database is opened. This
+/// must be called before `places_api_new()` to see the upgrade of an existing database.
+pub fn set_places_migration_observer(observer: Box<dyn MigrationObserver>) {
+    MIGRATION_OBSERVER.set(observer);
+}
+
+pub fn unset_places_migration_observer() {
+    MIGRATION_OBSERVER.unset();
+}
+
 /// The entry-point to the places API. This object gives access to database
 /// connections and other helpers. It enforces that only 1 write connection
 /// can exist to the database at once.
@@ -122,6 +122,34 @@ fn db_has_changes(db: &PlacesDb) -> Result<bool> {
         .unwrap_or(false))
 }
 
+// Like `db_has_changes`, but only checks for local changes which need to be
+// uploaded, ignoring incoming items waiting to be merged.
+fn db_has_local_changes(db: &PlacesDb) -> Result<bool> {
+    let sql = format!(
+        "SELECT
+            EXISTS (
+                WITH RECURSIVE
+                {}
+                SELECT 1
+                FROM localItems
+                WHERE syncChangeCounter > 0
+            ) OR EXISTS (
+                SELECT 1
+                FROM moz_bookmarks_deleted
+            )
+         AS hasChanges",
+        LocalItemsFragment("localItems")
+    );
+    Ok(db
+        .try_query_row(
+            &sql,
+            [],
+            |row| -> rusqlite::Result<_> { row.get::<_, bool>(0) },
+            false,
+        )?
+        .unwrap_or(false))
+}
+
 /// Builds a temporary table with the merge states of all nodes in the merged
 /// tree, then updates the local tree to match the merged tree.
 ///
@@ -948,6 +976,12 @@ impl SyncEngine for BookmarksSyncEngine {
         Ok(update_frecencies(&conn, &self.scope)?)
     }
 
+    // A folder lists its children, so uploading a folder without its new children (or the
+    // children without the folder) would leave other clients with a broken tree.
+    fn upload_must_be_atomic(&self) -> bool {
+        true
+    }
+
     fn sync_finished(&self) -> anyhow::Result<()> {
         let conn = self.db.lock();
         conn.pragma_update(None, "wal_checkpoint", "PASSIVE")?;
@@ -1025,6 +1059,10 @@ impl SyncEngine for BookmarksSyncEngine {
         tx.commit()?;
         Ok(())
     }
+
+    fn has_pending_changes(&self) -> anyhow::Result<bool> {
+        Ok(db_has_local_changes(&self.db.lock())?)
+    }
 }
 
 #[derive(Default)]
@@ -3018,6 +3056,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_has_pending_changes() -> Result<()> {
+        let api = new_mem_api();
+        let engine = create_sync_engine(&api);
+        // The roots haven't been uploaded yet.
+        assert!(engine.has_pending_changes().unwrap());
+
+        let outgoing = engine_apply_incoming(&engine, vec![]);
+        engine
+            .set_uploaded(
+                ServerTimestamp(0),
+                outgoing.into_iter().map(|p| p.envelope.id).collect(),
+            )
+            .expect("should work");
+        engine.sync_finished().expect("should work");
+        assert!(!engine.has_pending_changes().unwrap());
+
+        let writer = api.open_connection(ConnectionType::ReadWrite)?;
+        insert_local_json_tree(
+            &writer,
+            json!({
+                "guid": &BookmarkRootGuid::Unfiled.as_guid(),
+                "children": [{
+                    "guid": "bookmarkAAAA",
+                    "title": "A",
+                    "url": "http://example.com/a",
+                }],
+            }),
+        );
+        assert!(engine.has_pending_changes().unwrap());
+        Ok(())
+    }
+
     #[test]
     fn test_apply_tombstones() -> Result<()> {
         let local_modified = Timestamp::now();
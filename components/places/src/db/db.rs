@@ -10,7 +10,10 @@ use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use rusqlite::{self, Connection, Transaction};
 use sql_support::{
-    open_database::{self, open_database_with_flags, ConnectionInitializer},
+    open_database::{
+        self, open_database_with_flags, ConnectionInitializer, MigrationObserver,
+        MigrationObserverRegistry,
+    },
     ConnExt,
 };
 use std::collections::HashMap;
@@ -30,6 +33,9 @@ lazy_static! {
     pub static ref GLOBAL_BOOKMARK_CHANGE_COUNTERS: RwLock<HashMap<usize, AtomicI64>> = RwLock::new(HashMap::new());
 }
 
+// The observer the application registered with `set_places_migration_observer()`, if any.
+pub static MIGRATION_OBSERVER: MigrationObserverRegistry = MigrationObserverRegistry::new();
+
 pub struct PlacesInitializer {
     api_id: usize,
     conn_type: ConnectionType,
@@ -57,6 +63,10 @@ impl ConnectionInitializer for PlacesInitializer {
         Ok(schema::upgrade_from(tx, version)?)
     }
 
+    fn migration_observer(&self) -> Option<Arc<dyn MigrationObserver>> {
+        MIGRATION_OBSERVER.get()
+    }
+
     fn prepare(&self, conn: &Connection, db_empty: bool) -> open_database::Result<()> {
         // If this is an empty DB, setup incremental auto-vacuum now rather than wait for the first
         // run_maintenance_vacuum() call.  It should be much faster now with an empty DB.
@@ -317,6 +327,12 @@ fn define_functions(c: &Connection, api_id: usize) -> rusqlite::Result<()> {
         FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
         sql_fns::strip_prefix_and_userinfo,
     )?;
+    c.create_scalar_function(
+        "canonical_url",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        sql_fns::canonical_url,
+    )?;
     c.create_scalar_function(
         "reverse_host",
         1,
@@ -495,6 +511,13 @@ pub(crate) mod sql_fns {
         Ok(res)
     }
 
+    // Returns NULL for URLs the `url` crate can't parse.
+    #[inline(never)]
+    pub fn canonical_url(ctx: &Context<'_>) -> Result<Option<String>> {
+        let href = get_raw_str(ctx, "canonical_url", 0)?;
+        Ok(url::Url::parse(href).ok().map(String::from))
+    }
+
     #[inline(never)]
     pub fn now(_ctx: &Context<'_>) -> Result<Timestamp> {
         Ok(Timestamp::now())
@@ -11,7 +11,7 @@ use crate::types::SyncStatus;
 use rusqlite::Connection;
 use sql_support::ConnExt;
 
-pub const VERSION: u32 = 17;
+pub const VERSION: u32 = 19;
 
 // Shared schema and temp tables for the read-write and Sync connections.
 const CREATE_SHARED_SCHEMA_SQL: &str = include_str!("../../sql/create_shared_schema.sql");
@@ -288,6 +288,25 @@ pub fn upgrade_from(db: &Connection, from: u32) -> rusqlite::Result<()> {
                 (),
             )?;
         }
+        17 => {
+            // Add the `max_scroll_depth` column for history metadata
+            db.execute(
+                "ALTER TABLE moz_places_metadata ADD COLUMN max_scroll_depth INTEGER NOT NULL DEFAULT 0",
+                (),
+            )?;
+        }
+        18 => {
+            // Add the `moz_places_annotations` table
+            db.execute_batch(
+                "CREATE TABLE IF NOT EXISTS moz_places_annotations (
+                    place_id INTEGER NOT NULL REFERENCES moz_places(id) ON DELETE CASCADE,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL,
+                    PRIMARY KEY(place_id, key)
+                ) WITHOUT ROWID;",
+            )?;
+        }
         // Add more migrations here...
 
         // Any other from value indicates that something very wrong happened
@@ -881,6 +900,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_upgrade_schema_17_18() {
+        let db_file = MigratedDatabaseFile::new(PlacesInitializer::new_for_test(), CREATE_V15_DB);
+
+        db_file.upgrade_to(18);
+        let db = db_file.open();
+
+        // Test the max_scroll_depth column was added
+        assert_eq!(
+            db.query_one::<String>("SELECT type FROM pragma_table_info('moz_places_metadata') WHERE name = 'max_scroll_depth'").unwrap(),
+            "INTEGER"
+        );
+    }
+
     #[test]
     fn test_gh5464() {
         // Test the gh-5464 error case: A user with the `v16` schema, but with `user_version` set
@@ -893,6 +926,22 @@ mod tests {
         db_file.upgrade_to(16);
     }
 
+    #[test]
+    fn test_upgrade_schema_18_19() {
+        let db_file = MigratedDatabaseFile::new(PlacesInitializer::new_for_test(), CREATE_V15_DB);
+
+        db_file.upgrade_to(19);
+        let db = db_file.open();
+
+        // Test the moz_places_annotations table was added
+        assert!(db
+            .exists(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'moz_places_annotations'",
+                [],
+            )
+            .unwrap());
+    }
+
     #[test]
     fn test_all_upgrades() {
         // Test the migration process in general: open a fresh DB and a DB that's gone through the migration
@@ -926,6 +975,7 @@ mod tests {
             "moz_keywords",
             "moz_places_metadata",
             "moz_places_metadata_search_queries",
+            "moz_places_annotations",
         ];
         #[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
         struct ColumnInfo {
@@ -177,6 +177,8 @@ pub enum Corruption {
 pub enum InvalidMetadataObservation {
     #[error("Observed view time is invalid (too long)")]
     ViewTimeTooLong,
+    #[error("Observed scroll depth is invalid (not a percentage)")]
+    ScrollDepthOutOfRange,
 }
 
 // Define how our internal errors are handled and converted to external errors
@@ -192,6 +194,9 @@ impl GetErrorHandling for Error {
                     InvalidPlaceInfo::InvalidParent(..) => {
                         PlacesApiError::InvalidBookmarkOperation { reason: label }
                     }
+                    InvalidPlaceInfo::InvalidGuid => {
+                        PlacesApiError::InvalidBookmarkOperation { reason: label }
+                    }
                     InvalidPlaceInfo::UrlTooLong => {
                         PlacesApiError::UrlParseFailed { reason: label }
                     }
@@ -5,11 +5,13 @@
 // This module implement the traits that make the FFI code easier to manage.
 
 use crate::api::matcher::{self, search_frecent, SearchParams};
-pub use crate::api::places_api::places_api_new;
+pub use crate::api::places_api::{
+    places_api_new, set_places_migration_observer, unset_places_migration_observer,
+};
 pub use crate::error::Result;
 pub use crate::error::{ApiResult, PlacesApiError};
-pub use crate::import::common::HistoryMigrationResult;
-use crate::import::import_ios_history;
+pub use crate::import::common::{BookmarksImportResult, HistoryMigrationResult};
+use crate::import::{import_html_bookmarks, import_ios_history};
 use crate::storage;
 use crate::storage::bookmarks;
 pub use crate::storage::bookmarks::BookmarkPosition;
@@ -17,8 +19,9 @@ pub use crate::storage::history_metadata::{
     DocumentType, HistoryHighlight, HistoryHighlightWeights, HistoryMetadata,
     HistoryMetadataObservation,
 };
+pub use crate::storage::page_annotations::PageAnnotation;
 pub use crate::storage::RunMaintenanceMetrics;
-use crate::storage::{history, history_metadata};
+use crate::storage::{history, history_metadata, page_annotations};
 use crate::types::VisitTransitionSet;
 use crate::ConnectionType;
 use crate::UniffiCustomTypeConverter;
@@ -29,12 +32,23 @@ use error_support::handle_error;
 use interrupt_support::register_interrupt;
 pub use interrupt_support::SqlInterruptHandle;
 use parking_lot::Mutex;
+pub use sql_support::open_database::MigrationObserver as PlacesMigrationObserver;
+use std::fs::File;
+use std::io::BufWriter;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use sync15::client::Sync15StorageClientInit;
 pub use sync_guid::Guid;
 pub use types::Timestamp as PlacesTimestamp;
 pub use url::Url;
 
+/// Told how far `PlacesConnection::import_visits` has got.
+pub trait HistoryImportObserver: Send + Sync {
+    /// Called after each batch of observations is committed. The final call has
+    /// `imported == total`.
+    fn on_import_progress(&self, imported: u32, total: u32);
+}
+
 // From https://searchfox.org/mozilla-central/rev/1674b86019a96f076e0f98f1d0f5f3ab9d4e9020/browser/components/newtab/lib/TopSitesFeed.jsm#87
 const SKIP_ONE_PAGE_FRECENCY_THRESHOLD: i64 = 101 + 1;
 
@@ -75,11 +89,11 @@ impl UniffiCustomTypeConverter for PlacesTimestamp {
     type Builtin = i64;
 
     fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
-        Ok(PlacesTimestamp(val as u64))
+        Ok(val.try_into()?)
     }
 
     fn from_custom(obj: Self) -> Self::Builtin {
-        obj.as_millis() as i64
+        obj.into()
     }
 }
 
@@ -278,6 +292,21 @@ impl PlacesConnection {
         })
     }
 
+    #[handle_error(crate::Error)]
+    pub fn set_page_annotation(&self, url: Url, key: String, value: String) -> ApiResult<()> {
+        self.with_conn(|conn| page_annotations::set_page_annotation(conn, &url, &key, &value))
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn get_page_annotations(&self, url: Url) -> ApiResult<Vec<PageAnnotation>> {
+        self.with_conn(|conn| page_annotations::get_page_annotations(conn, &url))
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn delete_page_annotation(&self, url: Url, key: String) -> ApiResult<()> {
+        self.with_conn(|conn| page_annotations::delete_page_annotation(conn, &url, &key))
+    }
+
     /// Add an observation to the database.
     #[handle_error(crate::Error)]
     pub fn apply_observation(&self, visit: VisitObservation) -> ApiResult<()> {
@@ -285,6 +314,25 @@ impl PlacesConnection {
         Ok(())
     }
 
+    /// Add many observations to the database at once, for example when migrating a large
+    /// profile. Returns the number of visits added.
+    #[handle_error(crate::Error)]
+    pub fn import_visits(
+        &self,
+        visits: Vec<VisitObservation>,
+        observer: Option<Box<dyn HistoryImportObserver>>,
+    ) -> ApiResult<u32> {
+        let total = visits.len() as u32;
+        self.with_conn(|conn| {
+            let scope = conn.begin_interrupt_scope()?;
+            history::import_visits(conn, visits, &scope, |imported| {
+                if let Some(observer) = &observer {
+                    observer.on_import_progress(imported, total);
+                }
+            })
+        })
+    }
+
     #[handle_error(crate::Error)]
     pub fn get_visited_urls_in_range(
         &self,
@@ -355,6 +403,24 @@ impl PlacesConnection {
         Ok(result)
     }
 
+    // Like get_visited, invalid URLs get `None` rather than failing the whole call.
+    #[handle_error(crate::Error)]
+    pub fn get_visit_infos_for_urls(
+        &self,
+        urls: Vec<String>,
+    ) -> ApiResult<Vec<Option<HistoryInfo>>> {
+        let iter = urls.into_iter();
+        let mut result = vec![None; iter.len()];
+        let url_idxs = iter
+            .enumerate()
+            .filter_map(|(idx, s)| Url::parse(&s).ok().map(|url| (idx, url)))
+            .collect::<Vec<_>>();
+        self.with_conn(|conn| {
+            history::get_visit_infos_for_urls_into(conn, &url_idxs, &mut result)
+        })?;
+        Ok(result)
+    }
+
     #[handle_error(crate::Error)]
     pub fn delete_visits_for(&self, url: String) -> ApiResult<()> {
         self.with_conn(|conn| {
@@ -381,6 +447,13 @@ impl PlacesConnection {
         self.with_conn(|conn| history::delete_visits_between(conn, start, end))
     }
 
+    #[handle_error(crate::Error)]
+    pub fn delete_recent_history(&self, duration_ms: u64) -> ApiResult<u32> {
+        self.with_conn(|conn| {
+            history::delete_recent_history(conn, Duration::from_millis(duration_ms))
+        })
+    }
+
     #[handle_error(crate::Error)]
     pub fn delete_visit(&self, url: String, timestamp: PlacesTimestamp) -> ApiResult<()> {
         self.with_conn(|conn| {
@@ -412,6 +485,23 @@ impl PlacesConnection {
         })
     }
 
+    #[handle_error(crate::Error)]
+    pub fn get_frequent_unbookmarked(
+        &self,
+        limit: i32,
+        min_visits: i32,
+        exclude_urls: Vec<Url>,
+    ) -> ApiResult<Vec<HistoryInfo>> {
+        self.with_conn(|conn| {
+            history::get_frequent_unbookmarked(conn, limit, min_visits, &exclude_urls)
+        })
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn search_history(&self, query: String, limit: i32) -> ApiResult<Vec<HistoryMatch>> {
+        self.with_conn(|conn| history::search_history(conn, &query, limit))
+    }
+
     // XXX - We probably need to document/name this a little better as it's specifically for
     // history and NOT bookmarks...
     #[handle_error(crate::Error)]
@@ -427,6 +517,13 @@ impl PlacesConnection {
         history::delete_everything(&self.db.lock())
     }
 
+    // Unlike delete_everything_history, this keeps bookmarked pages, and the deletions are
+    // synced.
+    #[handle_error(crate::Error)]
+    pub fn delete_all_history(&self) -> ApiResult<()> {
+        self.with_conn(history::delete_all_history)
+    }
+
     // XXX - This just calls wipe_local under the hood...
     // should probably have this go away?
     #[handle_error(crate::Error)]
@@ -454,6 +551,21 @@ impl PlacesConnection {
         self.with_conn(storage::run_maintenance_checkpoint)
     }
 
+    #[handle_error(crate::Error)]
+    pub fn decay_frecency(&self) -> ApiResult<()> {
+        self.with_conn(|conn| storage::decay_frecency(conn, PlacesTimestamp::now()))
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn get_last_frecency_decay(&self) -> ApiResult<Option<PlacesTimestamp>> {
+        self.with_conn(storage::get_last_frecency_decay)
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn coalesce_duplicate_urls(&self) -> ApiResult<u32> {
+        self.with_conn(history::coalesce_duplicate_urls)
+    }
+
     #[handle_error(crate::Error)]
     pub fn query_autocomplete(&self, search: String, limit: i32) -> ApiResult<Vec<SearchResult>> {
         self.with_conn(|conn| {
@@ -510,20 +622,20 @@ impl PlacesConnection {
     pub fn bookmarks_get_all_with_url(&self, url: String) -> ApiResult<Vec<BookmarkItem>> {
         self.with_conn(|conn| {
             // XXX - We should return the exact type - ie, BookmarkData rather than BookmarkItem.
-            match Url::parse(&url) {
-                Ok(url) => Ok(bookmarks::fetch::fetch_bookmarks_by_url(conn, &url)?
-                    .into_iter()
-                    .map(|b| BookmarkItem::Bookmark { b })
-                    .collect::<Vec<BookmarkItem>>()),
-                Err(e) => {
-                    // There are no bookmarks with the URL if it's invalid.
-                    log::warn!("Invalid URL passed to bookmarks_get_all_with_url, {}", e);
-                    Ok(Vec::<BookmarkItem>::new())
-                }
-            }
+            Ok(fetch_bookmarks_with_url(conn, &url)?
+                .into_iter()
+                .map(|b| BookmarkItem::Bookmark { b })
+                .collect::<Vec<BookmarkItem>>())
         })
     }
 
+    /// Get every bookmark of `url`, each with the guid of the folder it's in, so the app can
+    /// show whether a page is bookmarked, and where.
+    #[handle_error(crate::Error)]
+    pub fn get_bookmarks_with_url(&self, url: String) -> ApiResult<Vec<BookmarkData>> {
+        self.with_conn(|conn| fetch_bookmarks_with_url(conn, &url))
+    }
+
     #[handle_error(crate::Error)]
     pub fn bookmarks_search(&self, query: String, limit: i32) -> ApiResult<Vec<BookmarkItem>> {
         self.with_conn(|conn| {
@@ -573,6 +685,19 @@ impl PlacesConnection {
         self.with_conn(|conn| bookmarks::update_bookmark_from_info(conn, item))
     }
 
+    #[handle_error(crate::Error)]
+    pub fn bookmarks_export_html(&self, path: String) -> ApiResult<()> {
+        self.with_conn(|conn| {
+            let mut writer = BufWriter::new(File::create(path)?);
+            bookmarks::html_export::export_bookmarks_html(conn, &mut writer)
+        })
+    }
+
+    #[handle_error(crate::Error)]
+    pub fn bookmarks_import_html(&self, path: String) -> ApiResult<BookmarksImportResult> {
+        self.with_conn(|conn| import_html_bookmarks(conn, File::open(path)?))
+    }
+
     #[handle_error(crate::Error)]
     pub fn places_history_import_from_ios(
         &self,
@@ -611,6 +736,32 @@ pub struct TopFrecentSiteInfo {
     pub title: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryInfo {
+    pub url: Url,
+    pub title: Option<String>,
+    pub visit_count: i32,
+    pub last_visit_date: PlacesTimestamp,
+}
+
+/// A part of a string to highlight. The offsets are in UTF-16 code units, which is how strings
+/// are indexed on Android and iOS, and `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryMatch {
+    pub url: Url,
+    pub title: Option<String>,
+    pub frecency: i64,
+    pub last_visit_date: PlacesTimestamp,
+    pub title_highlights: Vec<HighlightRange>,
+    pub url_highlights: Vec<HighlightRange>,
+}
+
 pub enum FrecencyThresholdOption {
     None,
     SkipOneTimePages,
@@ -625,6 +776,17 @@ impl FrecencyThresholdOption {
     }
 }
 
+fn fetch_bookmarks_with_url(conn: &PlacesDb, url: &str) -> crate::error::Result<Vec<BookmarkData>> {
+    match Url::parse(url) {
+        Ok(url) => bookmarks::fetch::fetch_bookmarks_by_url(conn, &url),
+        Err(e) => {
+            // There are no bookmarks with the URL if it's invalid.
+            log::warn!("Invalid URL passed when fetching bookmarks by URL, {}", e);
+            Ok(Vec::new())
+        }
+    }
+}
+
 // We define those types to cross the FFI
 // a better approach would be to:
 // - Rename the `Url` in the internal MatchReason to have a different name
@@ -658,7 +820,10 @@ pub struct Dummy {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::bookmarks::BookmarkRootGuid;
     use crate::test::new_mem_connection;
+    use crate::tests::insert_json_tree;
+    use serde_json::json;
 
     #[test]
     fn test_accept_result_with_invalid_url() {
@@ -673,4 +838,64 @@ mod tests {
         let invalid_url = "http://1234.56.78.90".to_string();
         assert!(PlacesConnection::bookmarks_get_all_with_url(&conn, invalid_url).is_ok());
     }
+
+    #[test]
+    fn test_get_bookmarks_with_url() {
+        let conn = PlacesConnection::new(new_mem_connection());
+        conn.with_conn(|db| {
+            insert_json_tree(
+                db,
+                json!({
+                    "guid": String::from(BookmarkRootGuid::Unfiled.as_str()),
+                    "children": [
+                        {
+                            "guid": "bookmark1___",
+                            "url": "https://www.example.com/",
+                        },
+                        {
+                            "guid": "folder1_____",
+                            "title": "A folder",
+                            "children": [
+                                {
+                                    "guid": "bookmark2___",
+                                    "url": "https://www.example.com/",
+                                },
+                                {
+                                    "guid": "bookmark3___",
+                                    "url": "https://www.example.com/other",
+                                },
+                            ],
+                        },
+                    ],
+                }),
+            );
+            Ok(())
+        })
+        .unwrap();
+
+        let mut bookmarks = conn
+            .get_bookmarks_with_url("https://www.example.com/".to_string())
+            .unwrap();
+        bookmarks.sort_by(|a, b| a.guid.as_str().cmp(b.guid.as_str()));
+        let found: Vec<_> = bookmarks
+            .iter()
+            .map(|b| (b.guid.as_str(), b.parent_guid.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("bookmark1___", BookmarkRootGuid::Unfiled.as_str()),
+                ("bookmark2___", "folder1_____"),
+            ]
+        );
+
+        assert!(conn
+            .get_bookmarks_with_url("https://www.example.com/missing".to_string())
+            .unwrap()
+            .is_empty());
+        assert!(conn
+            .get_bookmarks_with_url("http://1234.56.78.90".to_string())
+            .unwrap()
+            .is_empty());
+    }
 }
@@ -4,7 +4,10 @@
 
 use crate::db::{PlacesDb, SharedPlacesDb};
 use crate::error::*;
-use crate::storage::history::{delete_everything, history_sync::reset};
+use crate::storage::history::{
+    delete_everything,
+    history_sync::{has_outgoing, reset},
+};
 use crate::storage::{get_meta, put_meta};
 use interrupt_support::SqlInterruptScope;
 use std::sync::Arc;
@@ -44,7 +47,7 @@ fn do_sync_finished(
         "sync completed after uploading {} records",
         records_synced.len()
     );
-    finish_plan(db)?;
+    finish_plan(db, &records_synced)?;
 
     // write timestamp to reflect what we just wrote.
     // XXX - should clean up transactions, but we *are not* in a transaction
@@ -113,6 +116,11 @@ impl SyncEngine for HistorySyncEngine {
         Ok(())
     }
 
+    // History records don't depend on each other, so any we fail to upload can wait.
+    fn upload_must_be_atomic(&self) -> bool {
+        false
+    }
+
     fn get_collection_request(
         &self,
         server_timestamp: ServerTimestamp,
@@ -152,4 +160,8 @@ impl SyncEngine for HistorySyncEngine {
         delete_everything(&self.db.lock())?;
         Ok(())
     }
+
+    fn has_pending_changes(&self) -> anyhow::Result<bool> {
+        Ok(has_outgoing(&self.db.lock())?)
+    }
 }
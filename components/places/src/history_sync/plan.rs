@@ -280,9 +280,9 @@ pub fn get_planned_outgoing(db: &PlacesDb) -> Result<Vec<OutgoingBso>> {
     Ok(outgoing)
 }
 
-pub fn finish_plan(db: &PlacesDb) -> Result<()> {
+pub fn finish_plan(db: &PlacesDb, uploaded: &[SyncGuid]) -> Result<()> {
     let tx = db.begin_transaction()?;
-    finish_outgoing(db)?;
+    finish_outgoing(db, uploaded)?;
     log::trace!("Committing final sync plan");
     tx.commit()?;
     Ok(())
@@ -928,16 +928,67 @@ mod tests {
 
         let outgoing = apply_and_get_outgoing(&db, vec![]);
         assert_eq!(outgoing.len(), 1, "tombstone should be uploaded");
-        finish_plan(&db)?;
+        finish_plan(&db, &[guid])?;
         // tombstone should be removed.
         assert_eq!(get_tombstone_count(&db), 0);
 
         Ok(())
     }
 
+    #[test]
+    fn test_outgoing_not_uploaded() -> Result<()> {
+        let _ = env_logger::try_init();
+        let db = PlacesDb::open_in_memory(ConnectionType::Sync)?;
+        let observe = |url: &Url| {
+            apply_observation(
+                &db,
+                VisitObservation::new(url.clone())
+                    .with_visit_type(VisitTransition::Link)
+                    .with_at(Some(SystemTime::now().into())),
+            )
+        };
+        let uploaded_url = Url::parse("https://example.com/uploaded")?;
+        let skipped_url = Url::parse("https://example.com/skipped")?;
+        let deleted_url = Url::parse("https://example.com/deleted")?;
+        for url in [&uploaded_url, &skipped_url, &deleted_url] {
+            observe(url)?;
+        }
+        let deleted_guid = get_existing_guid(&db, &deleted_url);
+        let uploaded = apply_and_get_outgoing(&db, vec![])
+            .into_iter()
+            .map(|o| o.envelope.id)
+            .collect::<Vec<_>>();
+        finish_plan(&db, &uploaded)?;
+        delete_visits_for(&db, &deleted_guid)?;
+        // Change the other two again.
+        observe(&uploaded_url)?;
+        observe(&skipped_url)?;
+
+        let outgoing = apply_and_get_outgoing(&db, vec![]);
+        assert_eq!(outgoing.len(), 3);
+        // Only the first was uploaded.
+        finish_plan(&db, &[get_existing_guid(&db, &uploaded_url)])?;
+        assert_eq!(get_sync(&db, &uploaded_url), (SyncStatus::Normal, 0));
+        assert_eq!(get_sync(&db, &skipped_url).0, SyncStatus::Normal);
+        assert!(get_sync(&db, &skipped_url).1 > 0);
+        assert_eq!(get_tombstone_count(&db), 1);
+
+        // So the others are uploaded again next time.
+        let outgoing = apply_and_get_outgoing(&db, vec![]);
+        let mut ids = outgoing
+            .iter()
+            .map(|o| o.envelope.id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        let mut expected = vec![get_existing_guid(&db, &skipped_url), deleted_guid];
+        expected.sort();
+        assert_eq!(ids, expected);
+        Ok(())
+    }
+
     #[test]
     fn test_clamp_visit_date() {
-        let ts = Timestamp::from(727_747_199_999);
+        let ts = Timestamp::from(727_747_199_999u64);
         assert!(clamp_visit_date(ts).is_err());
 
         let ts = Timestamp::now();
@@ -171,6 +171,14 @@ pub struct HistoryMigrationResult {
     pub total_duration: u64,
 }
 
+#[derive(Serialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct BookmarksImportResult {
+    pub num_total: u32,
+    pub num_imported: u32,
+    // Items we couldn't import, or which were already there.
+    pub num_skipped: u32,
+}
+
 pub fn define_history_migration_functions(c: &Connection) -> Result<()> {
     use rusqlite::functions::FunctionFlags;
     c.create_scalar_function(
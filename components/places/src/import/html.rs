@@ -0,0 +1,577 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::io::Read;
+
+use crate::error::{Error, Result};
+use crate::import::common::BookmarksImportResult;
+use crate::storage::bookmarks::html_export::ROOT_FOLDERS;
+use crate::storage::bookmarks::{
+    insert_bookmark_in_tx, BookmarkPosition, BookmarkRootGuid, InsertableBookmark,
+    InsertableFolder, InsertableItem, InsertableSeparator,
+};
+use crate::storage::delete_pending_temp_tables;
+use crate::PlacesDb;
+use interrupt_support::SqlInterruptScope;
+use sql_support::ConnExt;
+use sync_guid::Guid as SyncGuid;
+use types::Timestamp;
+use url::Url;
+
+/// Imports a bookmarks file in the Netscape bookmark file format, as written
+/// by desktop browsers to `bookmarks.html`.
+///
+/// ### Basic process
+///
+/// - Read the whole file, replacing any invalid UTF-8.
+/// - Walk its tags, keeping a stack of the folders we're in. Items outside any
+///   folder go into the bookmarks menu, and the folders marked as the toolbar
+///   and "other bookmarks" are merged into our toolbar and unfiled roots. The
+///   menu and mobile folders from our own exports are merged into those roots.
+/// - Folders are merged with an existing folder of the same title in the same
+///   parent, and bookmarks are skipped if their parent already has a bookmark
+///   for the same URL, so importing the same file twice is harmless.
+/// - Anything we can't make sense of (unknown tags, invalid URLs, unbalanced
+///   lists) is skipped rather than failing the whole import.
+pub fn import(conn: &PlacesDb, mut reader: impl Read) -> Result<BookmarksImportResult> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let html = String::from_utf8_lossy(&bytes);
+
+    let scope = conn.begin_interrupt_scope()?;
+    let tx = conn.begin_transaction()?;
+    let result = do_import(conn, &html, &scope);
+    delete_pending_temp_tables(conn)?;
+    match result {
+        Ok(_) => tx.commit()?,
+        Err(_) => tx.rollback()?,
+    }
+    result
+}
+
+struct Parent {
+    guid: SyncGuid,
+    // Separators can't be deduped, so we only add them to folders created by
+    // this import.
+    is_new: bool,
+}
+
+fn do_import(
+    conn: &PlacesDb,
+    html: &str,
+    scope: &SqlInterruptScope,
+) -> Result<BookmarksImportResult> {
+    let mut result = BookmarksImportResult::default();
+    let mut parents: Vec<Parent> = Vec::new();
+    // The folder whose `<DL>` we expect next.
+    let mut pending_folder: Option<Parent> = None;
+
+    let mut tokens = Tokenizer::new(html);
+    while let Some(tag) = tokens.next_tag() {
+        scope.err_if_interrupted()?;
+        match tag.name.as_str() {
+            "DL" => {
+                let parent = pending_folder
+                    .take()
+                    .unwrap_or_else(|| match parents.last() {
+                        // A list without a heading, so keep adding to the current folder.
+                        Some(parent) => Parent {
+                            guid: parent.guid.clone(),
+                            is_new: parent.is_new,
+                        },
+                        None => Parent {
+                            guid: BookmarkRootGuid::Menu.as_guid(),
+                            is_new: false,
+                        },
+                    });
+                parents.push(parent);
+            }
+            "/DL" => {
+                pending_folder = None;
+                parents.pop();
+            }
+            "H3" => {
+                let title = decode_entities(tokens.text_until("/H3"));
+                result.num_total += 1;
+                let parent_guid = current_parent(&parents);
+                let root = ROOT_FOLDERS
+                    .iter()
+                    .find(|(_, attr, _)| tag.attr(attr).is_some())
+                    .map(|(root, _, _)| *root);
+                let folder = match root {
+                    Some(root) => Parent {
+                        guid: root.as_guid(),
+                        is_new: false,
+                    },
+                    None => match find_folder(conn, &parent_guid, &title)? {
+                        Some(guid) => Parent {
+                            guid,
+                            is_new: false,
+                        },
+                        None => {
+                            let guid = insert_bookmark_in_tx(
+                                conn,
+                                InsertableItem::Folder {
+                                    f: InsertableFolder {
+                                        parent_guid,
+                                        position: BookmarkPosition::Append,
+                                        date_added: tag.timestamp("ADD_DATE"),
+                                        last_modified: tag.timestamp("LAST_MODIFIED"),
+                                        guid: None,
+                                        title: Some(title),
+                                        children: Vec::new(),
+                                    },
+                                },
+                            )?;
+                            result.num_imported += 1;
+                            Parent { guid, is_new: true }
+                        }
+                    },
+                };
+                if !folder.is_new {
+                    result.num_skipped += 1;
+                }
+                pending_folder = Some(folder);
+            }
+            "A" => {
+                let title = decode_entities(tokens.text_until("/A"));
+                result.num_total += 1;
+                let url = tag
+                    .attr("HREF")
+                    .map(decode_entities)
+                    .and_then(|href| Url::parse(&href).ok())
+                    .filter(|url| url.scheme() != "place");
+                let url = match url {
+                    Some(url) => url,
+                    None => {
+                        result.num_skipped += 1;
+                        continue;
+                    }
+                };
+                let parent_guid = current_parent(&parents);
+                if has_bookmark(conn, &parent_guid, &url)? {
+                    result.num_skipped += 1;
+                    continue;
+                }
+                let inserted = insert_bookmark_in_tx(
+                    conn,
+                    InsertableItem::Bookmark {
+                        b: InsertableBookmark {
+                            parent_guid,
+                            position: BookmarkPosition::Append,
+                            date_added: tag.timestamp("ADD_DATE"),
+                            last_modified: tag.timestamp("LAST_MODIFIED"),
+                            guid: None,
+                            url,
+                            title: if title.is_empty() { None } else { Some(title) },
+                        },
+                    },
+                );
+                match inserted {
+                    Ok(_) => result.num_imported += 1,
+                    // eg, the URL is too long.
+                    Err(Error::InvalidPlaceInfo(e)) => {
+                        log::warn!("Skipping invalid bookmark: {}", e);
+                        result.num_skipped += 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            "HR" => match parents.last() {
+                Some(parent) if parent.is_new => {
+                    insert_bookmark_in_tx(
+                        conn,
+                        InsertableItem::Separator {
+                            s: InsertableSeparator {
+                                parent_guid: parent.guid.clone(),
+                                position: BookmarkPosition::Append,
+                                date_added: None,
+                                last_modified: None,
+                                guid: None,
+                            },
+                        },
+                    )?;
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+    Ok(result)
+}
+
+fn current_parent(parents: &[Parent]) -> SyncGuid {
+    match parents.last() {
+        Some(parent) => parent.guid.clone(),
+        None => BookmarkRootGuid::Menu.as_guid(),
+    }
+}
+
+fn find_folder(conn: &PlacesDb, parent_guid: &SyncGuid, title: &str) -> Result<Option<SyncGuid>> {
+    Ok(conn.try_query_row(
+        "SELECT b.guid FROM moz_bookmarks b
+         JOIN moz_bookmarks p ON p.id = b.parent
+         WHERE p.guid = :parent_guid AND b.type = 2 AND IFNULL(b.title, '') = :title
+         ORDER BY b.position
+         LIMIT 1",
+        rusqlite::named_params! {
+            ":parent_guid": parent_guid,
+            ":title": title,
+        },
+        |row| row.get::<_, SyncGuid>(0),
+        true,
+    )?)
+}
+
+fn has_bookmark(conn: &PlacesDb, parent_guid: &SyncGuid, url: &Url) -> Result<bool> {
+    Ok(conn.exists(
+        "SELECT 1 FROM moz_bookmarks b
+         JOIN moz_bookmarks p ON p.id = b.parent
+         JOIN moz_places h ON h.id = b.fk
+         WHERE p.guid = :parent_guid AND h.url_hash = hash(:url) AND h.url = :url",
+        rusqlite::named_params! {
+            ":parent_guid": parent_guid,
+            ":url": url.as_str(),
+        },
+    )?)
+}
+
+struct Tag {
+    // Upper-cased, with a leading `/` for closing tags.
+    name: String,
+    // Upper-cased names, and raw values.
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Dates in bookmark files are in seconds. We ignore any we can't make
+    // sense of, so the item gets the current time instead.
+    fn timestamp(&self, name: &str) -> Option<Timestamp> {
+        let seconds = self.attr(name)?.trim().parse::<u64>().ok()?;
+        let ts = Timestamp(seconds.checked_mul(1000)?);
+        (Timestamp::EARLIEST <= ts && ts <= Timestamp::now()).then_some(ts)
+    }
+}
+
+// A very forgiving tokenizer, which only knows enough about HTML to find the
+// tags we care about and the text following them.
+struct Tokenizer<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(html: &'a str) -> Self {
+        Self { rest: html }
+    }
+
+    fn next_tag(&mut self) -> Option<Tag> {
+        loop {
+            let start = self.rest.find('<')?;
+            let after = &self.rest[start + 1..];
+            // Skip comments and the doctype.
+            if let Some(comment) = after.strip_prefix("!--") {
+                self.rest = match comment.find("-->") {
+                    Some(end) => &comment[end + 3..],
+                    None => "",
+                };
+                continue;
+            }
+            let end = match after.find('>') {
+                Some(end) => end,
+                None => {
+                    self.rest = "";
+                    return None;
+                }
+            };
+            self.rest = &after[end + 1..];
+            let contents = &after[..end];
+            if contents.starts_with('!') || contents.starts_with('?') {
+                continue;
+            }
+            let name_end = contents
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(contents.len());
+            let name = contents[..name_end].trim_end_matches('/');
+            if name.is_empty() {
+                continue;
+            }
+            return Some(Tag {
+                name: name.to_ascii_uppercase(),
+                attrs: parse_attributes(&contents[name_end..]),
+            });
+        }
+    }
+
+    // Returns the text up to the next tag, consuming the closing tag `name` if
+    // it's the next one.
+    fn text_until(&mut self, name: &str) -> &'a str {
+        let end = self.rest.find('<').unwrap_or(self.rest.len());
+        let text = &self.rest[..end];
+        self.rest = &self.rest[end..];
+        let mut peek = Tokenizer { rest: self.rest };
+        if let Some(tag) = peek.next_tag() {
+            if tag.name == name {
+                self.rest = peek.rest;
+            }
+        }
+        text.trim()
+    }
+}
+
+fn parse_attributes(s: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].trim_end_matches('/').to_ascii_uppercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let after = &after[1..];
+                        match after.find(quote) {
+                            Some(end) => (&after[..end], &after[end + 1..]),
+                            None => (after, ""),
+                        }
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_ascii_whitespace())
+                            .unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remaining.trim_start();
+                value.to_string()
+            }
+            None => String::new(),
+        };
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+    }
+    attrs
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';').map(|end| &rest[1..=end]);
+        let c = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, c) {
+            (Some(entity), Some(c)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::places_api::test::new_mem_connection;
+    use crate::storage::bookmarks::fetch::{fetch_bookmarks_by_url, fetch_tree, Item};
+    use crate::storage::bookmarks::html_export::export_bookmarks_html;
+    use crate::tests::insert_json_tree;
+    use serde_json::json;
+
+    const HTML: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+
+<DL><p>
+    <DT><A HREF="https://www.mozilla.org/" ADD_DATE="1600000000" LAST_MODIFIED="1600000001">Mozilla &amp; friends</A>
+    <DT><H3 ADD_DATE="1500000000">Folder &lt;1&gt;</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/a">A</A>
+        <HR>
+        <DT><A HREF="not a url">Broken</A>
+        <DT><A HREF="place:sort=8&maxResults=10">Most Visited</A>
+        <DT><a href='https://example.com/b' add_date=bogus>B
+    </DL><p>
+    <DT><H3 PERSONAL_TOOLBAR_FOLDER="true">Bookmarks Toolbar</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/toolbar">On the toolbar</A>
+    </DL><p>
+</DL>
+"#;
+
+    fn children(conn: &PlacesDb, guid: &SyncGuid) -> Vec<Item> {
+        match fetch_tree(conn, guid).unwrap().unwrap() {
+            Item::Folder { f } => f.child_nodes.unwrap(),
+            _ => panic!("not a folder"),
+        }
+    }
+
+    #[test]
+    fn test_import_html() -> Result<()> {
+        let conn = new_mem_connection();
+        let result = import(&conn, HTML.as_bytes())?;
+        assert_eq!(
+            result,
+            BookmarksImportResult {
+                num_total: 8,
+                num_imported: 5,
+                num_skipped: 3,
+            }
+        );
+
+        let menu = children(&conn, &BookmarkRootGuid::Menu.as_guid());
+        assert_eq!(menu.len(), 2);
+        let mozilla = match &menu[0] {
+            Item::Bookmark { b } => b,
+            _ => panic!("expected a bookmark"),
+        };
+        assert_eq!(mozilla.title.as_deref(), Some("Mozilla & friends"));
+        assert_eq!(mozilla.date_added, Timestamp(1_600_000_000_000));
+        assert_eq!(mozilla.last_modified, Timestamp(1_600_000_001_000));
+
+        let folder = match &menu[1] {
+            Item::Folder { f } => f,
+            _ => panic!("expected a folder"),
+        };
+        assert_eq!(folder.title.as_deref(), Some("Folder <1>"));
+        assert_eq!(folder.date_added, Timestamp(1_500_000_000_000));
+        let folder_children = folder.child_nodes.as_ref().unwrap();
+        assert_eq!(folder_children.len(), 3);
+        assert!(matches!(folder_children[1], Item::Separator { .. }));
+        match &folder_children[2] {
+            Item::Bookmark { b } => {
+                assert_eq!(b.url.as_str(), "https://example.com/b");
+                assert_eq!(b.title.as_deref(), Some("B"));
+            }
+            _ => panic!("expected a bookmark"),
+        }
+
+        let toolbar = children(&conn, &BookmarkRootGuid::Toolbar.as_guid());
+        assert_eq!(toolbar.len(), 1);
+
+        // Importing the same file again doesn't duplicate anything.
+        let result = import(&conn, HTML.as_bytes())?;
+        assert_eq!(result.num_imported, 0);
+        assert_eq!(result.num_skipped, 8);
+        assert_eq!(children(&conn, &BookmarkRootGuid::Menu.as_guid()).len(), 2);
+        assert_eq!(
+            fetch_bookmarks_by_url(&conn, &Url::parse("https://example.com/a")?)?.len(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_malformed_html() -> Result<()> {
+        let conn = new_mem_connection();
+        let result = import(
+            &conn,
+            "</DL><p></DL><DT><A HREF=\"https://example.com/\">Unclosed<DT><H3>Dangling".as_bytes(),
+        )?;
+        assert_eq!(result.num_imported, 2);
+        assert_eq!(children(&conn, &BookmarkRootGuid::Menu.as_guid()).len(), 2);
+
+        // Garbage isn't an error, there's just nothing to import.
+        let result = import(&conn, &b"\xff\xfe<<>>< A"[..])?;
+        assert_eq!(result, BookmarksImportResult::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_round_trip() -> Result<()> {
+        let conn = new_mem_connection();
+        insert_json_tree(
+            &conn,
+            json!({
+                "guid": String::from(BookmarkRootGuid::Toolbar.as_str()),
+                "children": [
+                    {
+                        "title": "Folder",
+                        "children": [
+                            {"title": "Example", "url": "https://example.com/"},
+                        ],
+                    },
+                ]
+            }),
+        );
+        for (root, url) in [
+            (BookmarkRootGuid::Menu, "https://example.com/menu"),
+            (BookmarkRootGuid::Unfiled, "https://example.com/unfiled"),
+            (BookmarkRootGuid::Mobile, "https://example.com/mobile"),
+        ] {
+            insert_json_tree(
+                &conn,
+                json!({
+                    "guid": String::from(root.as_str()),
+                    "children": [{"title": "In a root", "url": url}],
+                }),
+            );
+        }
+        let mut html = Vec::new();
+        export_bookmarks_html(&conn, &mut html)?;
+
+        let other = new_mem_connection();
+        import(&other, html.as_slice())?;
+
+        // Each root's folder is mapped back on to the root, rather than
+        // becoming a new folder inside the menu.
+        for (root, url) in [
+            (BookmarkRootGuid::Menu, "https://example.com/menu"),
+            (BookmarkRootGuid::Unfiled, "https://example.com/unfiled"),
+            (BookmarkRootGuid::Mobile, "https://example.com/mobile"),
+        ] {
+            let items = children(&other, &root.as_guid());
+            assert_eq!(items.len(), 1, "{:?} has the wrong children", root);
+            match &items[0] {
+                Item::Bookmark { b } => assert_eq!(b.url.as_str(), url),
+                _ => panic!("expected a bookmark"),
+            }
+        }
+        let toolbar = children(&other, &BookmarkRootGuid::Toolbar.as_guid());
+        assert_eq!(toolbar.len(), 1);
+        match &toolbar[0] {
+            Item::Folder { f } => {
+                assert_eq!(f.title.as_deref(), Some("Folder"));
+                assert_eq!(f.child_nodes.as_ref().unwrap().len(), 1);
+            }
+            _ => panic!("expected a folder"),
+        }
+        Ok(())
+    }
+}
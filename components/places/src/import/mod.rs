@@ -3,5 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 pub mod common;
+pub mod html;
 pub mod ios;
+pub use html::import as import_html_bookmarks;
 pub use ios::import_history as import_ios_history;
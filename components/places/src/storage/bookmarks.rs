@@ -25,6 +25,7 @@ pub use root_guid::{BookmarkRootGuid, USER_CONTENT_ROOTS};
 
 mod conversions;
 pub mod fetch;
+pub mod html_export;
 pub mod json_tree;
 mod root_guid;
 
@@ -284,7 +285,7 @@ pub fn maybe_truncate_title<'a>(t: &Option<&'a str>) -> Option<&'a str> {
     t.map(|title| slice_up_to(title, TITLE_LENGTH_MAX))
 }
 
-fn insert_bookmark_in_tx(db: &PlacesDb, bm: InsertableItem) -> Result<SyncGuid> {
+pub(crate) fn insert_bookmark_in_tx(db: &PlacesDb, bm: InsertableItem) -> Result<SyncGuid> {
     // find the row ID of the parent.
     if bm.parent_guid() == BookmarkRootGuid::Root {
         return Err(InvalidPlaceInfo::CannotUpdateRoot(BookmarkRootGuid::Root).into());
@@ -318,7 +319,7 @@ fn insert_bookmark_in_tx(db: &PlacesDb, bm: InsertableItem) -> Result<SyncGuid>
                :guid, :syncStatus, :syncChangeCounter)";
 
     let guid = bm.guid().clone().unwrap_or_else(SyncGuid::random);
-    if !guid.is_valid_for_places() || !guid.is_valid_for_sync_server() {
+    if !guid.is_valid_for_places() || !guid.is_valid() {
         return Err(InvalidPlaceInfo::InvalidGuid.into());
     }
     let date_added = bm.date_added().unwrap_or_else(Timestamp::now);
@@ -560,6 +561,10 @@ pub struct BookmarkUpdateInfo {
 }
 
 pub fn update_bookmark_from_info(db: &PlacesDb, info: BookmarkUpdateInfo) -> Result<()> {
+    // A malformed guid can't name an existing bookmark.
+    if !info.guid.is_valid() {
+        return Err(InvalidPlaceInfo::NoSuchGuid(info.guid.to_string()).into());
+    }
     let tx = db.begin_transaction()?;
     let existing = get_raw_bookmark(db, &info.guid)?
         .ok_or_else(|| InvalidPlaceInfo::NoSuchGuid(info.guid.to_string()))?;
@@ -571,6 +576,9 @@ pub fn update_bookmark_from_info(db: &PlacesDb, info: BookmarkUpdateInfo) -> Res
 }
 
 pub fn update_bookmark(db: &PlacesDb, guid: &SyncGuid, item: &UpdatableItem) -> Result<()> {
+    if !guid.is_valid() {
+        return Err(InvalidPlaceInfo::NoSuchGuid(guid.to_string()).into());
+    }
     let tx = db.begin_transaction()?;
     let existing = get_raw_bookmark(db, guid)?
         .ok_or_else(|| InvalidPlaceInfo::NoSuchGuid(guid.to_string()))?;
@@ -1796,6 +1804,41 @@ mod tests {
         .expect_err("can't move to the root");
     }
 
+    #[test]
+    fn test_invalid_guids() {
+        let conn = new_mem_connection();
+        let malformed = ["", "bookmark,___", "bookmark\u{e9}___", &"b".repeat(65)];
+
+        for guid in malformed {
+            let bm = InsertableItem::Bookmark {
+                b: InsertableBookmark {
+                    parent_guid: BookmarkRootGuid::Unfiled.into(),
+                    position: BookmarkPosition::Append,
+                    date_added: None,
+                    last_modified: None,
+                    guid: Some(guid.into()),
+                    url: Url::parse("https://www.example.com").unwrap(),
+                    title: None,
+                },
+            };
+            let err = insert_bookmark(&conn, bm).expect_err("should fail to insert");
+            assert!(
+                matches!(err, Error::InvalidPlaceInfo(InvalidPlaceInfo::InvalidGuid)),
+                "unexpected error inserting {guid:?}: {err:?}"
+            );
+
+            let err = update_bookmark(&conn, &guid.into(), &UpdatableBookmark::default().into())
+                .expect_err("should fail to update");
+            assert!(
+                matches!(
+                    err,
+                    Error::InvalidPlaceInfo(InvalidPlaceInfo::NoSuchGuid(_))
+                ),
+                "unexpected error updating {guid:?}: {err:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_delete_everything() -> Result<()> {
         let conn = new_mem_connection();
@@ -5,6 +5,7 @@
 use super::super::bookmarks::json_tree::{self, FetchDepth};
 use super::*;
 use rusqlite::Row;
+use std::collections::HashMap;
 
 // A helper that will ensure tests fail, but in production will make log noise instead.
 fn noisy_debug_assert_eq<T: std::cmp::PartialEq + std::fmt::Debug>(a: &T, b: &T, msg: &str) {
@@ -137,11 +138,16 @@ impl Item {
 /// expects us to walk a tree, so we do.
 ///
 /// Extra complication for the fact the root has a None parent_guid :)
+///
+/// Children take their stored position from `positions`, rather than their
+/// index in the tree, because malformed bookmarks are left out of the tree and
+/// the positions we report must match the ones used to insert and move items.
 fn folder_from_node_with_parent_info(
     f: json_tree::FolderNode,
     parent_guid: Option<SyncGuid>,
     position: u32,
     depth_left: usize,
+    positions: &HashMap<SyncGuid, u32>,
 ) -> Folder {
     let guid = f.guid.expect("all items have guids");
     // We always provide child_guids, and only provide child_nodes if we are
@@ -158,11 +164,16 @@ fn folder_from_node_with_parent_info(
                 .into_iter()
                 .enumerate()
                 .map(|(child_pos, child)| {
+                    let child_pos = positions
+                        .get(child.guid())
+                        .copied()
+                        .unwrap_or(child_pos as u32);
                     item_from_node_with_parent_info(
                         child,
                         guid.clone(),
-                        child_pos as u32,
+                        child_pos,
                         depth_left - 1,
+                        positions,
                     )
                 })
                 .collect(),
@@ -187,6 +198,7 @@ fn item_from_node_with_parent_info(
     parent_guid: SyncGuid,
     position: u32,
     depth_left: usize,
+    positions: &HashMap<SyncGuid, u32>,
 ) -> Item {
     match n {
         json_tree::BookmarkTreeNode::Bookmark { b } => BookmarkData {
@@ -208,7 +220,8 @@ fn item_from_node_with_parent_info(
         }
         .into(),
         json_tree::BookmarkTreeNode::Folder { f } => {
-            folder_from_node_with_parent_info(f, Some(parent_guid), position, depth_left).into()
+            folder_from_node_with_parent_info(f, Some(parent_guid), position, depth_left, positions)
+                .into()
         }
     }
 }
@@ -227,12 +240,14 @@ pub fn fetch_tree_with_depth(
     target_depth: &FetchDepth,
 ) -> Result<Option<Item>> {
     let _tx = db.begin_transaction()?;
-    let (tree, parent_guid, position) = if let Some((tree, parent_guid, position)) =
-        json_tree::fetch_tree(db, item_guid, target_depth)?
-    {
-        (tree, parent_guid, position)
-    } else {
-        return Ok(None);
+    let json_tree::FetchedTree {
+        root: tree,
+        parent_guid,
+        position,
+        positions,
+    } = match json_tree::fetch_tree_with_positions(db, item_guid, target_depth)? {
+        Some(fetched) => fetched,
+        None => return Ok(None),
     };
     // parent_guid being an Option<> is a bit if a pain :(
     Ok(Some(match tree {
@@ -245,13 +260,15 @@ pub fn fetch_tree_with_depth(
                 FetchDepth::Specific(v) => *v,
                 FetchDepth::Deepest => usize::MAX,
             };
-            folder_from_node_with_parent_info(f, parent_guid, position, depth_left).into()
+            folder_from_node_with_parent_info(f, parent_guid, position, depth_left, &positions)
+                .into()
         }
         _ => item_from_node_with_parent_info(
             tree,
             parent_guid.expect("must have parent"),
             position,
             0,
+            &positions,
         ),
     }))
 }
@@ -788,6 +805,185 @@ mod test {
 
         Ok(())
     }
+    #[test]
+    fn test_fetch_separators() -> Result<()> {
+        let conns = new_mem_connections();
+
+        insert_json_tree(
+            &conns.write,
+            json!({
+                "guid": BookmarkRootGuid::Mobile.as_guid(),
+                "children": [
+                    {
+                        "guid": "bookmark1___",
+                        "url": "https://www.example1.com/"
+                    },
+                    {
+                        "guid": "separator1__",
+                        "type": BookmarkType::Separator as u8,
+                    },
+                    {
+                        "guid": "folder1_____",
+                        "title": "A folder",
+                        "children": [
+                            {
+                                "guid": "separator2__",
+                                "type": BookmarkType::Separator as u8,
+                            },
+                            {
+                                "guid": "bookmark2___",
+                                "url": "https://www.example2.com/"
+                            },
+                        ]
+                    },
+                    {
+                        "guid": "separator3__",
+                        "type": BookmarkType::Separator as u8,
+                    },
+                ]
+            }),
+        );
+
+        // The guid, position and whether it's a separator, for each child.
+        fn children(nodes: &[Item]) -> Vec<(String, u32, bool)> {
+            nodes
+                .iter()
+                .map(|c| {
+                    (
+                        c.guid().to_string(),
+                        *c.position(),
+                        matches!(c, Item::Separator { .. }),
+                    )
+                })
+                .collect()
+        }
+        let fetch_mobile = |get_tree: bool| -> Result<Folder> {
+            let item = if get_tree {
+                fetch_tree(&conns.read, BookmarkRootGuid::Mobile.guid())?
+            } else {
+                fetch_bookmark(&conns.read, BookmarkRootGuid::Mobile.guid(), true)?
+            };
+            match item.unwrap() {
+                Item::Folder { f } => {
+                    // The child guids include separators too.
+                    assert_eq!(
+                        f.child_guids.as_ref().unwrap(),
+                        &f.child_nodes
+                            .as_ref()
+                            .unwrap()
+                            .iter()
+                            .map(|c| c.guid().clone())
+                            .collect::<Vec<SyncGuid>>()
+                    );
+                    Ok(f)
+                }
+                _ => panic!("not a folder?"),
+            }
+        };
+
+        for get_tree in [true, false] {
+            let mobile = fetch_mobile(get_tree)?;
+            let nodes = mobile.child_nodes.unwrap();
+            assert_eq!(
+                children(&nodes),
+                [
+                    ("bookmark1___".to_string(), 0, false),
+                    ("separator1__".to_string(), 1, true),
+                    ("folder1_____".to_string(), 2, false),
+                    ("separator3__".to_string(), 3, true),
+                ]
+            );
+            let folder = match &nodes[2] {
+                Item::Folder { f } => f,
+                _ => panic!("not a folder?"),
+            };
+            if get_tree {
+                assert_eq!(
+                    children(folder.child_nodes.as_ref().unwrap()),
+                    [
+                        ("separator2__".to_string(), 0, true),
+                        ("bookmark2___".to_string(), 1, false),
+                    ]
+                );
+            } else {
+                assert!(folder.child_nodes.is_none());
+                assert_eq!(folder.child_guids.as_ref().unwrap().len(), 2);
+            }
+        }
+
+        // Fetching a separator directly gives its parent and position.
+        match fetch_bookmark(&conns.read, &SyncGuid::from("separator3__"), true)?.unwrap() {
+            Item::Separator { s } => {
+                assert_eq!(s.parent_guid, BookmarkRootGuid::Mobile);
+                assert_eq!(s.position, 3);
+            }
+            _ => panic!("not a separator?"),
+        }
+
+        // Inserting and moving separators keeps the positions in step.
+        let new_sep = insert_bookmark(
+            &conns.write,
+            InsertableSeparator {
+                parent_guid: BookmarkRootGuid::Mobile.into(),
+                position: BookmarkPosition::Specific { pos: 1 },
+                date_added: None,
+                last_modified: None,
+                guid: Some(SyncGuid::from("separator4__")),
+            }
+            .into(),
+        )?;
+        update_bookmark(
+            &conns.write,
+            &SyncGuid::from("separator3__"),
+            &UpdatableSeparator {
+                location: UpdateTreeLocation::Position {
+                    pos: BookmarkPosition::Specific { pos: 0 },
+                },
+            }
+            .into(),
+        )?;
+        assert_eq!(
+            children(&fetch_mobile(true)?.child_nodes.unwrap()),
+            [
+                ("separator3__".to_string(), 0, true),
+                ("bookmark1___".to_string(), 1, false),
+                (new_sep.to_string(), 2, true),
+                ("separator1__".to_string(), 3, true),
+                ("folder1_____".to_string(), 4, false),
+            ]
+        );
+
+        // A malformed bookmark is left out, but the items after it keep their
+        // stored positions, so they can still be moved relative to each other.
+        append_invalid_bookmark(
+            &conns.write,
+            BookmarkRootGuid::Mobile.guid(),
+            "invalid url",
+            "badurl",
+        );
+        insert_bookmark(
+            &conns.write,
+            InsertableSeparator {
+                parent_guid: BookmarkRootGuid::Mobile.into(),
+                position: BookmarkPosition::Append,
+                date_added: None,
+                last_modified: None,
+                guid: Some(SyncGuid::from("separator5__")),
+            }
+            .into(),
+        )?;
+        for get_tree in [true, false] {
+            let nodes = fetch_mobile(get_tree)?.child_nodes.unwrap();
+            assert_eq!(nodes.len(), 6);
+            assert_eq!(
+                children(&nodes[5..]),
+                [("separator5__".to_string(), 6, true)]
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_recent() -> Result<()> {
         let conns = new_mem_connections();
@@ -0,0 +1,321 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Exports the bookmark tree in the Netscape bookmark file format, which is
+// what desktop writes to `bookmarks.html`, and which most browsers can import.
+//
+// The tree is streamed to the writer as we walk it, so we never hold more than
+// a single row in memory.
+
+use super::BookmarkRootGuid;
+use crate::db::PlacesDb;
+use crate::error::Result;
+use crate::types::BookmarkType;
+use rusqlite::Row;
+use std::io::Write;
+use types::Timestamp;
+
+const HEADER: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<meta http-equiv="Content-Security-Policy"
+      content="default-src 'self'; script-src 'none'; img-src data: *; object-src 'none'"></meta>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+
+<DL><p>
+"#;
+
+// Walks the tree depth-first. `path` is only used for ordering: it's built from
+// the zero-padded positions of each ancestor, so sorting by it puts every item
+// after its parent and before its next sibling.
+const EXPORT_SQL: &str = "
+    WITH RECURSIVE
+    descendants(id, guid, fk, type, level, path, title, dateAdded, lastModified) AS (
+        SELECT id, guid, fk, type, 0, '', title, dateAdded, lastModified
+        FROM moz_bookmarks WHERE guid = :root_guid
+        UNION ALL
+        SELECT b.id, b.guid, b.fk, b.type, d.level + 1, printf('%s%08d/', d.path, b.position),
+               b.title, b.dateAdded, b.lastModified
+        FROM moz_bookmarks b
+        JOIN descendants d ON b.parent = d.id
+    )
+    SELECT d.guid, d.type, d.level, NULLIF(d.title, '') AS title, d.dateAdded,
+           d.lastModified, h.url,
+           (SELECT GROUP_CONCAT(t.tag, ',')
+            FROM moz_tags t
+            JOIN moz_tags_relation r ON r.tag_id = t.id
+            WHERE r.place_id = h.id) AS tags
+    FROM descendants d
+    LEFT JOIN moz_places h ON h.id = d.fk
+    WHERE d.level > 0
+    ORDER BY d.path";
+
+/// Each of our roots is exported as a folder with one of these attributes, so
+/// the importer can map it back to the root. Other browsers recognise the
+/// toolbar and unfiled ones; the menu and mobile ones are our own.
+pub(crate) const ROOT_FOLDERS: [(BookmarkRootGuid, &str, &str); 4] = [
+    (
+        BookmarkRootGuid::Menu,
+        "BOOKMARKS_MENU_FOLDER",
+        "Bookmarks Menu",
+    ),
+    (
+        BookmarkRootGuid::Toolbar,
+        "PERSONAL_TOOLBAR_FOLDER",
+        "Bookmarks Toolbar",
+    ),
+    (
+        BookmarkRootGuid::Unfiled,
+        "UNFILED_BOOKMARKS_FOLDER",
+        "Other Bookmarks",
+    ),
+    (
+        BookmarkRootGuid::Mobile,
+        "MOBILE_BOOKMARKS_FOLDER",
+        "Mobile Bookmarks",
+    ),
+];
+
+struct ExportRow {
+    node_type: BookmarkType,
+    level: u32,
+    guid: String,
+    title: Option<String>,
+    date_added: Timestamp,
+    last_modified: Timestamp,
+    url: Option<String>,
+    tags: Option<String>,
+}
+
+impl ExportRow {
+    fn from_row(row: &Row<'_>) -> Result<Self> {
+        let url = row.get::<_, Option<String>>("url")?;
+        Ok(Self {
+            node_type: BookmarkType::from_u8_with_valid_url(row.get::<_, u8>("type")?, || {
+                url.is_some()
+            }),
+            level: row.get("level")?,
+            guid: row.get("guid")?,
+            title: row.get("title")?,
+            date_added: row.get("dateAdded")?,
+            last_modified: row.get("lastModified")?,
+            url,
+            tags: row.get("tags")?,
+        })
+    }
+
+    // The Netscape format uses seconds, not milliseconds.
+    fn date_attributes(&self) -> String {
+        format!(
+            r#"ADD_DATE="{}" LAST_MODIFIED="{}""#,
+            self.date_added.as_millis() / 1000,
+            self.last_modified.as_millis() / 1000
+        )
+    }
+}
+
+/// Write every bookmark in `db` to `writer` in the Netscape bookmark file
+/// format, preserving the folder structure, dates and tags.
+pub fn export_bookmarks_html<W: Write>(db: &PlacesDb, writer: &mut W) -> Result<()> {
+    let scope = db.begin_interrupt_scope()?;
+    writer.write_all(HEADER.as_bytes())?;
+
+    let mut stmt = db.conn().prepare(EXPORT_SQL)?;
+    let rows = stmt.query_and_then(
+        &[(":root_guid", BookmarkRootGuid::Root.as_str())],
+        ExportRow::from_row,
+    )?;
+
+    // The level of the innermost folder whose `<DL>` we've opened. The
+    // top-level `<DL>` from the header is level 0.
+    let mut open_level = 0;
+    for row in rows {
+        scope.err_if_interrupted()?;
+        let row = row?;
+        while open_level >= row.level {
+            write_indent(writer, open_level)?;
+            writer.write_all(b"</DL><p>\n")?;
+            open_level -= 1;
+        }
+        write_indent(writer, row.level)?;
+        match row.node_type {
+            BookmarkType::Folder => {
+                // Roots are stored with internal names as their titles, so
+                // they get the names users know them by.
+                let root = BookmarkRootGuid::well_known(&row.guid).and_then(|root| {
+                    ROOT_FOLDERS
+                        .iter()
+                        .find(|(root_guid, _, _)| *root_guid == root)
+                });
+                let (special, title) = match root {
+                    Some((_, attr, title)) => (format!(r#" {}="true""#, attr), *title),
+                    None => (String::new(), row.title.as_deref().unwrap_or_default()),
+                };
+                writeln!(
+                    writer,
+                    "<DT><H3 {}{}>{}</H3>",
+                    row.date_attributes(),
+                    special,
+                    escape_html(title)
+                )?;
+                write_indent(writer, row.level)?;
+                writer.write_all(b"<DL><p>\n")?;
+                open_level = row.level;
+            }
+            BookmarkType::Bookmark => {
+                // `from_u8_with_valid_url` guarantees we have a url here.
+                let url = row.url.as_deref().unwrap_or_default();
+                let tags = match &row.tags {
+                    Some(tags) => format!(r#" TAGS="{}""#, escape_html(tags)),
+                    None => String::new(),
+                };
+                writeln!(
+                    writer,
+                    r#"<DT><A HREF="{}" {}{}>{}</A>"#,
+                    escape_html(url),
+                    row.date_attributes(),
+                    tags,
+                    escape_html(row.title.as_deref().unwrap_or_default())
+                )?;
+            }
+            BookmarkType::Separator => {
+                writer.write_all(b"<HR>\n")?;
+            }
+        }
+    }
+    while open_level > 0 {
+        write_indent(writer, open_level)?;
+        writer.write_all(b"</DL><p>\n")?;
+        open_level -= 1;
+    }
+    writer.write_all(b"</DL>\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_indent<W: Write>(writer: &mut W, level: u32) -> std::io::Result<()> {
+    for _ in 0..level {
+        writer.write_all(b"    ")?;
+    }
+    Ok(())
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::places_api::test::new_mem_connection;
+    use crate::storage::tags::tag_url;
+    use crate::tests::insert_json_tree;
+    use serde_json::json;
+    use url::Url;
+
+    #[test]
+    fn test_export_html() -> Result<()> {
+        let conn = new_mem_connection();
+        insert_json_tree(
+            &conn,
+            json!({
+                "guid": String::from(BookmarkRootGuid::Unfiled.as_str()),
+                "children": [
+                    {
+                        "title": "A <folder>",
+                        "date_added": 1_000_000,
+                        "last_modified": 2_000_000,
+                        "children": [
+                            {
+                                "title": "Mozilla & friends",
+                                "url": "https://www.mozilla.org/",
+                                "date_added": 3_000_000,
+                                "last_modified": 4_000_000,
+                            },
+                            {"type": 3},
+                            {
+                                "title": "Empty",
+                                "date_added": 5_000_000,
+                                "last_modified": 6_000_000,
+                                "children": [],
+                            },
+                        ],
+                    },
+                    {
+                        "title": "Example",
+                        "url": "https://example.com/?a=1&b=\"2\"",
+                        "date_added": 7_000_000,
+                        "last_modified": 8_000_000,
+                    },
+                ]
+            }),
+        );
+        tag_url(&conn, &Url::parse("https://www.mozilla.org/")?, "foo")?;
+        tag_url(&conn, &Url::parse("https://www.mozilla.org/")?, "bar")?;
+
+        let mut out = Vec::new();
+        export_bookmarks_html(&conn, &mut out)?;
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n"));
+        assert!(html.ends_with("</DL><p>\n</DL>\n"));
+        // Every root is its own folder.
+        for (attr, title) in [
+            ("BOOKMARKS_MENU_FOLDER", "Bookmarks Menu"),
+            ("PERSONAL_TOOLBAR_FOLDER", "Bookmarks Toolbar"),
+            ("UNFILED_BOOKMARKS_FOLDER", "Other Bookmarks"),
+            ("MOBILE_BOOKMARKS_FOLDER", "Mobile Bookmarks"),
+        ] {
+            assert!(
+                html.contains(&format!(r#"{}="true">{}</H3>"#, attr, title)),
+                "missing the {} folder",
+                title
+            );
+        }
+
+        // The unfiled folder is a child of the root, and `A <folder>` a child of that.
+        let unfiled = html.find(r#"UNFILED_BOOKMARKS_FOLDER="true""#).unwrap();
+        let folder = html
+            .find(r#"        <DT><H3 ADD_DATE="1000" LAST_MODIFIED="2000">A &lt;folder&gt;</H3>"#)
+            .unwrap();
+        let mozilla = html.find(r#"            <DT><A HREF="https://www.mozilla.org/" ADD_DATE="3000" LAST_MODIFIED="4000" TAGS="#).unwrap();
+        let separator = html.find("            <HR>\n").unwrap();
+        let empty = html
+            .find(r#"            <DT><H3 ADD_DATE="5000" LAST_MODIFIED="6000">Empty</H3>"#)
+            .unwrap();
+        let example = html
+            .find(r#"        <DT><A HREF="https://example.com/?a=1&amp;b=%222%22" ADD_DATE="7000" LAST_MODIFIED="8000">Example</A>"#)
+            .unwrap();
+        assert!(unfiled < folder);
+        assert!(folder < mozilla);
+        assert!(mozilla < separator);
+        assert!(separator < empty);
+        assert!(empty < example);
+
+        // Tags are comma separated, in no particular order.
+        let line = html[mozilla..].lines().next().unwrap();
+        assert!(line.ends_with(">Mozilla &amp; friends</A>"));
+        assert!(line.contains(r#"TAGS="foo,bar""#) || line.contains(r#"TAGS="bar,foo""#));
+
+        // Every list we open is closed.
+        assert_eq!(
+            html.matches("<DL><p>").count(),
+            html.matches("</DL><p>").count() + 1
+        );
+        Ok(())
+    }
+}
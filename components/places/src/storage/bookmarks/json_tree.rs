@@ -491,6 +491,28 @@ pub fn fetch_tree(
     item_guid: &SyncGuid,
     target_depth: &FetchDepth,
 ) -> Result<Option<(BookmarkTreeNode, Option<SyncGuid>, u32)>> {
+    Ok(fetch_tree_with_positions(db, item_guid, target_depth)?
+        .map(|t| (t.root, t.parent_guid, t.position)))
+}
+
+/// A tree fetched by `fetch_tree_with_positions`.
+pub struct FetchedTree {
+    pub root: BookmarkTreeNode,
+    pub parent_guid: Option<SyncGuid>,
+    pub position: u32,
+    /// The stored position of each descendant in its parent. Malformed
+    /// bookmarks are left out of the tree, so this can differ from the index
+    /// of a node in its parent's `children`.
+    pub positions: HashMap<SyncGuid, u32>,
+}
+
+/// Like `fetch_tree`, but also returns the position of every descendant, so
+/// that they can be reported in the same way as when inserting or moving items.
+pub fn fetch_tree_with_positions(
+    db: &PlacesDb,
+    item_guid: &SyncGuid,
+    target_depth: &FetchDepth,
+) -> Result<Option<FetchedTree>> {
     // XXX - this needs additional work for tags - unlike desktop, there's no
     // "tags" folder, but instead a couple of tables to join on.
     let sql = r#"
@@ -600,7 +622,12 @@ pub fn fetch_tree(
 
     // Skip the rest and return if root is not a folder
     if let BookmarkTreeNode::Bookmark { .. } | BookmarkTreeNode::Separator { .. } = root {
-        return Ok(Some((root, parent_guid, position)));
+        return Ok(Some(FetchedTree {
+            root,
+            parent_guid,
+            position,
+            positions: HashMap::new(),
+        }));
     }
 
     scope.err_if_interrupted()?;
@@ -609,6 +636,7 @@ pub fn fetch_tree(
     // results in level order, so we'll see a node's siblings and cousins (same
     // level, but different parents) before any of their descendants.
     let mut pseudo_tree: HashMap<SyncGuid, Vec<BookmarkTreeNode>> = HashMap::new();
+    let mut positions = HashMap::new();
     for result in results {
         let row = result?;
         scope.err_if_interrupted()?;
@@ -661,12 +689,18 @@ pub fn fetch_tree(
         if let Some(parent_guid) = row.parent_guid.as_ref().cloned() {
             let children = pseudo_tree.entry(parent_guid).or_default();
             children.push(node);
+            positions.insert(row.guid, row.position);
         }
     }
 
     // Finally, inflate our tree.
     inflate(&mut root, &mut pseudo_tree);
-    Ok(Some((root, parent_guid, position)))
+    Ok(Some(FetchedTree {
+        root,
+        parent_guid,
+        position,
+        positions,
+    }))
 }
 
 #[cfg(test)]
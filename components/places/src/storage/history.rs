@@ -7,20 +7,26 @@ mod actions;
 use super::{fetch_page_info, new_page_info, PageInfo, RowId};
 use crate::db::PlacesDb;
 use crate::error::Result;
-use crate::ffi::{HistoryVisitInfo, HistoryVisitInfosWithBound, TopFrecentSiteInfo};
+use crate::ffi::{
+    HighlightRange, HistoryInfo, HistoryMatch, HistoryVisitInfo, HistoryVisitInfosWithBound,
+    TopFrecentSiteInfo,
+};
 use crate::frecency;
 use crate::hash;
 use crate::history_sync::engine::{
     COLLECTION_SYNCID_META_KEY, GLOBAL_SYNCID_META_KEY, LAST_SYNC_META_KEY,
 };
+use crate::match_impl::{MatchBehavior, SearchBehavior};
 use crate::observation::VisitObservation;
 use crate::storage::{
     delete_meta, delete_pending_temp_tables, get_meta, history_metadata, put_meta,
+    update_all_frecencies_at_once,
 };
 use crate::types::{
     serialize_unknown_fields, SyncStatus, UnknownFields, VisitTransition, VisitTransitionSet,
 };
 use actions::*;
+use interrupt_support::SqlInterruptScope;
 use rusqlite::types::ToSql;
 use rusqlite::Result as RusqliteResult;
 use rusqlite::Row;
@@ -54,6 +60,53 @@ pub fn apply_observation(db: &PlacesDb, visit_ob: VisitObservation) -> Result<Op
 pub fn apply_observation_direct(
     db: &PlacesDb,
     visit_ob: VisitObservation,
+) -> Result<Option<RowId>> {
+    apply_observation_impl(db, visit_ob, false)
+}
+
+/// The number of observations `import_visits` applies in each transaction.
+const IMPORT_VISITS_CHUNK_SIZE: usize = 1000;
+
+/// Applies `observations` in bulk, for example when migrating a large profile. This is much
+/// faster than calling `apply_observation` for each one, because they're applied in
+/// transactions of `IMPORT_VISITS_CHUNK_SIZE` observations, and frecencies are only
+/// recalculated once at the end. `on_progress` is called with the number of observations
+/// applied so far after each transaction.
+///
+/// Returns the number of visits added. If the import is interrupted, the observations applied
+/// so far are kept, and their frecencies are left marked as stale.
+pub fn import_visits(
+    db: &PlacesDb,
+    observations: impl IntoIterator<Item = VisitObservation>,
+    scope: &SqlInterruptScope,
+    mut on_progress: impl FnMut(u32),
+) -> Result<u32> {
+    let mut observations = observations.into_iter().peekable();
+    let mut num_applied = 0;
+    let mut num_visits = 0;
+    while observations.peek().is_some() {
+        let tx = db.begin_transaction()?;
+        for visit_ob in observations.by_ref().take(IMPORT_VISITS_CHUNK_SIZE) {
+            scope.err_if_interrupted()?;
+            if apply_observation_impl(db, visit_ob, true)?.is_some() {
+                num_visits += 1;
+            }
+            num_applied += 1;
+        }
+        delete_pending_temp_tables(db)?;
+        tx.commit()?;
+        on_progress(num_applied);
+    }
+    update_all_frecencies_at_once(db, scope)?;
+    Ok(num_visits)
+}
+
+// Applies an observation. If `defer_frecency` is true, the page's frecency is marked as stale
+// instead of being recalculated, and it's up to the caller to recalculate it later.
+fn apply_observation_impl(
+    db: &PlacesDb,
+    visit_ob: VisitObservation,
+    defer_frecency: bool,
 ) -> Result<Option<RowId>> {
     // Don't insert urls larger than our length max.
     if visit_ob.url.as_str().len() > super::URL_LENGTH_MAX {
@@ -145,7 +198,16 @@ pub fn apply_observation_direct(
         db.execute(&sql, &params[..])?;
     }
     // This needs to happen after the other updates.
-    if update_frec {
+    if update_frec && defer_frecency {
+        db.execute_cached(
+            "REPLACE INTO moz_places_stale_frecencies(place_id, stale_at)
+             VALUES(:page_id, :now)",
+            rusqlite::named_params! {
+                ":page_id": page_info.row_id,
+                ":now": Timestamp::now(),
+            },
+        )?;
+    } else if update_frec {
         update_frecency(
             db,
             page_info.row_id,
@@ -386,6 +448,20 @@ pub fn delete_visits_between(db: &PlacesDb, start: Timestamp, end: Timestamp) ->
     Ok(())
 }
 
+/// Delete all visits in the last `duration`, for a "forget the last hour" button. Visits
+/// dated in the future, eg because the clock was changed, are deleted too. Pages left without
+/// visits are cleaned up, and the deletions are synced, as for `delete_visits_between`.
+/// Returns how many visits were deleted.
+pub fn delete_recent_history(db: &PlacesDb, duration: Duration) -> Result<u32> {
+    let start = Timestamp::now()
+        .checked_sub(duration)
+        .unwrap_or(Timestamp::EARLIEST);
+    let tx = db.begin_transaction()?;
+    let deleted = delete_visits_between_in_tx(db, start, Timestamp(i64::MAX as u64))?;
+    tx.commit()?;
+    Ok(u32::try_from(deleted).unwrap_or(u32::MAX))
+}
+
 pub fn delete_place_visit_at_time(db: &PlacesDb, place: &Url, visit: Timestamp) -> Result<()> {
     delete_place_visit_at_time_by_href(db, place.as_str(), visit)
 }
@@ -494,6 +570,167 @@ fn find_exotic_visits_to_prune(
     )
 }
 
+#[derive(Debug)]
+struct PageToCoalesce {
+    id: RowId,
+    guid: SyncGuid,
+    url: String,
+    canonical_url: String,
+    sync_status: SyncStatus,
+}
+
+impl PageToCoalesce {
+    pub fn from_row(row: &Row<'_>) -> Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            guid: row.get("guid")?,
+            url: row.get("url")?,
+            canonical_url: row.get("canonical_url")?,
+            sync_status: row.get("sync_status")?,
+        })
+    }
+}
+
+/// Merges pages whose URLs are the same once they're canonicalized by the `url` crate,
+/// which can happen when its canonicalization changes. For each set of duplicates, the
+/// page whose URL is already canonical (or otherwise, the one with the highest frecency)
+/// is kept, and gets the visits, bookmarks, tags, keywords and metadata of the others.
+/// The others are removed, with Sync tombstones if they've been synced. Returns how many
+/// pages were removed.
+pub fn coalesce_duplicate_urls(db: &PlacesDb) -> Result<u32> {
+    let tx = db.begin_transaction()?;
+    // Only the pages which have duplicates are loaded, grouped by their canonical URL with the
+    // page to keep first. Pages we can't parse can't be compared, so they're left alone.
+    let pages = db.query_rows_and_then(
+        "WITH pages AS (
+             SELECT id, guid, url, frecency, sync_status, canonical_url(url) AS canonical_url
+             FROM moz_places
+         )
+         SELECT id, guid, url, canonical_url, sync_status FROM pages
+         WHERE canonical_url IN (SELECT canonical_url FROM pages
+                                 WHERE canonical_url NOT NULL
+                                 GROUP BY canonical_url
+                                 HAVING COUNT(*) > 1)
+         ORDER BY canonical_url, url <> canonical_url, frecency DESC, id",
+        [],
+        PageToCoalesce::from_row,
+    )?;
+    let mut merged = 0;
+    let mut pages = pages.into_iter().peekable();
+    while let Some(keep) = pages.next() {
+        while let Some(duplicate) = pages.next_if(|p| p.canonical_url == keep.canonical_url) {
+            merge_page_into(db, &duplicate, keep.id)?;
+            merged += 1;
+        }
+        if keep.url != keep.canonical_url {
+            // Rewriting the URL can move the page to a different origin. This does what the
+            // triggers do when a page is removed and added again, so that `origin_id` and the
+            // origin frecencies are updated when the temp tables are cleared below.
+            db.execute_cached(
+                "INSERT INTO moz_updateoriginsdelete_temp (prefix, host, frecency_delta)
+                 SELECT get_prefix(url), get_host_and_port(url), -MAX(frecency, 0)
+                 FROM moz_places WHERE id = :page_id
+                 ON CONFLICT(prefix, host) DO UPDATE
+                 SET frecency_delta = frecency_delta + EXCLUDED.frecency_delta",
+                rusqlite::named_params! { ":page_id": keep.id },
+            )?;
+            db.execute_cached(
+                "INSERT OR IGNORE INTO moz_updateoriginsinsert_temp
+                     (place_id, prefix, host, rev_host, frecency)
+                 VALUES (
+                     :page_id,
+                     get_prefix(:url),
+                     get_host_and_port(:url),
+                     reverse_host(get_host_and_port(:url)),
+                     (SELECT frecency FROM moz_places WHERE id = :page_id)
+                 )",
+                rusqlite::named_params! {
+                    ":page_id": keep.id,
+                    ":url": keep.canonical_url,
+                },
+            )?;
+        }
+        // The merged visits change the page's visit counts, frecency and Sync record.
+        db.execute_cached(
+            "UPDATE moz_places SET
+                url = :url,
+                url_hash = hash(:url),
+                visit_count_local = (SELECT COUNT(*) FROM moz_historyvisits
+                                     WHERE place_id = :page_id AND is_local
+                                     AND visit_type NOT IN (0, 4, 7, 8, 9)),
+                visit_count_remote = (SELECT COUNT(*) FROM moz_historyvisits
+                                      WHERE place_id = :page_id AND NOT is_local
+                                      AND visit_type NOT IN (0, 4, 7, 8, 9)),
+                last_visit_date_local = IFNULL((SELECT MAX(visit_date) FROM moz_historyvisits
+                                                WHERE place_id = :page_id AND is_local), 0),
+                last_visit_date_remote = IFNULL((SELECT MAX(visit_date) FROM moz_historyvisits
+                                                 WHERE place_id = :page_id AND NOT is_local), 0),
+                sync_change_counter = sync_change_counter + 1
+             WHERE id = :page_id",
+            rusqlite::named_params! {
+                ":url": keep.canonical_url,
+                ":page_id": keep.id,
+            },
+        )?;
+        update_frecency(db, keep.id, None)?;
+    }
+    delete_pending_temp_tables(db)?;
+    tx.commit()?;
+    Ok(merged)
+}
+
+/// Moves everything which refers to `duplicate` to the page `keep_id`, then removes
+/// `duplicate`. Visits at the same time as one of `keep_id`'s are dropped, and so are
+/// tags, keywords and annotations which `keep_id` already has.
+fn merge_page_into(db: &PlacesDb, duplicate: &PageToCoalesce, keep_id: RowId) -> Result<()> {
+    let params = rusqlite::named_params! {
+        ":duplicate_id": duplicate.id,
+        ":keep_id": keep_id,
+    };
+    for sql in [
+        // Moving visits doesn't fire the triggers which keep the visit counts up to
+        // date, so the caller updates them once every duplicate has been merged.
+        "UPDATE moz_historyvisits SET place_id = :keep_id
+         WHERE place_id = :duplicate_id
+           AND visit_date NOT IN (SELECT visit_date FROM moz_historyvisits
+                                  WHERE place_id = :keep_id)",
+        "UPDATE moz_places SET
+            title = IFNULL(title, (SELECT title FROM moz_places WHERE id = :duplicate_id)),
+            typed = typed + (SELECT typed FROM moz_places WHERE id = :duplicate_id)
+         WHERE id = :keep_id",
+        "UPDATE moz_bookmarks SET
+            fk = :keep_id,
+            syncChangeCounter = syncChangeCounter + 1
+         WHERE fk = :duplicate_id",
+        "UPDATE moz_bookmarks_synced SET placeId = :keep_id
+         WHERE placeId = :duplicate_id",
+        "UPDATE OR IGNORE moz_tags_relation SET place_id = :keep_id
+         WHERE place_id = :duplicate_id",
+        "DELETE FROM moz_tags_relation WHERE place_id = :duplicate_id",
+        "UPDATE OR IGNORE moz_keywords SET place_id = :keep_id
+         WHERE place_id = :duplicate_id",
+        "DELETE FROM moz_keywords WHERE place_id = :duplicate_id",
+        "UPDATE OR IGNORE moz_inputhistory SET place_id = :keep_id
+         WHERE place_id = :duplicate_id",
+        "UPDATE OR IGNORE moz_places_annotations SET place_id = :keep_id
+         WHERE place_id = :duplicate_id",
+        // Metadata for a visit from one duplicate to the other is dropped, because a page
+        // can't be its own referrer.
+        "UPDATE OR IGNORE moz_places_metadata SET place_id = :keep_id
+         WHERE place_id = :duplicate_id",
+        "UPDATE OR IGNORE moz_places_metadata SET referrer_place_id = :keep_id
+         WHERE referrer_place_id = :duplicate_id",
+    ] {
+        db.execute_cached(sql, params)?;
+    }
+    if duplicate.sync_status == SyncStatus::Normal {
+        insert_tombstone_for_page(db, &duplicate.guid)?;
+    }
+    // Anything which wasn't moved, like a visit to both pages at the same time, is
+    // removed along with the page.
+    delete_page(db, duplicate.id)
+}
+
 pub fn wipe_local(db: &PlacesDb) -> Result<()> {
     let tx = db.begin_transaction()?;
     wipe_local_in_tx(db)?;
@@ -571,6 +808,53 @@ pub fn delete_everything(db: &PlacesDb) -> Result<()> {
     Ok(())
 }
 
+/// Deletes all visits and every page which isn't bookmarked (or has a keyword or tags),
+/// in a single transaction. Unlike `delete_everything`, the deletions are synced: pages
+/// which have been synced get a tombstone, and so do the visits of the pages we keep.
+pub fn delete_all_history(db: &PlacesDb) -> Result<()> {
+    let tx = db.begin_transaction()?;
+    delete_all_history_in_tx(db)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn delete_all_history_in_tx(db: &PlacesDb) -> Result<()> {
+    db.execute_all(&[
+        &format!(
+            "INSERT OR IGNORE INTO moz_places_tombstones (guid)
+             SELECT guid FROM moz_places
+             WHERE foreign_count = 0 AND sync_status = {}",
+            SyncStatus::Normal as u8
+        ),
+        &format!(
+            "INSERT OR IGNORE INTO moz_historyvisit_tombstones (place_id, visit_date)
+             SELECT v.place_id, v.visit_date
+             FROM moz_historyvisits v
+             JOIN moz_places h ON h.id = v.place_id
+             WHERE h.foreign_count != 0 AND h.sync_status = {}",
+            SyncStatus::Normal as u8
+        ),
+        "DELETE FROM moz_historyvisits",
+        "DELETE FROM moz_places WHERE foreign_count = 0",
+        "DELETE FROM moz_places_metadata",
+        "DELETE FROM moz_places_metadata_search_queries",
+        "DELETE FROM moz_inputhistory AS i WHERE NOT EXISTS(
+             SELECT 1 FROM moz_places h
+             WHERE h.id = i.place_id)",
+        "DELETE FROM moz_origins
+         WHERE id NOT IN (SELECT origin_id FROM moz_places)",
+    ])?;
+
+    // The pages we kept have lost their visits, so their frecency needs updating.
+    let need_frecency_update =
+        db.query_rows_and_then("SELECT id FROM moz_places", [], |r| r.get::<_, RowId>(0))?;
+    for row_id in need_frecency_update {
+        update_frecency(db, row_id, None)?;
+    }
+    delete_pending_temp_tables(db)?;
+    Ok(())
+}
+
 fn delete_place_visit_at_time_in_tx(db: &PlacesDb, url: &str, visit_date: Timestamp) -> Result<()> {
     DbAction::apply_all(
         db,
@@ -591,7 +875,12 @@ fn delete_place_visit_at_time_in_tx(db: &PlacesDb, url: &str, visit_date: Timest
     )
 }
 
-pub fn delete_visits_between_in_tx(db: &PlacesDb, start: Timestamp, end: Timestamp) -> Result<()> {
+/// Returns how many visits were deleted.
+pub fn delete_visits_between_in_tx(
+    db: &PlacesDb,
+    start: Timestamp,
+    end: Timestamp,
+) -> Result<usize> {
     // Like desktop's removeVisitsByFilter, we query the visit and place ids
     // affected, then delete all visits, then delete all place ids in the set
     // which are orphans after the delete.
@@ -666,7 +955,7 @@ pub fn delete_visits_between_in_tx(db: &PlacesDb, start: Timestamp, end: Timesta
     // Clean up history metadata between start and end
     history_metadata::delete_between(db, start.as_millis_i64(), end.as_millis_i64())?;
     delete_pending_temp_tables(db)?;
-    Ok(())
+    Ok(visits.len())
 }
 
 #[derive(Debug)]
@@ -1202,7 +1491,26 @@ pub mod history_sync {
         Ok(result)
     }
 
-    pub fn finish_outgoing(db: &PlacesDb) -> Result<()> {
+    /// Whether `fetch_outgoing()` would find anything to upload, without
+    /// fetching it.
+    pub fn has_outgoing(db: &PlacesDb) -> Result<bool> {
+        Ok(db.exists(
+            &format!(
+                "SELECT 1 FROM moz_places
+                 WHERE (sync_change_counter > 0 OR sync_status != {}) AND
+                       NOT hidden
+                 UNION ALL
+                 SELECT 1 FROM moz_places_tombstones
+                 LIMIT 1",
+                (SyncStatus::Normal as u8)
+            ),
+            [],
+        )?)
+    }
+
+    /// Called after uploading the records from `fetch_outgoing()`, with the IDs of the ones
+    /// which were uploaded. The rest are left to upload next time.
+    pub fn finish_outgoing(db: &PlacesDb, uploaded: &[SyncGuid]) -> Result<()> {
         // So all items *other* than those above must be set to "not dirty"
         // (ie, status=SyncStatus::Normal, change_counter=0). Otherwise every
         // subsequent sync will continue to add more and more local pages
@@ -1213,6 +1521,21 @@ pub mod history_sync {
         // we can't do chunking and building a literal string with the ids seems
         // wrong and likely to hit max sql length limits.
         // So we use a temp table.
+        // Records which we fetched but which weren't uploaded, because the server rejected
+        // them or they were too large, must be uploaded next time, so we need to know which
+        // ones were. That list can be large too, so it goes in another temp table.
+        db.execute(
+            "CREATE TEMP TABLE IF NOT EXISTS temp_sync_uploaded
+                    (guid TEXT PRIMARY KEY)",
+            [],
+        )?;
+        for guid in uploaded {
+            db.execute_cached(
+                "INSERT OR IGNORE INTO temp_sync_uploaded VALUES (:guid)",
+                &[(":guid", guid)],
+            )?;
+        }
+
         log::debug!("Updating all synced rows");
         // XXX - is there a better way to express this SQL? Multi-selects
         // doesn't seem ideal...
@@ -1222,6 +1545,20 @@ pub mod history_sync {
                 SET sync_change_counter = sync_change_counter -
                 (SELECT change_delta FROM temp_sync_updated_meta m WHERE moz_places.id = m.id)
             WHERE id IN (SELECT id FROM temp_sync_updated_meta)
+              AND guid IN (SELECT guid FROM temp_sync_uploaded)
+            ",
+            [],
+        )?;
+
+        // `fetch_outgoing()` already set their status to Normal, so a change counter is
+        // what makes them outgoing again.
+        log::debug!("Keeping rows which weren't uploaded");
+        db.conn().execute_cached(
+            "
+            UPDATE moz_places
+                SET sync_change_counter = MAX(sync_change_counter, 1)
+            WHERE id IN (SELECT id FROM temp_sync_updated_meta)
+              AND guid NOT IN (SELECT guid FROM temp_sync_uploaded)
             ",
             [],
         )?;
@@ -1237,9 +1574,12 @@ pub mod history_sync {
             "DELETE FROM temp_sync_updated_meta",
         ])?;
 
-        log::debug!("Removing local tombstones");
-        db.conn()
-            .execute_cached("DELETE from moz_places_tombstones", [])?;
+        log::debug!("Removing uploaded local tombstones");
+        db.execute_all(&[
+            "DELETE FROM moz_places_tombstones
+             WHERE guid IN (SELECT guid FROM temp_sync_uploaded)",
+            "DELETE FROM temp_sync_uploaded",
+        ])?;
 
         Ok(())
     }
@@ -1308,6 +1648,62 @@ pub fn get_visited_into(
     Ok(())
 }
 
+/// Get the title, visit count and last visit date of each of `urls`, in the same order. The
+/// result is `None` for URLs which have never been visited.
+pub fn get_visit_infos_for_urls<I>(db: &PlacesDb, urls: I) -> Result<Vec<Option<HistoryInfo>>>
+where
+    I: IntoIterator<Item = Url>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let iter = urls.into_iter();
+    let mut result = vec![None; iter.len()];
+    let url_idxs = iter.enumerate().collect::<Vec<_>>();
+    get_visit_infos_for_urls_into(db, &url_idxs, &mut result)?;
+    Ok(result)
+}
+
+/// Low level api used to implement both get_visit_infos_for_urls and the FFI call, in the same
+/// way as `get_visited_into()`.
+pub fn get_visit_infos_for_urls_into(
+    db: &PlacesDb,
+    urls_idxs: &[(usize, Url)],
+    result: &mut [Option<HistoryInfo>],
+) -> Result<()> {
+    sql_support::each_chunk_mapped(
+        urls_idxs,
+        |(_, url)| url.as_str(),
+        |chunk, offset| -> Result<()> {
+            let values_with_idx = sql_support::repeat_display(chunk.len(), ",", |i, f| {
+                let (idx, url) = &urls_idxs[i + offset];
+                write!(f, "({},{},?)", *idx, hash::hash_url(url.as_str()))
+            });
+            let sql = format!(
+                "WITH to_fetch(fetch_url_index, url_hash, url) AS (VALUES {})
+                 SELECT f.fetch_url_index, h.url, h.title,
+                        h.visit_count_local + h.visit_count_remote AS visit_count,
+                        MAX(h.last_visit_date_local, h.last_visit_date_remote) AS last_visit_date
+                 FROM moz_places h
+                 JOIN to_fetch f ON h.url_hash = f.url_hash
+                   AND h.url = f.url
+                 WHERE h.visit_count_local + h.visit_count_remote > 0",
+                values_with_idx
+            );
+            let mut stmt = db.prepare(&sql)?;
+            for row in
+                stmt.query_and_then(rusqlite::params_from_iter(chunk), |row| -> Result<_> {
+                    let idx = row.get::<_, i64>("fetch_url_index")? as usize;
+                    Ok((idx, HistoryInfo::from_row(row)?))
+                })?
+            {
+                let (idx, info) = row?;
+                result[idx] = Some(info);
+            }
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
+
 /// Get the set of urls that were visited between `start` and `end`. Only considers local visits
 /// unless you pass in `include_remote`.
 pub fn get_visited_urls(
@@ -1379,6 +1775,129 @@ pub fn get_top_frecent_site_infos(
     Ok(infos)
 }
 
+/// Pages visited at least `min_visits` times which aren't bookmarked, most frecent first.
+/// Pages whose URLs are in `exclude_urls` (eg, because the user dismissed them as
+/// suggestions) are skipped.
+pub fn get_frequent_unbookmarked(
+    db: &PlacesDb,
+    limit: i32,
+    min_visits: i32,
+    exclude_urls: &[Url],
+) -> Result<Vec<HistoryInfo>> {
+    let excluded: HashSet<&str> = exclude_urls.iter().map(Url::as_str).collect();
+    let mut stmt = db.prepare_cached(
+        "SELECT h.url, h.title, h.visit_count_local + h.visit_count_remote AS visit_count,
+                MAX(h.last_visit_date_local, h.last_visit_date_remote) AS last_visit_date
+         FROM moz_places h
+         WHERE (SUBSTR(h.url, 1, 6) == 'https:' OR SUBSTR(h.url, 1, 5) == 'http:')
+           AND h.visit_count_local + h.visit_count_remote >= :min_visits
+           AND NOT h.hidden
+           AND NOT EXISTS (SELECT 1 FROM moz_bookmarks b WHERE b.fk = h.id)
+         ORDER BY h.frecency DESC, h.id",
+    )?;
+    let mut rows = stmt.query(rusqlite::named_params! { ":min_visits": min_visits })?;
+    // The exclusion list is filtered here, rather than in the query, so it can be any length.
+    let mut infos = Vec::new();
+    while infos.len() < limit.max(0) as usize {
+        let row = match rows.next()? {
+            Some(row) => row,
+            None => break,
+        };
+        let info = HistoryInfo::from_row(row)?;
+        if !excluded.contains(info.url.as_str()) {
+            infos.push(info);
+        }
+    }
+    Ok(infos)
+}
+
+/// Searches history for pages whose title or URL contains every word in `query`, ignoring case.
+/// Matches are sorted by frecency, then by most recent visit. Each match includes the parts of
+/// its title and URL which matched a word, so they can be highlighted.
+///
+/// Pages are matched with the same `AUTOCOMPLETE_MATCH` function as autocomplete; there's no
+/// full-text index for history.
+pub fn search_history(db: &PlacesDb, query: &str, limit: i32) -> Result<Vec<HistoryMatch>> {
+    let tokens: Vec<&str> = query.split_ascii_whitespace().collect();
+    if tokens.is_empty() || limit <= 0 {
+        return Ok(Vec::new());
+    }
+    db.query_rows_and_then_cached(
+        "SELECT h.url, h.title, h.frecency,
+                MAX(h.last_visit_date_local, h.last_visit_date_remote) AS last_visit_date
+         FROM moz_places h
+         WHERE (h.visit_count_local > 0 OR h.visit_count_remote > 0)
+           AND NOT h.hidden
+           AND AUTOCOMPLETE_MATCH(:query, h.url, h.title, NULL,
+                                  h.visit_count_local + h.visit_count_remote, h.typed,
+                                  0, NULL, :match_behavior, :search_behavior)
+         ORDER BY h.frecency DESC, last_visit_date DESC, h.id DESC
+         LIMIT :limit",
+        rusqlite::named_params! {
+            ":query": query,
+            ":match_behavior": MatchBehavior::Anywhere,
+            ":search_behavior": SearchBehavior::HISTORY,
+            ":limit": limit,
+        },
+        |row| -> Result<_> {
+            let url: String = row.get("url")?;
+            let title: Option<String> = row.get("title")?;
+            Ok(HistoryMatch {
+                title_highlights: title
+                    .as_deref()
+                    .map(|title| find_highlights(title, &tokens))
+                    .unwrap_or_default(),
+                url_highlights: find_highlights(&url, &tokens),
+                url: Url::parse(&url)?,
+                title,
+                frecency: row.get("frecency")?,
+                last_visit_date: row.get("last_visit_date")?,
+            })
+        },
+    )
+}
+
+/// Returns the ranges of `text` which match any of `tokens`, ignoring case, in order. Ranges
+/// which overlap or touch are merged. Offsets are in UTF-16 code units, like `HighlightRange`.
+fn find_highlights(text: &str, tokens: &[&str]) -> Vec<HighlightRange> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    // The lowercased characters of `text`, with the UTF-16 offset each one starts at.
+    let mut chars = Vec::new();
+    let mut offset = 0u32;
+    for c in text.chars() {
+        chars.push((offset, lower(c)));
+        offset += c.len_utf16() as u32;
+    }
+    let text_len = offset;
+
+    let mut ranges = Vec::new();
+    for token in tokens {
+        let token: Vec<char> = token.chars().map(lower).collect();
+        if token.is_empty() || token.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - token.len() {
+            let end = start + token.len();
+            if chars[start..end].iter().map(|(_, c)| c).eq(token.iter()) {
+                ranges.push(HighlightRange {
+                    start: chars[start].0,
+                    end: chars.get(end).map_or(text_len, |(offset, _)| *offset),
+                });
+            }
+        }
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<HighlightRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 pub fn get_visit_infos(
     db: &PlacesDb,
     start: Timestamp,
@@ -2040,7 +2559,7 @@ mod tests {
         assert!(outgoing[0].envelope.id != outgoing[1].envelope.id);
         assert!(outgoing[0].envelope.id == pi.guid || outgoing[0].envelope.id == pi2.guid);
         assert!(outgoing[1].envelope.id == pi.guid || outgoing[1].envelope.id == pi2.guid);
-        finish_outgoing(&conn)?;
+        finish_outgoing(&conn, &[pi.guid.clone(), pi2.guid.clone()])?;
 
         pi = fetch_page_info(&conn, &pi.url)?
             .expect("page should exist")
@@ -2293,6 +2812,122 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_coalesce_duplicate_urls() -> Result<()> {
+        let db = PlacesDb::open_in_memory(ConnectionType::ReadWrite)?;
+        let url = Url::parse("https://example.com/a")?;
+        let first_visit = Timestamp(1_000_000);
+        let second_visit = Timestamp(2_000_000);
+        apply_observation(
+            &db,
+            VisitObservation::new(url.clone())
+                .with_visit_type(VisitTransition::Link)
+                .with_at(first_visit),
+        )?;
+        apply_observation(
+            &db,
+            VisitObservation::new(Url::parse("https://example.com/b")?)
+                .with_visit_type(VisitTransition::Link)
+                .with_at(first_visit),
+        )?;
+        let guid = url_to_guid(&db, &url)?.expect("should exist");
+
+        // A synced page whose URL was stored before canonicalization changed, with a visit
+        // at the same time as the canonical page's, a later visit and a keyword.
+        let dupe_url = "HTTPS://EXAMPLE.COM/a";
+        db.execute(
+            "INSERT INTO moz_places(guid, url, url_hash, title, sync_status)
+             VALUES ('dupeguid0001', :url, hash(:url), 'Page A', :status)",
+            rusqlite::named_params! {
+                ":url": dupe_url,
+                ":status": SyncStatus::Normal,
+            },
+        )?;
+        let dupe_id: RowId = db.query_row(
+            "SELECT id FROM moz_places WHERE guid = 'dupeguid0001'",
+            [],
+            |row| row.get(0),
+        )?;
+        for date in [first_visit, second_visit] {
+            db.execute(
+                "INSERT INTO moz_historyvisits(place_id, visit_date, visit_type, is_local)
+                 VALUES (:page_id, :date, :visit_type, 1)",
+                rusqlite::named_params! {
+                    ":page_id": dupe_id,
+                    ":date": date,
+                    ":visit_type": VisitTransition::Link,
+                },
+            )?;
+        }
+        db.execute(
+            "INSERT INTO moz_keywords(place_id, keyword) VALUES (:page_id, 'a')",
+            rusqlite::named_params! { ":page_id": dupe_id },
+        )?;
+
+        assert_eq!(coalesce_duplicate_urls(&db)?, 1);
+
+        let infos = get_visit_infos_for_urls(&db, vec![url.clone()])?;
+        let info = infos[0].as_ref().expect("should still exist");
+        assert_eq!(info.title.as_deref(), Some("Page A"));
+        assert_eq!(info.visit_count, 2);
+        assert_eq!(info.last_visit_date, second_visit);
+        assert_eq!(url_to_guid(&db, &url)?, Some(guid));
+        assert_eq!(
+            db.query_one::<i64>("SELECT COUNT(*) FROM moz_places")?,
+            2,
+            "the duplicate should be removed"
+        );
+        assert_eq!(
+            db.query_one::<String>("SELECT guid FROM moz_places_tombstones")?,
+            "dupeguid0001"
+        );
+        assert_eq!(
+            db.query_one::<String>(
+                "SELECT p.url FROM moz_keywords k JOIN moz_places p ON p.id = k.place_id"
+            )?,
+            url.as_str()
+        );
+
+        // Nothing's left to merge.
+        assert_eq!(coalesce_duplicate_urls(&db)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_coalesce_duplicate_urls_moves_origin() -> Result<()> {
+        let db = PlacesDb::open_in_memory(ConnectionType::ReadWrite)?;
+        // Neither URL is canonical, so the kept page's URL is rewritten.
+        for (guid, url) in [
+            ("dupeguid0001", "HTTPS://EXAMPLE.COM/a"),
+            ("dupeguid0002", "https://EXAMPLE.com/a"),
+        ] {
+            db.execute(
+                "INSERT INTO moz_places(guid, url, url_hash, frecency)
+                 VALUES (:guid, :url, hash(:url), 100)",
+                rusqlite::named_params! { ":guid": guid, ":url": url },
+            )?;
+        }
+        delete_pending_temp_tables(&db)?;
+
+        assert_eq!(coalesce_duplicate_urls(&db)?, 1);
+
+        let (url, prefix, host): (String, String, String) = db.query_row(
+            "SELECT p.url, o.prefix, o.host
+             FROM moz_places p JOIN moz_origins o ON o.id = p.origin_id",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!(url, "https://example.com/a");
+        assert_eq!(prefix, "https://");
+        assert_eq!(host, "example.com");
+        assert_eq!(
+            db.query_one::<i64>("SELECT COUNT(*) FROM moz_origins")?,
+            1,
+            "the old origins should be removed"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_reset() -> Result<()> {
         fn mark_all_as_synced(db: &PlacesDb) -> Result<()> {
@@ -2397,6 +3032,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_has_outgoing() -> Result<()> {
+        let _ = env_logger::try_init();
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite)?;
+        assert!(!has_outgoing(&conn)?);
+        let pi = get_observed_page(&mut conn, "http://example.com/1")?;
+        assert!(has_outgoing(&conn)?);
+        apply_synced_reconciliation(&conn, &pi.guid)?;
+        assert!(!has_outgoing(&conn)?);
+        // Deleting a synced page leaves a tombstone to upload.
+        delete_visits_for(&conn, &pi.guid)?;
+        assert_eq!(get_tombstone_count(&conn), 1);
+        assert!(has_outgoing(&conn)?);
+        Ok(())
+    }
+
     #[test]
     fn test_apply_synced_deletion_new() -> Result<()> {
         let _ = env_logger::try_init();
@@ -2449,6 +3100,50 @@ mod tests {
         assert_eq!(expected, tombstones);
     }
 
+    #[test]
+    fn test_delete_recent_history() {
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).unwrap();
+        let now = Timestamp::now();
+        let old = Url::parse("http://example.com/old").unwrap();
+        let recent = Url::parse("http://example.com/recent").unwrap();
+        let ten_minutes_ago = Timestamp(now.0 - 10 * 60 * 1000);
+        let two_hours_ago = Timestamp(now.0 - 2 * 60 * 60 * 1000);
+        for (url, date) in [
+            (&old, two_hours_ago),
+            (&old, ten_minutes_ago),
+            (&recent, ten_minutes_ago),
+            (&recent, now),
+        ] {
+            get_custom_observed_page(&mut conn, url.as_str(), |o| o.with_at(date)).unwrap();
+        }
+        conn.execute_cached(
+            &format!(
+                "UPDATE moz_places SET sync_status = {}",
+                (SyncStatus::Normal as u8)
+            ),
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(
+            delete_recent_history(&conn, Duration::from_secs(15 * 60)).unwrap(),
+            3
+        );
+        let (_, visits) = fetch_visits(&conn, &old, 100).unwrap().unwrap();
+        assert_eq!(
+            visits.iter().map(|v| v.visit_date).collect::<Vec<_>>(),
+            vec![two_hours_ago]
+        );
+        // The page with only recent visits is gone, and its removal will be synced.
+        assert!(fetch_page_info(&conn, &recent).unwrap().is_none());
+        assert_eq!(get_tombstone_count(&conn), 1);
+
+        assert_eq!(
+            delete_recent_history(&conn, Duration::from_secs(15 * 60)).unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_visit_tombstones() {
         use url::Url;
@@ -2833,6 +3528,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_delete_all_history() {
+        use crate::storage::bookmarks::{
+            self, BookmarkPosition, BookmarkRootGuid, InsertableBookmark,
+        };
+        let _ = env_logger::try_init();
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).unwrap();
+        let now = Timestamp::now();
+
+        let mut guids = vec![];
+        for url in &[
+            "http://example.com/synced",
+            "http://example.com/new",
+            "http://example.com/bookmarked",
+        ] {
+            for date in &[Timestamp(now.0 - 10000), now] {
+                get_custom_observed_page(&mut conn, url, |o| o.with_at(*date)).unwrap();
+            }
+            guids.push(href_to_guid(&conn, url).unwrap().unwrap());
+        }
+        bookmarks::insert_bookmark(
+            &conn,
+            InsertableBookmark {
+                parent_guid: BookmarkRootGuid::Unfiled.into(),
+                position: BookmarkPosition::Append,
+                date_added: None,
+                last_modified: None,
+                guid: None,
+                url: Url::parse("http://example.com/bookmarked").unwrap(),
+                title: None,
+            }
+            .into(),
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE moz_places SET sync_status = :status WHERE guid IN (:synced, :bookmarked)",
+            rusqlite::named_params! {
+                ":status": SyncStatus::Normal as u8,
+                ":synced": &guids[0],
+                ":bookmarked": &guids[2],
+            },
+        )
+        .unwrap();
+
+        delete_all_history(&conn).expect("should delete history");
+
+        // The bookmarked page is kept, without any visits.
+        assert_eq!(
+            conn.query_rows_and_then(
+                "SELECT guid FROM moz_places",
+                [],
+                |row| -> rusqlite::Result<_> { row.get::<_, SyncGuid>(0) },
+            )
+            .unwrap(),
+            vec![guids[2].clone()]
+        );
+        assert_eq!(
+            0,
+            conn.query_one::<i64>("SELECT COUNT(*) FROM moz_historyvisits")
+                .unwrap(),
+        );
+        let page = fetch_page_info(&conn, &Url::parse("http://example.com/bookmarked").unwrap())
+            .unwrap()
+            .unwrap()
+            .page;
+        assert_eq!(page.visit_count_local, 0);
+        assert_eq!(page.last_visit_date_local, Timestamp(0));
+
+        // Only the synced page which was removed gets a page tombstone, and only the visits
+        // of the synced page which was kept get visit tombstones.
+        assert_eq!(
+            conn.query_rows_and_then(
+                "SELECT guid FROM moz_places_tombstones",
+                [],
+                |row| -> rusqlite::Result<_> { row.get::<_, SyncGuid>(0) },
+            )
+            .unwrap(),
+            vec![guids[0].clone()]
+        );
+        assert_eq!(
+            2,
+            conn.query_one::<i64>("SELECT COUNT(*) FROM moz_historyvisit_tombstones")
+                .unwrap(),
+        );
+    }
+
     // See https://github.com/mozilla-mobile/fenix/issues/8531#issuecomment-590498878.
     #[test]
     fn test_delete_everything_deletes_origins() {
@@ -2883,6 +3664,61 @@ mod tests {
         assert_eq!(origins, &["example1.com", "example2.com",]);
     }
 
+    #[test]
+    fn test_import_visits() -> Result<()> {
+        let conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite)?;
+        let observation = |i: usize| {
+            VisitObservation::new(Url::parse(&format!("https://example.com/{}", i % 1000)).unwrap())
+                .with_visit_type(VisitTransition::Link)
+                .with_at(Timestamp(Timestamp::now().0 - (i as u64) * 1000))
+        };
+        let count = |sql: &str| -> Result<i64> { Ok(conn.query_one(sql)?) };
+
+        let mut progress = vec![];
+        let scope = conn.begin_interrupt_scope()?;
+        let num_visits = import_visits(
+            &conn,
+            (0..2500)
+                .map(observation)
+                // Observations without a visit don't add one.
+                .chain(std::iter::once(
+                    VisitObservation::new(Url::parse("https://example.com/0").unwrap())
+                        .with_title("Title".to_string()),
+                )),
+            &scope,
+            |n| progress.push(n),
+        )?;
+        assert_eq!(num_visits, 2500);
+        assert_eq!(progress, vec![1000, 2000, 2501]);
+        assert_eq!(count("SELECT COUNT(*) FROM moz_historyvisits")?, 2500);
+        assert_eq!(count("SELECT COUNT(*) FROM moz_places")?, 1000);
+        // Frecencies were recalculated at the end.
+        assert_eq!(
+            count("SELECT COUNT(*) FROM moz_places WHERE frecency <= 0")?,
+            0
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM moz_places_stale_frecencies")?,
+            0
+        );
+
+        // An interrupted import keeps the chunks it applied, and leaves their frecencies stale.
+        let conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite)?;
+        let handle = conn.new_interrupt_handle();
+        let scope = conn.begin_interrupt_scope()?;
+        let result = import_visits(&conn, (0..1500).map(observation), &scope, |_| {
+            handle.interrupt()
+        });
+        assert!(matches!(result, Err(crate::Error::InterruptedError(_))));
+        let count = |sql: &str| -> Result<i64> { Ok(conn.query_one(sql)?) };
+        assert_eq!(count("SELECT COUNT(*) FROM moz_historyvisits")?, 1000);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM moz_places_stale_frecencies")?,
+            1000
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_preview_url() {
         let conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).unwrap();
@@ -3016,6 +3852,207 @@ mod tests {
         assert!(title.starts_with(&db_title));
     }
 
+    #[test]
+    fn test_get_frequent_unbookmarked() {
+        use crate::storage::bookmarks::{
+            self, BookmarkPosition, BookmarkRootGuid, InsertableBookmark,
+        };
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).expect("no memory db");
+        // (url, number of visits)
+        let to_add = [
+            ("https://www.example.com/often", 5),
+            ("https://www.example.com/bookmarked", 5),
+            ("https://www.example.com/dismissed", 4),
+            ("https://www.example.com/sometimes", 3),
+            ("https://www.example.com/once", 1),
+        ];
+        for (url, visits) in to_add {
+            for _ in 0..visits {
+                get_observed_page(&mut conn, url).unwrap();
+            }
+        }
+        bookmarks::insert_bookmark(
+            &conn,
+            InsertableBookmark {
+                parent_guid: BookmarkRootGuid::Unfiled.into(),
+                position: BookmarkPosition::Append,
+                date_added: None,
+                last_modified: None,
+                guid: None,
+                url: Url::parse("https://www.example.com/bookmarked").unwrap(),
+                title: None,
+            }
+            .into(),
+        )
+        .unwrap();
+
+        let urls = |infos: Vec<HistoryInfo>| {
+            infos
+                .into_iter()
+                .map(|info| info.url.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let infos = get_frequent_unbookmarked(&conn, 10, 3, &[]).unwrap();
+        assert_eq!(infos[0].visit_count, 5);
+        assert!(infos[0].last_visit_date.0 > 0);
+        assert_eq!(
+            urls(infos),
+            vec![
+                "https://www.example.com/often",
+                "https://www.example.com/dismissed",
+                "https://www.example.com/sometimes",
+            ]
+        );
+
+        let dismissed = [Url::parse("https://www.example.com/dismissed").unwrap()];
+        assert_eq!(
+            urls(get_frequent_unbookmarked(&conn, 10, 3, &dismissed).unwrap()),
+            vec![
+                "https://www.example.com/often",
+                "https://www.example.com/sometimes",
+            ]
+        );
+        assert_eq!(
+            urls(get_frequent_unbookmarked(&conn, 1, 1, &dismissed).unwrap()),
+            vec!["https://www.example.com/often"]
+        );
+    }
+
+    #[test]
+    fn test_search_history() {
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).expect("no memory db");
+        // (url, title, number of visits)
+        let to_add = [
+            ("https://www.example.com/rust", "Learning Rust", 3),
+            ("https://www.rust-lang.org/", "Rust Programming Language", 1),
+            ("https://www.example.com/other", "Something else", 5),
+        ];
+        for (url, title, visits) in to_add {
+            for _ in 0..visits {
+                get_custom_observed_page(&mut conn, url, |o| o.with_title(title.to_string()))
+                    .unwrap();
+            }
+        }
+        let range = |start, end| HighlightRange { start, end };
+
+        let matches = search_history(&conn, "RUST", 10).unwrap();
+        assert_eq!(
+            matches.iter().map(|m| m.url.as_str()).collect::<Vec<_>>(),
+            vec!["https://www.example.com/rust", "https://www.rust-lang.org/"]
+        );
+        assert_eq!(matches[0].title.as_deref(), Some("Learning Rust"));
+        assert!(matches[0].frecency > matches[1].frecency);
+        assert!(matches[0].last_visit_date.0 > 0);
+        assert_eq!(matches[0].title_highlights, vec![range(9, 13)]);
+        assert_eq!(matches[0].url_highlights, vec![range(24, 28)]);
+
+        // Every word has to match, in either the title or the URL.
+        let matches = search_history(&conn, "rust  lang", 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].url.as_str(), "https://www.rust-lang.org/");
+        assert_eq!(
+            matches[0].title_highlights,
+            vec![range(0, 4), range(17, 21)]
+        );
+        assert_eq!(
+            matches[0].url_highlights,
+            vec![range(12, 16), range(17, 21)]
+        );
+
+        assert_eq!(search_history(&conn, "rust", 1).unwrap().len(), 1);
+        assert!(search_history(&conn, "  ", 10).unwrap().is_empty());
+        assert!(search_history(&conn, "python", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_highlights() {
+        let range = |start, end| HighlightRange { start, end };
+        assert_eq!(
+            find_highlights("Hello World", &["world"]),
+            vec![range(6, 11)]
+        );
+        // Overlapping and touching matches are merged.
+        assert_eq!(find_highlights("aaa", &["aa"]), vec![range(0, 3)]);
+        assert_eq!(find_highlights("abcd", &["cd", "ab"]), vec![range(0, 4)]);
+        // Offsets are in UTF-16 code units.
+        assert_eq!(
+            find_highlights("\u{1F980} Rust", &["rust"]),
+            vec![range(3, 7)]
+        );
+        assert_eq!(find_highlights("Über", &["über"]), vec![range(0, 4)]);
+        assert!(find_highlights("short", &["longer"]).is_empty());
+    }
+
+    #[test]
+    fn test_get_visit_infos_for_urls() {
+        use crate::storage::bookmarks::{
+            self, BookmarkPosition, BookmarkRootGuid, InsertableBookmark,
+        };
+        let mut conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).expect("no memory db");
+        for _ in 0..2 {
+            apply_observation(
+                &conn,
+                VisitObservation::new(Url::parse("https://www.example.com/often").unwrap())
+                    .with_title(Some("Often".into()))
+                    .with_visit_type(VisitTransition::Link),
+            )
+            .unwrap();
+        }
+        get_observed_page(&mut conn, "https://www.example.com/once").unwrap();
+        // Bookmarking a page adds it to moz_places without visiting it.
+        bookmarks::insert_bookmark(
+            &conn,
+            InsertableBookmark {
+                parent_guid: BookmarkRootGuid::Unfiled.into(),
+                position: BookmarkPosition::Append,
+                date_added: None,
+                last_modified: None,
+                guid: None,
+                url: Url::parse("https://www.example.com/bookmarked").unwrap(),
+                title: None,
+            }
+            .into(),
+        )
+        .unwrap();
+
+        let urls = [
+            "https://www.example.com/once",
+            "https://www.example.com/never",
+            "https://www.example.com/often",
+            "https://www.example.com/bookmarked",
+            "https://www.example.com/once",
+        ]
+        .iter()
+        .map(|url| Url::parse(url).unwrap())
+        .collect::<Vec<_>>();
+        let infos = get_visit_infos_for_urls(&conn, urls).unwrap();
+        assert_eq!(infos.len(), 5);
+        let once = infos[0].as_ref().expect("should have visited once");
+        assert_eq!(once.url.as_str(), "https://www.example.com/once");
+        assert_eq!(once.visit_count, 1);
+        assert!(once.last_visit_date.0 > 0);
+        assert_eq!(infos[4].as_ref(), Some(once));
+        assert!(infos[1].is_none());
+        let often = infos[2].as_ref().expect("should have visited often");
+        assert_eq!(often.url.as_str(), "https://www.example.com/often");
+        assert_eq!(often.title.as_deref(), Some("Often"));
+        assert_eq!(often.visit_count, 2);
+        assert!(infos[3].is_none());
+
+        // More URLs than fit in one query.
+        let many = (0..2000)
+            .map(|i| Url::parse(&format!("https://www.example.com/{}", i)).unwrap())
+            .chain(std::iter::once(
+                Url::parse("https://www.example.com/often").unwrap(),
+            ))
+            .collect::<Vec<_>>();
+        let infos = get_visit_infos_for_urls(&conn, many).unwrap();
+        assert_eq!(infos.len(), 2001);
+        assert!(infos[..2000].iter().all(Option::is_none));
+        assert_eq!(infos[2000].as_ref().unwrap().visit_count, 2);
+    }
+
     #[test]
     fn test_get_visit_page_with_bound() {
         use std::time::SystemTime;
@@ -78,6 +78,8 @@ pub struct HistoryMetadataObservation {
     pub document_type: Option<DocumentType>,
     pub referrer_url: Option<String>,
     pub title: Option<String>,
+    // How far down the page the user scrolled, as a percentage.
+    pub scroll_depth: Option<i32>,
 }
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HistoryMetadata {
@@ -90,6 +92,7 @@ pub struct HistoryMetadata {
     pub search_term: Option<String>,
     pub document_type: DocumentType,
     pub referrer_url: Option<String>,
+    pub max_scroll_depth: i32,
 }
 
 impl HistoryMetadata {
@@ -119,6 +122,7 @@ impl HistoryMetadata {
             search_term: row.get("search_term")?,
             document_type: row.get("document_type")?,
             referrer_url: row.get("referrer_url")?,
+            max_scroll_depth: row.get("max_scroll_depth")?,
         })
     }
 }
@@ -263,6 +267,7 @@ struct HistoryMetadataCompoundKey {
 struct MetadataObservation {
     document_type: Option<DocumentType>,
     view_time: Option<i32>,
+    scroll_depth: Option<i32>,
 }
 
 impl HistoryMetadataCompoundKey {
@@ -328,7 +333,8 @@ const COMMON_METADATA_SELECT: &str = "
 SELECT
     m.id as metadata_id, p.url as url, p.title as title, p.preview_image_url as preview_image_url,
     m.created_at as created_at, m.updated_at as updated_at, m.total_view_time as total_view_time,
-    m.document_type as document_type, o.url as referrer_url, s.term as search_term
+    m.document_type as document_type, o.url as referrer_url, s.term as search_term,
+    m.max_scroll_depth as max_scroll_depth
 FROM moz_places_metadata m
 LEFT JOIN moz_places p ON m.place_id = p.id
 LEFT JOIN moz_places_metadata_search_queries s ON m.search_query_id = s.id
@@ -591,6 +597,11 @@ pub fn apply_metadata_observation(
             return Err(InvalidMetadataObservation::ViewTimeTooLong.into());
         }
     }
+    if let Some(scroll_depth) = observation.scroll_depth {
+        if !(0..=100).contains(&scroll_depth) {
+            return Err(InvalidMetadataObservation::ScrollDepthOutOfRange.into());
+        }
+    }
 
     // Begin a write transaction. We do this before any other work (e.g. SELECTs) to avoid racing against
     // other writers. Even though we expect to only have a single application writer, a sync writer
@@ -639,6 +650,7 @@ fn apply_metadata_observation_impl(
     let observation = MetadataObservation {
         document_type: observation.document_type,
         view_time: observation.view_time,
+        scroll_depth: observation.scroll_depth,
     };
 
     let now = Timestamp::now().as_millis() as i64;
@@ -649,10 +661,12 @@ fn apply_metadata_observation_impl(
     match matching_metadata {
         Some(metadata_id) => {
             // If document_type isn't part of the observation, make sure we don't accidentally erase what's currently set.
+            // Scroll depth observations are coalesced by keeping the furthest one.
             match observation {
                 MetadataObservation {
                     document_type: Some(dt),
                     view_time,
+                    scroll_depth,
                 } => {
                     tx.execute_cached(
                         "UPDATE
@@ -660,12 +674,14 @@ fn apply_metadata_observation_impl(
                         SET
                             document_type = :document_type,
                             total_view_time = total_view_time + :view_time_delta,
+                            max_scroll_depth = MAX(max_scroll_depth, :scroll_depth),
                             updated_at = :updated_at
                         WHERE id = :id",
                         rusqlite::named_params! {
                             ":id": metadata_id,
                             ":document_type": dt,
                             ":view_time_delta": view_time.unwrap_or(0),
+                            ":scroll_depth": scroll_depth.unwrap_or(0),
                             ":updated_at": now
                         },
                     )?;
@@ -673,17 +689,20 @@ fn apply_metadata_observation_impl(
                 MetadataObservation {
                     document_type: None,
                     view_time,
+                    scroll_depth,
                 } => {
                     tx.execute_cached(
                         "UPDATE
                             moz_places_metadata
                         SET
                             total_view_time = total_view_time + :view_time_delta,
+                            max_scroll_depth = MAX(max_scroll_depth, :scroll_depth),
                             updated_at = :updated_at
                         WHERE id = :id",
                         rusqlite::named_params! {
                             ":id": metadata_id,
                             ":view_time_delta": view_time.unwrap_or(0),
+                            ":scroll_depth": scroll_depth.unwrap_or(0),
                             ":updated_at": now
                         },
                     )?;
@@ -717,9 +736,9 @@ fn insert_metadata_in_tx(
     let place_id = key.place_entry.get_or_insert(tx)?;
 
     let sql = "INSERT INTO moz_places_metadata
-        (place_id, created_at, updated_at, total_view_time, search_query_id, document_type, referrer_place_id)
+        (place_id, created_at, updated_at, total_view_time, search_query_id, document_type, referrer_place_id, max_scroll_depth)
     VALUES
-        (:place_id, :created_at, :updated_at, :total_view_time, :search_query_id, :document_type, :referrer_place_id)";
+        (:place_id, :created_at, :updated_at, :total_view_time, :search_query_id, :document_type, :referrer_place_id, :max_scroll_depth)";
 
     tx.execute_cached(
         sql,
@@ -734,6 +753,7 @@ fn insert_metadata_in_tx(
                 &observation.document_type.unwrap_or(DocumentType::Regular),
             ),
             (":total_view_time", &observation.view_time.unwrap_or(0)),
+            (":max_scroll_depth", &observation.scroll_depth.unwrap_or(0)),
         ],
     )?;
 
@@ -859,6 +879,7 @@ mod tests {
                     document_type: $document_type,
                     referrer_url: $referrer_url.map(|s: &str| s.to_string()),
                     title: $title.map(|s: &str| s.to_string()),
+                    scroll_depth: None,
                 },
             )
             .unwrap();
@@ -1137,7 +1158,8 @@ mod tests {
                 search_term: None,
                 document_type: None,
                 referrer_url: None,
-                title: None
+                title: None,
+                scroll_depth: None,
             }
         )
         .is_err());
@@ -1151,12 +1173,49 @@ mod tests {
                 search_term: None,
                 document_type: None,
                 referrer_url: None,
-                title: None
+                title: None,
+                scroll_depth: None,
             }
         )
         .is_ok());
     }
 
+    #[test]
+    fn test_note_observation_scroll_depth() {
+        let conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).unwrap();
+        let observe = |view_time, scroll_depth| {
+            apply_metadata_observation(
+                &conn,
+                HistoryMetadataObservation {
+                    url: String::from("https://www.mozilla.org"),
+                    view_time,
+                    search_term: None,
+                    document_type: None,
+                    referrer_url: None,
+                    title: None,
+                    scroll_depth,
+                },
+            )
+        };
+
+        observe(Some(1000), Some(40)).unwrap();
+        // Observations for the same view are coalesced, keeping the furthest scroll depth.
+        observe(Some(500), Some(20)).unwrap();
+        observe(None, Some(75)).unwrap();
+        observe(Some(250), None).unwrap();
+
+        assert_table_size!(&conn, "moz_places_metadata", 1);
+        let m = get_latest_for_url(&conn, &Url::parse("https://www.mozilla.org").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(1750, m.total_view_time);
+        assert_eq!(75, m.max_scroll_depth);
+
+        // Scroll depths are percentages.
+        assert!(observe(None, Some(101)).is_err());
+        assert!(observe(None, Some(-1)).is_err());
+    }
+
     #[test]
     fn test_get_between() {
         let conn = PlacesDb::open_in_memory(ConnectionType::ReadWrite).expect("memory db");
@@ -8,12 +8,13 @@
 pub mod bookmarks;
 pub mod history;
 pub mod history_metadata;
+pub mod page_annotations;
 pub mod tags;
 
 use crate::db::PlacesDb;
 use crate::error::{Error, InvalidPlaceInfo, Result};
-use crate::ffi::HistoryVisitInfo;
 use crate::ffi::TopFrecentSiteInfo;
+use crate::ffi::{HistoryInfo, HistoryVisitInfo};
 use crate::frecency::{calculate_frecency, DEFAULT_FRECENCY_SETTINGS};
 use crate::types::{SyncStatus, UnknownFields, VisitTransition};
 use interrupt_support::SqlInterruptScope;
@@ -219,6 +220,18 @@ impl HistoryVisitInfo {
     }
 }
 
+impl HistoryInfo {
+    fn from_row(row: &rusqlite::Row<'_>) -> Result<Self> {
+        let url: String = row.get("url")?;
+        Ok(Self {
+            url: Url::parse(&url)?,
+            title: row.get("title")?,
+            visit_count: row.get("visit_count")?,
+            last_visit_date: row.get("last_visit_date")?,
+        })
+    }
+}
+
 impl TopFrecentSiteInfo {
     pub(crate) fn from_row(row: &rusqlite::Row<'_>) -> Result<Self> {
         let url: String = row.get("url")?;
@@ -303,6 +316,37 @@ pub fn run_maintenance_checkpoint(conn: &PlacesDb) -> Result<()> {
     Ok(())
 }
 
+/// How much frecencies are multiplied by each time they decay. Desktop decays
+/// them by this rate once a day.
+pub const FRECENCY_DECAY_RATE: f64 = 0.975;
+
+pub const LAST_FRECENCY_DECAY_META_KEY: &str = "last_frecency_decay";
+
+/// Decay the frecency of every page, and so every origin, so that pages which
+/// haven't been visited in a while drop in the rankings. Frecencies are
+/// recalculated as usual when a page is visited again.
+///
+/// This is meant to be called periodically by the embedding app, ideally once
+/// a day, during idle time. `now` is recorded as the last time we decayed.
+pub fn decay_frecency(db: &PlacesDb, now: Timestamp) -> Result<()> {
+    let tx = db.begin_transaction()?;
+    db.execute_cached(
+        "UPDATE moz_places SET frecency = ROUND(frecency * :decay_rate)
+         WHERE frecency > 0",
+        &[(":decay_rate", &FRECENCY_DECAY_RATE)],
+    )?;
+    // Updating the frecencies staged the changes to the origins.
+    delete_pending_temp_tables(db)?;
+    put_meta(db, LAST_FRECENCY_DECAY_META_KEY, &now)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// The last time `decay_frecency` was called, if it ever has been.
+pub fn get_last_frecency_decay(db: &PlacesDb) -> Result<Option<Timestamp>> {
+    get_meta(db, LAST_FRECENCY_DECAY_META_KEY)
+}
+
 pub fn update_all_frecencies_at_once(db: &PlacesDb, scope: &SqlInterruptScope) -> Result<()> {
     let tx = db.begin_transaction()?;
 
@@ -456,7 +500,7 @@ mod tests {
     fn test_removal_visit_at_time() {
         do_test_removal_places_and_origins(|conn: &PlacesDb, _guid: &SyncGuid| {
             let url = Url::parse("http://example.com/foo").unwrap();
-            let visit = Timestamp::from(727_747_200_001);
+            let visit = Timestamp::from(727_747_200_001u64);
             history::delete_place_visit_at_time(conn, &url, visit)
         })
     }
@@ -514,7 +558,7 @@ mod tests {
         assert!(apply_observation(
             &conn,
             VisitObservation::new(url)
-                .with_at(Timestamp::from(727_747_200_001))
+                .with_at(Timestamp::from(727_747_200_001u64))
                 .with_visit_type(VisitTransition::Link)
         )
         .unwrap()
@@ -547,6 +591,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decay_frecency() {
+        let conn = new_mem_connection();
+        for url in ["https://example.com/a", "https://example.com/b"] {
+            apply_observation(
+                &conn,
+                VisitObservation::new(Url::parse(url).unwrap())
+                    .with_visit_type(VisitTransition::Typed),
+            )
+            .expect("should apply");
+        }
+        let frecencies = || -> Vec<i64> {
+            conn.query_rows_and_then("SELECT frecency FROM moz_places ORDER BY url", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        let origin_frecency = || -> i64 {
+            conn.query_one("SELECT frecency FROM moz_origins WHERE host = 'example.com'")
+                .unwrap()
+        };
+        let before = frecencies();
+        assert!(before.iter().all(|&f| f > 0));
+        assert_eq!(origin_frecency(), before.iter().sum::<i64>());
+        assert_eq!(get_last_frecency_decay(&conn).unwrap(), None);
+
+        let now = Timestamp::now();
+        decay_frecency(&conn, now).expect("should decay");
+        let after = frecencies();
+        for (before, after) in before.iter().zip(&after) {
+            assert_eq!(
+                *after,
+                (*before as f64 * FRECENCY_DECAY_RATE).round() as i64
+            );
+            assert!(after < before);
+        }
+        // The origin is decayed too.
+        assert_eq!(origin_frecency(), after.iter().sum::<i64>());
+        assert_eq!(get_last_frecency_decay(&conn).unwrap(), Some(now));
+    }
+
     // Similar to the above, but if the bookmark has no visits the place/origin should die
     // without requiring history removal
     #[test]
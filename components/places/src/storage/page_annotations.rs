@@ -0,0 +1,145 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Arbitrary key/value annotations on pages, for features like a reading list
+// which need to remember things about a page ("read later", "archived"...).
+// Annotations are local-only: they aren't synced, and are removed along with
+// the page they annotate.
+
+use super::fetch_page_info;
+use crate::db::PlacesDb;
+use crate::error::{InvalidPlaceInfo, Result};
+use sql_support::ConnExt;
+use types::Timestamp;
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageAnnotation {
+    pub key: String,
+    pub value: String,
+    pub updated_at: Timestamp,
+}
+
+impl PageAnnotation {
+    pub(crate) fn from_row(row: &rusqlite::Row<'_>) -> Result<Self> {
+        Ok(Self {
+            key: row.get("key")?,
+            value: row.get("value")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// Sets the annotation `key` on the specified URL to `value`, replacing any
+/// existing value for that key.
+///
+/// Like tagging, this will not create a new place, and fails with `NoSuchUrl`
+/// if the URL isn't in the database.
+pub fn set_page_annotation(db: &PlacesDb, url: &Url, key: &str, value: &str) -> Result<()> {
+    let place_id = match fetch_page_info(db, url)? {
+        Some(info) => info.page.row_id,
+        None => return Err(InvalidPlaceInfo::NoSuchUrl.into()),
+    };
+    db.execute_cached(
+        "INSERT OR REPLACE INTO moz_places_annotations(place_id, key, value, updated_at)
+         VALUES(:place_id, :key, :value, now())",
+        rusqlite::named_params! {
+            ":place_id": place_id,
+            ":key": key,
+            ":value": value,
+        },
+    )?;
+    Ok(())
+}
+
+/// Retrieves every annotation on the specified URL, ordered by key. Returns an
+/// empty list if the URL has no annotations, or isn't in the database.
+pub fn get_page_annotations(db: &PlacesDb, url: &Url) -> Result<Vec<PageAnnotation>> {
+    db.query_rows_and_then_cached(
+        "SELECT a.key, a.value, a.updated_at
+         FROM moz_places_annotations a
+         JOIN moz_places h ON h.id = a.place_id
+         WHERE h.url_hash = hash(:url) AND h.url = :url
+         ORDER BY a.key",
+        &[(":url", &url.as_str())],
+        PageAnnotation::from_row,
+    )
+}
+
+/// Removes the annotation `key` from the specified URL. This is ignored if the
+/// URL doesn't have the annotation.
+pub fn delete_page_annotation(db: &PlacesDb, url: &Url, key: &str) -> Result<()> {
+    db.execute_cached(
+        "DELETE FROM moz_places_annotations
+         WHERE key = :key
+         AND place_id = (SELECT id FROM moz_places
+                         WHERE url_hash = hash(:url)
+                         AND url = :url)",
+        &[(":key", &key), (":url", &url.as_str())],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::places_api::test::new_mem_connection;
+    use crate::storage::history::delete_visits_for;
+    use crate::storage::new_page_info;
+
+    fn keys_and_values(db: &PlacesDb, url: &Url) -> Vec<(String, String)> {
+        get_page_annotations(db, url)
+            .expect("should work")
+            .into_iter()
+            .map(|a| (a.key, a.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_page_annotations() {
+        let conn = new_mem_connection();
+        let url = Url::parse("https://example.com/article").expect("valid url");
+        let other = Url::parse("https://example.com/other").expect("valid url");
+
+        // Annotating an unknown page fails, rather than creating it.
+        assert!(set_page_annotation(&conn, &url, "read-later", "true").is_err());
+        assert!(keys_and_values(&conn, &url).is_empty());
+
+        let guid = new_page_info(&conn, &url, None).expect("should work").guid;
+        new_page_info(&conn, &other, None).expect("should work");
+
+        set_page_annotation(&conn, &url, "read-later", "true").expect("should work");
+        set_page_annotation(&conn, &url, "archived", "false").expect("should work");
+        set_page_annotation(&conn, &other, "archived", "true").expect("should work");
+        assert_eq!(
+            keys_and_values(&conn, &url),
+            [
+                ("archived".to_string(), "false".to_string()),
+                ("read-later".to_string(), "true".to_string()),
+            ]
+        );
+
+        // Setting an existing key replaces its value.
+        set_page_annotation(&conn, &url, "archived", "true").expect("should work");
+        delete_page_annotation(&conn, &url, "read-later").expect("should work");
+        // Deleting a missing annotation is fine.
+        delete_page_annotation(&conn, &url, "read-later").expect("should work");
+        assert_eq!(
+            keys_and_values(&conn, &url),
+            [("archived".to_string(), "true".to_string())]
+        );
+        assert_eq!(
+            keys_and_values(&conn, &other),
+            [("archived".to_string(), "true".to_string())]
+        );
+
+        // Annotations are removed along with their page.
+        delete_visits_for(&conn, &guid).expect("should work");
+        assert!(keys_and_values(&conn, &url).is_empty());
+        let count: i64 = conn
+            .query_one("SELECT COUNT(*) FROM moz_places_annotations")
+            .expect("should work");
+        assert_eq!(count, 1);
+    }
+}
@@ -27,13 +27,26 @@ pub enum VisitTransition {
     // URL bar or selected it from UI (URL bar autocomplete results, etc)
     Typed = 2,
 
-    // XXX - moar comments.
+    // This transition type means the user opened the page from a bookmark.
     Bookmark = 3,
+
+    // This transition type means the page was loaded as part of another page,
+    // like an image or an iframe which the user didn't navigate to.
     Embed = 4,
+
+    // These transition types mean the page was the target of a permanent
+    // (301) or temporary (302, 303 or 307) redirect.
     RedirectPermanent = 5,
     RedirectTemporary = 6,
+
+    // This transition type means the user downloaded the page's URL.
     Download = 7,
+
+    // This transition type means the user followed a link in a frame, rather
+    // than in the top-level page.
     FramedLink = 8,
+
+    // This transition type means the user reloaded the page.
     Reload = 9,
 }
 
@@ -18,6 +18,10 @@ pub enum PushApiError {
     #[error("No record for chid {0}")]
     RecordNotFoundError(String),
 
+    /// The push message used a content encoding we can't decrypt
+    #[error("Unsupported content encoding: {0}")]
+    UnsupportedContentEncoding(String),
+
     /// Internal Error
     #[error("Internal Error: {0}")]
     InternalError(String),
@@ -32,6 +36,10 @@ pub enum PushError {
     #[error("Crypto error: {0}")]
     CryptoError(String),
 
+    /// The push message used a content encoding other than "aes128gcm" or "aesgcm"
+    #[error("Unsupported content encoding: {0}")]
+    UnsupportedContentEncoding(String),
+
     /// A Client communication error
     #[error("Communication Error: {0:?}")]
     CommunicationError(String),
@@ -109,6 +117,10 @@ impl GetErrorHandling for PushError {
             Self::RecordNotFoundError(s) => {
                 ErrorHandling::convert(PushApiError::RecordNotFoundError(s.clone()))
             }
+            Self::UnsupportedContentEncoding(s) => {
+                ErrorHandling::convert(PushApiError::UnsupportedContentEncoding(s.clone()))
+                    .log_warning()
+            }
 
             _ => ErrorHandling::convert(PushApiError::InternalError(self.to_string())),
         }
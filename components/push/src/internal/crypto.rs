@@ -44,15 +44,10 @@ impl FromStr for CryptoEncoding {
     type Err = PushError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_lowercase().as_str() {
+        Ok(match s.trim().to_lowercase().as_str() {
             "aesgcm" => Self::Aesgcm,
             "aes128gcm" => Self::Aes128gcm,
-            _ => {
-                return Err(PushError::CryptoError(format!(
-                    "Invalid crypto encoding {}",
-                    s
-                )))
-            }
+            _ => return Err(PushError::UnsupportedContentEncoding(s.to_string())),
         })
     }
 }
@@ -150,7 +145,7 @@ pub fn get_random_bytes(size: usize) -> error::Result<Vec<u8>> {
 
 /// Extract the sub-value from the header.
 /// Sub values have the form of `label=value`. Due to a bug in some push providers, treat ',' and ';' as
-/// equivalent.
+/// equivalent. Some providers also put whitespace after the separators, or quote the value.
 fn extract_value(val: &str, target: &str) -> Option<Vec<u8>> {
     if !val.contains(&format!("{}=", target)) {
         log::debug!("No sub-value found for {}", target);
@@ -159,8 +154,8 @@ fn extract_value(val: &str, target: &str) -> Option<Vec<u8>> {
     let items = val.split(|c| c == ',' || c == ';');
     for item in items {
         let mut kv = item.split('=');
-        if kv.next() == Some(target) {
-            if let Some(val) = kv.next() {
+        if kv.next().map(str::trim) == Some(target) {
+            if let Some(val) = kv.next().map(|v| v.trim().trim_matches('"')) {
                 return match base64::decode_config(val, base64::URL_SAFE_NO_PAD) {
                     Ok(v) => Some(v),
                     Err(e) => {
@@ -246,9 +241,19 @@ impl<'a> TryFrom<&'a HashMap<String, String>> for PushPayload<'a> {
         let body = value
             .get("body")
             .ok_or_else(|| PushError::CryptoError("Invalid Push payload".to_string()))?;
-        let encoding = value.get("con").map(|s| s.as_str()).unwrap_or("aes128gcm");
         let salt = value.get("enc").map(|s| s.as_str()).unwrap_or("");
         let dh = value.get("cryptokey").map(|s| s.as_str()).unwrap_or("");
+        // Not every provider tells us the encoding. Only "aesgcm" sends the salt
+        // and the sender's public key in separate headers, so if we have both,
+        // that's what it is.
+        let encoding = match value.get("con").map(|s| s.trim()) {
+            Some(encoding) if !encoding.is_empty() => encoding,
+            _ if !salt.is_empty() && !dh.is_empty() => "aesgcm",
+            _ => "aes128gcm",
+        };
+        // Fail early for encodings we can't decrypt, rather than after looking
+        // up the key.
+        CryptoEncoding::from_str(encoding)?;
         Ok(Self {
             channel_id,
             body,
@@ -334,4 +339,61 @@ mod crypto_tests {
         let decrypted = decrypter(ciphertext, "aes128gcm", "", "").unwrap();
         assert_eq!(String::from_utf8(decrypted).unwrap(), PLAINTEXT.to_string());
     }
+
+    #[test]
+    fn test_decrypt_aesgcm_loose_headers() {
+        // The same message as `test_decrypt_aesgcm`, with whitespace after the
+        // separators, a quoted salt and an upper case encoding.
+        let ciphertext = "BNKu5uTFhjyS-06eECU9-6O61int3Rr7ARbm-xPhFuyDO5sfxVs-HywGaVonvzkarvfvXE9IRT_YNA81Og2uSqDasdMuw\
+                          qm1zd0O3f7049IkQep3RJ2pEZTy5DqvI7kwMLDLzea9nroq3EMH5hYhvQtQgtKXeWieEL_3yVDQVg";
+        let dh = "keyid=foo; dh=BMOebOMWSRisAhWpRK9ZPszJC8BL9MiWvLZBoBU6pG6Kh6vUFSW4BHFMh0b83xCg3_7IgfQZXwmVuyu27vwiv5c, otherval=abcde";
+        let salt = "keyid=foo; salt=\"tSf2qu43C9BD0zkvRW5eUg\"";
+
+        let decrypted = decrypter(ciphertext, " AESGCM", salt, dh).unwrap();
+        assert_eq!(String::from_utf8(decrypted).unwrap(), PLAINTEXT.to_string());
+    }
+
+    #[test]
+    fn test_decrypt_unsupported_encoding() {
+        let err = decrypter("AAAA", "aes256gcm", "", "").unwrap_err();
+        assert!(matches!(err, PushError::UnsupportedContentEncoding(e) if e == "aes256gcm"));
+    }
+
+    fn payload(headers: &[(&str, &str)]) -> HashMap<String, String> {
+        let mut payload: HashMap<String, String> = headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        payload.insert("chid".to_string(), "channel_id".to_string());
+        payload.insert("body".to_string(), "body".to_string());
+        payload
+    }
+
+    #[test]
+    fn test_payload_encoding() {
+        let salt = "salt=tSf2qu43C9BD0zkvRW5eUg";
+        let dh = "dh=BMOebOMWSRisAhWpRK9ZPszJC8BL9MiWvLZBoBU6pG6Kh6vUFSW4BHFMh0b83xCg3_7IgfQZXwmVuyu27vwiv5c";
+
+        // An explicit encoding always wins.
+        let p = payload(&[("con", "aes128gcm"), ("enc", salt), ("cryptokey", dh)]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aes128gcm");
+        let p = payload(&[("con", "aesgcm")]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aesgcm");
+
+        // Otherwise, it's "aesgcm" only if we have both of its headers.
+        let p = payload(&[("enc", salt), ("cryptokey", dh)]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aesgcm");
+        let p = payload(&[("con", ""), ("enc", salt), ("cryptokey", dh)]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aesgcm");
+        let p = payload(&[("enc", salt)]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aes128gcm");
+        let p = payload(&[("enc", ""), ("cryptokey", "")]);
+        assert_eq!(PushPayload::try_from(&p).unwrap().encoding, "aes128gcm");
+
+        let p = payload(&[("con", "gzip")]);
+        assert!(matches!(
+            PushPayload::try_from(&p),
+            Err(PushError::UnsupportedContentEncoding(e)) if e == "gzip"
+        ));
+    }
 }
@@ -119,6 +119,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
         &mut self,
         scope: &str,
         server_key: Option<&str>,
+        group: Option<&str>,
     ) -> Result<SubscriptionResponse> {
         // While potentially an error, a misconfigured system may use "" as
         // an application key. In that case, we drop the application key.
@@ -128,7 +129,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
             server_key
         };
         // Don't fetch the subscription from the server if we've already got one.
-        if let Some(record) = self.store.get_record_by_scope(scope)? {
+        if let Some(mut record) = self.store.get_record_by_scope(scope)? {
             if self.uaid.is_none() {
                 // should be impossible - we should delete all records when we lose our uiad.
                 return Err(PushError::StorageError(
@@ -136,6 +137,11 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
                 ));
             }
             log::debug!("returning existing subscription for '{}'", scope);
+            // Allow existing subscriptions to be added to a group after the fact.
+            if group.is_some() && record.group.as_deref() != group {
+                record.group = group.map(ToOwned::to_owned);
+                self.store.put_record(&record)?;
+            }
             return record.try_into();
         }
 
@@ -145,7 +151,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
             .ok_or_else(|| PushError::CommunicationError("No native id".to_string()))?
             .clone();
 
-        self.impl_subscribe(scope, &registration_id, server_key)
+        self.impl_subscribe(scope, &registration_id, server_key, group)
     }
 
     pub fn get_subscription(&self, scope: &str) -> Result<Option<SubscriptionResponse>> {
@@ -168,6 +174,20 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
         }
     }
 
+    pub fn unsubscribe_all_for_group(&mut self, group: &str) -> Result<u32> {
+        let records = self.store.get_records_by_group(group)?;
+        if records.is_empty() {
+            return Ok(0);
+        }
+        let (uaid, auth) = self.ensure_auth_pair()?;
+        for record in &records {
+            self.connection
+                .unsubscribe(&record.channel_id, uaid, auth)?;
+            self.store.delete_record(&record.channel_id)?;
+        }
+        Ok(records.len() as u32)
+    }
+
     pub fn unsubscribe_all(&mut self) -> Result<()> {
         let (uaid, auth) = self.ensure_auth_pair()?;
 
@@ -297,11 +317,12 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
         scope: &str,
         registration_id: &str,
         server_key: Option<&str>,
+        group: Option<&str>,
     ) -> error::Result<SubscriptionResponse> {
         if let (Some(uaid), Some(auth)) = (&self.uaid, &self.auth) {
-            self.subscribe_with_uaid(scope, uaid, auth, registration_id, server_key)
+            self.subscribe_with_uaid(scope, uaid, auth, registration_id, server_key, group)
         } else {
-            self.register(scope, registration_id, server_key)
+            self.register(scope, registration_id, server_key, group)
         }
     }
 
@@ -312,6 +333,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
         auth: &str,
         registration_id: &str,
         app_server_key: Option<&str>,
+        group: Option<&str>,
     ) -> error::Result<SubscriptionResponse> {
         let app_server_key = app_server_key.map(|v| v.to_owned());
 
@@ -326,6 +348,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
             subscription_key.clone(),
         )?;
         record.app_server_key = app_server_key;
+        record.group = group.map(|v| v.to_owned());
         self.store.put_record(&record)?;
         log::debug!("subscribed OK");
         Ok(SubscriptionResponse {
@@ -342,6 +365,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
         scope: &str,
         registration_id: &str,
         app_server_key: Option<&str>,
+        group: Option<&str>,
     ) -> error::Result<SubscriptionResponse> {
         let app_server_key = app_server_key.map(|v| v.to_owned());
         let register_response = self.connection.register(registration_id, &app_server_key)?;
@@ -359,6 +383,7 @@ impl<Co: Connection, Cr: Cryptography, S: Storage> PushManager<Co, Cr, S> {
             subscription_key.clone(),
         )?;
         record.app_server_key = app_server_key;
+        record.group = group.map(|v| v.to_owned());
         self.store.put_record(&record)?;
         log::debug!("subscribed OK");
         Ok(SubscriptionResponse {
@@ -459,9 +484,9 @@ mod test {
                 auth,
             })
         });
-        let resp = pm.subscribe("test-scope", None)?;
+        let resp = pm.subscribe("test-scope", None, None)?;
         // verify that a subsequent request for the same channel ID returns the same subscription
-        let resp2 = pm.subscribe("test-scope", None)?;
+        let resp2 = pm.subscribe("test-scope", None, None)?;
         assert_eq!(Some(TEST_AUTH.to_owned()), pm.store.get_auth()?);
         assert_eq!(
             resp.subscription_info.endpoint,
@@ -521,7 +546,7 @@ mod test {
             })
         });
 
-        let resp = pm.subscribe("test-scope", None)?;
+        let resp = pm.subscribe("test-scope", None, None)?;
         let key_info = resp.subscription_info.keys;
         let remote_pub = base64::decode_config(&key_info.p256dh, base64::URL_SAFE_NO_PAD).unwrap();
         let auth = base64::decode_config(&key_info.auth, base64::URL_SAFE_NO_PAD).unwrap();
@@ -596,7 +621,7 @@ mod test {
                 auth,
             })
         });
-        let resp = pm.subscribe("test-scope", None)?;
+        let resp = pm.subscribe("test-scope", None, None)?;
         let key_info = resp.subscription_info.keys;
         let remote_pub = base64::decode_config(&key_info.p256dh, base64::URL_SAFE_NO_PAD).unwrap();
         let auth = base64::decode_config(&key_info.auth, base64::URL_SAFE_NO_PAD).unwrap();
@@ -671,8 +696,8 @@ mod test {
                 auth,
             })
         });
-        let sub_1 = pm.subscribe("test-scope", None)?;
-        let sub_2 = pm.subscribe("test-scope", None)?;
+        let sub_1 = pm.subscribe("test-scope", None, None)?;
+        let sub_2 = pm.subscribe("test-scope", None, None)?;
         assert_eq!(sub_1, sub_2);
         Ok(())
     }
@@ -720,7 +745,7 @@ mod test {
             .with(eq(TEST_UAID), eq(TEST_AUTH))
             .times(1)
             .returning(|_, _| Ok(()));
-        let _ = pm.subscribe("test-scope", None)?;
+        let _ = pm.subscribe("test-scope", None, None)?;
         // verify that a uaid got added to our store and
         // that there is a record associated with the channel ID provided
         assert_eq!(pm.store.get_uaid()?.unwrap(), TEST_UAID);
@@ -738,7 +763,7 @@ mod test {
 
         // we now check that a new subscription will cause us to
         // re-generate a uaid and store it in our store
-        let _ = pm.subscribe("test-scope", None)?;
+        let _ = pm.subscribe("test-scope", None, None)?;
         // verify that the uaid got added to our store and
         // that there is a record associated with the channel ID provided
         assert_eq!(pm.store.get_uaid()?.unwrap(), TEST_UAID);
@@ -792,7 +817,7 @@ mod test {
                 ))
             });
 
-        let _ = pm.subscribe("test-scope", None)?;
+        let _ = pm.subscribe("test-scope", None, None)?;
         // verify that a uaid got added to our store and
         // that there is a record associated with the channel ID provided
         assert_eq!(pm.store.get_uaid()?.unwrap(), TEST_UAID);
@@ -853,7 +878,7 @@ mod test {
                 ))
             });
 
-        let _ = pm.subscribe("test-scope", None)?;
+        let _ = pm.subscribe("test-scope", None, None)?;
         // verify that a uaid got added to our store and
         // that there is a record associated with the channel ID provided
         assert_eq!(pm.store.get_uaid()?.unwrap(), TEST_UAID);
@@ -914,8 +939,8 @@ mod test {
             })
         });
 
-        let resp_1 = pm.subscribe("test-scope", None)?;
-        let resp_2 = pm.subscribe("another-scope", None)?;
+        let resp_1 = pm.subscribe("test-scope", None, None)?;
+        let resp_2 = pm.subscribe("another-scope", None, None)?;
         assert_eq!(
             resp_1.subscription_info.endpoint,
             "https://example.com/dummy-endpoint"
@@ -927,6 +952,77 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_unsubscribe_all_for_group() -> Result<()> {
+        let _m = get_lock(&MTX);
+        let ctx = MockConnection::connect_context();
+        ctx.expect().returning(|_| Default::default());
+
+        let mut pm = get_test_manager()?;
+        pm.connection
+            .expect_register()
+            .with(eq("native-id"), eq(None))
+            .times(1)
+            .returning(|_, _| {
+                Ok(RegisterResponse {
+                    uaid: TEST_UAID.to_string(),
+                    channel_id: TEST_CHANNEL_ID.to_string(),
+                    secret: TEST_AUTH.to_string(),
+                    endpoint: "https://example.com/dummy-endpoint".to_string(),
+                    sender_id: Some("test".to_string()),
+                })
+            });
+        pm.connection
+            .expect_subscribe()
+            .with(eq(TEST_UAID), eq(TEST_AUTH), eq("native-id"), eq(None))
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(SubscribeResponse {
+                    channel_id: TEST_CHANNEL_ID2.to_string(),
+                    endpoint: "https://example.com/different-dummy-endpoint".to_string(),
+                    sender_id: Some("test".to_string()),
+                })
+            });
+        let crypto_ctx = MockCryptography::generate_key_context();
+        crypto_ctx.expect().returning(|| {
+            let components = EcKeyComponents::new(
+                base64::decode_config(PRIV_KEY_D, base64::URL_SAFE_NO_PAD).unwrap(),
+                base64::decode_config(PUB_KEY_RAW, base64::URL_SAFE_NO_PAD).unwrap(),
+            );
+            let auth = base64::decode_config(TEST_AUTH, base64::URL_SAFE_NO_PAD).unwrap();
+            Ok(Key {
+                p256key: components,
+                auth,
+            })
+        });
+
+        let _ = pm.subscribe("test-scope", None, Some("feature-a"))?;
+        let _ = pm.subscribe("another-scope", None, Some("feature-b"))?;
+
+        pm.connection
+            .expect_unsubscribe()
+            .with(eq(TEST_CHANNEL_ID), eq(TEST_UAID), eq(TEST_AUTH))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        assert_eq!(pm.unsubscribe_all_for_group("feature-a")?, 1);
+        assert!(pm.get_subscription("test-scope")?.is_none());
+        assert!(pm.get_subscription("another-scope")?.is_some());
+        // Nothing left in the group, so no network request.
+        assert_eq!(pm.unsubscribe_all_for_group("feature-a")?, 0);
+
+        // An existing subscription can be moved into a group by subscribing again.
+        let _ = pm.subscribe("another-scope", None, Some("feature-a"))?;
+        pm.connection
+            .expect_unsubscribe()
+            .with(eq(TEST_CHANNEL_ID2), eq(TEST_UAID), eq(TEST_AUTH))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        assert_eq!(pm.unsubscribe_all_for_group("feature-b")?, 0);
+        assert_eq!(pm.unsubscribe_all_for_group("feature-a")?, 1);
+        assert!(pm.get_subscription("another-scope")?.is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_verify_connection_rate_limiter() -> Result<()> {
         let _m = get_lock(&MTX);
@@ -959,7 +1055,7 @@ mod test {
                 auth,
             })
         });
-        let _ = pm.subscribe("test-scope", None)?;
+        let _ = pm.subscribe("test-scope", None, None)?;
         pm.connection
             .expect_channel_list()
             .with(eq(TEST_UAID), eq(TEST_AUTH))
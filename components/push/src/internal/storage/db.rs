@@ -17,6 +17,8 @@ pub trait Storage: Sized {
 
     fn get_record_by_scope(&self, scope: &str) -> Result<Option<PushRecord>>;
 
+    fn get_records_by_group(&self, group: &str) -> Result<Vec<PushRecord>>;
+
     fn put_record(&self, record: &PushRecord) -> Result<bool>;
 
     fn delete_record(&self, chid: &str) -> Result<bool>;
@@ -117,6 +119,15 @@ impl Storage for PushDb {
         self.try_query_row(&query, &[(":scope", scope)], PushRecord::from_row, false)
     }
 
+    fn get_records_by_group(&self, group: &str) -> Result<Vec<PushRecord>> {
+        let query = format!(
+            "SELECT {common_cols}
+             FROM push_record WHERE subscription_group = :group",
+            common_cols = schema::COMMON_COLS,
+        );
+        self.query_rows_and_then(&query, &[(":group", group)], PushRecord::from_row)
+    }
+
     fn put_record(&self, record: &PushRecord) -> Result<bool> {
         log::debug!(
             "adding push subscription for scope '{}', channel '{}', endpoint '{}'",
@@ -128,7 +139,7 @@ impl Storage for PushDb {
             "INSERT OR REPLACE INTO push_record
                  ({common_cols})
              VALUES
-                 (:channel_id, :endpoint, :scope, :key, :ctime, :app_server_key)",
+                 (:channel_id, :endpoint, :scope, :key, :ctime, :app_server_key, :group)",
             common_cols = schema::COMMON_COLS,
         );
         let affected_rows = self.execute(
@@ -143,6 +154,7 @@ impl Storage for PushDb {
                 (":key", &record.key),
                 (":ctime", &record.ctime),
                 (":app_server_key", &record.app_server_key),
+                (":group", &record.group),
             ],
         )?;
         Ok(affected_rows == 1)
@@ -324,6 +336,38 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn records_by_group() -> Result<()> {
+        let db = get_db()?;
+        let mut rec1 = prec(&get_uuid()?);
+        rec1.group = Some("feature-a".to_string());
+        let mut rec2 = prec(&get_uuid()?);
+        rec2.scope = "https://example.com/2".to_string();
+        rec2.group = Some("feature-a".to_string());
+        let mut rec3 = prec(&get_uuid()?);
+        rec3.scope = "https://example.com/3".to_string();
+        rec3.group = Some("feature-b".to_string());
+        let mut rec4 = prec(&get_uuid()?);
+        rec4.scope = "https://example.com/4".to_string();
+        for rec in [&rec1, &rec2, &rec3, &rec4] {
+            assert!(db.put_record(rec)?);
+        }
+
+        let mut chids = db
+            .get_records_by_group("feature-a")?
+            .into_iter()
+            .map(|r| r.channel_id)
+            .collect::<Vec<_>>();
+        chids.sort();
+        let mut expected = vec![rec1.channel_id.clone(), rec2.channel_id.clone()];
+        expected.sort();
+        assert_eq!(chids, expected);
+        assert_eq!(db.get_records_by_group("feature-b")?, vec![rec3]);
+        assert!(db.get_records_by_group("feature-c")?.is_empty());
+        assert_eq!(db.get_record(&rec4.channel_id)?.unwrap().group, None);
+        Ok(())
+    }
+
     #[test]
     fn delete_all_records() -> Result<()> {
         let db = get_db()?;
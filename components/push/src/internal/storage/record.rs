@@ -31,6 +31,10 @@ pub struct PushRecord {
     /// VAPID public key to restrict subscription updates for only those that sign
     /// using the private VAPID key.
     pub app_server_key: Option<String>,
+
+    /// Optional tag supplied by the consumer to group related subscriptions, so they
+    /// can all be removed together.
+    pub group: Option<String>,
 }
 
 impl PushRecord {
@@ -44,6 +48,7 @@ impl PushRecord {
             key: key.serialize()?,
             ctime: Timestamp::now(),
             app_server_key: None,
+            group: None,
         })
     }
 
@@ -55,6 +60,7 @@ impl PushRecord {
             key: row.get("key")?,
             ctime: row.get("ctime")?,
             app_server_key: row.get("app_server_key")?,
+            group: row.get("subscription_group")?,
         })
     }
 }
@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 use rusqlite::Transaction;
-use sql_support::open_database;
+use sql_support::{open_database, ConnExt};
 
 const CREATE_TABLE_PUSH_SQL: &str = include_str!("schema.sql");
 
@@ -10,7 +10,7 @@ pub struct PushConnectionInitializer;
 
 impl open_database::ConnectionInitializer for PushConnectionInitializer {
     const NAME: &'static str = "push db";
-    const END_VERSION: u32 = 3;
+    const END_VERSION: u32 = 4;
 
     // This is such a simple database that we do almost nothing!
     // * We have no foreign keys, so `PRAGMA foreign_keys = ON;` is pointless.
@@ -40,15 +40,26 @@ impl open_database::ConnectionInitializer for PushConnectionInitializer {
                     -- create the new table with the new schema.
                     {CREATE_TABLE_PUSH_SQL};
                     -- move the data across.
-                    INSERT OR IGNORE INTO push_record ({COMMON_COLS})
-                    SELECT {COMMON_COLS} FROM push_record_old WHERE length(scope) > 0;
+                    INSERT OR IGNORE INTO push_record ({V3_COLS})
+                    SELECT {V3_COLS} FROM push_record_old WHERE length(scope) > 0;
                     -- drop the old table
                     DROP TABLE push_record_old;",
                     CREATE_TABLE_PUSH_SQL = CREATE_TABLE_PUSH_SQL,
-                    COMMON_COLS = COMMON_COLS,
+                    V3_COLS = V3_COLS,
                 );
                 db.execute_batch(&sql)?;
             }
+            3 => {
+                // Add the `subscription_group` column. When upgrading from a version before 3,
+                // the table was just created with the current schema, so it may already exist.
+                let exists_sql = "SELECT 1 FROM pragma_table_info('push_record') WHERE name = 'subscription_group'";
+                if !db.exists(exists_sql, [])? {
+                    db.execute(
+                        "ALTER TABLE push_record ADD COLUMN subscription_group TEXT",
+                        [],
+                    )?;
+                }
+            }
             other => {
                 log::warn!(
                     "Loaded future schema version {} (we only understand version {}). \
@@ -63,6 +74,17 @@ impl open_database::ConnectionInitializer for PushConnectionInitializer {
 }
 
 pub const COMMON_COLS: &str = "
+    channel_id,
+    endpoint,
+    scope,
+    key,
+    ctime,
+    app_server_key,
+    subscription_group
+";
+
+// The columns which existed in version 3 of the schema, used when migrating from version 2.
+const V3_COLS: &str = "
     channel_id,
     endpoint,
     scope,
@@ -129,6 +151,7 @@ mod test {
         assert_eq!(record.key, [0x12, 0x34]);
         assert_eq!(record.ctime.0, 1);
         assert_eq!(record.app_server_key.unwrap(), "ask-1");
+        assert_eq!(record.group, None);
 
         // But both metadata ones.
         assert_eq!(
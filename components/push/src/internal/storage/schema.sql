@@ -13,6 +13,9 @@ IF NOT EXISTS push_record
     key                TEXT     NOT NULL,
     ctime              INTEGER  NOT NULL,
     app_server_key     TEXT,
+    -- An optional, consumer-defined tag which groups subscriptions together, so they can be
+    -- removed at once.
+    subscription_group TEXT,
     -- scope must have a value!
     CHECK(length(scope) > 0)
 );
@@ -237,6 +237,8 @@ impl PushManager {
     ///   - `channel_id` - Channel ID (UUID4) for new subscription, either pre-generated or "" and one will be created.
     ///   - `scope` - Site scope string (defaults to "" for no site scope string).
     ///   - `server_key` - optional VAPID public key to "lock" subscriptions (defaults to "" for no key)
+    ///   - `group` - optional tag used to group related subscriptions, so they can be removed
+    ///     together with [`PushManager::unsubscribe_all_for_group`]
     ///
     /// # Returns
     /// A Subscription response that includes the following:
@@ -254,11 +256,12 @@ impl PushManager {
         &self,
         scope: &str,
         server_key: &Option<String>,
+        group: &Option<String>,
     ) -> ApiResult<SubscriptionResponse> {
         self.internal
             .lock()
             .unwrap()
-            .subscribe(scope, server_key.as_deref())
+            .subscribe(scope, server_key.as_deref(), group.as_deref())
     }
 
     /// Retrieves an existing push subscription
@@ -300,6 +303,27 @@ impl PushManager {
         self.internal.lock().unwrap().unsubscribe(channel_id)
     }
 
+    /// Unsubscribe all channels which were subscribed with the given group
+    ///
+    /// # Arguments
+    ///   - `group` - The group passed to [`PushManager::subscribe`]
+    ///
+    /// # Returns
+    /// The number of subscriptions which were removed.
+    ///
+    /// # Errors
+    /// Returns an error in the following cases:
+    ///   - The PushManager does not contain a valid UAID
+    ///   - An error occurred sending an unsubscribe request to the autopush server
+    ///   - An error occurred accessing the PushManager's persisted storage
+    #[handle_error(PushError)]
+    pub fn unsubscribe_all_for_group(&self, group: &str) -> ApiResult<u32> {
+        self.internal
+            .lock()
+            .unwrap()
+            .unsubscribe_all_for_group(group)
+    }
+
     /// Unsubscribe all channels for the user
     ///
     /// # Errors
@@ -360,9 +384,10 @@ impl PushManager {
     /// # Arguments:
     ///   - `channel_id` - the ChannelID (included in the envelope of the message)
     ///   - `body` - The encrypted body of the message
-    ///   - `encoding` - The Content Encoding "enc" field of the message (defaults to "aes128gcm")
-    ///   - `salt` - The "salt" field (if present in the raw message, defaults to "")
-    ///   - `dh` - The "dh" field (if present in the raw message, defaults to "")
+    ///   - `con` - The Content Encoding of the message, either "aes128gcm" or "aesgcm". If it's missing,
+    ///     "aesgcm" is assumed when the `enc` and `cryptokey` fields are both present, and "aes128gcm" otherwise.
+    ///   - `enc` - The "salt" field (if present in the raw message, defaults to "")
+    ///   - `cryptokey` - The "dh" field (if present in the raw message, defaults to "")
     ///
     /// # Returns
     /// Decrypted message body as a signed byte array
@@ -373,6 +398,7 @@ impl PushManager {
     /// Returns an error in the following cases:
     ///   - The PushManager does not contain a valid UAID
     ///   - There are no records associated with the UAID the [`PushManager`] contains
+    ///   - The message uses a content encoding other than "aes128gcm" or "aesgcm"
     ///   - An error occurred while decrypting the message
     ///   - An error occurred accessing the PushManager's persisted storage
     #[handle_error(PushError)]
@@ -5,10 +5,12 @@
 use crate::config::RemoteSettingsConfig;
 use crate::error::{RemoteSettingsError, Result};
 use crate::UniffiCustomTypeConverter;
+use jexl_eval::Evaluator;
 use parking_lot::Mutex;
 use serde::Deserialize;
 use std::{
     borrow::Cow,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use url::Url;
@@ -24,12 +26,25 @@ pub struct Client {
     pub(crate) base_url: Url,
     pub(crate) bucket_name: String,
     pub(crate) collection_name: String,
-    pub(crate) remote_state: Mutex<RemoteState>,
+    pub(crate) filter_context: Option<RsJsonObject>,
+    pub(crate) remote_state: Arc<Mutex<RemoteState>>,
+    // What the server told us about the collection in its response to the
+    // last successful fetch of records.
+    last_fetch: Mutex<Option<FetchInfo>>,
 }
 
 impl Client {
     /// Create a new [Client] with properties matching config.
     pub fn new(config: RemoteSettingsConfig) -> Result<Self> {
+        Self::new_with_remote_state(config, Default::default())
+    }
+
+    /// Create a new [Client] which shares what it knows about the server,
+    /// like whether it asked us to back off, with other clients.
+    pub(crate) fn new_with_remote_state(
+        config: RemoteSettingsConfig,
+        remote_state: Arc<Mutex<RemoteState>>,
+    ) -> Result<Self> {
         let server_url = config
             .server_url
             .unwrap_or_else(|| String::from("https://firefox.settings.services.mozilla.com"));
@@ -40,7 +55,9 @@ impl Client {
             base_url,
             bucket_name,
             collection_name: config.collection_name,
-            remote_state: Default::default(),
+            filter_context: config.filter_context,
+            remote_state,
+            last_fetch: Default::default(),
         })
     }
 
@@ -48,16 +65,9 @@ impl Client {
     /// bucket, and collection defined by the [ClientConfig] used to generate
     /// this [Client].
     pub fn get_records(&self) -> Result<RemoteSettingsResponse> {
-        let resp = self.get_records_raw()?;
-        let records = resp.json::<RecordsResponse>()?.data;
-        let last_modified = resp
-            .headers
-            .get_as("etag")
-            .ok_or_else(|| RemoteSettingsError::ResponseError("no etag header".into()))??;
-        Ok(RemoteSettingsResponse {
-            records,
-            last_modified,
-        })
+        let mut resp = self.get_unfiltered_records(&GetItemsOptions::new())?;
+        resp.records = self.filter_records(resp.records);
+        Ok(resp)
     }
 
     /// Fetches all records for a collection that can be found in the server,
@@ -71,20 +81,73 @@ impl Client {
     /// for a collection that can be found in the server, bucket, and
     /// collection defined by the [ClientConfig] used to generate this [Client].
     pub fn get_records_since(&self, timestamp: u64) -> Result<RemoteSettingsResponse> {
-        let resp = self.get_records_raw_with_options(
+        let mut resp = self.get_unfiltered_records(
             GetItemsOptions::new().gt("last_modified", timestamp.to_string()),
         )?;
+        resp.records = self.filter_records(resp.records);
+        Ok(resp)
+    }
+
+    /// Fetches the records matching `options`, including any which don't match
+    /// our filter context, along with the collection's last modified time.
+    pub(crate) fn get_unfiltered_records(
+        &self,
+        options: &GetItemsOptions,
+    ) -> Result<RemoteSettingsResponse> {
+        let resp = self.get_records_raw_with_options(options)?;
         let records = resp.json::<RecordsResponse>()?.data;
+        let etag = resp
+            .headers
+            .get("etag")
+            .ok_or_else(|| RemoteSettingsError::ResponseError("no etag header".into()))?;
         let last_modified = resp
             .headers
             .get_as("etag")
             .ok_or_else(|| RemoteSettingsError::ResponseError("no etag header".into()))??;
+        *self.last_fetch.lock() = Some(FetchInfo {
+            etag: etag.to_string(),
+            last_modified,
+        });
         Ok(RemoteSettingsResponse {
             records,
             last_modified,
         })
     }
 
+    /// Fetches every record in the collection, after forgetting anything we've
+    /// cached about the server (currently, where its attachments live). Use this
+    /// to recover when records fetched with [Client::get_records_since] may have
+    /// diverged from the server: callers should replace the records they have,
+    /// and use the returned `last_modified` for later incremental fetches.
+    ///
+    /// Any backoff requested by the server is still respected.
+    pub fn force_full_sync(&self) -> Result<RemoteSettingsResponse> {
+        self.forget_server_info();
+        self.get_records()
+    }
+
+    /// The raw `ETag` header the server returned for the collection on the
+    /// last successful fetch of records, or `None` if we haven't fetched any yet.
+    pub fn get_last_etag(&self) -> Option<String> {
+        self.last_fetch
+            .lock()
+            .as_ref()
+            .map(|info| info.etag.clone())
+    }
+
+    /// The collection's last modified timestamp as of the last successful fetch
+    /// of records, or `None` if we haven't fetched any yet.
+    pub fn get_last_modified(&self) -> Option<u64> {
+        self.last_fetch
+            .lock()
+            .as_ref()
+            .map(|info| info.last_modified)
+    }
+
+    pub(crate) fn forget_server_info(&self) {
+        self.remote_state.lock().attachments_base_url = None;
+    }
+
     /// Fetches a raw network [Response] for records from this client's
     /// collection with the given options.
     pub fn get_records_raw_with_options(&self, options: &GetItemsOptions) -> Result<Response> {
@@ -122,6 +185,34 @@ impl Client {
         self.make_request(attachments_base_url.join(attachment_location)?)
     }
 
+    // Drops the records whose `filter_expression` doesn't match our filter
+    // context. Records without an expression always match, and so does
+    // everything if we weren't given a context.
+    pub(crate) fn filter_records(
+        &self,
+        records: Vec<RemoteSettingsRecord>,
+    ) -> Vec<RemoteSettingsRecord> {
+        let context = match &self.filter_context {
+            Some(context) => context,
+            None => return records,
+        };
+        let evaluator = Evaluator::new();
+        records
+            .into_iter()
+            .filter(|record| match record.fields.get("filter_expression") {
+                Some(serde_json::Value::String(expr)) if !expr.is_empty() => {
+                    // An expression we can't evaluate is treated as not
+                    // matching, as desktop does.
+                    evaluator
+                        .eval_in_context(expr, context)
+                        .map(|value| is_truthy(&value))
+                        .unwrap_or(false)
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
     fn make_request(&self, url: Url) -> Result<Response> {
         let mut current_remote_state = self.remote_state.lock();
         self.ensure_no_backoff(&mut current_remote_state.backoff)?;
@@ -185,6 +276,17 @@ impl Client {
     }
 }
 
+// JEXL truthiness, which is the same as JavaScript's.
+fn is_truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().map_or(false, |n| n != 0.0 && !n.is_nan()),
+        serde_json::Value::String(s) => !s.is_empty(),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => true,
+    }
+}
+
 /// Data structure representing the top-level response from the Remote Settings.
 /// [last_modified] will be extracted from the etag header of the response.
 pub struct RemoteSettingsResponse {
@@ -241,6 +343,12 @@ impl UniffiCustomTypeConverter for RsJsonObject {
     }
 }
 
+#[derive(Clone, Debug)]
+struct FetchInfo {
+    etag: String,
+    last_modified: u64,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct RemoteState {
     attachments_base_url: Option<Url>,
@@ -492,6 +600,7 @@ mod test {
             server_url: None,
             bucket_name: None,
             collection_name: String::from("the-collection"),
+            filter_context: None,
         };
         let client = Client::new(config).unwrap();
         assert_eq!(
@@ -524,6 +633,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: None,
         };
 
@@ -535,6 +645,99 @@ mod test {
         assert_eq!(resp.body, attachment_bytes);
     }
 
+    #[test]
+    fn test_force_full_sync() {
+        viaduct_reqwest::use_reqwest_backend();
+        let server_info_m = mock("GET", "/")
+            .with_body(attachment_metadata(mockito::server_url()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .expect(2)
+            .create();
+        let attachment_m = mock("GET", "/attachments/full-sync.jpg")
+            .with_body("I'm a JPG, I swear")
+            .with_status(200)
+            .expect(2)
+            .create();
+        let records_m = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/the-full-sync-collection/records",
+        )
+        .match_query(Matcher::Missing)
+        .with_body(r#"{"data": [{"id": "a", "last_modified": 1000}]}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .create();
+
+        let client = Client::new(RemoteSettingsConfig {
+            server_url: Some(mockito::server_url()),
+            collection_name: String::from("the-full-sync-collection"),
+            filter_context: None,
+            bucket_name: Some(String::from("the-bucket")),
+        })
+        .unwrap();
+        client.get_attachment("full-sync.jpg").unwrap();
+
+        // A full sync fetches every record, without a `gt_last_modified` filter...
+        let resp = client.force_full_sync().unwrap();
+        assert_eq!(resp.records.len(), 1);
+        assert_eq!(resp.last_modified, 1000);
+
+        // ...and fetches the server info again the next time it's needed.
+        client.get_attachment("full-sync.jpg").unwrap();
+        records_m.expect(1).assert();
+        server_info_m.assert();
+        attachment_m.assert();
+    }
+
+    #[test]
+    fn test_last_fetch_info() {
+        viaduct_reqwest::use_reqwest_backend();
+        let full_m = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/the-etag-collection/records",
+        )
+        .match_query(Matcher::Missing)
+        .with_body(r#"{"data": [{"id": "a", "last_modified": 1000}]}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .create();
+        let since_m = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/the-etag-collection/records",
+        )
+        .match_query(Matcher::UrlEncoded(
+            "gt_last_modified".into(),
+            "1000".into(),
+        ))
+        .with_status(500)
+        .create();
+
+        let client = Client::new(RemoteSettingsConfig {
+            server_url: Some(mockito::server_url()),
+            collection_name: String::from("the-etag-collection"),
+            filter_context: None,
+            bucket_name: Some(String::from("the-bucket")),
+        })
+        .unwrap();
+        assert_eq!(client.get_last_etag(), None);
+        assert_eq!(client.get_last_modified(), None);
+
+        client.get_records().unwrap();
+        assert_eq!(client.get_last_etag().as_deref(), Some("1000"));
+        assert_eq!(client.get_last_modified(), Some(1000));
+
+        // A failed fetch leaves what we got from the last successful one.
+        assert!(client.get_records_since(1000).is_err());
+        assert_eq!(client.get_last_etag().as_deref(), Some("1000"));
+        assert_eq!(client.get_last_modified(), Some(1000));
+
+        full_m.expect(1).assert();
+        since_m.expect(1).assert();
+    }
+
     #[test]
     fn test_attachment_errors_if_server_not_configured_for_attachments() {
         viaduct_reqwest::use_reqwest_backend();
@@ -558,6 +761,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: None,
         };
 
@@ -587,6 +791,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: Some(String::from("the-bucket")),
         };
         let http_client = Client::new(config).unwrap();
@@ -614,6 +819,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: Some(String::from("the-bucket")),
         };
         let http_client = Client::new(config).unwrap();
@@ -656,6 +862,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: Some(String::from("the-bucket")),
         };
         let http_client = Client::new(config).unwrap();
@@ -698,6 +905,7 @@ mod test {
         let config = RemoteSettingsConfig {
             server_url: Some(mockito::server_url()),
             collection_name: String::from("the-collection"),
+            filter_context: None,
             bucket_name: Some(String::from("the-bucket")),
         };
         let http_client = Client::new(config).unwrap();
@@ -723,6 +931,78 @@ mod test {
         m.expect(1).assert();
     }
 
+    #[test]
+    fn test_filter_expression() {
+        viaduct_reqwest::use_reqwest_backend();
+        let body = r#"{
+            "data": [
+                {"id": "no-expression", "last_modified": 1},
+                {"id": "empty-expression", "last_modified": 2, "filter_expression": ""},
+                {"id": "match", "last_modified": 3, "filter_expression": "locale == 'en-US' && version >= 117"},
+                {"id": "no-match", "last_modified": 4, "filter_expression": "locale == 'de'"},
+                {"id": "falsy", "last_modified": 5, "filter_expression": "missing"},
+                {"id": "invalid", "last_modified": 6, "filter_expression": "locale =="}
+            ]
+        }"#;
+        let m = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/the-filtered-collection/records",
+        )
+        .with_body(body)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .expect(2)
+        .create();
+        let context = serde_json::json!({"locale": "en-US", "version": 117});
+        let filtered = Client::new(RemoteSettingsConfig {
+            server_url: Some(mockito::server_url()),
+            collection_name: String::from("the-filtered-collection"),
+            filter_context: context.as_object().cloned(),
+            bucket_name: Some(String::from("the-bucket")),
+        })
+        .unwrap();
+        let ids = |resp: RemoteSettingsResponse| {
+            resp.records
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            ids(filtered.get_records().unwrap()),
+            vec!["no-expression", "empty-expression", "match"]
+        );
+
+        // Without a context, nothing is filtered.
+        let unfiltered = Client::new(RemoteSettingsConfig {
+            server_url: Some(mockito::server_url()),
+            collection_name: String::from("the-filtered-collection"),
+            filter_context: None,
+            bucket_name: Some(String::from("the-bucket")),
+        })
+        .unwrap();
+        assert_eq!(unfiltered.get_records().unwrap().records.len(), 6);
+        m.assert();
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        use serde_json::json;
+        for value in [json!(null), json!(false), json!(0), json!(""), json!(0.0)] {
+            assert!(!is_truthy(&value), "{value} should be falsy");
+        }
+        for value in [
+            json!(true),
+            json!(1),
+            json!(-1.5),
+            json!("a"),
+            json!([]),
+            json!({}),
+        ] {
+            assert!(is_truthy(&value), "{value} should be truthy");
+        }
+    }
+
     fn attachment_metadata(base_url: String) -> String {
         format!(
             r#"
@@ -0,0 +1,473 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A client for consumers which use several collections from the same server, so they don't
+//! need a [RemoteSettings](crate::RemoteSettings) with its own copy of the config for each one.
+//! The records for each collection are cached in memory, so after the first fetch only the
+//! changes since the last one are requested.
+
+use crate::client::{
+    Client, GetItemsOptions, RemoteSettingsRecord, RemoteSettingsResponse, RemoteState,
+    RsJsonObject,
+};
+use crate::config::RemoteSettingsClientConfig;
+use crate::error::Result;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::{fs::File, io::prelude::Write};
+use url::Url;
+
+pub struct RemoteSettingsClient {
+    pub config: RemoteSettingsClientConfig,
+    // Shared by the clients for every collection, so that they all respect a backoff
+    // requested by the server.
+    remote_state: Arc<Mutex<RemoteState>>,
+    collections: Mutex<HashMap<String, Arc<Collection>>>,
+    // Records set with `set_local_override`, by collection name and then record ID. These
+    // outlive `force_full_sync`, because they're set by hand.
+    local_overrides: Mutex<HashMap<String, BTreeMap<String, RemoteSettingsRecord>>>,
+}
+
+struct Collection {
+    client: Client,
+    // Held while fetching, so that concurrent fetches for the same collection don't race to
+    // merge their changes.
+    state: Mutex<CollectionState>,
+}
+
+#[derive(Default)]
+struct CollectionState {
+    // Every record in the collection, including ones which don't match the filter context,
+    // because a record can change to match it later.
+    records: Vec<RemoteSettingsRecord>,
+    // `None` until we've fetched the whole collection.
+    last_modified: Option<u64>,
+}
+
+impl CollectionState {
+    // Applies changes fetched since `last_modified`. Deleted records are returned as
+    // tombstones with a `deleted` field.
+    fn merge(&mut self, changes: Vec<RemoteSettingsRecord>, last_modified: u64) {
+        {
+            let changed_ids = changes
+                .iter()
+                .map(|record| record.id.as_str())
+                .collect::<HashSet<_>>();
+            self.records
+                .retain(|record| !changed_ids.contains(record.id.as_str()));
+        }
+        self.records.extend(changes.into_iter().filter(|record| {
+            !matches!(
+                record.fields.get("deleted"),
+                Some(serde_json::Value::Bool(true))
+            )
+        }));
+        self.last_modified = Some(last_modified);
+    }
+}
+
+impl RemoteSettingsClient {
+    pub fn new(config: RemoteSettingsClientConfig) -> Result<Self> {
+        // Check the URL now, rather than when the first collection is fetched.
+        if let Some(server_url) = &config.server_url {
+            Url::parse(server_url)?;
+        }
+        Ok(Self {
+            config,
+            remote_state: Default::default(),
+            collections: Default::default(),
+            local_overrides: Default::default(),
+        })
+    }
+
+    /// Fetches every record in `collection_name`. The first call fetches the whole
+    /// collection, and later calls only fetch what changed since the previous one.
+    ///
+    /// Records set with `set_local_override` replace the fetched records with the same ID.
+    pub fn get_records(&self, collection_name: &str) -> Result<RemoteSettingsResponse> {
+        let collection = self.get_collection(collection_name)?;
+        let mut state = collection.state.lock();
+        let mut options = GetItemsOptions::new();
+        if let Some(last_modified) = state.last_modified {
+            options.gt("last_modified", last_modified.to_string());
+        }
+        let resp = collection.client.get_unfiltered_records(&options)?;
+        state.merge(resp.records, resp.last_modified);
+        let records = collection.client.filter_records(state.records.clone());
+        Ok(RemoteSettingsResponse {
+            records: self.apply_local_overrides(collection_name, records),
+            last_modified: resp.last_modified,
+        })
+    }
+
+    /// Overrides the record `record_id` in `collection_name` with `fields`, so that QA can test
+    /// how records are handled without changing them on the server. `get_records` returns the
+    /// override in place of the fetched record with the same ID, or as well as the fetched
+    /// records if there isn't one. Overrides aren't checked against the filter context, and
+    /// only last as long as this client.
+    ///
+    /// `fields` is the JSON of the record. Its `id` is ignored, and its `last_modified`
+    /// defaults to 0.
+    pub fn set_local_override(
+        &self,
+        collection_name: &str,
+        record_id: String,
+        mut fields: RsJsonObject,
+    ) -> Result<()> {
+        fields.insert("id".into(), record_id.clone().into());
+        fields
+            .entry("last_modified")
+            .or_insert_with(|| serde_json::Value::from(0));
+        let record: RemoteSettingsRecord = serde_json::from_value(fields.into())?;
+        log::warn!(
+            "Locally overriding record {} in {}",
+            record_id,
+            collection_name
+        );
+        self.local_overrides
+            .lock()
+            .entry(collection_name.to_string())
+            .or_default()
+            .insert(record_id, record);
+        Ok(())
+    }
+
+    /// Removes every record set with `set_local_override`, in all collections.
+    pub fn clear_overrides(&self) {
+        self.local_overrides.lock().clear();
+    }
+
+    /// The IDs of the records in `collection_name` which are overridden with
+    /// `set_local_override`, so that diagnostics can show them.
+    pub fn get_local_overrides(&self, collection_name: &str) -> Vec<String> {
+        self.local_overrides
+            .lock()
+            .get(collection_name)
+            .map(|overrides| overrides.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn apply_local_overrides(
+        &self,
+        collection_name: &str,
+        mut records: Vec<RemoteSettingsRecord>,
+    ) -> Vec<RemoteSettingsRecord> {
+        let local_overrides = self.local_overrides.lock();
+        let overrides = match local_overrides.get(collection_name) {
+            Some(overrides) if !overrides.is_empty() => overrides,
+            _ => return records,
+        };
+        log::warn!(
+            "Returning {} locally overridden records for {}",
+            overrides.len(),
+            collection_name
+        );
+        records.retain(|record| !overrides.contains_key(&record.id));
+        records.extend(overrides.values().cloned());
+        records
+    }
+
+    /// Forgets the records cached for `collection_name`, along with what we know about the
+    /// server, and fetches the whole collection again. Use this to recover when the cached
+    /// records may have diverged from the server.
+    pub fn force_full_sync(&self, collection_name: &str) -> Result<RemoteSettingsResponse> {
+        let collection = self.get_collection(collection_name)?;
+        *collection.state.lock() = CollectionState::default();
+        collection.client.forget_server_info();
+        self.get_records(collection_name)
+    }
+
+    /// The raw `ETag` header from the last successful fetch of `collection_name`,
+    /// or `None` if it hasn't been fetched yet.
+    pub fn get_last_etag(&self, collection_name: &str) -> Option<String> {
+        self.collections
+            .lock()
+            .get(collection_name)
+            .and_then(|collection| collection.client.get_last_etag())
+    }
+
+    /// The last modified timestamp of `collection_name` as of its last successful
+    /// fetch, or `None` if it hasn't been fetched yet.
+    pub fn get_last_modified(&self, collection_name: &str) -> Option<u64> {
+        self.collections
+            .lock()
+            .get(collection_name)
+            .and_then(|collection| collection.client.get_last_modified())
+    }
+
+    /// Downloads an attachment of a record in `collection_name` to `path`.
+    pub fn download_attachment_to_path(
+        &self,
+        collection_name: &str,
+        attachment_location: String,
+        path: String,
+    ) -> Result<()> {
+        let resp = self
+            .get_collection(collection_name)?
+            .client
+            .get_attachment(&attachment_location)?;
+        let mut file = File::create(path)?;
+        file.write_all(&resp.body)?;
+        Ok(())
+    }
+
+    fn get_collection(&self, collection_name: &str) -> Result<Arc<Collection>> {
+        let mut collections = self.collections.lock();
+        if let Some(collection) = collections.get(collection_name) {
+            return Ok(collection.clone());
+        }
+        let collection = Arc::new(Collection {
+            client: Client::new_with_remote_state(
+                self.config.for_collection(collection_name),
+                self.remote_state.clone(),
+            )?,
+            state: Default::default(),
+        });
+        collections.insert(collection_name.to_string(), collection.clone());
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RemoteSettingsError;
+    use mockito::{mock, Matcher};
+
+    fn ids(resp: RemoteSettingsResponse) -> Vec<String> {
+        let mut ids = resp
+            .records
+            .into_iter()
+            .map(|r| r.id)
+            .collect::<Vec<String>>();
+        ids.sort();
+        ids
+    }
+
+    fn new_client(filter_context: Option<serde_json::Value>) -> RemoteSettingsClient {
+        RemoteSettingsClient::new(RemoteSettingsClientConfig {
+            server_url: Some(mockito::server_url()),
+            bucket_name: Some(String::from("the-bucket")),
+            filter_context: filter_context.and_then(|c| c.as_object().cloned()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_incremental_sync_per_collection() {
+        viaduct_reqwest::use_reqwest_backend();
+        let full_a = mock("GET", "/v1/buckets/the-bucket/collections/multi-a/records")
+            .match_query(Matcher::Missing)
+            .with_body(
+                r#"{"data": [
+                {"id": "one", "last_modified": 900},
+                {"id": "two", "last_modified": 1000}
+            ]}"#,
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "1000")
+            .create();
+        let since_a = mock("GET", "/v1/buckets/the-bucket/collections/multi-a/records")
+            .match_query(Matcher::UrlEncoded(
+                "gt_last_modified".into(),
+                "1000".into(),
+            ))
+            .with_body(
+                r#"{"data": [
+                {"id": "one", "last_modified": 1100, "deleted": true},
+                {"id": "three", "last_modified": 1200}
+            ]}"#,
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "1200")
+            .create();
+        let full_b = mock("GET", "/v1/buckets/the-bucket/collections/multi-b/records")
+            .match_query(Matcher::Missing)
+            .with_body(r#"{"data": [{"id": "other", "last_modified": 500}]}"#)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "500")
+            .create();
+
+        let client = new_client(None);
+        assert_eq!(client.get_last_etag("multi-a"), None);
+        assert_eq!(ids(client.get_records("multi-a").unwrap()), ["one", "two"]);
+        assert_eq!(client.get_last_modified("multi-a"), Some(1000));
+        assert_eq!(client.get_last_etag("multi-b"), None);
+        // The other collection has its own state, so it's fetched in full.
+        assert_eq!(ids(client.get_records("multi-b").unwrap()), ["other"]);
+        // The next fetch only asks for changes, and merges them with what we have.
+        let resp = client.get_records("multi-a").unwrap();
+        assert_eq!(resp.last_modified, 1200);
+        assert_eq!(ids(resp), ["three", "two"]);
+        assert_eq!(client.get_last_etag("multi-a").as_deref(), Some("1200"));
+        assert_eq!(client.get_last_modified("multi-b"), Some(500));
+
+        full_a.expect(1).assert();
+        since_a.expect(1).assert();
+        full_b.expect(1).assert();
+    }
+
+    #[test]
+    fn test_filter_changed_records() {
+        viaduct_reqwest::use_reqwest_backend();
+        let full = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/multi-filtered/records",
+        )
+        .match_query(Matcher::Missing)
+        .with_body(
+            r#"{"data": [
+                {"id": "a", "last_modified": 900, "filter_expression": "locale == 'de'"},
+                {"id": "b", "last_modified": 1000}
+            ]}"#,
+        )
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .create();
+        let since = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/multi-filtered/records",
+        )
+        .match_query(Matcher::UrlEncoded(
+            "gt_last_modified".into(),
+            "1000".into(),
+        ))
+        .with_body(
+            r#"{"data": [
+                {"id": "a", "last_modified": 1100},
+                {"id": "b", "last_modified": 1200, "filter_expression": "locale == 'de'"}
+            ]}"#,
+        )
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1200")
+        .create();
+
+        let client = new_client(Some(serde_json::json!({"locale": "en-US"})));
+        assert_eq!(ids(client.get_records("multi-filtered").unwrap()), ["b"]);
+        // Records which didn't match before are still cached, so they're returned once
+        // they change to match.
+        assert_eq!(ids(client.get_records("multi-filtered").unwrap()), ["a"]);
+        full.expect(1).assert();
+        since.expect(1).assert();
+    }
+
+    #[test]
+    fn test_local_overrides() {
+        viaduct_reqwest::use_reqwest_backend();
+        let full = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/multi-overrides/records",
+        )
+        .match_query(Matcher::Missing)
+        .with_body(
+            r#"{"data": [
+                {"id": "one", "last_modified": 900, "title": "server"},
+                {"id": "two", "last_modified": 1000}
+            ]}"#,
+        )
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .create();
+        let since = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/multi-overrides/records",
+        )
+        .match_query(Matcher::UrlEncoded(
+            "gt_last_modified".into(),
+            "1000".into(),
+        ))
+        .with_body(r#"{"data": []}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .create();
+
+        let client = new_client(Some(serde_json::json!({"locale": "en-US"})));
+        let fields = |value: serde_json::Value| value.as_object().cloned().unwrap();
+        client
+            .set_local_override(
+                "multi-overrides",
+                "one".into(),
+                fields(serde_json::json!({"title": "local", "last_modified": 1500})),
+            )
+            .unwrap();
+        // Overrides aren't filtered, and don't need to match a record on the server.
+        client
+            .set_local_override(
+                "multi-overrides",
+                "new".into(),
+                fields(serde_json::json!({"filter_expression": "locale == 'de'"})),
+            )
+            .unwrap();
+        assert!(client
+            .set_local_override(
+                "multi-overrides",
+                "bad".into(),
+                fields(serde_json::json!({"last_modified": "yesterday"})),
+            )
+            .is_err());
+        assert_eq!(
+            client.get_local_overrides("multi-overrides"),
+            ["new", "one"]
+        );
+        assert!(client.get_local_overrides("multi-other").is_empty());
+
+        let resp = client.get_records("multi-overrides").unwrap();
+        let one = resp.records.iter().find(|r| r.id == "one").unwrap();
+        assert_eq!(one.last_modified, 1500);
+        assert_eq!(one.fields["title"], "local");
+        let new = resp.records.iter().find(|r| r.id == "new").unwrap();
+        assert_eq!(new.last_modified, 0);
+        assert_eq!(ids(resp), ["new", "one", "two"]);
+
+        client.clear_overrides();
+        assert!(client.get_local_overrides("multi-overrides").is_empty());
+        let resp = client.get_records("multi-overrides").unwrap();
+        let one = resp.records.iter().find(|r| r.id == "one").unwrap();
+        assert_eq!(one.fields["title"], "server");
+        assert_eq!(ids(resp), ["one", "two"]);
+        full.expect(1).assert();
+        since.expect(1).assert();
+    }
+
+    #[test]
+    fn test_backoff_shared_between_collections() {
+        viaduct_reqwest::use_reqwest_backend();
+        let m = mock(
+            "GET",
+            "/v1/buckets/the-bucket/collections/multi-backoff/records",
+        )
+        .with_body(r#"{"data": []}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("etag", "1000")
+        .with_header("Backoff", "60")
+        .create();
+
+        let client = new_client(None);
+        client.get_records("multi-backoff").unwrap();
+        assert!(matches!(
+            client.get_records("multi-other"),
+            Err(RemoteSettingsError::BackoffError(_))
+        ));
+        m.expect(1).assert();
+    }
+
+    #[test]
+    fn test_invalid_server_url() {
+        assert!(RemoteSettingsClient::new(RemoteSettingsClientConfig {
+            server_url: Some(String::from("not a url")),
+            bucket_name: None,
+            filter_context: None,
+        })
+        .is_err());
+    }
+}
@@ -8,14 +8,59 @@
 //! The purpose of the configuration parameters are to allow consumers an easy debugging option,
 //! and the ability to be explicit about the server.
 
+use crate::RsJsonObject;
+
 /// Custom configuration for the client.
 /// Currently includes the following:
 /// - `server_url`: The optional url for the settings server. If not specified, the standard server will be used.
 /// - `bucket_name`: The optional name of the bucket containing the collection on the server. If not specified, the standard bucket will be used.
 /// - `collection_name`: The name of the collection for the settings server.
+/// - `filter_context`: The optional context used to evaluate each record's JEXL `filter_expression`.
+///   If specified, records whose expression doesn't match the context are left out. If not specified,
+///   all records are returned.
 #[derive(Debug, Clone)]
 pub struct RemoteSettingsConfig {
     pub server_url: Option<String>,
     pub bucket_name: Option<String>,
     pub collection_name: String,
+    pub filter_context: Option<RsJsonObject>,
+}
+
+/// Custom configuration for a [crate::RemoteSettingsClient], which fetches records from any
+/// number of collections in the same bucket. The fields are the same as for
+/// [RemoteSettingsConfig], with the collection given to each call instead.
+#[derive(Debug, Clone)]
+pub struct RemoteSettingsClientConfig {
+    pub server_url: Option<String>,
+    pub bucket_name: Option<String>,
+    pub filter_context: Option<RsJsonObject>,
+}
+
+impl RemoteSettingsClientConfig {
+    pub(crate) fn for_collection(&self, collection_name: &str) -> RemoteSettingsConfig {
+        RemoteSettingsConfig {
+            server_url: self.server_url.clone(),
+            bucket_name: self.bucket_name.clone(),
+            collection_name: collection_name.to_string(),
+            filter_context: self.filter_context.clone(),
+        }
+    }
+}
+
+/// Configuration for a [crate::RemoteSettingsWriter], which publishes to a collection.
+/// - `server_url`: The url of the settings server to write to. There's no default, because the standard server
+///   used by the read clients is a read-only CDN.
+/// - `bucket_name`: The optional name of the bucket to write to. If not specified, the standard workspace bucket,
+///   `main-workspace`, will be used.
+/// - `collection_name`: The name of the collection to write to.
+/// - `bearer_token`: The token sent in the `Authorization` header of every request. It must be allowed to write to
+///   the collection.
+///
+/// This deliberately doesn't implement `Debug`, so the token can't end up in logs.
+#[derive(Clone)]
+pub struct RemoteSettingsWriterConfig {
+    pub server_url: String,
+    pub bucket_name: Option<String>,
+    pub collection_name: String,
+    pub bearer_token: String,
 }
@@ -10,8 +10,12 @@ pub use client::{
     Attachment, Client, GetItemsOptions, RemoteSettingsRecord, RemoteSettingsResponse,
     RsJsonObject, SortOrder,
 };
+pub mod collections;
+pub use collections::RemoteSettingsClient;
 pub mod config;
-pub use config::RemoteSettingsConfig;
+pub use config::{RemoteSettingsClientConfig, RemoteSettingsConfig, RemoteSettingsWriterConfig};
+pub mod writer;
+pub use writer::RemoteSettingsWriter;
 
 uniffi::include_scaffolding!("remote_settings");
 
@@ -38,6 +42,19 @@ impl RemoteSettings {
         Ok(resp)
     }
 
+    pub fn force_full_sync(&self) -> Result<RemoteSettingsResponse> {
+        let resp = self.client.force_full_sync()?;
+        Ok(resp)
+    }
+
+    pub fn get_last_etag(&self) -> Option<String> {
+        self.client.get_last_etag()
+    }
+
+    pub fn get_last_modified(&self) -> Option<u64> {
+        self.client.get_last_modified()
+    }
+
     pub fn download_attachment_to_path(
         &self,
         attachment_location: String,
@@ -73,6 +90,7 @@ mod test {
             server_url: Some(mockito::server_url()),
             bucket_name: Some(String::from("the-bucket")),
             collection_name: String::from("the-collection"),
+            filter_context: None,
         };
         let remote_settings = RemoteSettings::new(config).unwrap();
 
@@ -101,6 +119,7 @@ mod test {
             server_url: Some(mockito::server_url()),
             bucket_name: Some(String::from("the-bucket")),
             collection_name: String::from("the-collection"),
+            filter_context: None,
         };
         let remote_settings = RemoteSettings::new(config).unwrap();
 
@@ -122,6 +141,7 @@ mod test {
             server_url: Some("http://localhost:8888".to_string()),
             bucket_name: Some(String::from("the-bucket")),
             collection_name: String::from("the-collection"),
+            filter_context: None,
         };
         let remote_settings = RemoteSettings::new(config).unwrap();
 
@@ -0,0 +1,268 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! An authenticated client for publishing to a Remote Settings collection, for tools which
+//! manage records rather than apps which read them. It's deliberately separate from the read
+//! clients, which never send credentials.
+//!
+//! Changes are made in the workspace bucket (`main-workspace` by default), and only reach the
+//! published bucket once they've been reviewed, so the usual flow is to make some changes and
+//! then call [RemoteSettingsWriter::request_review].
+//!
+//! Records are only replaced or deleted if they haven't changed since the `last_modified` time
+//! the caller last saw, so concurrent edits fail rather than overwriting each other.
+
+use crate::client::{RemoteSettingsRecord, RsJsonObject};
+use crate::config::RemoteSettingsWriterConfig;
+use crate::error::{RemoteSettingsError, Result};
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+use viaduct::{header_names, Request, Response};
+
+pub struct RemoteSettingsWriter {
+    base_url: Url,
+    bucket_name: String,
+    collection_name: String,
+    bearer_token: String,
+}
+
+impl RemoteSettingsWriter {
+    pub fn new(config: RemoteSettingsWriterConfig) -> Result<Self> {
+        Ok(Self {
+            base_url: Url::parse(&config.server_url)?,
+            bucket_name: config
+                .bucket_name
+                .unwrap_or_else(|| String::from("main-workspace")),
+            collection_name: config.collection_name,
+            bearer_token: config.bearer_token,
+        })
+    }
+
+    /// Creates a record with `fields`. The server picks the ID, unless `fields` has an `id`.
+    pub fn create_record(&self, fields: RsJsonObject) -> Result<RemoteSettingsRecord> {
+        let req = Request::post(self.records_url(None)?).json(&json!({ "data": fields }));
+        Ok(self.send(req)?.json::<RecordResponse>()?.data)
+    }
+
+    /// Replaces the fields of the record with `id` with `fields`, if it was last modified at
+    /// `last_modified`. If `last_modified` is `None`, the record is created instead, failing if
+    /// it already exists.
+    pub fn update_record(
+        &self,
+        id: &str,
+        fields: RsJsonObject,
+        last_modified: Option<u64>,
+    ) -> Result<RemoteSettingsRecord> {
+        let req = Request::put(self.records_url(Some(id))?).json(&json!({ "data": fields }));
+        let req = match last_modified {
+            Some(last_modified) => {
+                req.header(header_names::IF_MATCH, format!("\"{}\"", last_modified))?
+            }
+            None => req.header(header_names::IF_NONE_MATCH, "*")?,
+        };
+        Ok(self.send(req)?.json::<RecordResponse>()?.data)
+    }
+
+    /// Deletes the record with `id`, if it was last modified at `last_modified`. Fails if there
+    /// isn't one.
+    pub fn delete_record(&self, id: &str, last_modified: u64) -> Result<()> {
+        let req = Request::delete(self.records_url(Some(id))?)
+            .header(header_names::IF_MATCH, format!("\"{}\"", last_modified))?;
+        self.send(req)?;
+        Ok(())
+    }
+
+    /// Asks for the changes in the collection to be reviewed, so they can be published. The
+    /// optional `comment` is shown to the reviewer.
+    pub fn request_review(&self, comment: Option<&str>) -> Result<()> {
+        let mut data = json!({ "status": "to-review" });
+        if let Some(comment) = comment {
+            data["last_editor_comment"] = comment.into();
+        }
+        let req = Request::patch(self.collection_url()?).json(&json!({ "data": data }));
+        self.send(req)?;
+        Ok(())
+    }
+
+    fn collection_url(&self) -> Result<Url> {
+        let path = format!(
+            "v1/buckets/{}/collections/{}",
+            &self.bucket_name, &self.collection_name
+        );
+        Ok(self.base_url.join(&path)?)
+    }
+
+    fn records_url(&self, id: Option<&str>) -> Result<Url> {
+        let mut url = self.collection_url()?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?;
+            segments.push("records");
+            if let Some(id) = id {
+                segments.push(id);
+            }
+        }
+        Ok(url)
+    }
+
+    fn send(&self, req: Request) -> Result<Response> {
+        let resp = req
+            .header(
+                header_names::AUTHORIZATION,
+                format!("Bearer {}", self.bearer_token),
+            )?
+            .send()?;
+        if resp.is_success() {
+            Ok(resp)
+        } else {
+            Err(RemoteSettingsError::ResponseError(format!(
+                "{} {}",
+                resp.status,
+                resp.text()
+            )))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RecordResponse {
+    data: RemoteSettingsRecord,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    fn writer() -> RemoteSettingsWriter {
+        RemoteSettingsWriter::new(RemoteSettingsWriterConfig {
+            server_url: mockito::server_url(),
+            bucket_name: None,
+            collection_name: String::from("the-collection"),
+            bearer_token: String::from("the-token"),
+        })
+        .unwrap()
+    }
+
+    fn fields(value: serde_json::Value) -> RsJsonObject {
+        match value {
+            serde_json::Value::Object(obj) => obj,
+            _ => panic!("not an object"),
+        }
+    }
+
+    #[test]
+    fn test_create_and_update_record() {
+        viaduct_reqwest::use_reqwest_backend();
+        let create_m = mock(
+            "POST",
+            "/v1/buckets/main-workspace/collections/the-collection/records",
+        )
+        .match_header("authorization", "Bearer the-token")
+        .match_body(Matcher::Json(json!({ "data": { "title": "jpg" } })))
+        .with_body(r#"{"data": {"id": "rec-1", "last_modified": 100, "title": "jpg"}}"#)
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .create();
+        let update_m = mock(
+            "PUT",
+            "/v1/buckets/main-workspace/collections/the-collection/records/rec-1",
+        )
+        .match_header("authorization", "Bearer the-token")
+        .match_header("if-match", "\"100\"")
+        .match_body(Matcher::Json(json!({ "data": { "title": "png" } })))
+        .with_body(r#"{"data": {"id": "rec-1", "last_modified": 200, "title": "png"}}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .create();
+
+        let writer = writer();
+        let created = writer
+            .create_record(fields(json!({ "title": "jpg" })))
+            .unwrap();
+        assert_eq!(created.id, "rec-1");
+        assert_eq!(created.last_modified, 100);
+        let updated = writer
+            .update_record("rec-1", fields(json!({ "title": "png" })), Some(100))
+            .unwrap();
+        assert_eq!(updated.last_modified, 200);
+        assert_eq!(updated.fields["title"], "png");
+        create_m.expect(1).assert();
+        update_m.expect(1).assert();
+    }
+
+    #[test]
+    fn test_delete_record_and_request_review() {
+        viaduct_reqwest::use_reqwest_backend();
+        let delete_m = mock(
+            "DELETE",
+            "/v1/buckets/main-workspace/collections/the-collection/records/rec-2",
+        )
+        .match_header("authorization", "Bearer the-token")
+        .match_header("if-match", "\"200\"")
+        .with_body(r#"{"data": {"id": "rec-2", "last_modified": 300, "deleted": true}}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .create();
+        let review_m = mock(
+            "PATCH",
+            "/v1/buckets/main-workspace/collections/the-collection",
+        )
+        .match_header("authorization", "Bearer the-token")
+        .match_body(Matcher::Json(json!({ "data": {
+            "status": "to-review",
+            "last_editor_comment": "please",
+        }})))
+        .with_body(r#"{"data": {"id": "the-collection", "status": "to-review"}}"#)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .create();
+
+        let writer = writer();
+        writer.delete_record("rec-2", 200).unwrap();
+        writer.request_review(Some("please")).unwrap();
+        delete_m.expect(1).assert();
+        review_m.expect(1).assert();
+    }
+
+    #[test]
+    fn test_unauthorized() {
+        viaduct_reqwest::use_reqwest_backend();
+        let m = mock(
+            "DELETE",
+            "/v1/buckets/main-workspace/collections/the-collection/records/rec-3",
+        )
+        .with_body(r#"{"code": 401, "errno": 104, "error": "Unauthorized"}"#)
+        .with_status(401)
+        .with_header("content-type", "application/json")
+        .create();
+
+        let err = writer().delete_record("rec-3", 100).unwrap_err();
+        assert!(matches!(err, RemoteSettingsError::ResponseError(msg) if msg.starts_with("401")));
+        m.expect(1).assert();
+    }
+
+    #[test]
+    fn test_update_conflict() {
+        viaduct_reqwest::use_reqwest_backend();
+        let m = mock(
+            "PUT",
+            "/v1/buckets/main-workspace/collections/the-collection/records/rec-4",
+        )
+        .match_header("if-none-match", "*")
+        .with_body(r#"{"code": 412, "errno": 114, "error": "Precondition Failed"}"#)
+        .with_status(412)
+        .with_header("content-type", "application/json")
+        .create();
+
+        // The record was created by someone else in the meantime.
+        let err = writer()
+            .update_record("rec-4", fields(json!({ "title": "gif" })), None)
+            .unwrap_err();
+        assert!(matches!(err, RemoteSettingsError::ResponseError(msg) if msg.starts_with("412")));
+        m.expect(1).assert();
+    }
+}
@@ -89,6 +89,7 @@ impl SuggestStore {
                 server_url: None,
                 bucket_name: None,
                 collection_name: REMOTE_SETTINGS_COLLECTION.into(),
+                filter_context: None,
             }
         }))?;
         Ok(Self {
@@ -416,6 +417,7 @@ mod tests {
             server_url: Some(mockito::server_url()),
             bucket_name: None,
             collection_name: "quicksuggest".into(),
+            filter_context: None,
         };
 
         let store = SuggestStore::new_inner(
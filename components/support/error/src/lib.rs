@@ -38,6 +38,9 @@ pub use error_support_macros::handle_error;
 mod handling;
 pub use handling::{convert_log_report_error, ErrorHandling, ErrorReporting, GetErrorHandling};
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
 /// XXX - Most of this is now considered deprecated - only FxA uses it, and
 /// should be replaced with the facilities in the `handling` module.
 
@@ -0,0 +1,133 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Helpers for component test suites, to check that the types we expose survive
+//! the trip across the FFI and through JSON without losing any fields.
+//!
+//! This is only available with the `testing` feature, which components should
+//! only enable in their `[dev-dependencies]`.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+use uniffi::FfiConverter;
+
+/// Lower a value to its FFI type and lift it back, the way it crosses the FFI
+/// as an argument or return value, then write it in to a buffer and read it
+/// back, the way it crosses inside a record, optional or sequence. Both results
+/// must be equal to the original value, and reading must consume the whole
+/// buffer.
+///
+/// `UT` is the tag of the crate which defines the type: call this as
+/// `assert_ffi_round_trip::<crate::UniFfiTag, _>(value)`.
+pub fn assert_ffi_round_trip<UT, T>(value: T)
+where
+    T: FfiConverter<UT> + Clone + PartialEq + Debug,
+{
+    let lifted =
+        T::try_lift(T::lower(value.clone())).expect("the lowered value could not be lifted");
+    assert_eq!(value, lifted, "the value changed when lowered and lifted");
+
+    let mut written = Vec::new();
+    T::write(value.clone(), &mut written);
+    let mut buf = written.as_slice();
+    let read = T::try_read(&mut buf).expect("the written value could not be read");
+    assert!(
+        buf.is_empty(),
+        "{} bytes were left over after reading the value",
+        buf.len()
+    );
+    assert_eq!(value, read, "the value changed when written and read");
+}
+
+/// Deserialize `json` as a `T`, serialize it again, and check that every field
+/// in the original JSON is still present with the same value.
+///
+/// Fields which are `null` in the original may be missing in the result, and
+/// the result may contain extra fields, e.g. defaults: this is checking for
+/// fields being dropped, e.g. by a missing `rename`.
+pub fn assert_json_round_trip<T>(json: Value)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value: T = serde_json::from_value(json.clone()).expect("could not deserialize the JSON");
+    let result = serde_json::to_value(value).expect("could not serialize the value");
+    assert_json_contains(&json, &result, "$");
+}
+
+fn assert_json_contains(expected: &Value, observed: &Value, path: &str) {
+    match (expected, observed) {
+        (Value::Object(expected), Value::Object(observed)) => {
+            for (key, value) in expected {
+                let path = format!("{path}.{key}");
+                match observed.get(key) {
+                    Some(observed) => assert_json_contains(value, observed, &path),
+                    None if value.is_null() => (),
+                    None => panic!("{path} was dropped in the round trip"),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(observed)) => {
+            assert_eq!(
+                expected.len(),
+                observed.len(),
+                "{path} changed length in the round trip"
+            );
+            for (i, (e, o)) in expected.iter().zip(observed).enumerate() {
+                assert_json_contains(e, o, &format!("{path}[{i}]"));
+            }
+        }
+        _ => assert_eq!(expected, observed, "{path} changed in the round trip"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_derive::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Good {
+        user_facing_name: String,
+        reference_branch: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Lossy {
+        #[serde(alias = "userFacingName")]
+        user_facing_name: String,
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        assert_json_round_trip::<Good>(json!({
+            "userFacingName": "An experiment",
+            "referenceBranch": "control",
+        }));
+        assert_json_round_trip::<Good>(json!({
+            "userFacingName": "An experiment",
+            "referenceBranch": null,
+        }));
+        assert_json_round_trip::<Good>(json!({
+            "userFacingName": "An experiment",
+        }));
+    }
+
+    #[test]
+    #[should_panic(expected = "$.userFacingName was dropped in the round trip")]
+    fn test_json_round_trip_dropped_field() {
+        assert_json_round_trip::<Lossy>(json!({
+            "userFacingName": "An experiment",
+        }));
+    }
+
+    #[test]
+    fn test_ffi_round_trip() {
+        assert_ffi_round_trip::<crate::UniFfiTag, _>(Some("a string".to_string()));
+        assert_ffi_round_trip::<crate::UniFfiTag, _>(None::<String>);
+        assert_ffi_round_trip::<crate::UniFfiTag, _>(vec![Some(1i64), None, Some(-1)]);
+    }
+}
@@ -214,6 +214,16 @@ impl Guid {
                 .all(|b| (b' '..=b'~').contains(&b) && b != b',')
     }
 
+    /// Returns true for Guids that could identify a record in any of our stores.
+    ///
+    /// This is the same check as `is_valid_for_sync_server`, and is what the
+    /// stores check before adding or updating a record. Places is stricter about
+    /// the guids it creates: see `is_valid_for_places`.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.is_valid_for_sync_server()
+    }
+
     /// Returns true for Guids that are valid places guids, and false for all others.
     pub fn is_valid_for_places(&self) -> bool {
         self.len() == 12 && self.bytes().all(Guid::is_valid_places_byte)
@@ -427,6 +437,18 @@ mod test {
         assert!(!Guid::empty().is_valid_for_sync_server()); // empty isn't valid remotely.
     }
 
+    #[test]
+    fn test_valid() {
+        assert!(Guid::from("aaaabbbbcccc").is_valid());
+        assert!(Guid::from("{8b6a5d2c-41a3-4a7e-9e3c-1f0c7e0b2a3d}").is_valid());
+        assert!(Guid::from("a".repeat(64)).is_valid());
+        assert!(!Guid::empty().is_valid());
+        assert!(!Guid::from("a".repeat(65)).is_valid()); // too long
+        assert!(!Guid::from("aaaa,bbbb").is_valid()); // commas aren't allowed
+        assert!(!Guid::from("aaaa\nbbbb").is_valid()); // nor are control characters
+        assert!(!Guid::from("aaaa\u{e9}bbbb").is_valid()); // nor is anything outside of ASCII
+    }
+
     #[allow(clippy::cmp_owned)] // See clippy note below.
     #[test]
     fn test_comparison() {
@@ -22,9 +22,19 @@ License, v. 2.0. If a copy of the MPL was not distributed with this
 ///
 ///  See `PlacesDb::begin_interrupt_scope()` and `PlacesApi::new_connection()` for an example of
 ///  how this works.
+///
+/// The registered handles are also used by `interrupt_all()`, which lets the app cancel the
+/// in-flight work of every component at once (for example, when it's backgrounded) without
+/// tracking each component's handle itself.  Unlike `shutdown()`, this doesn't stop new work from
+/// starting.
+///
+/// All of these functions are thread-safe, and are typically called from a different thread than
+/// the one doing the work being interrupted.  Interrupted operations fail with
+/// `interrupt_support::Interrupted`, which each component converts to the `Interrupted` variant
+/// of its public error.
 use crate::Interruptee;
 use parking_lot::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Weak;
 
 use crate::SqlInterruptHandle;
@@ -33,6 +43,10 @@ use crate::SqlInterruptHandle;
 // variable.  It's just a flag so we don't need stronger synchronization guarentees.
 static IN_SHUTDOWN: AtomicBool = AtomicBool::new(false);
 
+// Counter that we increment on each `interrupt_all()` call, so that `GlobalInterruptee` can tell
+// if it's been interrupted.  As above, Ordering::Relaxed is fine since it's basically a flag.
+static INTERRUPT_ALL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 // `SqlInterruptHandle` instances to interrupt when we shutdown
 lazy_static::lazy_static! {
    static ref REGISTERED_INTERRUPTS: Mutex<Vec<Weak<dyn AsRef<SqlInterruptHandle> + Send + Sync>>> = Mutex::new(Vec::new());
@@ -41,6 +55,18 @@ lazy_static::lazy_static! {
 /// Initiate shutdown mode
 pub fn shutdown() {
     IN_SHUTDOWN.store(true, Ordering::Relaxed);
+    interrupt_registered();
+}
+
+/// Interrupt the current operations of every registered `SqlInterruptHandle`
+///
+/// Operations started after this returns aren't affected.
+pub fn interrupt_all() {
+    INTERRUPT_ALL_COUNTER.fetch_add(1, Ordering::Relaxed);
+    interrupt_registered();
+}
+
+fn interrupt_registered() {
     for weak in REGISTERED_INTERRUPTS.lock().iter() {
         if let Some(interrupt) = weak.upgrade() {
             interrupt.as_ref().as_ref().interrupt()
@@ -56,7 +82,7 @@ pub fn in_shutdown() -> bool {
 /// Register a ShutdownInterrupt implementation
 ///
 /// Call this function to ensure that the `SqlInterruptHandle::interrupt()` method will be called
-/// at shutdown.
+/// at shutdown and by `interrupt_all()`.
 pub fn register_interrupt(interrupt: Weak<dyn AsRef<SqlInterruptHandle> + Send + Sync>) {
     // Try to find an existing entry that's been dropped to replace.  This keeps the vector growth
     // in check
@@ -79,3 +105,59 @@ impl Interruptee for ShutdownInterruptee {
         in_shutdown()
     }
 }
+
+// Implements Interruptee by checking if we've entered shutdown mode, or `interrupt_all()` was
+// called since this was created.  Use this for operations which don't run inside an
+// `SqlInterruptScope`, like a sync.
+pub struct GlobalInterruptee {
+    start_value: usize,
+}
+
+impl GlobalInterruptee {
+    pub fn new() -> Self {
+        Self {
+            start_value: INTERRUPT_ALL_COUNTER.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for GlobalInterruptee {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interruptee for GlobalInterruptee {
+    #[inline]
+    fn was_interrupted(&self) -> bool {
+        in_shutdown() || INTERRUPT_ALL_COUNTER.load(Ordering::Relaxed) != self.start_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    // This is a single test, because `interrupt_all()` affects everything in the process.
+    #[test]
+    fn test_interrupt_all() {
+        let conn = Connection::open_in_memory().unwrap();
+        let handle = Arc::new(SqlInterruptHandle::new(&conn));
+        register_interrupt(Arc::<SqlInterruptHandle>::downgrade(&handle));
+        let scope = handle.begin_interrupt_scope().unwrap();
+        let interruptee = GlobalInterruptee::new();
+        assert!(!scope.was_interrupted());
+        assert!(!interruptee.was_interrupted());
+
+        interrupt_all();
+        // The work in progress is interrupted...
+        assert!(scope.was_interrupted());
+        assert!(interruptee.was_interrupted());
+        // ...but work started afterwards isn't.
+        assert!(!handle.begin_interrupt_scope().unwrap().was_interrupted());
+        assert!(!GlobalInterruptee::new().was_interrupted());
+        assert!(!in_shutdown());
+    }
+}
@@ -60,6 +60,14 @@ impl SqlInterruptHandle {
     }
 }
 
+// Lets components that don't have a wrapper type register the handle itself with
+// `register_interrupt()`.
+impl AsRef<SqlInterruptHandle> for SqlInterruptHandle {
+    fn as_ref(&self) -> &SqlInterruptHandle {
+        self
+    }
+}
+
 impl fmt::Debug for SqlInterruptHandle {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SqlInterruptHandle")
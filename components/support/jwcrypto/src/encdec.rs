@@ -126,6 +126,35 @@ impl<E: From<EncryptorDecryptorError>> EncryptorDecryptor<E> {
     }
 }
 
+/// Provides the key used to construct an `EncryptorDecryptor`
+///
+/// Components which encrypt local data can hold one of these, so the consumer registers a key
+/// provider once rather than passing the key to every call.  The key is fetched each time it's
+/// needed, which allows the consumer to retrieve it just-in-time (for example, behind a biometric
+/// unlock).  `None` means the key isn't currently available.
+pub trait KeyManager: Send + Sync {
+    fn get_key(&self) -> Option<String>;
+}
+
+/// KeyManager for a key that's already known
+///
+/// This is the default provider, for consumers who already have the key as a string.
+pub struct StaticKeyManager {
+    key: String,
+}
+
+impl StaticKeyManager {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+}
+
+impl KeyManager for StaticKeyManager {
+    fn get_key(&self) -> Option<String> {
+        Some(self.key.clone())
+    }
+}
+
 trait ToEncryptorDecryptorResult<T, E> {
     fn to_encdec_result(self, description: &str) -> Result<T, E>;
 }
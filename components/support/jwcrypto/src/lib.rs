@@ -29,7 +29,7 @@ pub mod ec;
 mod encdec;
 mod error;
 
-pub use encdec::EncryptorDecryptor;
+pub use encdec::{EncryptorDecryptor, KeyManager, StaticKeyManager};
 
 /// Specifies the mode, algorithm and keys of the encryption operation.
 pub enum EncryptionParameters<'a> {
@@ -4,8 +4,8 @@
 
 use std::path::PathBuf;
 
-use chrono::Utc;
-use clap::{Args, Parser, Subcommand};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(
@@ -34,7 +34,14 @@ pub(crate) enum CliCommand {
     /// Send a complete JSON file to the Nimbus SDK and apply it immediately.
     ApplyFile {
         /// The filename to be loaded into the SDK.
-        file: PathBuf,
+        #[arg(required_unless_present = "clipboard")]
+        file: Option<PathBuf>,
+
+        /// Read the recipes from the clipboard instead of a file.
+        ///
+        /// This is handy for pasting a recipe copied from the Experimenter web console.
+        #[arg(long, default_value = "false", conflicts_with = "file")]
+        clipboard: bool,
 
         /// Keeps existing enrollments and experiments before enrolling.
         ///
@@ -108,6 +115,22 @@ pub(crate) enum CliCommand {
         #[arg(long, default_value = "false")]
         no_validate: bool,
 
+        /// An optional JSON or YAML file of targeting attributes to override,
+        /// e.g. `{ "region": "DE" }`.
+        ///
+        /// These are given to the SDK before the experiment's targeting is evaluated,
+        /// so this changes which experiments the app enrolls in. This is for testing only.
+        #[arg(long, value_name = "CONTEXT_FILE", requires = "preserve_targeting")]
+        context: Option<PathBuf>,
+
+        /// Unenroll after this date, given as `yyyy-mm-dd` (midnight UTC) or as an
+        /// RFC 3339 timestamp.
+        ///
+        /// Nothing happens on the device at this time: the expiry is recorded, and
+        /// the `prune` command must be run after it has passed to actually unenroll.
+        #[arg(long, value_name = "DATE", value_parser = parse_until)]
+        until: Option<DateTime<Utc>>,
+
         #[command(flatten)]
         manifest: ManifestArgs,
     },
@@ -162,8 +185,14 @@ pub(crate) enum CliCommand {
         /// fetch --output file.json preview/my-experiment my-rollout
         ///
         /// Cannot be used with the server option: use `fetch-list` instead.
+        ///
+        /// A slug without a server uses the last server used for this app and channel.
         #[arg(value_name = "RECIPE")]
         recipes: Vec<String>,
+
+        /// Don't use or remember the last server used for this app and channel.
+        #[arg(long, default_value = "false")]
+        no_remember: bool,
     },
 
     /// Fetch a list of experiments and put it in a file.
@@ -192,6 +221,23 @@ pub(crate) enum CliCommand {
         list: ExperimentListArgs,
     },
 
+    /// List the features in the manifest, with their descriptions.
+    ///
+    /// This is useful for finding the feature ids to use with `test-feature`.
+    ListFeatures {
+        /// An optional substring to filter the feature ids by.
+        filter: Option<String>,
+
+        /// Print the features as JSON.
+        ///
+        /// Automated tools should use this, since the output is predictable.
+        #[arg(long, default_value = "false")]
+        json: bool,
+
+        #[command(flatten)]
+        manifest: ManifestArgs,
+    },
+
     /// Print the state of the Nimbus database to logs.
     ///
     /// This causes a restart of the app.
@@ -212,9 +258,47 @@ pub(crate) enum CliCommand {
         no_clobber: bool,
     },
 
+    /// Print the feature manifest, as resolved from the app, version or ref.
+    ///
+    /// This shows where the manifest was loaded from, and the features and
+    /// variables it contains.
+    PrintManifest {
+        /// Print the manifest as JSON.
+        ///
+        /// Automated tools should use this, since the output is predictable.
+        #[arg(long, default_value = "false")]
+        json: bool,
+
+        /// An optional file to print the manifest as JSON.
+        #[arg(short, long, value_name = "OUTPUT_FILE")]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        manifest: ManifestArgs,
+    },
+
+    /// Unenroll if any enrollment made with `enroll --until` is past due.
+    ///
+    /// The app can't unenroll from a single experiment, so this unenrolls from all
+    /// experiments and rollouts. If nothing is past due, the app isn't touched.
+    Prune,
+
     /// Reset the app back to its just installed state
     ResetApp,
 
+    /// Print the state of the Nimbus database as JSON.
+    ///
+    /// This reads a copy of the database from the device, so the app isn't restarted.
+    /// It shows the active experiments and rollouts with their branches, and the
+    /// experiments which the app isn't enrolled in.
+    ///
+    /// On Android, this only works for debuggable builds of the app.
+    State {
+        /// An optional file to print the output.
+        #[arg(short, long, value_name = "OUTPUT_FILE")]
+        output: Option<PathBuf>,
+    },
+
     /// Follow the logs for the given app.
     TailLogs,
 
@@ -262,7 +346,43 @@ pub(crate) enum CliCommand {
 
         #[command(flatten)]
         manifest: ManifestArgs,
+
+        /// The format of the validation report.
+        ///
+        /// Automated tools should use `json`, since the output is predictable.
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ValidateOutput::Text)]
+        output: ValidateOutput,
     },
+
+    /// Validate every recipe from a server or file against a feature manifest
+    ///
+    /// Each recipe is validated on its own, so that every broken recipe is
+    /// reported with its errors, rather than stopping at the first one.
+    #[command(alias = "bisect")]
+    ValidateList {
+        #[command(flatten)]
+        list: ExperimentListArgs,
+
+        #[command(flatten)]
+        manifest: ManifestArgs,
+
+        /// The format of the validation report.
+        ///
+        /// Automated tools should use `json`, since the output is predictable.
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ValidateOutput::Text)]
+        output: ValidateOutput,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ValidateOutput {
+    /// A table of each branch and feature, with any error.
+    Table,
+    /// A line per branch and feature, with any error.
+    #[default]
+    Text,
+    /// A JSON object, with a result per branch and feature.
+    Json,
 }
 
 #[derive(Args, Clone, Debug, Default)]
@@ -324,6 +444,11 @@ pub(crate) struct ExperimentArgs {
     #[arg(long, value_name = "EXPERIMENTS_FILE")]
     pub(crate) file: Option<PathBuf>,
 
+    /// Get the experiment from JSON in the clipboard, e.g. a recipe copied from
+    /// the Experimenter web console.
+    #[arg(long, default_value = "false", conflicts_with = "file")]
+    pub(crate) clipboard: bool,
+
     /// Use remote settings to fetch the experiment recipe.
     ///
     /// By default, the file is fetched from the v6 api of experimenter.
@@ -350,6 +475,8 @@ pub(crate) struct ExperimentListArgs {
 #[derive(Args, Clone, Debug, Default)]
 pub(crate) struct ExperimentListSourceArgs {
     /// A server slug e.g. preview, release, stage, stage/preview
+    ///
+    /// If omitted, the last server used for this app and channel is used.
     #[arg(default_value = "")]
     pub(crate) server: String,
 
@@ -365,6 +492,10 @@ pub(crate) struct ExperimentListSourceArgs {
     /// so this is considerably slower and longer than Remote Settings.
     #[arg(long, default_value = "false")]
     pub(crate) use_api: bool,
+
+    /// Don't use or remember the last server used for this app and channel.
+    #[arg(long, default_value = "false")]
+    pub(crate) no_remember: bool,
 }
 
 #[derive(Args, Clone, Debug, Default)]
@@ -415,3 +546,12 @@ fn validate_date(s: &str) -> Result<String, String> {
         _ => Err("Date string must be yyyy-mm-dd".to_string()),
     }
 }
+
+fn parse_until(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()));
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|_| "Date must be yyyy-mm-dd or an RFC 3339 timestamp".to_string())
+}
@@ -3,20 +3,28 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    output::deeplink,
+    cli::ValidateOutput,
+    config,
+    output::{
+        db_state::{NimbusDbState, NIMBUS_DB_FILE},
+        deeplink,
+        validate::{ListValidationReport, ValidationReport},
+    },
     protocol::StartAppProtocol,
     sources::ManifestSource,
-    value_utils::{
-        prepare_experiment, prepare_rollout, try_find_branches_from_experiment,
-        try_find_features_from_branch, CliUtils,
-    },
+    state,
+    value_utils::{self, prepare_experiment, prepare_rollout, CliUtils},
     AppCommand, AppOpenArgs, ExperimentListSource, ExperimentSource, LaunchableApp, NimbusApp,
 };
 use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use console::Term;
 use nimbus_fml::intermediate_representation::FeatureManifest;
 use serde_json::{json, Value};
-use std::{path::PathBuf, process::Command};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 pub(crate) fn process_cmd(cmd: &AppCommand) -> Result<bool> {
     let status = match cmd {
@@ -41,6 +49,8 @@ pub(crate) fn process_cmd(cmd: &AppCommand) -> Result<bool> {
             preserve_targeting,
             preserve_bucketing,
             preserve_nimbus_db,
+            context,
+            until,
             open,
             ..
         } => app.enroll(
@@ -51,6 +61,8 @@ pub(crate) fn process_cmd(cmd: &AppCommand) -> Result<bool> {
             preserve_targeting,
             preserve_bucketing,
             preserve_nimbus_db,
+            context.as_ref(),
+            until.as_ref(),
             open,
         )?,
         AppCommand::ExtractFeatures {
@@ -74,19 +86,38 @@ pub(crate) fn process_cmd(cmd: &AppCommand) -> Result<bool> {
         AppCommand::Info { experiment, output } => experiment.print_info(output.as_ref())?,
         AppCommand::Kill { app } => app.kill_app()?,
         AppCommand::List { list, .. } => list.print_list()?,
+        AppCommand::ListFeatures {
+            manifest,
+            filter,
+            json,
+        } => manifest.list_features(filter.as_deref(), *json)?,
         AppCommand::LogState { app, open } => app.log_state(open)?,
         AppCommand::NoOp => true,
         AppCommand::Open {
             app, open: args, ..
         } => app.open(args)?,
+        AppCommand::PrintManifest {
+            manifest,
+            json,
+            output,
+        } => manifest.print_manifest(*json, output.as_ref())?,
+        AppCommand::Prune { app, params } => app.prune(params)?,
         AppCommand::Reset { app } => app.reset_app()?,
+        AppCommand::State { app, output } => app.print_state(output.as_ref())?,
         AppCommand::TailLogs { app } => app.tail_logs()?,
         AppCommand::Unenroll { app, open } => app.unenroll_all(open)?,
         AppCommand::ValidateExperiment {
             params,
             manifest,
             experiment,
-        } => params.validate_experiment(manifest, experiment)?,
+            output,
+        } => params.validate_experiment(manifest, experiment, output)?,
+        AppCommand::ValidateList {
+            params,
+            manifest,
+            list,
+            output,
+        } => params.validate_experiment_list(manifest, list, output)?,
     };
 
     Ok(status)
@@ -103,13 +134,13 @@ fn prompt(term: &Term, command: &str) -> Result<()> {
     Ok(())
 }
 
-fn output_ok(term: &Term, title: &str) -> Result<()> {
+pub(crate) fn output_ok(term: &Term, title: &str) -> Result<()> {
     let style = term.style().green();
     term.write_line(&format!("✅ {}", style.apply_to(title)))?;
     Ok(())
 }
 
-fn output_err(term: &Term, title: &str, detail: &str) -> Result<()> {
+pub(crate) fn output_err(term: &Term, title: &str, detail: &str) -> Result<()> {
     let style = term.style().red();
     term.write_line(&format!("❎ {}: {detail}", style.apply_to(title),))?;
     Ok(())
@@ -174,6 +205,36 @@ impl LaunchableApp {
         self.start_app(protocol, open)
     }
 
+    fn prune(&self, params: &NimbusApp) -> Result<bool> {
+        let term = Term::stdout();
+        let (app, channel) = (params.app_name(), params.channel());
+        let expired = state::expired_enrollments(
+            config::state_file(),
+            app.as_deref(),
+            channel.as_deref(),
+            &Utc::now(),
+        );
+        if expired.is_empty() {
+            prompt(&term, "# No enrollments are past due")?;
+            return Ok(true);
+        }
+        for e in &expired {
+            prompt(
+                &term,
+                &format!(
+                    "# Unenrolling from '{0}', which expired at {1}",
+                    e.slug, e.until
+                ),
+            )?;
+        }
+        self.kill_app()?;
+        let success = self.unenroll_all(&Default::default())?;
+        if success {
+            state::forget_enrollments(config::state_file(), app.as_deref(), channel.as_deref())?;
+        }
+        Ok(success)
+    }
+
     fn reset_app(&self) -> Result<bool> {
         Ok(match self {
             Self::Android { package_name, .. } => self
@@ -284,6 +345,79 @@ impl LaunchableApp {
         }
     }
 
+    fn print_state(&self, output: Option<&PathBuf>) -> Result<bool> {
+        // The database is opened from a copy, so the app can keep running, and nothing we
+        // do to it can change the app's state.
+        let dir = std::env::temp_dir().join(format!("nimbus-cli-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("db"))?;
+        let state = self
+            .copy_nimbus_db(&dir.join("db").join(NIMBUS_DB_FILE))
+            .and_then(|_| NimbusDbState::try_from_dir(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+        value_utils::write_to_file_or_print(output, &state?)?;
+        Ok(true)
+    }
+
+    fn copy_nimbus_db(&self, file: &Path) -> Result<()> {
+        // The JSON goes to stdout, so the commands go to stderr.
+        let term = Term::stderr();
+        match self {
+            Self::Android { package_name, .. } => {
+                let db = format!("nimbus_data/db/{NIMBUS_DB_FILE}");
+                prompt(
+                    &term,
+                    &format!(
+                        "adb exec-out run-as {package_name} cat {db} > {}",
+                        file.display()
+                    ),
+                )?;
+                let output = self
+                    .exe()?
+                    .args(["exec-out", "run-as", package_name, "cat", &db])
+                    .output()?;
+                // run-as reports its errors on stdout.
+                if !output.status.success()
+                    || output.stdout.is_empty()
+                    || output.stdout.starts_with(b"run-as:")
+                {
+                    bail!(
+                        "Can't read the Nimbus database of {package_name}: {}. Only debuggable builds of the app can be read, after the app has started for the first time",
+                        String::from_utf8_lossy(&output.stdout).trim()
+                    );
+                }
+                std::fs::write(file, output.stdout)?;
+            }
+            Self::Ios { .. } => {
+                let mut containers = vec![self.ios_app_container("data")?];
+                let groups = self.ios_app_container("groups")?;
+                containers.extend(
+                    groups
+                        .lines()
+                        .filter_map(|line| line.split_once('\t'))
+                        .map(|(_, dir)| dir.to_string()),
+                );
+                let db = containers
+                    .iter()
+                    .filter(|dir| !dir.is_empty())
+                    .find_map(|dir| {
+                        glob::glob(&format!("{dir}/**/nimbus*/db/{NIMBUS_DB_FILE}"))
+                            .ok()?
+                            .flatten()
+                            .next()
+                    })
+                    .ok_or_else(|| {
+                        anyhow::Error::msg(
+                            "The Nimbus database is not available before the app is started for the first time",
+                        )
+                    })?;
+                prompt(&term, &format!("cp {} {}", db.display(), file.display()))?;
+                std::fs::copy(db, file)?;
+            }
+        }
+        Ok(())
+    }
+
     fn log_state(&self, open: &AppOpenArgs) -> Result<bool> {
         let protocol = StartAppProtocol {
             log_state: true,
@@ -302,10 +436,29 @@ impl LaunchableApp {
         preserve_targeting: &bool,
         preserve_bucketing: &bool,
         preserve_nimbus_db: &bool,
+        context: Option<&PathBuf>,
+        until: Option<&DateTime<Utc>>,
         open: &AppOpenArgs,
     ) -> Result<bool> {
         let term = Term::stdout();
 
+        let context = match context {
+            Some(file) => {
+                let value: Value = value_utils::read_from_file(file)?;
+                if !value.is_object() {
+                    bail!("The context file should contain an object of targeting attributes");
+                }
+                let style = term.style().yellow().bold();
+                term.write_line(&format!(
+                    "{} targeting attributes are being overridden from {}. This changes which experiments are enrolled, and is for testing only.",
+                    style.apply_to("Warning:"),
+                    file.display(),
+                ))?;
+                Some(value)
+            }
+            _ => None,
+        };
+
         let experiment = Value::try_from(experiment)?;
         let slug = experiment.get_str("slug")?.to_string();
 
@@ -338,8 +491,27 @@ impl LaunchableApp {
             reset_db: !preserve_nimbus_db,
             experiments: Some(&payload),
             log_state: true,
+            context: context.as_ref(),
         };
-        self.start_app(protocol, open)
+        if !self.start_app(protocol, open)? {
+            return Ok(false);
+        }
+
+        if let Some(until) = until {
+            prompt(
+                &term,
+                &format!("# Enrolled until {until}: run `nimbus-cli prune` after this to unenroll"),
+            )?;
+        }
+        state::record_enrollment(
+            config::state_file(),
+            params.app_name().as_deref(),
+            params.channel().as_deref(),
+            &slug,
+            until,
+            *preserve_nimbus_db,
+        )?;
+        Ok(true)
     }
 
     fn apply_list(
@@ -354,6 +526,7 @@ impl LaunchableApp {
             reset_db: !preserve_nimbus_db,
             experiments: Some(&value),
             log_state: true,
+            ..Default::default()
         };
         self.start_app(protocol, open)
     }
@@ -459,9 +632,10 @@ impl LaunchableApp {
                 reset_db,
                 experiments,
                 log_state,
+                context,
             } = app_protocol;
 
-            if log_state || experiments.is_some() || reset_db {
+            if log_state || experiments.is_some() || reset_db || context.is_some() {
                 args.extend(["--esn nimbus-cli".to_string(), "--ei version 1".to_string()]);
             }
 
@@ -472,6 +646,10 @@ impl LaunchableApp {
                 let json = s.to_string().replace('\'', "&apos;");
                 args.push(format!("--es experiments '{}'", json))
             }
+            if let Some(c) = context {
+                let json = c.to_string().replace('\'', "&apos;");
+                args.push(format!("--es context '{}'", json))
+            }
             if log_state {
                 args.push("--ez log-state true".to_string());
             };
@@ -514,9 +692,10 @@ impl LaunchableApp {
                     log_state,
                     experiments,
                     reset_db,
+                    context,
                 } = app_protocol;
 
-                if log_state || experiments.is_some() || reset_db {
+                if log_state || experiments.is_some() || reset_db || context.is_some() {
                     args.extend([
                         "--nimbus-cli".to_string(),
                         "--version".to_string(),
@@ -535,6 +714,12 @@ impl LaunchableApp {
                         s.to_string().replace('\'', "&apos;"),
                     ]);
                 }
+                if let Some(c) = context {
+                    args.extend([
+                        "--context".to_string(),
+                        c.to_string().replace('\'', "&apos;"),
+                    ]);
+                }
                 if log_state {
                     args.push("--log-state".to_string());
                 }
@@ -558,51 +743,61 @@ fn logcat_args<'a>() -> Vec<&'a str> {
     vec!["logcat", "-b", "main"]
 }
 
+fn load_manifest(term: &Term, manifest_source: &ManifestSource) -> Result<FeatureManifest> {
+    match TryInto::<FeatureManifest>::try_into(manifest_source) {
+        Ok(manifest) => Ok(manifest),
+        Err(err) => {
+            output_err(
+                term,
+                &format!("Problem with manifest from {manifest_source}"),
+                &err.to_string(),
+            )?;
+            bail!("Error when loading and validating the manifest");
+        }
+    }
+}
+
 impl NimbusApp {
     fn validate_experiment(
         &self,
         manifest_source: &ManifestSource,
         experiment: &ExperimentSource,
+        output: &ValidateOutput,
     ) -> Result<bool> {
         let term = Term::stdout();
         let value: Value = experiment.try_into()?;
 
-        let manifest = match TryInto::<FeatureManifest>::try_into(manifest_source) {
-            Ok(manifest) => {
-                output_ok(&term, &format!("Loaded manifest from {manifest_source}"))?;
-                manifest
-            }
-            Err(err) => {
-                output_err(
-                    &term,
-                    &format!("Problem with manifest from {manifest_source}"),
-                    &err.to_string(),
-                )?;
-                bail!("Error when loading and validating the manifest");
-            }
-        };
+        let manifest = load_manifest(&term, manifest_source)?;
 
-        let mut is_valid = true;
-        for b in try_find_branches_from_experiment(&value)? {
-            let branch = b.get_str("slug")?;
-            for f in try_find_features_from_branch(&b)? {
-                let id = f.get_str("featureId")?;
-                let value = f
-                    .get("value")
-                    .unwrap_or_else(|| panic!("Branch {branch} feature {id} has no value"));
-                let res = manifest.validate_feature_config(id, value.clone());
-                match res {
-                    Ok(_) => output_ok(&term, &format!("{branch: <15} {id}"))?,
-                    Err(err) => {
-                        is_valid = false;
-                        output_err(&term, &format!("{branch: <15} {id}"), &err.to_string())?
-                    }
-                }
-            }
-        }
-        if !is_valid {
+        let report = ValidationReport::try_new(&value, &manifest_source.to_string(), &manifest)?;
+        report.print(output)?;
+        if !report.valid {
             bail!("At least one error detected");
         }
         Ok(true)
     }
+
+    fn validate_experiment_list(
+        &self,
+        manifest_source: &ManifestSource,
+        list: &ExperimentListSource,
+        output: &ValidateOutput,
+    ) -> Result<bool> {
+        let term = Term::stdout();
+        let value: Value = list.try_into()?;
+        let recipes = value_utils::try_extract_data_list(&value)?;
+
+        let manifest = load_manifest(&term, manifest_source)?;
+
+        let report = ListValidationReport::new(&recipes, &manifest_source.to_string(), &manifest);
+        report.print(output)?;
+        if !report.valid {
+            bail!(
+                "{} of {} recipes are invalid",
+                report.failures.len(),
+                report.checked
+            );
+        }
+        Ok(true)
+    }
 }
@@ -194,6 +194,21 @@ pub(crate) fn api_v6_stage_server() -> String {
         .unwrap_or_else(|_| "https://stage.experimenter.nonprod.dataops.mozgcp.net".to_string())
 }
 
+/// The file used to remember state between commands, e.g. the last server used.
+///
+/// This is disabled for tests, so they don't depend on the commands previously run.
+pub(crate) fn state_file() -> Option<PathBuf> {
+    if cfg!(test) {
+        return None;
+    }
+    match std::env::var("NIMBUS_CLI_STATE_FILE") {
+        Ok(s) => Some(PathBuf::from(s)),
+        _ => std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".nimbus-cli").join("state.json")),
+    }
+}
+
 pub(crate) fn manifest_cache_dir() -> Option<PathBuf> {
     match std::env::var("NIMBUS_MANIFEST_CACHE") {
         Ok(s) => {
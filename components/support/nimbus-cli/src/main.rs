@@ -9,37 +9,45 @@ mod feature_utils;
 mod output;
 mod protocol;
 mod sources;
+mod state;
 mod updater;
 mod value_utils;
 
 use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use clap::Parser;
-use cli::{Cli, CliCommand, ExperimentArgs, OpenArgs};
+use cli::{Cli, CliCommand, ExperimentArgs, OpenArgs, ValidateOutput};
 use sources::{ExperimentListSource, ExperimentSource, ManifestSource};
-use std::{ffi::OsString, path::PathBuf};
+use std::path::PathBuf;
 
 pub(crate) static USER_AGENT: &str =
     concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
 fn main() -> Result<()> {
-    let cmds = get_commands_from_cli(std::env::args_os())?;
+    let cli = Cli::try_parse_from(std::env::args_os())?;
+    let cmds = get_commands(&cli)?;
     for c in cmds {
         let success = cmd::process_cmd(&c)?;
         if !success {
             bail!("Failed");
         }
     }
+    // Only once the commands have succeeded, so a mistyped server isn't remembered.
+    state::remember_server(config::state_file(), &cli)?;
     updater::check_for_update();
     Ok(())
 }
 
+#[cfg(test)]
 fn get_commands_from_cli<I, T>(args: I) -> Result<Vec<AppCommand>>
 where
     I: IntoIterator<Item = T>,
-    T: Into<OsString> + Clone,
+    T: Into<std::ffi::OsString> + Clone,
 {
-    let cli = Cli::try_parse_from(args)?;
+    get_commands(&Cli::try_parse_from(args)?)
+}
 
+fn get_commands(cli: &Cli) -> Result<Vec<AppCommand>> {
     let mut commands: Vec<AppCommand> = Default::default();
 
     // We do this here to ensure that all the command line is valid
@@ -47,21 +55,21 @@ where
     // as the cli has expanded, we've changed when we need `--app`
     // and `--channel`. We catch those types of errors early by doing this
     // here.
-    let main_command = AppCommand::try_from(&cli)?;
+    let main_command = AppCommand::try_from(cli)?;
 
     // Validating the command line args. Most of this should be done with clap,
     // but for everything else there's:
     cli.command.check_valid()?;
 
     // Validating experiments against manifests
-    commands.push(AppCommand::try_validate(&cli)?);
+    commands.push(AppCommand::try_validate(cli)?);
 
     if cli.command.should_kill() {
-        let app = LaunchableApp::try_from(&cli)?;
+        let app = LaunchableApp::try_from(cli)?;
         commands.push(AppCommand::Kill { app });
     }
     if cli.command.should_reset() {
-        let app = LaunchableApp::try_from(&cli)?;
+        let app = LaunchableApp::try_from(cli)?;
         commands.push(AppCommand::Reset { app });
     }
     commands.push(main_command);
@@ -146,6 +154,8 @@ enum AppCommand {
         preserve_targeting: bool,
         preserve_bucketing: bool,
         preserve_nimbus_db: bool,
+        context: Option<PathBuf>,
+        until: Option<DateTime<Utc>>,
         open: AppOpenArgs,
     },
 
@@ -179,6 +189,12 @@ enum AppCommand {
         list: ExperimentListSource,
     },
 
+    ListFeatures {
+        manifest: ManifestSource,
+        filter: Option<String>,
+        json: bool,
+    },
+
     LogState {
         app: LaunchableApp,
         open: AppOpenArgs,
@@ -192,10 +208,26 @@ enum AppCommand {
         open: AppOpenArgs,
     },
 
+    PrintManifest {
+        manifest: ManifestSource,
+        json: bool,
+        output: Option<PathBuf>,
+    },
+
+    Prune {
+        app: LaunchableApp,
+        params: NimbusApp,
+    },
+
     Reset {
         app: LaunchableApp,
     },
 
+    State {
+        app: LaunchableApp,
+        output: Option<PathBuf>,
+    },
+
     TailLogs {
         app: LaunchableApp,
     },
@@ -209,6 +241,14 @@ enum AppCommand {
         params: NimbusApp,
         manifest: ManifestSource,
         experiment: ExperimentSource,
+        output: ValidateOutput,
+    },
+
+    ValidateList {
+        params: NimbusApp,
+        manifest: ManifestSource,
+        list: ExperimentListSource,
+        output: ValidateOutput,
     },
 }
 
@@ -232,15 +272,31 @@ impl AppCommand {
                     params,
                     experiment,
                     manifest,
+                    output: Default::default(),
                 }
             }
-            CliCommand::Validate { manifest, .. } => {
+            CliCommand::Validate {
+                manifest, output, ..
+            } => {
                 let experiment = ExperimentSource::try_from(cli)?;
                 let manifest = ManifestSource::try_from(&params, manifest)?;
                 AppCommand::ValidateExperiment {
                     params,
                     experiment,
                     manifest,
+                    output: *output,
+                }
+            }
+            CliCommand::ValidateList {
+                manifest, output, ..
+            } => {
+                let list = ExperimentListSource::try_from(cli)?;
+                let manifest = ManifestSource::try_from(&params, manifest)?;
+                AppCommand::ValidateList {
+                    params,
+                    manifest,
+                    list,
+                    output: *output,
                 }
             }
             _ => Self::NoOp,
@@ -256,11 +312,15 @@ impl TryFrom<&Cli> for AppCommand {
         Ok(match cli.command.clone() {
             CliCommand::ApplyFile {
                 file,
+                clipboard,
                 preserve_nimbus_db,
                 open,
             } => {
                 let app = LaunchableApp::try_from(cli)?;
-                let list = ExperimentListSource::try_from(file.as_path())?;
+                let list = match file {
+                    Some(file) if !clipboard => ExperimentListSource::try_from(file.as_path())?,
+                    _ => ExperimentListSource::FromClipboard,
+                };
                 AppCommand::ApplyFile {
                     app,
                     open: open.into(),
@@ -290,6 +350,8 @@ impl TryFrom<&Cli> for AppCommand {
                 preserve_targeting,
                 preserve_bucketing,
                 preserve_nimbus_db,
+                context,
+                until,
                 experiment,
                 open,
                 ..
@@ -316,6 +378,8 @@ impl TryFrom<&Cli> for AppCommand {
                     preserve_targeting,
                     preserve_bucketing,
                     preserve_nimbus_db,
+                    context,
+                    until,
                     open: open.into(),
                 }
             }
@@ -353,6 +417,18 @@ impl TryFrom<&Cli> for AppCommand {
                 let list = ExperimentListSource::try_from(cli)?;
                 AppCommand::List { list }
             }
+            CliCommand::ListFeatures {
+                filter,
+                json,
+                manifest,
+            } => {
+                let manifest = ManifestSource::try_from(&params, &manifest)?;
+                AppCommand::ListFeatures {
+                    manifest,
+                    filter,
+                    json,
+                }
+            }
             CliCommand::LogState { open } => {
                 let app = LaunchableApp::try_from(cli)?;
                 AppCommand::LogState {
@@ -367,6 +443,26 @@ impl TryFrom<&Cli> for AppCommand {
                     open: open.into(),
                 }
             }
+            CliCommand::PrintManifest {
+                json,
+                output,
+                manifest,
+            } => {
+                let manifest = ManifestSource::try_from(&params, &manifest)?;
+                AppCommand::PrintManifest {
+                    manifest,
+                    json,
+                    output,
+                }
+            }
+            CliCommand::Prune => {
+                let app = LaunchableApp::try_from(cli)?;
+                AppCommand::Prune { app, params }
+            }
+            CliCommand::State { output } => {
+                let app = LaunchableApp::try_from(cli)?;
+                AppCommand::State { app, output }
+            }
             CliCommand::TailLogs => {
                 let app = LaunchableApp::try_from(cli)?;
                 AppCommand::TailLogs { app }
@@ -389,6 +485,8 @@ impl TryFrom<&Cli> for AppCommand {
                     preserve_targeting: false,
                     preserve_bucketing: false,
                     preserve_nimbus_db: false,
+                    context: None,
+                    until: None,
                 }
             }
             CliCommand::Unenroll { open } => {
@@ -775,10 +873,160 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
+                open: Default::default(),
+            },
+        ];
+        assert_eq!(expected, observed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_enroll_with_context() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "enroll",
+            "my-experiment",
+            "--branch",
+            "my-branch",
+            "--preserve-targeting",
+            "--context",
+            "./context.json",
+            "--no-validate",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::Kill { app: fenix() },
+            AppCommand::Enroll {
+                app: fenix(),
+                params: fenix_params(),
+                experiment: experiment("my-experiment"),
+                rollouts: Default::default(),
+                branch: "my-branch".to_string(),
+                preserve_targeting: true,
+                preserve_bucketing: false,
+                preserve_nimbus_db: false,
+                context: Some(PathBuf::from("./context.json")),
+                until: None,
                 open: Default::default(),
             },
         ];
         assert_eq!(expected, observed);
+
+        // Overriding the targeting context is meaningless if targeting is being replaced.
+        assert!(get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "enroll",
+            "my-experiment",
+            "--branch",
+            "my-branch",
+            "--context",
+            "./context.json",
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enroll_until() -> Result<()> {
+        let until = |date| {
+            get_commands_from_cli([
+                "nimbus-cli",
+                "--app",
+                "fenix",
+                "--channel",
+                "developer",
+                "enroll",
+                "my-experiment",
+                "--branch",
+                "my-branch",
+                "--no-validate",
+                "--until",
+                date,
+            ])
+            .map(|cmds| match cmds.last() {
+                Some(AppCommand::Enroll { until, .. }) => until.map(|d| d.to_rfc3339()),
+                _ => None,
+            })
+        };
+        assert_eq!(
+            Some("2023-07-01T00:00:00+00:00".to_string()),
+            until("2023-07-01")?
+        );
+        assert_eq!(
+            Some("2023-07-01T10:30:00+00:00".to_string()),
+            until("2023-07-01T12:30:00+02:00")?
+        );
+        assert!(until("next week").is_err());
+
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "prune",
+        ])?;
+        // Prune only stops the app if there's something to unenroll.
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::Prune {
+                app: fenix(),
+                params: fenix_params(),
+            },
+        ];
+        assert_eq!(expected, observed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_state() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "state",
+        ])?;
+        // The database is read from the device, so the app isn't stopped.
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::State {
+                app: fenix(),
+                output: None,
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "state",
+            "--output",
+            "./state.json",
+        ])?;
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::State {
+                app: fenix(),
+                output: Some("./state.json".into()),
+            },
+        ];
+        assert_eq!(expected, observed);
         Ok(())
     }
 
@@ -811,6 +1059,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -838,6 +1088,7 @@ mod unit_tests {
                 params: fenix_params(),
                 manifest: fenix_manifest(),
                 experiment: experiment("my-experiment"),
+                output: Default::default(),
             },
             AppCommand::Kill { app: fenix() },
             AppCommand::Reset { app: fenix() },
@@ -850,6 +1101,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -886,6 +1139,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: with_deeplink("host/path?key=value"),
             },
         ];
@@ -926,6 +1181,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: with_passthrough(&[
                     "--start-profiler",
                     "./profile.file",
@@ -966,6 +1223,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: with_pbcopy(),
             },
         ];
@@ -973,6 +1232,101 @@ mod unit_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_enroll_from_clipboard() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "enroll",
+            "my-experiment",
+            "--branch",
+            "my-branch",
+            "--no-validate",
+            "--clipboard",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::Kill { app: fenix() },
+            AppCommand::Enroll {
+                app: fenix(),
+                params: fenix_params(),
+                experiment: ExperimentSource::FromList {
+                    slug: "my-experiment".to_string(),
+                    list: ExperimentListSource::FromClipboard,
+                },
+                rollouts: Default::default(),
+                branch: "my-branch".to_string(),
+                preserve_targeting: false,
+                preserve_bucketing: false,
+                preserve_nimbus_db: false,
+                context: None,
+                until: None,
+                open: Default::default(),
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        assert!(get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "enroll",
+            "my-experiment",
+            "--branch",
+            "my-branch",
+            "--clipboard",
+            "--file",
+            "recipes.json",
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_file_from_clipboard() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "apply-file",
+            "--clipboard",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::Kill { app: fenix() },
+            AppCommand::ApplyFile {
+                app: fenix(),
+                open: Default::default(),
+                list: ExperimentListSource::FromClipboard,
+                preserve_nimbus_db: false,
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        // One of a file or --clipboard is needed, but not both.
+        for args in [&[][..], &["recipes.json", "--clipboard"][..]] {
+            let cli = [
+                "nimbus-cli",
+                "--app",
+                "fenix",
+                "--channel",
+                "developer",
+                "apply-file",
+            ];
+            assert!(get_commands_from_cli(cli.iter().chain(args)).is_err());
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_validate() -> Result<()> {
         let observed = get_commands_from_cli([
@@ -990,6 +1344,7 @@ mod unit_tests {
                 params: fenix_params(),
                 manifest: fenix_manifest(),
                 experiment: experiment("my-experiment"),
+                output: Default::default(),
             },
             AppCommand::NoOp,
         ];
@@ -1013,6 +1368,7 @@ mod unit_tests {
                 params: fenix_params(),
                 manifest: fenix_manifest_with_ref("releases_v114"),
                 experiment: experiment("my-experiment"),
+                output: Default::default(),
             },
             AppCommand::NoOp,
         ];
@@ -1036,6 +1392,31 @@ mod unit_tests {
                 params: fenix_params(),
                 manifest: fenix_manifest_with_ref("my-tag"),
                 experiment: experiment("my-experiment"),
+                output: Default::default(),
+            },
+            AppCommand::NoOp,
+        ];
+        assert_eq!(expected, observed);
+
+        // With a JSON report.
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "validate",
+            "my-experiment",
+            "--output",
+            "json",
+        ])?;
+
+        let expected = vec![
+            AppCommand::ValidateExperiment {
+                params: fenix_params(),
+                manifest: fenix_manifest(),
+                experiment: experiment("my-experiment"),
+                output: ValidateOutput::Json,
             },
             AppCommand::NoOp,
         ];
@@ -1060,11 +1441,167 @@ mod unit_tests {
                 },
                 manifest: manifest_from_file("./manifest.fml.yaml"),
                 experiment: experiment("my-experiment"),
+                output: Default::default(),
+            },
+            AppCommand::NoOp,
+        ];
+        assert_eq!(expected, observed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_list() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "validate-list",
+            "preview",
+        ])?;
+
+        let expected = vec![
+            AppCommand::ValidateList {
+                params: fenix_params(),
+                manifest: fenix_manifest(),
+                list: for_app(
+                    "fenix",
+                    ExperimentListSource::FromRemoteSettings {
+                        endpoint: config::rs_production_server(),
+                        is_preview: true,
+                    },
+                ),
+                output: Default::default(),
             },
             AppCommand::NoOp,
         ];
         assert_eq!(expected, observed);
 
+        // From a file, with the `bisect` alias and a JSON report.
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "bisect",
+            "--file",
+            "./recipes.json",
+            "--output",
+            "json",
+        ])?;
+
+        let expected = vec![
+            AppCommand::ValidateList {
+                params: fenix_params(),
+                manifest: fenix_manifest(),
+                list: for_app(
+                    "fenix",
+                    ExperimentListSource::FromFile {
+                        file: "./recipes.json".into(),
+                    },
+                ),
+                output: ValidateOutput::Json,
+            },
+            AppCommand::NoOp,
+        ];
+        assert_eq!(expected, observed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_manifest() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "print-manifest",
+            "--version",
+            "114",
+            "--json",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::PrintManifest {
+                manifest: fenix_manifest_with_ref("releases_v114"),
+                json: true,
+                output: None,
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--channel",
+            "developer",
+            "print-manifest",
+            "--manifest",
+            "./manifest.fml.yaml",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::PrintManifest {
+                manifest: manifest_from_file("./manifest.fml.yaml"),
+                json: false,
+                output: None,
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_features() -> Result<()> {
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--app",
+            "fenix",
+            "--channel",
+            "developer",
+            "list-features",
+            "--version",
+            "114",
+            "--json",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::ListFeatures {
+                manifest: fenix_manifest_with_ref("releases_v114"),
+                filter: None,
+                json: true,
+            },
+        ];
+        assert_eq!(expected, observed);
+
+        let observed = get_commands_from_cli([
+            "nimbus-cli",
+            "--channel",
+            "developer",
+            "list-features",
+            "messaging",
+            "--manifest",
+            "./manifest.fml.yaml",
+        ])?;
+
+        let expected = vec![
+            AppCommand::NoOp,
+            AppCommand::ListFeatures {
+                manifest: manifest_from_file("./manifest.fml.yaml"),
+                filter: Some("messaging".to_string()),
+                json: false,
+            },
+        ];
+        assert_eq!(expected, observed);
+
         Ok(())
     }
 
@@ -1090,6 +1627,7 @@ mod unit_tests {
                     "my-feature",
                     &["./my-branch.json", "./my-treatment.json"],
                 ),
+                output: Default::default(),
             },
             AppCommand::Kill { app: fenix() },
             AppCommand::Enroll {
@@ -1104,6 +1642,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -1132,6 +1672,7 @@ mod unit_tests {
                     "my-feature",
                     &["./my-branch.json", "./my-treatment.json"],
                 ),
+                output: Default::default(),
             },
             AppCommand::Kill { app: fenix() },
             AppCommand::Enroll {
@@ -1146,6 +1687,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -1174,6 +1717,7 @@ mod unit_tests {
                     "my-feature",
                     &["./my-branch.json", "./my-treatment.json"],
                 ),
+                output: Default::default(),
             },
             AppCommand::Kill { app: fenix() },
             AppCommand::Enroll {
@@ -1188,6 +1732,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -1216,6 +1762,7 @@ mod unit_tests {
                     "my-feature",
                     &["./my-branch.json", "./my-treatment.json"],
                 ),
+                output: Default::default(),
             },
             AppCommand::Kill { app: fenix() },
             AppCommand::Enroll {
@@ -1230,6 +1777,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: Default::default(),
             },
         ];
@@ -1265,6 +1814,8 @@ mod unit_tests {
                 preserve_targeting: false,
                 preserve_bucketing: false,
                 preserve_nimbus_db: false,
+                context: None,
+                until: None,
                 open: with_deeplink("host/path?key=value"),
             },
         ];
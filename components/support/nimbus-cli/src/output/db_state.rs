@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use nimbus::{
+    persistence::{Database, StoreId},
+    Experiment,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// The file the Nimbus SDK keeps its database in, inside `<data dir>/db`.
+pub(crate) const NIMBUS_DB_FILE: &str = "data.safe.bin";
+
+/// What's in a copy of an app's Nimbus database, for the `state` command.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct NimbusDbState {
+    nimbus_id: Option<String>,
+    user_opt_in: Option<bool>,
+    experiments: Vec<ActiveEnrollment>,
+    rollouts: Vec<ActiveEnrollment>,
+    /// The experiments and rollouts the app knows about, but isn't enrolled in.
+    not_enrolled: Vec<InactiveEnrollment>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ActiveEnrollment {
+    slug: String,
+    branch: String,
+    user_facing_name: String,
+    feature_ids: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct InactiveEnrollment {
+    slug: String,
+    /// One of `NotEnrolled`, `Disqualified`, `WasEnrolled` or `Error`.
+    status: String,
+    branch: Option<String>,
+    reason: Option<String>,
+}
+
+impl NimbusDbState {
+    /// Reads the state from the Nimbus database in `dir`. This should be a copy, because
+    /// opening the database may migrate it.
+    pub(crate) fn try_from_dir(dir: &Path) -> Result<Self> {
+        let db = Database::new(dir)?;
+        let reader = db.read()?;
+        let meta = db.get_store(StoreId::Meta);
+        let experiments: Vec<Experiment> = db
+            .get_store(StoreId::Experiments)
+            .try_collect_all(&reader)?;
+        // The SDK doesn't export its enrollment type, so these are read as JSON.
+        let enrollments: Vec<Value> = db
+            .get_store(StoreId::Enrollments)
+            .try_collect_all(&reader)?;
+        Ok(Self::new(
+            meta.get(&reader, "nimbus-id")?,
+            meta.get(&reader, "user-opt-in")?,
+            experiments,
+            enrollments,
+        ))
+    }
+
+    fn new(
+        nimbus_id: Option<String>,
+        user_opt_in: Option<bool>,
+        experiments: Vec<Experiment>,
+        enrollments: Vec<Value>,
+    ) -> Self {
+        let experiments: HashMap<_, _> = experiments
+            .into_iter()
+            .map(|exp| (exp.slug.clone(), exp))
+            .collect();
+        let mut state = Self {
+            nimbus_id,
+            user_opt_in,
+            ..Default::default()
+        };
+        for enrollment in enrollments {
+            let slug = enrollment["slug"].as_str().unwrap_or_default().to_string();
+            // Enrollment statuses are serialized as `{ "<status>": { ...fields } }`.
+            let (status, fields) = match enrollment["status"].as_object() {
+                Some(map) if map.len() == 1 => map.iter().next().unwrap(),
+                _ => continue,
+            };
+            let branch = fields["branch"].as_str().map(str::to_string);
+            match (status.as_str(), branch) {
+                ("Enrolled", Some(branch)) => {
+                    let exp = experiments.get(&slug);
+                    let active = ActiveEnrollment {
+                        slug,
+                        branch,
+                        user_facing_name: exp
+                            .map(|e| e.user_facing_name.clone())
+                            .unwrap_or_default(),
+                        feature_ids: exp.map(|e| e.feature_ids.clone()).unwrap_or_default(),
+                    };
+                    if exp.map_or(false, |e| e.is_rollout) {
+                        state.rollouts.push(active);
+                    } else {
+                        state.experiments.push(active);
+                    }
+                }
+                (status, branch) => state.not_enrolled.push(InactiveEnrollment {
+                    slug,
+                    status: status.to_string(),
+                    branch,
+                    reason: match &fields["reason"] {
+                        Value::Null => None,
+                        Value::String(s) => Some(s.clone()),
+                        v => Some(v.to_string()),
+                    },
+                }),
+            }
+        }
+        state.experiments.sort_by(|a, b| a.slug.cmp(&b.slug));
+        state.rollouts.sort_by(|a, b| a.slug.cmp(&b.slug));
+        state.not_enrolled.sort_by(|a, b| a.slug.cmp(&b.slug));
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn experiment(slug: &str, is_rollout: bool) -> Experiment {
+        Experiment {
+            slug: slug.to_string(),
+            user_facing_name: format!("{slug} name"),
+            feature_ids: vec!["feature".to_string()],
+            is_rollout,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_db_state() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("nimbus-cli-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        {
+            let db = Database::new(&dir)?;
+            let mut writer = db.write()?;
+            let meta = db.get_store(StoreId::Meta);
+            meta.put(&mut writer, "nimbus-id", &"my-nimbus-id".to_string())?;
+            meta.put(&mut writer, "user-opt-in", &true)?;
+            let experiments = db.get_store(StoreId::Experiments);
+            for exp in [
+                experiment("an-experiment", false),
+                experiment("a-rollout", true),
+                experiment("not-this-one", false),
+            ] {
+                experiments.put(&mut writer, &exp.slug, &exp)?;
+            }
+            let enrollments = db.get_store(StoreId::Enrollments);
+            for enrollment in [
+                json!({ "slug": "an-experiment", "status": { "Enrolled": {
+                    "enrollment_id": "ab03a5c8-4f3b-4b6b-9f8e-5a5a7d2c0e11",
+                    "reason": "Qualified",
+                    "branch": "treatment",
+                }}}),
+                json!({ "slug": "a-rollout", "status": { "Enrolled": {
+                    "enrollment_id": "1d5c5a1e-2f64-4d7e-8c1b-0f0e7a6c9b22",
+                    "reason": "Qualified",
+                    "branch": "control",
+                }}}),
+                json!({ "slug": "not-this-one", "status": { "NotEnrolled": {
+                    "reason": "NotTargeted",
+                }}}),
+            ] {
+                let slug = enrollment["slug"].as_str().unwrap().to_string();
+                enrollments.put(&mut writer, &slug, &enrollment)?;
+            }
+            writer.commit()?;
+        }
+
+        let state = NimbusDbState::try_from_dir(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            state?,
+            NimbusDbState {
+                nimbus_id: Some("my-nimbus-id".to_string()),
+                user_opt_in: Some(true),
+                experiments: vec![ActiveEnrollment {
+                    slug: "an-experiment".to_string(),
+                    branch: "treatment".to_string(),
+                    user_facing_name: "an-experiment name".to_string(),
+                    feature_ids: vec!["feature".to_string()],
+                }],
+                rollouts: vec![ActiveEnrollment {
+                    slug: "a-rollout".to_string(),
+                    branch: "control".to_string(),
+                    user_facing_name: "a-rollout name".to_string(),
+                    feature_ids: vec!["feature".to_string()],
+                }],
+                not_enrolled: vec![InactiveEnrollment {
+                    slug: "not-this-one".to_string(),
+                    status: "NotEnrolled".to_string(),
+                    branch: None,
+                    reason: Some("NotTargeted".to_string()),
+                }],
+            }
+        );
+        Ok(())
+    }
+}
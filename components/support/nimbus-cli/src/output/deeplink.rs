@@ -100,8 +100,9 @@ pub(crate) fn longform_deeplink_url(
         reset_db,
         experiments,
         log_state,
+        context,
     } = app_protocol;
-    if !reset_db && experiments.is_none() && !log_state {
+    if !reset_db && experiments.is_none() && !log_state && context.is_none() {
         return Ok(deeplink.to_string());
     }
 
@@ -111,6 +112,11 @@ pub(crate) fn longform_deeplink_url(
         let string = percent_encoding::utf8_percent_encode(&json, QUERY).to_string();
         parts.push(format!("--experiments={string}"));
     }
+    if let Some(v) = context {
+        let json = serde_json::to_string(v)?;
+        let string = percent_encoding::utf8_percent_encode(&json, QUERY).to_string();
+        parts.push(format!("--context={string}"));
+    }
 
     if reset_db {
         parts.push("--reset-db".to_string());
@@ -142,6 +148,7 @@ mod unit_tests {
             reset_db: false,
             experiments: None,
             log_state: false,
+            context: None,
         };
         assert_eq!(
             "host".to_string(),
@@ -160,6 +167,7 @@ mod unit_tests {
             reset_db: true,
             experiments: None,
             log_state: false,
+            context: None,
         };
         assert_eq!(
             "host?--nimbus-cli&--reset-db".to_string(),
@@ -179,6 +187,7 @@ mod unit_tests {
             reset_db: false,
             experiments: None,
             log_state: true,
+            context: None,
         };
         assert_eq!(
             "host?--nimbus-cli&--log-state".to_string(),
@@ -199,6 +208,7 @@ mod unit_tests {
             reset_db: false,
             experiments: Some(&v),
             log_state: false,
+            context: None,
         };
         assert_eq!(
             "host?--nimbus-cli&--experiments=%7B%22data%22%3A[]%7D".to_string(),
@@ -211,4 +221,19 @@ mod unit_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_url_context() -> Result<()> {
+        let v = json!({"region": "DE"});
+        let p = StartAppProtocol {
+            context: Some(&v),
+            ..Default::default()
+        };
+        assert_eq!(
+            "host?--nimbus-cli&--context=%7B%22region%22%3A%22DE%22%7D".to_string(),
+            longform_deeplink_url("host", p)?
+        );
+
+        Ok(())
+    }
 }
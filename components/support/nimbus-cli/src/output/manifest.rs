@@ -0,0 +1,238 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use console::Term;
+use nimbus_fml::intermediate_representation::{FeatureDef, FeatureManifest, PropDef};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{sources::ManifestSource, value_utils};
+
+#[derive(Serialize, Debug)]
+pub(crate) struct ManifestInfo {
+    pub(crate) source: String,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub(crate) git_ref: Option<String>,
+    pub(crate) channel: String,
+    pub(crate) features: Vec<FeatureInfo>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct FeatureInfo {
+    pub(crate) id: String,
+    pub(crate) description: String,
+    pub(crate) variables: Vec<VariableInfo>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct VariableInfo {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) typ: String,
+    pub(crate) description: String,
+    pub(crate) default: Value,
+}
+
+impl From<&FeatureDef> for FeatureInfo {
+    fn from(feature: &FeatureDef) -> Self {
+        Self {
+            id: feature.name(),
+            description: feature.doc(),
+            variables: feature.props().iter().map(VariableInfo::from).collect(),
+        }
+    }
+}
+
+impl From<&PropDef> for VariableInfo {
+    fn from(prop: &PropDef) -> Self {
+        Self {
+            name: prop.name(),
+            typ: prop.typ().to_string(),
+            description: prop.doc(),
+            default: prop.default(),
+        }
+    }
+}
+
+impl ManifestSource {
+    pub(crate) fn load_manifest(&self) -> Result<FeatureManifest> {
+        TryInto::<FeatureManifest>::try_into(self).with_context(|| match self {
+            Self::FromGithub {
+                github_repo, ref_, ..
+            } => format!(
+                "Could not load the manifest from {github_repo} at '{ref_}': check that this branch, tag or commit exists"
+            ),
+            Self::FromFile { manifest_file, .. } => {
+                format!("Could not load the manifest from {manifest_file}")
+            }
+        })
+    }
+
+    pub(crate) fn manifest_info(&self, fm: &FeatureManifest) -> Result<ManifestInfo> {
+        let mut features: Vec<FeatureInfo> = fm
+            .iter_all_feature_defs()
+            .map(|(_, f)| FeatureInfo::from(f))
+            .collect();
+        features.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(ManifestInfo {
+            source: self.source_url()?,
+            git_ref: self.git_ref().map(str::to_string),
+            channel: self.channel().to_string(),
+            features,
+        })
+    }
+
+    pub(crate) fn list_features(&self, filter: Option<&str>, json: bool) -> Result<bool> {
+        let fm = self.load_manifest()?;
+        let info = self.manifest_info(&fm)?;
+        let features = filter_features(info.features, filter);
+        if json {
+            value_utils::write_to_file_or_print(None::<&Path>, &features)?;
+            return Ok(true);
+        }
+
+        let term = Term::stdout();
+        let style = term.style().bold();
+        for feature in &features {
+            term.write_line(&format!(
+                "{: <30} {}",
+                style.apply_to(&feature.id),
+                feature.description
+            ))?;
+        }
+
+        Ok(true)
+    }
+
+    pub(crate) fn print_manifest<P>(&self, json: bool, output: Option<P>) -> Result<bool>
+    where
+        P: AsRef<Path>,
+    {
+        let fm = self.load_manifest()?;
+        let info = self.manifest_info(&fm)?;
+        if json || output.is_some() {
+            value_utils::write_to_file_or_print(output, &info)?;
+            return Ok(true);
+        }
+
+        let term = Term::stdout();
+        let t_style = term.style().italic();
+        let d_style = term.style().bold().cyan();
+        let line = |title: &str, detail: &str| {
+            _ = term.write_line(&format!(
+                "{: <11} {}",
+                t_style.apply_to(title),
+                d_style.apply_to(detail)
+            ));
+        };
+
+        line("Source", &info.source);
+        if let Some(git_ref) = &info.git_ref {
+            line("Ref", git_ref);
+        }
+        line("Channel", &info.channel);
+        line("Features", &info.features.len().to_string());
+
+        let f_style = term.style().bold();
+        for feature in &info.features {
+            term.write_line("")?;
+            term.write_line(&format!(
+                "{} {}",
+                f_style.apply_to(&feature.id),
+                feature.description
+            ))?;
+            for v in &feature.variables {
+                term.write_line(&format!("  {: <30} {}", v.name, v.typ))?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Keep only the features whose ids contain the filter, ignoring case.
+fn filter_features(features: Vec<FeatureInfo>, filter: Option<&str>) -> Vec<FeatureInfo> {
+    match filter {
+        Some(filter) => {
+            let filter = filter.to_lowercase();
+            features
+                .into_iter()
+                .filter(|f| f.id.to_lowercase().contains(&filter))
+                .collect()
+        }
+        _ => features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(filename: &str) -> ManifestSource {
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = dir.join("../nimbus-fml/fixtures/fe").join(filename);
+        ManifestSource::FromFile {
+            channel: "developer".to_string(),
+            manifest_file: file.to_str().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_manifest_info() -> Result<()> {
+        let manifest = fixture("browser.yaml");
+        let fm = manifest.load_manifest()?;
+        let info = manifest.manifest_info(&fm)?;
+
+        assert!(info.source.ends_with("browser.yaml"));
+        assert_eq!(None, info.git_ref);
+        assert_eq!("developer", info.channel);
+
+        let ids: Vec<_> = info.features.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(
+            vec!["homescreen", "nimbus-validation", "search-term-groups"],
+            ids
+        );
+
+        let homescreen = &info.features[0];
+        assert_eq!(1, homescreen.variables.len());
+        assert_eq!("sections-enabled", homescreen.variables[0].name);
+        assert_eq!(
+            "Map<HomeScreenSection, Boolean>",
+            homescreen.variables[0].typ
+        );
+
+        let json = serde_json::to_value(&info)?;
+        assert!(json.get("ref").is_none());
+        assert!(json["features"][0]["variables"][0].get("type").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_features() -> Result<()> {
+        let manifest = fixture("browser.yaml");
+        let fm = manifest.load_manifest()?;
+        let ids = |filter| -> Result<Vec<String>> {
+            let info = manifest.manifest_info(&fm)?;
+            Ok(filter_features(info.features, filter)
+                .into_iter()
+                .map(|f| f.id)
+                .collect())
+        };
+
+        assert_eq!(3, ids(None)?.len());
+        assert_eq!(vec!["search-term-groups"], ids(Some("TERM"))?);
+        assert_eq!(
+            vec!["nimbus-validation", "search-term-groups"],
+            ids(Some("-"))?
+        );
+        assert!(ids(Some("no-such-feature"))?.is_empty());
+
+        Ok(())
+    }
+}
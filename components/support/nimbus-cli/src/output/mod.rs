@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+pub(crate) mod db_state;
 pub(crate) mod deeplink;
 mod features;
 mod fetch;
 pub(crate) mod info;
+mod manifest;
+pub(crate) mod validate;
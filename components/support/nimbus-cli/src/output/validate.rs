@@ -0,0 +1,313 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use console::Term;
+use nimbus_fml::intermediate_representation::FeatureManifest;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    cli::ValidateOutput,
+    cmd::{output_err, output_ok},
+    value_utils::{try_find_branches_from_experiment, try_find_features_from_branch, CliUtils},
+};
+
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ValidationReport {
+    pub(crate) experiment: String,
+    pub(crate) manifest: String,
+    pub(crate) valid: bool,
+    pub(crate) results: Vec<FeatureValidation>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct FeatureValidation {
+    pub(crate) branch: String,
+    pub(crate) feature_id: String,
+    pub(crate) valid: bool,
+    pub(crate) errors: Vec<String>,
+}
+
+impl ValidationReport {
+    pub(crate) fn try_new(
+        experiment: &Value,
+        manifest_name: &str,
+        manifest: &FeatureManifest,
+    ) -> Result<Self> {
+        let mut results = Vec::new();
+        for b in try_find_branches_from_experiment(experiment)? {
+            let branch = b.get_str("slug")?;
+            for f in try_find_features_from_branch(&b)? {
+                let id = f.get_str("featureId")?;
+                let value = f.get("value").ok_or_else(|| {
+                    anyhow::format_err!("Branch {branch} feature {id} has no value")
+                })?;
+                let errors = match manifest.validate_feature_config(id, value.clone()) {
+                    Ok(_) => Default::default(),
+                    Err(err) => vec![err.to_string()],
+                };
+                results.push(FeatureValidation {
+                    branch: branch.to_string(),
+                    feature_id: id.to_string(),
+                    valid: errors.is_empty(),
+                    errors,
+                });
+            }
+        }
+
+        Ok(Self {
+            experiment: experiment.get_str("slug")?.to_string(),
+            manifest: manifest_name.to_string(),
+            valid: results.iter().all(|r| r.valid),
+            results,
+        })
+    }
+
+    pub(crate) fn print(&self, output: &ValidateOutput) -> Result<()> {
+        let term = Term::stdout();
+        match output {
+            ValidateOutput::Json => {
+                term.write_line(&serde_json::to_string_pretty(self)?)?;
+            }
+            ValidateOutput::Text => {
+                output_ok(&term, &format!("Loaded manifest from {}", self.manifest))?;
+                for r in &self.results {
+                    let title = format!("{: <15} {}", r.branch, r.feature_id);
+                    if r.valid {
+                        output_ok(&term, &title)?;
+                    } else {
+                        output_err(&term, &title, &r.errors.join("; "))?;
+                    }
+                }
+            }
+            ValidateOutput::Table => {
+                let style = term.style().italic().underlined();
+                term.write_line(&format!(
+                    "{branch: <21}|{feature: <31}|{result: <9}|{errors}",
+                    branch = style.apply_to("Branch"),
+                    feature = style.apply_to(" Feature"),
+                    result = style.apply_to(" Result"),
+                    errors = style.apply_to(" Errors"),
+                ))?;
+                for r in &self.results {
+                    term.write_line(&format!(
+                        " {branch: <20}| {feature: <30}| {result: <8}| {errors}",
+                        branch = r.branch,
+                        feature = r.feature_id,
+                        result = if r.valid { "valid" } else { "invalid" },
+                        errors = r.errors.join("; "),
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of validating every recipe in a list, keeping only the ones
+/// which failed.
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ListValidationReport {
+    pub(crate) manifest: String,
+    pub(crate) valid: bool,
+    pub(crate) checked: usize,
+    pub(crate) failures: Vec<RecipeFailure>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct RecipeFailure {
+    pub(crate) slug: String,
+    pub(crate) errors: Vec<String>,
+}
+
+impl ListValidationReport {
+    pub(crate) fn new(recipes: &[Value], manifest_name: &str, manifest: &FeatureManifest) -> Self {
+        let failures: Vec<_> = recipes
+            .iter()
+            .filter_map(|recipe| {
+                let errors = match ValidationReport::try_new(recipe, manifest_name, manifest) {
+                    Ok(report) => report
+                        .results
+                        .into_iter()
+                        .filter(|r| !r.valid)
+                        .map(|r| format!("{} {}: {}", r.branch, r.feature_id, r.errors.join("; ")))
+                        .collect(),
+                    // A recipe we can't read is as broken as one which doesn't validate.
+                    Err(err) => vec![err.to_string()],
+                };
+                if errors.is_empty() {
+                    return None;
+                }
+                let slug = recipe.get_str("slug").unwrap_or("<no slug>");
+                Some(RecipeFailure {
+                    slug: slug.to_string(),
+                    errors,
+                })
+            })
+            .collect();
+
+        Self {
+            manifest: manifest_name.to_string(),
+            valid: failures.is_empty(),
+            checked: recipes.len(),
+            failures,
+        }
+    }
+
+    pub(crate) fn print(&self, output: &ValidateOutput) -> Result<()> {
+        let term = Term::stdout();
+        match output {
+            ValidateOutput::Json => {
+                term.write_line(&serde_json::to_string_pretty(self)?)?;
+            }
+            ValidateOutput::Text => {
+                output_ok(&term, &format!("Loaded manifest from {}", self.manifest))?;
+                for f in &self.failures {
+                    output_err(&term, &f.slug, &f.errors.join("; "))?;
+                }
+                let summary = format!(
+                    "{} of {} recipes are valid",
+                    self.checked - self.failures.len(),
+                    self.checked
+                );
+                if self.valid {
+                    output_ok(&term, &summary)?;
+                } else {
+                    output_err(&term, &summary, "see above for the invalid recipes")?;
+                }
+            }
+            ValidateOutput::Table => {
+                let style = term.style().italic().underlined();
+                term.write_line(&format!(
+                    "{slug: <66}|{errors}",
+                    slug = style.apply_to("Experiment slug"),
+                    errors = style.apply_to(" Errors"),
+                ))?;
+                for f in &self.failures {
+                    for error in &f.errors {
+                        term.write_line(&format!(" {slug: <65}| {error}", slug = f.slug))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::sources::ManifestSource;
+
+    #[test]
+    fn test_validation_report() -> Result<()> {
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = dir.join("../nimbus-fml/fixtures/fe/browser.yaml");
+        let manifest = ManifestSource::FromFile {
+            channel: "developer".to_string(),
+            manifest_file: file.to_str().unwrap().to_string(),
+        };
+        let fm: FeatureManifest = (&manifest).try_into()?;
+
+        let experiment = json!({
+            "slug": "my-experiment",
+            "branches": [
+                {
+                    "slug": "control",
+                    "features": [
+                        { "featureId": "search-term-groups", "value": { "enabled": true } },
+                    ]
+                },
+                {
+                    "slug": "treatment",
+                    "features": [
+                        { "featureId": "search-term-groups", "value": { "enabled": 1 } },
+                        { "featureId": "no-such-feature", "value": {} },
+                    ]
+                },
+            ]
+        });
+
+        let report = ValidationReport::try_new(&experiment, "browser.yaml", &fm)?;
+        assert_eq!("my-experiment", report.experiment);
+        assert!(!report.valid);
+
+        let results: Vec<_> = report
+            .results
+            .iter()
+            .map(|r| (r.branch.as_str(), r.feature_id.as_str(), r.valid))
+            .collect();
+        assert_eq!(
+            vec![
+                ("control", "search-term-groups", true),
+                ("treatment", "search-term-groups", false),
+                ("treatment", "no-such-feature", false),
+            ],
+            results
+        );
+        assert!(report.results[0].errors.is_empty());
+        assert_eq!(1, report.results[1].errors.len());
+
+        let json = serde_json::to_value(&report)?;
+        assert_eq!(
+            json!({
+                "branch": "control",
+                "feature_id": "search-term-groups",
+                "valid": true,
+                "errors": [],
+            }),
+            json["results"][0]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_validation_report() -> Result<()> {
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let file = dir.join("../nimbus-fml/fixtures/fe/browser.yaml");
+        let manifest = ManifestSource::FromFile {
+            channel: "developer".to_string(),
+            manifest_file: file.to_str().unwrap().to_string(),
+        };
+        let fm: FeatureManifest = (&manifest).try_into()?;
+
+        let recipe = |slug: &str, value: Value| {
+            json!({
+                "slug": slug,
+                "branches": [
+                    {
+                        "slug": "control",
+                        "features": [
+                            { "featureId": "search-term-groups", "value": value },
+                        ]
+                    },
+                ]
+            })
+        };
+        let recipes = vec![
+            recipe("valid-1", json!({ "enabled": true })),
+            recipe("invalid", json!({ "enabled": 1 })),
+            json!({ "slug": "no-branches" }),
+            recipe("valid-2", json!({})),
+        ];
+
+        let report = ListValidationReport::new(&recipes, "browser.yaml", &fm);
+        assert!(!report.valid);
+        assert_eq!(4, report.checked);
+        let slugs: Vec<_> = report.failures.iter().map(|f| f.slug.as_str()).collect();
+        assert_eq!(vec!["invalid", "no-branches"], slugs);
+        assert_eq!(1, report.failures[0].errors.len());
+        assert!(report.failures[0].errors[0].starts_with("control search-term-groups: "));
+
+        let report = ListValidationReport::new(&recipes[..1], "browser.yaml", &fm);
+        assert!(report.valid);
+        assert!(report.failures.is_empty());
+
+        Ok(())
+    }
+}
@@ -141,8 +141,17 @@ impl TryFrom<&ExperimentArgs> for ExperimentSource {
             Some(_) if is_urlish => {
                 anyhow::bail!("Cannot load an experiment from a file and a URL at the same time")
             }
+            None if is_urlish && value.clipboard => {
+                anyhow::bail!(
+                    "Cannot load an experiment from the clipboard and a URL at the same time"
+                )
+            }
             None if is_urlish => Self::try_from_url(experiment.as_str())?,
             Some(file) => Self::try_from_file(file, experiment)?,
+            _ if value.clipboard => Self::FromList {
+                slug: experiment.clone(),
+                list: ExperimentListSource::FromClipboard,
+            },
             _ if value.use_rs => Self::try_from_rs(experiment)?,
             _ => Self::try_from_api(experiment.as_str())?,
         };
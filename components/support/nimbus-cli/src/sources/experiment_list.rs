@@ -4,7 +4,7 @@
 
 use crate::{
     cli::{Cli, CliCommand, ExperimentArgs, ExperimentListArgs, ExperimentListSourceArgs},
-    config,
+    config, state,
     value_utils::{self, CliUtils},
     USER_AGENT,
 };
@@ -27,6 +27,7 @@ pub(crate) enum ExperimentListSource {
     FromFile {
         file: PathBuf,
     },
+    FromClipboard,
     FromRemoteSettings {
         endpoint: String,
         is_preview: bool,
@@ -112,19 +113,38 @@ impl TryFrom<&Cli> for ExperimentListSource {
 
     fn try_from(value: &Cli) -> Result<Self> {
         let list = match &value.command {
-            CliCommand::FetchList { list, .. } | CliCommand::List { list } => {
-                ExperimentListSource::try_from(list)?
+            CliCommand::FetchList { list, .. }
+            | CliCommand::List { list }
+            | CliCommand::ValidateList { list, .. } => {
+                let source = state::with_remembered_server(
+                    config::state_file(),
+                    value.app.as_deref(),
+                    value.channel.as_deref(),
+                    &list.source,
+                )?;
+                ExperimentListSource::try_from(&ExperimentListArgs {
+                    source,
+                    ..list.clone()
+                })?
             }
             CliCommand::Fetch {
                 experiment,
                 recipes: slugs,
+                no_remember,
                 ..
             } => {
-                let mut recipes = vec![ExperimentSource::try_from(experiment)?];
-
-                for r in slugs {
+                // Recipes loaded from a file or the clipboard don't come from a server.
+                let no_remember = *no_remember || experiment.file.is_some() || experiment.clipboard;
+                let mut recipes = vec![];
+                for r in std::iter::once(&experiment.experiment).chain(slugs) {
                     let recipe = ExperimentArgs {
-                        experiment: r.clone(),
+                        experiment: state::with_remembered_server_for_slug(
+                            config::state_file(),
+                            value.app.as_deref(),
+                            value.channel.as_deref(),
+                            r,
+                            no_remember,
+                        )?,
                         ..experiment.clone()
                     };
                     recipes.push(ExperimentSource::try_from(&recipe)?);
@@ -166,6 +186,7 @@ impl TryFrom<&ExperimentListArgs> for ExperimentListSource {
                 server: s,
                 file: None,
                 use_api,
+                ..
             } => {
                 if *use_api {
                     Self::try_from_api(s)?
@@ -230,6 +251,7 @@ impl TryFrom<&ExperimentListSource> for Value {
                     server_url: Some(endpoint.clone()),
                     bucket_name: None,
                     collection_name,
+                    filter_context: None,
                 };
                 let client = Client::new(config)?;
 
@@ -238,17 +260,19 @@ impl TryFrom<&ExperimentListSource> for Value {
             }
             ExperimentListSource::FromFile { file } => {
                 let v: Value = value_utils::read_from_file(file)?;
-                if v.is_array() {
-                    serde_json::json!({ "data": v })
-                } else if v.get_array("data").is_ok() {
-                    v
-                } else if v.get_array("branches").is_ok() {
-                    serde_json::json!({ "data": [v] })
-                } else {
-                    bail!(
+                match recipes_to_list(v) {
+                    Some(v) => v,
+                    _ => bail!(
                         "An unrecognized recipes JSON file: {}",
                         file.as_path().to_str().unwrap_or_default()
-                    );
+                    ),
+                }
+            }
+            ExperimentListSource::FromClipboard => {
+                let v = value_utils::read_from_clipboard()?;
+                match recipes_to_list(v) {
+                    Some(v) => v,
+                    _ => bail!("The clipboard doesn't contain a recipe or a list of recipes"),
                 }
             }
             ExperimentListSource::FromApiV6 { endpoint } => {
@@ -288,6 +312,20 @@ impl TryFrom<&ExperimentListSource> for Value {
     }
 }
 
+// A recipes file can contain a list of recipes, a single recipe, or something we've fetched
+// from remote settings, i.e. `{ "data": [] }`.
+fn recipes_to_list(v: Value) -> Option<Value> {
+    if v.is_array() {
+        Some(serde_json::json!({ "data": v }))
+    } else if v.get_array("data").is_ok() {
+        Some(v)
+    } else if v.get_array("branches").is_ok() {
+        Some(serde_json::json!({ "data": [v] }))
+    } else {
+        None
+    }
+}
+
 fn filter_list(filter: &ExperimentListFilter, inner: &ExperimentListSource) -> Result<Value> {
     let v: Value = Value::try_from(inner)?;
     let data = v.get_array("data")?;
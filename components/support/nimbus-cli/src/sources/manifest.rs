@@ -33,11 +33,26 @@ impl ManifestSource {
         manifest_file
     }
 
-    fn channel(&self) -> &str {
+    pub(crate) fn channel(&self) -> &str {
         let (Self::FromFile { channel, .. } | Self::FromGithub { channel, .. }) = self;
         channel
     }
 
+    /// The branch, tag or commit the manifest is fetched from, if it comes from Github.
+    pub(crate) fn git_ref(&self) -> Option<&str> {
+        match self {
+            Self::FromGithub { ref_, .. } => Some(ref_),
+            _ => None,
+        }
+    }
+
+    /// The resolved URL or path of the top-level manifest file.
+    pub(crate) fn source_url(&self) -> Result<String> {
+        let files = self.manifest_loader()?;
+        let path = files.file_path(self.manifest_file())?;
+        Ok(path.to_string())
+    }
+
     fn manifest_loader(&self) -> Result<FileLoader> {
         let cwd = std::env::current_dir().expect("Current Working Directory is not set");
         let mut files = FileLoader::new(cwd, config::manifest_cache_dir(), Default::default())?;
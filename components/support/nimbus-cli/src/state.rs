@@ -0,0 +1,468 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A small amount of state that is kept between invocations of the CLI.
+//!
+//! Currently, this is the last server used to list or fetch experiments, and
+//! the expiry of enrollments made with `enroll --until`, for each app and channel.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use console::Term;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{Cli, CliCommand, ExperimentListSourceArgs},
+    value_utils,
+};
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct CliState {
+    #[serde(default)]
+    servers: BTreeMap<String, String>,
+    #[serde(default)]
+    expiries: BTreeMap<String, Vec<EnrollmentExpiry>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct EnrollmentExpiry {
+    pub(crate) slug: String,
+    /// An RFC 3339 timestamp. This is a string so that the state file is readable.
+    pub(crate) until: String,
+}
+
+impl EnrollmentExpiry {
+    fn is_expired(&self, now: &DateTime<Utc>) -> bool {
+        // A timestamp which we can't parse is treated as expired, so it is pruned
+        // rather than lingering forever.
+        match DateTime::parse_from_rfc3339(&self.until) {
+            Ok(until) => until <= *now,
+            _ => true,
+        }
+    }
+}
+
+impl CliState {
+    /// Load the state from the file. A missing or unreadable file gives an empty state:
+    /// this is a convenience, so it should never stop a command from running.
+    pub(crate) fn load(file: &Path) -> Self {
+        if !file.exists() {
+            return Default::default();
+        }
+        value_utils::read_from_file(file).unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, file: &Path) -> Result<()> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        value_utils::write_to_file_or_print(Some(file), self)
+    }
+
+    pub(crate) fn server(&self, app: &str, channel: &str) -> Option<&str> {
+        self.servers.get(&key(app, channel)).map(String::as_str)
+    }
+
+    pub(crate) fn set_server(&mut self, app: &str, channel: &str, server: &str) {
+        self.servers.insert(key(app, channel), server.to_string());
+    }
+
+    pub(crate) fn expired(
+        &self,
+        app: &str,
+        channel: &str,
+        now: &DateTime<Utc>,
+    ) -> Vec<EnrollmentExpiry> {
+        self.expiries
+            .get(&key(app, channel))
+            .map(|list| list.iter().filter(|e| e.is_expired(now)).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn key(app: &str, channel: &str) -> String {
+    format!("{app}/{channel}")
+}
+
+/// If no server is given, use the last server used for this app and channel.
+///
+/// Nothing is read if `--no-remember` is set, if the experiments are loaded from a file,
+/// or if either of the app or channel are missing.
+pub(crate) fn with_remembered_server(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+    args: &ExperimentListSourceArgs,
+) -> Result<ExperimentListSourceArgs> {
+    if !args.server.is_empty() || args.no_remember || args.file.is_some() {
+        return Ok(args.clone());
+    }
+    Ok(match remembered_server(state_file, app, channel)? {
+        Some(server) => ExperimentListSourceArgs {
+            server,
+            ..args.clone()
+        },
+        _ => args.clone(),
+    })
+}
+
+/// If a recipe slug doesn't name its server, prefix it with the last server used for
+/// this app and channel.
+///
+/// Nothing is read if `no_remember` is set, or if either of the app or channel are missing.
+pub(crate) fn with_remembered_server_for_slug(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+    slug: &str,
+    no_remember: bool,
+) -> Result<String> {
+    // URLs, and slugs like `stage/my-experiment`, already name their server.
+    if slug.contains('/') || no_remember {
+        return Ok(slug.to_string());
+    }
+    Ok(match remembered_server(state_file, app, channel)? {
+        Some(server) => format!("{server}/{slug}"),
+        _ => slug.to_string(),
+    })
+}
+
+fn remembered_server(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+) -> Result<Option<String>> {
+    let (file, app, channel) = match (state_file, app, channel) {
+        (Some(f), Some(a), Some(c)) => (f, a, c),
+        _ => return Ok(None),
+    };
+    let state = CliState::load(&file);
+    Ok(match state.server(app, channel) {
+        Some(server) => {
+            Term::stderr().write_line(&format!(
+                "# Using the last server for {app}/{channel}: {server} (use --no-remember to ignore it)"
+            ))?;
+            Some(server.to_string())
+        }
+        _ => None,
+    })
+}
+
+/// Remember the server given to list or fetch experiments, so the next command for this
+/// app and channel can use it.
+///
+/// This is called once the commands have run successfully, so a mistyped server isn't
+/// remembered. Nothing is written if `--no-remember` is set, if no server was given, or if
+/// either of the app or channel are missing.
+pub(crate) fn remember_server(state_file: Option<PathBuf>, cli: &Cli) -> Result<()> {
+    let (file, app, channel) = match (state_file, cli.app.as_deref(), cli.channel.as_deref()) {
+        (Some(f), Some(a), Some(c)) => (f, a, c),
+        _ => return Ok(()),
+    };
+    let server = match &cli.command {
+        CliCommand::FetchList { list, .. }
+        | CliCommand::List { list }
+        | CliCommand::ValidateList { list, .. }
+            if !list.source.no_remember && list.source.file.is_none() =>
+        {
+            list.source.server.as_str()
+        }
+        CliCommand::Fetch {
+            experiment,
+            no_remember: false,
+            ..
+        } if experiment.file.is_none()
+            && !experiment.clipboard
+            && !experiment.experiment.contains("://") =>
+        {
+            match experiment.experiment.rsplit_once('/') {
+                Some((server, _)) => server,
+                _ => "",
+            }
+        }
+        _ => return Ok(()),
+    };
+    if server.is_empty() {
+        return Ok(());
+    }
+
+    let mut state = CliState::load(&file);
+    if state.server(app, channel) != Some(server) {
+        state.set_server(app, channel, server);
+        state.save(&file)?;
+    }
+    Ok(())
+}
+
+/// Remember when the enrollment in `slug` should end, so the `prune` command can
+/// unenroll from it later.
+///
+/// Enrolling without `--preserve-nimbus-db` resets the Nimbus database, so the
+/// expiries of any previous enrollments are forgotten.
+pub(crate) fn record_enrollment(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+    slug: &str,
+    until: Option<&DateTime<Utc>>,
+    preserve_nimbus_db: bool,
+) -> Result<()> {
+    let (file, app, channel) = match (state_file, app, channel) {
+        (Some(f), Some(a), Some(c)) => (f, a, c),
+        _ => return Ok(()),
+    };
+    let mut state = CliState::load(&file);
+    let key = key(app, channel);
+    let before = state.expiries.get(&key).cloned();
+    let list = state.expiries.entry(key.clone()).or_default();
+    if !preserve_nimbus_db {
+        list.clear();
+    }
+    if let Some(until) = until {
+        list.retain(|e| e.slug != slug);
+        list.push(EnrollmentExpiry {
+            slug: slug.to_string(),
+            until: until.to_rfc3339(),
+        });
+    }
+    if list.is_empty() {
+        state.expiries.remove(&key);
+    }
+    if state.expiries.get(&key) != before.as_ref() {
+        state.save(&file)?;
+    }
+    Ok(())
+}
+
+/// The enrollments which are past due for this app and channel.
+pub(crate) fn expired_enrollments(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+    now: &DateTime<Utc>,
+) -> Vec<EnrollmentExpiry> {
+    match (state_file, app, channel) {
+        (Some(f), Some(a), Some(c)) => CliState::load(&f).expired(a, c, now),
+        _ => Default::default(),
+    }
+}
+
+/// Forget every expiry for this app and channel, once the app has been unenrolled.
+pub(crate) fn forget_enrollments(
+    state_file: Option<PathBuf>,
+    app: Option<&str>,
+    channel: Option<&str>,
+) -> Result<()> {
+    if let (Some(file), Some(app), Some(channel)) = (state_file, app, channel) {
+        let mut state = CliState::load(&file);
+        if state.expiries.remove(&key(app, channel)).is_some() {
+            state.save(&file)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn state_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nimbus-cli-state-{}", std::process::id()));
+        let file = dir.join(name);
+        _ = std::fs::remove_file(&file);
+        file
+    }
+
+    fn args(server: &str) -> ExperimentListSourceArgs {
+        ExperimentListSourceArgs {
+            server: server.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn cli(args: &[&str]) -> Cli {
+        let prefix = ["nimbus-cli", "--app", "fenix", "--channel", "developer"];
+        Cli::try_parse_from(prefix.iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn test_remember_server() -> Result<()> {
+        let file = state_file("remember.json");
+        let remembered = |app, channel| {
+            with_remembered_server(Some(file.clone()), Some(app), Some(channel), &args(""))
+                .map(|a| a.server)
+        };
+
+        // Nothing remembered yet.
+        assert_eq!("", remembered("fenix", "developer")?);
+
+        remember_server(Some(file.clone()), &cli(&["list", "stage/preview"]))?;
+        assert_eq!("stage/preview", remembered("fenix", "developer")?);
+
+        // An explicit server is used as is.
+        let observed = with_remembered_server(
+            Some(file.clone()),
+            Some("fenix"),
+            Some("developer"),
+            &args("release"),
+        )?;
+        assert_eq!("release", observed.server);
+
+        // Each app and channel is remembered separately.
+        assert_eq!("", remembered("fenix", "nightly")?);
+        assert_eq!("", remembered("firefox_ios", "developer")?);
+
+        // An explicit server replaces the remembered one.
+        remember_server(Some(file.clone()), &cli(&["fetch-list", "release"]))?;
+        assert_eq!("release", remembered("fenix", "developer")?);
+
+        // So does the server of a fetched recipe.
+        remember_server(
+            Some(file.clone()),
+            &cli(&["fetch", "preview/my-experiment"]),
+        )?;
+        assert_eq!("preview", remembered("fenix", "developer")?);
+
+        // A recipe without a server doesn't change it.
+        remember_server(Some(file.clone()), &cli(&["fetch", "my-experiment"]))?;
+        assert_eq!("preview", remembered("fenix", "developer")?);
+
+        let state = CliState::load(&file);
+        assert_eq!(Some("preview"), state.server("fenix", "developer"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remembered_server_for_slug() -> Result<()> {
+        let file = state_file("remember-slug.json");
+        let with_server = |slug, no_remember| {
+            with_remembered_server_for_slug(
+                Some(file.clone()),
+                Some("fenix"),
+                Some("developer"),
+                slug,
+                no_remember,
+            )
+        };
+
+        assert_eq!("my-experiment", with_server("my-experiment", false)?);
+
+        remember_server(Some(file.clone()), &cli(&["list", "stage/preview"]))?;
+        assert_eq!(
+            "stage/preview/my-experiment",
+            with_server("my-experiment", false)?
+        );
+        assert_eq!("my-experiment", with_server("my-experiment", true)?);
+
+        // Slugs and URLs which name their server are used as is.
+        assert_eq!(
+            "release/my-experiment",
+            with_server("release/my-experiment", false)?
+        );
+        let url = "https://experimenter.services.mozilla.com/nimbus/my-experiment";
+        assert_eq!(url, with_server(url, false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_remember() -> Result<()> {
+        let file = state_file("no-remember.json");
+
+        remember_server(
+            Some(file.clone()),
+            &cli(&["list", "--no-remember", "stage"]),
+        )?;
+        remember_server(
+            Some(file.clone()),
+            &cli(&["fetch", "--no-remember", "stage/my-experiment"]),
+        )?;
+        assert!(!file.exists());
+
+        remember_server(Some(file.clone()), &cli(&["list", "stage"]))?;
+        let observed = with_remembered_server(
+            Some(file.clone()),
+            Some("fenix"),
+            Some("developer"),
+            &ExperimentListSourceArgs {
+                no_remember: true,
+                ..args("")
+            },
+        )?;
+        assert_eq!("", observed.server);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enrollment_expiries() -> Result<()> {
+        let file = state_file("expiries.json");
+        let date = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let record = |slug, until: Option<&str>, preserve| {
+            record_enrollment(
+                Some(file.clone()),
+                Some("fenix"),
+                Some("developer"),
+                slug,
+                until.map(date).as_ref(),
+                preserve,
+            )
+        };
+        let expired = |now: &str| {
+            expired_enrollments(
+                Some(file.clone()),
+                Some("fenix"),
+                Some("developer"),
+                &date(now),
+            )
+            .into_iter()
+            .map(|e| e.slug)
+            .collect::<Vec<_>>()
+        };
+
+        // Enrolling without an expiry doesn't write anything.
+        record("no-expiry", None, false)?;
+        assert!(!file.exists());
+
+        record("first", Some("2023-07-01T00:00:00Z"), false)?;
+        record("second", Some("2023-08-01T00:00:00Z"), true)?;
+        assert!(expired("2023-06-30T00:00:00Z").is_empty());
+        assert_eq!(vec!["first"], expired("2023-07-01T00:00:00Z"));
+        assert_eq!(vec!["first", "second"], expired("2023-09-01T00:00:00Z"));
+
+        // Other apps and channels are kept separately.
+        let other = expired_enrollments(
+            Some(file.clone()),
+            Some("fenix"),
+            Some("nightly"),
+            &date("2023-09-01T00:00:00Z"),
+        );
+        assert!(other.is_empty());
+
+        // Enrolling again without preserving the database forgets the old expiries.
+        record("third", Some("2023-08-01T00:00:00Z"), false)?;
+        assert_eq!(vec!["third"], expired("2023-09-01T00:00:00Z"));
+
+        forget_enrollments(Some(file.clone()), Some("fenix"), Some("developer"))?;
+        assert!(expired("2023-09-01T00:00:00Z").is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_state_file() -> Result<()> {
+        let file = state_file("corrupt.json");
+        std::fs::create_dir_all(file.parent().unwrap())?;
+        std::fs::write(&file, "not json")?;
+        assert_eq!(CliState::default(), CliState::load(&file));
+        Ok(())
+    }
+}
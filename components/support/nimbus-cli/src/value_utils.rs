@@ -308,6 +308,15 @@ pub(crate) fn prepare_experiment(
     Ok(experiment)
 }
 
+pub(crate) fn read_from_clipboard() -> Result<Value> {
+    use copypasta::{ClipboardContext, ClipboardProvider};
+    let contents = ClipboardContext::new()
+        .and_then(|mut ctx| ctx.get_contents())
+        .map_err(|e| anyhow::anyhow!("Can't read from the clipboard: {}", e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("The clipboard doesn't contain valid JSON: {}", e))
+}
+
 fn is_yaml<P>(file: P) -> bool
 where
     P: AsRef<Path>,
@@ -31,6 +31,25 @@ pub fn quoted(string: &dyn Display) -> String {
     }
 }
 
+/// Renders a string as a single line Kotlin string literal, escaping the characters which
+/// would otherwise end the string or be interpolated, e.g. so a JSON string can be embedded.
+pub fn string_literal(string: &dyn Display) -> String {
+    let mut literal = String::from('"');
+    for c in string.to_string().chars() {
+        match c {
+            '\\' => literal.push_str(r"\\"),
+            '"' => literal.push_str(r#"\""#),
+            '$' => literal.push_str(r"\$"),
+            '\n' => literal.push_str(r"\n"),
+            '\r' => literal.push_str(r"\r"),
+            '\t' => literal.push_str(r"\t"),
+            _ => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
 pub(crate) mod code_type {
     use std::fmt::Display;
 
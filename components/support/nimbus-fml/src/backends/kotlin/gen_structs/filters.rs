@@ -82,3 +82,7 @@ pub fn comment(txt: &dyn fmt::Display, spaces: &str) -> Result<String, askama::E
 pub fn quoted(txt: &dyn fmt::Display) -> Result<String, askama::Error> {
     Ok(common::quoted(txt))
 }
+
+pub fn string_literal(txt: &dyn fmt::Display) -> Result<String, askama::Error> {
+    Ok(common::string_literal(txt))
+}
@@ -104,6 +104,23 @@ object {{ nimbus_object }} : FeatureManifestInterface<{{ nimbus_object }}.Featur
             {{- f|quoted }}
             {%- if !loop.last %}, {% endif %}
             {%- endfor %})
+
+    /**
+     * Get a list of the ids of every feature in the manifest, including imported features.
+     */
+    override fun getFeatureIds(): List<String> =
+        listOf(
+            {%- for f in self.fm.get_all_feature_ids() %}
+            {{- f|quoted }}
+            {%- if !loop.last %}, {% endif %}
+            {%- endfor %})
+
+    /**
+     * Get the default values of every feature in the manifest, including imported features,
+     * as a JSON object of feature id to feature JSON.
+     */
+    override fun getFeatureDefaults(): String =
+        {{ self.fm.default_json()|string_literal }}
     /**
      * Accessor object for generated configuration classes extracted from Nimbus, with built-in
      * default values.
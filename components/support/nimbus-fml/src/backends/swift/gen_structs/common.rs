@@ -24,6 +24,24 @@ pub fn quoted(v: &dyn Display) -> String {
     format!(r#""{}""#, v)
 }
 
+/// Renders a string as a single line Swift string literal, escaping the characters which
+/// would otherwise end the string or be interpolated, e.g. so a JSON string can be embedded.
+pub fn string_literal(v: &dyn Display) -> String {
+    let mut literal = String::from('"');
+    for c in v.to_string().chars() {
+        match c {
+            '\\' => literal.push_str(r"\\"),
+            '"' => literal.push_str(r#"\""#),
+            '\n' => literal.push_str(r"\n"),
+            '\r' => literal.push_str(r"\r"),
+            '\t' => literal.push_str(r"\t"),
+            _ => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
 pub(crate) mod code_type {
     use std::fmt::Display;
 
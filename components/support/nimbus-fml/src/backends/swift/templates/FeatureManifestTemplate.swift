@@ -55,6 +55,17 @@ public class {{ nimbus_object }} : FeatureManifestInterface {
             {%- endfor %}]
     }
 
+    public func getFeatureIds() -> [String] {
+        [{%- for f in self.fm.get_all_feature_ids() %}
+            {{- f|quoted }}
+            {%- if !loop.last %}, {% endif %}
+            {%- endfor %}]
+    }
+
+    public func getFeatureDefaults() -> String {
+        {{ self.fm.default_json()|string_literal }}
+    }
+
     ///
     /// All generated initialization code. Clients shouldn't need to override or call
     /// this.
@@ -8,7 +8,7 @@ use crate::error::ClientError::{
 };
 use crate::error::FMLError::ClientError;
 use crate::{
-    error::{FMLError, Result},
+    error::{FMLError, FmlValidationError, FmlVariableError, Result},
     intermediate_representation::{FeatureManifest, TypeRef},
     parser::Parser,
     util::loaders::FileLoader,
@@ -67,6 +67,41 @@ impl FmlClient {
             .map(|_| true)
     }
 
+    /// Validates a supplied feature configuration, reporting every invalid variable rather than
+    /// just the first.
+    ///
+    /// This is the same validation as `nimbus-cli validate`, for apps' developer tooling to check a
+    /// config (e.g. one passed to `set_experiments_locally`) before applying it.  Each variable is
+    /// checked on its own, so an unknown or mistyped variable doesn't hide problems with the
+    /// others.
+    pub fn validate_feature_config(
+        &self,
+        feature_id: String,
+        value: JsonObject,
+    ) -> Result<(), FmlValidationError> {
+        if self.manifest.find_feature(&feature_id).is_none() {
+            return Err(FmlValidationError::UnknownFeature { feature_id });
+        }
+        let errors: Vec<_> = value
+            .into_iter()
+            .filter_map(|(variable, value)| {
+                let config = JsonObject::from_iter([(variable.clone(), value)]);
+                self.manifest
+                    .validate_feature_config(&feature_id, serde_json::Value::Object(config))
+                    .err()
+                    .map(|e| FmlVariableError {
+                        variable,
+                        message: e.to_string(),
+                    })
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(FmlValidationError::InvalidVariables { errors })
+        }
+    }
+
     /// Validates a supplied list of feature configurations. The valid configurations will be merged into the manifest's
     /// default feature JSON, and invalid configurations will be returned as a list of their respective errors.
     pub fn merge(
@@ -213,6 +248,52 @@ mod unit_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_validate_feature_config_reports_each_variable() -> Result<()> {
+        let client: FmlClient = create_manifest().into();
+
+        client
+            .validate_feature_config(
+                "feature".to_string(),
+                Map::from_iter([("prop_1".to_string(), Value::String("new value".into()))]),
+            )
+            .unwrap();
+
+        let err = client
+            .validate_feature_config(
+                "feature".to_string(),
+                Map::from_iter([
+                    ("prop_1".to_string(), Value::Number(Number::from(1))),
+                    ("prop_2".to_string(), Value::String("unknown".into())),
+                ]),
+            )
+            .unwrap_err();
+        let errors = match err {
+            FmlValidationError::InvalidVariables { errors } => errors,
+            _ => panic!("expected InvalidVariables, got {err:?}"),
+        };
+        assert_eq!(
+            errors,
+            vec![
+                FmlVariableError {
+                    variable: "prop_1".to_string(),
+                    message: "Validation Error at features/feature.prop_1: Mismatch between type String and default 1".to_string(),
+                },
+                FmlVariableError {
+                    variable: "prop_2".to_string(),
+                    message: "Property `prop_2` not found on feature `feature`".to_string(),
+                },
+            ]
+        );
+
+        assert!(matches!(
+            client.validate_feature_config("no-such-feature".to_string(), Map::new()),
+            Err(FmlValidationError::UnknownFeature { feature_id }) if feature_id == "no-such-feature"
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_validate_and_merge_feature_configs() -> Result<()> {
         let client: FmlClient = create_manifest().into();
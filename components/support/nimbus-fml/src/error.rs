@@ -65,4 +65,22 @@ pub enum ClientError {
     JsonMergeError(String),
 }
 
+/// Error returned by `FmlClient::validate_feature_config()`
+#[cfg(feature = "client-lib")]
+#[derive(Debug, thiserror::Error)]
+pub enum FmlValidationError {
+    #[error("Feature `{feature_id}` not found on manifest")]
+    UnknownFeature { feature_id: String },
+    #[error("Feature config has {} invalid variable(s)", errors.len())]
+    InvalidVariables { errors: Vec<FmlVariableError> },
+}
+
+/// Why a single variable of a feature config is invalid
+#[cfg(feature = "client-lib")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmlVariableError {
+    pub variable: String,
+    pub message: String,
+}
+
 pub type Result<T, E = FMLError> = std::result::Result<T, E>;
@@ -647,6 +647,15 @@ impl FeatureManifest {
             .collect()
     }
 
+    pub fn get_all_feature_ids(&self) -> Vec<String> {
+        let mut ids: Vec<_> = self
+            .iter_all_feature_defs()
+            .map(|(_, f)| f.name())
+            .collect();
+        ids.sort();
+        ids
+    }
+
     pub fn find_feature(&self, nm: &str) -> Option<(&FeatureManifest, &FeatureDef)> {
         self.iter_all_feature_defs().find(|(_, f)| f.name() == nm)
     }
@@ -1917,6 +1926,47 @@ pub mod unit_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_all_feature_ids_finds_across_all_imports() -> Result<()> {
+        let fm_i = get_feature_manifest(
+            vec![],
+            vec![],
+            vec![FeatureDef {
+                name: "imported_feature".into(),
+                ..Default::default()
+            }],
+            HashMap::new(),
+        );
+
+        let fm = get_feature_manifest(
+            vec![],
+            vec![],
+            vec![
+                FeatureDef {
+                    name: "local_feature".into(),
+                    ..Default::default()
+                },
+                FeatureDef {
+                    name: "coenrolling_feature".into(),
+                    allow_coenrollment: true,
+                    ..Default::default()
+                },
+            ],
+            HashMap::from([(ModuleId::Local("test".into()), fm_i)]),
+        );
+
+        assert_eq!(
+            fm.get_all_feature_ids(),
+            vec![
+                "coenrolling_feature".to_string(),
+                "imported_feature".to_string(),
+                "local_feature".to_string(),
+            ]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_no_coenrolling_feature_finds_across_all_imports() -> Result<()> {
         let fm_i = get_feature_manifest(
@@ -303,7 +303,13 @@ impl FileLoader {
         Ok(if path_buf.exists() {
             std::fs::read_to_string(path_buf)?
         } else {
-            let res = self.fetch_client.get(url.clone()).send()?;
+            // Make sure we don't parse (or cache) an error page: e.g. a 404
+            // because the ref doesn't exist.
+            let res = self
+                .fetch_client
+                .get(url.clone())
+                .send()?
+                .error_for_status()?;
             let text = res.text()?;
 
             let parent = path_buf.parent().expect("Cache directory is specified");
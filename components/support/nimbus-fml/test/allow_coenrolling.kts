@@ -6,3 +6,10 @@ import com.example.app.nimbus.AppNimbus as MyNimbus
 
 val coenrollingFeatureIds = MyNimbus.getCoenrollingFeatureIds()
 assert(coenrollingFeatureIds == listOf("boring-app-menu", "fun-app-menu"))
+
+val featureIds = MyNimbus.getFeatureIds()
+assert(featureIds == listOf("app-menu", "boring-app-menu", "fun-app-menu"))
+
+val featureDefaults = org.json.JSONObject(MyNimbus.getFeatureDefaults())
+assert(featureDefaults.keySet() == setOf("app-menu", "boring-app-menu", "fun-app-menu"))
+assert(featureDefaults.getJSONObject("app-menu").getJSONArray("order").length() == 0)
@@ -3,6 +3,16 @@
 * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 import FeatureManifest
+import Foundation
 
 let coenrollingFeatureIds = AppNimbus.shared.getCoenrollingFeatureIds()
 assert(coenrollingFeatureIds == ["boring-app-menu", "fun-app-menu"])
+
+let featureIds = AppNimbus.shared.getFeatureIds()
+assert(featureIds == ["app-menu", "boring-app-menu", "fun-app-menu"])
+
+let featureDefaults = try! JSONSerialization.jsonObject(
+    with: AppNimbus.shared.getFeatureDefaults().data(using: .utf8)!
+) as! [String: Any]
+assert(Set(featureDefaults.keys) == ["app-menu", "boring-app-menu", "fun-app-menu"])
+assert((featureDefaults["app-menu"] as! [String: Any])["order"] as! [String] == [])
@@ -8,8 +8,10 @@ pub use crate::*;
 pub const NSSCK_VENDOR_NSS: u32 = 0x4E534350;
 
 pub const CKM_NSS: u32 = CKM_VENDOR_DEFINED | NSSCK_VENDOR_NSS;
+pub const CKM_NSS_HKDF_SHA1: u32 = CKM_NSS + 3;
 pub const CKM_NSS_HKDF_SHA256: u32 = CKM_NSS + 4;
 pub const CKM_NSS_HKDF_SHA384: u32 = CKM_NSS + 5;
+pub const CKM_NSS_HKDF_SHA512: u32 = CKM_NSS + 6;
 
 pub type CK_GCM_PARAMS = CK_GCM_PARAMS_V3;
 #[repr(C)]
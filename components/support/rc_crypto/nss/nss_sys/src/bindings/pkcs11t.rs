@@ -41,6 +41,7 @@ pub const CKA_EC_PARAMS: u32 = 384;
 pub const CKA_EC_POINT: u32 = 385;
 // https://searchfox.org/nss/rev/4d480919bbf204df5e199b9fdedec8f2a6295778/lib/util/pkcs11t.h#1244
 pub const CKM_VENDOR_DEFINED: u32 = 0x80000000;
+pub const CKM_SHA_1_HMAC: u32 = 545;
 pub const CKM_SHA256_HMAC: u32 = 593;
 pub const CKM_SHA384_HMAC: u32 = 609;
 pub const CKM_SHA512_HMAC: u32 = 625;
@@ -12,7 +12,6 @@ use crate::{
 };
 
 // Expose for consumers to choose the hashing algorithm
-// Currently only SHA256 supported
 pub use crate::pk11::context::HashAlgorithm;
 use nss_sys::SECOidTag;
 
@@ -28,8 +27,10 @@ pub fn pbkdf2_key_derive(
 ) -> Result<()> {
     ensure_nss_initialized();
     let oid_tag = match hash_algorithm {
+        HashAlgorithm::SHA1 => SECOidTag::SEC_OID_HMAC_SHA1 as u32,
         HashAlgorithm::SHA256 => SECOidTag::SEC_OID_HMAC_SHA256 as u32,
         HashAlgorithm::SHA384 => SECOidTag::SEC_OID_HMAC_SHA384 as u32,
+        HashAlgorithm::SHA512 => SECOidTag::SEC_OID_HMAC_SHA512 as u32,
     };
     let mut sec_salt = nss_sys::SECItem {
         len: u32::try_from(salt.len())?,
@@ -15,29 +15,37 @@ use std::ptr;
 #[derive(Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum HashAlgorithm {
+    SHA1,
     SHA256,
     SHA384,
+    SHA512,
 }
 
 impl HashAlgorithm {
     fn result_len(&self) -> u32 {
         match self {
+            HashAlgorithm::SHA1 => nss_sys::SHA1_LENGTH,
             HashAlgorithm::SHA256 => nss_sys::SHA256_LENGTH,
             HashAlgorithm::SHA384 => nss_sys::SHA384_LENGTH,
+            HashAlgorithm::SHA512 => nss_sys::SHA512_LENGTH,
         }
     }
 
     fn as_hmac_mechanism(&self) -> u32 {
         match self {
+            HashAlgorithm::SHA1 => nss_sys::CKM_SHA_1_HMAC,
             HashAlgorithm::SHA256 => nss_sys::CKM_SHA256_HMAC,
             HashAlgorithm::SHA384 => nss_sys::CKM_SHA384_HMAC,
+            HashAlgorithm::SHA512 => nss_sys::CKM_SHA512_HMAC,
         }
     }
 
     pub(crate) fn as_hkdf_mechanism(&self) -> u32 {
         match self {
+            HashAlgorithm::SHA1 => nss_sys::CKM_NSS_HKDF_SHA1,
             HashAlgorithm::SHA256 => nss_sys::CKM_NSS_HKDF_SHA256,
             HashAlgorithm::SHA384 => nss_sys::CKM_NSS_HKDF_SHA384,
+            HashAlgorithm::SHA512 => nss_sys::CKM_NSS_HKDF_SHA512,
         }
     }
 }
@@ -45,8 +53,10 @@ impl HashAlgorithm {
 impl From<&HashAlgorithm> for nss_sys::SECOidTag {
     fn from(alg: &HashAlgorithm) -> Self {
         match alg {
+            HashAlgorithm::SHA1 => nss_sys::SECOidTag::SEC_OID_SHA1,
             HashAlgorithm::SHA256 => nss_sys::SECOidTag::SEC_OID_SHA256,
             HashAlgorithm::SHA384 => nss_sys::SECOidTag::SEC_OID_SHA384,
+            HashAlgorithm::SHA512 => nss_sys::SECOidTag::SEC_OID_SHA512,
         }
     }
 }
@@ -27,11 +27,16 @@
 ///
 ///  See the autofill DB code for an example.
 ///
+///  Upgrading a large database can take a while. Consumers who want to show some feedback while
+///  that happens can register a `MigrationObserver`, which the `ConnectionInitializer` hands back
+///  from `migration_observer()`. There's no observer by default.
+///
 use crate::ConnExt;
 use rusqlite::{
     Connection, Error as RusqliteError, ErrorCode, OpenFlags, Transaction, TransactionBehavior,
 };
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -73,6 +78,47 @@ pub trait ConnectionInitializer {
     fn finish(&self, _conn: &Connection) -> Result<()> {
         Ok(())
     }
+
+    // The observer to report schema upgrade progress to, if the consumer registered one.
+    fn migration_observer(&self) -> Option<Arc<dyn MigrationObserver>> {
+        None
+    }
+}
+
+/// Receives progress reports while a database schema is being upgraded.
+///
+/// Components expose this to the application as a UniFFI callback interface, so it can show an
+/// "upgrading your data" screen instead of appearing frozen.
+pub trait MigrationObserver: Send + Sync {
+    /// Called with `current_version == from_version` before the first upgrade step runs, then
+    /// after each step. The final call has `current_version == to_version`. Nothing is reported
+    /// for new or already up-to-date databases.
+    fn on_migration_progress(&self, from_version: u32, to_version: u32, current_version: u32);
+}
+
+/// Holds the `MigrationObserver` a component's consumer registered, if any.
+///
+/// Components keep one of these in a static, and return its contents from
+/// `ConnectionInitializer::migration_observer()`.
+#[derive(Default)]
+pub struct MigrationObserverRegistry(RwLock<Option<Arc<dyn MigrationObserver>>>);
+
+impl MigrationObserverRegistry {
+    pub const fn new() -> Self {
+        Self(RwLock::new(None))
+    }
+
+    pub fn set(&self, observer: Box<dyn MigrationObserver>) {
+        *self.0.write().unwrap() = Some(observer.into());
+    }
+
+    pub fn unset(&self) {
+        *self.0.write().unwrap() = None;
+    }
+
+    pub fn get(&self) -> Option<Arc<dyn MigrationObserver>> {
+        self.0.read().unwrap().clone()
+    }
 }
 
 pub fn open_database<CI: ConnectionInitializer, P: AsRef<Path>>(
@@ -124,6 +170,16 @@ fn do_open_database_with_flags<CI: ConnectionInitializer, P: AsRef<Path>>(
             if current_version > CI::END_VERSION {
                 return Err(Error::IncompatibleVersion(current_version));
             }
+            let from_version = current_version;
+            let observer = connection_initializer.migration_observer();
+            let report_progress = |current_version| {
+                if let Some(observer) = &observer {
+                    observer.on_migration_progress(from_version, CI::END_VERSION, current_version);
+                }
+            };
+            if current_version < CI::END_VERSION {
+                report_progress(current_version);
+            }
             while current_version < CI::END_VERSION {
                 log::debug!(
                     "{}: upgrading database to {}",
@@ -132,6 +188,7 @@ fn do_open_database_with_flags<CI: ConnectionInitializer, P: AsRef<Path>>(
                 );
                 connection_initializer.upgrade_from(&tx, current_version)?;
                 current_version += 1;
+                report_progress(current_version);
             }
         }
         log::debug!("{}: finishing writable database open", CI::NAME);
@@ -301,10 +358,12 @@ mod test {
     use super::*;
     use std::cell::RefCell;
     use std::io::Write;
+    use std::sync::Mutex;
 
     struct TestConnectionInitializer {
         pub calls: RefCell<Vec<&'static str>>,
         pub buggy_v3_upgrade: bool,
+        pub migration_observer: MigrationObserverRegistry,
     }
 
     impl TestConnectionInitializer {
@@ -313,6 +372,7 @@ mod test {
             Self {
                 calls: RefCell::new(Vec::new()),
                 buggy_v3_upgrade: false,
+                migration_observer: MigrationObserverRegistry::new(),
             }
         }
         pub fn new_with_buggy_logic() -> Self {
@@ -320,6 +380,7 @@ mod test {
             Self {
                 calls: RefCell::new(Vec::new()),
                 buggy_v3_upgrade: true,
+                migration_observer: MigrationObserverRegistry::new(),
             }
         }
 
@@ -402,6 +463,24 @@ mod test {
             )?;
             Ok(())
         }
+
+        fn migration_observer(&self) -> Option<Arc<dyn MigrationObserver>> {
+            self.migration_observer.get()
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct TestMigrationObserver {
+        reports: Arc<Mutex<Vec<(u32, u32, u32)>>>,
+    }
+
+    impl MigrationObserver for TestMigrationObserver {
+        fn on_migration_progress(&self, from_version: u32, to_version: u32, current_version: u32) {
+            self.reports
+                .lock()
+                .unwrap()
+                .push((from_version, to_version, current_version));
+        }
     }
 
     // Initialize the database to v2 to test upgrading from there
@@ -441,6 +520,34 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn test_migration_observer() {
+        let db_file = MigratedDatabaseFile::new(TestConnectionInitializer::new(), INIT_V2);
+        let observer = TestMigrationObserver::default();
+        db_file
+            .connection_initializer
+            .migration_observer
+            .set(Box::new(observer.clone()));
+        open_database(db_file.path.clone(), &db_file.connection_initializer).unwrap();
+        assert_eq!(
+            *observer.reports.lock().unwrap(),
+            vec![(2, 4, 2), (2, 4, 3), (2, 4, 4)]
+        );
+
+        // Nothing is reported when there's nothing to upgrade.
+        observer.reports.lock().unwrap().clear();
+        open_database(db_file.path.clone(), &db_file.connection_initializer).unwrap();
+        assert!(observer.reports.lock().unwrap().is_empty());
+
+        // Nor for a new database.
+        let connection_initializer = TestConnectionInitializer::new();
+        connection_initializer
+            .migration_observer
+            .set(Box::new(observer.clone()));
+        open_memory_database(&connection_initializer).unwrap();
+        assert!(observer.reports.lock().unwrap().is_empty());
+    }
+
     #[test]
     fn test_open_current_version() {
         let db_file = MigratedDatabaseFile::new(TestConnectionInitializer::new(), INIT_V2);
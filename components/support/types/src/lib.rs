@@ -6,10 +6,17 @@ use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use rusqlite::Result as RusqliteResult;
 use serde_derive::*;
 use std::fmt;
+use std::num::TryFromIntError;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-// Typesafe way to manage timestamps.
-// We should probably work out how to share this too?
+/// A typesafe way to manage timestamps, shared by the components.
+///
+/// This is always the number of milliseconds since the unix epoch. Use
+/// [`Timestamp::from_secs`] and [`Timestamp::as_secs`] when dealing with
+/// seconds, rather than converting by hand.
+///
+/// Across the FFI, components expose this as an `i64` custom type (e.g.
+/// `PlacesTimestamp`), because UniFFI bindings can't use `u64` everywhere.
 #[derive(
     Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize, Serialize, Default,
 )]
@@ -24,18 +31,22 @@ impl Timestamp {
     /// negative timespans in rust).
     #[inline]
     pub fn duration_since(self, other: Timestamp) -> Option<Duration> {
-        // just do this via SystemTime.
-        SystemTime::from(self).duration_since(other.into()).ok()
+        self.0.checked_sub(other.0).map(Duration::from_millis)
     }
 
+    // These use plain integer math rather than going via SystemTime, because
+    // timestamps from the FFI can be later than the platform's SystemTime can
+    // represent.
     #[inline]
     pub fn checked_sub(self, d: Duration) -> Option<Timestamp> {
-        SystemTime::from(self).checked_sub(d).map(Timestamp::from)
+        let ms = u64::try_from(d.as_millis()).ok()?;
+        self.0.checked_sub(ms).map(Timestamp)
     }
 
     #[inline]
     pub fn checked_add(self, d: Duration) -> Option<Timestamp> {
-        SystemTime::from(self).checked_add(d).map(Timestamp::from)
+        let ms = u64::try_from(d.as_millis()).ok()?;
+        self.0.checked_add(ms).map(Timestamp)
     }
 
     pub fn as_millis(self) -> u64 {
@@ -45,6 +56,19 @@ impl Timestamp {
     pub fn as_millis_i64(self) -> i64 {
         self.0 as i64
     }
+
+    /// Seconds which are too large to represent in milliseconds saturate.
+    #[inline]
+    pub fn from_secs(secs: u64) -> Self {
+        Timestamp(secs.saturating_mul(1000))
+    }
+
+    /// The number of whole seconds since the unix epoch.
+    #[inline]
+    pub fn as_secs(self) -> u64 {
+        self.0 / 1000
+    }
+
     /// In desktop sync, bookmarks are clamped to Jan 23, 1993 (which is 727747200000)
     /// There's no good reason history records could be older than that, so we do
     /// the same here (even though desktop's history currently doesn't)
@@ -52,6 +76,10 @@ impl Timestamp {
     /// then all requests earlier than that are collapsed into a single visit at
     /// this timestamp.
     pub const EARLIEST: Timestamp = Timestamp(727_747_200_000);
+
+    /// The end of the year 9999, which every platform's SystemTime can
+    /// represent. Later timestamps are clamped to this when converted.
+    const LATEST_SYSTEM_TIME: Timestamp = Timestamp(253_402_300_799_999);
 }
 
 impl From<Timestamp> for u64 {
@@ -61,6 +89,24 @@ impl From<Timestamp> for u64 {
     }
 }
 
+impl From<Timestamp> for i64 {
+    #[inline]
+    fn from(ts: Timestamp) -> Self {
+        ts.as_millis_i64()
+    }
+}
+
+/// For the `i64` milliseconds we exchange with the FFI and the database.
+/// Negative values can't be valid timestamps, so fail to convert.
+impl TryFrom<i64> for Timestamp {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(ts: i64) -> Result<Self, Self::Error> {
+        u64::try_from(ts).map(Timestamp)
+    }
+}
+
 impl From<SystemTime> for Timestamp {
     #[inline]
     fn from(st: SystemTime) -> Self {
@@ -72,7 +118,11 @@ impl From<SystemTime> for Timestamp {
 impl From<Timestamp> for SystemTime {
     #[inline]
     fn from(ts: Timestamp) -> Self {
-        UNIX_EPOCH + Duration::from_millis(ts.into())
+        UNIX_EPOCH
+            .checked_add(Duration::from_millis(ts.into()))
+            .unwrap_or_else(|| {
+                UNIX_EPOCH + Duration::from_millis(Timestamp::LATEST_SYSTEM_TIME.into())
+            })
     }
 }
 
@@ -102,3 +152,47 @@ impl FromSql for Timestamp {
         value.as_i64().map(|v| Timestamp(v as u64)) // hrm - no u64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let ts = Timestamp::from_secs(1_690_000_000);
+        assert_eq!(ts.as_millis(), 1_690_000_000_000);
+        assert_eq!(ts.as_secs(), 1_690_000_000);
+        assert_eq!(Timestamp(1_690_000_000_999).as_secs(), 1_690_000_000);
+
+        assert_eq!(i64::from(ts), 1_690_000_000_000);
+        assert_eq!(Timestamp::try_from(1_690_000_000_000i64), Ok(ts));
+        assert_eq!(Timestamp::try_from(0i64), Ok(Timestamp(0)));
+        assert!(Timestamp::try_from(-1i64).is_err());
+        assert!(Timestamp::try_from(i64::MIN).is_err());
+
+        let st = SystemTime::from(ts);
+        assert_eq!(Timestamp::from(st), ts);
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Timestamp::from_secs(u64::MAX), Timestamp(u64::MAX));
+
+        let latest = Timestamp(i64::MAX as u64);
+        assert_eq!(Timestamp::try_from(i64::MAX), Ok(latest));
+        assert_eq!(latest.checked_add(Duration::from_millis(u64::MAX)), None);
+        assert_eq!(
+            latest.checked_add(Duration::from_millis(1)),
+            Some(Timestamp(i64::MAX as u64 + 1))
+        );
+        assert_eq!(Timestamp(1).checked_sub(Duration::from_millis(2)), None);
+        assert_eq!(
+            latest.duration_since(Timestamp(0)),
+            Some(Duration::from_millis(i64::MAX as u64))
+        );
+        assert_eq!(Timestamp(0).duration_since(latest), None);
+
+        // Doesn't panic, whatever the platform's range.
+        let _ = SystemTime::from(Timestamp(u64::MAX));
+    }
+}
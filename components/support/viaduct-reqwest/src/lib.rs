@@ -3,15 +3,17 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use once_cell::sync::Lazy;
-use std::{io::Read, sync::Once};
+use std::{collections::HashMap, io::Read, sync::Once, time::Instant};
 use viaduct::{settings::GLOBAL_SETTINGS, Backend};
 
+pub mod interceptor;
+pub use interceptor::{clear_interceptor, set_interceptor, Interceptor};
+
 // Note: we don't `use` things from reqwest or the viaduct crate because
 // it would be rather confusing given that we have the same name for
 // most things as them.
 
-static CLIENT: Lazy<reqwest::blocking::Client> = Lazy::new(|| {
-    let settings = GLOBAL_SETTINGS.read();
+fn client_builder(settings: &viaduct::settings::Settings) -> reqwest::blocking::ClientBuilder {
     let mut builder = reqwest::blocking::ClientBuilder::new()
         .timeout(settings.read_timeout)
         .connect_timeout(settings.connect_timeout)
@@ -20,6 +22,15 @@ static CLIENT: Lazy<reqwest::blocking::Client> = Lazy::new(|| {
         } else {
             reqwest::redirect::Policy::none()
         });
+    if let Some(max_idle) = settings.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = settings.pool_idle_timeout {
+        builder = builder.pool_idle_timeout(idle_timeout);
+    }
+    if let Some(keepalive) = settings.tcp_keepalive {
+        builder = builder.tcp_keepalive(keepalive);
+    }
     if cfg!(target_os = "ios") {
         // The FxA servers rely on the UA agent to filter
         // some push messages directed to iOS devices.
@@ -30,12 +41,93 @@ static CLIENT: Lazy<reqwest::blocking::Client> = Lazy::new(|| {
     }
     // Note: no cookie or cache support.
     builder
-        .build()
-        .expect("Failed to initialize global reqwest::Client")
+}
+
+// Fails with a description of the first certificate which can't be parsed.
+fn add_certificates<'a>(
+    mut builder: reqwest::blocking::ClientBuilder,
+    certs: impl IntoIterator<Item = &'a Vec<u8>>,
+    what: &str,
+) -> Result<reqwest::blocking::ClientBuilder, String> {
+    for (i, der) in certs.into_iter().enumerate() {
+        let cert = reqwest::Certificate::from_der(der)
+            .map_err(|e| format!("{} certificate {}: {}", what, i, e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder)
+}
+
+struct Clients {
+    default: reqwest::blocking::Client,
+    // One client per pinned host, which trusts nothing but the pinned
+    // certificates. This means pinning is enforced during the TLS handshake,
+    // before we've sent anything (like auth headers) to the server.
+    pinned: HashMap<String, reqwest::blocking::Client>,
+}
+
+fn build_clients(settings: &viaduct::settings::Settings) -> Result<Clients, String> {
+    let default = add_certificates(
+        client_builder(settings),
+        &settings.additional_root_certificates,
+        "additional root",
+    )?
+    .build()
+    .expect("Failed to initialize global reqwest::Client");
+    let pinned = settings
+        .pinned_certificates
+        .iter()
+        .map(|(host, certs)| {
+            let builder = client_builder(settings).tls_built_in_root_certs(false);
+            let client = add_certificates(builder, certs, &format!("{} pinned", host))?
+                .build()
+                .expect("Failed to initialize pinned reqwest::Client");
+            Ok((host.to_ascii_lowercase(), client))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Clients { default, pinned })
+}
+
+// If any of the certificates in the settings are invalid, we don't make any
+// requests at all, rather than quietly ignoring a root or a pin.
+static CLIENTS: Lazy<Result<Clients, String>> = Lazy::new(|| {
+    build_clients(&GLOBAL_SETTINGS.read()).map_err(|e| {
+        log::error!("Invalid certificate in the viaduct settings: {}", e);
+        e
+    })
 });
 
+fn clients() -> Result<&'static Clients, viaduct::Error> {
+    CLIENTS
+        .as_ref()
+        .map_err(|e| viaduct::Error::InvalidCertificate(e.clone()))
+}
+
+/// Checks the `additional_root_certificates` and `pinned_certificates` in the viaduct
+/// settings. The reqwest backend reads them once, before its first request, so this should
+/// be called after they're set. If any of them are invalid, this fails with
+/// `Error::InvalidCertificate`, and so does every request.
+pub fn check_certificates() -> Result<(), viaduct::Error> {
+    clients().map(|_| ())
+}
+
+// A pinned host's client trusts nothing but the pinned certificates, so a
+// failed TLS handshake with one means the server's certificate didn't chain to
+// any of them. reqwest wraps the native-tls error, so we look for it in the
+// error's sources.
+fn is_tls_error(e: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(err) = source {
+        if err.is::<native_tls::Error>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
 #[allow(clippy::unnecessary_wraps)] // not worth the time to untangle
 fn into_reqwest(request: viaduct::Request) -> Result<reqwest::blocking::Request, viaduct::Error> {
+    let timeout = request.timeout_override();
     let method = match request.method {
         viaduct::Method::Get => reqwest::Method::GET,
         viaduct::Method::Head => reqwest::Method::HEAD,
@@ -57,6 +149,9 @@ fn into_reqwest(request: viaduct::Request) -> Result<reqwest::blocking::Request,
             .insert(HeaderName::from_bytes(h.name().as_bytes()).unwrap(), value);
     }
     *result.body_mut() = request.body.map(reqwest::blocking::Body::from);
+    if let Some(timeout) = timeout {
+        *result.timeout_mut() = Some(timeout);
+    }
     Ok(result)
 }
 
@@ -64,42 +159,75 @@ pub struct ReqwestBackend;
 impl Backend for ReqwestBackend {
     fn send(&self, request: viaduct::Request) -> Result<viaduct::Response, viaduct::Error> {
         viaduct::note_backend("reqwest (untrusted)");
-        let request_method = request.method;
-        let req = into_reqwest(request)?;
-        let mut resp = CLIENT
-            .execute(req)
-            .map_err(|e| viaduct::Error::NetworkError(e.to_string()))?;
-        let status = resp.status().as_u16();
-        let url = resp.url().clone();
-        let mut body = Vec::with_capacity(resp.content_length().unwrap_or_default() as usize);
-        resp.read_to_end(&mut body).map_err(|e| {
-            log::error!("Failed to get body from response: {:?}", e);
-            viaduct::Error::NetworkError(e.to_string())
-        })?;
-        let mut headers = viaduct::Headers::with_capacity(resp.headers().len());
-        for (k, v) in resp.headers() {
-            let val = String::from_utf8_lossy(v.as_bytes()).to_string();
-            let hname = match viaduct::HeaderName::new(k.as_str().to_owned()) {
-                Ok(name) => name,
-                Err(e) => {
-                    // Ignore headers with invalid names, since nobody can look for them anyway.
-                    log::warn!("Server sent back invalid header name: '{}'", e);
-                    continue;
-                }
-            };
-            // Not using Header::new since the error it returns is for request headers.
-            headers.insert_header(viaduct::Header::new_unchecked(hname, val));
+        if !interceptor::is_active() {
+            return send_reqwest(request);
         }
-        Ok(viaduct::Response {
-            request_method,
-            url,
-            status,
-            headers,
-            body,
-        })
+        let request_meta = interceptor::RequestMetadata::from_request(&request);
+        interceptor::with_interceptor(|i| i.on_request(&request_meta));
+        let start = Instant::now();
+        let result = send_reqwest(request);
+        let elapsed = start.elapsed();
+        interceptor::with_interceptor(|i| match &result {
+            Ok(resp) => i.on_response(
+                &request_meta,
+                &interceptor::ResponseMetadata::from_response(resp, elapsed),
+            ),
+            Err(e) => i.on_error(&request_meta, e, elapsed),
+        });
+        result
     }
 }
 
+fn send_reqwest(request: viaduct::Request) -> Result<viaduct::Response, viaduct::Error> {
+    let request_method = request.method;
+    let req = into_reqwest(request)?;
+    let clients = clients()?;
+    let pinned_host = req
+        .url()
+        .host_str()
+        .map(str::to_ascii_lowercase)
+        .filter(|host| clients.pinned.contains_key(host));
+    let client = match &pinned_host {
+        Some(host) => &clients.pinned[host],
+        None => &clients.default,
+    };
+    let mut resp = client.execute(req).map_err(|e| match pinned_host {
+        Some(host) if e.is_connect() && is_tls_error(&e) => {
+            log::error!("Certificate pinning failure for {}: {}", host, e);
+            viaduct::Error::CertificatePinningError(host)
+        }
+        _ => viaduct::Error::NetworkError(e.to_string()),
+    })?;
+    let status = resp.status().as_u16();
+    let url = resp.url().clone();
+    let mut body = Vec::with_capacity(resp.content_length().unwrap_or_default() as usize);
+    resp.read_to_end(&mut body).map_err(|e| {
+        log::error!("Failed to get body from response: {:?}", e);
+        viaduct::Error::NetworkError(e.to_string())
+    })?;
+    let mut headers = viaduct::Headers::with_capacity(resp.headers().len());
+    for (k, v) in resp.headers() {
+        let val = String::from_utf8_lossy(v.as_bytes()).to_string();
+        let hname = match viaduct::HeaderName::new(k.as_str().to_owned()) {
+            Ok(name) => name,
+            Err(e) => {
+                // Ignore headers with invalid names, since nobody can look for them anyway.
+                log::warn!("Server sent back invalid header name: '{}'", e);
+                continue;
+            }
+        };
+        // Not using Header::new since the error it returns is for request headers.
+        headers.insert_header(viaduct::Header::new_unchecked(hname, val));
+    }
+    Ok(viaduct::Response {
+        request_method,
+        url,
+        status,
+        headers,
+        body,
+    })
+}
+
 static INIT_REQWEST_BACKEND: Once = Once::new();
 
 pub fn use_reqwest_backend() {
@@ -123,3 +251,27 @@ pub extern "C" fn viaduct_detect_reqwest_backend() {
         println!("Nothing to see here (reqwest backend available).");
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_certificates_fail() {
+        let mut settings = GLOBAL_SETTINGS.read().clone();
+        settings.pinned_certificates.insert(
+            "example.com".to_string(),
+            vec![b"not a certificate".to_vec()],
+        );
+        let err = build_clients(&settings).err().expect("should fail");
+        assert!(
+            err.starts_with("example.com pinned certificate 0:"),
+            "{}",
+            err
+        );
+
+        let mut settings = GLOBAL_SETTINGS.read().clone();
+        settings.additional_root_certificates = vec![vec![]];
+        assert!(build_clients(&settings).is_err());
+    }
+}
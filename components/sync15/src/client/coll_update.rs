@@ -3,13 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use super::{
-    request::{NormalResponseHandler, UploadInfo},
+    request::{BatchPoster, NormalResponseHandler, PostQueue, PostResponseHandler, UploadInfo},
     CollState, Sync15ClientResponse, Sync15StorageClient,
 };
 use crate::bso::{IncomingBso, OutgoingBso, OutgoingEncryptedBso};
 use crate::engine::CollectionRequest;
 use crate::error::{self, Error, Result};
-use crate::{CollectionName, KeyBundle, ServerTimestamp};
+use crate::{CollectionName, Guid, KeyBundle, ServerTimestamp};
 
 fn encrypt_outgoing(o: Vec<OutgoingBso>, key: &KeyBundle) -> Result<Vec<OutgoingEncryptedBso>> {
     o.into_iter()
@@ -90,8 +90,11 @@ impl<'a> CollectionUpdate<'a> {
 
     /// Returns a list of the IDs that failed if allowed_dropped_records is true, otherwise
     /// returns an empty vec.
+    ///
+    /// Records which are too large for the server to ever accept fail the upload with
+    /// `RecordTooLargeError` if it's fully atomic. Otherwise, they're skipped and returned
+    /// in `skipped_ids`.
     pub fn upload(self) -> error::Result<UploadInfo> {
-        let mut failed = vec![];
         let mut q = self.client.new_post_queue(
             &self.collection,
             &self.state.config,
@@ -99,16 +102,11 @@ impl<'a> CollectionUpdate<'a> {
             NormalResponseHandler::new(!self.fully_atomic),
         )?;
 
-        for record in self.to_update.into_iter() {
-            let enqueued = q.enqueue(&record)?;
-            if !enqueued && self.fully_atomic {
-                return Err(Error::RecordTooLargeError);
-            }
-        }
+        let mut skipped = enqueue_records(&mut q, self.to_update, self.fully_atomic)?;
 
         q.flush(true)?;
         let mut info = q.completed_upload_info();
-        info.failed_ids.append(&mut failed);
+        info.skipped_ids.append(&mut skipped);
         if self.fully_atomic {
             assert_eq!(
                 info.failed_ids.len(),
@@ -119,3 +117,110 @@ impl<'a> CollectionUpdate<'a> {
         Ok(info)
     }
 }
+
+/// Enqueues the records for upload, returning the IDs of those which were too large to
+/// enqueue, or failing if the upload is fully atomic and any were.
+fn enqueue_records<Poster, OnResponse>(
+    q: &mut PostQueue<Poster, OnResponse>,
+    records: Vec<OutgoingEncryptedBso>,
+    fully_atomic: bool,
+) -> Result<Vec<Guid>>
+where
+    Poster: BatchPoster,
+    OnResponse: PostResponseHandler,
+{
+    let mut skipped = vec![];
+    for record in records {
+        // The only reason a record can't be enqueued is that it's too large.
+        if !q.enqueue(&record)? {
+            if fully_atomic {
+                return Err(Error::RecordTooLargeError);
+            }
+            // The engine isn't told it was uploaded, so it stays changed locally, and we'll
+            // try it again on the next sync - where it will only be uploaded if it's shrunk.
+            skipped.push(record.envelope.id);
+        }
+    }
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::request::{InfoConfiguration, PostResponse};
+    use super::*;
+    use crate::bso::OutgoingEnvelope;
+    use crate::EncryptedPayload;
+
+    // None of these tests flush the queue, so nothing is ever posted.
+    struct NeverPosts;
+
+    impl BatchPoster for NeverPosts {
+        fn post<P, O>(
+            &self,
+            _body: Vec<u8>,
+            _xius: ServerTimestamp,
+            _batch: Option<String>,
+            _commit: bool,
+            _queue: &PostQueue<P, O>,
+        ) -> Result<PostResponse> {
+            unreachable!("nothing should be posted");
+        }
+    }
+
+    impl PostResponseHandler for NeverPosts {
+        fn handle_response(&mut self, _r: PostResponse, _mid_batch: bool) -> Result<()> {
+            unreachable!("nothing should be posted");
+        }
+    }
+
+    fn make_record(id: &str, ciphertext_len: usize) -> OutgoingEncryptedBso {
+        OutgoingEncryptedBso::new(
+            OutgoingEnvelope {
+                id: id.into(),
+                sortindex: None,
+                ttl: None,
+            },
+            EncryptedPayload {
+                iv: "".into(),
+                hmac: "".into(),
+                ciphertext: "x".repeat(ciphertext_len),
+            },
+        )
+    }
+
+    fn make_queue() -> PostQueue<NeverPosts, NeverPosts> {
+        let cfg = InfoConfiguration {
+            max_record_payload_bytes: 1000,
+            ..InfoConfiguration::default()
+        };
+        PostQueue::new(&cfg, ServerTimestamp(0), NeverPosts, NeverPosts)
+    }
+
+    fn make_records() -> Vec<OutgoingEncryptedBso> {
+        vec![
+            make_record("small1", 10),
+            make_record("large", 2000),
+            make_record("small2", 10),
+        ]
+    }
+
+    #[test]
+    fn test_enqueue_skips_large_records() {
+        let mut q = make_queue();
+        let skipped = enqueue_records(&mut q, make_records(), false).unwrap();
+        assert_eq!(skipped, vec![Guid::new("large")]);
+    }
+
+    #[test]
+    fn test_enqueue_atomic_fails_on_large_records() {
+        let mut q = make_queue();
+        assert!(matches!(
+            enqueue_records(&mut q, make_records(), true),
+            Err(Error::RecordTooLargeError)
+        ));
+        // Small records are fine either way.
+        let mut q = make_queue();
+        let records = vec![make_record("small1", 10), make_record("small2", 10)];
+        assert!(enqueue_records(&mut q, records, true).unwrap().is_empty());
+    }
+}
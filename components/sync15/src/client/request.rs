@@ -172,6 +172,11 @@ pub trait BatchPoster {
         commit: bool,
         queue: &PostQueue<P, O>,
     ) -> Result<PostResponse>;
+
+    /// The total number of bytes saved by compressing the bodies we've posted so far.
+    fn bytes_saved(&self) -> usize {
+        0
+    }
 }
 
 // We don't just use a FnMut here since we want to override it in mocking for RefCell<TestType>,
@@ -453,10 +458,13 @@ where
 pub struct UploadInfo {
     pub successful_ids: Vec<Guid>,
     pub failed_ids: Vec<Guid>,
+    // Records which were too large to upload, so weren't sent.
+    pub skipped_ids: Vec<Guid>,
     pub modified_timestamp: ServerTimestamp,
+    pub bytes_saved: usize,
 }
 
-impl<Poster> PostQueue<Poster, NormalResponseHandler> {
+impl<Poster: BatchPoster> PostQueue<Poster, NormalResponseHandler> {
     // TODO: should take by move
     pub fn completed_upload_info(&mut self) -> UploadInfo {
         let mut result = UploadInfo {
@@ -466,7 +474,9 @@ impl<Poster> PostQueue<Poster, NormalResponseHandler> {
                     + self.on_response.pending_failed.len()
                     + self.on_response.pending_success.len(),
             ),
+            skipped_ids: Vec::new(),
             modified_timestamp: self.last_modified,
+            bytes_saved: self.poster.bytes_saved(),
         };
 
         result
@@ -4,6 +4,7 @@
 
 use crate::error::{Error, ErrorResponse};
 use crate::telemetry::SyncTelemetryPing;
+use crate::Guid;
 use std::collections::HashMap;
 use std::time::{Duration, SystemTime};
 
@@ -77,6 +78,15 @@ pub struct SyncResult {
     /// Note that we expect the `String` to be replaced with an enum later.
     pub engine_results: HashMap<String, Result<(), Error>>,
 
+    /// The IDs of the outgoing records which each engine didn't upload because they're
+    /// larger than the server allows. The engine's sync still succeeds, and the records
+    /// are tried again on the next sync. Engines which skipped nothing aren't included.
+    pub skipped_records: HashMap<String, Vec<Guid>>,
+
+    /// The number of bytes each engine didn't need to upload because the server accepted
+    /// compressed uploads. Engines which saved nothing aren't included.
+    pub bytes_saved: HashMap<String, usize>,
+
     pub telemetry: SyncTelemetryPing,
 
     pub next_sync_after: Option<std::time::SystemTime>,
@@ -11,9 +11,15 @@ use crate::engine::{CollectionPost, CollectionRequest};
 use crate::error::{self, Error, ErrorResponse};
 use crate::record_types::MetaGlobalRecord;
 use crate::{CollectionName, Guid, ServerTimestamp};
+use flate2::{write::GzEncoder, Compression};
+use interrupt_support::Interrupted;
 use serde_json::Value;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::Write;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
 use url::Url;
 use viaduct::{
     header_names::{self, AUTHORIZATION},
@@ -160,6 +166,21 @@ pub(crate) fn new_backoff_listener() -> BackoffListener {
     std::sync::Arc::new(BackoffState::default())
 }
 
+// Gives `req` whatever time is left before `deadline` as its timeout, or fails without sending
+// it if there's none left.
+pub(crate) fn with_deadline(req: Request, deadline: Option<Instant>) -> error::Result<Request> {
+    match deadline {
+        Some(deadline) => {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Interrupted.into());
+            }
+            Ok(req.timeout(remaining))
+        }
+        None => Ok(req),
+    }
+}
+
 impl BackoffState {
     pub fn note_backoff(&self, noted: u32) {
         super::util::atomic_update_max(&self.backoff_secs, noted)
@@ -204,6 +225,12 @@ type OutMetaGlobalBso = OutgoingBso;
 pub struct Sync15StorageClient {
     tsc: token::TokenProvider,
     pub(crate) backoff: BackoffListener,
+    // Whether the server has told us it accepts gzipped request bodies, via an
+    // `Accept-Encoding` response header (RFC 7694).
+    gzip_uploads: AtomicBool,
+    // When the current sync has to be finished by, if it has a time limit. Requests are given
+    // the time left as their timeout.
+    deadline: Option<Instant>,
 }
 
 impl SetupStorageClient for Sync15StorageClient {
@@ -288,9 +315,17 @@ impl Sync15StorageClient {
         Ok(Sync15StorageClient {
             tsc,
             backoff: new_backoff_listener(),
+            gzip_uploads: AtomicBool::new(false),
+            deadline: None,
         })
     }
 
+    /// Bound every request this client makes, including fetching tokens, by `deadline`.
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+        self.tsc.set_deadline(deadline);
+    }
+
     pub fn get_encrypted_records(
         &self,
         collection_request: CollectionRequest,
@@ -338,7 +373,11 @@ impl Sync15StorageClient {
             req.url.path(),
             req.url.query()
         );
-        let resp = req.send()?;
+        let resp = with_deadline(req, self.deadline)?.send()?;
+        if let Some(accepted) = resp.headers.get(header_names::ACCEPT_ENCODING) {
+            self.gzip_uploads
+                .store(accepts_gzip(accepted), Ordering::Relaxed);
+        }
 
         let result = Sync15ClientResponse::from_response(resp, &self.backoff)?;
         match result {
@@ -372,7 +411,11 @@ impl Sync15StorageClient {
         ts: ServerTimestamp,
         on_response: F,
     ) -> error::Result<PostQueue<PostWrapper<'a>, F>> {
-        let pw = PostWrapper { client: self, coll };
+        let pw = PostWrapper {
+            client: self,
+            coll,
+            bytes_saved: Cell::new(0),
+        };
         Ok(PostQueue::new(config, ts, pw, on_response))
     }
 
@@ -407,14 +450,65 @@ impl Sync15StorageClient {
         self.tsc.hashed_uid()
     }
 
-    pub(crate) fn wipe_remote_engine(&self, engine: &str) -> error::Result<()> {
+    /// Fetch the number of records in each collection on the server, from
+    /// `info/collection_counts`. Returns `None` if the server doesn't support it.
+    pub fn fetch_collection_counts(&self) -> error::Result<Option<HashMap<String, u64>>> {
+        self.fetch_optional_info("info/collection_counts")
+    }
+
+    /// Fetch the size of each collection on the server, in KB, from `info/collection_usage`.
+    /// Returns `None` if the server doesn't support it.
+    pub fn fetch_collection_usage(&self) -> error::Result<Option<HashMap<String, f64>>> {
+        self.fetch_optional_info("info/collection_usage")
+    }
+
+    fn fetch_optional_info<T>(&self, relative_path: &str) -> error::Result<Option<T>>
+    where
+        for<'a> T: serde::de::Deserialize<'a>,
+    {
+        match self.relative_storage_request(Method::Get, relative_path)? {
+            Sync15ClientResponse::Success { record, .. } => Ok(Some(record)),
+            Sync15ClientResponse::Error(ErrorResponse::NotFound { .. }) => {
+                log::info!("Server doesn't support {}", relative_path);
+                Ok(None)
+            }
+            resp => Err(resp.create_storage_error()),
+        }
+    }
+
+    /// Delete every record in the collection named `engine` on the server. Returns `false` if
+    /// the collection didn't exist.
+    pub fn wipe_remote_engine(&self, engine: &str) -> error::Result<bool> {
         let s = self.tsc.api_endpoint()? + "/";
         let url = Url::parse(&s)?.join(&format!("storage/{}", engine))?;
         log::debug!("Wiping: {:?}", url);
         let req = self.build_request(Method::Delete, url)?;
         match self.exec_request::<Value>(req, false) {
-            Ok(Sync15ClientResponse::Error(ErrorResponse::NotFound { .. }))
-            | Ok(Sync15ClientResponse::Success { .. }) => Ok(()),
+            Ok(Sync15ClientResponse::Success { .. }) => Ok(true),
+            Ok(Sync15ClientResponse::Error(ErrorResponse::NotFound { .. })) => Ok(false),
+            Ok(resp) => Err(resp.create_storage_error()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the record `id` in `collection` on the server, if the collection hasn't been
+    /// modified since `xius`. Returns the new last-modified time of the collection, or `None`
+    /// if the record didn't exist.
+    pub fn delete_record(
+        &self,
+        collection: &str,
+        id: &Guid,
+        xius: ServerTimestamp,
+    ) -> error::Result<Option<ServerTimestamp>> {
+        let s = self.tsc.api_endpoint()? + "/";
+        let url = Url::parse(&s)?.join(&format!("storage/{}/{}", collection, id))?;
+        log::debug!("Deleting record: {:?}", url);
+        let req = self
+            .build_request(Method::Delete, url)?
+            .header(header_names::X_IF_UNMODIFIED_SINCE, format!("{}", xius))?;
+        match self.exec_request::<Value>(req, false) {
+            Ok(Sync15ClientResponse::Success { last_modified, .. }) => Ok(Some(last_modified)),
+            Ok(Sync15ClientResponse::Error(ErrorResponse::NotFound { .. })) => Ok(None),
             Ok(resp) => Err(resp.create_storage_error()),
             Err(e) => Err(e),
         }
@@ -424,29 +518,92 @@ impl Sync15StorageClient {
 pub struct PostWrapper<'a> {
     client: &'a Sync15StorageClient,
     coll: &'a CollectionName,
+    bytes_saved: Cell<usize>,
 }
 
-impl<'a> BatchPoster for PostWrapper<'a> {
-    fn post<T, O>(
+impl<'a> PostWrapper<'a> {
+    fn send(
         &self,
         bytes: Vec<u8>,
         xius: ServerTimestamp,
         batch: Option<String>,
         commit: bool,
-        _: &PostQueue<T, O>,
+        gzipped: bool,
     ) -> error::Result<PostResponse> {
         let r = CollectionPost::new(self.coll.clone())
             .batch(batch)
             .commit(commit);
         let url = build_collection_post_url(Url::parse(&self.client.tsc.api_endpoint()?)?, r)?;
 
-        let req = self
+        let mut req = self
             .client
             .build_request(Method::Post, url)?
             .header(header_names::CONTENT_TYPE, "application/json")?
-            .header(header_names::X_IF_UNMODIFIED_SINCE, format!("{}", xius))?
-            .body(bytes);
-        self.client.exec_request(req, false)
+            .header(header_names::X_IF_UNMODIFIED_SINCE, format!("{}", xius))?;
+        if gzipped {
+            req = req.header(header_names::CONTENT_ENCODING, "gzip")?;
+        }
+        self.client.exec_request(req.body(bytes), false)
+    }
+}
+
+impl<'a> BatchPoster for PostWrapper<'a> {
+    fn post<T, O>(
+        &self,
+        bytes: Vec<u8>,
+        xius: ServerTimestamp,
+        batch: Option<String>,
+        commit: bool,
+        _: &PostQueue<T, O>,
+    ) -> error::Result<PostResponse> {
+        if self.client.gzip_uploads.load(Ordering::Relaxed) {
+            if let Some(compressed) = gzip_if_smaller(&bytes) {
+                let saved = bytes.len() - compressed.len();
+                match self.send(compressed, xius, batch.clone(), commit, true)? {
+                    // The server changed its mind, so send the same post uncompressed.
+                    Sync15ClientResponse::Error(ErrorResponse::RequestFailed {
+                        status: 415,
+                        ..
+                    }) => {
+                        log::info!("Server rejected a gzipped upload; no longer compressing");
+                        self.client.gzip_uploads.store(false, Ordering::Relaxed);
+                    }
+                    resp => {
+                        self.bytes_saved.set(self.bytes_saved.get() + saved);
+                        return Ok(resp);
+                    }
+                }
+            }
+        }
+        self.send(bytes, xius, batch, commit, false)
+    }
+
+    fn bytes_saved(&self) -> usize {
+        self.bytes_saved.get()
+    }
+}
+
+fn accepts_gzip(accept_encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut parts = coding.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        // A quality of zero means "not acceptable".
+        let rejected =
+            parts.any(|p| p.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0));
+        name.eq_ignore_ascii_case("gzip") && !rejected
+    })
+}
+
+// Returns `None` if compressing wouldn't make the body any smaller, which can happen for very
+// small posts.
+fn gzip_if_smaller(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(bytes.len()), Compression::default());
+    encoder.write_all(bytes).ok()?;
+    let compressed = encoder.finish().ok()?;
+    if compressed.len() < bytes.len() {
+        Some(compressed)
+    } else {
+        None
     }
 }
 
@@ -536,6 +693,33 @@ mod test {
         assert_eq!(parse_seconds("4294967296"), None);
     }
 
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip("gzip"));
+        assert!(accepts_gzip("deflate, GZIP;q=0.5"));
+        assert!(!accepts_gzip("identity"));
+        assert!(!accepts_gzip("gzip;q=0, deflate"));
+        assert!(!accepts_gzip(""));
+    }
+
+    #[test]
+    fn test_gzip_if_smaller() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let body =
+            br#"[{"id":"aaaaaaaaaaaa"},{"id":"bbbbbbbbbbbb"},{"id":"cccccccccccc"}]"#.repeat(10);
+        let compressed = gzip_if_smaller(&body).unwrap();
+        assert!(compressed.len() < body.len());
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+
+        assert!(gzip_if_smaller(b"[]").is_none());
+    }
+
     #[test]
     fn test_query_building() {
         use crate::engine::RequestOrder;
@@ -602,4 +786,23 @@ mod test {
             "https://example.com/sync/storage/asdf?batch=1234abc&commit=true"
         );
     }
+
+    #[test]
+    fn test_with_deadline() {
+        let url = Url::parse("https://example.com/sync").unwrap();
+        let req = with_deadline(Request::get(url.clone()), None).unwrap();
+        assert_eq!(req.timeout_override(), None);
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(60);
+        let req = with_deadline(Request::get(url.clone()), Some(deadline)).unwrap();
+        let timeout = req.timeout_override().unwrap();
+        assert!(timeout > std::time::Duration::ZERO);
+        assert!(timeout <= std::time::Duration::from_secs(60));
+
+        // Requests aren't sent once the deadline has passed.
+        assert!(matches!(
+            with_deadline(Request::get(url), Some(Instant::now())),
+            Err(Error::Interrupted(_))
+        ));
+    }
 }
@@ -7,9 +7,19 @@ use crate::clients_engine;
 use crate::engine::SyncEngine;
 use crate::error::Error;
 use crate::telemetry;
-use crate::KeyBundle;
+use crate::{Guid, KeyBundle};
 use interrupt_support::Interruptee;
 
+/// What a single engine's sync did with its outgoing records, for the `SyncResult`.
+#[derive(Debug, Default)]
+pub struct UploadOutcome {
+    /// The IDs of outgoing records which were skipped because they're too large for the server.
+    pub skipped_ids: Vec<Guid>,
+    /// The number of bytes we didn't need to upload because the uploads were compressed.
+    pub bytes_saved: usize,
+}
+
+/// Sync a single engine, returning what happened to its outgoing records.
 #[allow(clippy::too_many_arguments)]
 pub fn synchronize_with_clients_engine(
     client: &Sync15StorageClient,
@@ -20,7 +30,7 @@ pub fn synchronize_with_clients_engine(
     fully_atomic: bool,
     telem_engine: &mut telemetry::Engine,
     interruptee: &dyn Interruptee,
-) -> Result<(), Error> {
+) -> Result<UploadOutcome, Error> {
     let collection = engine.collection_name();
     log::info!("Syncing collection {}", collection);
 
@@ -33,7 +43,7 @@ pub fn synchronize_with_clients_engine(
                 "can't setup for the {} collection - hopefully it works later",
                 collection
             );
-            return Ok(());
+            return Ok(UploadOutcome::default());
         }
     };
 
@@ -93,14 +103,17 @@ pub fn synchronize_with_clients_engine(
     )?
     .upload()?;
     log::info!(
-        "Upload success ({} records success, {} records failed)",
+        "Upload success ({} records success, {} records failed, {} records too large)",
         upload_info.successful_ids.len(),
-        upload_info.failed_ids.len()
+        upload_info.failed_ids.len(),
+        upload_info.skipped_ids.len()
     );
 
+    let failed = upload_info.failed_ids.len() + upload_info.skipped_ids.len();
     let mut telem_outgoing = telemetry::EngineOutgoing::new();
-    telem_outgoing.sent(upload_info.successful_ids.len() + upload_info.failed_ids.len());
-    telem_outgoing.failed(upload_info.failed_ids.len());
+    telem_outgoing.sent(upload_info.successful_ids.len() + failed);
+    telem_outgoing.failed(failed);
+    telem_outgoing.bytes_saved(upload_info.bytes_saved);
     telem_engine.outgoing(telem_outgoing);
 
     engine.set_uploaded(upload_info.modified_timestamp, upload_info.successful_ids)?;
@@ -110,5 +123,8 @@ pub fn synchronize_with_clients_engine(
     engine.sync_finished()?;
 
     log::info!("Sync finished!");
-    Ok(())
+    Ok(UploadOutcome {
+        skipped_ids: upload_info.skipped_ids,
+        bytes_saved: upload_info.bytes_saved,
+    })
 }
@@ -17,7 +17,7 @@ use interrupt_support::Interruptee;
 use std::collections::HashMap;
 use std::mem;
 use std::result;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Info about the client to use. We reuse the client unless
 /// we discover the client_init has changed, in which case we re-create one.
@@ -133,6 +133,8 @@ pub fn sync_multiple_with_command_processor(
         declined: None,
         next_sync_after: None,
         engine_results: HashMap::with_capacity(engines.len()),
+        skipped_records: HashMap::new(),
+        bytes_saved: HashMap::new(),
         telemetry: telemetry::SyncTelemetryPing::new(),
     };
     let backoff = super::storage_client::new_backoff_listener();
@@ -143,6 +145,7 @@ pub fn sync_multiple_with_command_processor(
         storage_init,
         interruptee,
         engines_to_state_change: req_info.engines_to_state_change,
+        deadline: req_info.deadline,
         backoff: backoff.clone(),
         root_sync_key,
         result: &mut sync_result,
@@ -182,6 +185,9 @@ pub fn sync_multiple_with_command_processor(
 pub struct SyncRequestInfo<'a> {
     pub engines_to_state_change: Option<&'a HashMap<String, bool>>,
     pub is_user_action: bool,
+    // If set, each request is given the time left before this as its timeout, and requests
+    // aren't sent once it's passed.
+    pub deadline: Option<Instant>,
 }
 
 // The sync multiple driver
@@ -193,6 +199,7 @@ struct SyncMultipleDriver<'info, 'res, 'pgs, 'mcs> {
     interruptee: &'info dyn Interruptee,
     backoff: BackoffListener,
     engines_to_state_change: Option<&'info HashMap<String, bool>>,
+    deadline: Option<Instant>,
     result: &'res mut SyncResult,
     persisted_global_state: &'pgs mut Option<String>,
     mem_cached_state: &'mcs mut MemoryCachedState,
@@ -316,10 +323,24 @@ impl<'info, 'res, 'pgs, 'mcs> SyncMultipleDriver<'info, 'res, 'pgs, 'mcs> {
                 self.root_sync_key,
                 clients,
                 *engine,
-                true,
+                engine.upload_must_be_atomic(),
                 &mut telem_engine,
                 self.interruptee,
-            );
+            )
+            .map(|outcome| {
+                let skipped = outcome.skipped_ids;
+                if !skipped.is_empty() {
+                    log::warn!("{} records were too large to upload", skipped.len());
+                    self.result
+                        .skipped_records
+                        .insert(name.to_string(), skipped);
+                }
+                if outcome.bytes_saved > 0 {
+                    self.result
+                        .bytes_saved
+                        .insert(name.to_string(), outcome.bytes_saved);
+                }
+            });
 
             match result {
                 Ok(()) => log::info!("Sync of {} was successful!", name),
@@ -451,6 +472,8 @@ impl<'info, 'res, 'pgs, 'mcs> SyncMultipleDriver<'info, 'res, 'pgs, 'mcs> {
         // Ensure we use the correct listener here rather than on all the branches
         // above, since it seems less error prone.
         client_info.client.backoff = self.backoff.clone();
+        // Likewise for the deadline, which is different for every sync.
+        client_info.client.set_deadline(self.deadline);
         Ok(client_info)
     }
 
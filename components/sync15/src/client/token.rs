@@ -9,7 +9,7 @@ use serde_derive::*;
 use std::borrow::{Borrow, Cow};
 use std::cell::RefCell;
 use std::fmt;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use url::Url;
 use viaduct::{header_names, Request};
 
@@ -61,6 +61,8 @@ struct TokenServerFetcher {
     server_url: Url,
     access_token: String,
     key_id: String,
+    // See `Sync15StorageClient::set_deadline()`.
+    deadline: Option<Instant>,
 }
 
 fn fixup_server_url(mut url: Url) -> url::Url {
@@ -93,6 +95,7 @@ impl TokenServerFetcher {
             server_url: fixup_server_url(base_url),
             access_token,
             key_id,
+            deadline: None,
         }
     }
 }
@@ -100,7 +103,11 @@ impl TokenServerFetcher {
 impl TokenFetcher for TokenServerFetcher {
     fn fetch_token(&self) -> Result<TokenFetchResult> {
         log::debug!("Fetching token from {}", self.server_url);
-        let resp = Request::get(self.server_url.clone())
+        let req = super::storage_client::with_deadline(
+            Request::get(self.server_url.clone()),
+            self.deadline,
+        )?;
+        let resp = req
             .header(
                 header_names::AUTHORIZATION,
                 format!("Bearer {}", self.access_token),
@@ -409,6 +416,10 @@ impl TokenProvider {
         })
     }
 
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.imp.fetcher.deadline = deadline;
+    }
+
     pub fn hashed_uid(&self) -> Result<String> {
         self.imp.hashed_uid()
     }
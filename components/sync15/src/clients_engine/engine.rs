@@ -20,7 +20,7 @@ use super::{
     Command, CommandProcessor, CommandStatus, CLIENTS_TTL,
 };
 
-const COLLECTION_NAME: &str = "clients";
+pub(super) const COLLECTION_NAME: &str = "clients";
 
 /// The driver for the clients engine. Internal; split out from the `Engine`
 /// struct to make testing easier.
@@ -0,0 +1,310 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Inspecting and cleaning up the clients collection outside of a sync. Records for devices
+//! which haven't synced in a long time stay on the server until their TTL expires, and show
+//! up as synced tabs devices in the meantime.
+
+use crate::bso::{IncomingBso, IncomingKind};
+use crate::client::{
+    CollectionKeys, SetupStorageClient, Sync15ClientResponse, Sync15StorageClient,
+};
+use crate::engine::CollectionRequest;
+use crate::error::{Error, ErrorResponse, Result};
+use crate::{DeviceType, Guid, KeyBundle, ServerTimestamp};
+
+use super::{engine::COLLECTION_NAME, record::ClientRecord};
+
+/// A record in the clients collection on the server.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientRecordInfo {
+    /// The ID of the record, which is the FxA device ID for clients which have one.
+    pub id: String,
+    pub fxa_device_id: Option<String>,
+    pub device_name: String,
+    pub device_type: DeviceType,
+    /// When the client last uploaded its record. Clients upload their record at least weekly
+    /// while they're syncing.
+    pub last_modified: ServerTimestamp,
+}
+
+/// Fetches every record in the clients collection, and the last-modified time of the
+/// collection. Tombstones and records which can't be decrypted or read are left out.
+pub fn fetch_client_records(
+    storage_client: &Sync15StorageClient,
+    root_sync_key: &KeyBundle,
+) -> Result<(Vec<ClientRecordInfo>, ServerTimestamp)> {
+    let key = match storage_client.fetch_crypto_keys()? {
+        Sync15ClientResponse::Success {
+            record,
+            last_modified,
+            ..
+        } => CollectionKeys::from_encrypted_payload(record.payload, last_modified, root_sync_key)?
+            .key_for_collection(COLLECTION_NAME)
+            .clone(),
+        other => return Err(other.create_storage_error()),
+    };
+    let (records, last_modified) = match storage_client
+        .get_encrypted_records(CollectionRequest::new(COLLECTION_NAME.into()).full())?
+    {
+        Sync15ClientResponse::Success {
+            record,
+            last_modified,
+            ..
+        } => (record, last_modified),
+        other => return Err(other.create_storage_error()),
+    };
+    let inbound = records
+        .into_iter()
+        .filter_map(|record| {
+            let id = record.envelope.id.clone();
+            match record.into_decrypted(&key) {
+                Ok(bso) => Some(bso),
+                Err(e) => {
+                    log::warn!(
+                        "Skipping client record {} which can't be decrypted: {}",
+                        id,
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok((client_record_infos(inbound), last_modified))
+}
+
+fn client_record_infos(inbound: Vec<IncomingBso>) -> Vec<ClientRecordInfo> {
+    inbound
+        .into_iter()
+        .filter_map(|bso| {
+            let content = bso.into_content::<ClientRecord>();
+            match content.kind {
+                IncomingKind::Content(client) => Some(ClientRecordInfo {
+                    id: client.id,
+                    fxa_device_id: client.fxa_device_id,
+                    device_name: client.name,
+                    device_type: client.typ,
+                    last_modified: content.envelope.modified,
+                }),
+                IncomingKind::Tombstone => None,
+                IncomingKind::Malformed => {
+                    log::warn!("Skipping malformed client record {}", content.envelope.id);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Returns the records which haven't been modified since `cutoff`, other than the record for
+/// `local_client_id`, which is never stale.
+pub fn find_stale_client_records(
+    records: Vec<ClientRecordInfo>,
+    local_client_id: &str,
+    cutoff: ServerTimestamp,
+) -> Vec<ClientRecordInfo> {
+    records
+        .into_iter()
+        .filter(|record| record.id != local_client_id && record.last_modified < cutoff)
+        .collect()
+}
+
+/// The client records which `delete_client_records` deleted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeletedClientRecords {
+    /// The IDs of the records which were deleted.
+    pub ids: Vec<String>,
+    /// False if another client changed the collection part way through, so we stopped before
+    /// trying to delete all the records.
+    pub complete: bool,
+}
+
+/// Deletes client records from the server. Records which are already gone are left out of
+/// the result. `last_modified` is the last-modified time of the collection when the records
+/// were fetched; if another client changes the collection after that, this stops rather than
+/// deleting a record which might have been refreshed, and returns the records it had already
+/// deleted as incomplete.
+pub fn delete_client_records(
+    storage_client: &Sync15StorageClient,
+    ids: &[String],
+    last_modified: ServerTimestamp,
+) -> Result<DeletedClientRecords> {
+    delete_records_until_conflict(ids, last_modified, |id, xius| {
+        storage_client.delete_record(COLLECTION_NAME, &Guid::new(id), xius)
+    })
+}
+
+fn delete_records_until_conflict(
+    ids: &[String],
+    last_modified: ServerTimestamp,
+    mut delete_record: impl FnMut(&str, ServerTimestamp) -> Result<Option<ServerTimestamp>>,
+) -> Result<DeletedClientRecords> {
+    let mut xius = last_modified;
+    let mut deleted = Vec::new();
+    for id in ids {
+        match delete_record(id, xius) {
+            Ok(Some(modified)) => {
+                xius = modified;
+                deleted.push(id.clone());
+            }
+            Ok(None) => {}
+            Err(Error::StorageHttpError(ErrorResponse::PreconditionFailed { .. })) => {
+                log::info!(
+                    "The clients collection changed after deleting {} records",
+                    deleted.len()
+                );
+                return Ok(DeletedClientRecords {
+                    ids: deleted,
+                    complete: false,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(DeletedClientRecords {
+        ids: deleted,
+        complete: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn info(id: &str, last_modified: i64) -> ClientRecordInfo {
+        ClientRecordInfo {
+            id: id.into(),
+            fxa_device_id: Some(id.into()),
+            device_name: format!("Device {}", id),
+            device_type: DeviceType::Mobile,
+            last_modified: ServerTimestamp::from_millis(last_modified),
+        }
+    }
+
+    #[test]
+    fn test_client_record_infos() {
+        let inbound = vec![
+            IncomingBso::from_test_content_ts(
+                json!({
+                    "id": "deviceAAAAAA",
+                    "name": "Laptop",
+                    "type": "desktop",
+                    "fxaDeviceId": "deviceAAAAAA",
+                }),
+                ServerTimestamp::from_millis(1000),
+            ),
+            IncomingBso::from_test_content_ts(
+                json!({
+                    "id": "deviceBBBBBB",
+                    "name": "Phone",
+                    "type": "mobile",
+                }),
+                ServerTimestamp::from_millis(2000),
+            ),
+            IncomingBso::new_test_tombstone(Guid::new("deviceCCCCCC")),
+        ];
+        assert_eq!(
+            client_record_infos(inbound),
+            vec![
+                ClientRecordInfo {
+                    id: "deviceAAAAAA".into(),
+                    fxa_device_id: Some("deviceAAAAAA".into()),
+                    device_name: "Laptop".into(),
+                    device_type: DeviceType::Desktop,
+                    last_modified: ServerTimestamp::from_millis(1000),
+                },
+                ClientRecordInfo {
+                    id: "deviceBBBBBB".into(),
+                    fxa_device_id: None,
+                    device_name: "Phone".into(),
+                    device_type: DeviceType::Mobile,
+                    last_modified: ServerTimestamp::from_millis(2000),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_stale_client_records() {
+        let records = vec![
+            info("deviceAAAAAA", 1000),
+            info("deviceBBBBBB", 5000),
+            // Our own record is never stale, even if we haven't synced for a while.
+            info("deviceCCCCCC", 1000),
+        ];
+        assert_eq!(
+            find_stale_client_records(records, "deviceCCCCCC", ServerTimestamp::from_millis(2000)),
+            vec![info("deviceAAAAAA", 1000)]
+        );
+    }
+
+    #[test]
+    fn test_delete_records_until_conflict() {
+        let ids: Vec<String> = vec![
+            "deviceAAAAAA".into(),
+            "deviceBBBBBB".into(),
+            "deviceCCCCCC".into(),
+        ];
+        let mut requests = Vec::new();
+        let result =
+            delete_records_until_conflict(&ids, ServerTimestamp::from_millis(1000), |id, xius| {
+                requests.push((id.to_string(), xius));
+                Ok(match id {
+                    // Already gone.
+                    "deviceBBBBBB" => None,
+                    _ => Some(ServerTimestamp::from_millis(1000 + requests.len() as i64)),
+                })
+            })
+            .unwrap();
+        assert_eq!(
+            result,
+            DeletedClientRecords {
+                ids: vec!["deviceAAAAAA".into(), "deviceCCCCCC".into()],
+                complete: true,
+            }
+        );
+        // Each delete is conditional on the collection not changing since the last one.
+        assert_eq!(
+            requests,
+            vec![
+                ("deviceAAAAAA".into(), ServerTimestamp::from_millis(1000)),
+                ("deviceBBBBBB".into(), ServerTimestamp::from_millis(1001)),
+                ("deviceCCCCCC".into(), ServerTimestamp::from_millis(1001)),
+            ]
+        );
+
+        // If another client changes the collection, we stop, but still report what we deleted.
+        let result = delete_records_until_conflict(
+            &ids,
+            ServerTimestamp::from_millis(1000),
+            |id, _| match id {
+                "deviceBBBBBB" => Err(Error::StorageHttpError(ErrorResponse::PreconditionFailed {
+                    route: "storage/clients".into(),
+                })),
+                _ => Ok(Some(ServerTimestamp::from_millis(2000))),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            DeletedClientRecords {
+                ids: vec!["deviceAAAAAA".into()],
+                complete: false,
+            }
+        );
+
+        // Other errors still fail.
+        assert!(
+            delete_records_until_conflict(&ids, ServerTimestamp::from_millis(1000), |_, _| Err(
+                Error::StorageHttpError(ErrorResponse::ServerError {
+                    route: "storage/clients".into(),
+                    status: 503,
+                })
+            ),)
+            .is_err()
+        );
+    }
+}
@@ -16,12 +16,17 @@
 use std::collections::HashSet;
 
 mod engine;
+mod maintenance;
 mod record;
 mod ser;
 
 use crate::DeviceType;
 use anyhow::Result;
 pub use engine::Engine;
+pub use maintenance::{
+    delete_client_records, fetch_client_records, find_stale_client_records, ClientRecordInfo,
+    DeletedClientRecords,
+};
 
 // These are what desktop uses.
 const CLIENTS_TTL: u32 = 15_552_000; // 180 days
@@ -4,6 +4,7 @@
 
 use crate::error;
 use crate::key_bundle::KeyBundle;
+use crate::payload_cipher::get_payload_cipher;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
@@ -24,7 +25,7 @@ impl EncryptedPayload {
     }
 
     pub fn decrypt(&self, key: &KeyBundle) -> error::Result<String> {
-        key.decrypt(&self.ciphertext, &self.iv, &self.hmac)
+        get_payload_cipher().decrypt(key, self)
     }
 
     pub fn decrypt_into<T>(&self, key: &KeyBundle) -> error::Result<T>
@@ -35,13 +36,7 @@ impl EncryptedPayload {
     }
 
     pub fn from_cleartext(key: &KeyBundle, cleartext: String) -> error::Result<Self> {
-        let (enc_base64, iv_base64, hmac_base16) =
-            key.encrypt_bytes_rand_iv(cleartext.as_bytes())?;
-        Ok(EncryptedPayload {
-            iv: iv_base64,
-            hmac: hmac_base16,
-            ciphertext: enc_base64,
-        })
+        get_payload_cipher().encrypt(key, &cleartext)
     }
 
     pub fn from_cleartext_payload<T: Serialize>(
@@ -194,6 +194,14 @@ pub trait SyncEngine {
     /// dynamically based on payload sizes and counts via the server's advertised limits.
     fn set_uploaded(&self, new_timestamp: ServerTimestamp, ids: Vec<Guid>) -> Result<()>;
 
+    /// Whether the engine's outgoing records must all be uploaded, or none of them. Engines
+    /// whose records don't depend on each other can return false, so that a record which the
+    /// server rejects, or which is too large to upload, is left for a later sync instead of
+    /// failing the whole sync. Only the uploaded records are passed to `set_uploaded()`.
+    fn upload_must_be_atomic(&self) -> bool {
+        true
+    }
+
     /// Called once the sync is finished. Not currently called if uploads fail (which
     /// seems sad, but the other batching confusion there needs sorting out first).
     /// Many engines will have nothing to do here, as most "post upload" work should be
@@ -224,6 +232,13 @@ pub trait SyncEngine {
     fn reset(&self, assoc: &EngineSyncAssociation) -> Result<()>;
 
     fn wipe(&self) -> Result<()>;
+
+    /// Whether there are local changes which the next sync would upload. This is
+    /// used to show the user that a sync is pending, so it must be cheap and must
+    /// not touch the network. Engines which can't tell return false.
+    fn has_pending_changes(&self) -> Result<bool> {
+        Ok(false)
+    }
 }
 
 #[cfg(test)]
@@ -49,6 +49,10 @@ pub enum Error {
     #[error("HAWK error: {0}")]
     HawkError(#[from] rc_crypto::hawk::Error),
 
+    #[cfg(feature = "crypto")]
+    #[error("The payload cipher has already been set")]
+    SetPayloadCipherError,
+
     //
     // Errors specific to this module.
     //
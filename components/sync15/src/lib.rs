@@ -23,6 +23,8 @@ pub mod engine;
 mod error;
 #[cfg(feature = "crypto")]
 mod key_bundle;
+#[cfg(feature = "crypto")]
+pub mod payload_cipher;
 mod record_types;
 mod server_timestamp;
 pub mod telemetry;
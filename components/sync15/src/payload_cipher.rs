@@ -0,0 +1,80 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Lets an embedder take over the encryption and decryption of record payloads, for example
+//! to do it with a key management service (KMS) rather than with the built-in code.
+//!
+//! This is a Rust API only: there are no Kotlin or Swift bindings, so Android and iOS apps
+//! can't provide a cipher.
+//!
+//! Every `EncryptedPayload` is encrypted and decrypted by the cipher set with
+//! [`set_payload_cipher`], which includes the records of every collection, `crypto/keys`
+//! itself, and anything else which uses `EncryptedPayload` directly. If no cipher is set, we
+//! use [`KeyBundleCipher`], which does exactly what sync has always done.
+//!
+//! # Security
+//!
+//! This is an advanced integration point, and a cipher is trusted completely:
+//!
+//! * It sees the cleartext of every record, and the `KeyBundle` for its collection.
+//! * Other clients on the account must be able to decrypt what it produces, so unless every
+//!   client uses the same cipher it must stay compatible with the built-in AES-256-CBC and
+//!   HMAC-SHA256 scheme.
+//! * Decryption must fail if the HMAC doesn't verify. Returning a bad payload instead
+//!   defeats the integrity checks which stop the server tampering with records.
+//!
+//! A cipher only takes over the encryption, not the keys. The root sync key still comes from
+//! FxA into this process, the collection keys from `crypto/keys` are still decrypted here, and
+//! the cipher is given the `KeyBundle` for each payload, so the keys are always in this
+//! process's memory whichever cipher is used. Sync keeps them in memory only: neither the root
+//! sync key nor the collection keys are ever written to disk, and a custom cipher shouldn't
+//! write them either.
+
+use crate::error::{self, Error};
+use crate::{EncryptedPayload, KeyBundle};
+use once_cell::sync::OnceCell;
+
+/// Encrypts and decrypts the payloads of records. See the module docs before implementing it.
+pub trait PayloadCipher: Send + Sync + 'static {
+    /// Encrypts `cleartext` (a JSON string) for upload, with the keys in `key`.
+    fn encrypt(&self, key: &KeyBundle, cleartext: &str) -> error::Result<EncryptedPayload>;
+
+    /// Verifies and decrypts `payload` with the keys in `key`, returning the cleartext JSON.
+    fn decrypt(&self, key: &KeyBundle, payload: &EncryptedPayload) -> error::Result<String>;
+}
+
+/// The built-in cipher, using the keys in the `KeyBundle`.
+pub struct KeyBundleCipher;
+
+impl PayloadCipher for KeyBundleCipher {
+    fn encrypt(&self, key: &KeyBundle, cleartext: &str) -> error::Result<EncryptedPayload> {
+        let (enc_base64, iv_base64, hmac_base16) =
+            key.encrypt_bytes_rand_iv(cleartext.as_bytes())?;
+        Ok(EncryptedPayload {
+            iv: iv_base64,
+            hmac: hmac_base16,
+            ciphertext: enc_base64,
+        })
+    }
+
+    fn decrypt(&self, key: &KeyBundle, payload: &EncryptedPayload) -> error::Result<String> {
+        key.decrypt(&payload.ciphertext, &payload.iv, &payload.hmac)
+    }
+}
+
+static PAYLOAD_CIPHER: OnceCell<&'static dyn PayloadCipher> = OnceCell::new();
+
+/// Sets the cipher used for all record payloads in this process. This can only be done once,
+/// and should be done before the first sync, so no payloads are handled by the built-in
+/// cipher first.
+pub fn set_payload_cipher(cipher: &'static dyn PayloadCipher) -> error::Result<()> {
+    PAYLOAD_CIPHER
+        .set(cipher)
+        .map_err(|_| Error::SetPayloadCipherError)
+}
+
+pub(crate) fn get_payload_cipher() -> &'static dyn PayloadCipher {
+    // Using the built-in cipher doesn't set it, so a cipher can still be set afterwards.
+    PAYLOAD_CIPHER.get().copied().unwrap_or(&KeyBundleCipher)
+}
@@ -75,6 +75,14 @@ impl Stopwatch {
             }
         }
     }
+
+    // How many milliseconds this took, or has taken so far if it hasn't finished.
+    fn took_ms(&self) -> u64 {
+        match self {
+            Stopwatch::Started(_, si) => si.elapsed().as_millis() as u64,
+            Stopwatch::Finished(wt) => wt.took,
+        }
+    }
 }
 
 impl Serialize for Stopwatch {
@@ -245,6 +253,18 @@ pub enum SyncFailure {
     Http { code: u16 },
 }
 
+impl std::fmt::Display for SyncFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncFailure::Shutdown => write!(f, "shutdown"),
+            SyncFailure::Other { error } => write!(f, "other error: {}", error),
+            SyncFailure::Unexpected { error } => write!(f, "unexpected error: {}", error),
+            SyncFailure::Auth { from } => write!(f, "auth error from {}", from),
+            SyncFailure::Http { code } => write!(f, "HTTP error {}", code),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -380,6 +400,11 @@ pub struct EngineOutgoing {
 
     #[serde(skip_serializing_if = "crate::skip_if_default")]
     failed: usize,
+
+    // The sync ping's schema has no field for this, so it's reported in the `SyncResult`
+    // instead of the ping.
+    #[serde(skip)]
+    bytes_saved: usize,
 }
 
 impl EngineOutgoing {
@@ -398,6 +423,30 @@ impl EngineOutgoing {
     pub fn failed(&mut self, n: usize) {
         self.failed += n;
     }
+
+    /// Bytes we didn't need to send because the upload was compressed.
+    #[inline]
+    pub fn bytes_saved(&mut self, n: usize) {
+        self.bytes_saved += n;
+    }
+
+    /// Get the value of `sent`.
+    #[inline]
+    pub fn get_sent(&self) -> usize {
+        self.sent
+    }
+
+    /// Get the value of `failed`.
+    #[inline]
+    pub fn get_failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Get the value of `bytes_saved`.
+    #[inline]
+    pub fn get_bytes_saved(&self) -> usize {
+        self.bytes_saved
+    }
 }
 
 /// One engine's sync.
@@ -450,6 +499,24 @@ impl Engine {
         self.outgoing.push(out);
     }
 
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// One for each batch posted.
+    pub fn get_outgoing(&self) -> &[EngineOutgoing] {
+        &self.outgoing
+    }
+
+    pub fn get_failure(&self) -> Option<&SyncFailure> {
+        self.failure.as_ref()
+    }
+
+    /// How many milliseconds this engine's sync took.
+    pub fn get_took_ms(&self) -> u64 {
+        self.when_took.took_ms()
+    }
+
     pub fn failure(&mut self, err: impl Into<SyncFailure>) {
         // Currently we take the first error, under the assumption that the
         // first is the most important and all others stem from that.
@@ -574,6 +641,23 @@ mod engine_tests {
         );
     }
 
+    #[test]
+    fn test_outgoing_bytes_saved() {
+        let mut o = EngineOutgoing::new();
+        o.sent(3);
+        o.bytes_saved(1024);
+        o.bytes_saved(512);
+        assert_eq!(o.get_bytes_saved(), 1536);
+        let mut e = Engine::new("TestEngine");
+        e.outgoing(o);
+        e.finished();
+        // It isn't part of the ping.
+        assert_json(
+            &e,
+            serde_json::json!({"name": "TestEngine", "when": 0.0, "outgoing": [{"sent": 3}]}),
+        );
+    }
+
     #[test]
     fn test_failure() {
         let mut e = Engine::new("TestEngine");
@@ -637,6 +721,14 @@ impl SyncTelemetry {
         self.failure = Some(failure);
     }
 
+    pub fn get_engines(&self) -> &[Engine] {
+        &self.engines
+    }
+
+    pub fn get_failure(&self) -> Option<&SyncFailure> {
+        self.failure.as_ref()
+    }
+
     // Note that unlike other 'finished' methods, this isn't private - someone
     // needs to explicitly call this before handling the json payload to
     // whatever ends up submitting it.
@@ -779,6 +871,10 @@ impl SyncTelemetryPing {
     pub fn event(&mut self, e: Event) {
         self.events.push(e);
     }
+
+    pub fn get_syncs(&self) -> &[SyncTelemetry] {
+        &self.syncs
+    }
 }
 
 ffi_support::implement_into_ffi_by_json!(SyncTelemetryPing);
@@ -0,0 +1,57 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// The payload cipher can only be set once per process, so this is tested in its own binary
+// rather than alongside the unit tests, which use the built-in cipher.
+
+#[cfg(feature = "crypto")]
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use sync15::payload_cipher::{set_payload_cipher, KeyBundleCipher, PayloadCipher};
+    use sync15::{EncryptedPayload, Error, KeyBundle, Result};
+
+    // Delegates to the built-in cipher, and counts the calls.
+    struct CountingCipher {
+        encrypted: AtomicUsize,
+        decrypted: AtomicUsize,
+    }
+
+    impl PayloadCipher for CountingCipher {
+        fn encrypt(&self, key: &KeyBundle, cleartext: &str) -> Result<EncryptedPayload> {
+            self.encrypted.fetch_add(1, Ordering::SeqCst);
+            KeyBundleCipher.encrypt(key, cleartext)
+        }
+
+        fn decrypt(&self, key: &KeyBundle, payload: &EncryptedPayload) -> Result<String> {
+            self.decrypted.fetch_add(1, Ordering::SeqCst);
+            KeyBundleCipher.decrypt(key, payload)
+        }
+    }
+
+    static COUNTING_CIPHER: CountingCipher = CountingCipher {
+        encrypted: AtomicUsize::new(0),
+        decrypted: AtomicUsize::new(0),
+    };
+
+    #[test]
+    fn test_set_payload_cipher() {
+        let key = KeyBundle::new_random().unwrap();
+        // Payloads handled before a cipher is set use the built-in one, without setting it.
+        let payload = EncryptedPayload::from_cleartext(&key, "{}".to_string()).unwrap();
+
+        set_payload_cipher(&COUNTING_CIPHER).unwrap();
+        assert!(matches!(
+            set_payload_cipher(&KeyBundleCipher),
+            Err(Error::SetPayloadCipherError)
+        ));
+
+        assert_eq!(payload.decrypt(&key).unwrap(), "{}");
+        assert_eq!(COUNTING_CIPHER.decrypted.load(Ordering::SeqCst), 1);
+        let payload = EncryptedPayload::from_cleartext(&key, "{}".to_string()).unwrap();
+        assert_eq!(COUNTING_CIPHER.encrypted.load(Ordering::SeqCst), 1);
+        assert_eq!(payload.decrypt(&key).unwrap(), "{}");
+        assert_eq!(COUNTING_CIPHER.decrypted.load(Ordering::SeqCst), 2);
+    }
+}
@@ -23,6 +23,50 @@ open class SyncManagerComponent {
         return api.getAvailableEngines()
     }
 
+    public func enginesWithPendingChanges() -> [String] {
+        return api.enginesWithPendingChanges()
+    }
+
+    public func wipeRemote(engine: String, authInfo: SyncAuthInfo) throws -> WipeRemoteResult {
+        return try api.wipeRemote(engine: engine, authInfo: authInfo)
+    }
+
+    public func estimateFirstSync(engines: SyncEngineSelection, authInfo: SyncAuthInfo) throws -> [EngineSyncEstimate] {
+        return try api.estimateFirstSync(engines: engines, authInfo: authInfo)
+    }
+
+    public func listClientRecords(authInfo: SyncAuthInfo) throws -> [ClientRecordInfo] {
+        return try api.listClientRecords(authInfo: authInfo)
+    }
+
+    public func removeStaleClientRecords(
+        localDeviceId: String,
+        maxAgeDays: UInt32,
+        authInfo: SyncAuthInfo
+    ) throws -> [ClientRecordInfo] {
+        return try api.removeStaleClientRecords(
+            localDeviceId: localDeviceId,
+            maxAgeDays: maxAgeDays,
+            authInfo: authInfo
+        )
+    }
+
+    public func pause(interruptRunningSync: Bool) -> SyncPauseGuard {
+        return api.pause(interruptRunningSync: interruptRunningSync)
+    }
+
+    public func resume() {
+        api.resume()
+    }
+
+    public func isPaused() -> Bool {
+        return api.isPaused()
+    }
+
+    public func takeTelemetry() -> SyncTelemetrySummary? {
+        return api.takeTelemetry()
+    }
+
     public static func reportSyncTelemetry(syncResult: SyncResult) throws {
         if let json = syncResult.telemetryJson {
             let telemetry = try RustSyncTelemetryPing.fromJSONString(jsonObjectText: json)
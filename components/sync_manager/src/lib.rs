@@ -14,7 +14,7 @@ pub use sync15::DeviceType;
 pub use error::{Result, SyncManagerError};
 pub use types::*;
 
-use manager::SyncManager;
+use manager::{SyncManager, SyncPauseGuard};
 use parking_lot::Mutex;
 
 lazy_static::lazy_static! {
@@ -3,23 +3,115 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use crate::error::*;
-use crate::types::{ServiceStatus, SyncEngineSelection, SyncParams, SyncReason, SyncResult};
+use crate::types::{
+    ClientRecordInfo, EngineSyncEstimate, RemovedClientRecords, ServiceStatus, SyncAuthInfo,
+    SyncEngineSelection, SyncParams, SyncReason, SyncResult, SyncTelemetrySummary,
+    WipeRemoteResult,
+};
 use crate::{reset, reset_all, wipe};
 use error_support::breadcrumb;
+use interrupt_support::{GlobalInterruptee, Interruptee};
 use parking_lot::Mutex;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use sync15::client::{
-    sync_multiple_with_command_processor, MemoryCachedState, Sync15StorageClientInit,
-    SyncRequestInfo,
+    sync_multiple_with_command_processor, MemoryCachedState, Sync15StorageClient,
+    Sync15StorageClientInit, SyncRequestInfo,
 };
 use sync15::clients_engine::{Command, CommandProcessor, CommandStatus, Settings};
 use sync15::engine::{EngineSyncAssociation, SyncEngine, SyncEngineId};
+use sync15::ServerTimestamp;
 
 #[derive(Default)]
 pub struct SyncManager {
     mem_cached_state: Mutex<Option<MemoryCachedState>>,
+    // Not behind `mem_cached_state`, which is held for the whole of a sync, so that a
+    // sync can be paused while it's running.
+    pause_state: Arc<Mutex<PauseState>>,
+    // A summary of the last sync, until it's read by `take_telemetry()`.
+    last_telemetry: Mutex<Option<SyncTelemetrySummary>>,
+}
+
+#[derive(Default)]
+struct PauseState {
+    // How many `SyncPauseGuard`s haven't resumed yet.
+    count: usize,
+    // Bumped by `SyncManager::resume()`, which releases every pause, so that the guards
+    // from before it don't resume again.
+    generation: u64,
+    // Bumped by each pause which interrupts a running sync.
+    interrupt_count: u64,
+}
+
+/// Returned by `SyncManager::pause()`. Sync stays paused until this is resumed or dropped,
+/// or `SyncManager::resume()` is called.
+pub struct SyncPauseGuard {
+    pause_state: Arc<Mutex<PauseState>>,
+    generation: u64,
+    resumed: Mutex<bool>,
+}
+
+impl SyncPauseGuard {
+    /// Release this pause. Sync can start again once every pause has been released. Calling
+    /// this more than once does nothing.
+    pub fn resume(&self) {
+        let mut resumed = self.resumed.lock();
+        if *resumed {
+            return;
+        }
+        *resumed = true;
+        let mut state = self.pause_state.lock();
+        if state.generation == self.generation {
+            state.count -= 1;
+        }
+    }
+}
+
+impl Drop for SyncPauseGuard {
+    fn drop(&mut self) {
+        self.resume();
+    }
+}
+
+// Interrupted like `GlobalInterruptee`, and also by a pause which asks to interrupt the
+// running sync, or by reaching the deadline from `SyncParams::time_limit_ms`.
+struct SyncInterruptee {
+    global: GlobalInterruptee,
+    pause_state: Arc<Mutex<PauseState>>,
+    start_interrupt_count: u64,
+    deadline: Option<Instant>,
+}
+
+impl SyncInterruptee {
+    fn new(pause_state: Arc<Mutex<PauseState>>, deadline: Option<Instant>) -> Self {
+        let start_interrupt_count = pause_state.lock().interrupt_count;
+        Self {
+            global: GlobalInterruptee::new(),
+            pause_state,
+            start_interrupt_count,
+            deadline,
+        }
+    }
+
+    fn was_paused(&self) -> bool {
+        self.pause_state.lock().interrupt_count != self.start_interrupt_count
+    }
+
+    fn timed_out(&self) -> bool {
+        deadline_passed(self.deadline)
+    }
+}
+
+impl Interruptee for SyncInterruptee {
+    fn was_interrupted(&self) -> bool {
+        self.global.was_interrupted() || self.was_paused() || self.timed_out()
+    }
+}
+
+fn deadline_passed(deadline: Option<Instant>) -> bool {
+    deadline.map_or(false, |deadline| Instant::now() >= deadline)
 }
 
 impl SyncManager {
@@ -63,6 +155,165 @@ impl SyncManager {
         Ok(())
     }
 
+    /// Delete an engine's data on the server, and reset its local sync data so that all of its
+    /// local records are uploaded again on the next sync.
+    ///
+    /// Unlike `wipe()`, the local records are kept, and unlike `reset()`, the server data is
+    /// deleted. This is for recovering from corrupt server data when the local copy is good.
+    pub fn wipe_remote(
+        &self,
+        engine_name: &str,
+        auth_info: SyncAuthInfo,
+    ) -> Result<WipeRemoteResult> {
+        breadcrumb!("SyncManager wipe_remote({})", engine_name);
+        let engine = Self::get_engine(&Self::get_engine_id(engine_name)?)
+            .ok_or_else(|| SyncManagerError::UnsupportedFeature(engine_name.to_string()))?;
+        let collection = engine.collection_name().to_string();
+        let client = new_storage_client(auth_info)?;
+        let had_server_data = client.wipe_remote_engine(&collection)?;
+        // The engine's sync ID no longer matches anything on the server, so on the next sync
+        // it's treated as a first sync, uploading everything.
+        engine.reset(&EngineSyncAssociation::Disconnected)?;
+        Ok(WipeRemoteResult {
+            collection,
+            had_server_data,
+        })
+    }
+
+    /// Estimate how much data the first sync of `engines` will download, so the user can be
+    /// asked to confirm it. This only fetches the record counts and sizes from the server,
+    /// without syncing anything.
+    ///
+    /// Servers which don't report counts or sizes are handled, with the missing values left
+    /// as `None`.
+    pub fn estimate_first_sync(
+        &self,
+        engines: SyncEngineSelection,
+        auth_info: SyncAuthInfo,
+    ) -> Result<Vec<EngineSyncEstimate>> {
+        breadcrumb!("SyncManager estimate_first_sync({:?})", engines);
+        let engines = self.calc_selected_engines(&engines)?;
+        let client = new_storage_client(auth_info)?;
+        let counts = client.fetch_collection_counts()?;
+        let usage = client.fetch_collection_usage()?;
+        Ok(engines
+            .into_iter()
+            .map(|(engine_id, engine)| {
+                let collection = engine.collection_name();
+                // Collections with no records on the server aren't included.
+                let record_count = counts
+                    .as_ref()
+                    .map(|counts| counts.get(&*collection).copied().unwrap_or_default());
+                let estimated_bytes = usage.as_ref().map(|usage| {
+                    let kb = usage.get(&*collection).copied().unwrap_or_default();
+                    (kb * 1024.0).round() as u64
+                });
+                EngineSyncEstimate {
+                    engine: engine_id.to_string(),
+                    record_count,
+                    estimated_bytes,
+                }
+            })
+            .collect())
+    }
+
+    /// List the records in the clients collection on the server, with when each client last
+    /// uploaded its record. This doesn't sync anything.
+    pub fn list_client_records(&self, auth_info: SyncAuthInfo) -> Result<Vec<ClientRecordInfo>> {
+        breadcrumb!("SyncManager list_client_records()");
+        let root_sync_key = sync15::KeyBundle::from_ksync_base64(&auth_info.sync_key)?;
+        let client = new_storage_client(auth_info)?;
+        let (records, _) = sync15::clients_engine::fetch_client_records(&client, &root_sync_key)?;
+        Ok(records.into_iter().map(ClientRecordInfo::from).collect())
+    }
+
+    /// Delete the records in the clients collection for devices which haven't uploaded their
+    /// record in `max_age_days`, so they stop showing up as synced tabs devices. The record for
+    /// `local_device_id` is always kept. Returns the records which were deleted, so the UI can
+    /// confirm them. `max_age_days` must be at least 1.
+    ///
+    /// This is a maintenance operation, separate from syncing. Clients which are still
+    /// connected upload their record again on their next sync. If another client changes the
+    /// collection while this is running, it stops rather than deleting a record which might
+    /// have been refreshed, and the result is marked as incomplete. Calling it again deletes
+    /// the records which are still stale.
+    pub fn remove_stale_client_records(
+        &self,
+        local_device_id: &str,
+        max_age_days: u32,
+        auth_info: SyncAuthInfo,
+    ) -> Result<RemovedClientRecords> {
+        breadcrumb!("SyncManager remove_stale_client_records({})", max_age_days);
+        if max_age_days == 0 {
+            return Err(SyncManagerError::InvalidArgument(
+                "max_age_days must be at least 1".into(),
+            ));
+        }
+        let root_sync_key = sync15::KeyBundle::from_ksync_base64(&auth_info.sync_key)?;
+        let client = new_storage_client(auth_info)?;
+        let max_age = Duration::from_secs(u64::from(max_age_days) * 24 * 60 * 60);
+        let cutoff = SystemTime::now()
+            .checked_sub(max_age)
+            .and_then(|cutoff| cutoff.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(ServerTimestamp::default(), |since_epoch| {
+                ServerTimestamp::from_millis(since_epoch.as_millis() as i64)
+            });
+        let (records, last_modified) =
+            sync15::clients_engine::fetch_client_records(&client, &root_sync_key)?;
+        let stale =
+            sync15::clients_engine::find_stale_client_records(records, local_device_id, cutoff);
+        let ids: Vec<String> = stale.iter().map(|record| record.id.clone()).collect();
+        let deleted = sync15::clients_engine::delete_client_records(&client, &ids, last_modified)?;
+        let deleted_ids: HashSet<String> = deleted.ids.into_iter().collect();
+        Ok(RemovedClientRecords {
+            records: stale
+                .into_iter()
+                .filter(|record| deleted_ids.contains(&record.id))
+                .map(ClientRecordInfo::from)
+                .collect(),
+            complete: deleted.complete,
+        })
+    }
+
+    /// Stop new syncs from starting until the returned guard is resumed or dropped, for
+    /// example while migrating a database or making a bulk edit. Syncs attempted while
+    /// paused return a `SyncResult` with the `Paused` status, without touching the network.
+    ///
+    /// If `interrupt_running_sync` is true, a sync which is already running is interrupted,
+    /// and also returns the `Paused` status. Otherwise, it's allowed to finish.
+    pub fn pause(&self, interrupt_running_sync: bool) -> Arc<SyncPauseGuard> {
+        breadcrumb!("SyncManager pause({})", interrupt_running_sync);
+        let mut state = self.pause_state.lock();
+        state.count += 1;
+        if interrupt_running_sync {
+            state.interrupt_count += 1;
+        }
+        Arc::new(SyncPauseGuard {
+            pause_state: Arc::clone(&self.pause_state),
+            generation: state.generation,
+            resumed: Mutex::new(false),
+        })
+    }
+
+    /// Release every outstanding pause, including ones whose guards haven't been resumed.
+    pub fn resume(&self) {
+        breadcrumb!("SyncManager resume()");
+        let mut state = self.pause_state.lock();
+        state.count = 0;
+        state.generation += 1;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_state.lock().count > 0
+    }
+
+    /// Returns a summary of the last sync, with per-engine counts, durations and errors, for
+    /// apps which record their own sync metrics. The summary is cleared when it's read, so
+    /// this returns None if no sync has finished since the last call.
+    pub fn take_telemetry(&self) -> Option<SyncTelemetrySummary> {
+        self.last_telemetry.lock().take()
+    }
+
     /// Disconnect engines from sync, deleting/resetting the sync-related data
     pub fn disconnect(&self) {
         breadcrumb!("SyncManager disconnect()");
@@ -83,29 +334,45 @@ impl SyncManager {
     }
 
     /// Perform a sync.  See [SyncParams] and [SyncResult] for details on how this works
+    ///
+    /// If `params.time_limit_ms` is set, the time spent waiting for a running sync to finish
+    /// counts towards it, and the sync is interrupted when it runs out. The engines check for
+    /// interruption between fetching, applying and uploading records, and each of those steps
+    /// either completes or leaves the engine as it was, so what's already done is kept. Each
+    /// request to the servers is given the time that's left as its timeout. Applying records to
+    /// the local database isn't interrupted part way though, so the limit should leave some
+    /// margin before the app's own time runs out.
     pub fn sync(&self, params: SyncParams) -> Result<SyncResult> {
         breadcrumb!("SyncManager::sync started");
+        let deadline = params
+            .time_limit_ms
+            .and_then(|ms| Instant::now().checked_add(Duration::from_millis(ms)));
         let mut state = self.mem_cached_state.lock();
+        // Checked after taking the lock, so that a sync which waited for a running one to
+        // finish doesn't start if sync was paused in the meantime.
+        if self.is_paused() {
+            breadcrumb!("Sync is paused, bailing out early");
+            return Ok(not_synced(ServiceStatus::Paused, None, params));
+        }
+        if deadline_passed(deadline) {
+            breadcrumb!("Sync ran out of time before it started, bailing out early");
+            return Ok(not_synced(ServiceStatus::TimedOut, None, params));
+        }
         let engines = self.calc_engines_to_sync(&params.engines)?;
         let next_sync_after = state.as_ref().and_then(|mcs| mcs.get_next_sync_after());
         let result = if !backoff_in_effect(next_sync_after, &params) {
             log::info!("No backoff in effect (or we decided to ignore it), starting sync");
-            self.do_sync(params, &mut state, engines)
+            self.do_sync(params, &mut state, engines, deadline)
         } else {
             breadcrumb!(
                 "Backoff still in effect (until {:?}), bailing out early",
                 next_sync_after
             );
-            Ok(SyncResult {
-                status: ServiceStatus::BackedOff,
-                successful: Default::default(),
-                failures: Default::default(),
-                declined: None,
-                next_sync_allowed_at: next_sync_after,
-                persisted_state: params.persisted_state.unwrap_or_default(),
-                // It would be nice to record telemetry here.
-                telemetry_json: None,
-            })
+            Ok(not_synced(
+                ServiceStatus::BackedOff,
+                next_sync_after,
+                params,
+            ))
         };
         breadcrumb!("SyncManager sync ended");
         result
@@ -116,10 +383,13 @@ impl SyncManager {
         mut params: SyncParams,
         state: &mut Option<MemoryCachedState>,
         mut engines: Vec<Box<dyn SyncEngine>>,
+        deadline: Option<Instant>,
     ) -> Result<SyncResult> {
         let key_bundle = sync15::KeyBundle::from_ksync_base64(&params.auth_info.sync_key)?;
         let tokenserver_url = url::Url::parse(&params.auth_info.tokenserver_url)?;
-        let interruptee = interrupt_support::ShutdownInterruptee;
+        // Interrupted by `interrupt_support::shutdown()` or `interrupt_all()`, by pausing, or
+        // by running out of time.
+        let interruptee = SyncInterruptee::new(Arc::clone(&self.pause_state), deadline);
         let mut mem_cached_state = state.take().unwrap_or_default();
         let mut disk_cached_state = params.persisted_state.take();
 
@@ -149,6 +419,8 @@ impl SyncManager {
             device_type: params.device_settings.kind,
         };
         let c = SyncClient::new(settings);
+        let started_at = SystemTime::now();
+        let start = Instant::now();
         let result = sync_multiple_with_command_processor(
             Some(&c),
             &engine_refs,
@@ -160,12 +432,30 @@ impl SyncManager {
             Some(SyncRequestInfo {
                 engines_to_state_change: engines_to_change,
                 is_user_action: matches!(params.reason, SyncReason::User),
+                deadline,
             }),
         );
         *state = Some(mem_cached_state);
+        *self.last_telemetry.lock() = Some(SyncTelemetrySummary::new(
+            &result.telemetry,
+            started_at,
+            start.elapsed(),
+        ));
 
         log::info!("Sync finished with status {:?}", result.service_status);
-        let status = ServiceStatus::from(result.service_status);
+        let status = match result.service_status {
+            sync15::client::ServiceStatus::Interrupted if interruptee.was_paused() => {
+                ServiceStatus::Paused
+            }
+            // A request which runs out of time fails with a network error.
+            sync15::client::ServiceStatus::Interrupted
+            | sync15::client::ServiceStatus::NetworkError
+                if interruptee.timed_out() =>
+            {
+                ServiceStatus::TimedOut
+            }
+            s15s => ServiceStatus::from(s15s),
+        };
         for (engine, result) in result.engine_results.iter() {
             log::info!("engine {:?} status: {:?}", engine, result);
         }
@@ -181,12 +471,24 @@ impl SyncManager {
                 }
             }
         }
+        let skipped_records = result
+            .skipped_records
+            .into_iter()
+            .map(|(engine, ids)| (engine, ids.into_iter().map(|id| id.into_string()).collect()))
+            .collect();
+        let bytes_saved = result
+            .bytes_saved
+            .into_iter()
+            .map(|(engine, bytes)| (engine, bytes as u64))
+            .collect();
         let telemetry_json = serde_json::to_string(&result.telemetry).unwrap();
 
         Ok(SyncResult {
             status,
             successful,
             failures,
+            skipped_records,
+            bytes_saved,
             declined: result.declined,
             next_sync_allowed_at: result.next_sync_after,
             persisted_state: disk_cached_state.unwrap_or_default(),
@@ -204,10 +506,42 @@ impl SyncManager {
             .collect()
     }
 
+    /// Get the registered engines which have local changes waiting to be uploaded. This only
+    /// reads the local databases, so it's cheap enough to call when showing sync status.
+    ///
+    /// Engines which fail to check are reported and left out.
+    pub fn engines_with_pending_changes(&self) -> Vec<String> {
+        self.iter_registered_engines()
+            .filter_map(|(engine_id, engine)| match engine.has_pending_changes() {
+                Ok(has_changes) => has_changes.then(|| engine_id.to_string()),
+                Err(e) => {
+                    error_support::report_error!(
+                        "sync-manager-pending-changes",
+                        "Failed to check {} for pending changes: {}",
+                        engine_id,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
     fn calc_engines_to_sync(
         &self,
         selection: &SyncEngineSelection,
     ) -> Result<Vec<Box<dyn SyncEngine>>> {
+        Ok(self
+            .calc_selected_engines(selection)?
+            .into_iter()
+            .map(|(_, engine)| engine)
+            .collect())
+    }
+
+    fn calc_selected_engines(
+        &self,
+        selection: &SyncEngineSelection,
+    ) -> Result<Vec<(SyncEngineId, Box<dyn SyncEngine>)>> {
         // BTreeMap to ensure we sync the engines in priority order.
         let mut engine_map: BTreeMap<_, _> = self.iter_registered_engines().collect();
         breadcrumb!(
@@ -234,7 +568,35 @@ impl SyncManager {
             // Filter engines based on the selection
             engine_map.retain(|engine_id, _| selected_engine_ids.contains(engine_id))
         }
-        Ok(engine_map.into_values().collect())
+        Ok(engine_map.into_iter().collect())
+    }
+}
+
+fn new_storage_client(auth_info: SyncAuthInfo) -> Result<Sync15StorageClient> {
+    Ok(Sync15StorageClient::new(Sync15StorageClientInit {
+        key_id: auth_info.kid,
+        access_token: auth_info.fxa_access_token,
+        tokenserver_url: url::Url::parse(&auth_info.tokenserver_url)?,
+    })?)
+}
+
+// The result of a sync which didn't start.
+fn not_synced(
+    status: ServiceStatus,
+    next_sync_allowed_at: Option<SystemTime>,
+    params: SyncParams,
+) -> SyncResult {
+    SyncResult {
+        status,
+        successful: Default::default(),
+        failures: Default::default(),
+        skipped_records: Default::default(),
+        bytes_saved: Default::default(),
+        declined: None,
+        next_sync_allowed_at,
+        persisted_state: params.persisted_state.unwrap_or_default(),
+        // It would be nice to record telemetry here.
+        telemetry_json: None,
     }
 }
 
@@ -317,10 +679,235 @@ impl CommandProcessor for SyncClient {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_wipe_remote_requires_engine() {
+        let auth_info = || SyncAuthInfo {
+            kid: "kid".to_string(),
+            fxa_access_token: "token".to_string(),
+            sync_key: "key".to_string(),
+            tokenserver_url: "https://example.com".to_string(),
+        };
+        let manager = SyncManager::new();
+        assert!(matches!(
+            manager.wipe_remote("not-an-engine", auth_info()),
+            Err(SyncManagerError::UnknownEngine(_))
+        ));
+        // No stores have been registered with the sync manager.
+        assert!(matches!(
+            manager.wipe_remote("passwords", auth_info()),
+            Err(SyncManagerError::UnsupportedFeature(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimate_first_sync_requires_engine() {
+        let auth_info = || SyncAuthInfo {
+            kid: "kid".to_string(),
+            fxa_access_token: "token".to_string(),
+            sync_key: "key".to_string(),
+            tokenserver_url: "https://example.com".to_string(),
+        };
+        let manager = SyncManager::new();
+        let selection = |name: &str| SyncEngineSelection::Some {
+            engines: vec![name.to_string()],
+        };
+        assert!(matches!(
+            manager.estimate_first_sync(selection("not-an-engine"), auth_info()),
+            Err(SyncManagerError::UnknownEngine(_))
+        ));
+        // No stores have been registered with the sync manager.
+        assert!(matches!(
+            manager.estimate_first_sync(selection("passwords"), auth_info()),
+            Err(SyncManagerError::UnsupportedFeature(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_records_require_sync_key() {
+        let auth_info = || SyncAuthInfo {
+            kid: "kid".to_string(),
+            fxa_access_token: "token".to_string(),
+            sync_key: "not a sync key".to_string(),
+            tokenserver_url: "https://example.com".to_string(),
+        };
+        let manager = SyncManager::new();
+        // The sync key is checked before making any requests.
+        assert!(matches!(
+            manager.list_client_records(auth_info()),
+            Err(SyncManagerError::Sync15Error(_))
+        ));
+        assert!(matches!(
+            manager.remove_stale_client_records("device", 30, auth_info()),
+            Err(SyncManagerError::Sync15Error(_))
+        ));
+        // Every record would be stale.
+        assert!(matches!(
+            manager.remove_stale_client_records("device", 0, auth_info()),
+            Err(SyncManagerError::InvalidArgument(_))
+        ));
+    }
+
+    fn sync_params() -> SyncParams {
+        SyncParams {
+            reason: SyncReason::User,
+            engines: SyncEngineSelection::All,
+            enabled_changes: HashMap::new(),
+            local_encryption_keys: HashMap::new(),
+            auth_info: SyncAuthInfo {
+                kid: "kid".to_string(),
+                fxa_access_token: "token".to_string(),
+                sync_key: "key".to_string(),
+                tokenserver_url: "https://example.com".to_string(),
+            },
+            persisted_state: Some("state".to_string()),
+            device_settings: crate::DeviceSettings {
+                fxa_device_id: "device".to_string(),
+                name: "device".to_string(),
+                kind: sync15::DeviceType::Desktop,
+            },
+            time_limit_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_pause() {
+        let manager = SyncManager::new();
+        assert!(!manager.is_paused());
+
+        let guard = manager.pause(false);
+        let other_guard = manager.pause(false);
+        assert!(manager.is_paused());
+        let result = manager.sync(sync_params()).unwrap();
+        assert!(matches!(result.status, ServiceStatus::Paused));
+        // The persisted state is handed back, so it isn't lost.
+        assert_eq!(result.persisted_state, "state");
+
+        // Sync stays paused until every guard is released, and releasing one twice
+        // doesn't count twice.
+        guard.resume();
+        guard.resume();
+        assert!(manager.is_paused());
+        drop(other_guard);
+        assert!(!manager.is_paused());
+
+        // `resume()` releases every pause, and the guards from before it don't affect
+        // later ones.
+        let stale_guard = manager.pause(true);
+        manager.resume();
+        assert!(!manager.is_paused());
+        let guard = manager.pause(false);
+        stale_guard.resume();
+        assert!(manager.is_paused());
+        drop(guard);
+        assert!(!manager.is_paused());
+    }
+
+    #[test]
+    fn test_pause_interrupts_sync() {
+        let manager = SyncManager::new();
+        let interruptee = SyncInterruptee::new(Arc::clone(&manager.pause_state), None);
+        // Pausing without interrupting lets the running sync finish.
+        let _guard = manager.pause(false);
+        assert!(!interruptee.was_interrupted());
+        let _guard = manager.pause(true);
+        assert!(interruptee.was_interrupted());
+        assert!(interruptee.was_paused());
+        // Syncs which start later aren't interrupted by an earlier pause.
+        assert!(!SyncInterruptee::new(Arc::clone(&manager.pause_state), None).was_interrupted());
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let manager = SyncManager::new();
+        let interruptee = SyncInterruptee::new(
+            Arc::clone(&manager.pause_state),
+            Some(Instant::now() + Duration::from_secs(60)),
+        );
+        assert!(!interruptee.was_interrupted());
+        let interruptee =
+            SyncInterruptee::new(Arc::clone(&manager.pause_state), Some(Instant::now()));
+        assert!(interruptee.was_interrupted());
+        assert!(interruptee.timed_out());
+        assert!(!interruptee.was_paused());
+
+        // A sync which has already run out of time doesn't start.
+        let params = SyncParams {
+            reason: SyncReason::Backgrounded,
+            time_limit_ms: Some(0),
+            ..sync_params()
+        };
+        let result = manager.sync(params).unwrap();
+        assert!(matches!(result.status, ServiceStatus::TimedOut));
+        assert_eq!(result.persisted_state, "state");
+
+        // A limit too far away to represent is no limit at all, rather than a panic.
+        let _guard = manager.pause(false);
+        let params = SyncParams {
+            time_limit_ms: Some(u64::MAX),
+            ..sync_params()
+        };
+        let result = manager.sync(params).unwrap();
+        assert!(matches!(result.status, ServiceStatus::Paused));
+    }
+
     #[test]
     fn test_engine_id_sanity() {
         for engine_id in SyncEngineId::iter() {
             assert_eq!(engine_id, SyncEngineId::try_from(engine_id.name()).unwrap());
         }
     }
+
+    #[test]
+    fn test_take_telemetry() {
+        use sync15::telemetry;
+
+        let mut incoming = telemetry::EngineIncoming::new();
+        incoming.applied(3);
+        incoming.failed(1);
+        let mut outgoing = telemetry::EngineOutgoing::new();
+        outgoing.sent(2);
+        let mut more_outgoing = telemetry::EngineOutgoing::new();
+        more_outgoing.sent(4);
+        more_outgoing.failed(1);
+        let mut bookmarks = telemetry::Engine::new("bookmarks");
+        bookmarks.incoming(incoming);
+        bookmarks.outgoing(outgoing);
+        bookmarks.outgoing(more_outgoing);
+        let mut history = telemetry::Engine::new("history");
+        history.failure(telemetry::SyncFailure::Http { code: 503 });
+        let mut sync = telemetry::SyncTelemetry::new();
+        sync.engine(bookmarks);
+        sync.engine(history);
+        sync.finished();
+        let mut ping = telemetry::SyncTelemetryPing::new();
+        ping.sync(sync);
+
+        let started_at = SystemTime::now();
+        let manager = SyncManager::new();
+        assert_eq!(manager.take_telemetry(), None);
+        *manager.last_telemetry.lock() = Some(SyncTelemetrySummary::new(
+            &ping,
+            started_at,
+            Duration::from_millis(1500),
+        ));
+        let summary = manager.take_telemetry().unwrap();
+        assert_eq!(summary.started_at, started_at);
+        assert_eq!(summary.took_ms, 1500);
+        assert_eq!(summary.failure, None);
+        assert_eq!(summary.engines.len(), 2);
+        let bookmarks = &summary.engines[0];
+        assert_eq!(bookmarks.name, "bookmarks");
+        assert_eq!(bookmarks.incoming_applied, 3);
+        assert_eq!(bookmarks.incoming_failed, 1);
+        assert_eq!(bookmarks.outgoing_sent, 6);
+        assert_eq!(bookmarks.outgoing_failed, 1);
+        assert_eq!(bookmarks.failure, None);
+        let history = &summary.engines[1];
+        assert_eq!(history.name, "history");
+        assert_eq!(history.incoming_applied, 0);
+        assert_eq!(history.outgoing_sent, 0);
+        assert_eq!(history.failure.as_deref(), Some("HTTP error 503"));
+        // The summary is cleared once it's been read.
+        assert_eq!(manager.take_telemetry(), None);
+    }
 }
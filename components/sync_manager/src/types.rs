@@ -1,5 +1,7 @@
+use serde_derive::Serialize;
 use std::collections::HashMap;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+use sync15::telemetry::SyncTelemetryPing;
 use sync15::DeviceType;
 
 #[derive(Debug)]
@@ -26,6 +28,10 @@ pub struct SyncParams {
     // Information about the current device, such as its name, formfactor and
     // FxA device ID.
     pub device_settings: DeviceSettings,
+    // If set, the sync is interrupted once this many milliseconds have passed since it was
+    // requested, and returns the `TimedOut` status. Engines stop between steps, so each one
+    // is left either synced or as it was before, never half-applied.
+    pub time_limit_ms: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -67,6 +73,12 @@ pub struct SyncResult {
     pub successful: Vec<String>,
     // Maps the names of engines that failed to sync to the reason why
     pub failures: HashMap<String, String>,
+    // Maps the names of engines to the IDs of outgoing records they didn't upload because
+    // they're larger than the server allows.
+    pub skipped_records: HashMap<String, Vec<String>>,
+    // Maps the names of engines to the number of bytes they didn't need to upload because
+    // the server accepted compressed uploads.
+    pub bytes_saved: HashMap<String, u64>,
     // State that should be persisted to disk and supplied to the sync method
     // on the next sync (See SyncParams.persisted_state).
     pub persisted_state: String,
@@ -83,6 +95,125 @@ pub struct SyncResult {
     pub telemetry_json: Option<String>,
 }
 
+#[derive(Debug)]
+pub struct WipeRemoteResult {
+    // The name of the collection that was deleted on the server
+    pub collection: String,
+    // False if the server didn't have the collection, so there was nothing to delete
+    pub had_server_data: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EngineSyncEstimate {
+    // The name of the engine
+    pub engine: String,
+    // How many records the server has for the engine, or None if the server didn't say
+    pub record_count: Option<u64>,
+    // Roughly how many bytes the engine's records take up on the server, or None if the
+    // server didn't say
+    pub estimated_bytes: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientRecordInfo {
+    // The ID of the record in the clients collection. This is the FxA device ID for
+    // clients which have one.
+    pub id: String,
+    pub fxa_device_id: Option<String>,
+    pub device_name: String,
+    pub device_type: DeviceType,
+    // When the client last uploaded its record. Clients upload it at least weekly while
+    // they're syncing.
+    pub last_modified: SystemTime,
+}
+
+impl From<sync15::clients_engine::ClientRecordInfo> for ClientRecordInfo {
+    fn from(record: sync15::clients_engine::ClientRecordInfo) -> Self {
+        Self {
+            id: record.id,
+            fxa_device_id: record.fxa_device_id,
+            device_name: record.device_name,
+            device_type: record.device_type,
+            last_modified: SystemTime::UNIX_EPOCH
+                + Duration::from_millis(record.last_modified.as_millis().max(0) as u64),
+        }
+    }
+}
+
+// A summary of a sync, for apps which record their own sync metrics, for example on a
+// dashboard. This is separate from the sync ping in `SyncResult.telemetry_json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncTelemetrySummary {
+    pub started_at: SystemTime,
+    pub took_ms: u64,
+    // Why the sync failed, or None if it didn't. Engines can fail without the sync failing.
+    pub failure: Option<String>,
+    // The engines which synced, in the order they synced.
+    pub engines: Vec<EngineTelemetry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EngineTelemetry {
+    pub name: String,
+    pub took_ms: u64,
+    // Incoming records which were applied locally
+    pub incoming_applied: u32,
+    // Incoming records which couldn't be applied
+    pub incoming_failed: u32,
+    // Incoming records which couldn't be applied, and hadn't failed on an earlier sync
+    pub incoming_new_failed: u32,
+    // Incoming records which were reconciled with local changes
+    pub incoming_reconciled: u32,
+    // Outgoing records which were uploaded
+    pub outgoing_sent: u64,
+    // Outgoing records which the server rejected
+    pub outgoing_failed: u64,
+    // Why the engine failed to sync, or None if it didn't
+    pub failure: Option<String>,
+}
+
+impl SyncTelemetrySummary {
+    pub(crate) fn new(ping: &SyncTelemetryPing, started_at: SystemTime, took: Duration) -> Self {
+        let syncs = ping.get_syncs();
+        Self {
+            started_at,
+            took_ms: took.as_millis() as u64,
+            failure: syncs
+                .iter()
+                .find_map(|sync| sync.get_failure())
+                .map(ToString::to_string),
+            engines: syncs
+                .iter()
+                .flat_map(|sync| sync.get_engines())
+                .map(|engine| {
+                    let incoming = engine.get_incoming().as_ref();
+                    let outgoing = engine.get_outgoing();
+                    EngineTelemetry {
+                        name: engine.get_name().to_string(),
+                        took_ms: engine.get_took_ms(),
+                        incoming_applied: incoming.map_or(0, |i| i.get_applied()),
+                        incoming_failed: incoming.map_or(0, |i| i.get_failed()),
+                        incoming_new_failed: incoming.map_or(0, |i| i.get_new_failed()),
+                        incoming_reconciled: incoming.map_or(0, |i| i.get_reconciled()),
+                        outgoing_sent: outgoing.iter().map(|o| o.get_sent() as u64).sum(),
+                        outgoing_failed: outgoing.iter().map(|o| o.get_failed() as u64).sum(),
+                        failure: engine.get_failure().map(ToString::to_string),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RemovedClientRecords {
+    // The records which were deleted
+    pub records: Vec<ClientRecordInfo>,
+    // False if another client changed the clients collection part way through, so we stopped
+    // before deleting every stale record
+    pub complete: bool,
+}
+
 #[derive(Debug)]
 pub enum ServiceStatus {
     Ok,
@@ -91,6 +222,10 @@ pub enum ServiceStatus {
     AuthError,
     BackedOff,
     OtherError,
+    // Sync was paused with `SyncManager::pause()`.
+    Paused,
+    // Sync ran out of time, see `SyncParams::time_limit_ms`.
+    TimedOut,
 }
 
 impl ServiceStatus {
@@ -26,6 +26,27 @@ open class TabsStorage {
         }
     }
 
+    /// Stop uploading local tabs which haven't been used for `days`, or upload all of them if nil.
+    open func setInactiveTabThreshold(days: UInt32?) {
+        queue.sync {
+            self.store.setInactiveTabThreshold(days: days)
+        }
+    }
+
+    /// How many local tabs were left out of the last upload for being inactive.
+    open func inactiveTabsExcluded() -> UInt32 {
+        return queue.sync {
+            self.store.inactiveTabsExcluded()
+        }
+    }
+
+    /// Forget the tabs of a remote client.
+    open func forgetClient(clientId: String) throws -> Bool {
+        return try queue.sync {
+            try self.store.forgetClient(clientId: clientId)
+        }
+    }
+
     open func registerWithSyncManager() {
         queue.sync {
             self.store.registerWithSyncManager()
@@ -18,9 +18,10 @@ use rusqlite::{
 use serde_derive::{Deserialize, Serialize};
 use sql_support::open_database::{self, open_database_with_flags};
 use sql_support::ConnExt;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use sync15::{RemoteClient, ServerTimestamp};
 pub type TabsDeviceType = crate::DeviceType;
 pub type RemoteTabRecord = RemoteTab;
@@ -29,6 +30,7 @@ pub(crate) const TABS_CLIENT_TTL: u32 = 15_552_000; // 180 days, same as CLIENTS
 const FAR_FUTURE: i64 = 4_102_405_200_000; // 2100/01/01
 const MAX_PAYLOAD_SIZE: usize = 512 * 1024; // Twice as big as desktop, still smaller than server max (2MB)
 const MAX_TITLE_CHAR_LENGTH: usize = 512; // We put an upper limit on title sizes for tabs to reduce memory
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RemoteTab {
@@ -80,6 +82,10 @@ fn devicetype_is_unknown(val: &DeviceType) -> bool {
 // no remote tabs in an existing DB is also a normal situation)
 pub struct TabsStorage {
     local_tabs: RefCell<Option<Vec<RemoteTab>>>,
+    // Local tabs which haven't been used for this many days aren't uploaded.
+    inactive_tab_threshold_days: Option<u32>,
+    // How many local tabs were left out of the last upload for being inactive.
+    inactive_tabs_excluded: Cell<u32>,
     db_path: PathBuf,
     db_connection: Option<Connection>,
 }
@@ -88,6 +94,8 @@ impl TabsStorage {
     pub fn new(db_path: impl AsRef<Path>) -> Self {
         Self {
             local_tabs: RefCell::default(),
+            inactive_tab_threshold_days: None,
+            inactive_tabs_excluded: Cell::new(0),
             db_path: db_path.as_ref().to_path_buf(),
             db_connection: None,
         }
@@ -148,11 +156,29 @@ impl TabsStorage {
         self.local_tabs.borrow_mut().replace(local_state);
     }
 
+    pub fn set_inactive_tab_threshold(&mut self, days: Option<u32>) {
+        self.inactive_tab_threshold_days = days;
+    }
+
+    pub fn inactive_tabs_excluded(&self) -> u32 {
+        self.inactive_tabs_excluded.get()
+    }
+
     // We try our best to fit as many tabs in a payload as possible, this includes
     // limiting the url history entries, title character count and finally drop enough tabs
     // until we have small enough payload that the server will accept
     pub fn prepare_local_tabs_for_upload(&self) -> Option<Vec<RemoteTab>> {
         if let Some(local_tabs) = self.local_tabs.borrow().as_ref() {
+            // Tabs which don't know when they were last used (ie, `last_used` is zero) are
+            // never considered inactive.
+            let cutoff = self.inactive_tab_threshold_days.map(|days| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as i64)
+                    .unwrap_or_default();
+                now - i64::from(days) * MS_PER_DAY
+            });
+            let mut excluded = 0;
             let mut sanitized_tabs: Vec<RemoteTab> = local_tabs
                 .iter()
                 .cloned()
@@ -160,6 +186,11 @@ impl TabsStorage {
                     if tab.url_history.is_empty() || !is_url_syncable(&tab.url_history[0]) {
                         return None;
                     }
+                    if matches!(cutoff, Some(cutoff) if tab.last_used > 0 && tab.last_used < cutoff)
+                    {
+                        excluded += 1;
+                        return None;
+                    }
                     let mut sanitized_history = Vec::with_capacity(TAB_ENTRIES_LIMIT);
                     for url in tab.url_history {
                         if sanitized_history.len() == TAB_ENTRIES_LIMIT {
@@ -176,6 +207,10 @@ impl TabsStorage {
                     Some(tab)
                 })
                 .collect();
+            if excluded > 0 {
+                log::info!("excluded {} inactive tabs from upload", excluded);
+            }
+            self.inactive_tabs_excluded.set(excluded);
             // Sort the tabs so when we trim tabs it's the oldest tabs
             sanitized_tabs.sort_by(|a, b| b.last_used.cmp(&a.last_used));
             // If trimming the tab length failed for some reason, just return the untrimmed tabs
@@ -258,7 +293,44 @@ impl TabsStorage {
             };
             crts.push(crt);
         }
-        Some(crts)
+        Some(merge_duplicate_clients(crts))
+    }
+
+    /// Delete the stored tabs for a remote client, where `client_id` is the id returned in
+    /// `ClientRemoteTabs`. Returns false if we didn't have any tabs for it. The client will
+    /// reappear if it uploads its tabs again.
+    pub(crate) fn forget_client(&mut self, client_id: &str) -> Result<bool> {
+        let remote_clients: HashMap<String, RemoteClient> =
+            match self.get_meta::<String>(schema::REMOTE_CLIENTS_KEY)? {
+                None => HashMap::default(),
+                Some(json) => serde_json::from_str(&json)?,
+            };
+        let conn = match self.open_if_exists()? {
+            None => return Ok(false),
+            Some(conn) => conn,
+        };
+        // We may have more than one record for the client - see `merge_duplicate_clients()`.
+        let guids: Vec<String> =
+            conn.query_rows_and_then_cached("SELECT guid FROM tabs", [], |row| -> Result<_> {
+                Ok(row.get::<_, String>(0)?)
+            })?;
+        let tx = conn.unchecked_transaction()?;
+        let mut num_removed = 0;
+        for guid in guids {
+            let id = remote_clients
+                .get(&guid)
+                .and_then(|c| c.fxa_device_id.as_deref())
+                .unwrap_or(&guid);
+            if id == client_id {
+                num_removed += tx.execute_cached(
+                    "DELETE FROM tabs WHERE guid = :guid",
+                    rusqlite::named_params! { ":guid": guid },
+                )?;
+            }
+        }
+        tx.commit()?;
+        log::info!("removed {} records for the forgotten client", num_removed);
+        Ok(num_removed > 0)
     }
 
     // Keep DB from growing infinitely since we only ask for records since our last sync
@@ -372,6 +444,37 @@ impl TabsStorage {
     }
 }
 
+// A device which reconnects (eg, after being reinstalled) gets a new sync client id but keeps its
+// FxA device id, so until the old record expires we can have two records for the same device.
+// Merge them so the device only appears once, using the name and type from the most recent
+// record and keeping the most recently used copy of each tab.
+fn merge_duplicate_clients(mut crts: Vec<ClientRemoteTabs>) -> Vec<ClientRemoteTabs> {
+    crts.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+    let mut merged: Vec<ClientRemoteTabs> = Vec::with_capacity(crts.len());
+    for crt in crts {
+        let existing = match merged.iter_mut().find(|m| m.client_id == crt.client_id) {
+            Some(existing) => existing,
+            None => {
+                merged.push(crt);
+                continue;
+            }
+        };
+        log::info!("merging duplicate records for a remote client");
+        for tab in crt.remote_tabs {
+            match existing
+                .remote_tabs
+                .iter_mut()
+                .find(|t| t.url_history.first() == tab.url_history.first())
+            {
+                Some(t) if tab.last_used > t.last_used => *t = tab,
+                Some(_) => (),
+                None => existing.remote_tabs.push(tab),
+            }
+        }
+    }
+    merged
+}
+
 // Trim the amount of tabs in a list to fit the specified memory size
 fn trim_tabs_length(tabs: &mut Vec<RemoteTab>, payload_size_max_bytes: usize) {
     // Ported from https://searchfox.org/mozilla-central/rev/84fb1c4511312a0b9187f647d90059e3a6dd27f8/services/sync/modules/util.sys.mjs#422
@@ -560,6 +663,46 @@ mod tests {
             ])
         );
     }
+    #[test]
+    fn test_inactive_tab_threshold() {
+        let mut storage = TabsStorage::new_with_mem_path("test_inactive_tab_threshold");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        let tab = |url: &str, last_used: i64| RemoteTab {
+            title: "".to_owned(),
+            url_history: vec![url.to_owned()],
+            icon: None,
+            last_used,
+        };
+        storage.update_local_state(vec![
+            tab("https://recent.example.com", now - MS_PER_DAY),
+            tab("https://old.example.com", now - 10 * MS_PER_DAY),
+            tab("https://unknown.example.com", 0),
+        ]);
+        // Nothing is excluded until there's a threshold.
+        assert_eq!(storage.prepare_local_tabs_for_upload().unwrap().len(), 3);
+        assert_eq!(storage.inactive_tabs_excluded(), 0);
+
+        storage.set_inactive_tab_threshold(Some(7));
+        let urls: Vec<String> = storage
+            .prepare_local_tabs_for_upload()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.url_history[0].clone())
+            .collect();
+        assert_eq!(
+            urls,
+            vec!["https://recent.example.com", "https://unknown.example.com"]
+        );
+        assert_eq!(storage.inactive_tabs_excluded(), 1);
+
+        storage.set_inactive_tab_threshold(None);
+        assert_eq!(storage.prepare_local_tabs_for_upload().unwrap().len(), 3);
+        assert_eq!(storage.inactive_tabs_excluded(), 0);
+    }
+
     #[test]
     fn test_trimming_tab_title() {
         let mut storage = TabsStorage::new_with_mem_path("test_prepare_local_tabs_for_upload");
@@ -724,4 +867,131 @@ mod tests {
         // Assert the correct record is still being returned
         assert_eq!(remote_tabs[0].client_id, "device-1");
     }
+
+    fn insert_record(storage: &mut TabsStorage, record: TabsSQLRecord) {
+        let db = storage.open_or_create().unwrap();
+        db.execute(
+            "INSERT INTO tabs (guid, record, last_modified) VALUES (:guid, :record, :last_modified);",
+            rusqlite::named_params! {
+                ":guid": &record.guid,
+                ":record": serde_json::to_string(&record.record).unwrap(),
+                ":last_modified": &record.last_modified,
+            },
+        )
+        .unwrap();
+    }
+
+    fn tab(url: &str, last_used: i64) -> TabsRecordTab {
+        TabsRecordTab {
+            title: "the title".to_string(),
+            url_history: vec![url.to_string()],
+            icon: None,
+            last_used,
+        }
+    }
+
+    // Two records from the same FxA device, which reconnected with a new sync client id.
+    fn setup_reconnected_device(storage: &mut TabsStorage) {
+        let remote_clients: HashMap<String, RemoteClient> = [
+            ("old-client", "fxa-1", "Old name"),
+            ("new-client", "fxa-1", "New name"),
+            ("other-client", "fxa-2", "Other"),
+        ]
+        .into_iter()
+        .map(|(guid, fxa_device_id, device_name)| {
+            (
+                guid.to_string(),
+                RemoteClient {
+                    fxa_device_id: Some(fxa_device_id.to_string()),
+                    device_name: device_name.to_string(),
+                    device_type: DeviceType::Mobile,
+                },
+            )
+        })
+        .collect();
+        storage
+            .put_meta(
+                schema::REMOTE_CLIENTS_KEY,
+                &serde_json::to_string(&remote_clients).unwrap(),
+            )
+            .unwrap();
+        for (guid, tabs, last_modified) in [
+            (
+                "old-client",
+                vec![tab("https://a.com/", 1000), tab("https://b.com/", 3000)],
+                1000,
+            ),
+            (
+                "new-client",
+                vec![tab("https://b.com/", 2000), tab("https://c.com/", 2000)],
+                2000,
+            ),
+            ("other-client", vec![tab("https://a.com/", 1000)], 1500),
+        ] {
+            insert_record(
+                storage,
+                TabsSQLRecord {
+                    guid: guid.to_string(),
+                    record: TabsRecord {
+                        id: guid.to_string(),
+                        client_name: "".to_string(),
+                        tabs,
+                    },
+                    last_modified,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_duplicate_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_name = dir.path().join("test_merge_duplicate_clients.db");
+        let mut storage = TabsStorage::new(db_name);
+        setup_reconnected_device(&mut storage);
+
+        let remote_tabs = storage.get_remote_tabs().unwrap();
+        assert_eq!(remote_tabs.len(), 2);
+        let merged = &remote_tabs[0];
+        assert_eq!(merged.client_id, "fxa-1");
+        // The most recent record wins.
+        assert_eq!(merged.client_name, "New name");
+        assert_eq!(merged.last_modified, 2000);
+        // Each tab only appears once, and we keep the most recently used copy.
+        let tabs: Vec<(&str, i64)> = merged
+            .remote_tabs
+            .iter()
+            .map(|t| (t.url_history[0].as_str(), t.last_used))
+            .collect();
+        assert_eq!(
+            tabs,
+            vec![
+                ("https://b.com/", 3_000_000),
+                ("https://c.com/", 2_000_000),
+                ("https://a.com/", 1_000_000),
+            ]
+        );
+        assert_eq!(remote_tabs[1].client_id, "fxa-2");
+        assert_eq!(remote_tabs[1].remote_tabs.len(), 1);
+    }
+
+    #[test]
+    fn test_forget_client() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_name = dir.path().join("test_forget_client.db");
+        let mut storage = TabsStorage::new(db_name);
+        assert!(!storage.forget_client("fxa-1").unwrap());
+        setup_reconnected_device(&mut storage);
+
+        // Both of the device's records are removed.
+        assert!(storage.forget_client("fxa-1").unwrap());
+        let remote_tabs = storage.get_remote_tabs().unwrap();
+        assert_eq!(remote_tabs.len(), 1);
+        assert_eq!(remote_tabs[0].client_id, "fxa-2");
+
+        assert!(!storage.forget_client("fxa-1").unwrap());
+        assert!(!storage.forget_client("other-client").unwrap());
+        assert!(storage.forget_client("fxa-2").unwrap());
+        assert!(storage.get_remote_tabs().unwrap().is_empty());
+    }
 }
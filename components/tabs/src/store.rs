@@ -2,7 +2,9 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use crate::error::{ApiResult, Error};
 use crate::storage::{ClientRemoteTabs, RemoteTab, TabsStorage};
+use error_support::handle_error;
 use std::path::Path;
 use std::sync::Mutex;
 
@@ -27,6 +29,20 @@ impl TabsStore {
         self.storage.lock().unwrap().update_local_state(local_state);
     }
 
+    /// Stop uploading local tabs which haven't been used for `days`, or upload them all if
+    /// `days` is `None`. Tabs which don't have a `last_used` time are always uploaded.
+    pub fn set_inactive_tab_threshold(&self, days: Option<u32>) {
+        self.storage
+            .lock()
+            .unwrap()
+            .set_inactive_tab_threshold(days);
+    }
+
+    /// How many local tabs were left out of the last upload for being inactive.
+    pub fn inactive_tabs_excluded(&self) -> u32 {
+        self.storage.lock().unwrap().inactive_tabs_excluded()
+    }
+
     // like remote_tabs, but serves the uniffi layer
     pub fn get_all(&self) -> Vec<ClientRemoteTabs> {
         match self.remote_tabs() {
@@ -38,4 +54,11 @@ impl TabsStore {
     pub fn remote_tabs(&self) -> Option<Vec<ClientRemoteTabs>> {
         self.storage.lock().unwrap().get_remote_tabs()
     }
+
+    /// Forget the tabs of a remote client, eg, one the user knows is gone for good. Returns
+    /// false if we didn't have any tabs for it.
+    #[handle_error(Error)]
+    pub fn forget_client(&self, client_id: &str) -> ApiResult<bool> {
+        self.storage.lock().unwrap().forget_client(client_id)
+    }
 }
@@ -5,6 +5,7 @@
 use crate::{backend::Backend, settings::GLOBAL_SETTINGS};
 use crate::{msg_types, Error};
 use ffi_support::{ByteBuffer, FfiStr};
+use std::time::Duration;
 
 ffi_support::implement_into_ffi_by_protobuf!(msg_types::Request);
 
@@ -20,12 +21,22 @@ impl From<crate::Request> for msg_types::Request {
             headers: request.headers.into(),
             follow_redirects: settings.follow_redirects,
             use_caches: settings.use_caches,
-            connect_timeout_secs: settings.connect_timeout.map_or(0, |d| d.as_secs() as i32),
-            read_timeout_secs: settings.read_timeout.map_or(0, |d| d.as_secs() as i32),
+            connect_timeout_secs: timeout_secs(request.timeout.or(settings.connect_timeout)),
+            read_timeout_secs: timeout_secs(request.timeout.or(settings.read_timeout)),
         }
     }
 }
 
+// 0 means no timeout, so timeouts are rounded up to whole seconds, rather than a timeout of
+// less than a second becoming none at all. Longer timeouts than fit are clamped, rather than
+// wrapping around.
+fn timeout_secs(timeout: Option<Duration>) -> i32 {
+    timeout.map_or(0, |d| {
+        let secs = d.as_secs() + u64::from(d.subsec_nanos() > 0);
+        i32::try_from(secs).unwrap_or(i32::MAX)
+    })
+}
+
 macro_rules! backend_error {
     ($($args:tt)*) => {{
         let msg = format!($($args)*);
@@ -26,6 +26,16 @@ pub enum Error {
 
     #[error("[no-sentry] Validation error: URL does not use TLS protocol.")]
     NonTlsUrl,
+
+    /// The server for a host with pinned certificates did not present a
+    /// certificate chaining to one of them.
+    #[error("Certificate pinning failure for host '{0}'")]
+    CertificatePinningError(String),
+
+    /// One of the certificates in the `additional_root_certificates` or
+    /// `pinned_certificates` settings couldn't be parsed.
+    #[error("Invalid certificate in the settings: {0}")]
+    InvalidCertificate(String),
 }
 
 impl From<url::ParseError> for Error {
@@ -379,8 +379,10 @@ pub mod consts {
         (ACCEPT_ENCODING, "accept-encoding"),
         (ACCEPT, "accept"),
         (AUTHORIZATION, "authorization"),
+        (CONTENT_ENCODING, "content-encoding"),
         (CONTENT_TYPE, "content-type"),
         (ETAG, "etag"),
+        (IF_MATCH, "if-match"),
         (IF_NONE_MATCH, "if-none-match"),
         (USER_AGENT, "user-agent"),
         // non-standard, but it's convenient to have these.
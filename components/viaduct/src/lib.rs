@@ -5,6 +5,7 @@
 #![allow(unknown_lints)]
 #![warn(rust_2018_idioms)]
 
+use std::time::Duration;
 use url::Url;
 #[macro_use]
 mod headers;
@@ -69,6 +70,8 @@ pub struct Request {
     pub url: Url,
     pub headers: Headers,
     pub body: Option<Vec<u8>>,
+    // Overrides the connect and read timeouts in the global settings for this request.
+    timeout: Option<Duration>,
 }
 
 impl Request {
@@ -80,6 +83,7 @@ impl Request {
             url,
             headers: Headers::new(),
             body: None,
+            timeout: None,
         }
     }
 
@@ -202,6 +206,19 @@ impl Request {
         self
     }
 
+    /// Give up on this request after `timeout`, rather than after the timeouts in the global
+    /// settings.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout set with `Request::timeout`, if any, for backends to use instead of the
+    /// timeouts in the global settings.
+    pub fn timeout_override(&self) -> Option<Duration> {
+        self.timeout
+    }
+
     /// Set body to the result of serializing `val`, and, unless it has already
     /// been set, set the Content-Type header to "application/json".
     ///
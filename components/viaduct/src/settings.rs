@@ -4,6 +4,7 @@
 
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::time::Duration;
 use url::Url;
 
@@ -16,7 +17,7 @@ use url::Url;
 /// with custom settings. In the reqwest backend this would store a Client, and
 /// in the concept-fetch backend it would only store the settings, and populate
 /// things on the fly.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub struct Settings {
     pub read_timeout: Option<Duration>,
@@ -26,6 +27,22 @@ pub struct Settings {
     // For testing purposes, we allow exactly one additional Url which is
     // allowed to not be https.
     pub addn_allowed_insecure_url: Option<Url>,
+    // Connection pool tuning. These are only honored by backends which manage
+    // their own connection pool (i.e. reqwest); `None` means "use the backend's
+    // default".
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+    // DER encoded certificates to trust in addition to the platform roots,
+    // e.g. for test environments which use a private CA.
+    pub additional_root_certificates: Vec<Vec<u8>>,
+    // Maps a host name to the DER encoded certificates which connections to
+    // that host must chain to. When a host is listed here, *only* these
+    // certificates are trusted for it. This replaces the host's trust anchors,
+    // rather than pinning a public key or fingerprint, so the certificates are
+    // usually the CA (or CAs) the host's certificate is issued by. Like the
+    // pool tuning above, these are only honored by the reqwest backend.
+    pub pinned_certificates: HashMap<String, Vec<Vec<u8>>>,
 }
 
 #[cfg(target_os = "ios")]
@@ -42,5 +59,10 @@ pub static GLOBAL_SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| {
         follow_redirects: true,
         use_caches: false,
         addn_allowed_insecure_url: None,
+        pool_max_idle_per_host: None,
+        pool_idle_timeout: None,
+        tcp_keepalive: None,
+        additional_root_certificates: Vec::new(),
+        pinned_certificates: HashMap::new(),
     })
 });
@@ -79,6 +79,8 @@ fn load_account(cli: &Cli) -> Result<FirefoxAccount> {
         redirect_uri: REDIRECT_URI.into(),
         client_id: CLIENT_ID.into(),
         token_server_url_override: None,
+        refresh_margin_seconds: None,
+        network_timeout_seconds: None,
     };
     get_cli_fxa(config, CREDENTIALS_PATH).map(|cli| cli.account)
 }
@@ -36,11 +36,11 @@ fn test_live_server() {
     println!("Scopes: [{}, {}]", scope1, scope2);
 
     println!("\n == Subscribing channels");
-    let sub1 = pm.subscribe(scope1, &None).expect("subscribe failed");
+    let sub1 = pm.subscribe(scope1, &None, &None).expect("subscribe failed");
 
     println!("## Subscription 1: {:?}", sub1);
     println!("## Info: {:?}", pm.get_subscription(scope1));
-    let sub2 = pm.subscribe(scope2, &None).unwrap();
+    let sub2 = pm.subscribe(scope2, &None, &None).unwrap();
     println!("## Subscription 2: {:?}", sub2);
 
     println!("\n == Unsubscribing single channel");
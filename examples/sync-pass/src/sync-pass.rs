@@ -60,7 +60,11 @@ fn read_form_based_login() -> LoginEntry {
             http_realm: None,
             origin,
         },
-        sec_fields: SecureLoginFields { username, password },
+        sec_fields: SecureLoginFields {
+            username,
+            password,
+            totp: None,
+        },
     }
 }
 
@@ -79,7 +83,11 @@ fn read_auth_based_login() -> LoginEntry {
             http_realm,
             origin,
         },
-        sec_fields: SecureLoginFields { username, password },
+        sec_fields: SecureLoginFields {
+            username,
+            password,
+            totp: None,
+        },
     }
 }
 
@@ -251,12 +259,12 @@ fn show_all(store: &LoginStore, encdec: &EncryptorDecryptor) -> Result<Vec<Strin
             &login.fields.password_field,
 
             login.record.times_used,
-            timestamp_to_string(login.record.time_created),
-            timestamp_to_string(login.record.time_password_changed),
-            if login.record.time_last_used == 0 {
+            timestamp_to_string(login.record.time_created.into()),
+            timestamp_to_string(login.record.time_password_changed.into()),
+            if login.record.time_last_used.as_millis() == 0 {
                 "Never".to_owned()
             } else {
-                timestamp_to_string(login.record.time_last_used)
+                timestamp_to_string(login.record.time_last_used.into())
             }
         ]);
         v.push(login.guid().to_string());
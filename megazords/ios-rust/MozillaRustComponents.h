@@ -19,3 +19,4 @@
 #import "errorFFI.h"
 #import "syncmanagerFFI.h"
 #import "remote_settingsFFI.h"
+#import "megazord_iosFFI.h"
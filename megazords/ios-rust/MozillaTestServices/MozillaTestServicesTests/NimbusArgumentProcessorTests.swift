@@ -46,6 +46,13 @@ final class NimbusArgumentProcessorTests: XCTestCase {
             ArgumentProcessor.createCommandLineArgs(args: ["--nimbus-cli", "--version", "1", "--log-state"]),
             CliArgs(resetDatabase: false, experiments: nil, logState: true)
         )
+
+        let context = "{\"region\": \"DE\"}"
+        XCTAssertEqual(
+            ArgumentProcessor.createCommandLineArgs(args: ["--nimbus-cli", "--version", "1", "--context", context]),
+            CliArgs(resetDatabase: false, experiments: nil, logState: false, context: context)
+        )
+        XCTAssertNil(ArgumentProcessor.createCommandLineArgs(args: ["--nimbus-cli", "--version", "1", "--context", "[]"]))
     }
 
     func testUrl() throws {
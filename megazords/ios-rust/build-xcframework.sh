@@ -5,6 +5,16 @@
 BUILD_PROFILE="release"
 FRAMEWORK_NAME="MozillaRustComponents"
 IS_FOCUS=
+# Whether to bundle the `crashtest` component. Defaults to "only in debug builds".
+INCLUDE_CRASHTEST=
+# Whether to enable Nimbus's test-only helpers, for builds used by UI tests.
+NIMBUS_TESTING=
+# Whether to leave out `viaduct-reqwest`, so the app supplies its own networking.
+NATIVE_NETWORKING=
+# Comma-separated list of components to bundle, defaulting to all of them.
+# These are the Cargo features of the same name in `Cargo.toml`.
+DEFAULT_COMPONENTS="autofill,fxa-client,logins,nimbus,places,push,remote_settings,sync15,sync_manager,tabs"
+COMPONENTS=
 # FRAMEWORK_FILENAME exist purely because we would like to ship
 # multiple frameworks that have the same swift code
 # namely for focus. However, componenets that use
@@ -17,6 +27,11 @@ while [[ "$#" -gt 0 ]]; do case $1 in
   --build-profile) BUILD_PROFILE="$2"; shift;shift;;
   --focus) IS_FOCUS="true"; FRAMEWORK_FILENAME="FocusRustComponents";shift;;
   --framework-name) FRAMEWORK_NAME="$2"; shift;shift;;
+  --with-crashtest) INCLUDE_CRASHTEST="true"; shift;;
+  --without-crashtest) INCLUDE_CRASHTEST="false"; shift;;
+  --with-nimbus-testing) NIMBUS_TESTING="true"; shift;;
+  --native-networking) NATIVE_NETWORKING="true"; shift;;
+  --components) COMPONENTS="$2"; shift;shift;;
   *) echo "Unknown parameter: $1"; exit 1;
 esac; done
 
@@ -72,6 +87,55 @@ case $BUILD_PROFILE in
   *) echo "Unknown build profile: $BUILD_PROFILE"; exit 1;
 esac
 
+if [[ -z $INCLUDE_CRASHTEST ]]; then
+  if [[ $BUILD_PROFILE == "debug" ]]; then
+    INCLUDE_CRASHTEST="true"
+  else
+    INCLUDE_CRASHTEST="false"
+  fi
+fi
+
+# Work out which components we're bundling, including the ones required by
+# the requested ones. This mirrors the `[features]` table in `Cargo.toml`.
+COMPONENTS=",${COMPONENTS:-$DEFAULT_COMPONENTS},"
+add_component () {
+  if [[ $COMPONENTS != *",$1,"* ]]; then
+    COMPONENTS="$COMPONENTS$1,"
+  fi
+}
+if [[ $COMPONENTS == *",sync_manager,"* ]]; then
+  for c in autofill logins places tabs; do add_component $c; done
+fi
+if [[ $COMPONENTS == *",nimbus,"* ]]; then
+  add_component remote_settings
+fi
+for c in autofill fxa-client logins places tabs; do
+  if [[ $COMPONENTS == *",$c,"* ]]; then
+    add_component sync15
+  fi
+done
+if [[ $INCLUDE_CRASHTEST == "true" ]]; then
+  add_component crashtest
+fi
+if [[ $NIMBUS_TESTING == "true" && $COMPONENTS == *",nimbus,"* ]]; then
+  add_component nimbus-testing
+fi
+
+# Returns success if the given component is being bundled. The focus megazord
+# has a fixed set of components, all of which are always bundled.
+component_enabled () {
+  [[ -n $IS_FOCUS || $COMPONENTS == *",$1,"* ]]
+}
+
+if [[ -z $IS_FOCUS ]]; then
+  FEATURES="${COMPONENTS#,}"
+  FEATURES="${FEATURES%,}"
+  if [[ -z $NATIVE_NETWORKING ]]; then
+    FEATURES="$FEATURES,reqwest-backend"
+  fi
+  BUILD_ARGS=("${BUILD_ARGS[@]}" --no-default-features --features "$FEATURES")
+fi
+
 cargo_build () {
   TARGET=$1
   case $TARGET in
@@ -145,10 +209,48 @@ mkdir -p "$COMMON/Headers"
 cp "$WORKING_DIR/$FRAMEWORK_NAME.h" "$COMMON/Headers"
 cp "$REPO_ROOT/components/rc_log/ios/RustLogFFI.h" "$COMMON/Headers"
 cp "$REPO_ROOT/components/viaduct/ios/RustViaductFFI.h" "$COMMON/Headers"
-$CARGO uniffi-bindgen generate "$REPO_ROOT/components/remote_settings/src/remote_settings.udl" -l swift -o "$COMMON/Headers"
-$CARGO uniffi-bindgen generate "$REPO_ROOT/components/nimbus/src/nimbus.udl" -l swift -o "$COMMON/Headers"
 $CARGO uniffi-bindgen generate "$REPO_ROOT/components/support/error/src/errorsupport.udl" -l swift -o "$COMMON/Headers"
 
+# Generate the header for a component if it's being bundled, otherwise remove
+# it from the umbrella header so we don't reference a header we didn't generate.
+generate_component_header () {
+  COMPONENT=$1
+  UDL_PATH=$2
+  HEADER=$3
+  if component_enabled "$COMPONENT"; then
+    $CARGO uniffi-bindgen generate "$REPO_ROOT/$UDL_PATH" -l swift -o "$COMMON/Headers"
+  else
+    sed -i.bak "/$HEADER/d" "$COMMON/Headers/$FRAMEWORK_NAME.h"
+    rm "$COMMON/Headers/$FRAMEWORK_NAME.h.bak"
+  fi
+}
+
+# Make the error type of each bundled component conform to `RustComponentError`
+# (see `ios/RustComponentError.swift`). The error types of the components we
+# leave out won't exist, so they're left out here too.
+generate_component_errors () {
+  ERRORS_FILE="$WORKING_DIR/ios/Generated/RustComponentErrors.swift"
+  mkdir -p "$(dirname "$ERRORS_FILE")"
+  {
+    echo "// This file was generated by build-xcframework.sh. Do not edit it."
+    for ENTRY in autofill:AutofillApiError crashtest:CrashTestError fxa-client:FxaError \
+        logins:LoginsApiError nimbus:NimbusError places:PlacesApiError push:PushApiError \
+        remote_settings:RemoteSettingsError sync_manager:SyncManagerError tabs:TabsApiError; do
+      COMPONENT="${ENTRY%%:*}"
+      ERROR_TYPE="${ENTRY#*:}"
+      if component_enabled "$COMPONENT"; then
+        echo
+        echo "extension $ERROR_TYPE: RustComponentError {"
+        echo "    public var component: String { \"${COMPONENT//-/_}\" }"
+        echo "}"
+      fi
+    done
+  } > "$ERRORS_FILE"
+}
+
+generate_component_header remote_settings components/remote_settings/src/remote_settings.udl remote_settingsFFI.h
+generate_component_header nimbus components/nimbus/src/nimbus.udl nimbusFFI.h
+
 
 
 # We now only move/generate the rest of the headers if we are generating a full
@@ -159,15 +261,17 @@ if [ -z $IS_FOCUS ]; then
   # all of the generated headers for all UniFFIed dependencies of a given crate.
   # For now we generate the Swift bindings to get the headers as a side effect,
   # then delete the generated Swift code. Bleh.
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/crashtest/src/crashtest.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/fxa-client/src/fxa_client.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/logins/src/logins.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/autofill/src/autofill.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/push/src/push.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/tabs/src/tabs.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/places/src/places.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/sync_manager/src/syncmanager.udl" -l swift -o "$COMMON/Headers"
-  $CARGO uniffi-bindgen generate "$REPO_ROOT/components/sync15/src/sync15.udl" -l swift -o "$COMMON/Headers"
+  generate_component_header crashtest components/crashtest/src/crashtest.udl crashtestFFI.h
+  generate_component_header fxa-client components/fxa-client/src/fxa_client.udl fxa_clientFFI.h
+  generate_component_header logins components/logins/src/logins.udl loginsFFI.h
+  generate_component_header autofill components/autofill/src/autofill.udl autofillFFI.h
+  generate_component_header push components/push/src/push.udl pushFFI.h
+  generate_component_header tabs components/tabs/src/tabs.udl tabsFFI.h
+  generate_component_header places components/places/src/places.udl placesFFI.h
+  generate_component_header sync_manager components/sync_manager/src/syncmanager.udl syncmanagerFFI.h
+  generate_component_header sync15 components/sync15/src/sync15.udl sync15FFI.h
+  $CARGO uniffi-bindgen generate "$REPO_ROOT/megazords/ios-rust/src/megazord_ios.udl" -l swift -o "$COMMON/Headers"
+  generate_component_errors
 fi
 rm -rf "$COMMON"/Headers/*.swift
 
@@ -0,0 +1,145 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::{env, fs, path::Path, process::Command};
+
+fn main() {
+    uniffi::generate_scaffolding("./src/megazord_ios.udl").unwrap();
+    write_build_info();
+}
+
+// Find the value of `field` in the `[package]` section of a Cargo.toml.
+fn package_field(manifest: &str, field: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == field {
+                    return Some(value.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn git(repo_root: &Path, args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .current_dir(repo_root)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Rerun when HEAD moves, including to a new commit on the current branch. We ask git where
+// these files are, because `.git` is a file rather than a directory in worktrees and
+// submodules.
+fn watch_git_head(repo_root: &Path) {
+    let mut paths = vec!["HEAD".to_string(), "packed-refs".to_string()];
+    if let Some(head_ref) = git(repo_root, &["symbolic-ref", "-q", "HEAD"]) {
+        paths.push(head_ref);
+    }
+    for path in paths {
+        if let Some(git_path) = git(repo_root, &["rev-parse", "--git-path", &path]) {
+            println!(
+                "cargo:rerun-if-changed={}",
+                repo_root.join(git_path).display()
+            );
+        }
+    }
+}
+
+// Is any enabled feature of ours turning on the optional dependency `dep_name`?
+fn optional_dep_enabled(manifest: &str, dep_name: &str) -> bool {
+    let dep_ref = format!("\"dep:{}\"", dep_name);
+    let mut in_features = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_features = line == "[features]";
+        } else if in_features && line.contains(&dep_ref) {
+            let feature = line.split('=').next().unwrap().trim();
+            let feature_var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            if env::var_os(feature_var).is_some() {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Generates `$OUT_DIR/build_info.rs`, which records the version of every
+// component we bundle, so that `megazord_ios_build_info()` can report exactly
+// what went into this build.
+fn write_build_info() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let manifest_dir = Path::new(&manifest_dir);
+    let repo_root = manifest_dir.join("../..");
+    let manifest_path = manifest_dir.join("Cargo.toml");
+    let version_path = repo_root.join("version.txt");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+    println!("cargo:rerun-if-changed={}", version_path.display());
+    println!("cargo:rerun-if-env-changed=MEGAZORD_IOS_GIT_HASH");
+
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    let mut components = Vec::new();
+    for line in manifest.lines() {
+        // We only care about our own (path) dependencies.
+        let Some((_, rest)) = line.split_once("path = \"") else {
+            continue;
+        };
+        let Some((path, _)) = rest.split_once('"') else {
+            continue;
+        };
+        // Skip optional components whose feature isn't enabled.
+        if line.contains("optional = true") {
+            let dep_name = line.split('=').next().unwrap().trim();
+            if !optional_dep_enabled(&manifest, dep_name) {
+                continue;
+            }
+        }
+        let dep_manifest_path = manifest_dir.join(path).join("Cargo.toml");
+        println!("cargo:rerun-if-changed={}", dep_manifest_path.display());
+        let dep_manifest = fs::read_to_string(&dep_manifest_path).unwrap();
+        if let (Some(name), Some(version)) = (
+            package_field(&dep_manifest, "name"),
+            package_field(&dep_manifest, "version"),
+        ) {
+            components.push((name, version));
+        }
+    }
+    components.sort();
+
+    let version = fs::read_to_string(version_path)
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    // CI can set this explicitly, otherwise we ask git.
+    let git_hash = env::var("MEGAZORD_IOS_GIT_HASH")
+        .ok()
+        .or_else(|| {
+            watch_git_head(&repo_root);
+            git(&repo_root, &["rev-parse", "HEAD"])
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "pub(crate) const VERSION: &str = {:?};\n",
+        version
+    ));
+    out.push_str(&format!(
+        "pub(crate) const GIT_HASH: &str = {:?};\n",
+        git_hash
+    ));
+    out.push_str("pub(crate) const COMPONENT_VERSIONS: &[(&str, &str)] = &[\n");
+    for (name, version) in components {
+        out.push_str(&format!("    ({:?}, {:?}),\n", name, version));
+    }
+    out.push_str("];\n");
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("build_info.rs");
+    fs::write(out_path, out).unwrap();
+}
@@ -0,0 +1,25 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+import Foundation
+#if canImport(MozillaRustComponents)
+    import MozillaRustComponents
+#endif
+
+/// Implemented by the error types of all the Rust components, so that callers
+/// can `catch let error as RustComponentError` once rather than handling each
+/// component's error type separately. The original error is still available
+/// by casting to the component's own type.
+public protocol RustComponentError: Error {
+    /// The name of the component the error came from.
+    var component: String { get }
+}
+
+// `build-xcframework.sh` generates the conformances for the components it
+// bundles into `Generated/RustComponentErrors.swift`, since the error types of
+// the components it leaves out don't exist.
+
+extension MegazordError: RustComponentError {
+    public var component: String { "megazord" }
+}
@@ -0,0 +1,85 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! An umbrella over the public error types of all the bundled components.
+//!
+//! Each component still throws its own error type over the FFI. This exists
+//! for code which deals with errors from several components and wants to
+//! handle them uniformly, while keeping the original error around. The Swift
+//! equivalent is the `RustComponentError` protocol in `ios/`.
+
+#[derive(Debug, thiserror::Error)]
+pub enum ComponentError {
+    #[cfg(feature = "autofill")]
+    #[error("autofill: {0}")]
+    Autofill(#[from] autofill::AutofillApiError),
+
+    #[cfg(feature = "crashtest")]
+    #[error("crashtest: {0}")]
+    Crashtest(#[from] crashtest::CrashTestError),
+
+    #[cfg(feature = "fxa-client")]
+    #[error("fxa_client: {0}")]
+    FxaClient(#[from] fxa_client::FxaError),
+
+    #[cfg(feature = "logins")]
+    #[error("logins: {0}")]
+    Logins(#[from] logins::LoginsApiError),
+
+    #[cfg(feature = "nimbus")]
+    #[error("nimbus: {0}")]
+    Nimbus(#[from] nimbus::NimbusError),
+
+    #[cfg(feature = "places")]
+    #[error("places: {0}")]
+    Places(#[from] places::PlacesApiError),
+
+    #[cfg(feature = "push")]
+    #[error("push: {0}")]
+    Push(#[from] push::PushApiError),
+
+    #[cfg(feature = "remote_settings")]
+    #[error("remote_settings: {0}")]
+    RemoteSettings(#[from] remote_settings::RemoteSettingsError),
+
+    #[cfg(feature = "sync_manager")]
+    #[error("sync_manager: {0}")]
+    SyncManager(#[from] sync_manager::SyncManagerError),
+
+    #[cfg(feature = "tabs")]
+    #[error("tabs: {0}")]
+    Tabs(#[from] tabs::TabsApiError),
+
+    #[error("megazord: {0}")]
+    Megazord(#[from] crate::MegazordError),
+}
+
+impl ComponentError {
+    /// The name of the component the error came from.
+    pub fn component(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "autofill")]
+            Self::Autofill(_) => "autofill",
+            #[cfg(feature = "crashtest")]
+            Self::Crashtest(_) => "crashtest",
+            #[cfg(feature = "fxa-client")]
+            Self::FxaClient(_) => "fxa_client",
+            #[cfg(feature = "logins")]
+            Self::Logins(_) => "logins",
+            #[cfg(feature = "nimbus")]
+            Self::Nimbus(_) => "nimbus",
+            #[cfg(feature = "places")]
+            Self::Places(_) => "places",
+            #[cfg(feature = "push")]
+            Self::Push(_) => "push",
+            #[cfg(feature = "remote_settings")]
+            Self::RemoteSettings(_) => "remote_settings",
+            #[cfg(feature = "sync_manager")]
+            Self::SyncManager(_) => "sync_manager",
+            #[cfg(feature = "tabs")]
+            Self::Tabs(_) => "tabs",
+            Self::Megazord(_) => "megazord",
+        }
+    }
+}
@@ -5,20 +5,208 @@
 #![allow(unknown_lints)]
 #![warn(rust_2018_idioms)]
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "autofill")]
 pub use autofill;
+#[cfg(feature = "crashtest")]
 pub use crashtest;
 pub use error_support;
+#[cfg(feature = "fxa-client")]
 pub use fxa_client;
+#[cfg(feature = "logins")]
 pub use logins;
+#[cfg(feature = "nimbus")]
 pub use nimbus;
+#[cfg(feature = "places")]
 pub use places;
+#[cfg(feature = "push")]
 pub use push;
 // TODO: Drop this dependency once firefox-ios switches to using `rust_log_forwarder` for log
 // forwarding.
 pub use rc_log_ffi;
+#[cfg(feature = "remote_settings")]
 pub use remote_settings;
 pub use rust_log_forwarder;
+#[cfg(feature = "sync15")]
 pub use sync15;
+#[cfg(feature = "sync_manager")]
 pub use sync_manager;
+#[cfg(feature = "tabs")]
 pub use tabs;
+// Always re-exported, since it provides the FFI networking backend when
+// the reqwest one isn't bundled.
+pub use viaduct;
+#[cfg(feature = "reqwest-backend")]
 pub use viaduct_reqwest;
+
+uniffi::include_scaffolding!("megazord_ios");
+
+mod error;
+pub use error::ComponentError;
+
+mod build_info {
+    include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MegazordError {
+    #[error("The iOS megazord has already been initialized")]
+    AlreadyInitialized,
+    #[error("The reqwest networking backend isn't bundled in this build")]
+    ReqwestBackendUnavailable,
+}
+
+pub struct MegazordConfig {
+    pub use_reqwest_backend: bool,
+    pub max_log_level: MegazordLogLevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MegazordLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<MegazordLogLevel> for rust_log_forwarder::Level {
+    fn from(level: MegazordLogLevel) -> Self {
+        match level {
+            MegazordLogLevel::Error => rust_log_forwarder::Level::Error,
+            MegazordLogLevel::Warn => rust_log_forwarder::Level::Warn,
+            MegazordLogLevel::Info => rust_log_forwarder::Level::Info,
+            MegazordLogLevel::Debug => rust_log_forwarder::Level::Debug,
+            MegazordLogLevel::Trace => rust_log_forwarder::Level::Trace,
+        }
+    }
+}
+
+impl From<rust_log_forwarder::Level> for MegazordLogLevel {
+    fn from(level: rust_log_forwarder::Level) -> Self {
+        match level {
+            rust_log_forwarder::Level::Error => MegazordLogLevel::Error,
+            rust_log_forwarder::Level::Warn => MegazordLogLevel::Warn,
+            rust_log_forwarder::Level::Info => MegazordLogLevel::Info,
+            rust_log_forwarder::Level::Debug => MegazordLogLevel::Debug,
+            rust_log_forwarder::Level::Trace => MegazordLogLevel::Trace,
+        }
+    }
+}
+
+pub trait MegazordLogger: Send + Sync {
+    fn log(&self, level: MegazordLogLevel, target: String, message: String);
+}
+
+pub trait MegazordErrorReporter: Send + Sync {
+    fn report_error(&self, type_name: String, message: String);
+    fn report_breadcrumb(&self, message: String, module: String, line: u32, column: u32);
+}
+
+// The log forwarder and error support crates define their own callback
+// interfaces, which we can't name from our UDL, so we adapt ours to theirs.
+struct LoggerAdapter(Box<dyn MegazordLogger>);
+
+impl rust_log_forwarder::Logger for LoggerAdapter {
+    fn log(&self, record: rust_log_forwarder::Record) {
+        self.0
+            .log(record.level.into(), record.target, record.message)
+    }
+}
+
+struct ErrorReporterAdapter(Box<dyn MegazordErrorReporter>);
+
+impl error_support::ApplicationErrorReporter for ErrorReporterAdapter {
+    fn report_error(&self, type_name: String, message: String) {
+        self.0.report_error(type_name, message)
+    }
+
+    fn report_breadcrumb(&self, message: String, module: String, line: u32, column: u32) {
+        self.0.report_breadcrumb(message, module, line, column)
+    }
+}
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Set up networking, log forwarding and error reporting for all the bundled
+/// components, in the order they need to happen.
+///
+/// Only the first call has any effect; later calls return
+/// `MegazordError::AlreadyInitialized` without touching the existing setup.
+pub fn megazord_ios_initialize(
+    config: MegazordConfig,
+    logger: Option<Box<dyn MegazordLogger>>,
+    error_reporter: Option<Box<dyn MegazordErrorReporter>>,
+) -> Result<(), MegazordError> {
+    if config.use_reqwest_backend && !cfg!(feature = "reqwest-backend") {
+        return Err(MegazordError::ReqwestBackendUnavailable);
+    }
+    if INITIALIZED.swap(true, Ordering::SeqCst) {
+        return Err(MegazordError::AlreadyInitialized);
+    }
+    // Error reporting goes first, so that anything going wrong below
+    // gets reported.
+    if let Some(reporter) = error_reporter {
+        error_support::set_application_error_reporter(Box::new(ErrorReporterAdapter(reporter)));
+    }
+    if let Some(logger) = logger {
+        rust_log_forwarder::set_max_level(config.max_log_level.into());
+        rust_log_forwarder::set_logger(Some(Box::new(LoggerAdapter(logger))));
+    }
+    #[cfg(feature = "reqwest-backend")]
+    if config.use_reqwest_backend {
+        viaduct_reqwest::use_reqwest_backend();
+    }
+    Ok(())
+}
+
+pub struct MegazordBuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub components: Vec<ComponentVersion>,
+}
+
+pub struct ComponentVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// Return the build information generated by `build.rs`.
+pub fn megazord_ios_build_info() -> MegazordBuildInfo {
+    MegazordBuildInfo {
+        version: build_info::VERSION.to_string(),
+        git_hash: build_info::GIT_HASH.to_string(),
+        components: build_info::COMPONENT_VERSIONS
+            .iter()
+            .map(|(name, version)| ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Get all the bundled components ready for the app to terminate.
+///
+/// This puts `interrupt_support` into shutdown mode, which interrupts any
+/// in-progress database operations and causes new ones to fail immediately, so
+/// that no component is left holding a write lock or part way through a
+/// transaction. Then we flush any pending log output and drop our references
+/// to the app's logger and error reporter.
+pub fn megazord_ios_shutdown() {
+    log::info!("Shutting down the iOS megazord");
+    interrupt_support::shutdown();
+    log::logger().flush();
+    rust_log_forwarder::set_logger(None);
+    error_support::unset_application_error_reporter();
+}
+
+/// Interrupt the in-progress work of all the bundled components.
+///
+/// This is `interrupt_support::interrupt_all()`, which interrupts every registered
+/// database connection and any sync that's running. It's safe to call from any thread.
+pub fn megazord_ios_interrupt_all() {
+    log::info!("Interrupting all in-progress operations");
+    interrupt_support::interrupt_all();
+}
@@ -21,6 +21,7 @@ BINDINGS_UDL_PATHS = [
     "components/sync15/src/sync15.udl",
     "components/sync_manager/src/syncmanager.udl",
     "components/tabs/src/tabs.udl",
+    "megazords/ios-rust/src/megazord_ios.udl",
 ]
 
 # List of udl_paths to generate bindings for
@@ -41,6 +42,7 @@ SOURCE_TO_COPY = [
     "components/sync_manager/ios/SyncManager",
     "components/rc_log/ios/*",
     "components/viaduct/ios/*",
+    "megazords/ios-rust/ios/*",
 ]
 
 # List of udl_paths to generate bindings for
@@ -78,6 +78,7 @@ impl TestClient {
                 name: self.device.display_name.clone(),
                 kind: self.device.device_type,
             },
+            time_limit_ms: None,
         };
         let result = self.sync_manager.sync(params)?;
         // We expect all syncs in these tests to pass, so let's catch that here
@@ -163,6 +164,8 @@ impl FxaConfigUrl {
                 client_id: client_id.to_string(),
                 redirect_uri: redirect.to_string(),
                 token_server_url_override: None,
+                refresh_margin_seconds: None,
+                network_timeout_seconds: None,
             },
         }
     }
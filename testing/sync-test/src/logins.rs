@@ -110,6 +110,7 @@ fn test_login_general(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username".into(),
                 password: "hunter2".into(),
+                totp: None,
             },
         },
         &key,
@@ -131,6 +132,7 @@ fn test_login_general(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username".into(),
                 password: "sekret".into(),
+                totp: None,
             },
         },
         &key,
@@ -199,6 +201,7 @@ fn test_login_general(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: login0_c0.sec_fields.username,
                 password: "testtesttest".into(),
+                totp: None,
             },
             record: login0_c0.record,
         },
@@ -235,6 +238,7 @@ fn test_login_deletes(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username".into(),
                 password: "hunter2".into(),
+                totp: None,
             },
         },
         &key,
@@ -253,6 +257,7 @@ fn test_login_deletes(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username".into(),
                 password: "sekret".into(),
+                totp: None,
             },
         },
         &key,
@@ -271,6 +276,7 @@ fn test_login_deletes(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username100".into(),
                 password: "123454321".into(),
+                totp: None,
             },
         },
         &key,
@@ -289,6 +295,7 @@ fn test_login_deletes(c0: &mut TestClient, c1: &mut TestClient) {
             sec_fields: SecureLoginFields {
                 username: "cool_username99".into(),
                 password: "aaaaa".into(),
+                totp: None,
             },
         },
         &key,